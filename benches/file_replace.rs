@@ -0,0 +1,85 @@
+//! Times a dry-run `file_replace` over a generated tree with `cpu_concurrency` set to one and
+//! to the number of CPU cores.
+//!
+//! ```bash
+//! cargo bench --bench file_replace
+//! BENCH_FILES=2000 cargo bench --bench file_replace
+//! ```
+
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::FileReplaceParam;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const DEFAULT_FILES: usize = 10_000;
+
+/// A module with a few functions and two `var` declarations to rewrite
+fn module(index: usize) -> String {
+    let mut code = format!("var total{index} = 0;\nvar name{index} = 'module {index}';\n");
+    for function in 0..8 {
+        code.push_str(&format!(
+            "\nfunction step{function}(items) {{\n  let sum = 0;\n  for (const item of items) {{\n    if (item.enabled && item.weight > {function}) {{\n      sum += item.weight * {index};\n    }}\n  }}\n  return sum;\n}}\n"
+        ));
+    }
+    code
+}
+
+fn generate(root: &Path, files: usize) {
+    for index in 0..files {
+        let directory = root.join(format!("pkg{}", index / 100));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join(format!("m{index}.js")), module(index)).unwrap();
+    }
+}
+
+async fn time_replace(root: &Path, cpu_concurrency: usize, files: usize) -> Duration {
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![root.to_path_buf()],
+        undo_directory: root.join(".undo"),
+        cpu_concurrency,
+        ..Default::default()
+    });
+    let started = Instant::now();
+    let result = service
+        .file_replace(FileReplaceParam {
+            path_pattern: "**/*.js".to_string(),
+            pattern: "var $A = $B".to_string(),
+            replacement: "let $A = $B".to_string(),
+            language: "javascript".to_string(),
+            max_results: files * 2,
+            dry_run: true,
+            summary_only: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let elapsed = started.elapsed();
+    assert_eq!(result.files_with_changes, files);
+    elapsed
+}
+
+fn main() {
+    let files = std::env::var("BENCH_FILES")
+        .ok()
+        .and_then(|files| files.parse().ok())
+        .unwrap_or(DEFAULT_FILES);
+    let cores = std::thread::available_parallelism().map_or(1, usize::from);
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    generate(temp_dir.path(), files);
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    // The first run warms the page cache for both measurements
+    runtime.block_on(time_replace(temp_dir.path(), cores, files));
+    let sequential = runtime.block_on(time_replace(temp_dir.path(), 1, files));
+    let parallel = runtime.block_on(time_replace(temp_dir.path(), cores, files));
+
+    println!("file_replace over {files} files");
+    println!("  cpu_concurrency 1: {sequential:>10.2?}");
+    println!("  cpu_concurrency {cores}: {parallel:>10.2?}");
+    println!(
+        "  speedup: {:.2}x",
+        sequential.as_secs_f64() / parallel.as_secs_f64()
+    );
+}
@@ -16,6 +16,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         context_before: None,
         context_after: None,
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result = service.search(param).await?;
@@ -18,6 +18,7 @@ async fn main() {
     let class_param = GenerateAstParam {
         code: class_code.to_string(),
         language: "javascript".to_string(),
+        buffer_id: None,
     };
 
     match service.generate_ast(class_param).await {
@@ -32,6 +33,7 @@ async fn main() {
     let func_param = GenerateAstParam {
         code: func_code.to_string(),
         language: "javascript".to_string(),
+        buffer_id: None,
     };
 
     match service.generate_ast(func_param).await {
@@ -46,6 +46,13 @@ rule:
         max_results: 100,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result1 = service.rule_search(param1).await.unwrap();
@@ -79,6 +86,13 @@ rule:
         max_results: 100,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result2 = service.rule_search(param2).await.unwrap();
@@ -115,6 +129,13 @@ rule:
         max_results: 100,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result3 = service.rule_search(param3).await.unwrap();
@@ -31,6 +31,8 @@ async fn main() {
                 pattern: "class $CLASS { $METHODS }".to_string(),
             },
         )),
+        stop_by: ast_grep_mcp::rules::ast::StopBy::End,
+        field: None,
     };
 
     let all_rule = Rule::All(vec![
@@ -69,6 +71,7 @@ rule:
     - kind: method_definition
     - inside:
         pattern: class $CLASS { $METHODS }
+        stopBy: end
 "#;
 
     let param = RuleSearchParam {
@@ -77,6 +80,13 @@ rule:
         max_results: 100,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     match service.rule_search(param).await {
@@ -0,0 +1,173 @@
+//! # Project Analysis
+//!
+//! A first look at an unfamiliar project in one call: which languages it is written in, how its
+//! files are spread over the top of the directory tree, which files are largest and which
+//! `path_pattern` globs narrow follow-up searches to each language. Only file names and sizes
+//! are read, so the overview stays cheap on large repositories.
+
+use crate::extra_languages::AnyLanguage;
+use crate::types::{
+    DirectoryOverview, FileSize, LanguageOverview, PathPatternSuggestion, ProjectOverviewParam,
+    ProjectOverviewResult,
+};
+use crate::walk::{WalkSettings, walk_files};
+use ast_grep_core::Language as _;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Files and bytes below one directory of the tree
+#[derive(Default)]
+struct DirectoryTally {
+    files: usize,
+    bytes: u64,
+    languages: BTreeMap<String, usize>,
+}
+
+/// Files of one language across every root
+#[derive(Default)]
+struct LanguageTally {
+    files: usize,
+    bytes: u64,
+    extensions: BTreeSet<String>,
+    /// First directory below the root of each file; `None` for files directly in a root
+    top_directories: BTreeSet<Option<String>>,
+}
+
+/// Overview of the files under `roots` that the walk settings allow
+pub fn project_overview(
+    roots: &[PathBuf],
+    walk: &WalkSettings,
+    param: &ProjectOverviewParam,
+) -> ProjectOverviewResult {
+    let mut languages: BTreeMap<String, LanguageTally> = BTreeMap::new();
+    let mut files = Vec::new();
+    let mut tree = Vec::new();
+    let mut other_files = 0;
+
+    for root in roots {
+        // Keyed by the path components below the root, so parents sort before their children
+        let mut directories: BTreeMap<Vec<String>, DirectoryTally> = BTreeMap::new();
+
+        for entry in walk_files(root, walk) {
+            let path = entry.path();
+            let bytes = entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            let language = AnyLanguage::from_path(path).map(|lang| lang.to_string().to_lowercase());
+            let relative: Vec<String> = path
+                .strip_prefix(root)
+                .unwrap_or(path)
+                .parent()
+                .into_iter()
+                .flat_map(Path::components)
+                .map(|component| component.as_os_str().to_string_lossy().to_string())
+                .collect();
+
+            for depth in 0..=relative.len().min(param.tree_depth) {
+                let tally = directories.entry(relative[..depth].to_vec()).or_default();
+                tally.files += 1;
+                tally.bytes += bytes;
+                if let Some(language) = &language {
+                    *tally.languages.entry(language.clone()).or_default() += 1;
+                }
+            }
+
+            match &language {
+                Some(language) => {
+                    let tally = languages.entry(language.clone()).or_default();
+                    tally.files += 1;
+                    tally.bytes += bytes;
+                    if let Some(extension) = path.extension() {
+                        tally
+                            .extensions
+                            .insert(extension.to_string_lossy().to_string());
+                    }
+                    tally.top_directories.insert(relative.first().cloned());
+                }
+                None => other_files += 1,
+            }
+
+            files.push(FileSize {
+                file_path: path.to_string_lossy().to_string(),
+                bytes,
+                language,
+            });
+        }
+
+        tree.extend(directories.into_iter().map(|(components, tally)| {
+            DirectoryOverview {
+                path: components
+                    .iter()
+                    .fold(root.clone(), |path, component| path.join(component))
+                    .to_string_lossy()
+                    .to_string(),
+                depth: components.len(),
+                files: tally.files,
+                bytes: tally.bytes,
+                main_language: most_common(&tally.languages),
+            }
+        }));
+    }
+
+    let total_files = files.len();
+    let total_bytes = files.iter().map(|file| file.bytes).sum();
+    files.sort_by(|a, b| {
+        b.bytes
+            .cmp(&a.bytes)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    });
+    files.truncate(param.largest_files);
+
+    let mut languages: Vec<(String, LanguageTally)> = languages.into_iter().collect();
+    languages.sort_by(|a, b| b.1.files.cmp(&a.1.files).then_with(|| a.0.cmp(&b.0)));
+    let suggested_path_patterns = languages
+        .iter()
+        .filter_map(|(language, tally)| {
+            Some(PathPatternSuggestion {
+                path_pattern: suggest_path_pattern(tally)?,
+                language: language.clone(),
+                files: tally.files,
+            })
+        })
+        .collect();
+
+    ProjectOverviewResult {
+        total_files,
+        total_bytes,
+        languages: languages
+            .into_iter()
+            .map(|(language, tally)| LanguageOverview {
+                language,
+                files: tally.files,
+                bytes: tally.bytes,
+                extensions: tally.extensions.into_iter().collect(),
+            })
+            .collect(),
+        other_files,
+        tree,
+        largest_files: files,
+        suggested_path_patterns,
+    }
+}
+
+/// The language with the most files, the alphabetically first on a tie
+fn most_common(languages: &BTreeMap<String, usize>) -> Option<String> {
+    languages
+        .iter()
+        .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(language, _)| language.clone())
+}
+
+/// A glob matching every file of a language: its extensions, under its one top-level directory
+/// when all of its files share one. `None` for languages only seen in files without extension
+fn suggest_path_pattern(tally: &LanguageTally) -> Option<String> {
+    let extensions: Vec<&str> = tally.extensions.iter().map(String::as_str).collect();
+    let file_glob = match extensions.as_slice() {
+        [] => return None,
+        [extension] => format!("*.{extension}"),
+        extensions => format!("*.{{{}}}", extensions.join(",")),
+    };
+    let mut top_directories = tally.top_directories.iter();
+    Some(match (top_directories.next(), top_directories.next()) {
+        (Some(Some(directory)), None) => format!("{directory}/**/{file_glob}"),
+        _ => format!("**/{file_glob}"),
+    })
+}
@@ -0,0 +1,209 @@
+//! # Annotations
+//!
+//! `add_annotation` inserts a comment on its own line above every match of a pattern: a
+//! deprecation notice, a suppression marker or a TODO with a ticket number. The comment is
+//! written in the file's comment syntax and indented like the line it annotates. A line that
+//! already has the comment right above it is left alone, so running the same annotation twice
+//! changes nothing the second time.
+
+use crate::errors::ServiceError;
+use crate::extra_languages::AnyLanguage;
+use crate::file_fetch::content_hash;
+use crate::search::SearchService;
+use crate::types::{
+    AddAnnotationParam, AddAnnotationResult, ChangeResult, FileDiffResult, FileSearchParam,
+};
+use ast_grep_language::SupportLang as Language;
+use std::collections::BTreeSet;
+
+/// How a language writes a comment on a line of its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentSyntax {
+    /// A marker running to the end of the line, like `//` or `#`
+    Line(&'static str),
+    /// Opening and closing delimiters, like `/*` and `*/`
+    Block(&'static str, &'static str),
+}
+
+impl CommentSyntax {
+    /// The comment lines for `comment`, one per line of it, without indentation
+    pub fn render(&self, comment: &str) -> Vec<String> {
+        comment
+            .lines()
+            .map(|line| match (self, line.trim_end()) {
+                (CommentSyntax::Line(marker), "") => marker.to_string(),
+                (CommentSyntax::Line(marker), line) => format!("{marker} {line}"),
+                (CommentSyntax::Block(open, close), line) => format!("{open} {line} {close}"),
+            })
+            .collect()
+    }
+}
+
+/// The comment syntax of `lang`; `None` for languages without comments, like JSON, and for
+/// custom languages, whose comments aren't known
+pub fn comment_syntax(lang: AnyLanguage) -> Option<CommentSyntax> {
+    Some(match lang {
+        AnyLanguage::Builtin(lang) => match lang {
+            Language::Bash
+            | Language::Elixir
+            | Language::Python
+            | Language::Ruby
+            | Language::Yaml => CommentSyntax::Line("#"),
+            Language::Haskell | Language::Lua => CommentSyntax::Line("--"),
+            Language::Css => CommentSyntax::Block("/*", "*/"),
+            Language::Html => CommentSyntax::Block("<!--", "-->"),
+            Language::Json => return None,
+            Language::C
+            | Language::Cpp
+            | Language::CSharp
+            | Language::Go
+            | Language::Java
+            | Language::JavaScript
+            | Language::Kotlin
+            | Language::Php
+            | Language::Rust
+            | Language::Scala
+            | Language::Swift
+            | Language::Tsx
+            | Language::TypeScript => CommentSyntax::Line("//"),
+        },
+        AnyLanguage::Extra(lang) => CommentSyntax::Line(lang.line_comment()),
+        AnyLanguage::Dynamic(_) => return None,
+    })
+}
+
+/// Insert `param.comment` above the lines where matches of `param.pattern` start, writing the
+/// files unless `param.dry_run` is set
+pub async fn add_annotation(
+    search_service: &SearchService,
+    param: AddAnnotationParam,
+) -> Result<AddAnnotationResult, ServiceError> {
+    if param.comment.trim().is_empty() {
+        return Err(ServiceError::Internal(
+            "add_annotation needs a non-empty comment".to_string(),
+        ));
+    }
+    let lang = AnyLanguage::resolve(&param.language, None)?;
+
+    let search = search_service
+        .file_search(FileSearchParam {
+            path_pattern: param.path_pattern.clone(),
+            pattern: param.pattern.clone(),
+            language: param.language.clone(),
+            selector: param.selector.clone(),
+            context: param.context.clone(),
+            max_results: param.max_results,
+            max_file_size: param.max_file_size,
+            workspace: param.workspace.clone(),
+            allow_broad: param.allow_broad,
+            walk: param.walk.clone(),
+            ..Default::default()
+        })
+        .await?;
+
+    let mut result = AddAnnotationResult {
+        file_results: Vec::new(),
+        total_annotations: 0,
+        files_with_changes: 0,
+        already_annotated: 0,
+        truncated: search.next_cursor.is_some_and(|cursor| !cursor.is_complete),
+        dry_run: param.dry_run,
+    };
+    for file in search.matches {
+        let file_lang = lang.for_file(&file.file_path);
+        let syntax = comment_syntax(file_lang).ok_or_else(|| {
+            ServiceError::Internal(format!(
+                "{file_lang} has no comment syntax to write an annotation with"
+            ))
+        })?;
+        let comment = syntax.render(&param.comment);
+        let content = tokio::fs::read_to_string(&file.file_path)
+            .await
+            .map_err(|e| ServiceError::FileIoError {
+                message: e.to_string(),
+                path: file.file_path.clone(),
+            })?;
+        let lines: BTreeSet<usize> = file.matches.iter().map(|m| m.start_line).collect();
+        let (annotated, changes, already_annotated) = annotate(&content, &lines, &comment);
+        result.already_annotated += already_annotated;
+        if changes.is_empty() {
+            continue;
+        }
+
+        if !param.dry_run {
+            tokio::fs::write(&file.file_path, &annotated)
+                .await
+                .map_err(|e| ServiceError::FileIoError {
+                    message: e.to_string(),
+                    path: file.file_path.clone(),
+                })?;
+        }
+        result.files_with_changes += 1;
+        result.total_annotations += changes.len();
+        result.file_results.push(FileDiffResult {
+            file_path: file.file_path,
+            file_size_bytes: content.len() as u64,
+            total_changes: changes.len(),
+            changes,
+            file_hash: content_hash(&content),
+            workspace: file.workspace,
+        });
+    }
+    Ok(result)
+}
+
+/// `content` with the `comment` lines inserted above each of `lines` (0-based) that doesn't
+/// already have them, one change per annotated line, and how many lines already had them
+fn annotate(
+    content: &str,
+    lines: &BTreeSet<usize>,
+    comment: &[String],
+) -> (String, Vec<ChangeResult>, usize) {
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let source: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut annotated = String::with_capacity(content.len());
+    let mut changes = Vec::new();
+    let mut already_annotated = 0;
+    let mut inserted_bytes = 0;
+    for (index, line) in source.iter().enumerate() {
+        // Byte offset of the line in `content`
+        let line_start = annotated.len() - inserted_bytes;
+        if !lines.contains(&index) {
+            annotated.push_str(line);
+            continue;
+        }
+        let above = source[index.saturating_sub(comment.len())..index]
+            .iter()
+            .map(|line| line.trim());
+        if index >= comment.len() && above.eq(comment.iter().map(|line| line.trim())) {
+            already_annotated += 1;
+            annotated.push_str(line);
+            continue;
+        }
+
+        let text = line.trim_end_matches(['\r', '\n']);
+        let indent = &text[..text.len() - text.trim_start().len()];
+        let inserted: String = comment
+            .iter()
+            .map(|comment| format!("{indent}{comment}{newline}"))
+            .collect();
+        annotated.push_str(&inserted);
+        annotated.push_str(line);
+        inserted_bytes += inserted.len();
+        changes.push(ChangeResult {
+            start_line: index,
+            end_line: index,
+            start_col: 0,
+            end_col: text.chars().count(),
+            start_byte: line_start,
+            end_byte: line_start + text.len(),
+            old_text: text.to_string(),
+            new_text: format!("{inserted}{text}"),
+        });
+    }
+    (annotated, changes, already_annotated)
+}
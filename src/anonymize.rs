@@ -0,0 +1,300 @@
+//! # Result Anonymization
+//!
+//! Rewrites a tool result so it can be pasted into a bug report or sent to an external model
+//! without the code it came from. Every identifier, string literal and file path is replaced by
+//! a placeholder and every comment by a stub, while the syntax around them is kept, so
+//! `user.save(id)` becomes `id1.id2(id3)` and still shows what the pattern matched.
+//!
+//! Placeholders are consistent across the whole result: a name gets the same placeholder in the
+//! match text, the captured variables, the context lines and the enclosing symbol. Identifiers
+//! keep the shape of their case (`UserService` becomes `Id1`, `MAX_SIZE` becomes `ID_2`).
+//!
+//! Code is found by key: `text`, `old_text`, `new_text` and `code` hold code, `context_before`
+//! and `context_after` hold lines of code and `vars` maps metavariables to code. `file_path` and
+//! `path` are paths, and `file_hash` and `matches_summary` are dropped. Everything else is kept.
+
+use crate::errors::ServiceError;
+use crate::types::{AnonymizeResultParam, AnonymizeResultResult};
+use ast_grep_core::Language as _;
+use ast_grep_core::Node;
+use ast_grep_core::tree_sitter::{LanguageExt, StrDoc};
+use ast_grep_language::SupportLang as Language;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+
+const CODE_KEYS: &[&str] = &["text", "old_text", "new_text", "code"];
+const LINE_KEYS: &[&str] = &["context_before", "context_after"];
+const PATH_KEYS: &[&str] = &["file_path", "path"];
+/// Keys whose values would give the original away without being needed to read the result
+const DROPPED_KEYS: &[&str] = &["file_hash", "matches_summary"];
+
+/// Rewrite `param.result`; `lang` is used for code outside any object with a `file_path`
+pub fn anonymize_result(
+    param: AnonymizeResultParam,
+    lang: Option<Language>,
+) -> Result<AnonymizeResultResult, ServiceError> {
+    let mut anonymizer = Anonymizer {
+        keep: param.keep_identifiers.into_iter().collect(),
+        ..Default::default()
+    };
+    let mut result = param.result;
+    anonymizer.value(&mut result, lang);
+    if anonymizer.missing_language {
+        return Err(ServiceError::Internal(
+            "anonymize_result needs a language for code that isn't under a file_path".to_string(),
+        ));
+    }
+
+    let mapping = param.include_mapping.then(|| {
+        [
+            &anonymizer.identifiers,
+            &anonymizer.strings,
+            &anonymizer.paths,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|(original, placeholder)| (placeholder.clone(), original.clone()))
+        .collect()
+    });
+    Ok(AnonymizeResultResult {
+        result,
+        identifiers: anonymizer.identifiers.len(),
+        strings: anonymizer.strings.len(),
+        comments: anonymizer.comments,
+        files: anonymizer.paths.len(),
+        mapping,
+    })
+}
+
+/// Placeholders handed out so far, keyed by the original text
+#[derive(Default)]
+struct Anonymizer {
+    keep: HashSet<String>,
+    identifiers: HashMap<String, String>,
+    strings: HashMap<String, String>,
+    directories: HashMap<String, String>,
+    paths: HashMap<String, String>,
+    comments: usize,
+    /// Code was found where no language applies, and was left out of the result
+    missing_language: bool,
+}
+
+impl Anonymizer {
+    fn value(&mut self, value: &mut Value, lang: Option<Language>) {
+        match value {
+            Value::Object(map) => {
+                let lang = map
+                    .get("file_path")
+                    .and_then(Value::as_str)
+                    .and_then(|path| Language::from_path(Path::new(path)))
+                    .or(lang);
+                for key in DROPPED_KEYS {
+                    map.remove(*key);
+                }
+                for (key, value) in map.iter_mut() {
+                    let key = key.as_str();
+                    if CODE_KEYS.contains(&key) {
+                        self.code_value(value, lang);
+                    } else if LINE_KEYS.contains(&key) {
+                        if let Value::Array(lines) = value {
+                            lines
+                                .iter_mut()
+                                .for_each(|line| self.code_value(line, lang));
+                        }
+                    } else if key == "vars" {
+                        if let Value::Object(vars) = value {
+                            vars.values_mut().for_each(|var| self.code_value(var, lang));
+                        }
+                    } else if PATH_KEYS.contains(&key) {
+                        if let Value::String(path) = value {
+                            *path = self.path(path);
+                        }
+                    } else if key == "enclosing_symbol" {
+                        if let Some(Value::String(name)) = value.get_mut("name") {
+                            *name = self.qualified_name(name);
+                        }
+                    } else {
+                        self.value(value, lang);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.value(item, lang)),
+            _ => {}
+        }
+    }
+
+    fn code_value(&mut self, value: &mut Value, lang: Option<Language>) {
+        let Value::String(code) = value else {
+            return;
+        };
+        match lang {
+            Some(lang) => *code = self.code(code, lang),
+            None => {
+                self.missing_language = true;
+                code.clear();
+            }
+        }
+    }
+
+    /// `code` with its identifiers, string contents and comments replaced
+    fn code(&mut self, code: &str, lang: Language) -> String {
+        let ast = lang.ast_grep(code);
+        let mut edits = Vec::new();
+        self.collect_edits(&ast.root(), &mut edits);
+        let mut anonymized = code.to_string();
+        for (range, replacement) in edits.into_iter().rev() {
+            anonymized.replace_range(range, &replacement);
+        }
+        anonymized
+    }
+
+    /// Replacements for the leaves under `node`, in source order
+    fn collect_edits(
+        &mut self,
+        node: &Node<StrDoc<Language>>,
+        edits: &mut Vec<(Range<usize>, String)>,
+    ) {
+        let kind = node.kind();
+        if kind.contains("comment") {
+            self.comments += 1;
+            edits.push((node.range(), comment_stub(&node.text())));
+            return;
+        }
+        if !node.is_leaf() {
+            for child in node.children() {
+                self.collect_edits(&child, edits);
+            }
+            return;
+        }
+        let text = node.text();
+        if kind.contains("identifier") {
+            if !self.keep.contains(text.as_ref()) {
+                edits.push((node.range(), self.identifier(&text)));
+            }
+        } else if kind == "string_fragment"
+            || kind.ends_with("string_content")
+            || kind.ends_with("literal_content")
+        {
+            edits.push((node.range(), self.string(&text)));
+        } else if kind.contains("string") {
+            // Grammars without a separate content node have the quotes in the leaf itself
+            let quote = |c: char| matches!(c, '"' | '\'' | '`');
+            let inner = text.trim_start_matches(quote).trim_end_matches(quote);
+            if !inner.is_empty() && text.starts_with(quote) {
+                let start = node.range().start + text.find(inner).unwrap_or(0);
+                edits.push((start..start + inner.len(), self.string(inner)));
+            }
+        }
+    }
+
+    fn identifier(&mut self, name: &str) -> String {
+        let n = self.identifiers.len() + 1;
+        self.identifiers
+            .entry(name.to_string())
+            .or_insert_with(|| {
+                let underscores = &name[..name.len() - name.trim_start_matches('_').len()];
+                let rest = &name[underscores.len()..];
+                let shape = if rest.len() > 1
+                    && rest.chars().all(|c| !c.is_lowercase())
+                    && rest.chars().any(char::is_uppercase)
+                {
+                    format!("ID_{n}")
+                } else if rest.starts_with(char::is_uppercase) {
+                    format!("Id{n}")
+                } else {
+                    format!("id{n}")
+                };
+                format!("{underscores}{shape}")
+            })
+            .clone()
+    }
+
+    fn string(&mut self, content: &str) -> String {
+        let n = self.strings.len() + 1;
+        self.strings
+            .entry(content.to_string())
+            .or_insert_with(|| format!("str{n}"))
+            .clone()
+    }
+
+    /// A dotted name such as `UserService.login`, one identifier at a time
+    fn qualified_name(&mut self, name: &str) -> String {
+        name.split('.')
+            .map(|part| {
+                if self.keep.contains(part) {
+                    part.to_string()
+                } else {
+                    self.identifier(part)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    /// `path` with each directory and the file name replaced, keeping the extension
+    fn path(&mut self, path: &str) -> String {
+        if let Some(anonymized) = self.paths.get(path) {
+            return anonymized.clone();
+        }
+        let mut segments: Vec<&str> = path.split(['/', '\\']).collect();
+        let file_name = segments.pop().unwrap_or_default();
+        let mut parts: Vec<String> = segments
+            .into_iter()
+            .map(|segment| match segment {
+                "" | "." | ".." => segment.to_string(),
+                _ => {
+                    let n = self.directories.len() + 1;
+                    self.directories
+                        .entry(segment.to_string())
+                        .or_insert_with(|| format!("dir{n}"))
+                        .clone()
+                }
+            })
+            .collect();
+        let extension = Path::new(file_name)
+            .extension()
+            .map(|extension| format!(".{}", extension.to_string_lossy()))
+            .unwrap_or_default();
+        parts.push(format!("file{}{extension}", self.paths.len() + 1));
+        let anonymized = parts.join("/");
+        self.paths.insert(path.to_string(), anonymized.clone());
+        anonymized
+    }
+}
+
+/// The comment markers of `comment` around the word `comment`, e.g. `/* comment */`
+fn comment_stub(comment: &str) -> String {
+    let prefix: String = comment
+        .chars()
+        .take_while(|c| !c.is_alphanumeric() && !c.is_whitespace())
+        .collect();
+    let suffix = if comment.ends_with("*/") {
+        " */"
+    } else if comment.ends_with("-->") {
+        " -->"
+    } else {
+        ""
+    };
+    format!("{prefix} comment{suffix}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifiers_keep_their_shape() {
+        let mut anonymizer = Anonymizer::default();
+        assert_eq!(
+            anonymizer.code(
+                "const MAX_SIZE = new UserStore(_cache, \"secret\"); // why\n",
+                Language::JavaScript
+            ),
+            "const ID_1 = new Id2(_id3, \"str1\"); // comment\n"
+        );
+        assert_eq!(anonymizer.qualified_name("UserStore.load"), "Id2.id4");
+        assert_eq!(comment_stub("/** docs */"), "/** comment */");
+    }
+}
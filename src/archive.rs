@@ -0,0 +1,249 @@
+//! # Archive Search
+//!
+//! Read-only access to source files inside zip, jar and tar archives, for investigations that
+//! need to look inside built artifacts. Entries are extracted to memory, never to disk, and are
+//! addressed as `path/to/archive.zip!path/inside.js`. Archives are only searched; replace tools
+//! never see these paths.
+
+use crate::walk::{WalkSettings, walk_files};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// Separates the archive path from the entry path in reported file paths
+pub const ENTRY_SEPARATOR: char = '!';
+
+/// Upper bound on the text extracted from one archive for a single search page
+pub const MAX_EXTRACTED_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// zip and the Java archives built on it (jar, war, ear)
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// The format of `path`, judged by its extension
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        let format = if [".zip", ".jar", ".war", ".ear"]
+            .iter()
+            .any(|extension| name.ends_with(extension))
+        {
+            ArchiveFormat::Zip
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            ArchiveFormat::TarGz
+        } else if name.ends_with(".tar") {
+            ArchiveFormat::Tar
+        } else {
+            return None;
+        };
+        Some(format)
+    }
+}
+
+/// The reported path of `entry` inside `archive`
+pub fn entry_path(archive: &str, entry: &str) -> String {
+    format!("{archive}{ENTRY_SEPARATOR}{entry}")
+}
+
+/// Split `archive.zip!inside.js` into the archive path and the entry name
+pub fn split_entry_path(path: &str) -> Option<(&str, &str)> {
+    path.match_indices(ENTRY_SEPARATOR)
+        .map(|(index, _)| (&path[..index], &path[index + 1..]))
+        .find(|(archive, _)| ArchiveFormat::for_path(Path::new(archive)).is_some())
+}
+
+/// Archives under `roots`
+pub fn find_archives(roots: &[PathBuf], walk: &WalkSettings) -> Vec<PathBuf> {
+    roots
+        .iter()
+        .flat_map(|root| {
+            walk_files(root, walk)
+                .map(|entry| entry.into_path())
+                .filter(|path| ArchiveFormat::for_path(path).is_some())
+        })
+        .collect()
+}
+
+/// Entries of the archives under `roots` accepted by `include`, as `(entry_path, size)`.
+/// Archives that can't be read are logged and left out
+pub fn find_entries(
+    roots: &[PathBuf],
+    walk: &WalkSettings,
+    include: impl Fn(&str) -> bool,
+    max_entry_size: u64,
+) -> Vec<(String, u64)> {
+    let mut found = Vec::new();
+    for archive in find_archives(roots, walk) {
+        let archive_path = archive.to_string_lossy().to_string();
+        match list_entries(&archive, &include, max_entry_size) {
+            Ok(entries) => found.extend(
+                entries
+                    .into_iter()
+                    .map(|(name, size)| (entry_path(&archive_path, &name), size)),
+            ),
+            Err(e) => tracing::warn!("Skipping archive {archive_path}: {e}"),
+        }
+    }
+    found
+}
+
+/// Names and sizes of the files in `archive` accepted by `include`, leaving out those larger
+/// than `max_entry_size`
+pub fn list_entries(
+    archive: &Path,
+    include: impl Fn(&str) -> bool,
+    max_entry_size: u64,
+) -> io::Result<Vec<(String, u64)>> {
+    let mut entries = Vec::new();
+    for_each_entry(archive, |name, size, _| {
+        if size <= max_entry_size && include(name) {
+            entries.push((name.to_string(), size));
+        }
+        Ok(())
+    })?;
+    Ok(entries)
+}
+
+/// Text of the named entries in one archive
+#[derive(Debug, Default)]
+pub struct ArchiveContents {
+    /// Content keyed by entry name. Entries that are not UTF-8 text are left out
+    pub entries: HashMap<String, String>,
+    /// Entries that were not read, with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Read the entries in `names` from `archive`, stopping at `MAX_EXTRACTED_BYTES` in total
+pub fn read_entries(
+    archive: &Path,
+    names: &HashSet<&str>,
+    max_entry_size: u64,
+) -> io::Result<ArchiveContents> {
+    let mut contents = ArchiveContents::default();
+    let mut extracted = 0;
+    for_each_entry(archive, |name, size, reader| {
+        if !names.contains(name) {
+            return Ok(());
+        }
+        if extracted + size > MAX_EXTRACTED_BYTES {
+            contents.skipped.push((
+                name.to_string(),
+                format!("archive extraction limit of {MAX_EXTRACTED_BYTES} bytes reached"),
+            ));
+            return Ok(());
+        }
+        // Declared sizes can't be trusted, so never read past the limit
+        let mut bytes = Vec::new();
+        reader.take(max_entry_size + 1).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 > max_entry_size {
+            contents.skipped.push((
+                name.to_string(),
+                format!("entry is larger than {max_entry_size} bytes"),
+            ));
+            return Ok(());
+        }
+        extracted += bytes.len() as u64;
+        if let Ok(text) = String::from_utf8(bytes) {
+            contents.entries.insert(name.to_string(), text);
+        }
+        Ok(())
+    })?;
+    Ok(contents)
+}
+
+/// Read the archive entries among `paths`, opening each archive once. Paths outside archives
+/// are ignored. The result is keyed by archive path; an archive that can't be read reports all
+/// its requested entries as skipped
+pub fn read_entry_paths<'a>(
+    paths: impl IntoIterator<Item = &'a str>,
+    max_entry_size: u64,
+) -> HashMap<String, ArchiveContents> {
+    let mut requested: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for path in paths {
+        if let Some((archive, entry)) = split_entry_path(path) {
+            requested.entry(archive).or_default().insert(entry);
+        }
+    }
+    requested
+        .into_iter()
+        .map(|(archive, names)| {
+            let contents =
+                read_entries(Path::new(archive), &names, max_entry_size).unwrap_or_else(|e| {
+                    ArchiveContents {
+                        entries: HashMap::new(),
+                        skipped: names
+                            .iter()
+                            .map(|name| (name.to_string(), format!("failed to read archive: {e}")))
+                            .collect(),
+                    }
+                });
+            (archive.to_string(), contents)
+        })
+        .collect()
+}
+
+/// Call `visit` with the name, declared size and content of every file in `archive`
+fn for_each_entry(
+    archive: &Path,
+    mut visit: impl FnMut(&str, u64, &mut dyn Read) -> io::Result<()>,
+) -> io::Result<()> {
+    let format = ArchiveFormat::for_path(archive)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a supported archive"))?;
+    let file = BufReader::new(File::open(archive)?);
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = zip::ZipArchive::new(file).map_err(io::Error::other)?;
+            for index in 0..zip.len() {
+                let mut entry = zip.by_index(index).map_err(io::Error::other)?;
+                if entry.is_file() {
+                    let name = entry.name().to_string();
+                    let size = entry.size();
+                    visit(&name, size, &mut entry)?;
+                }
+            }
+        }
+        ArchiveFormat::Tar => visit_tar(tar::Archive::new(file), visit)?,
+        ArchiveFormat::TarGz => {
+            visit_tar(tar::Archive::new(flate2::read::GzDecoder::new(file)), visit)?
+        }
+    }
+    Ok(())
+}
+
+fn visit_tar<R: Read>(
+    mut archive: tar::Archive<R>,
+    mut visit: impl FnMut(&str, u64, &mut dyn Read) -> io::Result<()>,
+) -> io::Result<()> {
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.header().entry_type().is_file() {
+            let name = entry.path()?.to_string_lossy().to_string();
+            let size = entry.size();
+            visit(&name, size, &mut entry)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_entry_path() {
+        assert_eq!(
+            split_entry_path("/work/lib/app.jar!com/example/App.java"),
+            Some(("/work/lib/app.jar", "com/example/App.java"))
+        );
+        assert_eq!(
+            split_entry_path("/work/bang!dir/site.tar.gz!index.js"),
+            Some(("/work/bang!dir/site.tar.gz", "index.js"))
+        );
+        assert_eq!(split_entry_path("/work/notes!draft.js"), None);
+    }
+}
@@ -1,23 +1,67 @@
+use crate::analysis::project_overview;
+use crate::annotation::add_annotation;
+use crate::anonymize::anonymize_result;
 use crate::ast_utils::AstParser;
+use crate::code_buffer::CodeBuffers;
+use crate::codemods::default_path_pattern;
+use crate::compression::{compress_response, requested_compression};
 use crate::config::ServiceConfig;
+use crate::conventions::detect_conventions;
+use crate::cursor::CursorScope;
+use crate::dedupe_code::dedupe_code;
+use crate::directory_move::{apply_plan, plan_directory_move};
+use crate::directory_summary::{SummaryCache, source_files, summarize_directories};
+use crate::doc_coverage::doc_coverage;
+use crate::dynamic_languages::DynamicLanguage;
 use crate::errors::ServiceError;
+use crate::experiment::run_experiment;
+use crate::extra_languages::{AnyLanguage, ExtraLanguage};
+use crate::file_fetch::get_file_by_hash;
+use crate::health_check::health_check;
+use crate::index::FileIndex;
+use crate::input_limits::check_input_sizes;
+use crate::language_detection;
 use crate::learning::{
     ExplorePatternParam, GeneratePromptParam, GeneratedPrompt, LearningService, PatternCatalog,
-    ValidatePatternParam, ValidationResult,
+    ProjectContext, SuggestPatternParam, SuggestPatternResult, ValidatePatternParam,
+    ValidationResult, suggest_pattern,
+};
+use crate::path_validation::{
+    resolve_output_path, resolve_path_pattern, validate_creatable_path, validate_path_within_roots,
 };
 use crate::pattern::PatternMatcher;
-use crate::replace::ReplaceService;
+use crate::playground::{self, write_playground};
+use crate::prewarm::{PrewarmStats, prewarm};
+use crate::refactoring::rename::plan_rename;
+use crate::references::{find_references, is_identifier, same_family, supports};
+use crate::replace::{ReplaceService, preview_rule_fix};
+use crate::resources::{self, LatestResult};
 use crate::response_formatter::ResponseFormatter;
+use crate::rules::lint::lint_report;
+use crate::rules::pack;
+use crate::rules::quick_fix::fix_rules;
+use crate::rules::rewrite::{apply_transforms, check_transforms};
 use crate::rules::*;
-use crate::rules::{RuleEvaluator, RuleService, RuleStorage};
+use crate::rules::{FileRuleStorage, RuleEvaluator, RuleService, open_rule_storage};
 use crate::search::SearchService;
+use crate::similar_code::find_similar_code;
+use crate::symbol_search::{SymbolIndex, search_symbols};
+use crate::telemetry;
+use crate::tool_middleware::{MiddlewareStack, ToolMiddleware};
+use crate::tool_registry::{CustomTool, ToolRegistry};
 use crate::tool_router::ToolRouter;
 use crate::types::*;
+use crate::wrap::wrap_matches;
+use crate::write_lock::{self, WriteLock};
 
-use ast_grep_core::{AstGrep, Pattern};
+use ast_grep_core::tree_sitter::LanguageExt;
+use ast_grep_core::{AstGrep, Language as _, Pattern};
 
 use lru::LruCache;
+use std::collections::{HashMap, HashSet};
 use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use std::{borrow::Cow, str::FromStr, sync::Arc, sync::Mutex};
 
 use ast_grep_language::SupportLang as Language;
@@ -28,7 +72,6 @@ const ALL_LANGUAGES: &[&str] = &[
     "cpp",
     "csharp",
     "css",
-    "dart",
     "elixir",
     "go",
     "haskell",
@@ -53,14 +96,65 @@ use rmcp::{
     ServerHandler,
     model::{
         CallToolRequestParam, CallToolResult, ErrorData, GetPromptRequestParam, GetPromptResult,
-        Implementation, InitializeResult, ListPromptsResult, ListToolsResult,
-        PaginatedRequestParam, Prompt, PromptArgument, PromptMessage, PromptMessageContent,
-        PromptMessageRole, PromptsCapability, ProtocolVersion, ServerCapabilities,
+        Implementation, InitializeResult, JsonObject, ListPromptsResult, ListResourcesResult,
+        ListToolsResult, LoggingLevel, LoggingMessageNotificationParam, PaginatedRequestParam,
+        ProgressNotificationParam, ProgressToken, Prompt, PromptArgument, PromptMessage,
+        PromptMessageContent, PromptMessageRole, PromptsCapability, ProtocolVersion,
+        ReadResourceRequestParam, ReadResourceResult, Resource, ResourceContents,
+        ResourcesCapability, ServerCapabilities, SetLevelRequestParam,
     },
-    service::{RequestContext, RoleServer},
+    service::{Peer, RequestContext, RoleServer},
 };
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+use tokio_util::sync::CancellationToken;
 // Removed unused serde imports
 
+/// Where a tool call that carried a `progressToken` sends its progress notifications
+struct ProgressReporter {
+    token: ProgressToken,
+    peer: Peer<RoleServer>,
+}
+
+impl ProgressReporter {
+    /// Notify the client of one file of a streaming `file_search`, carrying the file as JSON
+    async fn send(&self, update: &FileSearchProgress) {
+        let notification = ProgressNotificationParam {
+            progress_token: self.token.clone(),
+            progress: update.files_searched as u32,
+            total: Some(update.total_files as u32),
+            message: serde_json::to_string(update).ok(),
+        };
+        if let Err(e) = self.peer.notify_progress(notification).await {
+            tracing::warn!("Failed to send file_search progress: {e}");
+        }
+    }
+}
+
+/// Logging levels above `error`; a client that asked for one of them isn't sent scan findings
+const ABOVE_ERROR: [LoggingLevel; 3] = [
+    LoggingLevel::Critical,
+    LoggingLevel::Alert,
+    LoggingLevel::Emergency,
+];
+
+/// Log each error-severity finding of a running `scan` to the client as it arrives on
+/// `findings`
+async fn log_scan_errors(
+    peer: Peer<RoleServer>,
+    mut findings: tokio::sync::mpsc::UnboundedReceiver<ScanFinding>,
+) {
+    while let Some(finding) = findings.recv().await {
+        let notification = LoggingMessageNotificationParam {
+            level: LoggingLevel::Error,
+            logger: Some("scan".to_string()),
+            data: serde_json::to_value(&finding).unwrap_or_default(),
+        };
+        if let Err(e) = peer.notify_logging_message(notification).await {
+            tracing::warn!("Failed to log scan finding: {e}");
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct AstGrepService {
     #[allow(dead_code)]
@@ -75,6 +169,15 @@ pub struct AstGrepService {
     pub(crate) replace_service: ReplaceService,
     pub(crate) rule_service: RuleService,
     pub(crate) learning_service: LearningService,
+    pub(crate) summary_cache: SummaryCache,
+    pub(crate) symbol_index: SymbolIndex,
+    pub(crate) code_buffers: CodeBuffers,
+    pub(crate) tool_registry: ToolRegistry,
+    pub(crate) middleware: MiddlewareStack,
+    /// The level the client asked for with `logging/setLevel`, if it did
+    pub(crate) log_level: Arc<Mutex<Option<LoggingLevel>>>,
+    /// The session's most recent search or replace result, published as `results://latest`
+    pub(crate) latest_result: LatestResult,
 }
 
 impl Default for AstGrepService {
@@ -99,22 +202,37 @@ impl AstGrepService {
         })
     }
 
-    /// Extract unique Tree-sitter node kinds from the given code
+    /// Extract unique Tree-sitter node kinds from the given code, most frequent first
     /// This is useful for users to discover what node kinds are available for use in Kind rules
     fn extract_node_kinds(&self, code: &str, lang: Language) -> Result<Vec<String>, ServiceError> {
-        let ast = AstGrep::new(code, lang);
+        Ok(self
+            .count_node_kinds(code, lang)
+            .into_iter()
+            .map(|count| count.kind)
+            .collect())
+    }
 
-        // Use a catch-all pattern to find all nodes
-        let pattern = Pattern::new("$_", lang);
+    /// Count the nodes of each kind, most frequent first and ties in alphabetical order
+    fn count_node_kinds<L: LanguageExt + Copy>(&self, code: &str, lang: L) -> Vec<NodeKindCount> {
+        let ast = AstGrep::new(code, lang);
 
-        let mut unique_kinds = std::collections::HashSet::new();
-        for node_match in ast.root().find_all(pattern) {
-            unique_kinds.insert(node_match.get_node().kind().to_string());
+        // Walk the tree rather than matching a `$_` catch-all, which not every grammar parses
+        // as a metavariable
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for node in ast.root().dfs().filter(|node| node.is_named()) {
+            *counts.entry(node.kind().to_string()).or_default() += 1;
         }
 
-        let mut kinds: Vec<String> = unique_kinds.into_iter().collect();
-        kinds.sort();
-        Ok(kinds)
+        let mut kinds: Vec<NodeKindCount> = counts
+            .into_iter()
+            .map(|(kind, count)| NodeKindCount {
+                category: NodeCategory::for_kind(&kind),
+                kind,
+                count,
+            })
+            .collect();
+        kinds.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.kind.cmp(&b.kind)));
+        kinds
     }
 
     /// Generate a simple metavariable pattern from code examples
@@ -160,7 +278,14 @@ impl AstGrepService {
             pattern_matcher.clone(),
             rule_evaluator.clone(),
         );
-        let rule_storage = RuleStorage::with_directories(config.all_rule_directories());
+        let rule_storage = open_rule_storage(&config).unwrap_or_else(|e| {
+            // The server checks the database when it starts, so this only fails if it went away
+            tracing::error!("Falling back to YAML rule files: {e}");
+            Arc::new(
+                FileRuleStorage::with_directories(config.all_rule_directories())
+                    .with_env_vars(config.env_vars.clone()),
+            )
+        });
         let rule_service = RuleService::new(config.clone(), rule_evaluator.clone(), rule_storage);
         let learning_service = LearningService::new().unwrap_or_else(|_| {
             // If learning service fails to initialize, create a minimal one
@@ -176,6 +301,13 @@ impl AstGrepService {
             replace_service,
             rule_service,
             learning_service,
+            summary_cache: SummaryCache::new(),
+            symbol_index: SymbolIndex::new(),
+            code_buffers: CodeBuffers::new(),
+            tool_registry: ToolRegistry::new(),
+            middleware: MiddlewareStack::new(),
+            log_level: Arc::new(Mutex::new(None)),
+            latest_result: LatestResult::new(),
         }
     }
 
@@ -188,24 +320,180 @@ impl AstGrepService {
         }
     }
 
+    /// A clone of the service for one client session. Caches, rules and custom tools stay shared;
+    /// state a client builds up across calls, such as its code buffers and dry run diffs, is only
+    /// visible to services for the same `session_id`. Transports serving several clients call
+    /// this once per client
+    pub fn for_session(&self, session_id: &str) -> Self {
+        Self {
+            code_buffers: self.code_buffers.for_session(session_id),
+            replace_service: self.replace_service.for_session(),
+            log_level: Arc::new(Mutex::new(None)),
+            latest_result: LatestResult::new(),
+            ..self.clone()
+        }
+    }
+
+    /// This service for one request, whose file searches and replacements stop and return
+    /// partial results once `token` is cancelled
+    pub fn with_cancellation(&self, token: CancellationToken) -> Self {
+        Self {
+            search_service: self.search_service.with_cancellation(token.clone()),
+            replace_service: self.replace_service.with_cancellation(token),
+            ..self.clone()
+        }
+    }
+
+    /// Start filling the file index and symbol cache in the background when
+    /// `config.prewarm` is set; see [`crate::prewarm`]
+    pub fn spawn_prewarm(&self) -> Option<tokio::task::JoinHandle<PrewarmStats>> {
+        let delay = self.config.prewarm?;
+        let index = self.search_service.index().cloned();
+        let symbols = self.symbol_index.clone();
+        let roots = self.config.root_directories.clone();
+        Some(tokio::task::spawn_blocking(move || {
+            let started = Instant::now();
+            let stats = prewarm(index.as_ref(), &symbols, &roots, delay);
+            tracing::info!(
+                "Pre-warmed {} indexed files and {} symbol files in {:?}",
+                stats.indexed_files,
+                stats.symbol_files,
+                started.elapsed()
+            );
+            stats
+        }))
+    }
+
+    /// Drop the state of this service's session, e.g. when its client disconnects
+    pub fn end_session(&self) {
+        self.code_buffers.end_session();
+    }
+
+    /// Register a custom tool so it is listed and routed alongside the built-in tools
+    pub fn register_tool(&self, tool: impl CustomTool + 'static) -> Result<(), ServiceError> {
+        self.tool_registry.register(tool)
+    }
+
+    /// Remove a previously registered custom tool
+    pub fn unregister_tool(&self, name: &str) -> bool {
+        self.tool_registry.unregister(name)
+    }
+
+    /// Add a middleware layer that runs around every tool call
+    pub fn add_middleware(&self, middleware: impl ToolMiddleware + 'static) {
+        self.middleware.push(middleware);
+    }
+
+    /// Run a tool call exactly as the MCP `call_tool` handler does, middleware included. Used
+    /// by front ends that don't speak MCP, such as `--batch` mode. A `compress` argument on any
+    /// tool packs the result JSON (see [`crate::compression`]).
+    #[tracing::instrument(
+        name = "tool_call",
+        skip_all,
+        fields(tool = %request.name, outcome = tracing::field::Empty, duration_ms = tracing::field::Empty, files = tracing::field::Empty)
+    )]
+    pub async fn handle_tool_call(
+        &self,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.handle_tool_call_with_progress(request, None, None)
+            .await
+    }
+
+    /// [`Self::handle_tool_call`] for a request from an MCP client, which `peer` can send log
+    /// messages to, and that may have asked for progress notifications
+    async fn handle_tool_call_with_progress(
+        &self,
+        request: CallToolRequestParam,
+        progress: Option<ProgressReporter>,
+        peer: Option<Peer<RoleServer>>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let tool_name = request.name.to_string();
+        let started = Instant::now();
+        let result = async {
+            let compression = requested_compression(&request)?;
+            check_input_sizes(&request, &self.config.max_input_sizes)?;
+            let response = self
+                .middleware
+                .call(request, |request| async move {
+                    // Special handling for file_search with large results
+                    if request.name == "file_search" {
+                        return self
+                            .handle_file_search_with_optimization(
+                                request,
+                                compression.is_some(),
+                                progress,
+                            )
+                            .await;
+                    }
+                    if request.name == "scan"
+                        && let Some(peer) = peer.filter(|_| self.logs_scan_errors())
+                    {
+                        return self.handle_scan_with_logging(request, peer).await;
+                    }
+
+                    // Special handling for list_languages which has custom implementation
+                    match request.name.as_ref() {
+                        "list_languages" => self.handle_list_languages_tool(request).await,
+                        _ => ToolRouter::dispatch(self, request).await,
+                    }
+                })
+                .await?;
+            self.latest_result.record(&tool_name, &response);
+            match compression {
+                Some(compression) => compress_response(response, compression),
+                None => Ok(response),
+            }
+        }
+        .await;
+        telemetry::record_tool_call(&tool_name, &result, started.elapsed());
+        result
+    }
+
+    /// Built-in tools followed by any registered custom tools
+    pub fn available_tools(&self) -> ListToolsResult {
+        let mut result = crate::tools::ToolService::list_tools();
+        result.tools.extend(self.tool_registry.definitions());
+        result
+    }
+
     /// Generate a stringified syntax tree for the given code and language
     /// This exposes the Tree-sitter AST structure for debugging and understanding
     pub async fn generate_ast(
         &self,
         param: GenerateAstParam,
     ) -> Result<GenerateAstResult, ServiceError> {
-        let lang = self.parse_language(&param.language)?;
+        let param = GenerateAstParam {
+            code: self
+                .code_buffers
+                .resolve(param.code, param.buffer_id.as_deref())?,
+            buffer_id: None,
+            ..param
+        };
+        let lang = match AnyLanguage::from_str(&param.language) {
+            Ok(lang) => lang,
+            Err(_) => AnyLanguage::Builtin(self.parse_language(&param.language)?),
+        };
         let ast_parser = AstParser::new();
         let ast_string = ast_parser.generate_ast_debug_string(&param.code, lang);
 
-        // Extract unique node kinds from the AST
-        let node_kinds = self.extract_node_kinds(&param.code, lang)?;
+        let node_kind_stats = self.count_node_kinds(&param.code, lang);
+        let node_kinds = node_kind_stats
+            .iter()
+            .map(|stat| stat.kind.clone())
+            .collect();
+        let kind_counts = node_kind_stats
+            .iter()
+            .map(|stat| (stat.kind.clone(), stat.count))
+            .collect();
 
         Ok(GenerateAstResult {
             ast: ast_string,
             language: param.language,
             code_length: param.code.chars().count(),
             node_kinds,
+            node_kind_stats,
+            kind_counts,
         })
     }
 
@@ -246,6 +534,23 @@ impl AstGrepService {
             });
         }
 
+        // Utils must resolve without cycles or references to undefined utils
+        self.rule_evaluator.with_utils(&config.utils)?;
+
+        for (name, constraint) in &config.constraints {
+            if !is_metavariable_name(name) {
+                return Err(ServiceError::ParserError(format!(
+                    "Constraint '{name}' must name a metavariable without the $, e.g. VAR for $VAR"
+                )));
+            }
+            if !self.has_rule_condition(constraint) {
+                return Err(ServiceError::ParserError(format!(
+                    "Constraint on ${name} must have at least one condition (e.g., pattern, kind, regex)"
+                )));
+            }
+        }
+        check_transforms(config)?;
+
         Ok(())
     }
 
@@ -273,17 +578,96 @@ impl AstGrepService {
 
     #[tracing::instrument(skip(self), fields(language = %param.language, pattern = %param.pattern))]
     pub async fn search(&self, param: SearchParam) -> Result<SearchResult, ServiceError> {
+        let param = SearchParam {
+            code: self
+                .code_buffers
+                .resolve(param.code, param.buffer_id.as_deref())?,
+            buffer_id: None,
+            ..param
+        };
         let result = self.search_service.search(param).await?;
         tracing::Span::current().record("matches_found", result.matches.len());
         Ok(result)
     }
 
+    /// Open a server-side buffer for code too large to send in one message
+    #[tracing::instrument(skip(self, param), fields(size_bytes = param.content.len()))]
+    pub fn begin_code_buffer(
+        &self,
+        param: BeginCodeBufferParam,
+    ) -> Result<CodeBufferInfo, ServiceError> {
+        self.code_buffers.begin(
+            param.content,
+            Duration::from_secs(param.ttl_seconds),
+            self.config.max_file_size,
+        )
+    }
+
+    /// Add a chunk of code to a buffer opened with [`Self::begin_code_buffer`]
+    #[tracing::instrument(skip(self, param), fields(buffer_id = %param.buffer_id, size_bytes = param.content.len()))]
+    pub fn append_code_buffer(
+        &self,
+        param: AppendCodeBufferParam,
+    ) -> Result<CodeBufferInfo, ServiceError> {
+        self.code_buffers
+            .append(&param.buffer_id, &param.content, self.config.max_file_size)
+    }
+
+    /// [`Self::search`] over the content of a code buffer
+    pub async fn search_buffer(&self, param: SearchParam) -> Result<SearchResult, ServiceError> {
+        if param.buffer_id.is_none() {
+            return Err(ServiceError::Internal(
+                "search_buffer requires buffer_id; use search for inline code".to_string(),
+            ));
+        }
+        self.search(param).await
+    }
+
     #[tracing::instrument(skip(self), fields(language = %param.language, pattern = %param.pattern, path_pattern = %param.path_pattern))]
     pub async fn file_search(
         &self,
         param: FileSearchParam,
     ) -> Result<FileSearchResult, ServiceError> {
-        let result = self.search_service.file_search(param).await?;
+        self.file_search_with_progress(param, None).await
+    }
+
+    /// [`Self::file_search`], sending each file with matches to `progress` as soon as it has
+    /// been searched. The returned result still holds every match
+    #[tracing::instrument(skip(self, progress), fields(language = %param.language, pattern = %param.pattern, path_pattern = %param.path_pattern))]
+    pub async fn file_search_streaming(
+        &self,
+        param: FileSearchParam,
+        progress: UnboundedSender<FileSearchProgress>,
+    ) -> Result<FileSearchResult, ServiceError> {
+        self.file_search_with_progress(param, Some(&progress)).await
+    }
+
+    /// Several file searches keyed by id, run over one walk of the root directories
+    #[tracing::instrument(skip(self, param), fields(searches = param.searches.len()))]
+    pub async fn batch_search(
+        &self,
+        param: BatchSearchParam,
+    ) -> Result<BatchSearchResult, ServiceError> {
+        let result = self.search_service.batch_search(param).await?;
+        tracing::Span::current().record("files_searched", result.files_searched);
+        Ok(result)
+    }
+
+    async fn file_search_with_progress(
+        &self,
+        param: FileSearchParam,
+        progress: Option<&UnboundedSender<FileSearchProgress>>,
+    ) -> Result<FileSearchResult, ServiceError> {
+        let scope = self.cursor_scope(&param, param.cursor.as_ref())?;
+        let fix_rules = match param.suggest_fixes {
+            true => fix_rules(self.rule_service.storage().load_all_rules().await?),
+            false => Vec::new(),
+        };
+        let mut result = self
+            .search_service
+            .file_search_with_fixes(param, &fix_rules, progress)
+            .await?;
+        scope.stamp(result.next_cursor.as_mut());
         tracing::Span::current().record("total_files_found", result.total_files_found);
         tracing::Span::current().record("files_with_matches", result.matches.len());
         Ok(result)
@@ -291,32 +675,274 @@ impl AstGrepService {
 
     #[tracing::instrument(skip(self), fields(language = %param.language, pattern = %param.pattern, replacement = %param.replacement))]
     pub async fn replace(&self, param: ReplaceParam) -> Result<ReplaceResult, ServiceError> {
+        let param = ReplaceParam {
+            code: self
+                .code_buffers
+                .resolve(param.code, param.buffer_id.as_deref())?,
+            buffer_id: None,
+            ..param
+        };
         let result = self.replace_service.replace(param).await?;
         tracing::Span::current().record("changes_made", result.changes.len());
         Ok(result)
     }
 
+    /// Hold the write lock of the root directories in `workspace` while a call that `writes`
+    /// runs; previews take no lock
+    async fn lock_roots(
+        &self,
+        workspace: Option<&str>,
+        writes: bool,
+    ) -> Result<Option<WriteLock>, ServiceError> {
+        if !writes {
+            return Ok(None);
+        }
+        write_lock::acquire(&self.config, workspace).await.map(Some)
+    }
+
+    /// Check that `cursor` was issued by a call with the same `param` over the same root
+    /// directories, returning the scope to stamp the cursor of the next page with
+    fn cursor_scope<P: serde::Serialize>(
+        &self,
+        param: &P,
+        cursor: Option<&CursorParam>,
+    ) -> Result<CursorScope, ServiceError> {
+        let scope = CursorScope::new(param, &self.config.root_directories);
+        scope.check(cursor, self.config.cursor_ttl)?;
+        Ok(scope)
+    }
+
     #[tracing::instrument(skip(self), fields(language = %param.language, pattern = %param.pattern, path_pattern = %param.path_pattern, replacement = %param.replacement, dry_run = %param.dry_run))]
     pub async fn file_replace(
         &self,
         param: FileReplaceParam,
     ) -> Result<FileReplaceResult, ServiceError> {
-        let result = self.replace_service.file_replace(param).await?;
+        let scope = self.cursor_scope(&param, param.cursor.as_ref())?;
+        let _lock = self
+            .lock_roots(param.workspace.as_deref(), !param.dry_run)
+            .await?;
+        let mut result = self.replace_service.file_replace(param).await?;
+        scope.stamp(result.next_cursor.as_mut());
         tracing::Span::current().record("total_files_found", result.total_files_found);
         tracing::Span::current().record("files_with_changes", result.files_with_changes);
         tracing::Span::current().record("total_changes", result.total_changes);
         Ok(result)
     }
 
+    /// Insert a comment above every match of a pattern, in each file's comment syntax
+    #[tracing::instrument(skip(self), fields(language = %param.language, pattern = %param.pattern, path_pattern = %param.path_pattern, dry_run = %param.dry_run))]
+    pub async fn add_annotation(
+        &self,
+        param: AddAnnotationParam,
+    ) -> Result<AddAnnotationResult, ServiceError> {
+        let _lock = self
+            .lock_roots(param.workspace.as_deref(), !param.dry_run)
+            .await?;
+        let result = add_annotation(&self.search_service, param).await?;
+        tracing::Span::current().record("files_with_changes", result.files_with_changes);
+        tracing::Span::current().record("total_annotations", result.total_annotations);
+        Ok(result)
+    }
+
+    /// Wrap every match of a pattern in a template around `$MATCH`
+    #[tracing::instrument(skip(self), fields(language = %param.language, pattern = %param.pattern, path_pattern = %param.path_pattern, dry_run = %param.dry_run))]
+    pub async fn wrap_matches(
+        &self,
+        param: WrapMatchesParam,
+    ) -> Result<WrapMatchesResult, ServiceError> {
+        let _lock = self
+            .lock_roots(param.workspace.as_deref(), !param.dry_run)
+            .await?;
+        let result = wrap_matches(&self.search_service, param).await?;
+        tracing::Span::current().record("files_with_changes", result.files_with_changes);
+        tracing::Span::current().record("total_wraps", result.total_wraps);
+        Ok(result)
+    }
+
+    /// Apply a file replacement, run a configured verification command and optionally roll the
+    /// files back when it fails
+    #[tracing::instrument(skip(self), fields(command = %param.command, path_pattern = %param.replace.path_pattern, rollback_on_failure = %param.rollback_on_failure))]
+    pub async fn verify_refactoring(
+        &self,
+        param: VerifyRefactoringParam,
+    ) -> Result<VerifyRefactoringResult, ServiceError> {
+        let _lock = self
+            .lock_roots(param.replace.workspace.as_deref(), true)
+            .await?;
+        self.replace_service.verify_refactoring(param).await
+    }
+
+    /// The full diff of one file from a recent file_replace or rule_replace dry run
+    #[tracing::instrument(skip(self), fields(operation_id = %param.operation_id))]
+    pub async fn get_file_diff(
+        &self,
+        param: GetFileDiffParam,
+    ) -> Result<GetFileDiffResult, ServiceError> {
+        self.replace_service.get_file_diff(param)
+    }
+
+    /// Apply a unified diff returned by a file_replace dry run, or any other patch of files in
+    /// the root directories
+    #[tracing::instrument(skip(self, param), fields(dry_run = %param.dry_run))]
+    pub async fn apply_patch(
+        &self,
+        param: ApplyPatchParam,
+    ) -> Result<ApplyPatchResult, ServiceError> {
+        let _lock = self
+            .lock_roots(param.workspace.as_deref(), !param.dry_run)
+            .await?;
+        let result = self.replace_service.apply_patch(param).await?;
+        tracing::Span::current().record("files", result.files.len());
+        Ok(result)
+    }
+
+    /// Restore the files of an applied file_replace recorded under `param.apply_id`
+    #[tracing::instrument(skip(self), fields(apply_id = %param.apply_id, force = %param.force))]
+    pub async fn rollback_replace(
+        &self,
+        param: RollbackReplaceParam,
+    ) -> Result<RollbackReplaceResult, ServiceError> {
+        let _lock = self.lock_roots(None, true).await?;
+        let result = self.replace_service.rollback_replace(param).await?;
+        tracing::Span::current().record("restored_files", result.restored_files.len());
+        Ok(result)
+    }
+
+    /// The applied file_replace changes recorded in the undo directory, newest first
+    #[tracing::instrument(skip(self), fields(limit = %param.limit, include_rolled_back = %param.include_rolled_back))]
+    pub async fn list_applied_changes(
+        &self,
+        param: ListAppliedChangesParam,
+    ) -> Result<ListAppliedChangesResult, ServiceError> {
+        self.replace_service.list_applied_changes(param)
+    }
+
     pub async fn list_languages(
         &self,
         _param: ListLanguagesParam,
     ) -> Result<ListLanguagesResult, ServiceError> {
+        let extra = ExtraLanguage::ALL.iter().map(ExtraLanguage::name);
+        let dynamic = DynamicLanguage::all().into_iter().map(|lang| lang.name());
         Ok(ListLanguagesResult {
-            languages: ALL_LANGUAGES.iter().map(|&s| s.to_string()).collect(),
+            languages: ALL_LANGUAGES
+                .iter()
+                .copied()
+                .chain(extra)
+                .chain(dynamic)
+                .map(str::to_string)
+                .collect(),
+        })
+    }
+
+    /// Write the sample project of [`crate::playground`] below a root directory, with tool
+    /// calls to try on it
+    #[tracing::instrument(skip(self), fields(directory = %param.directory))]
+    pub async fn create_playground(
+        &self,
+        param: CreatePlaygroundParam,
+    ) -> Result<CreatePlaygroundResult, ServiceError> {
+        let roots = &self.config.root_directories;
+        let directory = resolve_output_path(&param.directory, roots)?;
+        let _lock = self.lock_roots(None, true).await?;
+        // Neither the directory nor a sample file may lead outside the roots through a symlink
+        for (name, _) in playground::FILES {
+            validate_creatable_path(&directory.join(name), roots)?;
+        }
+        std::fs::create_dir_all(&directory).map_err(|e| ServiceError::FileIoError {
+            message: e.to_string(),
+            path: directory.to_string_lossy().to_string(),
+        })?;
+        // Re-check now that the directory exists, in case it changed in the meantime
+        validate_path_within_roots(&directory, roots)?;
+        let files = write_playground(&directory, param.overwrite)?;
+
+        // Path patterns are matched relative to the root directory holding the project
+        let directory = directory.canonicalize().unwrap_or(directory);
+        let prefix = self
+            .config
+            .root_directories
+            .iter()
+            .filter_map(|root| root.canonicalize().ok())
+            .find_map(|root| {
+                directory
+                    .strip_prefix(root)
+                    .ok()
+                    .map(|relative| relative.to_string_lossy().to_string())
+            })
+            .unwrap_or_default();
+        Ok(CreatePlaygroundResult {
+            directory: directory.to_string_lossy().to_string(),
+            files,
+            suggestions: playground::suggestions(&prefix),
         })
     }
 
+    /// The language a path, extension or snippet is parsed as, by the rules of
+    /// [`crate::language_detection`]. A `path` naming a file below a root directory is read
+    /// when there is no `code`
+    #[tracing::instrument(skip(self, param), fields(path = ?param.path, extension = ?param.extension))]
+    pub async fn detect_language(
+        &self,
+        param: DetectLanguageParam,
+    ) -> Result<DetectLanguageResult, ServiceError> {
+        if param.path.is_none() && param.extension.is_none() && param.code.is_none() {
+            return Err(ServiceError::Internal(
+                "detect_language needs a path, an extension or code".to_string(),
+            ));
+        }
+        let content = match (&param.code, &param.path) {
+            (Some(code), _) => Some(code.clone()),
+            (None, Some(path)) => resolve_output_path(path, &self.config.root_directories)
+                .ok()
+                .filter(|file| file.is_file())
+                .and_then(|file| language_detection::read_head(&file)),
+            (None, None) => None,
+        };
+        Ok(language_detection::detect(
+            param.path.as_deref(),
+            param.extension.as_deref(),
+            content.as_deref(),
+        ))
+    }
+
+    /// State of the file index; `enabled` is false when the server keeps none
+    #[tracing::instrument(skip(self))]
+    pub async fn index_status(
+        &self,
+        _param: IndexStatusParam,
+    ) -> Result<IndexStatus, ServiceError> {
+        Ok(self
+            .search_service
+            .index()
+            .map(FileIndex::status)
+            .unwrap_or_default())
+    }
+
+    /// Whether the root directories, the directories the server writes to and every
+    /// advertised grammar are usable; see [`crate::health_check`]
+    #[tracing::instrument(skip(self))]
+    pub async fn health_check(
+        &self,
+        _param: HealthCheckParam,
+    ) -> Result<HealthCheckResult, ServiceError> {
+        let languages = self.list_languages(ListLanguagesParam {}).await?.languages;
+        Ok(health_check(&self.config, &languages))
+    }
+
+    /// Drop everything the file index kept and walk the root directories again
+    #[tracing::instrument(skip(self))]
+    pub async fn rebuild_index(
+        &self,
+        _param: RebuildIndexParam,
+    ) -> Result<IndexStatus, ServiceError> {
+        let index = self.search_service.index().ok_or_else(|| {
+            ServiceError::Internal(
+                "The server keeps no file index; start it with --index".to_string(),
+            )
+        })?;
+        Ok(index.rebuild())
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn validate_rule(
         &self,
@@ -327,7 +953,8 @@ impl AstGrepService {
         let mut test_matches: Option<RuleTestResult> = None;
 
         // Parse the rule configuration
-        let config = match self.parse_rule_config(&param.rule_config) {
+        let rule_config = self.config.interpolate_env(&param.rule_config);
+        let config = match self.parse_rule_config(&rule_config) {
             Ok(config) => config,
             Err(e) => {
                 errors.push(e.to_string());
@@ -350,19 +977,50 @@ impl AstGrepService {
             && let Some(pattern_str) = self.extract_pattern_from_rule(&config.rule)
         {
             match self.parse_language(&config.language) {
-                Ok(_lang) => {
+                Ok(lang) => {
                     let search_param = SearchParam::new(test_code, &pattern_str, &config.language);
 
-                    match self.search(search_param).await {
-                        Ok(result) => {
+                    // Matches whose captures fail a constraint are reported as violations
+                    let checked = self.search(search_param).await.and_then(|result| {
+                        let evaluator = self.rule_evaluator.with_utils(&config.utils)?;
+                        evaluator.check_constraints(
+                            result.matches,
+                            &config.constraints,
+                            test_code,
+                            lang,
+                        )
+                    });
+                    match checked {
+                        Ok((mut matches, constraint_violations)) => {
+                            // Rules with a fix also show what it would make of the test code
+                            let (fixed_code, fix_hunks) = match &config.fix {
+                                Some(fix) => match apply_transforms(
+                                    &self.rule_evaluator,
+                                    &config,
+                                    test_code,
+                                    lang,
+                                    &mut matches,
+                                )
+                                .and_then(|()| preview_rule_fix(test_code, &matches, fix))
+                                {
+                                    Ok((fixed_code, fix_hunks)) => (Some(fixed_code), fix_hunks),
+                                    Err(e) => {
+                                        warnings.push(format!("Fix preview failed: {e}"));
+                                        (None, Vec::new())
+                                    }
+                                },
+                                None => (None, Vec::new()),
+                            };
                             test_matches = Some(RuleTestResult {
-                                matches_found: result.matches.len(),
-                                sample_matches: result
-                                    .matches
+                                matches_found: matches.len(),
+                                sample_matches: matches
                                     .into_iter()
                                     .take(5)
                                     .map(|m| m.text)
                                     .collect(),
+                                constraint_violations,
+                                fixed_code,
+                                fix_hunks,
                             });
                         }
                         Err(e) => {
@@ -385,29 +1043,753 @@ impl AstGrepService {
         })
     }
 
+    /// Check many rule configurations, or every stored rule, in one call
+    #[tracing::instrument(skip(self, param), fields(rule_configs = param.rule_configs.len(), all_stored = %param.all_stored))]
+    pub async fn validate_rules(
+        &self,
+        param: ValidateRulesParam,
+    ) -> Result<ValidateRulesResult, ServiceError> {
+        crate::rules::validate_rules(
+            self.rule_service.storage(),
+            self.rule_service.evaluator(),
+            &self.config.env_vars,
+            param,
+        )
+    }
+
     #[tracing::instrument(skip(self), fields(rule_id))]
     pub async fn rule_search(
         &self,
         param: RuleSearchParam,
     ) -> Result<FileSearchResult, ServiceError> {
-        let result = self.search_service.rule_search(param).await?;
+        if let Some(pack) = param.pack.clone() {
+            return self.rule_search_pack(param, &pack).await;
+        }
+        let rule_id = parse_rule_config(&param.rule_config)?.id;
+        tracing::Span::current().record("rule_id", rule_id.as_str());
+        let scope = self.cursor_scope(&param, param.cursor.as_ref())?;
+        let (mut result, suppressions) = self
+            .search_service
+            .rule_search_with_suppressions(param)
+            .await?;
+        scope.stamp(result.next_cursor.as_mut());
+        tracing::Span::current().record("total_files_found", result.total_files_found);
+        tracing::Span::current().record("files_with_matches", result.matches.len());
+
+        // A scan cut short by its budget would understate the rule's match counts
+        if result.budget_exceeded {
+            tracing::warn!("Rule '{rule_id}' exceeded its budget; statistics not recorded");
+            return Ok(result);
+        }
+        let matches = result.matches.iter().map(|file| file.matches.len()).sum();
+        if let Err(e) = self
+            .rule_service
+            .stats()
+            .record_scan(&rule_id, matches, suppressions)
+        {
+            tracing::warn!("Failed to record statistics for rule '{rule_id}': {e}");
+        }
+        Ok(result)
+    }
+
+    /// Run every rule of `pack` over the same files, as one search whose matches carry the id of
+    /// the rule that found them. A page ends where the first rule stopped, so the next page
+    /// resumes every rule from the same file
+    async fn rule_search_pack(
+        &self,
+        param: RuleSearchParam,
+        pack: &str,
+    ) -> Result<FileSearchResult, ServiceError> {
+        if !param.rule_config.trim().is_empty() {
+            return Err(ServiceError::Internal(
+                "Pass either rule_config or pack to rule_search, not both".to_string(),
+            ));
+        }
+        let rules = pack::pack_rules(self.rule_service.storage(), pack)?;
+        let scope = self.cursor_scope(&param, param.cursor.as_ref())?;
+        let rule_directories = self.config.all_rule_directories();
+
+        let mut results = Vec::with_capacity(rules.len());
+        for rule in rules {
+            // Without a path pattern, each rule only walks the files of its own language
+            let path_pattern = param.path_pattern.clone().or_else(|| {
+                let config = self.config.parse_rule_config(&rule.content).ok()?;
+                let lang = AnyLanguage::from_str(&config.language).ok()?;
+                language_detection::language_path_pattern(lang)
+            });
+            let (mut result, suppressions) = self
+                .search_service
+                .rule_search_with_suppressions(RuleSearchParam {
+                    rule_config: rule.content,
+                    pack: None,
+                    path_pattern,
+                    ..param.clone()
+                })
+                .await?;
+            pack::drop_rule_files(&mut result, &rule_directories);
+            for file in &mut result.matches {
+                for found in &mut file.matches {
+                    found.rule_id = Some(rule.id.clone());
+                }
+            }
+            if result.budget_exceeded {
+                tracing::warn!(
+                    "Rule '{}' exceeded its budget; statistics not recorded",
+                    rule.id
+                );
+            } else {
+                let matches = result.matches.iter().map(|file| file.matches.len()).sum();
+                if let Err(e) =
+                    self.rule_service
+                        .stats()
+                        .record_scan(&rule.id, matches, suppressions)
+                {
+                    tracing::warn!("Failed to record statistics for rule '{}': {e}", rule.id);
+                }
+            }
+            results.push(result);
+        }
+
+        let mut result = pack::merge_pack_results(results);
+        scope.stamp(result.next_cursor.as_mut());
+        tracing::Span::current().record("total_files_found", result.total_files_found);
+        tracing::Span::current().record("files_with_matches", result.matches.len());
+        Ok(result)
+    }
+
+    #[tracing::instrument(skip(self), fields(language = %param.language, path_pattern = %param.path_pattern))]
+    pub async fn search_strings(
+        &self,
+        param: ValueSearchParam,
+    ) -> Result<FileSearchResult, ServiceError> {
+        let scope = self.cursor_scope(&param, param.cursor.as_ref())?;
+        let mut result = self.search_service.search_strings(param).await?;
+        scope.stamp(result.next_cursor.as_mut());
+        tracing::Span::current().record("total_files_found", result.total_files_found);
+        tracing::Span::current().record("files_with_matches", result.matches.len());
+        Ok(result)
+    }
+
+    #[tracing::instrument(skip(self), fields(language = %param.language, path_pattern = %param.path_pattern))]
+    pub async fn search_identifiers(
+        &self,
+        param: ValueSearchParam,
+    ) -> Result<FileSearchResult, ServiceError> {
+        let scope = self.cursor_scope(&param, param.cursor.as_ref())?;
+        let mut result = self.search_service.search_identifiers(param).await?;
+        scope.stamp(result.next_cursor.as_mut());
         tracing::Span::current().record("total_files_found", result.total_files_found);
         tracing::Span::current().record("files_with_matches", result.matches.len());
         Ok(result)
     }
 
+    #[tracing::instrument(skip(self), fields(language = %param.language, path_pattern = %param.path_pattern))]
+    pub async fn find_comments(
+        &self,
+        param: FindCommentsParam,
+    ) -> Result<FindCommentsResult, ServiceError> {
+        let scope = self.cursor_scope(&param, param.cursor.as_ref())?;
+        let mut result = self.search_service.find_comments(param).await?;
+        scope.stamp(result.next_cursor.as_mut());
+        tracing::Span::current().record("total_files_found", result.total_files_found);
+        tracing::Span::current().record("comments_found", result.comments.len());
+        Ok(result)
+    }
+
+    /// Languages, top-level directory tree, largest files and suggested path patterns of the
+    /// root directories
+    #[tracing::instrument(skip(self))]
+    pub async fn project_overview(
+        &self,
+        param: ProjectOverviewParam,
+    ) -> Result<ProjectOverviewResult, ServiceError> {
+        let walk = self.config.walk_settings(&param.walk);
+        Ok(project_overview(
+            &self.config.root_directories,
+            &walk,
+            &param,
+        ))
+    }
+
+    /// Per-file function counts, class counts, lines of code and largest functions for a
+    /// directory, reusing summaries of files that haven't changed since the last call
+    #[tracing::instrument(skip(self), fields(directory = %param.directory))]
+    pub async fn summarize_directory(
+        &self,
+        param: SummarizeDirectoryParam,
+    ) -> Result<SummarizeDirectoryResult, ServiceError> {
+        let directories: Vec<PathBuf> =
+            resolve_path_pattern(&param.directory, &self.config.root_directories)?
+                .into_iter()
+                .filter(|path| path.is_dir())
+                .collect();
+        if directories.is_empty() {
+            return Err(ServiceError::Internal(format!(
+                "Directory not found under the root directories: {}",
+                param.directory
+            )));
+        }
+        Ok(summarize_directories(
+            &self.summary_cache,
+            &directories,
+            &param,
+        ))
+    }
+
+    /// Declarations under a directory whose names resemble `param.query`, ranked by similarity
+    #[tracing::instrument(skip(self), fields(query = %param.query, directory = %param.directory))]
+    pub async fn search_symbols(
+        &self,
+        param: SymbolSearchParam,
+    ) -> Result<SymbolSearchResult, ServiceError> {
+        if param.query.trim().is_empty() {
+            return Err(ServiceError::Internal(
+                "search_symbols needs a non-empty query".to_string(),
+            ));
+        }
+        let directories: Vec<PathBuf> =
+            resolve_path_pattern(&param.directory, &self.config.root_directories)?
+                .into_iter()
+                .filter(|path| path.is_dir())
+                .collect();
+        if directories.is_empty() {
+            return Err(ServiceError::Internal(format!(
+                "Directory not found under the root directories: {}",
+                param.directory
+            )));
+        }
+        Ok(search_symbols(&self.symbol_index, &directories, &param))
+    }
+
+    /// Doc comment coverage of the public symbols under a directory, with a page of the
+    /// undocumented ones
+    #[tracing::instrument(skip(self), fields(directory = %param.directory))]
+    pub async fn doc_coverage(
+        &self,
+        param: DocCoverageParam,
+    ) -> Result<DocCoverageResult, ServiceError> {
+        let directories: Vec<PathBuf> =
+            resolve_path_pattern(&param.directory, &self.config.root_directories)?
+                .into_iter()
+                .filter(|path| path.is_dir())
+                .collect();
+        if directories.is_empty() {
+            return Err(ServiceError::Internal(format!(
+                "Directory not found under the root directories: {}",
+                param.directory
+            )));
+        }
+        Ok(doc_coverage(&self.symbol_index, &directories, &param))
+    }
+
+    /// Definitions, calls and imports of a symbol under a directory, grouped per file
+    #[tracing::instrument(skip(self), fields(symbol = %param.symbol, language = %param.language))]
+    pub async fn find_references(
+        &self,
+        param: FindReferencesParam,
+    ) -> Result<FindReferencesResult, ServiceError> {
+        if !is_identifier(&param.symbol) {
+            return Err(ServiceError::Internal(format!(
+                "find_references needs a plain identifier, got '{}'",
+                param.symbol
+            )));
+        }
+        let lang = self.parse_language(&param.language)?;
+        if !supports(lang) {
+            return Err(ServiceError::Internal(format!(
+                "find_references supports JavaScript, TypeScript, Python, Rust and Go, not {}",
+                param.language
+            )));
+        }
+        let directories: Vec<PathBuf> =
+            resolve_path_pattern(&param.directory, &self.config.root_directories)?
+                .into_iter()
+                .filter(|path| path.is_dir())
+                .collect();
+        if directories.is_empty() {
+            return Err(ServiceError::Internal(format!(
+                "Directory not found under the root directories: {}",
+                param.directory
+            )));
+        }
+        Ok(find_references(&directories, &param, lang))
+    }
+
+    /// Rename a symbol's definitions, calls, imports and other uses in a file or directory
+    #[tracing::instrument(skip(self), fields(old_name = %param.old_name, new_name = %param.new_name, language = %param.language, dry_run = %param.dry_run, total_renames))]
+    pub async fn rename_symbol(
+        &self,
+        param: RenameSymbolParam,
+    ) -> Result<RenameSymbolResult, ServiceError> {
+        for name in [&param.old_name, &param.new_name] {
+            if !is_identifier(name) {
+                return Err(ServiceError::Internal(format!(
+                    "rename_symbol needs plain identifiers, got '{name}'"
+                )));
+            }
+        }
+        if param.old_name == param.new_name {
+            return Err(ServiceError::Internal(format!(
+                "'{}' already has that name",
+                param.old_name
+            )));
+        }
+        let lang = self.parse_language(&param.language)?;
+        if !supports(lang) {
+            return Err(ServiceError::Internal(format!(
+                "rename_symbol supports JavaScript, TypeScript, Python, Rust and Go, not {}",
+                param.language
+            )));
+        }
+
+        let paths = resolve_path_pattern(&param.path, &self.config.root_directories)?;
+        let files: Vec<(PathBuf, Language)> = match param.scope {
+            RenameScope::File => paths
+                .into_iter()
+                .filter(|path| path.is_file())
+                .filter_map(|path| {
+                    let file_lang = Language::from_path(&path)?;
+                    same_family(lang, file_lang).then_some((path, file_lang))
+                })
+                .collect(),
+            RenameScope::Project => {
+                let directories: Vec<PathBuf> =
+                    paths.into_iter().filter(|path| path.is_dir()).collect();
+                source_files(&directories)
+                    .into_iter()
+                    .filter(|(_, file_lang)| same_family(lang, *file_lang))
+                    .collect()
+            }
+        };
+        if files.is_empty() {
+            return Err(ServiceError::Internal(format!(
+                "No {} {} found under the root directories: {}",
+                param.language,
+                match param.scope {
+                    RenameScope::File => "file",
+                    RenameScope::Project => "files in a directory",
+                },
+                param.path
+            )));
+        }
+
+        // Held from reading the files to writing them back
+        let _lock = self.lock_roots(None, !param.dry_run).await?;
+        let (mut result, rewritten) = plan_rename(files, &param);
+        tracing::Span::current().record("total_renames", result.total_renames);
+        if param.dry_run {
+            return Ok(result);
+        }
+        if let Some(collision) = result.collisions.first()
+            && !param.force
+        {
+            return Err(ServiceError::Internal(format!(
+                "'{}' is already defined in scope, e.g. {} line {}; review the dry run and set \
+                 force=true to rename anyway",
+                param.new_name, collision.file_path, collision.references[0].line
+            )));
+        }
+        for (path, code) in rewritten {
+            tokio::fs::write(&path, code)
+                .await
+                .map_err(|e| ServiceError::FileIoError {
+                    message: e.to_string(),
+                    path: path.to_string_lossy().to_string(),
+                })?;
+        }
+        result.applied = true;
+        Ok(result)
+    }
+
+    /// Code under a directory shaped like an example snippet, ranked by similarity to it
+    #[tracing::instrument(skip(self, param), fields(language = %param.language, directory = %param.directory))]
+    pub async fn find_similar_code(
+        &self,
+        param: FindSimilarCodeParam,
+    ) -> Result<FindSimilarCodeResult, ServiceError> {
+        if param.code.trim().is_empty() {
+            return Err(ServiceError::Internal(
+                "find_similar_code needs a non-empty code snippet".to_string(),
+            ));
+        }
+        let lang = self.parse_language(&param.language)?;
+        let directories: Vec<PathBuf> =
+            resolve_path_pattern(&param.directory, &self.config.root_directories)?
+                .into_iter()
+                .filter(|path| path.is_dir())
+                .collect();
+        if directories.is_empty() {
+            return Err(ServiceError::Internal(format!(
+                "Directory not found under the root directories: {}",
+                param.directory
+            )));
+        }
+        find_similar_code(&directories, &param, lang).map_err(ServiceError::ParserError)
+    }
+
+    /// Groups of functions and blocks under a directory that were copied, structurally or
+    /// verbatim, the most duplicated first
+    #[tracing::instrument(skip(self, param), fields(directory = %param.directory))]
+    pub async fn dedupe_code(
+        &self,
+        param: DedupeCodeParam,
+    ) -> Result<DedupeCodeResult, ServiceError> {
+        let lang = param
+            .language
+            .as_deref()
+            .map(|language| self.parse_language(language))
+            .transpose()?;
+        let directories: Vec<PathBuf> =
+            resolve_path_pattern(&param.directory, &self.config.root_directories)?
+                .into_iter()
+                .filter(|path| path.is_dir())
+                .collect();
+        if directories.is_empty() {
+            return Err(ServiceError::Internal(format!(
+                "Directory not found under the root directories: {}",
+                param.directory
+            )));
+        }
+        Ok(dedupe_code(&directories, &param, lang))
+    }
+
+    /// Quote style, declaration, error-handling and test-framework conventions measured over a
+    /// sample of the files under a directory
+    #[tracing::instrument(skip(self), fields(directory = %param.directory))]
+    pub async fn detect_conventions(
+        &self,
+        param: DetectConventionsParam,
+    ) -> Result<DetectConventionsResult, ServiceError> {
+        let directories: Vec<PathBuf> =
+            resolve_path_pattern(&param.directory, &self.config.root_directories)?
+                .into_iter()
+                .filter(|path| path.is_dir())
+                .collect();
+        if directories.is_empty() {
+            return Err(ServiceError::Internal(format!(
+                "Directory not found under the root directories: {}",
+                param.directory
+            )));
+        }
+        Ok(detect_conventions(&directories, &param))
+    }
+
+    /// Run several candidate patterns, with optional replacements, over the same code
+    #[tracing::instrument(skip(self, param), fields(language = %param.language, candidates = param.candidates.len()))]
+    pub async fn experiment(
+        &self,
+        param: ExperimentParam,
+    ) -> Result<ExperimentResult, ServiceError> {
+        let param = ExperimentParam {
+            code: self
+                .code_buffers
+                .resolve(param.code, param.buffer_id.as_deref())?,
+            buffer_id: None,
+            ..param
+        };
+        run_experiment(&self.search_service, &self.replace_service, param).await
+    }
+
+    /// Count the remaining occurrences of a migration's patterns and record them as a snapshot
+    #[tracing::instrument(skip(self), fields(name = %param.name, patterns = param.patterns.len()))]
+    pub async fn pattern_coverage(
+        &self,
+        param: PatternCoverageParam,
+    ) -> Result<PatternCoverageResult, ServiceError> {
+        crate::coverage::pattern_coverage(
+            &self.search_service,
+            &self.config.coverage_directory,
+            param,
+        )
+        .await
+    }
+
+    /// Read a file from a search result if its content still has the reported hash
+    #[tracing::instrument(skip(self), fields(file_path = %param.file_path))]
+    pub fn get_file_by_hash(
+        &self,
+        param: GetFileByHashParam,
+    ) -> Result<GetFileByHashResult, ServiceError> {
+        get_file_by_hash(
+            &param,
+            &self.config.root_directories,
+            self.config.max_file_size,
+        )
+    }
+
+    /// Replace the code, paths and symbol names in another tool's result with placeholders
+    #[tracing::instrument(skip(self, param))]
+    pub fn anonymize_result(
+        &self,
+        param: AnonymizeResultParam,
+    ) -> Result<AnonymizeResultResult, ServiceError> {
+        let lang = param
+            .language
+            .as_deref()
+            .map(|language| self.parse_language(language))
+            .transpose()?;
+        anonymize_result(param, lang)
+    }
+
+    /// Write a search result to a file inside the workspace and return its summary
+    #[tracing::instrument(skip(self, result))]
+    pub fn export_search_result(
+        &self,
+        result: &FileSearchResult,
+        output_file: &str,
+    ) -> Result<ExportedSearchResult, ServiceError> {
+        crate::export::export_search_result(result, output_file, &self.config.root_directories)
+    }
+
+    /// Write `document`, a rendering of `result` such as a SARIF log, to a workspace file
+    pub fn export_search_document<T: serde::Serialize>(
+        &self,
+        result: &FileSearchResult,
+        document: &T,
+        output_file: &str,
+    ) -> Result<ExportedSearchResult, ServiceError> {
+        crate::export::export_search_document(
+            result,
+            document,
+            output_file,
+            &self.config.root_directories,
+        )
+    }
+
+    /// Write a scan result to a file inside the workspace and return its summary
+    #[tracing::instrument(skip(self, result))]
+    pub fn export_scan_result(
+        &self,
+        result: &ScanResult,
+        output_file: &str,
+    ) -> Result<ExportedScanResult, ServiceError> {
+        crate::export::export_scan_result(result, output_file, &self.config.root_directories)
+    }
+
+    /// A SARIF 2.1.0 log of the findings of the rule in `rule_config`
+    pub fn sarif_log(
+        &self,
+        result: &FileSearchResult,
+        rule_config: &str,
+    ) -> Result<serde_json::Value, ServiceError> {
+        let rule = self.config.parse_rule_config(rule_config)?;
+        Ok(ResponseFormatter::format_sarif_log(
+            result,
+            &rule,
+            &self.config.root_directories,
+        ))
+    }
+
     #[tracing::instrument(skip(self), fields(rule_id))]
     pub async fn rule_replace(
         &self,
         param: RuleReplaceParam,
     ) -> Result<FileReplaceResult, ServiceError> {
-        let result = self.replace_service.rule_replace(param).await?;
+        let rule_id = parse_rule_config(&param.rule_config)?.id;
+        tracing::Span::current().record("rule_id", rule_id.as_str());
+        let scope = self.cursor_scope(&param, param.cursor.as_ref())?;
+        let _lock = self
+            .lock_roots(param.workspace.as_deref(), !param.dry_run)
+            .await?;
+        let mut result = self.replace_service.rule_replace(param).await?;
+        scope.stamp(result.next_cursor.as_mut());
         tracing::Span::current().record("total_files_found", result.total_files_found);
         tracing::Span::current().record("files_with_changes", result.files_with_changes);
         tracing::Span::current().record("total_changes", result.total_changes);
+
+        if !result.dry_run
+            && let Err(e) = self
+                .rule_service
+                .stats()
+                .record_fixes(&rule_id, result.total_changes)
+        {
+            tracing::warn!("Failed to record statistics for rule '{rule_id}': {e}");
+        }
+        Ok(result)
+    }
+
+    /// Run the stored rules, or the ones in `param.rule_ids`, and group findings by severity
+    #[tracing::instrument(skip(self), fields(total_findings))]
+    pub async fn scan(&self, param: ScanParam) -> Result<ScanResult, ServiceError> {
+        self.scan_with_errors(param, None).await
+    }
+
+    /// [`Self::scan`], sending each error-severity finding to `errors` as soon as it is found.
+    /// The returned result still holds every finding
+    #[tracing::instrument(skip(self, errors), fields(total_findings))]
+    pub async fn scan_streaming(
+        &self,
+        param: ScanParam,
+        errors: UnboundedSender<ScanFinding>,
+    ) -> Result<ScanResult, ServiceError> {
+        self.scan_with_errors(param, Some(&errors)).await
+    }
+
+    async fn scan_with_errors(
+        &self,
+        param: ScanParam,
+        errors: Option<&UnboundedSender<ScanFinding>>,
+    ) -> Result<ScanResult, ServiceError> {
+        let mut rules = self.rule_service.storage().load_all_rules().await?;
+        if !param.rule_ids.is_empty() {
+            if let Some(missing) = param
+                .rule_ids
+                .iter()
+                .find(|id| !rules.iter().any(|rule| &rule.id == *id))
+            {
+                return Err(ServiceError::Internal(format!(
+                    "Rule '{missing}' not found"
+                )));
+            }
+            rules.retain(|rule| param.rule_ids.contains(&rule.id));
+        }
+        let result = self.search_service.scan(&rules, param, errors).await?;
+        tracing::Span::current().record("total_findings", result.total_findings);
+        Ok(result)
+    }
+
+    /// Run every stored rule and report whether findings reach `param.fail_on`, with counts
+    /// per severity and rule and one page of findings
+    #[tracing::instrument(skip(self), fields(total_findings, passed))]
+    pub async fn lint_workspace(
+        &self,
+        param: LintWorkspaceParam,
+    ) -> Result<LintWorkspaceResult, ServiceError> {
+        let rules = self.rule_service.storage().load_all_rules().await?;
+        let scan = ScanParam {
+            path_pattern: param.path_pattern,
+            max_file_size: param.max_file_size,
+            workspace: param.workspace,
+            walk: param.walk,
+            // Every finding, to page across the buckets
+            page_size: usize::MAX,
+            ..Default::default()
+        };
+        let scan = self.search_service.scan(&rules, scan, None).await?;
+        let result = lint_report(scan, param.fail_on, param.offset, param.page_size);
+        tracing::Span::current().record("total_findings", result.total_findings);
+        tracing::Span::current().record("passed", result.passed);
         Ok(result)
     }
 
+    /// Run a built-in codemod by applying its generated rules one after another. The rules are
+    /// not stored, so their fixes are not recorded in the rule statistics
+    #[tracing::instrument(skip(self), fields(codemod = param.codemod.name(), total_changes))]
+    pub async fn run_codemod(
+        &self,
+        param: RunCodemodParam,
+    ) -> Result<RunCodemodResult, ServiceError> {
+        let rules = param.codemod.rules(&param.language)?;
+        let _lock = self
+            .lock_roots(param.workspace.as_deref(), !param.dry_run)
+            .await?;
+        let path_pattern = param
+            .path_pattern
+            .clone()
+            .unwrap_or_else(|| default_path_pattern(&param.language).to_string());
+
+        let mut steps = Vec::new();
+        let mut changed_files = HashSet::new();
+        for rule_config in rules {
+            let rule_id = parse_rule_config(&rule_config)?.id;
+            let result = self
+                .replace_service
+                .rule_replace(RuleReplaceParam {
+                    rule_config: rule_config.clone(),
+                    path_pattern: Some(path_pattern.clone()),
+                    max_results: param.max_results,
+                    max_file_size: param.max_file_size,
+                    dry_run: param.dry_run,
+                    summary_only: false,
+                    cursor: None,
+                    allow_unsafe: false,
+                    workspace: param.workspace.clone(),
+                    ignore_whitespace_changes: true,
+                    timeout_ms: None,
+                    walk: Default::default(),
+                })
+                .await?;
+            changed_files.extend(
+                result
+                    .file_results
+                    .iter()
+                    .map(|file| file.file_path.clone()),
+            );
+            steps.push(CodemodStep {
+                rule_id,
+                rule_config,
+                total_changes: result.total_changes,
+                files_with_changes: result.files_with_changes,
+                file_results: result.file_results,
+                unfixed_matches: result
+                    .rule_stats
+                    .iter()
+                    .map(|stats| stats.unfixed_matches)
+                    .sum(),
+                incomplete: result.next_cursor.is_some_and(|cursor| !cursor.is_complete),
+            });
+        }
+
+        let total_changes = steps.iter().map(|step| step.total_changes).sum();
+        tracing::Span::current().record("total_changes", total_changes);
+        Ok(RunCodemodResult {
+            codemod: param.codemod.name().to_string(),
+            dry_run: param.dry_run,
+            steps,
+            total_changes,
+            files_with_changes: changed_files.len(),
+        })
+    }
+
+    /// Plan moving a directory: the files to move and every import path the move would break
+    #[tracing::instrument(skip(self), fields(source = %param.source, destination = %param.destination))]
+    pub async fn plan_directory_move(
+        &self,
+        param: PlanDirectoryMoveParam,
+    ) -> Result<EditPlan, ServiceError> {
+        let roots: Vec<PathBuf> = self
+            .config
+            .workspace_roots(param.workspace.as_deref())?
+            .into_iter()
+            .filter_map(|root| root.canonicalize().ok())
+            .collect();
+        let source = resolve_path_pattern(&param.source, &roots)?
+            .into_iter()
+            .find(|path| path.is_dir())
+            .ok_or_else(|| {
+                ServiceError::Internal(format!(
+                    "Directory not found under the root directories: {}",
+                    param.source
+                ))
+            })?;
+        if roots.contains(&source) {
+            return Err(ServiceError::Internal(
+                "Cannot move a root directory".to_string(),
+            ));
+        }
+        let destination = resolve_output_path(&param.destination, &roots)?;
+        validate_creatable_path(&destination, &roots)?;
+        if destination.exists() {
+            return Err(ServiceError::Internal(format!(
+                "Destination already exists: {}",
+                destination.display()
+            )));
+        }
+        if destination.starts_with(&source) {
+            return Err(ServiceError::Internal(
+                "Cannot move a directory into itself".to_string(),
+            ));
+        }
+        plan_directory_move(&roots, &source, &destination, param.max_file_size)
+    }
+
+    /// Execute a plan from `plan_directory_move`, undoing it if any step fails
+    #[tracing::instrument(skip(self, param), fields(source = %param.plan.source, destination = %param.plan.destination))]
+    pub async fn apply_plan(&self, param: ApplyPlanParam) -> Result<ApplyPlanResult, ServiceError> {
+        let _lock = self.lock_roots(None, true).await?;
+        apply_plan(&self.config.root_directories, &param.plan)
+    }
+
     #[tracing::instrument(skip(self), fields(rule_id))]
     pub async fn create_rule(
         &self,
@@ -429,11 +1811,240 @@ impl AstGrepService {
         self.rule_service.storage().delete_rule(param).await
     }
 
+    /// Publish a stored rule to the team rule registry
+    #[tracing::instrument(skip(self), fields(rule_id = %param.rule_id))]
+    pub async fn push_rule(&self, param: PushRuleParam) -> Result<PushRuleResult, ServiceError> {
+        let client = self.registry_client()?;
+        registry::push_rule(
+            self.rule_service.storage(),
+            &client,
+            &self.config.rules_directory,
+            param,
+        )
+        .await
+    }
+
+    /// Store the team rule registry's rules locally
+    #[tracing::instrument(skip(self))]
+    pub async fn pull_rules(&self, param: PullRulesParam) -> Result<PullRulesResult, ServiceError> {
+        let client = self.registry_client()?;
+        registry::pull_rules(
+            self.rule_service.storage(),
+            &client,
+            &self.config.rules_directory,
+            param,
+        )
+        .await
+    }
+
+    fn registry_client(&self) -> Result<registry::RegistryClient, ServiceError> {
+        match &self.config.rule_registry {
+            Some(config) => Ok(registry::RegistryClient::new(config)),
+            None => Err(ServiceError::Internal(
+                "No rule registry configured; start the server with --rule-registry URL"
+                    .to_string(),
+            )),
+        }
+    }
+
+    /// Trace how a rule is evaluated against one file
+    #[tracing::instrument(skip(self, param), fields(file_path = %param.file_path))]
+    pub async fn explain_rule_evaluation(
+        &self,
+        param: ExplainRuleParam,
+    ) -> Result<ExplainRuleResult, ServiceError> {
+        self.search_service.explain_rule_evaluation(param).await
+    }
+
+    /// Convert Semgrep or ESLint rules into ast-grep rule configurations
+    #[tracing::instrument(skip(self, param), fields(format = ?param.format))]
+    pub async fn convert_rule(
+        &self,
+        param: ConvertRuleParam,
+    ) -> Result<ConvertRuleResult, ServiceError> {
+        convert_rules(&param)
+    }
+
+    /// Write stored rules into an ast-grep CLI project (sgconfig.yml + rules/) in the workspace
+    #[tracing::instrument(skip(self), fields(output_dir = %param.output_dir))]
+    pub async fn export_to_sgconfig(
+        &self,
+        param: ExportToSgconfigParam,
+    ) -> Result<ExportToSgconfigResult, ServiceError> {
+        let stored = self
+            .rule_service
+            .storage()
+            .list_rules(ListRulesParam {
+                language: None,
+                severity: None,
+                tag: None,
+            })
+            .await?
+            .rules;
+
+        let (rules, missing) = match &param.rule_ids {
+            Some(ids) => {
+                let missing: Vec<SkippedRule> = ids
+                    .iter()
+                    .filter(|id| !stored.iter().any(|rule| rule.id == **id))
+                    .map(|id| SkippedRule {
+                        rule_id: id.clone(),
+                        reason: "no stored rule with this id".to_string(),
+                    })
+                    .collect();
+                let rules = stored
+                    .into_iter()
+                    .filter(|rule| ids.contains(&rule.id))
+                    .collect();
+                (rules, missing)
+            }
+            None => (stored, Vec::new()),
+        };
+
+        let mut result =
+            crate::export::export_rules_to_sgconfig(rules, &param, &self.config.root_directories)?;
+        result.skipped.extend(missing);
+        Ok(result)
+    }
+
+    /// Write stored rules, or the rules of a pack, to one multi-document file or a directory
+    #[tracing::instrument(skip(self))]
+    pub async fn export_rules(
+        &self,
+        param: ExportRulesParam,
+    ) -> Result<ExportRulesResult, ServiceError> {
+        pack::export_rules(
+            self.rule_service.storage(),
+            param,
+            &self.config.root_directories,
+        )
+    }
+
+    /// Store the rules of a file or directory, optionally together as a rule pack
+    #[tracing::instrument(skip(self), fields(path = %param.path))]
+    pub async fn import_rules(
+        &self,
+        param: ImportRulesParam,
+    ) -> Result<ImportRulesResult, ServiceError> {
+        pack::import_rules(
+            self.rule_service.storage(),
+            param,
+            &self.config.root_directories,
+        )
+        .await
+    }
+
+    /// Report recorded rule statistics and flag rules that never fire or are noisy
+    #[tracing::instrument(skip(self))]
+    pub async fn rule_stats(&self, param: RuleStatsParam) -> Result<RuleStatsResult, ServiceError> {
+        let mut recorded = self.rule_service.stats().load()?;
+        let stored = self
+            .rule_service
+            .storage()
+            .list_rules(ListRulesParam {
+                language: None,
+                severity: None,
+                tag: None,
+            })
+            .await?;
+
+        let mut never_scanned: Vec<String> = stored
+            .rules
+            .into_iter()
+            .map(|rule| rule.id)
+            .filter(|id| !recorded.contains_key(id))
+            .collect();
+        if let Some(rule_id) = &param.rule_id {
+            recorded.retain(|id, _| id == rule_id);
+            never_scanned.retain(|id| id == rule_id);
+        }
+        never_scanned.sort();
+        never_scanned.dedup();
+
+        let rules: Vec<RuleStats> = recorded.into_values().collect();
+        let never_fired = rules
+            .iter()
+            .filter(|stats| stats.scans >= param.min_scans && stats.total_matches == 0)
+            .map(|stats| stats.rule_id.clone())
+            .collect();
+        let noisy = rules
+            .iter()
+            .filter(|stats| stats.average_matches_per_scan() > param.noise_threshold)
+            .map(|stats| stats.rule_id.clone())
+            .collect();
+
+        Ok(RuleStatsResult {
+            rules,
+            never_fired,
+            noisy,
+            never_scanned,
+        })
+    }
+
     #[tracing::instrument(skip(self), fields(rule_id = %param.rule_id))]
     pub async fn get_rule(&self, param: GetRuleParam) -> Result<GetRuleResult, ServiceError> {
         self.rule_service.storage().get_rule(param).await
     }
 
+    /// The resources `resources/list` publishes: every stored rule, the tool usage guide and,
+    /// once a search or replace ran in this session, its result. See [`crate::resources`]
+    pub async fn list_resources(&self) -> Result<Vec<Resource>, ServiceError> {
+        let rules = self
+            .list_rules(ListRulesParam {
+                language: None,
+                severity: None,
+                tag: None,
+            })
+            .await?;
+        let mut listed: Vec<Resource> = rules
+            .rules
+            .iter()
+            .map(|rule| resources::rule_resource(&rule.id, rule.message.as_deref()))
+            .collect();
+        listed.push(resources::tool_usage_guide_resource());
+        if let Some(latest) = self.latest_result.get() {
+            listed.push(resources::latest_result_resource(&latest));
+        }
+        Ok(listed)
+    }
+
+    /// Contents of the resource at `uri`, one of those [`Self::list_resources`] lists
+    pub async fn read_resource(&self, uri: &str) -> Result<ReadResourceResult, ErrorData> {
+        let (text, mime_type) = if let Some(rule_id) = uri.strip_prefix(resources::RULE_SCHEME) {
+            let rule = self
+                .get_rule(GetRuleParam {
+                    rule_id: rule_id.to_string(),
+                })
+                .await
+                .map_err(|e| ErrorData::resource_not_found(e.to_string(), None))?;
+            let yaml = serde_yaml::to_string(&rule.rule_config)
+                .map_err(|e| ErrorData::internal_error(e.to_string(), None))?;
+            (yaml, "application/yaml")
+        } else if uri == resources::TOOL_USAGE_GUIDE_URI {
+            (resources::TOOL_USAGE_GUIDE.to_string(), "text/markdown")
+        } else if uri == resources::LATEST_RESULT_URI {
+            let latest = self.latest_result.get().ok_or_else(|| {
+                ErrorData::resource_not_found(
+                    "No search or replace has run in this session yet",
+                    None,
+                )
+            })?;
+            (latest.json, "application/json")
+        } else {
+            return Err(ErrorData::resource_not_found(
+                format!("Unknown resource: {uri}"),
+                None,
+            ));
+        };
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::TextResourceContents {
+                uri: uri.to_string(),
+                mime_type: Some(mime_type.to_string()),
+                text,
+            }],
+        })
+    }
+
     /// Validate a pattern with learning insights
     #[tracing::instrument(skip(self), fields(pattern = %param.pattern, language = %param.language))]
     pub async fn validate_pattern(
@@ -452,6 +2063,15 @@ impl AstGrepService {
         self.learning_service.explore_patterns(param).await
     }
 
+    /// Synthesize candidate patterns from before/after examples
+    #[tracing::instrument(skip(self), fields(language = %param.language, examples = param.examples.len()))]
+    pub async fn suggest_pattern(
+        &self,
+        param: SuggestPatternParam,
+    ) -> Result<SuggestPatternResult, ServiceError> {
+        suggest_pattern(&param, &self.pattern_matcher)
+    }
+
     /// Generate LLM prompt for enhanced learning assistance
     pub fn generate_prompt(
         &self,
@@ -537,6 +2157,8 @@ impl ServerHandler for AstGrepService {
             capabilities: ServerCapabilities {
                 tools: Some(rmcp::model::ToolsCapability { list_changed: Some(true) }),
                 prompts: Some(PromptsCapability { list_changed: Some(true) }),
+                resources: Some(ResourcesCapability { subscribe: None, list_changed: None }),
+                logging: Some(JsonObject::default()),
                 ..Default::default()
             },
             instructions: Some("AST-Grep MCP Server: Structural code search and transformation using Tree-sitter AST patterns. Supports 20+ languages including JavaScript, TypeScript, Python, Rust, Java, Go. IMPORTANT: Use $VAR for single node captures, and $ for multiple node (list) captures. When searching/replacing, use 'search' or 'replace' for code snippets (requires 'code' parameter). Use 'file_search' or 'file_replace' for operations across files (requires 'path_pattern' parameter). For bulk changes, ALWAYS use 'file_replace' with 'dry_run: true' first to preview changes. For complex logic, use rule-based tools ('rule_search', 'rule_replace', 'validate_rule') with YAML configurations. Refer to TOOL_USAGE_GUIDE.md for comprehensive examples and advanced usage.".to_string()),
@@ -549,25 +2171,54 @@ impl ServerHandler for AstGrepService {
         _request: Option<PaginatedRequestParam>,
         _context: RequestContext<RoleServer>,
     ) -> Result<ListToolsResult, ErrorData> {
-        Ok(crate::tools::ToolService::list_tools())
+        Ok(self.available_tools())
     }
 
-    #[tracing::instrument(skip(self, request, _context), fields(tool_name = %request.name))]
+    #[tracing::instrument(skip(self, request, context), fields(tool_name = %request.name))]
     async fn call_tool(
         &self,
         request: CallToolRequestParam,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, ErrorData> {
-        // Special handling for file_search with large results
-        if request.name == "file_search" {
-            return self.handle_file_search_with_optimization(request).await;
-        }
+        let progress = context
+            .meta
+            .get_progress_token()
+            .map(|token| ProgressReporter {
+                token,
+                peer: context.peer.clone(),
+            });
+        // rmcp cancels `ct` when the client sends notifications/cancelled for this request
+        self.with_cancellation(context.ct)
+            .handle_tool_call_with_progress(request, progress, Some(context.peer))
+            .await
+    }
 
-        // Special handling for list_languages which has custom implementation
-        match request.name.as_ref() {
-            "list_languages" => self.handle_list_languages_tool(request).await,
-            _ => ToolRouter::route_tool_call(self, request).await,
-        }
+    async fn set_level(
+        &self,
+        request: SetLevelRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        *self.log_level.lock().unwrap() = Some(request.level);
+        Ok(())
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        Ok(ListResourcesResult {
+            resources: AstGrepService::list_resources(self).await?,
+            ..Default::default()
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        AstGrepService::read_resource(self, &request.uri).await
     }
 
     async fn list_prompts(
@@ -635,6 +2286,53 @@ impl ServerHandler for AstGrepService {
                         },
                     ]),
                 },
+                Prompt {
+                    name: "plan_bulk_refactor".to_string(),
+                    description: Some("Plan a project-wide refactor, grounded in the languages, sample files and match counts of the workspace".to_string()),
+                    arguments: Some(vec![
+                        PromptArgument {
+                            name: "goal".to_string(),
+                            description: Some("What the refactor should achieve (e.g., 'replace var with let')".to_string()),
+                            required: Some(true),
+                        },
+                        PromptArgument {
+                            name: "pattern".to_string(),
+                            description: Some("AST pattern for the code to change; its matches are counted".to_string()),
+                            required: Some(false),
+                        },
+                        PromptArgument {
+                            name: "language".to_string(),
+                            description: Some("Language the pattern is written in".to_string()),
+                            required: Some(false),
+                        },
+                    ]),
+                },
+                Prompt {
+                    name: "write_codemod_rule".to_string(),
+                    description: Some("Draft a YAML codemod rule using the workspace's languages and sample files as context".to_string()),
+                    arguments: Some(vec![
+                        PromptArgument {
+                            name: "goal".to_string(),
+                            description: Some("What the codemod should change".to_string()),
+                            required: Some(true),
+                        },
+                        PromptArgument {
+                            name: "language".to_string(),
+                            description: Some("Programming language of the target files".to_string()),
+                            required: Some(true),
+                        },
+                        PromptArgument {
+                            name: "pattern".to_string(),
+                            description: Some("Starting AST pattern; its matches are counted".to_string()),
+                            required: Some(false),
+                        },
+                        PromptArgument {
+                            name: "example".to_string(),
+                            description: Some("Example code the codemod should transform".to_string()),
+                            required: Some(false),
+                        },
+                    ]),
+                },
             ],
             ..Default::default()
         })
@@ -649,6 +2347,8 @@ impl ServerHandler for AstGrepService {
             "pattern_help" => self.get_pattern_help_prompt(request.arguments),
             "pattern_debug" => self.get_pattern_debug_prompt(request.arguments),
             "pattern_optimize" => self.get_pattern_optimize_prompt(request.arguments),
+            "plan_bulk_refactor" => self.get_plan_bulk_refactor_prompt(request.arguments),
+            "write_codemod_rule" => self.get_write_codemod_rule_prompt(request.arguments),
             _ => Err(ErrorData::invalid_params(
                 std::borrow::Cow::Borrowed("Unknown prompt name"),
                 None,
@@ -658,22 +2358,96 @@ impl ServerHandler for AstGrepService {
 }
 
 impl AstGrepService {
+    /// Whether the client's logging level lets error-severity scan findings through
+    fn logs_scan_errors(&self) -> bool {
+        let level = *self.log_level.lock().unwrap();
+        !level.is_some_and(|level| ABOVE_ERROR.contains(&level))
+    }
+
+    /// Helper method to handle scan, logging each error-severity finding to the client while
+    /// the scan is still running
+    async fn handle_scan_with_logging(
+        &self,
+        request: CallToolRequestParam,
+        peer: Peer<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: ScanParam = serde_json::from_value(serde_json::Value::Object(
+            request.arguments.clone().unwrap_or_default(),
+        ))
+        .map_err(|e| ErrorData::invalid_params(Cow::Owned(e.to_string()), None))?;
+
+        let output_file = param.output_file.clone();
+        let (sender, receiver) = unbounded_channel::<ScanFinding>();
+        let forward = tokio::spawn(log_scan_errors(peer, receiver));
+        let result = self.scan_streaming(param, sender).await;
+        // The sender is dropped with the scan, so the logging task ends once every finding has
+        // gone out
+        let _ = forward.await;
+        let result = result.map_err(ErrorData::from)?;
+        if let Some(output_file) = output_file {
+            let exported = self
+                .export_scan_result(&result, &output_file)
+                .map_err(ErrorData::from)?;
+            let summary = ResponseFormatter::format_exported_scan_result(&exported);
+            return ResponseFormatter::create_formatted_response(&exported, summary)
+                .map_err(|e| ErrorData::internal_error(Cow::Owned(e.to_string()), None));
+        }
+        let summary = ResponseFormatter::format_scan_result(&result);
+        ResponseFormatter::create_formatted_response(&result, summary)
+            .map_err(|e| ErrorData::internal_error(Cow::Owned(e.to_string()), None))
+    }
+
     /// Helper method to handle file_search with response optimization
     async fn handle_file_search_with_optimization(
         &self,
         request: CallToolRequestParam,
+        full_result: bool,
+        progress: Option<ProgressReporter>,
     ) -> Result<CallToolResult, ErrorData> {
         let param: FileSearchParam = serde_json::from_value(serde_json::Value::Object(
             request.arguments.clone().unwrap_or_default(),
         ))
         .map_err(|e| ErrorData::invalid_params(Cow::Owned(e.to_string()), None))?;
 
+        let output_file = param.output_file.clone();
+        if let Some(progress) = progress.filter(|_| param.stream && output_file.is_none()) {
+            let (sender, mut receiver) = unbounded_channel::<FileSearchProgress>();
+            let forward = tokio::spawn(async move {
+                let mut streamed_files = 0;
+                while let Some(update) = receiver.recv().await {
+                    progress.send(&update).await;
+                    streamed_files += 1;
+                }
+                streamed_files
+            });
+            let result = self.file_search_streaming(param, sender).await;
+            // The sender is dropped with the search, so the forwarding task ends once every
+            // notification has gone out
+            let streamed_files = forward.await.unwrap_or_default();
+            let mut result = result.map_err(ErrorData::from)?;
+            result.matches.clear();
+            let summary =
+                ResponseFormatter::format_streamed_file_search_result(&result, streamed_files);
+            return ResponseFormatter::create_formatted_response(&result, summary)
+                .map_err(|e| ErrorData::internal_error(Cow::Owned(e.to_string()), None));
+        }
         let result = self.file_search(param).await.map_err(ErrorData::from)?;
+
+        if let Some(output_file) = output_file {
+            let exported = self
+                .export_search_result(&result, &output_file)
+                .map_err(ErrorData::from)?;
+            let summary = ResponseFormatter::format_exported_search_result(&exported);
+            return ResponseFormatter::create_formatted_response(&exported, summary)
+                .map_err(|e| ErrorData::internal_error(Cow::Owned(e.to_string()), None));
+        }
+
         let summary = ResponseFormatter::format_file_search_result(&result);
 
-        // Use lightweight response for large results to avoid token limits
+        // Use lightweight response for large results to avoid token limits, unless the full
+        // result is about to be compressed
         let total_matches: usize = result.matches.iter().map(|f| f.matches.len()).sum();
-        if result.matches.len() > 10 || total_matches > 50 {
+        if !full_result && (result.matches.len() > 10 || total_matches > 50) {
             ResponseFormatter::create_lightweight_response_for_file_search(&result, summary)
                 .map_err(|e| ErrorData::internal_error(Cow::Owned(e.to_string()), None))
         } else {
@@ -946,6 +2720,90 @@ impl AstGrepService {
         })
     }
 
+    /// Get bulk refactor planning prompt with project context
+    fn get_plan_bulk_refactor_prompt(
+        &self,
+        arguments: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let args = arguments
+            .ok_or_else(|| ErrorData::invalid_params(Cow::Borrowed("Missing arguments"), None))?;
+
+        let goal = args
+            .get("goal")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ErrorData::invalid_params(Cow::Borrowed("Missing goal"), None))?;
+        let pattern = args.get("pattern").and_then(|v| v.as_str());
+        let language = args
+            .get("language")
+            .and_then(|v| v.as_str())
+            .map(|lang| self.parse_language(lang))
+            .transpose()
+            .map_err(ErrorData::from)?;
+
+        let context = ProjectContext::collect(
+            &self.config.root_directories,
+            &self.pattern_matcher,
+            pattern,
+            language,
+        );
+        let text = self
+            .learning_service
+            .prompt_generator
+            .generate_bulk_refactor_plan(goal, pattern, &context)
+            .map_err(ErrorData::from)?;
+
+        Ok(GetPromptResult {
+            description: Some(format!("Bulk refactor plan for: {goal}")),
+            messages: vec![PromptMessage {
+                role: PromptMessageRole::Assistant,
+                content: PromptMessageContent::Text { text },
+            }],
+        })
+    }
+
+    /// Get codemod rule writing prompt with project context
+    fn get_write_codemod_rule_prompt(
+        &self,
+        arguments: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let args = arguments
+            .ok_or_else(|| ErrorData::invalid_params(Cow::Borrowed("Missing arguments"), None))?;
+
+        let goal = args
+            .get("goal")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ErrorData::invalid_params(Cow::Borrowed("Missing goal"), None))?;
+        let language_name = args
+            .get("language")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ErrorData::invalid_params(Cow::Borrowed("Missing language"), None))?;
+        let language = self
+            .parse_language(language_name)
+            .map_err(ErrorData::from)?;
+        let pattern = args.get("pattern").and_then(|v| v.as_str());
+        let example = args.get("example").and_then(|v| v.as_str());
+
+        let context = ProjectContext::collect(
+            &self.config.root_directories,
+            &self.pattern_matcher,
+            pattern,
+            Some(language),
+        );
+        let text = self
+            .learning_service
+            .prompt_generator
+            .generate_codemod_rule_prompt(goal, language_name, pattern, example, &context)
+            .map_err(ErrorData::from)?;
+
+        Ok(GetPromptResult {
+            description: Some(format!("Codemod rule for: {goal}")),
+            messages: vec![PromptMessage {
+                role: PromptMessageRole::Assistant,
+                content: PromptMessageContent::Text { text },
+            }],
+        })
+    }
+
     /// Suggest a pattern based on use case description
     fn suggest_pattern_for_use_case(&self, use_case: &str, language: &str) -> String {
         let use_case_lower = use_case.to_lowercase();
@@ -1139,14 +2997,14 @@ impl AstGrepService {
         let is_pure = side_effects.is_empty() && external_writes.is_empty();
         
         let suggested_signature = FunctionSignatureInfo {
-            name: "extracted_function".to_string(),
+            name: "extractedFunction".to_string(),
             parameters: parameters.clone(),
             return_info: suggested_return_strategy
                 .as_ref()
                 .map(|s| s.strategy_type.clone())
                 .unwrap_or_else(|| "void".to_string()),
             full_signature: format!(
-                "function extracted_function({}): {}",
+                "function extractedFunction({}): {}",
                 parameters.join(", "),
                 suggested_return_strategy
                     .as_ref()
@@ -1304,6 +3162,8 @@ impl AstGrepService {
         use crate::refactoring::RefactoringService;
         use std::sync::Arc;
         
+        let preview = param.options.as_ref().is_none_or(|options| options.preview);
+        let _lock = self.lock_roots(None, !preview).await?;
         let service = RefactoringService::new(
             Arc::new(self.search_service.clone()),
             Arc::new(self.replace_service.clone())
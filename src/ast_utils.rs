@@ -3,7 +3,10 @@
 //! Provides utilities for working with AST parsing and pattern creation,
 //! including caching for improved performance.
 
-use ast_grep_core::{AstGrep, Pattern, tree_sitter::StrDoc};
+use ast_grep_core::{
+    AstGrep, Pattern,
+    tree_sitter::{LanguageExt, StrDoc},
+};
 use ast_grep_language::SupportLang as Language;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -30,7 +33,7 @@ impl AstParser {
     }
 
     /// Create a new AST from code
-    pub fn parse_code(&self, code: &str, lang: Language) -> AstGrep<StrDoc<Language>> {
+    pub fn parse_code<L: LanguageExt>(&self, code: &str, lang: L) -> AstGrep<StrDoc<L>> {
         AstGrep::new(code, lang)
     }
 
@@ -96,7 +99,7 @@ impl AstParser {
     }
 
     /// Generate a stringified AST for debugging
-    pub fn generate_ast_debug_string(&self, code: &str, lang: Language) -> String {
+    pub fn generate_ast_debug_string<L: LanguageExt>(&self, code: &str, lang: L) -> String {
         let ast = self.parse_code(code, lang);
         Self::build_ast_string(ast.root(), 0)
     }
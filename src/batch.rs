@@ -0,0 +1,124 @@
+//! # Batch Mode
+//!
+//! Line-delimited JSON front end for scripts that want the tool semantics without an MCP
+//! client. Each input line names a tool and its arguments:
+//!
+//! ```json
+//! {"id": 1, "tool": "file_search", "arguments": {"path_pattern": "src/**/*.js", "pattern": "console.log($A)", "language": "javascript"}}
+//! ```
+//!
+//! and produces one output line with the MCP `CallToolResult` or error for that call:
+//!
+//! ```json
+//! {"id": 1, "tool": "file_search", "result": {"content": [...], "isError": false}}
+//! {"id": 2, "tool": "nope", "error": {"code": -32601, "message": "..."}}
+//! ```
+//!
+//! Requests run one at a time in input order. Blank lines are skipped.
+
+use crate::ast_grep_service::AstGrepService;
+use rmcp::model::{CallToolRequestParam, CallToolResult, ErrorData, JsonObject};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+/// One line of batch input
+#[derive(Debug, Deserialize)]
+pub struct BatchRequest {
+    /// Echoed back unchanged so callers can correlate responses
+    #[serde(default)]
+    pub id: Value,
+    /// Tool to call
+    #[serde(alias = "name")]
+    pub tool: String,
+    #[serde(default)]
+    pub arguments: Option<JsonObject>,
+}
+
+/// One line of batch output
+#[derive(Debug, Serialize)]
+pub struct BatchResponse {
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<CallToolResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorData>,
+}
+
+/// Counts of calls processed by [`run_batch`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+/// Read requests from `input` until EOF, writing one response line per request to `output`
+pub async fn run_batch<R, W>(
+    service: &AstGrepService,
+    input: R,
+    mut output: W,
+) -> std::io::Result<BatchSummary>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut summary = BatchSummary::default();
+    let mut lines = input.lines();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<BatchRequest>(&line) {
+            Ok(request) => call(service, request).await,
+            Err(e) => BatchResponse {
+                id: Value::Null,
+                tool: None,
+                result: None,
+                error: Some(ErrorData::parse_error(
+                    format!("Invalid batch request: {e}"),
+                    None,
+                )),
+            },
+        };
+        let failed = response.error.is_some()
+            || response
+                .result
+                .as_ref()
+                .is_some_and(|result| result.is_error == Some(true));
+        if failed {
+            summary.failed += 1;
+        } else {
+            summary.succeeded += 1;
+        }
+
+        let mut json = serde_json::to_string(&response)?;
+        json.push('\n');
+        output.write_all(json.as_bytes()).await?;
+        output.flush().await?;
+    }
+
+    Ok(summary)
+}
+
+async fn call(service: &AstGrepService, request: BatchRequest) -> BatchResponse {
+    let outcome = service
+        .handle_tool_call(CallToolRequestParam {
+            name: request.tool.clone().into(),
+            arguments: request.arguments,
+        })
+        .await;
+    let (result, error) = match outcome {
+        Ok(result) => (Some(result), None),
+        Err(e) => (None, Some(e)),
+    };
+    BatchResponse {
+        id: request.id,
+        tool: Some(request.tool),
+        result,
+        error,
+    }
+}
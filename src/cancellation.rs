@@ -0,0 +1,53 @@
+//! # Request Cancellation
+//!
+//! A glob such as `**/*` over a slow or network-mounted tree can keep a file-based tool busy
+//! for a long time. Each MCP tool call carries a [`CancellationToken`] that rmcp cancels when
+//! the client sends `notifications/cancelled`, and `file_search`, `file_replace`, `rule_search`
+//! and `rule_replace` also take a `timeout_ms`. The search and replace services check a
+//! [`StopSignal`] combining both while walking the root directories and before each file, and
+//! return what they found so far marked `timed_out` instead of running on.
+
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// When a file-based operation should stop: once `token` is cancelled or `deadline` passes
+#[derive(Debug, Clone, Default)]
+pub struct StopSignal {
+    token: CancellationToken,
+    deadline: Option<Instant>,
+}
+
+impl StopSignal {
+    /// Stop when `token` is cancelled or, with a `timeout_ms`, that many milliseconds from now
+    pub fn new(token: CancellationToken, timeout_ms: Option<u64>) -> Self {
+        Self {
+            token,
+            deadline: timeout_ms.map(|ms| Instant::now() + Duration::from_millis(ms)),
+        }
+    }
+
+    /// Whether the operation should stop and return its partial results
+    pub fn should_stop(&self) -> bool {
+        self.token.is_cancelled()
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stops_on_cancel_or_deadline() {
+        let token = CancellationToken::new();
+        let signal = StopSignal::new(token.clone(), None);
+        assert!(!signal.should_stop());
+        token.cancel();
+        assert!(signal.should_stop());
+
+        assert!(StopSignal::new(CancellationToken::new(), Some(0)).should_stop());
+        assert!(!StopSignal::new(CancellationToken::new(), Some(60_000)).should_stop());
+    }
+}
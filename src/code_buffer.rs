@@ -0,0 +1,274 @@
+//! # Code Buffers
+//!
+//! MCP clients limit the size of a single message, so a large file can't always be sent as the
+//! `code` of `search`, `replace` or `generate_ast`. `begin_code_buffer` opens a buffer on the
+//! server and `append_code_buffer` adds chunks to it; those tools (and `search_buffer`) then take
+//! the buffer's `buffer_id` instead of `code`.
+//!
+//! Buffers live in memory only. Each one expires `ttl_seconds` after it was last used, and the
+//! least recently used buffer is dropped when too many are open.
+//!
+//! Buffers belong to a client session. A transport serving several clients from one process
+//! hands each client the service from [`CodeBuffers::for_session`] (via
+//! `AstGrepService::for_session`), so a client can only read the buffers it opened, and opening
+//! many buffers only evicts its own. A session disappears once all of its buffers have expired.
+//! The stdio transport has a single client and uses [`DEFAULT_SESSION`].
+
+use crate::errors::ServiceError;
+use crate::types::CodeBufferInfo;
+use lru::LruCache;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Number of buffers a session keeps open at once
+const MAX_BUFFERS: usize = 64;
+
+/// Session of a service that was not created for a particular client
+pub const DEFAULT_SESSION: &str = "default";
+
+struct CodeBuffer {
+    content: String,
+    chunks: usize,
+    ttl: Duration,
+    expires_at: Instant,
+}
+
+impl CodeBuffer {
+    fn info(&self, buffer_id: &str) -> CodeBufferInfo {
+        CodeBufferInfo {
+            buffer_id: buffer_id.to_string(),
+            size_bytes: self.content.len() as u64,
+            chunks: self.chunks,
+            expires_in_seconds: self.ttl.as_secs(),
+        }
+    }
+}
+
+type SessionBuffers = LruCache<String, CodeBuffer>;
+
+/// Open code buffers keyed by session and id, shared between clones of the service. Each handle
+/// reads and writes the buffers of one session
+#[derive(Clone)]
+pub struct CodeBuffers {
+    sessions: Arc<Mutex<HashMap<String, SessionBuffers>>>,
+    session: String,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Default for CodeBuffers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CodeBuffers {
+    pub fn new() -> Self {
+        Self {
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+            session: DEFAULT_SESSION.to_string(),
+            next_id: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// A handle on the buffers of `session_id`, sharing storage with `self`
+    pub fn for_session(&self, session_id: &str) -> Self {
+        Self {
+            sessions: self.sessions.clone(),
+            session: session_id.to_string(),
+            next_id: self.next_id.clone(),
+        }
+    }
+
+    /// Drop every buffer of this handle's session, e.g. when its client disconnects
+    pub fn end_session(&self) {
+        self.sessions.lock().unwrap().remove(&self.session);
+    }
+
+    /// Number of sessions with at least one buffer that has not expired
+    pub fn active_sessions(&self) -> usize {
+        let now = Instant::now();
+        self.sessions
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|buffers| buffers.iter().any(|(_, buffer)| buffer.expires_at > now))
+            .count()
+    }
+
+    /// Open a buffer holding `content` that expires `ttl` after its last use
+    pub fn begin(
+        &self,
+        content: String,
+        ttl: Duration,
+        max_size: u64,
+    ) -> Result<CodeBufferInfo, ServiceError> {
+        check_size(content.len(), max_size)?;
+        let buffer_id = self.new_id();
+        let buffer = CodeBuffer {
+            chunks: usize::from(!content.is_empty()),
+            content,
+            ttl,
+            expires_at: Instant::now() + ttl,
+        };
+        let info = buffer.info(&buffer_id);
+        let mut sessions = self.sessions.lock().unwrap();
+        // Forget sessions whose buffers have all expired
+        let now = Instant::now();
+        sessions.retain(|_, buffers| buffers.iter().any(|(_, buffer)| buffer.expires_at > now));
+        sessions
+            .entry(self.session.clone())
+            .or_insert_with(|| LruCache::new(NonZeroUsize::new(MAX_BUFFERS).unwrap()))
+            .put(buffer_id, buffer);
+        Ok(info)
+    }
+
+    /// Add `content` to the end of a buffer
+    pub fn append(
+        &self,
+        buffer_id: &str,
+        content: &str,
+        max_size: u64,
+    ) -> Result<CodeBufferInfo, ServiceError> {
+        self.with_buffer(buffer_id, |buffer| {
+            check_size(buffer.content.len() + content.len(), max_size)?;
+            buffer.content.push_str(content);
+            buffer.chunks += 1;
+            Ok(buffer.info(buffer_id))
+        })
+    }
+
+    /// `code`, or the content of the buffer when `buffer_id` is given instead
+    pub fn resolve(&self, code: String, buffer_id: Option<&str>) -> Result<String, ServiceError> {
+        let Some(buffer_id) = buffer_id else {
+            return Ok(code);
+        };
+        if !code.is_empty() {
+            return Err(ServiceError::Internal(
+                "Pass either code or buffer_id, not both".to_string(),
+            ));
+        }
+        self.with_buffer(buffer_id, |buffer| Ok(buffer.content.clone()))
+    }
+
+    /// Run `f` on a live buffer and push its expiry back
+    fn with_buffer<T>(
+        &self,
+        buffer_id: &str,
+        f: impl FnOnce(&mut CodeBuffer) -> Result<T, ServiceError>,
+    ) -> Result<T, ServiceError> {
+        let unknown = || {
+            ServiceError::Internal(format!(
+                "Unknown code buffer '{buffer_id}'; start one with begin_code_buffer"
+            ))
+        };
+        let mut sessions = self.sessions.lock().unwrap();
+        let buffers = sessions.get_mut(&self.session).ok_or_else(unknown)?;
+        let now = Instant::now();
+        let buffer = match buffers.get_mut(buffer_id) {
+            Some(buffer) if buffer.expires_at > now => buffer,
+            Some(_) => {
+                buffers.pop(buffer_id);
+                return Err(ServiceError::Internal(format!(
+                    "Code buffer '{buffer_id}' has expired; start a new one with begin_code_buffer"
+                )));
+            }
+            None => return Err(unknown()),
+        };
+        buffer.expires_at = now + buffer.ttl;
+        f(buffer)
+    }
+
+    /// An id that can't be guessed from the ones handed out before it
+    fn new_id(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.next_id.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+        hasher.update(std::process::id().to_le_bytes());
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        hasher.update(nanos.to_le_bytes());
+        format!("buf-{}", &hex::encode(hasher.finalize())[..16])
+    }
+}
+
+fn check_size(size: usize, max_size: u64) -> Result<(), ServiceError> {
+    if size as u64 > max_size {
+        return Err(ServiceError::Internal(format!(
+            "Code buffer would hold {size} bytes, over the {max_size} byte limit"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffers_accumulate_and_expire() {
+        let buffers = CodeBuffers::new();
+        let info = buffers
+            .begin("fn a() {}\n".to_string(), Duration::from_secs(60), 100)
+            .unwrap();
+        let info = buffers.append(&info.buffer_id, "fn b() {}\n", 100).unwrap();
+        assert_eq!((info.chunks, info.size_bytes), (2, 20));
+        assert_eq!(
+            buffers
+                .resolve(String::new(), Some(&info.buffer_id))
+                .unwrap(),
+            "fn a() {}\nfn b() {}\n"
+        );
+        assert!(
+            buffers
+                .append(&info.buffer_id, &"x".repeat(81), 100)
+                .is_err()
+        );
+        assert!(
+            buffers
+                .resolve("code".to_string(), Some(&info.buffer_id))
+                .is_err()
+        );
+
+        let expired = buffers.begin(String::new(), Duration::ZERO, 100).unwrap();
+        assert!(buffers.append(&expired.buffer_id, "x", 100).is_err());
+    }
+
+    #[test]
+    fn test_sessions_evict_and_expire_independently() {
+        let buffers = CodeBuffers::new();
+        let kept = buffers
+            .for_session("a")
+            .begin("a".to_string(), Duration::from_secs(60), 100)
+            .unwrap();
+        let busy = buffers.for_session("b");
+        for _ in 0..MAX_BUFFERS + 1 {
+            busy.begin("b".to_string(), Duration::from_secs(60), 100)
+                .unwrap();
+        }
+        assert!(
+            buffers
+                .for_session("a")
+                .resolve(String::new(), Some(&kept.buffer_id))
+                .is_ok()
+        );
+        assert_eq!(buffers.active_sessions(), 2);
+
+        // A session is forgotten once all of its buffers have expired
+        buffers
+            .for_session("c")
+            .begin(String::new(), Duration::ZERO, 100)
+            .unwrap();
+        busy.end_session();
+        assert_eq!(buffers.active_sessions(), 1);
+        buffers
+            .for_session("d")
+            .begin(String::new(), Duration::from_secs(60), 100)
+            .unwrap();
+        assert_eq!(buffers.sessions.lock().unwrap().len(), 2);
+    }
+}
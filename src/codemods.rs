@@ -0,0 +1,333 @@
+//! # Built-in Codemods
+//!
+//! A small library of common JavaScript/TypeScript refactorings, each selected by name and
+//! configured with typed parameters. A codemod is nothing more than a list of generated rule
+//! configurations with a `fix`; `run_codemod` hands them to `rule_replace` one after another,
+//! so previews, limits and writes behave exactly as they do for hand-written rules.
+//!
+//! - **rename_import_source**: point imports, re-exports, `require` calls and dynamic imports
+//!   of one module at another, keeping the original quote style
+//! - **wrap_calls_with_try_catch**: wrap call statements of a function in `try`/`catch`,
+//!   leaving calls that are already inside a `try` alone
+//! - **add_argument_with_default**: append a value to the calls of a function that pass
+//!   exactly `arity` arguments, e.g. after the function gained a new required parameter
+//! - **convert_default_export_to_named**: turn `export default` of a named function, class or
+//!   identifier into a named export
+//!
+//! Every codemod is idempotent, so applying it again after a partial run only picks up the
+//! code it has not converted yet.
+
+use crate::errors::ServiceError;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::sync::LazyLock;
+
+/// Languages the codemods are written for
+pub const LANGUAGES: [&str; 3] = ["javascript", "typescript", "tsx"];
+
+static IDENTIFIER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z_$][A-Za-z0-9_$]*$").unwrap());
+static DOTTED_IDENTIFIER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[A-Za-z_$][A-Za-z0-9_$]*(\.[A-Za-z_$][A-Za-z0-9_$]*)*$").unwrap()
+});
+
+/// A built-in codemod and its parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "codemod", content = "params", rename_all = "snake_case")]
+pub enum Codemod {
+    RenameImportSource {
+        /// Module specifier to replace, e.g. `lodash`
+        from: String,
+        /// Module specifier to use instead, e.g. `lodash-es`
+        to: String,
+    },
+    WrapCallsWithTryCatch {
+        /// Function whose calls are wrapped, e.g. `saveUser` or `api.save`
+        callee: String,
+        /// Statements for the catch block; the caught value is bound to `error`
+        #[serde(default = "default_catch_handler")]
+        handler: String,
+    },
+    AddArgumentWithDefault {
+        /// Function whose calls get the new argument, e.g. `connect` or `db.connect`
+        function: String,
+        /// Number of arguments the calls pass today; calls passing more or fewer are left
+        /// alone, which also keeps the codemod from adding the argument twice
+        arity: usize,
+        /// Expression passed as the new last argument
+        default: String,
+    },
+    ConvertDefaultExportToNamed {
+        /// Only convert the default export declaring or naming this identifier
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+}
+
+fn default_catch_handler() -> String {
+    "console.error(error);".to_string()
+}
+
+impl Codemod {
+    /// Name used to select the codemod
+    pub fn name(&self) -> &'static str {
+        match self {
+            Codemod::RenameImportSource { .. } => "rename_import_source",
+            Codemod::WrapCallsWithTryCatch { .. } => "wrap_calls_with_try_catch",
+            Codemod::AddArgumentWithDefault { .. } => "add_argument_with_default",
+            Codemod::ConvertDefaultExportToNamed { .. } => "convert_default_export_to_named",
+        }
+    }
+
+    /// Check the parameters and generate the rule configurations implementing the codemod for
+    /// `language`, in the order they should be applied
+    pub fn rules(&self, language: &str) -> Result<Vec<String>, ServiceError> {
+        if !LANGUAGES.contains(&language) {
+            return Err(ServiceError::Internal(format!(
+                "Codemod '{}' does not support language '{language}'. Supported languages: {}",
+                self.name(),
+                LANGUAGES.join(", ")
+            )));
+        }
+
+        match self {
+            Codemod::RenameImportSource { from, to } => {
+                validate_module("from", from)?;
+                validate_module("to", to)?;
+                // Module strings only count where they name a module, not anywhere in the code
+                let module_position = any(vec![
+                    single("inside", single("kind", "import_statement")),
+                    single("inside", single("kind", "export_statement")),
+                    single("inside", stop_by_end(single("pattern", "require($SOURCE)"))),
+                    single("inside", stop_by_end(single("pattern", "import($SOURCE)"))),
+                ]);
+                [("single-quoted", '\''), ("double-quoted", '"')]
+                    .into_iter()
+                    .map(|(suffix, quote)| {
+                        self.rule_yaml(
+                            suffix,
+                            language,
+                            all(vec![
+                                single("pattern", format!("{quote}{from}{quote}")),
+                                module_position.clone(),
+                            ]),
+                            format!("{quote}{to}{quote}"),
+                        )
+                    })
+                    .collect()
+            }
+            Codemod::WrapCallsWithTryCatch { callee, handler } => {
+                validate_identifier("callee", callee, &DOTTED_IDENTIFIER)?;
+                // `$CALL` keeps the call text exactly as written; `$$$ARGS` would not
+                let rule = all(vec![
+                    single("pattern", "$CALL;"),
+                    any(vec![
+                        single("pattern", format!("{callee}($$$ARGS);")),
+                        single("pattern", format!("await {callee}($$$ARGS);")),
+                    ]),
+                    single(
+                        "not",
+                        single("inside", stop_by_end(single("kind", "try_statement"))),
+                    ),
+                ]);
+                let handler = handler.trim();
+                let catch_block = if handler.is_empty() {
+                    "{}".to_string()
+                } else {
+                    format!("{{ {handler} }}")
+                };
+                Ok(vec![self.rule_yaml(
+                    "statement",
+                    language,
+                    rule,
+                    format!("try {{ $CALL; }} catch (error) {catch_block}"),
+                )?])
+            }
+            Codemod::AddArgumentWithDefault {
+                function,
+                arity,
+                default,
+            } => {
+                validate_identifier("function", function, &DOTTED_IDENTIFIER)?;
+                let default = default.trim();
+                if default.is_empty() {
+                    return Err(ServiceError::Internal(
+                        "Parameter 'default' must not be empty".to_string(),
+                    ));
+                }
+                let arguments: Vec<String> = (1..=*arity).map(|i| format!("$ARG{i}")).collect();
+                let mut new_arguments = arguments.clone();
+                new_arguments.push(default.to_string());
+                Ok(vec![self.rule_yaml(
+                    "call",
+                    language,
+                    single("pattern", format!("{function}({})", arguments.join(", "))),
+                    format!("{function}({})", new_arguments.join(", ")),
+                )?])
+            }
+            Codemod::ConvertDefaultExportToNamed { name } => {
+                let name_pattern = match name {
+                    Some(name) => {
+                        validate_identifier("name", name, &IDENTIFIER)?;
+                        regex::escape(name)
+                    }
+                    None => "[A-Za-z_$][A-Za-z0-9_$]*".to_string(),
+                };
+                // The regexes run against the text of the whole export statement
+                let declaration = all(vec![
+                    single("pattern", "export default $DECL"),
+                    single(
+                        "regex",
+                        format!(
+                            r"^export\s+default\s+(async\s+)?(abstract\s+)?(function\s*\*?|class|interface)\s*{name_pattern}[\s(<{{]"
+                        ),
+                    ),
+                ]);
+                let identifier = all(vec![
+                    single("pattern", "export default $NAME;"),
+                    single(
+                        "regex",
+                        format!(r"^export\s+default\s+{name_pattern}\s*;?$"),
+                    ),
+                ]);
+                Ok(vec![
+                    self.rule_yaml(
+                        "declaration",
+                        language,
+                        declaration,
+                        "export $DECL".to_string(),
+                    )?,
+                    self.rule_yaml(
+                        "identifier",
+                        language,
+                        identifier,
+                        "export { $NAME };".to_string(),
+                    )?,
+                ])
+            }
+        }
+    }
+
+    /// Serialize one generated rule; ids look like `codemod-rename-import-source-single-quoted`
+    fn rule_yaml(
+        &self,
+        suffix: &str,
+        language: &str,
+        rule: Value,
+        fix: String,
+    ) -> Result<String, ServiceError> {
+        let mut config = Mapping::new();
+        config.insert(
+            "id".into(),
+            format!("codemod-{}-{suffix}", self.name().replace('_', "-")).into(),
+        );
+        config.insert("language".into(), language.into());
+        config.insert("rule".into(), rule);
+        config.insert("fix".into(), fix.into());
+        Ok(serde_yaml::to_string(&config)?)
+    }
+}
+
+/// Files the codemods look at when no path pattern is given
+pub fn default_path_pattern(language: &str) -> &'static str {
+    match language {
+        "typescript" => "**/*.{ts,mts,cts}",
+        "tsx" => "**/*.tsx",
+        _ => "**/*.{js,jsx,mjs,cjs}",
+    }
+}
+
+fn validate_identifier(parameter: &str, value: &str, shape: &Regex) -> Result<(), ServiceError> {
+    if shape.is_match(value) {
+        Ok(())
+    } else {
+        Err(ServiceError::Internal(format!(
+            "Parameter '{parameter}' must be an identifier, got '{value}'"
+        )))
+    }
+}
+
+fn validate_module(parameter: &str, value: &str) -> Result<(), ServiceError> {
+    if value.is_empty() || value.contains(['\'', '"', '`', '\\', '\n', '\r']) {
+        return Err(ServiceError::Internal(format!(
+            "Parameter '{parameter}' must be a module specifier without quotes or backslashes, got '{value}'"
+        )));
+    }
+    Ok(())
+}
+
+fn single(key: &str, value: impl Into<Value>) -> Value {
+    let mut mapping = Mapping::new();
+    mapping.insert(key.into(), value.into());
+    Value::Mapping(mapping)
+}
+
+/// `rule` as the target of a relational rule that searches all ancestors, descendants or
+/// siblings rather than only the nearest one
+fn stop_by_end(mut rule: Value) -> Value {
+    if let Value::Mapping(mapping) = &mut rule {
+        mapping.insert("stopBy".into(), "end".into());
+    }
+    rule
+}
+
+fn all(rules: Vec<Value>) -> Value {
+    single("all", Value::Sequence(rules))
+}
+
+fn any(rules: Vec<Value>) -> Value {
+    single("any", Value::Sequence(rules))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::parse_rule_config;
+
+    #[test]
+    fn test_generated_rules_parse() {
+        let codemods = [
+            Codemod::RenameImportSource {
+                from: "lodash".to_string(),
+                to: "lodash-es".to_string(),
+            },
+            Codemod::WrapCallsWithTryCatch {
+                callee: "api.save".to_string(),
+                handler: "report(error);".to_string(),
+            },
+            Codemod::AddArgumentWithDefault {
+                function: "connect".to_string(),
+                arity: 2,
+                default: "{ retries: 3 }".to_string(),
+            },
+            Codemod::ConvertDefaultExportToNamed { name: None },
+        ];
+        for codemod in codemods {
+            for rule in codemod.rules("typescript").unwrap() {
+                let config = parse_rule_config(&rule).unwrap();
+                assert!(config.id.starts_with("codemod-"), "{}", config.id);
+                assert_eq!(config.language, "typescript");
+                assert!(config.fix.is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn test_parameters_are_validated() {
+        let rename = Codemod::RenameImportSource {
+            from: "a'; evil('".to_string(),
+            to: "b".to_string(),
+        };
+        assert!(rename.rules("javascript").is_err());
+
+        let wrap = Codemod::WrapCallsWithTryCatch {
+            callee: "save()".to_string(),
+            handler: default_catch_handler(),
+        };
+        assert!(wrap.rules("javascript").is_err());
+
+        let export = Codemod::ConvertDefaultExportToNamed { name: None };
+        assert!(export.rules("python").is_err());
+    }
+}
@@ -0,0 +1,135 @@
+//! # Result Compression
+//!
+//! The opt-in `compress` argument, accepted by every tool. When set, the tool's result JSON is
+//! gzipped and base64-encoded into a single [`CompressedResult`] that replaces the JSON content
+//! of the response, while the text summary is kept. Clients that can decompress locally get a
+//! medium-large result in one response instead of paging through it.
+
+use crate::errors::ServiceError;
+use crate::types::{CompressedResult, ResponseCompression};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use rmcp::model::{CallToolRequestParam, CallToolResult, Content, ErrorData};
+use std::borrow::Cow;
+use std::io::{Read, Write};
+
+/// Name of the tool argument that requests compression
+pub const COMPRESS_ARGUMENT: &str = "compress";
+
+/// The compression a tool call asked for, if any
+pub fn requested_compression(
+    request: &CallToolRequestParam,
+) -> Result<Option<ResponseCompression>, ErrorData> {
+    let Some(value) = request
+        .arguments
+        .as_ref()
+        .and_then(|arguments| arguments.get(COMPRESS_ARGUMENT))
+    else {
+        return Ok(None);
+    };
+    if value.is_null() {
+        return Ok(None);
+    }
+    serde_json::from_value(value.clone()).map(Some).map_err(|_| {
+        ErrorData::invalid_params(
+            Cow::Owned(format!(
+                "Unsupported compress value {value}; the only supported value is \"gzip+base64\""
+            )),
+            None,
+        )
+    })
+}
+
+/// Compress `json` with `compression`
+pub fn compress_json(
+    json: &[u8],
+    compression: ResponseCompression,
+) -> Result<CompressedResult, ServiceError> {
+    match compression {
+        ResponseCompression::GzipBase64 => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder
+                .write_all(json)
+                .and_then(|_| encoder.finish())
+                .map(|gzipped| CompressedResult {
+                    compression,
+                    original_bytes: json.len(),
+                    compressed_bytes: gzipped.len(),
+                    data: STANDARD.encode(gzipped),
+                })
+                .map_err(|e| ServiceError::Internal(format!("Failed to compress result: {e}")))
+        }
+    }
+}
+
+/// Decode a [`CompressedResult`] back into the result JSON it was made from
+pub fn decompress_json(compressed: &CompressedResult) -> Result<serde_json::Value, ServiceError> {
+    match compressed.compression {
+        ResponseCompression::GzipBase64 => {
+            let gzipped = STANDARD
+                .decode(&compressed.data)
+                .map_err(|e| ServiceError::Internal(format!("Invalid base64 data: {e}")))?;
+            let mut json = Vec::with_capacity(compressed.original_bytes);
+            GzDecoder::new(gzipped.as_slice())
+                .read_to_end(&mut json)
+                .map_err(|e| ServiceError::Internal(format!("Invalid gzip data: {e}")))?;
+            Ok(serde_json::from_slice(&json)?)
+        }
+    }
+}
+
+/// Replace the JSON content of a tool response with its compressed form and note the sizes
+/// after the summary. Error responses and responses without JSON content pass through.
+pub fn compress_response(
+    mut response: CallToolResult,
+    compression: ResponseCompression,
+) -> Result<CallToolResult, ErrorData> {
+    if response.is_error == Some(true) {
+        return Ok(response);
+    }
+    // Tools send their summary first and the result JSON last
+    let Some(index) = response.content.iter().rposition(is_json_content) else {
+        return Ok(response);
+    };
+    let json = response.content[index]
+        .as_text()
+        .map(|text| text.text.clone())
+        .unwrap_or_default();
+
+    let compressed = compress_json(json.as_bytes(), compression).map_err(ErrorData::from)?;
+    let note = format!(
+        "📦 Full result compressed with gzip+base64: {} bytes → {} bytes",
+        compressed.original_bytes, compressed.compressed_bytes
+    );
+    response.content[index] = Content::json(&compressed)?;
+    response.content.insert(index, Content::text(note));
+    Ok(response)
+}
+
+/// Whether a content item is a JSON object or array, as produced by `Content::json`
+fn is_json_content(content: &Content) -> bool {
+    content.as_text().is_some_and(|text| {
+        let text = text.text.trim_start();
+        (text.starts_with('{') || text.starts_with('['))
+            && serde_json::from_str::<serde_json::Value>(text).is_ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_round_trip() {
+        let value = json!({"matches": vec!["console.log(x)"; 200]});
+        let json = serde_json::to_vec(&value).unwrap();
+        let compressed = compress_json(&json, ResponseCompression::GzipBase64).unwrap();
+        assert_eq!(compressed.original_bytes, json.len());
+        assert!(compressed.compressed_bytes < json.len() / 10);
+        assert_eq!(decompress_json(&compressed).unwrap(), value);
+    }
+}
@@ -3,8 +3,14 @@
 //! Configuration structures and defaults for the ast-grep MCP service.
 //! These settings control performance, resource limits, and file system access.
 
+use crate::dynamic_languages;
+use crate::errors::ServiceError;
+use crate::input_limits::default_max_input_sizes;
 use crate::sg_config::SgConfig;
+use crate::worker::WorkerConfig;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// Configuration for the ast-grep MCP service.
 ///
@@ -29,12 +35,19 @@ pub struct ServiceConfig {
     pub max_file_size: u64,
     /// Maximum number of concurrent file operations
     pub max_concurrency: usize,
+    /// Number of files parsed and matched at once by CPU-bound work such as `file_replace`,
+    /// apart from `max_concurrency`, which bounds file I/O
+    pub cpu_concurrency: usize,
     /// Maximum number of results to return per search
     pub limit: usize,
     /// Root directories for file search (defaults to current working directory)
     pub root_directories: Vec<PathBuf>,
     /// Directory for storing custom rules created by LLMs
     pub rules_directory: PathBuf,
+    /// Keep created rules in this sqlite database instead of YAML files in `rules_directory`
+    pub rules_database: Option<PathBuf>,
+    /// Team rule registry that `push_rule` and `pull_rules` sync stored rules with
+    pub rule_registry: Option<RuleRegistryConfig>,
     /// Maximum number of compiled patterns to cache (default: 1000)
     pub pattern_cache_size: usize,
     /// Additional rule directories from sgconfig.yml
@@ -43,6 +56,63 @@ pub struct ServiceConfig {
     pub util_dirs: Vec<PathBuf>,
     /// Path to the loaded sgconfig.yml (if any)
     pub sg_config_path: Option<PathBuf>,
+    /// Parse and match files in sandboxed worker processes (default: in-process)
+    pub worker_isolation: Option<WorkerConfig>,
+    /// Formatter commands used by `verify_formatting`, keyed by language name; `{file}` in an
+    /// argument is replaced with the file name. Languages not listed use the built-in defaults
+    pub formatters: HashMap<String, Vec<String>>,
+    /// Labels for root directories. Search and replace tools take a `workspace` label to stay
+    /// inside one root, and label each file they report. Every labelled directory must also be
+    /// listed in `root_directories`
+    pub workspaces: BTreeMap<String, PathBuf>,
+    /// Commands `verify_refactoring` may run after applying a replacement, keyed by the name a
+    /// tool call selects them with
+    pub verify_commands: BTreeMap<String, Vec<String>>,
+    /// Directory where `pattern_coverage` keeps the snapshots of each migration
+    pub coverage_directory: PathBuf,
+    /// Directory where `file_replace` records the previous contents of the files it writes, so
+    /// `rollback_replace` can restore them
+    pub undo_directory: PathBuf,
+    /// Time a `rule_search` may spend evaluating one rule before it stops with the results so far
+    pub rule_timeout: Duration,
+    /// Matches a `rule_search` may collect for one rule before it stops with the results so far
+    pub rule_max_matches: usize,
+    /// How many directory levels below a root the file-based tools descend
+    pub walk_max_depth: usize,
+    /// Directory names the file-based tools never descend into, such as `node_modules`
+    pub prune_dirs: Vec<String>,
+    /// Whether the file-based tools walk hidden directories and files
+    pub follow_hidden: bool,
+    /// Whether the file-based tools leave out files excluded by `.gitignore` and similar files
+    pub respect_gitignore: bool,
+    /// Allowlisted environment variables and their values, which rule configs and path patterns
+    /// may reference as `${NAME}`
+    pub env_vars: BTreeMap<String, String>,
+    /// Keep the file lists and syntax trees of the root directories in memory between searches
+    pub file_index: bool,
+    /// Fill the file index and symbol cache in the background after startup, pausing this long
+    /// between batches of entries walked and between files parsed; see [`crate::prewarm`]
+    pub prewarm: Option<Duration>,
+    /// Take a lockfile in each root directory a tool writes to, so servers sharing a checkout
+    /// never apply changes to the same root at once
+    pub write_lock: bool,
+    /// Time a write waits for another server's lock on its root directories before failing
+    pub write_lock_timeout: Duration,
+    /// How long a pagination cursor stays valid after the page that returned it
+    pub cursor_ttl: Duration,
+    /// Largest size in bytes of a tool argument, keyed by argument name such as `code` or
+    /// `rule_config`. Calls with a larger argument fail before they are parsed; arguments not
+    /// listed are unlimited
+    pub max_input_sizes: BTreeMap<String, usize>,
+}
+
+/// Where a team keeps its shared rule library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleRegistryConfig {
+    /// Base URL of the registry's HTTP API, e.g. `https://rules.example.com/api`
+    pub url: String,
+    /// Sent as a bearer token with every request
+    pub token: Option<String>,
 }
 
 impl Default for ServiceConfig {
@@ -51,21 +121,65 @@ impl Default for ServiceConfig {
     /// Default values:
     /// - `max_file_size`: 50MB
     /// - `max_concurrency`: 10 concurrent operations
+    /// - `cpu_concurrency`: one file per CPU core
     /// - `limit`: 100 results per search
     /// - `root_directories`: Current working directory
     /// - `rules_directory`: `.ast-grep-rules` in current directory
+    /// - `rules_database`: none, rules are stored as YAML files
+    /// - `rule_registry`: none, rules are not shared
     /// - `pattern_cache_size`: 1000 cached compiled patterns
+    /// - `worker_isolation`: disabled, files are parsed in-process
+    /// - `formatters`: none, the built-in formatter commands are used
+    /// - `workspaces`: none, roots are unlabelled
+    /// - `verify_commands`: none, so `verify_refactoring` has nothing to run
+    /// - `coverage_directory`: `.ast-grep-coverage` in current directory
+    /// - `undo_directory`: `.ast-grep-undo` in current directory
+    /// - `rule_timeout`: 30 seconds per rule
+    /// - `rule_max_matches`: 10000 matches per rule
+    /// - `walk_max_depth`: 10 directory levels
+    /// - `prune_dirs`: none, every directory is walked
+    /// - `follow_hidden`: true, hidden entries are walked
+    /// - `respect_gitignore`: true, ignored files and `.git` are left out
+    /// - `env_vars`: none, `${NAME}` is never interpolated
+    /// - `file_index`: false, every search walks and parses the files again
+    /// - `prewarm`: none, caches fill on first use
+    /// - `write_lock`: true, writes take a lockfile in their root directories
+    /// - `write_lock_timeout`: 30 seconds
+    /// - `cursor_ttl`: 1 hour
+    /// - `max_input_sizes`: 1 MiB of `code`, 256 KiB of `rule_config`
     fn default() -> Self {
         Self {
             max_file_size: 50 * 1024 * 1024, // 50MB
             max_concurrency: 10,
+            cpu_concurrency: std::thread::available_parallelism().map_or(4, usize::from),
             limit: 100,
             root_directories: vec![std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))],
             rules_directory: PathBuf::from(".ast-grep-rules"),
+            rules_database: None,
+            rule_registry: None,
             pattern_cache_size: 1000, // Cache up to 1000 compiled patterns
             additional_rule_dirs: Vec::new(),
             util_dirs: Vec::new(),
             sg_config_path: None,
+            worker_isolation: None,
+            formatters: HashMap::new(),
+            workspaces: BTreeMap::new(),
+            verify_commands: BTreeMap::new(),
+            coverage_directory: PathBuf::from(".ast-grep-coverage"),
+            undo_directory: PathBuf::from(".ast-grep-undo"),
+            rule_timeout: Duration::from_secs(30),
+            rule_max_matches: 10_000,
+            walk_max_depth: 10,
+            prune_dirs: Vec::new(),
+            follow_hidden: true,
+            respect_gitignore: true,
+            env_vars: BTreeMap::new(),
+            file_index: false,
+            prewarm: None,
+            write_lock: true,
+            write_lock_timeout: Duration::from_secs(30),
+            cursor_ttl: Duration::from_secs(60 * 60),
+            max_input_sizes: default_max_input_sizes(),
         }
     }
 }
@@ -118,11 +232,96 @@ impl ServiceConfig {
         self.util_dirs = sg_config.util_dirs;
         self.sg_config_path = config_path;
 
-        // TODO: Handle test_configs and custom_languages when needed
+        let mut custom_languages: Vec<_> = sg_config.custom_languages.languages.iter().collect();
+        custom_languages.sort_by_key(|(name, _)| name.as_str());
+        for (name, language) in custom_languages {
+            if let Err(e) = dynamic_languages::register(name, language) {
+                eprintln!("Warning: Failed to load custom language '{name}': {e}");
+            }
+        }
+
+        // TODO: Handle test_configs when needed
 
         self
     }
 
+    /// Roots a tool call may touch: the root labelled `workspace`, or every root when no
+    /// workspace is given
+    pub fn workspace_roots(&self, workspace: Option<&str>) -> Result<Vec<PathBuf>, ServiceError> {
+        let Some(label) = workspace else {
+            return Ok(self.root_directories.clone());
+        };
+        match self.workspaces.get(label) {
+            Some(root) => Ok(vec![root.clone()]),
+            None if self.workspaces.is_empty() => Err(ServiceError::Internal(format!(
+                "Unknown workspace '{label}': no workspaces are configured"
+            ))),
+            None => Err(ServiceError::Internal(format!(
+                "Unknown workspace '{label}'; configured workspaces: {}",
+                self.workspaces
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        }
+    }
+
+    /// Warnings about root directories that reach the same files: the same directory, or one
+    /// inside another, once relative components and symlinks are resolved. Files under both are
+    /// searched once, under the root listed first. Empty when `workspace` narrows the call to a
+    /// single root
+    pub fn overlapping_root_warnings(&self, workspace: Option<&str>) -> Vec<String> {
+        if workspace.is_some() {
+            return Vec::new();
+        }
+        let roots: Vec<(&PathBuf, PathBuf)> = self
+            .root_directories
+            .iter()
+            .map(|root| (root, root.canonicalize().unwrap_or_else(|_| root.clone())))
+            .collect();
+        let mut warnings = Vec::new();
+        for (index, (root, canonical)) in roots.iter().enumerate() {
+            for (other, other_canonical) in &roots[index + 1..] {
+                let warning = if canonical == other_canonical {
+                    format!(
+                        "Root directories '{}' and '{}' are the same directory",
+                        root.display(),
+                        other.display()
+                    )
+                } else if other_canonical.starts_with(canonical) {
+                    format!(
+                        "Root directory '{}' is inside root directory '{}'",
+                        other.display(),
+                        root.display()
+                    )
+                } else if canonical.starts_with(other_canonical) {
+                    format!(
+                        "Root directory '{}' is inside root directory '{}'",
+                        root.display(),
+                        other.display()
+                    )
+                } else {
+                    continue;
+                };
+                warnings.push(format!(
+                    "{warning}; files under both are only reported once"
+                ));
+            }
+        }
+        warnings
+    }
+
+    /// Label of the workspace containing `path`; the most specific one when workspaces nest
+    pub fn workspace_label(&self, path: &str) -> Option<String> {
+        let path = Path::new(path);
+        self.workspaces
+            .iter()
+            .filter(|(_, root)| path.starts_with(root))
+            .max_by_key(|(_, root)| root.components().count())
+            .map(|(label, _)| label.clone())
+    }
+
     /// Get all rule directories (including the main rules_directory and additional ones)
     pub fn all_rule_directories(&self) -> Vec<PathBuf> {
         let mut dirs = vec![self.rules_directory.clone()];
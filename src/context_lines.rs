@@ -1,4 +1,11 @@
-use crate::types::MatchResult;
+use crate::enclosing_symbol::{TYPE_KINDS, symbol_kind};
+use crate::types::{ContextScope, EnclosingNode, LeadingTrivia, MatchResult, TriviaKind};
+use ast_grep_core::tree_sitter::{LanguageExt, StrDoc};
+use ast_grep_core::{AstGrep, Node};
+
+/// Node kind suffixes of statements and declarations across the bundled grammars, e.g.
+/// `expression_statement`, `lexical_declaration`, `function_definition` and `let_declaration`
+const STATEMENT_SUFFIXES: &[&str] = &["_statement", "_declaration", "_definition", "_item"];
 
 /// Extract context lines from source code for a match result
 pub fn extract_context_lines(
@@ -85,6 +92,157 @@ pub fn extract_context_lines(
         .collect()
 }
 
+/// Attach to each match the smallest node of `scope` containing it, in place of context lines.
+/// Matches outside any such node, like top-level code for `ContextScope::Function`, get none
+pub fn extract_context_scope<L: LanguageExt>(
+    source_code: &str,
+    lang: L,
+    matches: &[MatchResult],
+    scope: ContextScope,
+) -> Vec<MatchResult> {
+    let ast = AstGrep::new(source_code, lang);
+    let root = ast.root();
+    matches
+        .iter()
+        .map(|m| {
+            let mut m = m.clone();
+            let node = covering_node(&root, m.start_line, m.start_col, m.end_line, m.end_col);
+            m.enclosing_node = std::iter::once(node.clone())
+                .chain(node.ancestors())
+                .find(|node| in_scope(&node.kind(), scope))
+                .map(|node| EnclosingNode {
+                    kind: node.kind().to_string(),
+                    start_line: node.start_pos().line(),
+                    end_line: node.end_pos().line(),
+                    text: node.text().to_string(),
+                });
+            m
+        })
+        .collect()
+}
+
+/// Attach to each match the comments and attributes directly above it, such as a doc comment
+/// and `#[derive]` over a Rust item or decorators over a Python function. A match with nothing
+/// above it borrows the trivia of a node it starts on the line of, so `export function` still
+/// finds the comment above `export`, and trivia that starts its parent continues with the
+/// parent's, so a comment above a decorated Python function is kept with the decorators. A
+/// blank line ends the trivia, as does a comment trailing code on its own line
+pub fn attach_leading_trivia<L: LanguageExt>(
+    source_code: &str,
+    lang: L,
+    matches: &mut [MatchResult],
+) {
+    let ast = AstGrep::new(source_code, lang);
+    let root = ast.root();
+    for m in matches {
+        let node = covering_node(&root, m.start_line, m.start_col, m.end_line, m.end_col);
+        m.leading_trivia = leading_trivia(node);
+    }
+}
+
+/// The comments and attributes directly above `node`, first to last, found the way
+/// [`attach_leading_trivia`] finds them for a match
+pub(crate) fn leading_trivia<L: LanguageExt>(mut node: Node<'_, StrDoc<L>>) -> Vec<LeadingTrivia> {
+    let mut top_line = node.start_pos().line();
+    let mut trivia = Vec::new();
+    loop {
+        let reached_first = collect_leading_trivia(&node, &mut top_line, &mut trivia);
+        match node.parent() {
+            Some(parent)
+                if (trivia.is_empty() || reached_first)
+                    && parent.parent().is_some()
+                    && parent.start_pos().line() == top_line =>
+            {
+                node = parent
+            }
+            _ => break,
+        }
+    }
+    trivia.reverse();
+    trivia
+}
+
+/// Push the comment and attribute siblings right above `node` onto `trivia`, nearest first,
+/// moving `top_line` up to the first of them. Returns whether they reach the first sibling
+fn collect_leading_trivia<L: LanguageExt>(
+    node: &Node<'_, StrDoc<L>>,
+    top_line: &mut usize,
+    trivia: &mut Vec<LeadingTrivia>,
+) -> bool {
+    let mut sibling = node.prev();
+    while let Some(item) = sibling {
+        let Some(kind) = trivia_kind(&item.kind()) else {
+            return false;
+        };
+        // Line comments of some grammars take in their newline
+        let text = item.text().trim_end().to_string();
+        let start_line = item.start_pos().line();
+        let end_line = start_line + text.matches('\n').count();
+        let trailing = item.prev().is_some_and(|prev| {
+            prev.end_pos().line() == start_line && trivia_kind(&prev.kind()).is_none()
+        });
+        if end_line + 1 < *top_line || trailing {
+            return false;
+        }
+        trivia.push(LeadingTrivia {
+            kind,
+            node_kind: item.kind().to_string(),
+            start_line,
+            end_line,
+            text,
+        });
+        *top_line = start_line;
+        sibling = item.prev();
+    }
+    true
+}
+
+fn trivia_kind(kind: &str) -> Option<TriviaKind> {
+    if kind.contains("comment") {
+        Some(TriviaKind::Comment)
+    } else if matches!(kind, "attribute_item" | "attribute_list")
+        || kind.ends_with("decorator")
+        || kind.ends_with("annotation")
+    {
+        Some(TriviaKind::Attribute)
+    } else {
+        None
+    }
+}
+
+/// The deepest node spanning from (start_line, start_col) to (end_line, end_col)
+fn covering_node<'r, L: LanguageExt>(
+    root: &Node<'r, StrDoc<L>>,
+    start_line: usize,
+    start_col: usize,
+    end_line: usize,
+    end_col: usize,
+) -> Node<'r, StrDoc<L>> {
+    let mut node = root.clone();
+    loop {
+        let child = node.children().find(|child| {
+            let start = child.start_pos();
+            let end = child.end_pos();
+            (start.line(), start.column(child)) <= (start_line, start_col)
+                && (end.line(), end.column(child)) >= (end_line, end_col)
+        });
+        match child {
+            Some(child) => node = child,
+            None => return node,
+        }
+    }
+}
+
+fn in_scope(kind: &str, scope: ContextScope) -> bool {
+    match scope {
+        ContextScope::Statement => STATEMENT_SUFFIXES
+            .iter()
+            .any(|suffix| kind.ends_with(suffix)),
+        ContextScope::Function => matches!(symbol_kind(kind), Some("function" | "method")),
+        ContextScope::Class => symbol_kind(kind).is_some_and(|kind| TYPE_KINDS.contains(&kind)),
+    }
+}
+
 /// Add context lines to search results
 pub fn add_context_to_search_result(
     source_code: &str,
@@ -125,6 +283,12 @@ mod tests {
             vars: HashMap::new(),
             context_before: None,
             context_after: None,
+            enclosing_symbol: None,
+            ast_context: None,
+            quick_fixes: Vec::new(),
+            enclosing_node: None,
+            leading_trivia: Vec::new(),
+            rule_id: None,
         }];
 
         let result = extract_context_lines(source, &matches, Some(1), Some(1), None);
@@ -150,6 +314,12 @@ mod tests {
             vars: HashMap::new(),
             context_before: None,
             context_after: None,
+            enclosing_symbol: None,
+            ast_context: None,
+            quick_fixes: Vec::new(),
+            enclosing_node: None,
+            leading_trivia: Vec::new(),
+            rule_id: None,
         }];
 
         let result = extract_context_lines(source, &matches, Some(2), Some(1), None);
@@ -172,6 +342,12 @@ mod tests {
             vars: HashMap::new(),
             context_before: None,
             context_after: None,
+            enclosing_symbol: None,
+            ast_context: None,
+            quick_fixes: Vec::new(),
+            enclosing_node: None,
+            leading_trivia: Vec::new(),
+            rule_id: None,
         }];
 
         let result = extract_context_lines(source, &matches, None, None, Some(2));
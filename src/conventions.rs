@@ -0,0 +1,415 @@
+//! # Codebase Conventions
+//!
+//! Infers the house style of a codebase from a sample of its files, so proposed changes can
+//! follow it: quote style, how variables are declared, how errors are handled and which test
+//! framework is in use. Each convention is measured with a fixed set of structural probes,
+//! either a node kind (optionally narrowed by its text) or an ast-grep pattern, and reported
+//! with how often each option was seen.
+
+use crate::directory_summary::source_files;
+use crate::types::{
+    Convention, ConventionExample, DetectConventionsParam, DetectConventionsResult, SkippedFile,
+};
+use ast_grep_core::tree_sitter::{LanguageExt, StrDoc};
+use ast_grep_core::{AstGrep, Node, Pattern};
+use ast_grep_language::SupportLang as Language;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+
+const JS_FAMILY: &[Language] = &[Language::JavaScript, Language::TypeScript, Language::Tsx];
+
+/// How a probe recognizes one option of a convention
+enum Matcher {
+    /// Nodes of this kind whose text satisfies the predicate
+    Kind(&'static str, fn(&str) -> bool),
+    /// Matches of an ast-grep pattern
+    Pattern(&'static str),
+}
+
+/// One option of a convention, such as single quotes for `quote_style`
+struct Probe {
+    topic: &'static str,
+    option: &'static str,
+    /// Language family the counts are pooled under
+    family: &'static str,
+    languages: &'static [Language],
+    matcher: Matcher,
+}
+
+/// The text of a string literal after any prefix such as Python's `f` or `r`
+fn unprefixed(text: &str) -> &str {
+    text.trim_start_matches(|c: char| c.is_ascii_alphabetic())
+}
+
+const PROBES: &[Probe] = &[
+    // JavaScript and TypeScript
+    Probe {
+        topic: "quote_style",
+        option: "single",
+        family: "javascript",
+        languages: JS_FAMILY,
+        matcher: Matcher::Kind("string", |text| text.starts_with('\'')),
+    },
+    Probe {
+        topic: "quote_style",
+        option: "double",
+        family: "javascript",
+        languages: JS_FAMILY,
+        matcher: Matcher::Kind("string", |text| text.starts_with('"')),
+    },
+    Probe {
+        topic: "variable_declarations",
+        option: "const",
+        family: "javascript",
+        languages: JS_FAMILY,
+        matcher: Matcher::Kind("lexical_declaration", |text| text.starts_with("const")),
+    },
+    Probe {
+        topic: "variable_declarations",
+        option: "let",
+        family: "javascript",
+        languages: JS_FAMILY,
+        matcher: Matcher::Kind("lexical_declaration", |text| text.starts_with("let")),
+    },
+    Probe {
+        topic: "variable_declarations",
+        option: "var",
+        family: "javascript",
+        languages: JS_FAMILY,
+        matcher: Matcher::Kind("variable_declaration", |_| true),
+    },
+    Probe {
+        topic: "error_handling",
+        option: "try/catch",
+        family: "javascript",
+        languages: JS_FAMILY,
+        matcher: Matcher::Kind("try_statement", |_| true),
+    },
+    Probe {
+        topic: "error_handling",
+        option: "promise .catch()",
+        family: "javascript",
+        languages: JS_FAMILY,
+        matcher: Matcher::Pattern("$PROMISE.catch($$$)"),
+    },
+    Probe {
+        topic: "test_framework",
+        option: "jest",
+        family: "javascript",
+        languages: JS_FAMILY,
+        matcher: Matcher::Pattern("jest.$METHOD($$$)"),
+    },
+    Probe {
+        topic: "test_framework",
+        option: "jest",
+        family: "javascript",
+        languages: JS_FAMILY,
+        matcher: Matcher::Kind("import_statement", |text| text.contains("@jest/globals")),
+    },
+    Probe {
+        topic: "test_framework",
+        option: "vitest",
+        family: "javascript",
+        languages: JS_FAMILY,
+        matcher: Matcher::Kind("import_statement", |text| {
+            text.contains("'vitest'") || text.contains("\"vitest\"")
+        }),
+    },
+    Probe {
+        topic: "test_framework",
+        option: "mocha",
+        family: "javascript",
+        languages: JS_FAMILY,
+        matcher: Matcher::Kind("import_statement", |text| {
+            text.contains("'mocha'") || text.contains("\"mocha\"")
+        }),
+    },
+    Probe {
+        topic: "test_framework",
+        option: "node:test",
+        family: "javascript",
+        languages: JS_FAMILY,
+        matcher: Matcher::Kind("import_statement", |text| text.contains("node:test")),
+    },
+    // Python
+    Probe {
+        topic: "quote_style",
+        option: "single",
+        family: "python",
+        languages: &[Language::Python],
+        matcher: Matcher::Kind("string", |text| unprefixed(text).starts_with('\'')),
+    },
+    Probe {
+        topic: "quote_style",
+        option: "double",
+        family: "python",
+        languages: &[Language::Python],
+        matcher: Matcher::Kind("string", |text| unprefixed(text).starts_with('"')),
+    },
+    Probe {
+        topic: "error_handling",
+        option: "try/except",
+        family: "python",
+        languages: &[Language::Python],
+        matcher: Matcher::Kind("try_statement", |_| true),
+    },
+    Probe {
+        topic: "error_handling",
+        option: "with (context manager)",
+        family: "python",
+        languages: &[Language::Python],
+        matcher: Matcher::Kind("with_statement", |_| true),
+    },
+    Probe {
+        topic: "test_framework",
+        option: "pytest",
+        family: "python",
+        languages: &[Language::Python],
+        matcher: Matcher::Kind("import_statement", |text| text.contains("pytest")),
+    },
+    Probe {
+        topic: "test_framework",
+        option: "unittest",
+        family: "python",
+        languages: &[Language::Python],
+        matcher: Matcher::Kind("import_statement", |text| text.contains("unittest")),
+    },
+    Probe {
+        topic: "test_framework",
+        option: "unittest",
+        family: "python",
+        languages: &[Language::Python],
+        matcher: Matcher::Kind("import_from_statement", |text| {
+            text.starts_with("from unittest")
+        }),
+    },
+    // Rust
+    Probe {
+        topic: "error_handling",
+        option: "? operator",
+        family: "rust",
+        languages: &[Language::Rust],
+        matcher: Matcher::Kind("try_expression", |_| true),
+    },
+    Probe {
+        topic: "error_handling",
+        option: ".unwrap()",
+        family: "rust",
+        languages: &[Language::Rust],
+        matcher: Matcher::Pattern("$VALUE.unwrap()"),
+    },
+    Probe {
+        topic: "error_handling",
+        option: ".expect()",
+        family: "rust",
+        languages: &[Language::Rust],
+        matcher: Matcher::Pattern("$VALUE.expect($MESSAGE)"),
+    },
+    Probe {
+        topic: "test_framework",
+        option: "#[test]",
+        family: "rust",
+        languages: &[Language::Rust],
+        matcher: Matcher::Kind("attribute_item", |text| text == "#[test]"),
+    },
+    Probe {
+        topic: "test_framework",
+        option: "#[tokio::test]",
+        family: "rust",
+        languages: &[Language::Rust],
+        matcher: Matcher::Kind("attribute_item", |text| text.starts_with("#[tokio::test")),
+    },
+    // Go
+    Probe {
+        topic: "error_handling",
+        option: "if err != nil",
+        family: "go",
+        languages: &[Language::Go],
+        matcher: Matcher::Pattern("if err != nil { $$$ }"),
+    },
+    Probe {
+        topic: "error_handling",
+        option: "panic()",
+        family: "go",
+        languages: &[Language::Go],
+        matcher: Matcher::Pattern("panic($$$)"),
+    },
+    Probe {
+        topic: "test_framework",
+        option: "testing",
+        family: "go",
+        languages: &[Language::Go],
+        matcher: Matcher::Kind("import_spec", |text| text == "\"testing\""),
+    },
+    Probe {
+        topic: "test_framework",
+        option: "testify",
+        family: "go",
+        languages: &[Language::Go],
+        matcher: Matcher::Kind("import_spec", |text| text.contains("stretchr/testify")),
+    },
+];
+
+/// Counts for one option, with the first place it was seen
+#[derive(Default)]
+struct Tally {
+    count: usize,
+    example: Option<ConventionExample>,
+}
+
+/// Sample the source files under `directories` and measure every probe that applies to them
+pub fn detect_conventions(
+    directories: &[PathBuf],
+    param: &DetectConventionsParam,
+) -> DetectConventionsResult {
+    let (files, files_found) = sample(source_files(directories), param.max_files);
+    let mut tallies: BTreeMap<(&str, &str), BTreeMap<&str, Tally>> = BTreeMap::new();
+    let mut result = DetectConventionsResult {
+        files_sampled: 0,
+        files_found,
+        languages: BTreeMap::new(),
+        conventions: Vec::new(),
+        skipped_files: Vec::new(),
+    };
+
+    for (path, lang) in files {
+        let probes: Vec<&Probe> = PROBES
+            .iter()
+            .filter(|probe| probe.languages.contains(&lang))
+            .collect();
+        if probes.is_empty() {
+            continue;
+        }
+        let file_path = path.to_string_lossy().to_string();
+        let too_large = std::fs::metadata(&path)
+            .map(|metadata| metadata.len() > param.max_file_size)
+            .unwrap_or(false);
+        if too_large {
+            result.skipped_files.push(SkippedFile {
+                file_path,
+                reason: format!("larger than {} bytes", param.max_file_size),
+            });
+            continue;
+        }
+        let code = match std::fs::read_to_string(&path) {
+            Ok(code) => code,
+            Err(e) => {
+                result.skipped_files.push(SkippedFile {
+                    file_path,
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        result.files_sampled += 1;
+        *result
+            .languages
+            .entry(lang.to_string().to_lowercase())
+            .or_default() += 1;
+        let ast = lang.ast_grep(&code);
+        for probe in probes {
+            let tally = tallies
+                .entry((probe.family, probe.topic))
+                .or_default()
+                .entry(probe.option)
+                .or_default();
+            for node in probe_matches(&ast, &probe.matcher, lang) {
+                tally.count += 1;
+                tally.example.get_or_insert_with(|| ConventionExample {
+                    file_path: file_path.clone(),
+                    line: node.start_pos().line(),
+                    text: node.text().lines().next().unwrap_or_default().to_string(),
+                });
+            }
+        }
+    }
+
+    result.conventions = tallies
+        .into_iter()
+        .filter_map(|((family, topic), options)| summarize(family, topic, options))
+        .collect();
+    result
+}
+
+/// Nodes in `ast` recognized by `matcher`; patterns that don't parse in `lang` match nothing
+fn probe_matches<'a>(
+    ast: &'a AstGrep<StrDoc<Language>>,
+    matcher: &Matcher,
+    lang: Language,
+) -> Vec<Node<'a, StrDoc<Language>>> {
+    match matcher {
+        Matcher::Kind(kind, accepts) => ast
+            .root()
+            .dfs()
+            .filter(|node| node.kind() == *kind && accepts(&node.text()))
+            .collect(),
+        Matcher::Pattern(pattern) => match Pattern::try_new(pattern, lang) {
+            Ok(pattern) => ast
+                .root()
+                .find_all(pattern)
+                .map(|node_match| node_match.get_node().clone())
+                .collect(),
+            Err(_) => Vec::new(),
+        },
+    }
+}
+
+/// The convention for one topic, or `None` when none of its options were seen
+fn summarize(family: &str, topic: &str, options: BTreeMap<&str, Tally>) -> Option<Convention> {
+    let observations: usize = options.values().map(|tally| tally.count).sum();
+    // Most frequent first; ties keep the alphabetical order of the options
+    let (dominant, tally) = options.iter().filter(|(_, tally)| tally.count > 0).max_by(
+        |(a_option, a), (b_option, b)| a.count.cmp(&b.count).then(b_option.cmp(a_option)),
+    )?;
+
+    Some(Convention {
+        topic: topic.to_string(),
+        language: family.to_string(),
+        dominant: dominant.to_string(),
+        share: tally.count as f64 / observations as f64,
+        observations,
+        example: tally.example.clone(),
+        counts: options
+            .iter()
+            .filter(|(_, tally)| tally.count > 0)
+            .map(|(option, tally)| (option.to_string(), tally.count))
+            .collect(),
+    })
+}
+
+/// Spread at most `max_files` picks evenly over `files`, so the sample covers every directory
+/// rather than the first few in path order. Returns the sample and how many files there were
+fn sample(files: Vec<(PathBuf, Language)>, max_files: usize) -> (Vec<(PathBuf, Language)>, usize) {
+    let total = files.len();
+    if total <= max_files {
+        return (files, total);
+    }
+    let picks: HashSet<usize> = (0..max_files).map(|i| i * total / max_files).collect();
+    let sampled = files
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| picks.contains(i))
+        .map(|(_, file)| file)
+        .collect();
+    (sampled, total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_spreads_over_files() {
+        let files: Vec<(PathBuf, Language)> = (0..10)
+            .map(|i| (PathBuf::from(format!("{i}.js")), Language::JavaScript))
+            .collect();
+        let (sampled, total) = sample(files, 3);
+        assert_eq!(total, 10);
+        let names: Vec<String> = sampled
+            .iter()
+            .map(|(path, _)| path.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["0.js", "3.js", "6.js"]);
+    }
+}
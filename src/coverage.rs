@@ -0,0 +1,265 @@
+//! # Pattern Coverage
+//!
+//! Tracks the progress of a migration: `pattern_coverage` counts the remaining occurrences of
+//! a set of patterns (the old API calls) and appends the counts to a named history, so that
+//! repeated calls show how many were removed since the migration started and since the last
+//! call.
+//!
+//! Each migration's snapshots are kept as JSON in `<coverage directory>/<name>.json`. A count
+//! runs under a time budget; one that doesn't finish in time is returned for inspection but
+//! never recorded, since partial counts would read as progress.
+
+use crate::errors::ServiceError;
+use crate::search::SearchService;
+use crate::types::{
+    CursorParam, FileSearchParam, PatternCoverageCount, PatternCoverageParam, PatternCoverageResult,
+};
+use ast_grep_language::SupportLang as Language;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Number of files searched per page while counting
+const FILES_PER_PAGE: usize = 200;
+
+/// Snapshots kept per migration; the oldest after the first are dropped beyond this
+const MAX_SNAPSHOTS: usize = 1000;
+
+/// Counts recorded by one `pattern_coverage` call
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageSnapshot {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Remaining occurrences keyed by pattern id
+    pub counts: BTreeMap<String, usize>,
+    pub total: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CoverageHistory {
+    snapshots: Vec<CoverageSnapshot>,
+}
+
+/// Count `param.patterns` under the roots and, when asked, record the counts in `directory`
+pub async fn pattern_coverage(
+    search_service: &SearchService,
+    directory: &Path,
+    param: PatternCoverageParam,
+) -> Result<PatternCoverageResult, ServiceError> {
+    let history_path = history_path(directory, &param.name)?;
+    if param.patterns.is_empty() {
+        return Err(ServiceError::Internal(
+            "pattern_coverage needs at least one pattern".to_string(),
+        ));
+    }
+    let mut ids = HashSet::new();
+    for pattern in &param.patterns {
+        if !ids.insert(pattern.id.as_str()) {
+            return Err(ServiceError::Internal(format!(
+                "Pattern id '{}' is used twice",
+                pattern.id
+            )));
+        }
+    }
+
+    let started = Instant::now();
+    let deadline = started + Duration::from_millis(param.timeout_ms);
+    let mut history = load_history(&history_path)?;
+    let mut counts = Vec::new();
+    let mut complete = true;
+    for pattern in &param.patterns {
+        let (remaining, files, finished) = count_pattern(
+            search_service,
+            &param,
+            &pattern.pattern,
+            &pattern.language,
+            deadline,
+        )
+        .await?;
+        complete &= finished;
+        counts.push(PatternCoverageCount {
+            id: pattern.id.clone(),
+            pattern: pattern.pattern.clone(),
+            language: pattern.language.clone(),
+            remaining,
+            files,
+            baseline: history
+                .snapshots
+                .iter()
+                .find_map(|snapshot| snapshot.counts.get(&pattern.id).copied()),
+            previous: history
+                .snapshots
+                .iter()
+                .rev()
+                .find_map(|snapshot| snapshot.counts.get(&pattern.id).copied()),
+        });
+        if !complete {
+            break;
+        }
+    }
+
+    let total_remaining = counts.iter().map(|count| count.remaining).sum();
+    let recorded = param.record && complete;
+    if recorded {
+        history.snapshots.push(CoverageSnapshot {
+            timestamp: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            counts: counts
+                .iter()
+                .map(|count| (count.id.clone(), count.remaining))
+                .collect(),
+            total: total_remaining,
+        });
+        if history.snapshots.len() > MAX_SNAPSHOTS {
+            // Keep the first snapshot, it is the baseline progress is measured against
+            let excess = history.snapshots.len() - MAX_SNAPSHOTS;
+            history.snapshots.drain(1..=excess);
+        }
+        save_history(&history_path, &history)?;
+    }
+
+    let skip = history.snapshots.len().saturating_sub(param.history_limit);
+    Ok(PatternCoverageResult {
+        name: param.name,
+        progress_percent: progress_percent(&counts),
+        patterns: counts,
+        total_remaining,
+        complete,
+        recorded,
+        duration_ms: started.elapsed().as_millis() as u64,
+        history: history.snapshots.split_off(skip),
+    })
+}
+
+/// Occurrences and files matching one pattern, and whether the count finished before
+/// `deadline`
+async fn count_pattern(
+    search_service: &SearchService,
+    param: &PatternCoverageParam,
+    pattern: &str,
+    language: &str,
+    deadline: Instant,
+) -> Result<(usize, usize, bool), ServiceError> {
+    let mut search_param = FileSearchParam {
+        path_pattern: param.path_pattern.clone(),
+        max_results: FILES_PER_PAGE,
+        max_file_size: param.max_file_size,
+        workspace: param.workspace.clone(),
+        ..Default::default()
+    };
+    // Built-in languages are counted only in files of that language, the way
+    // language_patterns picks files; the extra grammars already filter by extension
+    match Language::from_str(language) {
+        Ok(_) => {
+            search_param.language_patterns =
+                HashMap::from([(language.to_string(), pattern.to_string())]);
+        }
+        Err(_) => {
+            search_param.pattern = pattern.to_string();
+            search_param.language = language.to_string();
+        }
+    }
+
+    let (mut remaining, mut files) = (0, 0);
+    loop {
+        if Instant::now() >= deadline {
+            return Ok((remaining, files, false));
+        }
+        let result = search_service.file_search(search_param.clone()).await?;
+        files += result.matches.len();
+        remaining += result
+            .matches
+            .iter()
+            .map(|file| file.matches.len())
+            .sum::<usize>();
+        match result.next_cursor {
+            Some(cursor) if !cursor.is_complete => {
+                search_param.cursor = Some(CursorParam {
+                    last_file_path: cursor.last_file_path,
+                    is_complete: false,
+                    token: None,
+                });
+            }
+            _ => return Ok((remaining, files, true)),
+        }
+    }
+}
+
+/// Share of the baseline occurrences of the current patterns that are gone
+fn progress_percent(counts: &[PatternCoverageCount]) -> Option<f64> {
+    let (baseline, remaining) = counts
+        .iter()
+        .filter_map(|count| Some((count.baseline?, count.remaining)))
+        .fold((0, 0), |(baseline, remaining), (b, r)| {
+            (baseline + b, remaining + r)
+        });
+    (baseline > 0).then(|| (baseline as f64 - remaining as f64) * 100.0 / baseline as f64)
+}
+
+/// File holding the snapshots of migration `name`
+fn history_path(directory: &Path, name: &str) -> Result<PathBuf, ServiceError> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(ServiceError::Internal(format!(
+            "Invalid migration name '{name}'; use letters, digits, '-', '_' and '.'"
+        )));
+    }
+    Ok(directory.join(format!("{name}.json")))
+}
+
+fn load_history(path: &Path) -> Result<CoverageHistory, ServiceError> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Default::default()),
+        Err(e) => return Err(io_error(e, path)),
+    };
+    serde_json::from_str(&content).map_err(|e| {
+        ServiceError::Internal(format!(
+            "Could not read coverage snapshots from {}: {e}",
+            path.display()
+        ))
+    })
+}
+
+/// Write the history through a temporary file so a crash never leaves it half written
+fn save_history(path: &Path, history: &CoverageHistory) -> Result<(), ServiceError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| io_error(e, parent))?;
+    }
+    let temp_path = path.with_extension("json.tmp");
+    let content = serde_json::to_string_pretty(history)?;
+    std::fs::write(&temp_path, content).map_err(|e| io_error(e, &temp_path))?;
+    std::fs::rename(&temp_path, path).map_err(|e| io_error(e, path))
+}
+
+fn io_error(e: std::io::Error, path: &Path) -> ServiceError {
+    ServiceError::FileIoError {
+        message: e.to_string(),
+        path: path.display().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_path_rejects_traversal() {
+        let directory = Path::new("/tmp/coverage");
+        assert_eq!(
+            history_path(directory, "react-18").unwrap(),
+            directory.join("react-18.json")
+        );
+        assert!(history_path(directory, "../escape").is_err());
+        assert!(history_path(directory, ".hidden").is_err());
+        assert!(history_path(directory, "").is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! # Pagination Cursors
+//!
+//! A cursor names the last file of a page, and the next page starts after it in path order. A
+//! cursor passed to a call it was not issued for pages through another file list without any
+//! error: the files of the new query sorting before it are silently left out. So every cursor
+//! for another page is stamped with a token recording the call that issued it:
+//!
+//! - a hash of the call's parameters, leaving out those that only shape a page, such as
+//!   `max_results` or `dry_run`, and of the server's root directories
+//! - the time it was issued, so a cursor older than `--cursor-ttl-secs` is refused
+//!
+//! A cursor whose token does not match fails with [`ServiceError::StaleCursor`], reported to
+//! clients with the `STALE_CURSOR` code, and pagination must start over.
+//!
+//! The token is a staleness check, not a signature. It is an unkeyed checksum any client can
+//! compute, and cursors without a token are still accepted as long as they name a file below
+//! the root directories. It catches a cursor reused by mistake but does not stop a client from
+//! forging one, which gains nothing: a cursor only picks where a page starts among files the
+//! client may search anyway. Being unkeyed, it also stays valid when the server restarts.
+
+use crate::errors::ServiceError;
+use crate::types::{CursorParam, CursorResult};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Parameters that may change from one page to the next
+const PAGE_ONLY: &[&str] = &[
+    "cursor",
+    "max_results",
+    "dry_run",
+    "summary_only",
+    "include_samples",
+    "max_samples",
+    "output_file",
+    "output_format",
+    "stream",
+    "timeout_ms",
+];
+
+/// What a cursor is bound to: the parameters and root directories of the call issuing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorScope {
+    digest: String,
+    roots: Vec<PathBuf>,
+}
+
+impl CursorScope {
+    /// The scope of a call with `param` over `roots`
+    pub fn new<P: Serialize>(param: &P, roots: &[PathBuf]) -> Self {
+        let mut hasher = Sha256::new();
+        if let Ok(Value::Object(mut fields)) = serde_json::to_value(param) {
+            for field in PAGE_ONLY {
+                fields.remove(*field);
+            }
+            hash_value(&mut hasher, &Value::Object(fields));
+        }
+        for root in roots {
+            hasher.update(root.to_string_lossy().as_bytes());
+            hasher.update([0]);
+        }
+        Self {
+            digest: hex::encode(hasher.finalize()),
+            roots: roots.to_vec(),
+        }
+    }
+
+    /// Stamp `cursor` with a token for this scope if it leads to another page
+    pub fn stamp(&self, cursor: Option<&mut CursorResult>) {
+        if let Some(cursor) = cursor.filter(|cursor| !cursor.is_complete) {
+            let issued_at = now_secs();
+            cursor.token = Some(format!(
+                "{issued_at}.{}",
+                self.checksum(&cursor.last_file_path, issued_at)
+            ));
+        }
+    }
+
+    /// Check that `cursor` was issued by a call in this scope less than `ttl` ago
+    pub fn check(&self, cursor: Option<&CursorParam>, ttl: Duration) -> Result<(), ServiceError> {
+        let Some(cursor) = cursor.filter(|cursor| !cursor.is_complete) else {
+            return Ok(());
+        };
+        let Some(token) = &cursor.token else {
+            return match self.roots.is_empty() || self.contains(&cursor.last_file_path) {
+                true => Ok(()),
+                false => Err(ServiceError::StaleCursor(format!(
+                    "{} is not below the root directories",
+                    cursor.last_file_path
+                ))),
+            };
+        };
+
+        let (issued_at, checksum) = token
+            .split_once('.')
+            .and_then(|(issued_at, checksum)| Some((issued_at.parse().ok()?, checksum)))
+            .ok_or_else(|| ServiceError::StaleCursor("the cursor token is malformed".into()))?;
+        if checksum != self.checksum(&cursor.last_file_path, issued_at) {
+            return Err(ServiceError::StaleCursor(
+                "the cursor was issued for other parameters or root directories, or was altered"
+                    .into(),
+            ));
+        }
+        let age = now_secs().saturating_sub(issued_at);
+        if age >= ttl.as_secs() {
+            return Err(ServiceError::StaleCursor(format!(
+                "the cursor expired {}s after it was issued",
+                ttl.as_secs()
+            )));
+        }
+        Ok(())
+    }
+
+    fn checksum(&self, last_file_path: &str, issued_at: u64) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.digest.as_bytes());
+        hasher.update([0]);
+        hasher.update(last_file_path.as_bytes());
+        hasher.update([0]);
+        hasher.update(issued_at.to_be_bytes());
+        hex::encode(&hasher.finalize()[..16])
+    }
+
+    fn contains(&self, path: &str) -> bool {
+        let path = Path::new(path);
+        path.is_relative() || self.roots.iter().any(|root| path.starts_with(root))
+    }
+}
+
+/// Feed `value` to `hasher` with object keys in sorted order, so maps hash the same whatever
+/// order they were built in
+fn hash_value(hasher: &mut Sha256, value: &Value) {
+    match value {
+        Value::Object(fields) => {
+            let mut keys: Vec<&String> = fields.keys().collect();
+            keys.sort();
+            hasher.update(b"{");
+            for key in keys {
+                hasher.update(key.as_bytes());
+                hasher.update(b":");
+                hash_value(hasher, &fields[key]);
+                hasher.update(b",");
+            }
+            hasher.update(b"}");
+        }
+        Value::Array(items) => {
+            hasher.update(b"[");
+            for item in items {
+                hash_value(hasher, item);
+                hasher.update(b",");
+            }
+            hasher.update(b"]");
+        }
+        scalar => hasher.update(scalar.to_string().as_bytes()),
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
@@ -0,0 +1,212 @@
+//! # Duplicate Code
+//!
+//! Finds functions and blocks that were copied between or within files. Every function, method,
+//! closure and block spanning at least `min_lines` is reduced to its shape: its leaf tokens in
+//! order, comments left out and, unless asked to keep them, identifiers and literals replaced by
+//! placeholders. Units of the same language whose shapes hash the same form a clone group.
+//!
+//! A clone's body is a clone too, so groups whose every copy sits inside a larger reported clone
+//! are left out. Each group is scored by how many tokens its copies share verbatim, so copies
+//! that kept the original names score 1 and renamed ones less.
+
+use crate::directory_summary::source_files;
+use crate::similar_code::{is_comment, is_identifier, is_literal, similarity, tokens};
+use crate::types::{CloneGroup, CloneInstance, DedupeCodeParam, DedupeCodeResult, SkippedFile};
+use ast_grep_core::Node;
+use ast_grep_core::tree_sitter::{LanguageExt, StrDoc};
+use ast_grep_language::SupportLang as Language;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// A function or block large enough to report
+struct Unit {
+    file_path: String,
+    range: Range<usize>,
+    start_line: usize,
+    end_line: usize,
+    kind: String,
+    /// Length of the normalized shape
+    shape_len: usize,
+    /// Leaf texts as written, for scoring
+    tokens: Vec<String>,
+}
+
+/// Clone groups among the functions and blocks under `directories`, the most duplicated code
+/// first. Only files in `lang` are read when it is given
+pub fn dedupe_code(
+    directories: &[PathBuf],
+    param: &DedupeCodeParam,
+    lang: Option<Language>,
+) -> DedupeCodeResult {
+    let mut units: HashMap<(Language, u64), Vec<Unit>> = HashMap::new();
+    let mut files_scanned = 0;
+    let mut skipped_files = Vec::new();
+    for (path, file_lang) in source_files(directories) {
+        if lang.is_some_and(|lang| lang != file_lang) {
+            continue;
+        }
+        let file_path = path.to_string_lossy().to_string();
+        let too_large = std::fs::metadata(&path)
+            .map(|metadata| metadata.len() > param.max_file_size)
+            .unwrap_or(false);
+        if too_large {
+            skipped_files.push(SkippedFile {
+                file_path,
+                reason: format!("larger than {} bytes", param.max_file_size),
+            });
+            continue;
+        }
+        let code = match std::fs::read_to_string(&path) {
+            Ok(code) => code,
+            Err(e) => {
+                skipped_files.push(SkippedFile {
+                    file_path,
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        files_scanned += 1;
+        let ast = file_lang.ast_grep(&code);
+        for node in ast.root().dfs() {
+            if !is_unit(&node.kind()) {
+                continue;
+            }
+            let start_line = node.start_pos().line();
+            let end_line = node.end_pos().line();
+            if end_line - start_line + 1 < param.min_lines {
+                continue;
+            }
+            let mut shape = Vec::new();
+            collect_shape(&node, param, &mut shape);
+            if shape.len() < param.min_tokens {
+                continue;
+            }
+            let mut hasher = DefaultHasher::new();
+            shape.hash(&mut hasher);
+            units
+                .entry((file_lang, hasher.finish()))
+                .or_default()
+                .push(Unit {
+                    file_path: file_path.clone(),
+                    range: node.range(),
+                    start_line,
+                    end_line,
+                    kind: node.kind().to_string(),
+                    shape_len: shape.len(),
+                    tokens: tokens(&node),
+                });
+        }
+    }
+
+    // Largest clones first, so the copies of their bodies can be recognized and left out
+    let mut candidates: Vec<((Language, u64), Vec<Unit>)> = units
+        .into_iter()
+        .filter(|(_, copies)| copies.len() > 1)
+        .collect();
+    candidates.sort_by(|(_, a), (_, b)| {
+        b[0].shape_len.cmp(&a[0].shape_len).then_with(|| {
+            (&a[0].file_path, a[0].start_line).cmp(&(&b[0].file_path, b[0].start_line))
+        })
+    });
+    let mut reported: HashMap<String, Vec<Range<usize>>> = HashMap::new();
+    let mut groups = Vec::new();
+    for ((group_lang, _), copies) in candidates {
+        let copies: Vec<Unit> = copies
+            .into_iter()
+            .filter(|unit| {
+                !reported.get(&unit.file_path).is_some_and(|ranges| {
+                    ranges
+                        .iter()
+                        .any(|range| range.start <= unit.range.start && unit.range.end <= range.end)
+                })
+            })
+            .collect();
+        if copies.len() < 2 {
+            continue;
+        }
+        for unit in &copies {
+            reported
+                .entry(unit.file_path.clone())
+                .or_default()
+                .push(unit.range.clone());
+        }
+        let first = &copies[0];
+        let score = copies[1..]
+            .iter()
+            .map(|unit| similarity(&first.tokens, &unit.tokens))
+            .fold(1.0, f64::min);
+        groups.push(CloneGroup {
+            language: group_lang.to_string(),
+            tokens: first.shape_len,
+            lines: first.end_line - first.start_line + 1,
+            duplicated_lines: copies[1..]
+                .iter()
+                .map(|unit| unit.end_line - unit.start_line + 1)
+                .sum(),
+            similarity: score,
+            instances: copies
+                .into_iter()
+                .map(|unit| CloneInstance {
+                    file_path: unit.file_path,
+                    start_line: unit.start_line,
+                    end_line: unit.end_line,
+                    kind: unit.kind,
+                })
+                .collect(),
+        });
+    }
+
+    // Groups were found largest first, so ties stay ordered by size
+    groups.sort_by(|a, b| {
+        (b.tokens * (b.instances.len() - 1)).cmp(&(a.tokens * (a.instances.len() - 1)))
+    });
+    let total_groups = groups.len();
+    let duplicated_lines = groups.iter().map(|group| group.duplicated_lines).sum();
+    let truncated = total_groups > param.max_groups;
+    groups.truncate(param.max_groups);
+    DedupeCodeResult {
+        groups,
+        total_groups,
+        duplicated_lines,
+        files_scanned,
+        truncated,
+        skipped_files,
+    }
+}
+
+/// Whether nodes of `kind` are compared: functions, methods, closures and blocks
+fn is_unit(kind: &str) -> bool {
+    kind.contains("function")
+        || kind.contains("method")
+        || kind.contains("lambda")
+        || kind.contains("closure")
+        || kind == "func_literal"
+        || kind == "block"
+        || kind.ends_with("_block")
+        || kind == "compound_statement"
+        || kind == "constructor_body"
+}
+
+/// The normalized leaf tokens of `node`'s descendants, in source order
+fn collect_shape(node: &Node<StrDoc<Language>>, param: &DedupeCodeParam, shape: &mut Vec<String>) {
+    for child in node.children() {
+        if is_comment(&child) {
+            continue;
+        }
+        if param.ignore_identifiers && is_identifier(&child) {
+            shape.push("$ID".to_string());
+        } else if param.ignore_literals && is_literal(&child) {
+            shape.push("$LIT".to_string());
+        } else if child.is_leaf() {
+            if !child.text().is_empty() {
+                shape.push(child.text().to_string());
+            }
+        } else {
+            collect_shape(&child, param, shape);
+        }
+    }
+}
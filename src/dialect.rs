@@ -0,0 +1,263 @@
+//! # Language Dialects
+//!
+//! JavaScript and TypeScript come in several grammars. The TypeScript parser rejects JSX, the
+//! TSX parser rejects `<T>value` casts, and the JavaScript parser rejects type annotations.
+//! Decorators and JSX-in-JavaScript need nothing special: the bundled grammars accept them.
+//!
+//! Callers pick a grammar explicitly with a `dialect` parameter, or leave it unset and let
+//! file-based searches choose per file from the extension, so `language: "typescript"` over
+//! `src/**/*.{ts,tsx}` parses `.tsx` files with the TSX grammar.
+//!
+//! Polyglot searches pass `language_patterns` instead, one pattern per language, and each
+//! file is matched with the entry for the language its extension implies.
+//!
+//! Searches that leave `language` empty or set it to `auto` detect each file's language from
+//! its extension, or from the interpreter of its `#!` line when the extension names none.
+
+use crate::errors::ServiceError;
+use crate::extra_languages::AnyLanguage;
+use ast_grep_core::Language as _;
+use ast_grep_language::SupportLang as Language;
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Languages whose dialects are interchangeable
+const ECMASCRIPT: &[Language] = &[Language::JavaScript, Language::TypeScript, Language::Tsx];
+
+/// Resolve `language`, switching to `dialect` when one is given
+pub fn resolve_language(language: &str, dialect: Option<&str>) -> Result<Language, ServiceError> {
+    let lang = Language::from_str(language)
+        .map_err(|_| ServiceError::Internal("Failed to parse language".to_string()))?;
+    let Some(dialect) = dialect else {
+        return Ok(lang);
+    };
+
+    let variant = Language::from_str(dialect)
+        .map_err(|_| ServiceError::ParserError(format!("Unknown dialect '{dialect}'")))?;
+    if variant == lang || (ECMASCRIPT.contains(&lang) && ECMASCRIPT.contains(&variant)) {
+        Ok(variant)
+    } else {
+        Err(ServiceError::ParserError(format!(
+            "Dialect '{dialect}' does not apply to {language}"
+        )))
+    }
+}
+
+/// The grammar to parse `file_path` with when the caller asked for `lang` without a dialect.
+///
+/// Files with a TypeScript extension get the grammar the extension implies; everything else
+/// keeps `lang`
+pub fn language_for_file(lang: Language, file_path: &str) -> Language {
+    if !ECMASCRIPT.contains(&lang) {
+        return lang;
+    }
+    match Language::from_path(Path::new(file_path)) {
+        Some(variant @ (Language::TypeScript | Language::Tsx)) => variant,
+        _ => lang,
+    }
+}
+
+/// A `language_patterns` entry: the pattern to match files of `language` with
+#[derive(Debug, Clone)]
+pub struct LanguagePattern {
+    /// The key as the caller wrote it, reported back on matches
+    pub key: String,
+    pub language: Language,
+    pub pattern: String,
+}
+
+impl LanguagePattern {
+    /// The entry to match `file_path` with, and the grammar to parse it with.
+    ///
+    /// An entry for the file's own language wins; otherwise an ECMAScript entry covers the
+    /// extensions `language_for_file` maps it to, so a lone `javascript` pattern also
+    /// searches `.ts` files
+    pub fn for_file<'a>(
+        patterns: &'a [LanguagePattern],
+        file_path: &str,
+    ) -> Option<(Language, &'a LanguagePattern)> {
+        let file_lang = Language::from_path(Path::new(file_path))?;
+        patterns
+            .iter()
+            .find(|entry| entry.language == file_lang)
+            .or_else(|| {
+                patterns
+                    .iter()
+                    .find(|entry| language_for_file(entry.language, file_path) == file_lang)
+            })
+            .map(|entry| (file_lang, entry))
+    }
+}
+
+/// Whether `language` asks for each file's language to be detected
+pub fn is_auto_language(language: &str) -> bool {
+    language.is_empty() || language.eq_ignore_ascii_case("auto")
+}
+
+/// The language of `file_path` by its extension, or by the interpreter its `#!` line runs
+pub fn detect_language(file_path: &str, content: &str) -> Option<AnyLanguage> {
+    AnyLanguage::from_path(Path::new(file_path))
+        .or_else(|| shebang_language(content).map(AnyLanguage::Builtin))
+}
+
+/// The language of the interpreter named by the `#!` line starting `content`, looking past
+/// `env` and its options
+fn shebang_language(content: &str) -> Option<Language> {
+    let line = content.strip_prefix("#!")?.lines().next()?;
+    let mut words = line.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        interpreter = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+    }
+    // python3.12 runs Python just like python
+    let name = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    Some(match name {
+        "sh" | "bash" | "dash" | "ksh" | "zsh" => Language::Bash,
+        "node" | "nodejs" => Language::JavaScript,
+        "deno" | "bun" | "ts-node" | "tsx" => Language::TypeScript,
+        "python" | "pypy" => Language::Python,
+        "ruby" => Language::Ruby,
+        "lua" | "luajit" => Language::Lua,
+        "php" => Language::Php,
+        "elixir" => Language::Elixir,
+        "runghc" | "runhaskell" => Language::Haskell,
+        "scala" => Language::Scala,
+        "swift" => Language::Swift,
+        "kotlin" => Language::Kotlin,
+        _ => return None,
+    })
+}
+
+/// Parse a `language_patterns` map, ordered by key so results are deterministic
+pub fn resolve_language_patterns(
+    patterns: &HashMap<String, String>,
+) -> Result<Vec<LanguagePattern>, ServiceError> {
+    let mut resolved = patterns
+        .iter()
+        .map(|(key, pattern)| {
+            let language = Language::from_str(key).map_err(|_| {
+                ServiceError::ParserError(format!("Unknown language '{key}' in language_patterns"))
+            })?;
+            Ok(LanguagePattern {
+                key: key.clone(),
+                language,
+                pattern: pattern.clone(),
+            })
+        })
+        .collect::<Result<Vec<_>, ServiceError>>()?;
+    resolved.sort_by(|a, b| a.key.cmp(&b.key));
+
+    for (i, entry) in resolved.iter().enumerate() {
+        if let Some(other) = resolved[i + 1..]
+            .iter()
+            .find(|other| other.language == entry.language)
+        {
+            return Err(ServiceError::ParserError(format!(
+                "language_patterns lists {} twice ('{}' and '{}')",
+                entry.language, entry.key, other.key
+            )));
+        }
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_language_with_dialect() {
+        assert_eq!(
+            resolve_language("typescript", None).unwrap(),
+            Language::TypeScript
+        );
+        assert_eq!(
+            resolve_language("typescript", Some("tsx")).unwrap(),
+            Language::Tsx
+        );
+        assert_eq!(
+            resolve_language("javascript", Some("jsx")).unwrap(),
+            Language::JavaScript
+        );
+        assert_eq!(
+            resolve_language("rust", Some("rs")).unwrap(),
+            Language::Rust
+        );
+        assert!(resolve_language("python", Some("tsx")).is_err());
+        assert!(resolve_language("typescript", Some("flow")).is_err());
+    }
+
+    #[test]
+    fn test_language_for_file_uses_extension() {
+        assert_eq!(
+            language_for_file(Language::TypeScript, "src/App.tsx"),
+            Language::Tsx
+        );
+        assert_eq!(
+            language_for_file(Language::Tsx, "src/util.mts"),
+            Language::TypeScript
+        );
+        assert_eq!(
+            language_for_file(Language::TypeScript, "src/legacy.js"),
+            Language::TypeScript
+        );
+        assert_eq!(
+            language_for_file(Language::JavaScript, "src/App.jsx"),
+            Language::JavaScript
+        );
+        assert_eq!(
+            language_for_file(Language::Python, "weird.tsx"),
+            Language::Python
+        );
+    }
+
+    #[test]
+    fn test_language_pattern_for_file() {
+        let patterns = resolve_language_patterns(&HashMap::from([
+            ("py".to_string(), "print($A)".to_string()),
+            ("javascript".to_string(), "console.log($A)".to_string()),
+        ]))
+        .unwrap();
+
+        let (lang, entry) = LanguagePattern::for_file(&patterns, "app/main.py").unwrap();
+        assert_eq!((lang, entry.key.as_str()), (Language::Python, "py"));
+        let (lang, entry) = LanguagePattern::for_file(&patterns, "web/App.tsx").unwrap();
+        assert_eq!((lang, entry.key.as_str()), (Language::Tsx, "javascript"));
+        assert!(LanguagePattern::for_file(&patterns, "src/lib.rs").is_none());
+        assert!(LanguagePattern::for_file(&patterns, "README").is_none());
+    }
+
+    #[test]
+    fn test_detect_language_from_extension_and_shebang() {
+        let detect =
+            |file_path, content| detect_language(file_path, content).map(|l| l.to_string());
+        assert_eq!(detect("src/app.ts", "").as_deref(), Some("TypeScript"));
+        assert_eq!(
+            detect("bin/deploy", "#!/usr/bin/env python3.12\n").as_deref(),
+            Some("Python")
+        );
+        assert_eq!(
+            detect("bin/run", "#!/usr/bin/env -S node --no-warnings\n").as_deref(),
+            Some("JavaScript")
+        );
+        assert_eq!(
+            detect("bin/setup", "#!/bin/sh -e\n").as_deref(),
+            Some("Bash")
+        );
+        assert_eq!(detect("README", "# Title\n"), None);
+        assert_eq!(detect("bin/tool", "#!/usr/bin/perl\n"), None);
+    }
+
+    #[test]
+    fn test_resolve_language_patterns_rejects_bad_keys() {
+        let unknown = HashMap::from([("cobol".to_string(), "$X".to_string())]);
+        assert!(resolve_language_patterns(&unknown).is_err());
+
+        let duplicate = HashMap::from([
+            ("js".to_string(), "$X".to_string()),
+            ("javascript".to_string(), "$Y".to_string()),
+        ]);
+        assert!(resolve_language_patterns(&duplicate).is_err());
+    }
+}
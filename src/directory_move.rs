@@ -0,0 +1,461 @@
+//! # Directory Moves
+//!
+//! Moving a directory breaks every relative import that crosses its boundary: imports of the
+//! moved files from outside, and imports of outside files from the moved files. Imports between
+//! two moved files keep working because both ends move together.
+//!
+//! [`plan_directory_move`] finds the affected import paths and computes their new spelling
+//! without touching anything; the resulting [`EditPlan`] can be reviewed and then handed to
+//! [`apply_plan`], which rewrites the imports and moves the files as one step:
+//!
+//! - the plan is checked against the files first, so a file edited since planning fails the
+//!   whole plan before anything is written
+//! - when a write or move fails halfway, the steps already taken are undone
+//!
+//! Import paths are recognized in JavaScript and TypeScript (`import`, `export ... from`,
+//! `require()`, `import()` and `import x = require()` with a `./` or `../` specifier) and in
+//! C and C++ (`#include "..."` that resolves next to the including file). Bare module names,
+//! path aliases and include search paths are left alone.
+
+use crate::directory_summary::source_files;
+use crate::errors::ServiceError;
+use crate::path_validation::{validate_creatable_path, validate_path_within_roots};
+use crate::types::{
+    ApplyPlanResult, EditPlan, PlannedFileEdit, PlannedImportEdit, PlannedMove, SkippedFile,
+};
+use ast_grep_core::AstGrep;
+use ast_grep_language::SupportLang as Language;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// How an import path is resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathStyle {
+    /// JavaScript module specifier, which must start with `./` or `../` to be relative
+    Module,
+    /// C include path, relative to the including file without a prefix
+    Include,
+}
+
+/// An import path found in a file; the byte range covers the path without its quotes
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ImportPath {
+    specifier: String,
+    start_byte: usize,
+    end_byte: usize,
+    start_line: usize,
+    style: PathStyle,
+}
+
+/// Relative import paths in `code`, in source order
+fn import_paths(code: &str, lang: Language) -> Vec<ImportPath> {
+    let style = match lang {
+        Language::JavaScript | Language::TypeScript | Language::Tsx => PathStyle::Module,
+        Language::C | Language::Cpp => PathStyle::Include,
+        _ => return Vec::new(),
+    };
+    let ast = AstGrep::new(code, lang);
+    ast.root()
+        .dfs()
+        .filter_map(|node| {
+            let path = match style {
+                PathStyle::Module => {
+                    if node.kind() != "string" {
+                        return None;
+                    }
+                    let parent = node.parent()?;
+                    let is_import = match parent.kind().as_ref() {
+                        "import_statement" | "export_statement" | "import_require_clause" => true,
+                        "arguments" => parent
+                            .parent()
+                            .filter(|call| call.kind() == "call_expression")
+                            .and_then(|call| call.field("function"))
+                            .is_some_and(|function| {
+                                function.kind() == "import" || function.text() == "require"
+                            }),
+                        _ => false,
+                    };
+                    if !is_import {
+                        return None;
+                    }
+                    node
+                }
+                PathStyle::Include => {
+                    if node.kind() != "preproc_include" {
+                        return None;
+                    }
+                    node.field("path")
+                        .filter(|path| path.kind() == "string_literal")?
+                }
+            };
+            let text = path.text();
+            let range = path.range();
+            if text.len() < 2 {
+                return None;
+            }
+            let specifier = text[1..text.len() - 1].to_string();
+            if style == PathStyle::Module
+                && !(specifier.starts_with("./")
+                    || specifier.starts_with("../")
+                    || specifier == "."
+                    || specifier == "..")
+            {
+                return None;
+            }
+            Some(ImportPath {
+                specifier,
+                start_byte: range.start + 1,
+                end_byte: range.end - 1,
+                start_line: path.start_pos().line(),
+                style,
+            })
+        })
+        .collect()
+}
+
+/// `path` with `.` and `..` resolved without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Where `path` ends up when `source` moves to `destination`
+fn moved_path(path: &Path, source: &Path, destination: &Path) -> PathBuf {
+    match path.strip_prefix(source) {
+        Ok(rest) => destination.join(rest),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Spell the path from `from_dir` to `target` the way `original` was spelled
+fn relative_specifier(from_dir: &Path, target: &Path, original: &str, style: PathStyle) -> String {
+    let from: Vec<Component> = from_dir.components().collect();
+    let to: Vec<Component> = target.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<String> = vec!["..".to_string(); from.len() - common];
+    parts.extend(
+        to[common..]
+            .iter()
+            .map(|part| part.as_os_str().to_string_lossy().to_string()),
+    );
+
+    let mut specifier = parts.join("/");
+    if specifier.is_empty() {
+        specifier = ".".to_string();
+    } else if !specifier.starts_with("..")
+        && (style == PathStyle::Module || original.starts_with("./"))
+    {
+        specifier = format!("./{specifier}");
+    }
+    if original.ends_with('/') && !specifier.ends_with('/') {
+        specifier.push('/');
+    }
+    specifier
+}
+
+/// Plan moving the directory `source` to `destination`: every file under `source` and every
+/// import path under `roots` that the move would break. Nothing is written
+pub fn plan_directory_move(
+    roots: &[PathBuf],
+    source: &Path,
+    destination: &Path,
+    max_file_size: u64,
+) -> Result<EditPlan, ServiceError> {
+    let mut moves = Vec::new();
+    for entry in WalkDir::new(source).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            moves.push(PlannedMove {
+                from: entry.path().to_string_lossy().to_string(),
+                to: moved_path(entry.path(), source, destination)
+                    .to_string_lossy()
+                    .to_string(),
+            });
+        }
+    }
+
+    let mut edits = Vec::new();
+    let mut skipped_files = Vec::new();
+    for (path, lang) in source_files(roots) {
+        if !matches!(
+            lang,
+            Language::JavaScript
+                | Language::TypeScript
+                | Language::Tsx
+                | Language::C
+                | Language::Cpp
+        ) {
+            continue;
+        }
+        let file_path = path.to_string_lossy().to_string();
+        let too_large = std::fs::metadata(&path)
+            .map(|metadata| metadata.len() > max_file_size)
+            .unwrap_or(false);
+        if too_large {
+            skipped_files.push(SkippedFile {
+                file_path,
+                reason: format!("larger than {max_file_size} bytes"),
+            });
+            continue;
+        }
+        let code = match std::fs::read_to_string(&path) {
+            Ok(code) => code,
+            Err(e) => {
+                skipped_files.push(SkippedFile {
+                    file_path,
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let Some(directory) = path.parent() else {
+            continue;
+        };
+        let new_directory = moved_path(directory, source, destination);
+        let changes: Vec<PlannedImportEdit> = import_paths(&code, lang)
+            .into_iter()
+            .filter_map(|import| {
+                let unresolved = directory.join(&import.specifier);
+                // Includes that are not next to the file come from the include search path
+                if import.style == PathStyle::Include && !unresolved.exists() {
+                    return None;
+                }
+                let target = normalize(&unresolved);
+                if !path.starts_with(source) && !target.starts_with(source) {
+                    return None;
+                }
+                let new_specifier = relative_specifier(
+                    &new_directory,
+                    &moved_path(&target, source, destination),
+                    &import.specifier,
+                    import.style,
+                );
+                (new_specifier != import.specifier).then_some(PlannedImportEdit {
+                    start_line: import.start_line,
+                    start_byte: import.start_byte,
+                    end_byte: import.end_byte,
+                    old_text: import.specifier,
+                    new_text: new_specifier,
+                })
+            })
+            .collect();
+        if !changes.is_empty() {
+            edits.push(PlannedFileEdit { file_path, changes });
+        }
+    }
+
+    Ok(EditPlan {
+        source: source.to_string_lossy().to_string(),
+        destination: destination.to_string_lossy().to_string(),
+        moves,
+        edits,
+        skipped_files,
+    })
+}
+
+/// Steps taken by [`apply_plan`], kept so they can be undone
+#[derive(Default)]
+struct Journal {
+    written: Vec<(PathBuf, String)>,
+    moved: Vec<(PathBuf, PathBuf)>,
+    created_directories: Vec<PathBuf>,
+}
+
+impl Journal {
+    /// Undo every step in reverse order, continuing past failures so as much as possible is
+    /// restored
+    fn roll_back(self) {
+        for (from, to) in self.moved.iter().rev() {
+            let _ = std::fs::rename(to, from);
+        }
+        for (path, original) in self.written.iter().rev() {
+            let _ = std::fs::write(path, original);
+        }
+        for directory in self.created_directories.iter().rev() {
+            let _ = std::fs::remove_dir(directory);
+        }
+    }
+}
+
+/// Execute a plan from [`plan_directory_move`]: rewrite the import paths, then move the files.
+/// Nothing is written unless the whole plan still matches the files, and a failure partway
+/// through undoes the steps already taken
+pub fn apply_plan(roots: &[PathBuf], plan: &EditPlan) -> Result<ApplyPlanResult, ServiceError> {
+    let stale = |path: &str, reason: String| {
+        ServiceError::Internal(format!(
+            "Plan no longer applies to {path}: {reason}; run plan_directory_move again"
+        ))
+    };
+
+    let source = validate_path_within_roots(Path::new(&plan.source), roots)?;
+    let mut rewritten = Vec::new();
+    for edit in &plan.edits {
+        let path = validate_path_within_roots(Path::new(&edit.file_path), roots)?;
+        let original = std::fs::read_to_string(&path).map_err(|e| io_error(e, &path))?;
+        let mut changes: Vec<&PlannedImportEdit> = edit.changes.iter().collect();
+        changes.sort_by_key(|change| change.start_byte);
+        let mut content = String::with_capacity(original.len());
+        let mut position = 0;
+        for change in changes {
+            if change.start_byte < position
+                || original.get(change.start_byte..change.end_byte) != Some(&change.old_text)
+            {
+                return Err(stale(
+                    &edit.file_path,
+                    format!(
+                        "expected '{}' on line {}",
+                        change.old_text,
+                        change.start_line + 1
+                    ),
+                ));
+            }
+            content.push_str(&original[position..change.start_byte]);
+            content.push_str(&change.new_text);
+            position = change.end_byte;
+        }
+        content.push_str(&original[position..]);
+        rewritten.push((path, original, content));
+    }
+
+    let mut moves = Vec::new();
+    for planned in &plan.moves {
+        let from = validate_path_within_roots(Path::new(&planned.from), roots)?;
+        let to = validate_path_within_roots(Path::new(&planned.to), roots)?;
+        // The plan comes from the client, so a destination reached through a symlink must not
+        // lead outside the roots
+        validate_creatable_path(&to, roots)?;
+        if !from.is_file() {
+            return Err(stale(&planned.from, "the file is gone".to_string()));
+        }
+        if to.exists() {
+            return Err(stale(&planned.to, "the destination exists".to_string()));
+        }
+        moves.push((from, to));
+    }
+
+    let mut journal = Journal::default();
+    if let Err(e) = execute(&rewritten, &moves, &mut journal) {
+        journal.roll_back();
+        return Err(e);
+    }
+
+    // Directories emptied by the move, deepest first
+    let mut removed_directories = Vec::new();
+    for entry in WalkDir::new(&source)
+        .contents_first(true)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_dir())
+    {
+        if std::fs::remove_dir(entry.path()).is_ok() {
+            removed_directories.push(entry.path().to_string_lossy().to_string());
+        }
+    }
+
+    Ok(ApplyPlanResult {
+        source: plan.source.clone(),
+        destination: plan.destination.clone(),
+        files_edited: rewritten.len(),
+        total_changes: plan.edits.iter().map(|edit| edit.changes.len()).sum(),
+        files_moved: moves.len(),
+        removed_directories,
+    })
+}
+
+fn execute(
+    rewritten: &[(PathBuf, String, String)],
+    moves: &[(PathBuf, PathBuf)],
+    journal: &mut Journal,
+) -> Result<(), ServiceError> {
+    for (path, original, content) in rewritten {
+        std::fs::write(path, content).map_err(|e| io_error(e, path))?;
+        journal.written.push((path.clone(), original.clone()));
+    }
+    for (from, to) in moves {
+        if let Some(parent) = to.parent() {
+            let missing: Vec<PathBuf> = parent
+                .ancestors()
+                .take_while(|ancestor| !ancestor.exists())
+                .map(Path::to_path_buf)
+                .collect();
+            std::fs::create_dir_all(parent).map_err(|e| io_error(e, parent))?;
+            journal
+                .created_directories
+                .extend(missing.into_iter().rev());
+        }
+        std::fs::rename(from, to).map_err(|e| io_error(e, from))?;
+        journal.moved.push((from.clone(), to.clone()));
+    }
+    Ok(())
+}
+
+fn io_error(e: std::io::Error, path: &Path) -> ServiceError {
+    ServiceError::FileIoError {
+        message: e.to_string(),
+        path: path.display().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_paths_only_relative_module_specifiers() {
+        let code = "import a from './a';\nimport b from 'b';\nexport * from \"../c\";\nconst d = require('./d');\nconst e = import('./e');\nconst f = './f';\n";
+        let specifiers: Vec<String> = import_paths(code, Language::TypeScript)
+            .into_iter()
+            .map(|import| import.specifier)
+            .collect();
+        assert_eq!(specifiers, vec!["./a", "../c", "./d", "./e"]);
+
+        let import = &import_paths(code, Language::TypeScript)[0];
+        assert_eq!(&code[import.start_byte..import.end_byte], "./a");
+    }
+
+    #[test]
+    fn test_relative_specifier_keeps_style() {
+        let from = Path::new("/r/src/app");
+        assert_eq!(
+            relative_specifier(
+                from,
+                Path::new("/r/src/app/util"),
+                "../app/util",
+                PathStyle::Module
+            ),
+            "./util"
+        );
+        assert_eq!(
+            relative_specifier(from, Path::new("/r/lib/x"), "./x", PathStyle::Module),
+            "../../lib/x"
+        );
+        assert_eq!(
+            relative_specifier(
+                from,
+                Path::new("/r/src/app/x.h"),
+                "../app/x.h",
+                PathStyle::Include
+            ),
+            "x.h"
+        );
+        assert_eq!(
+            relative_specifier(
+                from,
+                Path::new("/r/src/app/lib"),
+                "./old/",
+                PathStyle::Module
+            ),
+            "./lib/"
+        );
+    }
+}
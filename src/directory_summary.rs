@@ -0,0 +1,260 @@
+//! # Directory Summaries
+//!
+//! Per-file size statistics for triaging unfamiliar code: lines of code, function and class
+//! counts and the longest functions, gathered in one pass over each file's syntax tree.
+//! Summaries are cached by path and reused while the file's size and modification time stay
+//! the same, so repeated calls over a large directory only parse what changed.
+
+use crate::enclosing_symbol::{TYPE_KINDS, enclosing_symbol, symbol_kind, symbol_name};
+use crate::types::{
+    FileSummary, FunctionSize, SkippedFile, SummarizeDirectoryParam, SummarizeDirectoryResult,
+};
+use ast_grep_core::AstGrep;
+use ast_grep_core::Language as _;
+use ast_grep_language::SupportLang as Language;
+use lru::LruCache;
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+/// Number of file summaries kept between calls
+const CACHE_CAPACITY: usize = 20_000;
+
+/// Summarize `code`, listing every named function from longest to shortest
+pub fn summarize_code(file_path: &str, code: &str, lang: Language) -> FileSummary {
+    let ast = AstGrep::new(code, lang);
+    let mut functions = Vec::new();
+    let mut classes = 0;
+
+    for node in ast.root().dfs() {
+        let Some(kind) = symbol_kind(&node.kind()) else {
+            continue;
+        };
+        if kind != "function" && kind != "method" {
+            classes += usize::from(kind != "impl" && kind != "module");
+            continue;
+        }
+        let Some(name) = symbol_name(&node) else {
+            continue;
+        };
+        let outer = enclosing_symbol(&node);
+        let in_type = outer
+            .as_ref()
+            .is_some_and(|outer| TYPE_KINDS.contains(&outer.kind.as_str()));
+        functions.push(FunctionSize {
+            name: match outer {
+                Some(outer) => format!("{}.{name}", outer.name),
+                None => name,
+            },
+            kind: if in_type { "method" } else { kind }.to_string(),
+            start_line: node.start_pos().line(),
+            lines: node.end_pos().line() - node.start_pos().line() + 1,
+            file_path: None,
+        });
+    }
+    functions.sort_by(|a, b| {
+        b.lines
+            .cmp(&a.lines)
+            .then_with(|| a.start_line.cmp(&b.start_line))
+    });
+
+    FileSummary {
+        file_path: file_path.to_string(),
+        language: lang.to_string().to_lowercase(),
+        lines_of_code: code.lines().filter(|line| !line.trim().is_empty()).count(),
+        functions: functions.len(),
+        classes,
+        largest_functions: functions,
+    }
+}
+
+/// Summarize the source files under `directories`, reusing cached summaries where possible
+pub fn summarize_directories(
+    cache: &SummaryCache,
+    directories: &[PathBuf],
+    param: &SummarizeDirectoryParam,
+) -> SummarizeDirectoryResult {
+    let mut paths = source_files(directories);
+    let truncated = paths.len() > param.max_files;
+    paths.truncate(param.max_files);
+
+    let mut result = SummarizeDirectoryResult {
+        files: Vec::new(),
+        languages: BTreeMap::new(),
+        total_lines_of_code: 0,
+        total_functions: 0,
+        total_classes: 0,
+        largest_functions: Vec::new(),
+        cached_files: 0,
+        truncated,
+        skipped_files: Vec::new(),
+    };
+    let mut all_functions = Vec::new();
+
+    for (path, lang) in paths {
+        let file_path = path.to_string_lossy().to_string();
+        let too_large = std::fs::metadata(&path)
+            .map(|metadata| metadata.len() > param.max_file_size)
+            .unwrap_or(false);
+        if too_large {
+            result.skipped_files.push(SkippedFile {
+                file_path,
+                reason: format!("larger than {} bytes", param.max_file_size),
+            });
+            continue;
+        }
+        let (mut summary, cached) = match cache.summarize(&path, lang) {
+            Ok(summarized) => summarized,
+            Err(e) => {
+                result.skipped_files.push(SkippedFile {
+                    file_path,
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        result.cached_files += usize::from(cached);
+        *result
+            .languages
+            .entry(summary.language.clone())
+            .or_default() += 1;
+        result.total_lines_of_code += summary.lines_of_code;
+        result.total_functions += summary.functions;
+        result.total_classes += summary.classes;
+        all_functions.extend(
+            summary
+                .largest_functions
+                .iter()
+                .take(param.largest_functions)
+                .map(|function| FunctionSize {
+                    file_path: Some(file_path.clone()),
+                    ..function.clone()
+                }),
+        );
+        summary.largest_functions.truncate(param.largest_functions);
+        result.files.push(summary);
+    }
+
+    all_functions.sort_by_key(|function| std::cmp::Reverse(function.lines));
+    all_functions.truncate(param.largest_functions);
+    result.largest_functions = all_functions;
+    result
+}
+
+/// Files under `directories` in a language ast-grep can parse, in path order
+pub(crate) fn source_files(directories: &[PathBuf]) -> Vec<(PathBuf, Language)> {
+    let mut paths: Vec<(PathBuf, Language)> = directories
+        .iter()
+        .flat_map(|directory| {
+            WalkDir::new(directory)
+                .max_depth(10)
+                .into_iter()
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_file())
+                .filter_map(|entry| {
+                    let lang = Language::from_path(entry.path())?;
+                    Some((entry.into_path(), lang))
+                })
+        })
+        .collect();
+    paths.sort_by(|a, b| a.0.cmp(&b.0));
+    paths.dedup_by(|a, b| a.0 == b.0);
+    paths
+}
+
+#[derive(Clone)]
+struct CachedSummary {
+    modified: SystemTime,
+    len: u64,
+    summary: FileSummary,
+}
+
+/// File summaries keyed by path, shared between clones of the service
+#[derive(Clone)]
+pub struct SummaryCache {
+    entries: Arc<Mutex<LruCache<PathBuf, CachedSummary>>>,
+}
+
+impl Default for SummaryCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SummaryCache {
+    pub fn new() -> Self {
+        let capacity = NonZeroUsize::new(CACHE_CAPACITY).unwrap();
+        Self {
+            entries: Arc::new(Mutex::new(LruCache::new(capacity))),
+        }
+    }
+
+    /// The summary of the file at `path`, and whether it came from the cache
+    pub fn summarize(&self, path: &Path, lang: Language) -> std::io::Result<(FileSummary, bool)> {
+        let metadata = std::fs::metadata(path)?;
+        let modified = metadata.modified()?;
+        if let Some(cached) = self.entries.lock().unwrap().get(path)
+            && cached.modified == modified
+            && cached.len == metadata.len()
+        {
+            return Ok((cached.summary.clone(), true));
+        }
+
+        let code = std::fs::read_to_string(path)?;
+        let summary = summarize_code(&path.to_string_lossy(), &code, lang);
+        self.entries.lock().unwrap().put(
+            path.to_path_buf(),
+            CachedSummary {
+                modified,
+                len: metadata.len(),
+                summary: summary.clone(),
+            },
+        );
+        Ok((summary, false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_code_counts_and_ranks_functions() {
+        let code = r#"
+class Cart {
+  total() {
+    return this.items
+      .map((item) => item.price)
+      .reduce((a, b) => a + b, 0);
+  }
+}
+
+function checkout(cart) {
+  pay(cart.total());
+}
+
+const format = (value) => `$${value}`;
+"#;
+        let summary = summarize_code("cart.js", code, Language::JavaScript);
+        assert_eq!(summary.classes, 1);
+        assert_eq!(summary.functions, 3);
+        assert_eq!(summary.lines_of_code, 11);
+        let largest: Vec<(&str, &str, usize)> = summary
+            .largest_functions
+            .iter()
+            .map(|f| (f.name.as_str(), f.kind.as_str(), f.lines))
+            .collect();
+        assert_eq!(
+            largest,
+            vec![
+                ("Cart.total", "method", 5),
+                ("checkout", "function", 3),
+                ("format", "function", 1)
+            ]
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! # Doc Comment Coverage
+//!
+//! Audits which public functions, methods and types have doc comments, using the declarations
+//! of the symbol index. What counts as public and as documented depends on the language:
+//!
+//! - **Rust**: items declared `pub`, documented by a `///` or `/** */` comment or a `#[doc]`
+//!   attribute
+//! - **Python**: functions and classes whose names (and enclosing classes' names) don't start
+//!   with `_` and that aren't nested in a function, documented by a docstring
+//! - **JavaScript and TypeScript**: exported declarations and the non-private members of
+//!   exported classes, documented by a `/** */` JSDoc comment
+//!
+//! Files in other languages are not audited.
+
+use crate::context_lines::leading_trivia;
+use crate::directory_summary::source_files;
+use crate::enclosing_symbol::{symbol_kind, symbol_name};
+use crate::symbol_search::SymbolIndex;
+use crate::types::{
+    DocCoverage, DocCoverageParam, DocCoverageResult, ModuleDocCoverage, SkippedFile, TriviaKind,
+    UndocumentedSymbol,
+};
+use ast_grep_core::Node;
+use ast_grep_core::tree_sitter::StrDoc;
+use ast_grep_language::SupportLang as Language;
+use std::path::PathBuf;
+
+/// Symbol kinds that are expected to have doc comments
+const AUDITED_KINDS: &[&str] = &[
+    "function",
+    "method",
+    "class",
+    "struct",
+    "enum",
+    "trait",
+    "interface",
+];
+
+/// Whether `doc_coverage` knows what is public and documented in `lang`
+pub fn audits(lang: Language) -> bool {
+    matches!(
+        lang,
+        Language::Rust
+            | Language::Python
+            | Language::JavaScript
+            | Language::TypeScript
+            | Language::Tsx
+    )
+}
+
+/// Whether the declaration `node` is part of its module's public API
+pub(crate) fn is_public(node: &Node<'_, StrDoc<Language>>, lang: Language) -> bool {
+    match lang {
+        Language::Rust => node
+            .children()
+            .any(|child| child.kind() == "visibility_modifier" && child.text() == "pub"),
+        Language::Python => python_public(node),
+        Language::JavaScript | Language::TypeScript | Language::Tsx => js_public(node),
+        _ => false,
+    }
+}
+
+/// Whether the declaration `node` has a doc comment or docstring
+pub(crate) fn is_documented(node: &Node<'_, StrDoc<Language>>, lang: Language) -> bool {
+    match lang {
+        Language::Rust => leading_trivia(node.clone())
+            .iter()
+            .any(|item| match item.kind {
+                TriviaKind::Comment => {
+                    (item.text.starts_with("///") && !item.text.starts_with("////"))
+                        || (item.text.starts_with("/**") && !item.text.starts_with("/***"))
+                }
+                TriviaKind::Attribute => item.text.starts_with("#[doc"),
+            }),
+        Language::Python => has_docstring(node),
+        Language::JavaScript | Language::TypeScript | Language::Tsx => {
+            leading_trivia(node.clone()).iter().any(|item| {
+                item.kind == TriviaKind::Comment
+                    && item.text.starts_with("/**")
+                    && !item.text.starts_with("/***")
+            })
+        }
+        _ => false,
+    }
+}
+
+fn python_public(node: &Node<'_, StrDoc<Language>>) -> bool {
+    let private = |node: &Node<'_, StrDoc<Language>>| {
+        symbol_name(node).is_some_and(|name| name.starts_with('_'))
+    };
+    !private(node)
+        && node
+            .ancestors()
+            .all(|ancestor| match symbol_kind(&ancestor.kind()) {
+                Some("function") => false,
+                Some(_) => !private(&ancestor),
+                None => true,
+            })
+}
+
+fn has_docstring(node: &Node<'_, StrDoc<Language>>) -> bool {
+    let Some(body) = node.field("body") else {
+        return false;
+    };
+    let first = body
+        .children()
+        .find(|child| child.is_named() && child.kind() != "comment");
+    first.is_some_and(|statement| {
+        statement.kind() == "expression_statement"
+            && statement
+                .child(0)
+                .is_some_and(|expression| expression.kind() == "string")
+    })
+}
+
+fn js_public(node: &Node<'_, StrDoc<Language>>) -> bool {
+    // Arrow functions assigned to class fields are members like methods
+    let member = match node.parent() {
+        Some(parent)
+            if matches!(
+                &*parent.kind(),
+                "public_field_definition" | "field_definition"
+            ) =>
+        {
+            parent
+        }
+        _ => node.clone(),
+    };
+    let Some(body) = member
+        .parent()
+        .filter(|parent| parent.kind() == "class_body")
+    else {
+        return js_exported(node);
+    };
+    let private = member
+        .children()
+        .any(|child| child.kind() == "accessibility_modifier" && child.text() != "public")
+        || member
+            .field("name")
+            .is_some_and(|name| name.text().starts_with('#'));
+    !private && body.parent().is_some_and(|class| js_exported(&class))
+}
+
+/// Whether `node` is declared by an `export` statement, directly or through a variable
+/// declaration
+fn js_exported(node: &Node<'_, StrDoc<Language>>) -> bool {
+    let mut node = node.clone();
+    while let Some(parent) = node.parent() {
+        match &*parent.kind() {
+            "export_statement" => return true,
+            "variable_declarator" | "lexical_declaration" | "variable_declaration" => node = parent,
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// Audit the doc comments of the public symbols under `directories`
+pub fn doc_coverage(
+    index: &SymbolIndex,
+    directories: &[PathBuf],
+    param: &DocCoverageParam,
+) -> DocCoverageResult {
+    let mut paths: Vec<(PathBuf, Language)> = source_files(directories)
+        .into_iter()
+        .filter(|(_, lang)| audits(*lang))
+        .collect();
+    let truncated = paths.len() > param.max_files;
+    paths.truncate(param.max_files);
+
+    let mut result = DocCoverageResult {
+        total: DocCoverage::default(),
+        languages: Default::default(),
+        modules: Vec::new(),
+        undocumented: Vec::new(),
+        next_offset: None,
+        files_indexed: 0,
+        cached_files: 0,
+        truncated,
+        skipped_files: Vec::new(),
+    };
+    let mut undocumented = Vec::new();
+
+    for (path, lang) in paths {
+        let file_path = path.to_string_lossy().to_string();
+        let too_large = std::fs::metadata(&path)
+            .map(|metadata| metadata.len() > param.max_file_size)
+            .unwrap_or(false);
+        if too_large {
+            result.skipped_files.push(SkippedFile {
+                file_path,
+                reason: format!("larger than {} bytes", param.max_file_size),
+            });
+            continue;
+        }
+        let (symbols, cached) = match index.symbols(&path, lang) {
+            Ok(indexed) => indexed,
+            Err(e) => {
+                result.skipped_files.push(SkippedFile {
+                    file_path,
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        result.files_indexed += 1;
+        result.cached_files += usize::from(cached);
+        let mut coverage = DocCoverage::default();
+        for symbol in symbols.iter() {
+            if !symbol.public || !AUDITED_KINDS.contains(&symbol.kind.as_str()) {
+                continue;
+            }
+            coverage.public_symbols += 1;
+            if symbol.documented {
+                coverage.documented += 1;
+            } else {
+                undocumented.push(UndocumentedSymbol {
+                    name: symbol.name.clone(),
+                    qualified_name: symbol.qualified_name.clone(),
+                    kind: symbol.kind.clone(),
+                    file_path: file_path.clone(),
+                    start_line: symbol.start_line,
+                });
+            }
+        }
+        if coverage.public_symbols == 0 {
+            continue;
+        }
+
+        let language = lang.to_string().to_lowercase();
+        for total in [
+            &mut result.total,
+            result.languages.entry(language.clone()).or_default(),
+        ] {
+            total.public_symbols += coverage.public_symbols;
+            total.documented += coverage.documented;
+        }
+        result.modules.push(ModuleDocCoverage {
+            file_path,
+            language,
+            coverage: with_percent(coverage),
+        });
+    }
+
+    result.total = with_percent(result.total);
+    for coverage in result.languages.values_mut() {
+        *coverage = with_percent(coverage.clone());
+    }
+    let end = undocumented.len().min(param.offset + param.page_size);
+    result.next_offset = (end < undocumented.len()).then_some(end);
+    result.undocumented = undocumented
+        .into_iter()
+        .skip(param.offset)
+        .take(param.page_size)
+        .collect();
+    result
+}
+
+/// `coverage` with its percentage worked out from its counts
+fn with_percent(coverage: DocCoverage) -> DocCoverage {
+    let coverage_percent = if coverage.public_symbols == 0 {
+        100.0
+    } else {
+        let share = coverage.documented as f64 / coverage.public_symbols as f64;
+        (share * 1000.0).round() / 10.0
+    };
+    DocCoverage {
+        coverage_percent,
+        ..coverage
+    }
+}
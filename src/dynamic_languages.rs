@@ -0,0 +1,260 @@
+//! # Dynamic Languages
+//!
+//! Tree-sitter grammars loaded at runtime from shared libraries, for languages that neither
+//! ast-grep-language nor [`crate::extra_languages`] bundles, such as Solidity or Zig. They are
+//! registered from the `customLanguages` section of `sgconfig.yml`, in ast-grep's format:
+//!
+//! ```yaml
+//! customLanguages:
+//!   solidity:
+//!     libraryPath: grammars/solidity.so
+//!     extensions: [sol]
+//!     expandoChar: _
+//! ```
+//!
+//! The library must export the grammar's language function, `tree_sitter_<name>` unless
+//! `languageSymbol` names another one. It stays loaded for the life of the process. Once
+//! registered, a language is found by name or extension wherever a pattern tool resolves its
+//! `language`, after the bundled and extra languages.
+//!
+//! Like ast-grep, metavariables are rewritten to `expandoChar` before a pattern is parsed, for
+//! grammars that don't accept `$` in identifiers; without one, patterns are parsed as written.
+
+use crate::errors::ServiceError;
+use crate::extra_languages::{ExtraLanguage, expand_meta_vars};
+use crate::sg_config::CustomLanguage;
+use ast_grep_core::matcher::PatternBuilder;
+use ast_grep_core::tree_sitter::{LanguageExt, StrDoc, TSLanguage};
+use ast_grep_core::{Pattern, PatternError};
+use ast_grep_language::SupportLang as Language;
+use std::borrow::Cow;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+/// Every registered grammar; entries are leaked so [`DynamicLanguage`] can be `Copy`
+static REGISTRY: RwLock<Vec<&'static Grammar>> = RwLock::new(Vec::new());
+
+/// A registered grammar
+struct Grammar {
+    name: String,
+    extensions: Vec<String>,
+    expando_char: char,
+    language: TSLanguage,
+}
+
+/// A grammar registered with [`register`] or [`register_language`]
+#[derive(Clone, Copy)]
+pub struct DynamicLanguage(&'static Grammar);
+
+impl DynamicLanguage {
+    /// Every registered grammar, in registration order
+    pub fn all() -> Vec<DynamicLanguage> {
+        registry().iter().copied().map(DynamicLanguage).collect()
+    }
+
+    /// Name the grammar was registered under, lowercased
+    pub fn name(&self) -> &'static str {
+        &self.0.name
+    }
+
+    /// File extensions parsed with this grammar
+    pub fn extensions(&self) -> &'static [String] {
+        &self.0.extensions
+    }
+
+    /// Whether `path` has one of this grammar's extensions
+    pub fn matches_path(&self, path: &str) -> bool {
+        <Self as ast_grep_core::Language>::from_path(path) == Some(*self)
+    }
+}
+
+impl PartialEq for DynamicLanguage {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for DynamicLanguage {}
+
+impl fmt::Debug for DynamicLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DynamicLanguage")
+            .field(&self.0.name)
+            .finish()
+    }
+}
+
+impl fmt::Display for DynamicLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for DynamicLanguage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let name = s.to_ascii_lowercase();
+        registry()
+            .iter()
+            .find(|grammar| grammar.name == name)
+            .map(|&grammar| DynamicLanguage(grammar))
+            .ok_or_else(|| format!("{s} is not a registered custom language"))
+    }
+}
+
+impl ast_grep_core::Language for DynamicLanguage {
+    fn kind_to_id(&self, kind: &str) -> u16 {
+        self.0.language.id_for_node_kind(kind, true)
+    }
+
+    fn field_to_id(&self, field: &str) -> Option<u16> {
+        self.0.language.field_id_for_name(field).map(|id| id.get())
+    }
+
+    fn expando_char(&self) -> char {
+        self.0.expando_char
+    }
+
+    fn pre_process_pattern<'q>(&self, query: &'q str) -> Cow<'q, str> {
+        match self.0.expando_char {
+            '$' => Cow::Borrowed(query),
+            expando => Cow::Owned(expand_meta_vars(query, expando)),
+        }
+    }
+
+    fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?;
+        registry()
+            .iter()
+            .find(|grammar| grammar.extensions.iter().any(|e| e == extension))
+            .map(|&grammar| DynamicLanguage(grammar))
+    }
+
+    fn build_pattern(&self, builder: &PatternBuilder) -> Result<Pattern, PatternError> {
+        builder.build(|src| StrDoc::try_new(src, *self))
+    }
+}
+
+impl LanguageExt for DynamicLanguage {
+    fn get_ts_language(&self) -> TSLanguage {
+        self.0.language.clone()
+    }
+}
+
+/// Load the grammar of `config` from its shared library and register it as `name`
+pub fn register(name: &str, config: &CustomLanguage) -> Result<DynamicLanguage, ServiceError> {
+    let symbol = match &config.language_symbol {
+        Some(symbol) => symbol.clone(),
+        None => format!(
+            "tree_sitter_{}",
+            name.to_ascii_lowercase().replace('-', "_")
+        ),
+    };
+    let language = load_language(&config.library_path, &symbol)?;
+    register_language(name, &config.extensions, config.expando_char, language)
+}
+
+/// Register `language` as `name`, parsing files with `extensions`. A grammar registered under
+/// the same name before is replaced
+pub fn register_language(
+    name: &str,
+    extensions: &[String],
+    expando_char: Option<char>,
+    language: TSLanguage,
+) -> Result<DynamicLanguage, ServiceError> {
+    let name = name.to_ascii_lowercase();
+    if Language::from_str(&name).is_ok() || ExtraLanguage::from_str(&name).is_ok() {
+        return Err(ServiceError::Internal(format!(
+            "Custom language '{name}' has the name of a bundled language"
+        )));
+    }
+    if extensions.is_empty() {
+        return Err(ServiceError::Internal(format!(
+            "Custom language '{name}' needs at least one file extension"
+        )));
+    }
+    let grammar: &'static Grammar = Box::leak(Box::new(Grammar {
+        name,
+        extensions: extensions
+            .iter()
+            .map(|e| e.trim_start_matches('.').to_string())
+            .collect(),
+        expando_char: expando_char.unwrap_or('$'),
+        language,
+    }));
+    // A grammar built for another tree-sitter ABI can't be used by the parser
+    StrDoc::try_new("", DynamicLanguage(grammar)).map_err(|e| {
+        ServiceError::Internal(format!(
+            "Custom language '{}' was built for tree-sitter ABI {}, which this build can't load: {e}",
+            grammar.name,
+            grammar.language.abi_version()
+        ))
+    })?;
+
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    match registry.iter_mut().find(|other| other.name == grammar.name) {
+        Some(other) => *other = grammar,
+        None => registry.push(grammar),
+    }
+    Ok(DynamicLanguage(grammar))
+}
+
+fn registry() -> std::sync::RwLockReadGuard<'static, Vec<&'static Grammar>> {
+    REGISTRY.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Open the shared library at `path` and call its `symbol` language function
+#[cfg(unix)]
+fn load_language(path: &Path, symbol: &str) -> Result<TSLanguage, ServiceError> {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+
+    let dl_error = || {
+        let message = unsafe { libc::dlerror() };
+        match message.is_null() {
+            true => "unknown error".to_string(),
+            false => unsafe { CStr::from_ptr(message) }
+                .to_string_lossy()
+                .into_owned(),
+        }
+    };
+    let c_path = CString::new(path.as_os_str().as_bytes()).map_err(|_| {
+        ServiceError::Internal(format!("Invalid grammar library path {}", path.display()))
+    })?;
+    let c_symbol = CString::new(symbol)
+        .map_err(|_| ServiceError::Internal(format!("Invalid language symbol '{symbol}'")))?;
+
+    let handle = unsafe { libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+    if handle.is_null() {
+        return Err(ServiceError::Internal(format!(
+            "Could not load grammar library {}: {}",
+            path.display(),
+            dl_error()
+        )));
+    }
+    let function = unsafe { libc::dlsym(handle, c_symbol.as_ptr()) };
+    if function.is_null() {
+        let error = dl_error();
+        unsafe { libc::dlclose(handle) };
+        return Err(ServiceError::Internal(format!(
+            "Grammar library {} has no symbol '{symbol}': {error}",
+            path.display()
+        )));
+    }
+    // The handle is never closed, since the grammar's tables live in the library
+    let function: unsafe extern "C" fn() -> *const () = unsafe { std::mem::transmute(function) };
+    Ok(TSLanguage::new(unsafe {
+        tree_sitter_language::LanguageFn::from_raw(function)
+    }))
+}
+
+#[cfg(not(unix))]
+fn load_language(path: &Path, _symbol: &str) -> Result<TSLanguage, ServiceError> {
+    Err(ServiceError::Internal(format!(
+        "Could not load grammar library {}: custom languages are only supported on Unix",
+        path.display()
+    )))
+}
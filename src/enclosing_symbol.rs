@@ -0,0 +1,182 @@
+//! # Enclosing Symbols
+//!
+//! Names the function, method, class or module a match sits in, so reports can say "in
+//! `UserService.login`" instead of giving only line numbers. The symbol is found by walking the
+//! match's ancestors and reading the name of each declaration on the way up.
+
+use ast_grep_core::{Doc, Node};
+use serde::{Deserialize, Serialize};
+
+/// The declarations surrounding a match
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnclosingSymbol {
+    /// Names of the enclosing declarations from outermost to innermost, e.g. `UserService.login`
+    pub name: String,
+    /// Kind of the innermost declaration: function, method, class, struct, enum, trait,
+    /// interface, impl or module
+    pub kind: String,
+}
+
+/// Declaration node kinds across the bundled grammars and the symbol kind they define
+const SYMBOL_KINDS: &[(&str, &str)] = &[
+    ("function_declaration", "function"),
+    ("function_definition", "function"),
+    ("function_item", "function"),
+    ("generator_function_declaration", "function"),
+    ("arrow_function", "function"),
+    ("function_expression", "function"),
+    ("method_definition", "method"),
+    ("method_declaration", "method"),
+    ("constructor_declaration", "method"),
+    ("class_declaration", "class"),
+    ("abstract_class_declaration", "class"),
+    ("class_definition", "class"),
+    ("class_specifier", "class"),
+    ("struct_item", "struct"),
+    ("struct_specifier", "struct"),
+    ("enum_item", "enum"),
+    ("enum_declaration", "enum"),
+    ("trait_item", "trait"),
+    ("interface_declaration", "interface"),
+    ("impl_item", "impl"),
+    ("mod_item", "module"),
+    ("namespace_definition", "module"),
+    ("internal_module", "module"),
+];
+
+/// Symbol kinds whose functions are reported as methods
+pub(crate) const TYPE_KINDS: &[&str] = &["class", "struct", "enum", "trait", "interface", "impl"];
+
+/// The named declarations enclosing `node`, not counting `node` itself
+pub fn enclosing_symbol<D: Doc>(node: &Node<'_, D>) -> Option<EnclosingSymbol> {
+    let mut symbols: Vec<(String, &'static str)> = node
+        .ancestors()
+        .filter_map(|ancestor| {
+            let kind = symbol_kind(&ancestor.kind())?;
+            Some((symbol_name(&ancestor)?, kind))
+        })
+        .collect();
+    symbols.reverse();
+
+    let (_, innermost) = *symbols.last()?;
+    let kind = match symbols.len().checked_sub(2).map(|i| symbols[i].1) {
+        Some(outer) if innermost == "function" && TYPE_KINDS.contains(&outer) => "method",
+        _ => innermost,
+    };
+    let name = symbols
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(".");
+    Some(EnclosingSymbol {
+        name,
+        kind: kind.to_string(),
+    })
+}
+
+pub(crate) fn symbol_kind(kind: &str) -> Option<&'static str> {
+    SYMBOL_KINDS
+        .iter()
+        .find(|(node_kind, _)| *node_kind == kind)
+        .map(|(_, symbol_kind)| *symbol_kind)
+}
+
+/// The declared name of a symbol node, if it has one
+pub(crate) fn symbol_name<D: Doc>(node: &Node<'_, D>) -> Option<String> {
+    if let Some(name) = node.field("name") {
+        return Some(name.text().to_string());
+    }
+    // Rust `impl Type` and `impl Trait for Type` blocks are named after the type
+    if node.kind() == "impl_item" {
+        return node.field("type").map(|ty| ty.text().to_string());
+    }
+    // C and C++ functions nest the name inside their declarators
+    if let Some(mut declarator) = node.field("declarator") {
+        while let Some(inner) = declarator.field("declarator") {
+            declarator = inner;
+        }
+        return declarator
+            .kind()
+            .contains("identifier")
+            .then(|| declarator.text().to_string());
+    }
+    // Anonymous functions take the name they are assigned to
+    let parent = node.parent()?;
+    let target = match &*parent.kind() {
+        "variable_declarator" | "public_field_definition" | "field_definition" => {
+            parent.field("name")
+        }
+        "pair" => parent.field("key"),
+        "assignment_expression" | "assignment" => parent.field("left"),
+        _ => None,
+    }?;
+    Some(target.text().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast_grep_core::AstGrep;
+    use ast_grep_language::SupportLang as Language;
+
+    fn symbol_at(code: &str, lang: Language, needle: &str) -> Option<EnclosingSymbol> {
+        let ast = AstGrep::new(code, lang);
+        let root = ast.root();
+        let node = root.find(needle).expect("needle should match");
+        enclosing_symbol(node.get_node())
+    }
+
+    fn symbol(name: &str, kind: &str) -> Option<EnclosingSymbol> {
+        Some(EnclosingSymbol {
+            name: name.to_string(),
+            kind: kind.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_javascript_class_method() {
+        let code = "class UserService {\n  login(user) {\n    check(user);\n  }\n}";
+        assert_eq!(
+            symbol_at(code, Language::JavaScript, "check($A)"),
+            symbol("UserService.login", "method")
+        );
+    }
+
+    #[test]
+    fn test_anonymous_function_takes_variable_name() {
+        let code = "const handler = async (req) => { check(req); };";
+        assert_eq!(
+            symbol_at(code, Language::JavaScript, "check($A)"),
+            symbol("handler", "function")
+        );
+    }
+
+    #[test]
+    fn test_python_method_and_top_level() {
+        let code = "class Repo:\n    def save(self):\n        check(self)\n\ncheck(1)\n";
+        assert_eq!(
+            symbol_at(code, Language::Python, "check(self)"),
+            symbol("Repo.save", "method")
+        );
+        assert_eq!(symbol_at(code, Language::Python, "check(1)"), None);
+    }
+
+    #[test]
+    fn test_rust_impl_and_module() {
+        let code =
+            "mod auth {\n    impl Session {\n        fn refresh(&self) { check(self); }\n    }\n}";
+        assert_eq!(
+            symbol_at(code, Language::Rust, "check($A)"),
+            symbol("auth.Session.refresh", "method")
+        );
+    }
+
+    #[test]
+    fn test_c_function_declarator() {
+        let code = "int *parse(char *input) {\n    check(input);\n}";
+        assert_eq!(
+            symbol_at(code, Language::C, "check($A);"),
+            symbol("parse", "function")
+        );
+    }
+}
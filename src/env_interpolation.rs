@@ -0,0 +1,89 @@
+//! # Environment Interpolation
+//!
+//! Shared rule packs often need a project-specific value, such as the prefix of an internal
+//! module or the name of a package. Rule configs and path patterns may reference such values as
+//! `${NAME}`, which is replaced by the environment variable `NAME` when the text is parsed.
+//!
+//! Only variables on the server's allowlist (`--allow-env`) are interpolated, so a rule can't
+//! read arbitrary secrets from the server's environment. Their values are captured into
+//! [`ServiceConfig::env_vars`] at startup. Any other `${...}`, such as a template literal in a
+//! JavaScript pattern, is left as it is.
+
+use crate::config::ServiceConfig;
+use crate::errors::ServiceError;
+use crate::rules::{RuleConfig, parse_rule_config};
+use regex::{Captures, Regex};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap());
+
+impl ServiceConfig {
+    /// Replace every `${NAME}` in `text` whose `NAME` is an allowlisted variable
+    pub fn interpolate_env<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        interpolate(text, &self.env_vars)
+    }
+
+    /// Parse `content` as a rule configuration after interpolating it
+    pub fn parse_rule_config(&self, content: &str) -> Result<RuleConfig, ServiceError> {
+        parse_interpolated_rule(content, &self.env_vars)
+    }
+}
+
+/// Replace the placeholders in `text` that name one of `vars` with its value
+pub fn interpolate<'a>(text: &'a str, vars: &BTreeMap<String, String>) -> Cow<'a, str> {
+    if vars.is_empty() || !text.contains("${") {
+        return Cow::Borrowed(text);
+    }
+    PLACEHOLDER.replace_all(text, |captures: &Captures| match vars.get(&captures[1]) {
+        Some(value) => value.clone(),
+        None => captures[0].to_string(),
+    })
+}
+
+/// Parse `content` as a rule configuration once the placeholders naming one of `vars` are
+/// replaced. Rules from clients and from storage are all parsed through here
+pub fn parse_interpolated_rule(
+    content: &str,
+    vars: &BTreeMap<String, String>,
+) -> Result<RuleConfig, ServiceError> {
+    parse_rule_config(&interpolate(content, vars))
+}
+
+/// Read the allowlisted variables from the process environment. A variable that isn't set is
+/// an error rather than an empty value, which would silently widen a pattern or path
+pub fn capture_env_vars(allowlist: &[String]) -> Result<BTreeMap<String, String>, String> {
+    allowlist
+        .iter()
+        .map(|name| match std::env::var(name) {
+            Ok(value) => Ok((name.clone(), value)),
+            Err(_) => Err(format!("--allow-env {name}: the variable is not set")),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_allowlisted_names_are_replaced() {
+        let vars = BTreeMap::from([("PKG".to_string(), "@acme/core".to_string())]);
+        assert_eq!(
+            interpolate("import $X from '${PKG}'; `${HOME}`", &vars),
+            "import $X from '@acme/core'; `${HOME}`"
+        );
+        assert!(matches!(
+            interpolate("${PKG}", &BTreeMap::new()),
+            Cow::Borrowed("${PKG}")
+        ));
+    }
+
+    #[test]
+    fn test_unset_allowlisted_variable_is_an_error() {
+        let error = capture_env_vars(&["AST_GREP_MCP_SURELY_UNSET".to_string()]).unwrap_err();
+        assert!(error.contains("AST_GREP_MCP_SURELY_UNSET"));
+    }
+}
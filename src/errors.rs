@@ -3,6 +3,7 @@
 //! Error handling for the ast-grep MCP service.
 //! Provides structured error types that can be converted to MCP ErrorData.
 
+use crate::input_limits::input_size_hint;
 use rmcp::model::ErrorData;
 use std::fmt;
 use std::path::PathBuf;
@@ -33,6 +34,17 @@ pub enum ServiceError {
     Glob(globset::Error),
     /// MCP tool not found
     ToolNotFound(String),
+    /// A rule evaluation ran past its time budget
+    BudgetExceeded(String),
+    /// A pagination cursor was issued for other parameters or root directories, was
+    /// altered, or has expired
+    StaleCursor(String),
+    /// A tool argument is larger than the server's limit for it (see `--max-input-size`)
+    InputTooLarge {
+        parameter: String,
+        size: usize,
+        limit: usize,
+    },
     /// Error during AST analysis, includes AST structure for debugging
     AstAnalysisError {
         message: String,
@@ -58,6 +70,20 @@ impl fmt::Display for ServiceError {
             ServiceError::FileNotFound(path) => write!(f, "File not found: {}", path.display()),
             ServiceError::Glob(err) => write!(f, "Glob error: {err}"),
             ServiceError::ToolNotFound(tool) => write!(f, "Tool not found: {tool}"),
+            ServiceError::BudgetExceeded(msg) => write!(f, "Budget exceeded: {msg}"),
+            ServiceError::StaleCursor(msg) => write!(
+                f,
+                "Stale cursor: {msg}; restart pagination by repeating the call without a cursor"
+            ),
+            ServiceError::InputTooLarge {
+                parameter,
+                size,
+                limit,
+            } => write!(
+                f,
+                "Input too large: {parameter} is {size} bytes, over the {limit} byte limit; {}",
+                input_size_hint(parameter)
+            ),
             ServiceError::AstAnalysisError {
                 message,
                 code,
@@ -120,6 +146,13 @@ impl From<globset::Error> for ServiceError {
     }
 }
 
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for ServiceError {
+    fn from(err: rusqlite::Error) -> Self {
+        ServiceError::Internal(format!("Rule database error: {err}"))
+    }
+}
+
 impl From<ServiceError> for ErrorData {
     fn from(err: ServiceError) -> Self {
         match err {
@@ -138,6 +171,27 @@ impl From<ServiceError> for ErrorData {
                 });
                 ErrorData::internal_error(message, Some(debug_info))
             }
+            ServiceError::StaleCursor(_) => ErrorData::invalid_params(
+                err.to_string(),
+                Some(serde_json::json!({
+                    "code": "STALE_CURSOR",
+                    "hint": "repeat the call without a cursor to restart pagination",
+                })),
+            ),
+            ServiceError::InputTooLarge {
+                ref parameter,
+                size,
+                limit,
+            } => {
+                let data = serde_json::json!({
+                    "code": "INPUT_TOO_LARGE",
+                    "parameter": parameter,
+                    "size": size,
+                    "limit": limit,
+                    "hint": input_size_hint(parameter),
+                });
+                ErrorData::invalid_params(err.to_string(), Some(data))
+            }
             _ => ErrorData::internal_error(err.to_string(), None),
         }
     }
@@ -0,0 +1,106 @@
+//! # Pattern Experiments
+//!
+//! The `experiment` tool: several candidate patterns, each with an optional replacement, are
+//! run over the same code and reported side by side, so a pattern can be tuned by comparing
+//! match counts and rewrites from one call instead of a search per attempt.
+
+use crate::errors::ServiceError;
+use crate::replace::ReplaceService;
+use crate::search::SearchService;
+use crate::types::{
+    ExperimentCandidate, ExperimentOutcome, ExperimentParam, ExperimentResult, ReplaceParam,
+    SearchParam,
+};
+
+/// Most candidates a single experiment may try
+pub const MAX_CANDIDATES: usize = 20;
+
+/// Run every candidate of `param` over `param.code`
+pub async fn run_experiment(
+    search_service: &SearchService,
+    replace_service: &ReplaceService,
+    param: ExperimentParam,
+) -> Result<ExperimentResult, ServiceError> {
+    if param.candidates.is_empty() {
+        return Err(ServiceError::Internal(
+            "experiment needs at least one candidate".to_string(),
+        ));
+    }
+    if param.candidates.len() > MAX_CANDIDATES {
+        return Err(ServiceError::Internal(format!(
+            "experiment accepts at most {MAX_CANDIDATES} candidates, got {}",
+            param.candidates.len()
+        )));
+    }
+
+    let mut outcomes = Vec::with_capacity(param.candidates.len());
+    for (index, candidate) in param.candidates.iter().enumerate() {
+        let language = candidate
+            .language
+            .clone()
+            .unwrap_or_else(|| param.language.clone());
+        let mut outcome = ExperimentOutcome {
+            id: candidate
+                .id
+                .clone()
+                .unwrap_or_else(|| (index + 1).to_string()),
+            pattern: candidate.pattern.clone(),
+            language: language.clone(),
+            match_count: 0,
+            matches: Vec::new(),
+            new_code: None,
+            error: None,
+        };
+        if let Err(e) = try_candidate(
+            search_service,
+            replace_service,
+            &param,
+            candidate,
+            language,
+            &mut outcome,
+        )
+        .await
+        {
+            outcome.error = Some(e.to_string());
+        }
+        outcomes.push(outcome);
+    }
+    Ok(ExperimentResult { outcomes })
+}
+
+/// Fill in the matches and rewrite of one candidate
+async fn try_candidate(
+    search_service: &SearchService,
+    replace_service: &ReplaceService,
+    param: &ExperimentParam,
+    candidate: &ExperimentCandidate,
+    language: String,
+    outcome: &mut ExperimentOutcome,
+) -> Result<(), ServiceError> {
+    let mut matches = search_service
+        .search(SearchParam {
+            code: param.code.clone(),
+            pattern: candidate.pattern.clone(),
+            language: language.clone(),
+            strictness: candidate.strictness,
+            selector: candidate.selector.clone(),
+            ..Default::default()
+        })
+        .await?
+        .matches;
+    outcome.match_count = matches.len();
+    matches.truncate(param.max_matches);
+    outcome.matches = matches;
+
+    if let Some(replacement) = &candidate.replacement {
+        let result = replace_service
+            .replace(ReplaceParam {
+                strictness: candidate.strictness,
+                selector: candidate.selector.clone(),
+                ..ReplaceParam::new(&param.code, &candidate.pattern, replacement, &language)
+            })
+            .await?;
+        outcome.new_code = Some(result.new_code);
+    }
+    Ok(())
+}
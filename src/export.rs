@@ -0,0 +1,181 @@
+//! # Result Export
+//!
+//! Writes full tool results to files inside the workspace so that very large audits can be
+//! analyzed offline instead of being pushed through an MCP response, and writes stored rules
+//! out as an ast-grep CLI project so the same rules can run in CI with the upstream binary.
+
+use crate::errors::ServiceError;
+use crate::path_validation::{
+    resolve_output_path, validate_creatable_path, validate_path_within_roots,
+};
+use crate::rules::{
+    ExportToSgconfigParam, ExportToSgconfigResult, ExportedScanResult, RuleInfo, ScanResult,
+    SkippedRule,
+};
+use crate::sg_config::SgConfig;
+use crate::types::{ExportedSearchResult, FileSearchResult};
+use serde::Serialize;
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the rule directory inside an exported project, relative to sgconfig.yml
+const EXPORT_RULES_DIR: &str = "rules";
+
+/// Serialize `result` as pretty-printed JSON into `output_file`.
+///
+/// The path must resolve inside one of `root_directories`; missing parent directories are
+/// created once the part of the path that exists is known to resolve inside them. Returns the final path and the number of bytes written.
+pub fn write_json_export<T: Serialize>(
+    result: &T,
+    output_file: &str,
+    root_directories: &[PathBuf],
+) -> Result<(PathBuf, u64), ServiceError> {
+    let output_path = resolve_output_path(output_file, root_directories)?;
+
+    if let Some(parent) = output_path.parent() {
+        validate_creatable_path(parent, root_directories)?;
+        std::fs::create_dir_all(parent).map_err(|e| io_error(e, parent))?;
+        // Re-check now that the directory exists, in case it changed in the meantime
+        validate_path_within_roots(parent, root_directories)?;
+    }
+
+    let json = serde_json::to_vec_pretty(result)?;
+    std::fs::write(&output_path, &json).map_err(|e| io_error(e, &output_path))?;
+
+    Ok((output_path, json.len() as u64))
+}
+
+/// Write a file search result to `output_file` and build the summary returned to the client.
+pub fn export_search_result(
+    result: &FileSearchResult,
+    output_file: &str,
+    root_directories: &[PathBuf],
+) -> Result<ExportedSearchResult, ServiceError> {
+    export_search_document(result, result, output_file, root_directories)
+}
+
+/// Write `document`, a rendering of `result` such as a SARIF log, to `output_file` and build
+/// the summary of `result` returned to the client.
+pub fn export_search_document<T: Serialize>(
+    result: &FileSearchResult,
+    document: &T,
+    output_file: &str,
+    root_directories: &[PathBuf],
+) -> Result<ExportedSearchResult, ServiceError> {
+    let (output_path, bytes_written) = write_json_export(document, output_file, root_directories)?;
+
+    Ok(ExportedSearchResult {
+        output_file: output_path.to_string_lossy().to_string(),
+        bytes_written,
+        total_files_found: result.total_files_found,
+        files_with_matches: result.matches.len(),
+        total_matches: result.matches.iter().map(|f| f.matches.len()).sum(),
+        next_cursor: result.next_cursor.clone(),
+    })
+}
+
+/// Write a scan result to `output_file` and build the summary returned to the client.
+pub fn export_scan_result(
+    result: &ScanResult,
+    output_file: &str,
+    root_directories: &[PathBuf],
+) -> Result<ExportedScanResult, ServiceError> {
+    let (output_path, bytes_written) = write_json_export(result, output_file, root_directories)?;
+
+    Ok(ExportedScanResult {
+        output_file: output_path.to_string_lossy().to_string(),
+        bytes_written,
+        total_findings: result.total_findings,
+        files_scanned: result.files_scanned,
+        rules_run: result.rules_run,
+        truncated_rules: result.truncated_rules.clone(),
+        severity_counts: result
+            .buckets
+            .iter()
+            .map(|bucket| (bucket.severity.clone(), bucket.count))
+            .collect(),
+    })
+}
+
+/// Copy `rules` into `<output_dir>/rules/` and make sure `<output_dir>/sgconfig.yml` lists
+/// that directory.
+///
+/// Rule files are copied verbatim. An existing rule file with different content is only
+/// replaced with `overwrite`; an existing sgconfig.yml that doesn't list `rules/` is only
+/// rewritten with `overwrite`, since rewriting drops its comments.
+pub fn export_rules_to_sgconfig(
+    rules: Vec<RuleInfo>,
+    param: &ExportToSgconfigParam,
+    root_directories: &[PathBuf],
+) -> Result<ExportToSgconfigResult, ServiceError> {
+    let output_dir = resolve_output_path(&param.output_dir, root_directories)?;
+    let rules_dir = output_dir.join(EXPORT_RULES_DIR);
+    validate_creatable_path(&rules_dir, root_directories)?;
+    std::fs::create_dir_all(&rules_dir).map_err(|e| io_error(e, &rules_dir))?;
+    validate_path_within_roots(&rules_dir, root_directories)?;
+
+    let sgconfig_path = output_dir.join("sgconfig.yml");
+    write_sgconfig(&sgconfig_path, param.overwrite)?;
+
+    let mut exported = Vec::new();
+    let mut skipped = Vec::new();
+    for mut rule in rules {
+        let content = std::fs::read_to_string(&rule.file_path)
+            .map_err(|e| io_error(e, Path::new(&rule.file_path)))?;
+        let target = rules_dir.join(format!("{}.yml", rule.id));
+        if !param.overwrite
+            && let Ok(existing) = std::fs::read_to_string(&target)
+            && existing != content
+        {
+            skipped.push(SkippedRule {
+                rule_id: rule.id,
+                reason: format!(
+                    "{} already exists with different content; pass overwrite to replace it",
+                    target.display()
+                ),
+            });
+            continue;
+        }
+        std::fs::write(&target, content).map_err(|e| io_error(e, &target))?;
+        rule.file_path = target.to_string_lossy().to_string();
+        exported.push(rule);
+    }
+
+    Ok(ExportToSgconfigResult {
+        sgconfig_path: sgconfig_path.to_string_lossy().to_string(),
+        rules_dir: rules_dir.to_string_lossy().to_string(),
+        exported,
+        skipped,
+    })
+}
+
+fn write_sgconfig(path: &Path, overwrite: bool) -> Result<(), ServiceError> {
+    let mut config = if path.exists() {
+        SgConfig::from_file(path)?
+    } else {
+        SgConfig::default()
+    };
+    if config.rule_dirs.iter().any(|dir| {
+        dir.components()
+            .filter(|c| *c != Component::CurDir)
+            .eq(Path::new(EXPORT_RULES_DIR).components())
+    }) {
+        return Ok(());
+    }
+    if path.exists() && !overwrite {
+        return Err(ServiceError::Internal(format!(
+            "{} does not list '{EXPORT_RULES_DIR}' in ruleDirs; pass overwrite to add it",
+            path.display()
+        )));
+    }
+
+    config.rule_dirs.push(PathBuf::from(EXPORT_RULES_DIR));
+    let yaml = serde_yaml::to_string(&config)?;
+    std::fs::write(path, yaml).map_err(|e| io_error(e, path))
+}
+
+fn io_error(err: std::io::Error, path: &Path) -> ServiceError {
+    ServiceError::FileIoError {
+        message: err.to_string(),
+        path: path.to_string_lossy().to_string(),
+    }
+}
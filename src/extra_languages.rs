@@ -0,0 +1,345 @@
+//! # Extra Languages
+//!
+//! Grammars that ast-grep-language does not bundle, each behind a cargo feature that is on by
+//! default:
+//!
+//! - `graphql`: GraphQL schemas and operations (`.graphql`, `.graphqls`, `.gql`)
+//! - `protobuf`: Protocol Buffers definitions (`.proto`)
+//!
+//! The pattern tools (`search`, `file_search`, `replace`, `file_replace`, `generate_ast`)
+//! resolve their `language` to an [`AnyLanguage`], which is either a bundled language or one
+//! of these, or a grammar registered at runtime (see [`crate::dynamic_languages`]). Rules
+//! still need a bundled language.
+//!
+//! Neither grammar accepts `$NAME` as an identifier, so metavariables are rewritten to `_NAME`
+//! before a pattern is parsed, the way ast-grep rewrites them to `µNAME` for Python or Rust.
+//! Lowercase names such as `$id` are left alone and still match GraphQL variables.
+
+use crate::dialect::{language_for_file, resolve_language};
+use crate::dynamic_languages::DynamicLanguage;
+use crate::errors::ServiceError;
+use ast_grep_core::matcher::PatternBuilder;
+use ast_grep_core::meta_var::MetaVariable;
+use ast_grep_core::tree_sitter::{LanguageExt, StrDoc, TSLanguage, TSRange};
+use ast_grep_core::{Language as _, Node, Pattern, PatternError};
+use ast_grep_language::SupportLang as Language;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Character metavariables are rewritten to before parsing
+const EXPANDO_CHAR: char = '_';
+
+/// A grammar compiled in by a cargo feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExtraLanguage {
+    #[cfg(feature = "graphql")]
+    GraphQl,
+    #[cfg(feature = "protobuf")]
+    Protobuf,
+}
+
+impl ExtraLanguage {
+    /// Every grammar enabled in this build
+    pub const ALL: &[ExtraLanguage] = &[
+        #[cfg(feature = "graphql")]
+        ExtraLanguage::GraphQl,
+        #[cfg(feature = "protobuf")]
+        ExtraLanguage::Protobuf,
+    ];
+
+    /// Name reported by `list_languages`
+    pub fn name(&self) -> &'static str {
+        match *self {
+            #[cfg(feature = "graphql")]
+            ExtraLanguage::GraphQl => "graphql",
+            #[cfg(feature = "protobuf")]
+            ExtraLanguage::Protobuf => "protobuf",
+        }
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        match *self {
+            #[cfg(feature = "graphql")]
+            ExtraLanguage::GraphQl => &["graphql", "gql"],
+            #[cfg(feature = "protobuf")]
+            ExtraLanguage::Protobuf => &["protobuf", "proto"],
+        }
+    }
+
+    /// File extensions parsed with this grammar
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match *self {
+            #[cfg(feature = "graphql")]
+            ExtraLanguage::GraphQl => &["graphql", "graphqls", "gql"],
+            #[cfg(feature = "protobuf")]
+            ExtraLanguage::Protobuf => &["proto"],
+        }
+    }
+
+    /// Marker of a comment running to the end of the line
+    pub fn line_comment(&self) -> &'static str {
+        match *self {
+            #[cfg(feature = "graphql")]
+            ExtraLanguage::GraphQl => "#",
+            #[cfg(feature = "protobuf")]
+            ExtraLanguage::Protobuf => "//",
+        }
+    }
+
+    /// Whether `path` has one of this grammar's extensions
+    pub fn matches_path(&self, path: &str) -> bool {
+        Self::from_path(path) == Some(*self)
+    }
+}
+
+impl FromStr for ExtraLanguage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let name = s.to_ascii_lowercase();
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|lang| lang.aliases().contains(&name.as_str()))
+            .ok_or_else(|| format!("{s} is not an extra language"))
+    }
+}
+
+impl fmt::Display for ExtraLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl ast_grep_core::Language for ExtraLanguage {
+    fn kind_to_id(&self, kind: &str) -> u16 {
+        self.get_ts_language().id_for_node_kind(kind, true)
+    }
+
+    fn field_to_id(&self, field: &str) -> Option<u16> {
+        self.get_ts_language()
+            .field_id_for_name(field)
+            .map(|id| id.get())
+    }
+
+    fn expando_char(&self) -> char {
+        EXPANDO_CHAR
+    }
+
+    fn pre_process_pattern<'q>(&self, query: &'q str) -> Cow<'q, str> {
+        Cow::Owned(expand_meta_vars(query, EXPANDO_CHAR))
+    }
+
+    fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?;
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|lang| lang.extensions().contains(&extension))
+    }
+
+    fn build_pattern(&self, builder: &PatternBuilder) -> Result<Pattern, PatternError> {
+        builder.build(|src| StrDoc::try_new(src, *self))
+    }
+}
+
+impl LanguageExt for ExtraLanguage {
+    fn get_ts_language(&self) -> TSLanguage {
+        match *self {
+            #[cfg(feature = "graphql")]
+            ExtraLanguage::GraphQl => tree_sitter_graphql::LANGUAGE.into(),
+            #[cfg(feature = "protobuf")]
+            ExtraLanguage::Protobuf => tree_sitter_proto::LANGUAGE.into(),
+        }
+    }
+}
+
+/// Replace the `$` of `$NAME`, `$$NAME`, `$_` and `$$$` with `expando`, as ast-grep-language
+/// does for its own languages
+pub(crate) fn expand_meta_vars(query: &str, expando: char) -> String {
+    let mut expanded = String::with_capacity(query.len());
+    let mut dollars = 0;
+    for c in query.chars() {
+        if c == '$' {
+            dollars += 1;
+            continue;
+        }
+        let is_meta_var = matches!(c, 'A'..='Z' | '_') || dollars == 3;
+        let sigil = if is_meta_var { expando } else { '$' };
+        expanded.extend(std::iter::repeat_n(sigil, dollars));
+        dollars = 0;
+        expanded.push(c);
+    }
+    let sigil = if dollars == 3 { expando } else { '$' };
+    expanded.extend(std::iter::repeat_n(sigil, dollars));
+    expanded
+}
+
+/// A bundled ast-grep language, an [`ExtraLanguage`] or a [`DynamicLanguage`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnyLanguage {
+    Builtin(Language),
+    Extra(ExtraLanguage),
+    Dynamic(DynamicLanguage),
+}
+
+macro_rules! delegate {
+    ($self:expr, $lang:ident => $body:expr) => {
+        match $self {
+            AnyLanguage::Builtin($lang) => $body,
+            AnyLanguage::Extra($lang) => $body,
+            AnyLanguage::Dynamic($lang) => $body,
+        }
+    };
+}
+
+impl AnyLanguage {
+    /// Resolve `language` like [`resolve_language`], falling back to an extra or a dynamic
+    /// language when no dialect is given
+    pub fn resolve(language: &str, dialect: Option<&str>) -> Result<Self, ServiceError> {
+        match resolve_language(language, dialect) {
+            Ok(lang) => Ok(AnyLanguage::Builtin(lang)),
+            Err(e) => match dialect {
+                None => AnyLanguage::from_str(language).map_err(|_| e),
+                Some(_) => Err(e),
+            },
+        }
+    }
+
+    /// The grammar to parse `file_path` with; see [`language_for_file`]
+    pub fn for_file(self, file_path: &str) -> Self {
+        match self {
+            AnyLanguage::Builtin(lang) => AnyLanguage::Builtin(language_for_file(lang, file_path)),
+            other => other,
+        }
+    }
+
+    /// Whether `path` is parsed with this grammar by extension
+    pub fn matches_path(self, path: &str) -> bool {
+        AnyLanguage::from_path(path) == Some(self)
+    }
+}
+
+impl FromStr for AnyLanguage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        Language::from_str(s)
+            .map(AnyLanguage::Builtin)
+            .or_else(|_| ExtraLanguage::from_str(s).map(AnyLanguage::Extra))
+            .or_else(|_| DynamicLanguage::from_str(s).map(AnyLanguage::Dynamic))
+            .map_err(|_| format!("{s} is not a supported language"))
+    }
+}
+
+impl fmt::Display for AnyLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        delegate!(self, lang => lang.fmt(f))
+    }
+}
+
+impl ast_grep_core::Language for AnyLanguage {
+    fn pre_process_pattern<'q>(&self, query: &'q str) -> Cow<'q, str> {
+        delegate!(self, lang => lang.pre_process_pattern(query))
+    }
+
+    fn meta_var_char(&self) -> char {
+        delegate!(self, lang => lang.meta_var_char())
+    }
+
+    fn expando_char(&self) -> char {
+        delegate!(self, lang => lang.expando_char())
+    }
+
+    fn extract_meta_var(&self, source: &str) -> Option<MetaVariable> {
+        delegate!(self, lang => lang.extract_meta_var(source))
+    }
+
+    fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        Language::from_path(path.as_ref())
+            .map(AnyLanguage::Builtin)
+            .or_else(|| ExtraLanguage::from_path(path.as_ref()).map(AnyLanguage::Extra))
+            .or_else(|| DynamicLanguage::from_path(path).map(AnyLanguage::Dynamic))
+    }
+
+    fn kind_to_id(&self, kind: &str) -> u16 {
+        delegate!(self, lang => lang.kind_to_id(kind))
+    }
+
+    fn field_to_id(&self, field: &str) -> Option<u16> {
+        delegate!(self, lang => lang.field_to_id(field))
+    }
+
+    fn build_pattern(&self, builder: &PatternBuilder) -> Result<Pattern, PatternError> {
+        delegate!(self, lang => lang.build_pattern(builder))
+    }
+}
+
+impl LanguageExt for AnyLanguage {
+    fn get_ts_language(&self) -> TSLanguage {
+        delegate!(self, lang => lang.get_ts_language())
+    }
+
+    fn injectable_languages(&self) -> Option<&'static [&'static str]> {
+        delegate!(self, lang => lang.injectable_languages())
+    }
+
+    fn extract_injections<L: LanguageExt>(
+        &self,
+        root: Node<StrDoc<L>>,
+    ) -> HashMap<String, Vec<TSRange>> {
+        delegate!(self, lang => lang.extract_injections(root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_meta_vars() {
+        assert_eq!(
+            expand_meta_vars("query $NAME($id: ID) { $$$BODY $_ }", '_'),
+            "query _NAME($id: ID) { ___BODY __ }"
+        );
+        assert_eq!(expand_meta_vars("f($$$)", '_'), "f(___)");
+    }
+
+    #[test]
+    fn test_resolve_prefers_bundled_languages() {
+        assert_eq!(
+            AnyLanguage::resolve("rust", None).unwrap(),
+            AnyLanguage::Builtin(Language::Rust)
+        );
+        assert!(AnyLanguage::resolve("cobol", None).is_err());
+    }
+
+    #[cfg(feature = "graphql")]
+    #[test]
+    fn test_graphql_aliases_and_extensions() {
+        assert_eq!(
+            AnyLanguage::resolve("GQL", None).unwrap(),
+            AnyLanguage::Extra(ExtraLanguage::GraphQl)
+        );
+        assert!(AnyLanguage::resolve("graphql", Some("tsx")).is_err());
+        assert!(ExtraLanguage::GraphQl.matches_path("schema/user.graphqls"));
+        assert!(!ExtraLanguage::GraphQl.matches_path("schema/user.ts"));
+    }
+
+    #[cfg(feature = "protobuf")]
+    #[test]
+    fn test_protobuf_pattern_matches_message() {
+        let lang = ExtraLanguage::Protobuf;
+        let ast = lang.ast_grep("syntax = \"proto3\";\nmessage User { string name = 1; }\n");
+        let pattern = Pattern::new("message $NAME { $$$ }", lang);
+        let names: Vec<String> = ast
+            .root()
+            .find_all(pattern)
+            .filter_map(|m| Some(m.get_env().get_match("NAME")?.text().to_string()))
+            .collect();
+        assert_eq!(names, vec!["User"]);
+    }
+}
@@ -0,0 +1,77 @@
+//! # Hash-Checked File Fetch
+//!
+//! Search results carry a `file_hash` for every file with matches. `get_file_by_hash` returns a
+//! file's content only while its hash is still the one the client saw, so context fetched for an
+//! earlier match can't silently come from a file that was edited in between.
+
+use crate::errors::ServiceError;
+use crate::path_validation::resolve_path_pattern;
+use crate::types::{GetFileByHashParam, GetFileByHashResult};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+/// The `sha256:<hex>` hash reported as `file_hash` for `content`
+pub fn content_hash(content: &str) -> String {
+    format!("sha256:{}", hex::encode(Sha256::digest(content.as_bytes())))
+}
+
+/// Read `param.file_path` and return the requested lines if its content still hashes to
+/// `param.file_hash`
+pub fn get_file_by_hash(
+    param: &GetFileByHashParam,
+    root_directories: &[PathBuf],
+    max_file_size: u64,
+) -> Result<GetFileByHashResult, ServiceError> {
+    let path = resolve_path_pattern(&param.file_path, root_directories)?
+        .into_iter()
+        .find(|path| path.is_file())
+        .ok_or_else(|| ServiceError::FileNotFound(PathBuf::from(&param.file_path)))?;
+    let size = std::fs::metadata(&path)
+        .map_err(|e| file_error(e, &path))?
+        .len();
+    if size > max_file_size {
+        return Err(ServiceError::Internal(format!(
+            "{} is {size} bytes, larger than the {max_file_size} byte limit",
+            path.display()
+        )));
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| file_error(e, &path))?;
+
+    let file_hash = content_hash(&content);
+    let total_lines = content.lines().count();
+    let unchanged = file_hash == param.file_hash;
+    let start_line = param.start_line.unwrap_or(0).min(total_lines);
+    let end_line = param
+        .end_line
+        .map_or(total_lines, |end| (end + 1).min(total_lines))
+        .max(start_line);
+    let content = unchanged.then(|| {
+        if param.start_line.is_none() && param.end_line.is_none() {
+            content
+        } else {
+            content
+                .lines()
+                .skip(start_line)
+                .take(end_line - start_line)
+                .map(|line| format!("{line}\n"))
+                .collect()
+        }
+    });
+
+    Ok(GetFileByHashResult {
+        file_path: path.to_string_lossy().to_string(),
+        file_hash,
+        unchanged,
+        content,
+        start_line,
+        end_line: end_line.saturating_sub(1),
+        total_lines,
+    })
+}
+
+fn file_error(e: std::io::Error, path: &std::path::Path) -> ServiceError {
+    ServiceError::FileIoError {
+        message: e.to_string(),
+        path: path.to_string_lossy().to_string(),
+    }
+}
@@ -0,0 +1,363 @@
+//! # Formatter Verification
+//!
+//! Optional check run after a replacement: the original and rewritten code are both piped
+//! through the language's formatter (rustfmt, prettier, black, ...) and the formatted versions
+//! are compared line by line. Every changed line has to hold part of one of the replacement's
+//! edits, so a template whose output the formatter reflows into neighbouring code (a missing
+//! semicolon joining two statements, a brace closing a block early, broken indentation) is
+//! reported instead of slipping through. Edits are located in the formatted code ignoring
+//! whitespace, quote style and `;`/`,` separators, which formatters are free to change.
+//!
+//! The check is skipped, not failed, when no formatter is configured or installed for the
+//! language, or when the formatter rejects the original code.
+//!
+//! The same formatters can also reformat the rewrite itself (`format_after_replace`). Only the
+//! rewritten regions take the formatter's style: the formatted rewrite is merged back into the
+//! original with a three-way merge against the formatted original, so code the replacement
+//! didn't touch keeps its formatting even in a project that isn't formatter-clean.
+
+use crate::merge::{changed_hunks, merge3};
+use crate::types::ChangeResult;
+use ast_grep_language::SupportLang as Language;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// How long a formatter may run on one input
+const FORMATTER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Placeholder in formatter commands replaced with the file name, for formatters that pick a
+/// parser from the extension
+pub const FILE_PLACEHOLDER: &str = "{file}";
+
+/// Outcome of verifying a replacement with a formatter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormatCheck {
+    /// Formatter command that was run, or would have been
+    pub formatter: String,
+    pub status: FormatCheckStatus,
+    /// Why the check failed or was skipped
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issues: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FormatCheckStatus {
+    /// Formatted old and new code differ only where the replacement edited
+    Passed,
+    /// The rewritten code was rejected by the formatter or changed outside the edits
+    Failed,
+    /// No usable formatter, or the original code could not be formatted
+    Skipped,
+}
+
+/// Formatter command for `lang` when the configuration doesn't name one
+pub fn default_formatter(lang: Language) -> Option<Vec<String>> {
+    let command: &[&str] = match lang {
+        Language::Rust => &["rustfmt", "--edition", "2024"],
+        Language::JavaScript
+        | Language::TypeScript
+        | Language::Tsx
+        | Language::Css
+        | Language::Html
+        | Language::Json
+        | Language::Yaml => &["prettier", "--stdin-filepath", FILE_PLACEHOLDER],
+        Language::Python => &["black", "--quiet", "-"],
+        Language::Go => &["gofmt"],
+        Language::C | Language::Cpp => &["clang-format", "--assume-filename={file}"],
+        _ => return None,
+    };
+    Some(command.iter().map(|arg| arg.to_string()).collect())
+}
+
+/// The formatter for `lang`: a `formatters` entry whose key names the language, or the default
+pub fn formatter_for(
+    formatters: &HashMap<String, Vec<String>>,
+    lang: Language,
+) -> Option<Vec<String>> {
+    formatters
+        .iter()
+        .find(|(key, _)| Language::from_str(key).ok() == Some(lang))
+        .map(|(_, command)| command.clone())
+        .or_else(|| default_formatter(lang))
+}
+
+/// A file name with the extension ast-grep associates with `lang`, for code without a path
+pub fn placeholder_file_name(lang: Language) -> String {
+    let extension = match lang {
+        Language::JavaScript => "js",
+        Language::TypeScript => "ts",
+        Language::Tsx => "tsx",
+        Language::Css => "css",
+        Language::Html => "html",
+        Language::Json => "json",
+        Language::Yaml => "yaml",
+        Language::Python => "py",
+        Language::Rust => "rs",
+        Language::Go => "go",
+        Language::C => "c",
+        Language::Cpp => "cpp",
+        _ => "txt",
+    };
+    format!("input.{extension}")
+}
+
+/// Format `original` and `rewritten` with `command` and check that they differ only where
+/// `edits` changed the code
+pub async fn verify_formatting(
+    command: &[String],
+    file_name: &str,
+    original: &str,
+    rewritten: &str,
+    edits: &[ChangeResult],
+) -> FormatCheck {
+    let command: Vec<String> = command
+        .iter()
+        .map(|arg| arg.replace(FILE_PLACEHOLDER, file_name))
+        .collect();
+    let formatter = command.join(" ");
+    let check = |status, issues| FormatCheck {
+        formatter: formatter.clone(),
+        status,
+        issues,
+    };
+
+    let formatted_original = match run_formatter(&command, original).await {
+        Ok(formatted) => formatted,
+        Err(reason) => return check(FormatCheckStatus::Skipped, vec![reason]),
+    };
+    let formatted_rewritten = match run_formatter(&command, rewritten).await {
+        Ok(formatted) => formatted,
+        Err(reason) => {
+            return check(
+                FormatCheckStatus::Failed,
+                vec![format!(
+                    "the formatter rejected the rewritten code: {reason}"
+                )],
+            );
+        }
+    };
+
+    let old_lines: Vec<&str> = formatted_original.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = formatted_rewritten.split_inclusive('\n').collect();
+    let old_covered = covered_lines(&old_lines, edits.iter().map(|edit| edit.old_text.as_str()));
+    let new_covered = covered_lines(&new_lines, edits.iter().map(|edit| edit.new_text.as_str()));
+
+    let mut issues = Vec::new();
+    for (old_range, new_range) in changed_hunks(&old_lines, &new_lines) {
+        let stray_old = old_range.clone().find(|&line| !old_covered[line]);
+        let stray_new = new_range.clone().find(|&line| !new_covered[line]);
+        if let Some(line) = stray_new {
+            issues.push(format!(
+                "formatted line {} changed outside the replaced code: `{}`",
+                line + 1,
+                new_lines[line].trim()
+            ));
+        } else if let Some(line) = stray_old {
+            issues.push(format!(
+                "formatted line {} of the original changed outside the replaced code: `{}`",
+                line + 1,
+                old_lines[line].trim()
+            ));
+        }
+    }
+
+    match issues.is_empty() {
+        true => check(FormatCheckStatus::Passed, issues),
+        false => check(FormatCheckStatus::Failed, issues),
+    }
+}
+
+/// Format the regions of `rewritten` that differ from `original` with `command`, or say why
+/// they can't be
+pub async fn format_rewritten(
+    command: &[String],
+    file_name: &str,
+    original: &str,
+    rewritten: &str,
+) -> Result<String, String> {
+    let command: Vec<String> = command
+        .iter()
+        .map(|arg| arg.replace(FILE_PLACEHOLDER, file_name))
+        .collect();
+    let formatted_rewritten = run_formatter(&command, rewritten)
+        .await
+        .map_err(|reason| format!("the formatter rejected the rewritten code: {reason}"))?;
+    let formatted_original = run_formatter(&command, original)
+        .await
+        .map_err(|reason| format!("the formatter rejected the original code: {reason}"))?;
+    if formatted_original == original {
+        return Ok(formatted_rewritten);
+    }
+
+    // The original's own style is one side of the merge, the formatted edits the other
+    let merged = merge3(&formatted_original, original, &formatted_rewritten);
+    match merged.is_clean() {
+        true => Ok(merged.text),
+        false => Err(format!(
+            "formatting the rewrite would also reformat unformatted code next to it ({} places)",
+            merged.conflicts
+        )),
+    }
+}
+
+/// Whole-line edits turning `original` into `rewritten`, for reporting a formatted rewrite
+pub fn line_edits(original: &str, rewritten: &str) -> Vec<ChangeResult> {
+    let old_lines: Vec<&str> = original.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = rewritten.split_inclusive('\n').collect();
+    changed_hunks(&old_lines, &new_lines)
+        .into_iter()
+        .map(|(old_range, new_range)| {
+            let (end_line, end_col) = match old_lines[old_range.clone()].last() {
+                Some(last) if !last.ends_with('\n') => (old_range.end - 1, last.chars().count()),
+                _ => (old_range.end, 0),
+            };
+            let start_byte: usize = old_lines[..old_range.start].iter().map(|l| l.len()).sum();
+            let old_text = old_lines[old_range.clone()].concat();
+            ChangeResult {
+                start_line: old_range.start,
+                end_line,
+                start_col: 0,
+                end_col,
+                start_byte,
+                end_byte: start_byte + old_text.len(),
+                old_text,
+                new_text: new_lines[new_range].concat(),
+            }
+        })
+        .collect()
+}
+
+/// For each line, whether it holds part of one of `texts`. Texts are located ignoring
+/// whitespace, quote style and separators, since the formatter may have changed those
+fn covered_lines<'a>(lines: &[&str], texts: impl Iterator<Item = &'a str>) -> Vec<bool> {
+    let mut haystack = String::new();
+    let mut line_of_byte = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let normalized = squash(line);
+        line_of_byte.extend(std::iter::repeat_n(index, normalized.len()));
+        haystack.push_str(&normalized);
+    }
+
+    let mut covered = vec![false; lines.len()];
+    for text in texts {
+        let needle = squash(text);
+        if needle.is_empty() {
+            continue;
+        }
+        for (start, found) in haystack.match_indices(&needle) {
+            for &line in &line_of_byte[start..start + found.len()] {
+                covered[line] = true;
+            }
+        }
+    }
+    covered
+}
+
+/// `text` without whitespace, with quotes unified and separators dropped
+fn squash(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace() && *c != ';' && *c != ',')
+        .map(|c| if c == '\'' || c == '`' { '"' } else { c })
+        .collect()
+}
+
+/// Pipe `code` through the formatter and return its output, or why it failed
+async fn run_formatter(command: &[String], code: &str) -> Result<String, String> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| "the formatter command is empty".to_string())?;
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("could not run `{program}`: {e}"))?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = code.to_string();
+    let writer = tokio::spawn(async move {
+        // A formatter that exits early closes stdin; its exit status reports why
+        let _ = stdin.write_all(input.as_bytes()).await;
+    });
+
+    let output = tokio::time::timeout(FORMATTER_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("`{program}` timed out"))?
+        .map_err(|e| format!("`{program}` failed: {e}"))?;
+    let _ = writer.await;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().find(|line| !line.trim().is_empty());
+        return Err(format!(
+            "`{program}` exited with {}{}",
+            output.status,
+            reason
+                .map(|line| format!(": {}", line.trim()))
+                .unwrap_or_default()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|_| format!("`{program}` printed invalid UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_squash_ignores_formatting_choices() {
+        assert_eq!(squash("foo('a', b);\n"), squash("foo(\"a\" b)"));
+        assert_ne!(squash("foo(a)"), squash("foo(b)"));
+    }
+
+    #[test]
+    fn test_covered_lines_follow_reflowed_text() {
+        let lines = [
+            "fn main() {\n",
+            "    call(a, b);\n",
+            "    other();\n",
+            "}\n",
+        ];
+        let covered = covered_lines(&lines, ["call(a,\n  b)", "missing()"].into_iter());
+        assert_eq!(covered, vec![false, true, false, false]);
+    }
+
+    #[test]
+    fn test_line_edits_cover_whole_lines() {
+        let edits = line_edits("a\nb\nc\nd", "a\nB\nc\nd\ne\n");
+        assert_eq!(edits.len(), 2);
+        assert_eq!((edits[0].start_line, edits[0].end_line), (1, 2));
+        assert_eq!((edits[0].start_byte, edits[0].end_byte), (2, 4));
+        assert_eq!(
+            (edits[0].old_text.as_str(), edits[0].new_text.as_str()),
+            ("b\n", "B\n")
+        );
+        // The last line has no newline, so the edit ends at its last column
+        assert_eq!(
+            (edits[1].start_line, edits[1].end_line, edits[1].end_col),
+            (3, 3, 1)
+        );
+        assert_eq!(edits[1].new_text, "d\ne\n");
+    }
+
+    #[test]
+    fn test_formatter_lookup() {
+        let configured = HashMap::from([("js".to_string(), vec!["biome".to_string()])]);
+        assert_eq!(
+            formatter_for(&configured, Language::JavaScript),
+            Some(vec!["biome".to_string()])
+        );
+        assert_eq!(
+            formatter_for(&configured, Language::Rust).unwrap()[0],
+            "rustfmt"
+        );
+        assert_eq!(formatter_for(&configured, Language::Lua), None);
+    }
+}
@@ -0,0 +1,173 @@
+//! # Git Integration
+//!
+//! Thin wrappers around the `git` command line for metadata that the syntax tree can't provide,
+//! such as who last touched a line, and for reading files as they were at a past revision.
+
+use crate::errors::ServiceError;
+use crate::types::BlameInfo;
+use std::path::Path;
+use std::process::Command;
+
+/// Blame every line of `file_path`, indexed by 0-based line number.
+///
+/// Returns `None` when git is unavailable or the file isn't tracked in a repository.
+pub fn blame_file(file_path: &Path) -> Option<Vec<BlameInfo>> {
+    let dir = file_path.parent()?;
+    let output = Command::new("git")
+        .arg("blame")
+        .arg("--line-porcelain")
+        .arg("--")
+        .arg(file_path.file_name()?)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(parse_line_porcelain(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+/// Run git in `dir`, returning stdout or the trimmed stderr as an error
+fn run_git(dir: &Path, args: &[&str]) -> Result<Vec<u8>, ServiceError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| ServiceError::Internal(format!("Failed to run git: {e}")))?;
+    match output.status.success() {
+        true => Ok(output.stdout),
+        false => Err(ServiceError::Internal(format!(
+            "git {} failed in {}: {}",
+            args[0],
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))),
+    }
+}
+
+/// Resolve a commit-ish such as a tag, branch or abbreviated hash to a full commit id in the
+/// repository containing `dir`.
+pub fn resolve_revision(dir: &Path, revision: &str) -> Result<String, ServiceError> {
+    // A leading dash would be read as an option rather than a revision
+    if revision.is_empty() || revision.starts_with('-') {
+        return Err(ServiceError::Internal(format!(
+            "Invalid revision '{revision}'"
+        )));
+    }
+    let stdout = run_git(
+        dir,
+        &[
+            "rev-parse",
+            "--verify",
+            "--end-of-options",
+            &format!("{revision}^{{commit}}"),
+        ],
+    )?;
+    Ok(String::from_utf8_lossy(&stdout).trim().to_string())
+}
+
+/// List the files under `dir` as they were at `commit`, as paths relative to `dir` with their
+/// sizes in bytes. Submodules and symlinks are left out.
+pub fn list_files_at(dir: &Path, commit: &str) -> Result<Vec<(String, u64)>, ServiceError> {
+    let stdout = run_git(dir, &["ls-tree", "-r", "-l", "-z", commit])?;
+    Ok(parse_ls_tree(&String::from_utf8_lossy(&stdout)))
+}
+
+/// Parse `git ls-tree -r -l -z` output: `<mode> <type> <object> <size>\t<path>` per entry
+fn parse_ls_tree(output: &str) -> Vec<(String, u64)> {
+    output
+        .split('\0')
+        .filter_map(|entry| {
+            let (header, path) = entry.split_once('\t')?;
+            let mut fields = header.split_whitespace();
+            let mode = fields.next()?;
+            if fields.next()? != "blob" || mode == "120000" {
+                return None;
+            }
+            let size = fields.nth(1)?.parse().ok()?;
+            Some((path.to_string(), size))
+        })
+        .collect()
+}
+
+/// Read a file as it was at `commit`, by its path relative to `dir`
+pub fn read_file_at(dir: &Path, commit: &str, path: &str) -> Result<String, ServiceError> {
+    let stdout = run_git(dir, &["cat-file", "blob", &format!("{commit}:./{path}")])?;
+    String::from_utf8(stdout).map_err(|_| ServiceError::FileIoError {
+        message: format!("File is not valid UTF-8 at revision {commit}"),
+        path: path.to_string(),
+    })
+}
+
+/// Parse `git blame --line-porcelain` output, where every line carries a full header block
+fn parse_line_porcelain(output: &str) -> Vec<BlameInfo> {
+    let mut lines = Vec::new();
+    let mut current: Option<BlameInfo> = None;
+
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            // The source line itself terminates the header block
+            lines.extend(current.take());
+        } else if let Some(entry) = current.as_mut() {
+            if let Some(author) = line.strip_prefix("author ") {
+                entry.author = author.to_string();
+            } else if let Some(mail) = line.strip_prefix("author-mail ") {
+                entry.author_mail = mail.trim_matches(['<', '>']).to_string();
+            }
+        } else if let Some(commit) = line.split(' ').next() {
+            current = Some(BlameInfo {
+                commit: commit.to_string(),
+                author: String::new(),
+                author_mail: String::new(),
+            });
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line_porcelain() {
+        let output = "\
+4c2d9a1e 1 1 2
+author Ada Lovelace
+author-mail <ada@example.com>
+author-time 1700000000
+summary Initial commit
+filename app.js
+\t// TODO: first
+4c2d9a1e 2 2
+author Ada Lovelace
+author-mail <ada@example.com>
+filename app.js
+\tlet x = 1;
+";
+        let lines = parse_line_porcelain(output);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].commit, "4c2d9a1e");
+        assert_eq!(lines[0].author, "Ada Lovelace");
+        assert_eq!(lines[1].author_mail, "ada@example.com");
+    }
+
+    #[test]
+    fn test_parse_ls_tree() {
+        let output = "100644 blob 78981922613b2afb6025042ff6bd878ac1994e85       2\td/x.js\0\
+120000 blob 1f2d3c4b5a69788796a5b4c3d2e1f0a9b8c7d6e5      10\tlink.js\0\
+160000 commit 0a1b2c3d4e5f60718293a4b5c6d7e8f901234567       -\tvendor/lib\0\
+100755 blob 0f1e2d3c4b5a69788796a5b4c3d2e1f0a9b8c7d6     120\tbin/run with space.sh\0";
+        assert_eq!(
+            parse_ls_tree(output),
+            vec![
+                ("d/x.js".to_string(), 2),
+                ("bin/run with space.sh".to_string(), 120)
+            ]
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! # Health Check
+//!
+//! Checks the setup the tools depend on, for `health_check` and the `doctor` subcommand: the
+//! root directories exist and can be listed, the directories the server writes to (rules,
+//! pattern coverage snapshots and undo records) can be written, and every advertised grammar
+//! parses. Directories that don't exist yet are a warning when they can be created. Nothing is
+//! created: writability is probed with a file that is removed right away.
+
+use crate::config::ServiceConfig;
+use crate::extra_languages::AnyLanguage;
+use crate::types::{CheckStatus, HealthCheck, HealthCheckResult};
+use ast_grep_core::tree_sitter::LanguageExt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Run every check against `config`, parsing an empty file with each of `languages`
+pub fn health_check(config: &ServiceConfig, languages: &[String]) -> HealthCheckResult {
+    let mut checks: Vec<HealthCheck> = config
+        .root_directories
+        .iter()
+        .map(|root| check_root(root))
+        .collect();
+    checks.push(match &config.rules_database {
+        Some(database) => check_writable_file("rules_database", database),
+        None => check_writable_dir("rules_directory", &config.rules_directory),
+    });
+    checks.push(check_writable_dir(
+        "coverage_directory",
+        &config.coverage_directory,
+    ));
+    checks.push(check_writable_dir("undo_directory", &config.undo_directory));
+    checks.push(check_grammars(languages));
+
+    let errors = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Error)
+        .count();
+    let warnings = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Warning)
+        .count();
+    HealthCheckResult {
+        healthy: errors == 0,
+        errors,
+        warnings,
+        checks,
+    }
+}
+
+fn check(name: &str, target: &Path, status: CheckStatus, message: String) -> HealthCheck {
+    HealthCheck {
+        check: name.to_string(),
+        target: Some(target.to_string_lossy().into_owned()),
+        status,
+        message,
+    }
+}
+
+/// A root directory passes when it is a directory whose entries can be listed
+fn check_root(root: &Path) -> HealthCheck {
+    let (status, message) = match std::fs::metadata(root) {
+        Err(e) => (CheckStatus::Error, format!("Can't be read: {e}")),
+        Ok(metadata) if !metadata.is_dir() => {
+            (CheckStatus::Error, "Is not a directory".to_string())
+        }
+        Ok(_) => match std::fs::read_dir(root) {
+            Err(e) => (CheckStatus::Error, format!("Can't be listed: {e}")),
+            Ok(entries) => (
+                CheckStatus::Ok,
+                format!("Readable, {} entries", entries.count()),
+            ),
+        },
+    };
+    check("root_directory", root, status, message)
+}
+
+/// `directory` passes when a file can be written in it, or in the nearest existing directory
+/// above it when it doesn't exist yet
+fn check_writable_dir(name: &str, directory: &Path) -> HealthCheck {
+    if directory.is_dir() {
+        return match probe_write(directory) {
+            Ok(()) => check(name, directory, CheckStatus::Ok, "Writable".to_string()),
+            Err(e) => check(
+                name,
+                directory,
+                CheckStatus::Error,
+                format!("Not writable: {e}"),
+            ),
+        };
+    }
+    if directory.exists() {
+        return check(
+            name,
+            directory,
+            CheckStatus::Error,
+            "Exists but is not a directory".to_string(),
+        );
+    }
+    let Some(ancestor) = directory.ancestors().skip(1).find(|parent| parent.is_dir()) else {
+        return check(
+            name,
+            directory,
+            CheckStatus::Error,
+            "Doesn't exist and has no existing parent directory".to_string(),
+        );
+    };
+    match probe_write(ancestor) {
+        Ok(()) => check(
+            name,
+            directory,
+            CheckStatus::Warning,
+            format!(
+                "Doesn't exist yet; it will be created in {} on first use",
+                ancestor.display()
+            ),
+        ),
+        Err(e) => check(
+            name,
+            directory,
+            CheckStatus::Error,
+            format!(
+                "Doesn't exist and can't be created in {}: {e}",
+                ancestor.display()
+            ),
+        ),
+    }
+}
+
+/// `file` passes when it is writable, or when its directory is and it doesn't exist yet
+fn check_writable_file(name: &str, file: &Path) -> HealthCheck {
+    if file.is_file() {
+        return match std::fs::OpenOptions::new().append(true).open(file) {
+            Ok(_) => check(name, file, CheckStatus::Ok, "Writable".to_string()),
+            Err(e) => check(name, file, CheckStatus::Error, format!("Not writable: {e}")),
+        };
+    }
+    let directory = file.parent().unwrap_or(Path::new("."));
+    let mut result = check_writable_dir(name, directory);
+    result.target = Some(file.to_string_lossy().into_owned());
+    if result.status == CheckStatus::Ok {
+        result.status = CheckStatus::Warning;
+        result.message = "Doesn't exist yet; it will be created on first use".to_string();
+    }
+    result
+}
+
+/// Write and remove a probe file in `directory`
+fn probe_write(directory: &Path) -> std::io::Result<()> {
+    let probe = directory.join(format!(".ast-grep-mcp-health-{}", std::process::id()));
+    std::fs::write(&probe, b"")?;
+    std::fs::remove_file(&probe)
+}
+
+/// Every grammar passes when it parses an empty file without panicking
+fn check_grammars(languages: &[String]) -> HealthCheck {
+    let failures: Vec<String> = languages
+        .iter()
+        .filter_map(|name| {
+            let loaded = AnyLanguage::from_str(name).and_then(|lang| {
+                std::panic::catch_unwind(|| lang.ast_grep("").root().kind().to_string())
+                    .map_err(|_| "the grammar panicked while parsing".to_string())
+            });
+            loaded.err().map(|e| format!("{name} ({e})"))
+        })
+        .collect();
+    let (status, message) = match failures.is_empty() {
+        true => (
+            CheckStatus::Ok,
+            format!("All {} grammars load", languages.len()),
+        ),
+        false => (
+            CheckStatus::Error,
+            format!(
+                "{} of {} grammars failed to load: {}",
+                failures.len(),
+                languages.len(),
+                failures.join(", ")
+            ),
+        ),
+    };
+    HealthCheck {
+        check: "grammars".to_string(),
+        target: None,
+        status,
+        message,
+    }
+}
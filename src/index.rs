@@ -0,0 +1,304 @@
+//! # File Index
+//!
+//! An optional in-memory index of the files under the root directories, for interactive
+//! sessions that search the same tree over and over (see `--index`). Each root's file list is
+//! walked once, on first use, with the server's default walk settings, and the syntax tree of
+//! every file a search parses is kept.
+//!
+//! With the `watch` feature the root directories are watched while the index is built: an
+//! edited file drops its tree and gets its new size, a deleted one leaves the list, and a
+//! created or renamed one has the list walked again on next use, as only a walk knows whether
+//! `.gitignore` or the pruned directories leave it out. A kept tree is only reused for content
+//! identical to what it was parsed from, so a missed event costs a parse, never a wrong match.
+
+use crate::config::ServiceConfig;
+use crate::types::{IndexStatus, WalkOptions};
+use crate::walk::{WalkSettings, walk_files};
+use ast_grep_core::AstGrep;
+use ast_grep_core::tree_sitter::StrDoc;
+use ast_grep_language::SupportLang as Language;
+use lru::LruCache;
+use std::collections::{BTreeMap, HashMap};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Syntax trees kept at most; the least recently searched file's goes first
+const TREE_CAPACITY: usize = 5000;
+
+type Tree = Arc<AstGrep<StrDoc<Language>>>;
+
+/// File lists and syntax trees of the root directories, shared between clones of the service
+#[derive(Clone)]
+pub struct FileIndex {
+    roots: Vec<PathBuf>,
+    walk: WalkSettings,
+    state: Arc<Mutex<IndexState>>,
+    #[cfg(feature = "watch")]
+    watcher: Arc<Mutex<Option<notify::RecommendedWatcher>>>,
+}
+
+struct IndexState {
+    /// Size of each file under each root, or `None` until the roots are walked (again)
+    files: Option<HashMap<PathBuf, BTreeMap<PathBuf, u64>>>,
+    trees: LruCache<PathBuf, (Language, Tree)>,
+    builds: usize,
+    last_build: Option<Duration>,
+    file_events: usize,
+    cache_hits: usize,
+    cache_misses: usize,
+}
+
+impl FileIndex {
+    /// An empty index of the root directories of `config`, walked as its defaults allow
+    pub fn new(config: &ServiceConfig) -> Self {
+        let capacity = NonZeroUsize::new(TREE_CAPACITY).unwrap();
+        Self {
+            roots: config.root_directories.clone(),
+            walk: config.walk_settings(&WalkOptions::default()),
+            state: Arc::new(Mutex::new(IndexState {
+                files: None,
+                trees: LruCache::new(capacity),
+                builds: 0,
+                last_build: None,
+                file_events: 0,
+                cache_hits: 0,
+                cache_misses: 0,
+            })),
+            #[cfg(feature = "watch")]
+            watcher: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// The walk settings the file lists were taken with
+    pub fn walk(&self) -> &WalkSettings {
+        &self.walk
+    }
+
+    /// The files under `root` with their sizes, walking the roots first if they haven't been,
+    /// or `None` when `root` isn't one of the indexed root directories
+    pub fn files_under(&self, root: &Path) -> Option<Vec<(PathBuf, u64)>> {
+        if !self.roots.iter().any(|indexed| indexed == root) {
+            return None;
+        }
+        let mut state = self.state.lock().unwrap();
+        self.ensure_built(&mut state);
+        let files = state.files.as_ref()?.get(root)?;
+        Some(
+            files
+                .iter()
+                .map(|(path, size)| (path.clone(), *size))
+                .collect(),
+        )
+    }
+
+    /// The syntax tree of `content`, read from `path`, in `lang`. The tree kept for `path` is
+    /// reused when it was parsed from the same content in the same language
+    pub fn parse(&self, path: &str, content: &str, lang: Language) -> Tree {
+        let path = PathBuf::from(path);
+        let mut state = self.state.lock().unwrap();
+        if let Some((kept_lang, tree)) = state.trees.get(&path)
+            && *kept_lang == lang
+            && tree.get_text() == content
+        {
+            let tree = tree.clone();
+            state.cache_hits += 1;
+            return tree;
+        }
+        state.cache_misses += 1;
+        // Parsing doesn't need the lock
+        drop(state);
+        let tree = Arc::new(AstGrep::new(content, lang));
+        self.state
+            .lock()
+            .unwrap()
+            .trees
+            .put(path, (lang, tree.clone()));
+        tree
+    }
+
+    /// Drop every file list and syntax tree and walk the roots again
+    pub fn rebuild(&self) -> IndexStatus {
+        let mut state = self.state.lock().unwrap();
+        state.files = None;
+        state.trees.clear();
+        self.ensure_built(&mut state);
+        self.status_of(&state)
+    }
+
+    /// Walk the roots ahead of the first search, calling `pace` after each entry so the caller
+    /// can throttle the walk. The index isn't held meanwhile, so a search that comes first walks
+    /// for itself; the walked list is kept only if no list was built and no file changed while
+    /// it was taken. Returns the number of files listed
+    pub fn prewarm(&self, mut pace: impl FnMut()) -> usize {
+        let file_events = {
+            let state = self.state.lock().unwrap();
+            if state.files.is_some() {
+                return 0;
+            }
+            state.file_events
+        };
+        self.start_watching();
+        let started = Instant::now();
+        let files: HashMap<PathBuf, BTreeMap<PathBuf, u64>> = self
+            .roots
+            .iter()
+            .map(|root| {
+                let sizes = walk_files(root, &self.walk)
+                    .filter_map(|entry| {
+                        pace();
+                        let size = entry.metadata().ok()?.len();
+                        Some((entry.into_path(), size))
+                    })
+                    .collect();
+                (root.clone(), sizes)
+            })
+            .collect();
+        let listed = files.values().map(BTreeMap::len).sum();
+
+        let mut state = self.state.lock().unwrap();
+        if state.files.is_some() || state.file_events != file_events {
+            return 0;
+        }
+        state.files = Some(files);
+        state.builds += 1;
+        state.last_build = Some(started.elapsed());
+        listed
+    }
+
+    pub fn status(&self) -> IndexStatus {
+        self.status_of(&self.state.lock().unwrap())
+    }
+
+    fn status_of(&self, state: &IndexState) -> IndexStatus {
+        IndexStatus {
+            enabled: true,
+            watching: self.watching(),
+            built: state.files.is_some(),
+            root_directories: self
+                .roots
+                .iter()
+                .map(|root| root.to_string_lossy().to_string())
+                .collect(),
+            files: state
+                .files
+                .as_ref()
+                .map_or(0, |files| files.values().map(BTreeMap::len).sum()),
+            parsed_files: state.trees.len(),
+            builds: state.builds,
+            last_build_ms: state.last_build.map(|elapsed| elapsed.as_millis() as u64),
+            file_events: state.file_events,
+            cache_hits: state.cache_hits,
+            cache_misses: state.cache_misses,
+        }
+    }
+
+    /// Walk the roots unless their file lists are current. The watcher is started first, so no
+    /// change made during the walk is missed
+    fn ensure_built(&self, state: &mut IndexState) {
+        if state.files.is_some() {
+            return;
+        }
+        self.start_watching();
+        let started = Instant::now();
+        let files = self
+            .roots
+            .iter()
+            .map(|root| {
+                let sizes = walk_files(root, &self.walk)
+                    .filter_map(|entry| {
+                        let size = entry.metadata().ok()?.len();
+                        Some((entry.into_path(), size))
+                    })
+                    .collect();
+                (root.clone(), sizes)
+            })
+            .collect();
+        state.files = Some(files);
+        state.builds += 1;
+        state.last_build = Some(started.elapsed());
+    }
+
+    #[cfg(feature = "watch")]
+    fn watching(&self) -> bool {
+        self.watcher.lock().unwrap().is_some()
+    }
+
+    #[cfg(not(feature = "watch"))]
+    fn watching(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "watch")]
+    fn start_watching(&self) {
+        use notify::{RecursiveMode, Watcher};
+
+        let mut watcher = self.watcher.lock().unwrap();
+        if watcher.is_some() {
+            return;
+        }
+        let state = Arc::downgrade(&self.state);
+        let handler = move |event: notify::Result<notify::Event>| match (event, state.upgrade()) {
+            (Ok(event), Some(state)) => apply_event(&mut state.lock().unwrap(), &event),
+            (Err(e), _) => tracing::warn!("File index watcher error: {e}"),
+            (Ok(_), None) => {}
+        };
+        let started = notify::recommended_watcher(handler).and_then(|mut started| {
+            for root in &self.roots {
+                started.watch(root, RecursiveMode::Recursive)?;
+            }
+            Ok(started)
+        });
+        match started {
+            Ok(started) => *watcher = Some(started),
+            Err(e) => tracing::warn!("Not watching the root directories: {e}"),
+        }
+    }
+
+    /// Without the `watch` feature the file lists are only walked again by `rebuild`
+    #[cfg(not(feature = "watch"))]
+    fn start_watching(&self) {}
+}
+
+/// Bring the file lists and trees in line with a change under a watched root
+#[cfg(feature = "watch")]
+fn apply_event(state: &mut IndexState, event: &notify::Event) {
+    use notify::EventKind;
+    use notify::event::ModifyKind;
+
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+    state.file_events += 1;
+    for path in &event.paths {
+        state.trees.pop(path);
+    }
+    let Some(files) = state.files.as_mut() else {
+        return;
+    };
+    match event.kind {
+        EventKind::Modify(ModifyKind::Name(_)) | EventKind::Create(_) => state.files = None,
+        EventKind::Modify(_) => {
+            for sizes in files.values_mut() {
+                for path in &event.paths {
+                    if let Some(size) = sizes.get_mut(path) {
+                        match std::fs::metadata(path) {
+                            Ok(metadata) => *size = metadata.len(),
+                            Err(_) => {
+                                sizes.remove(path);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        // A removed directory takes the files below it along
+        EventKind::Remove(_) => {
+            for sizes in files.values_mut() {
+                sizes.retain(|file, _| !event.paths.iter().any(|path| file.starts_with(path)));
+            }
+        }
+        _ => state.files = None,
+    }
+}
@@ -0,0 +1,126 @@
+//! # Input Size Limits
+//!
+//! Tool arguments such as `code` or `rule_config` arrive as one JSON string and would otherwise
+//! be parsed however large they are. Every tool call is checked against
+//! `ServiceConfig::max_input_sizes` before it reaches middleware or a handler: an argument over
+//! the limit for its name, at any depth of the arguments (so `batch_search` items count too),
+//! fails the call with an `INPUT_TOO_LARGE` error saying how to send the input instead.
+//!
+//! The size of an argument is the number of bytes in its strings, so a limit on an array of
+//! strings, such as `rule_configs`, applies to their total.
+
+use crate::errors::ServiceError;
+use rmcp::model::CallToolRequestParam;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Default limit on `code`, 1 MiB
+pub const DEFAULT_MAX_CODE_SIZE: usize = 1024 * 1024;
+
+/// Default limit on `rule_config`, 256 KiB
+pub const DEFAULT_MAX_RULE_CONFIG_SIZE: usize = 256 * 1024;
+
+/// The limits a server starts with, keyed by argument name
+pub fn default_max_input_sizes() -> BTreeMap<String, usize> {
+    BTreeMap::from([
+        ("code".to_string(), DEFAULT_MAX_CODE_SIZE),
+        ("rule_config".to_string(), DEFAULT_MAX_RULE_CONFIG_SIZE),
+    ])
+}
+
+/// Fail with [`ServiceError::InputTooLarge`] for the first argument of `request` over its
+/// limit in `limits`
+pub fn check_input_sizes(
+    request: &CallToolRequestParam,
+    limits: &BTreeMap<String, usize>,
+) -> Result<(), ServiceError> {
+    if limits.is_empty() {
+        return Ok(());
+    }
+    match &request.arguments {
+        Some(arguments) => check_object(arguments, limits),
+        None => Ok(()),
+    }
+}
+
+fn check_object(
+    object: &serde_json::Map<String, Value>,
+    limits: &BTreeMap<String, usize>,
+) -> Result<(), ServiceError> {
+    for (name, value) in object {
+        if let Some(&limit) = limits.get(name) {
+            let size = string_bytes(value);
+            if size > limit {
+                return Err(ServiceError::InputTooLarge {
+                    parameter: name.clone(),
+                    size,
+                    limit,
+                });
+            }
+        }
+        check_value(value, limits)?;
+    }
+    Ok(())
+}
+
+fn check_value(value: &Value, limits: &BTreeMap<String, usize>) -> Result<(), ServiceError> {
+    match value {
+        Value::Object(object) => check_object(object, limits),
+        Value::Array(items) => items.iter().try_for_each(|item| check_value(item, limits)),
+        _ => Ok(()),
+    }
+}
+
+/// Total bytes of the strings in `value`
+fn string_bytes(value: &Value) -> usize {
+    match value {
+        Value::String(text) => text.len(),
+        Value::Array(items) => items.iter().map(string_bytes).sum(),
+        Value::Object(object) => object.values().map(string_bytes).sum(),
+        _ => 0,
+    }
+}
+
+/// What to do instead of sending `parameter` in one piece
+pub fn input_size_hint(parameter: &str) -> String {
+    match parameter {
+        "code" => "send the code in chunks with begin_code_buffer and append_code_buffer and pass the returned buffer_id instead of code, or save it to a file under a root directory and use file_search or file_replace".to_string(),
+        "rule_config" | "rule_configs" => "save the rule as a YAML file in the rules directory and run it by id with scan's rule_ids, or split it into smaller rules".to_string(),
+        _ => format!("send a smaller {parameter}, or raise the limit with --max-input-size"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(arguments: Value) -> CallToolRequestParam {
+        CallToolRequestParam {
+            name: "search".into(),
+            arguments: arguments.as_object().cloned(),
+        }
+    }
+
+    #[test]
+    fn test_limits_apply_at_any_depth() {
+        let limits = BTreeMap::from([("code".to_string(), 4), ("rule_configs".to_string(), 5)]);
+        let ok = request(json!({ "code": "abcd", "pattern": "long pattern" }));
+        assert!(check_input_sizes(&ok, &limits).is_ok());
+
+        let nested = request(json!({ "searches": [{ "code": "ab" }, { "code": "abcde" }] }));
+        let error = check_input_sizes(&nested, &limits).unwrap_err();
+        assert!(matches!(
+            error,
+            ServiceError::InputTooLarge {
+                size: 5,
+                limit: 4,
+                ..
+            }
+        ));
+
+        let array = request(json!({ "rule_configs": ["abc", "abc"] }));
+        assert!(check_input_sizes(&array, &limits).is_err());
+        assert!(check_input_sizes(&array, &BTreeMap::new()).is_ok());
+    }
+}
@@ -0,0 +1,155 @@
+//! # JSON Patch Output
+//!
+//! Replacements in JSON and YAML files can be reported as an RFC 6902 JSON Patch instead of
+//! text diffs, for infrastructure-as-code pipelines that apply document edits themselves. The
+//! file is parsed before and after the replacement and the two documents are compared; paths
+//! are JSON Pointers, which address YAML documents the same way.
+//!
+//! Arrays are compared index by index, so inserting an element in the middle shows up as
+//! `replace` operations on the following elements rather than a single `add`.
+
+use crate::errors::ServiceError;
+use crate::extra_languages::AnyLanguage;
+use ast_grep_language::SupportLang as Language;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// One RFC 6902 operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonPatchOperation {
+    pub op: JsonPatchOp,
+    /// JSON Pointer to the value
+    pub path: String,
+    /// New value, absent for `remove`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonPatchOp {
+    Add,
+    Remove,
+    Replace,
+}
+
+/// Patch turning the `before` document into the `after` document, both in `lang`
+pub fn document_patch(
+    lang: AnyLanguage,
+    before: &str,
+    after: &str,
+) -> Result<Vec<JsonPatchOperation>, ServiceError> {
+    let parse = match lang {
+        AnyLanguage::Builtin(Language::Json) => parse_json,
+        AnyLanguage::Builtin(Language::Yaml) => parse_yaml,
+        _ => {
+            return Err(ServiceError::Internal(
+                "json_patch output is only available for json and yaml".to_string(),
+            ));
+        }
+    };
+    let before = parse(before).map_err(|e| {
+        ServiceError::Internal(format!("Could not parse the original document: {e}"))
+    })?;
+    let after = parse(after).map_err(|e| {
+        ServiceError::Internal(format!(
+            "The replacement does not leave a valid document: {e}"
+        ))
+    })?;
+    let mut operations = Vec::new();
+    diff(&before, &after, String::new(), &mut operations);
+    Ok(operations)
+}
+
+fn parse_json(text: &str) -> Result<Value, String> {
+    serde_json::from_str(text).map_err(|e| e.to_string())
+}
+
+fn parse_yaml(text: &str) -> Result<Value, String> {
+    serde_yaml::from_str(text).map_err(|e| e.to_string())
+}
+
+fn diff(before: &Value, after: &Value, path: String, operations: &mut Vec<JsonPatchOperation>) {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            for (key, old) in before {
+                let path = format!("{path}/{}", escape(key));
+                match after.get(key) {
+                    Some(new) => diff(old, new, path, operations),
+                    None => operations.push(operation(JsonPatchOp::Remove, path, None)),
+                }
+            }
+            for (key, new) in after {
+                if !before.contains_key(key) {
+                    let path = format!("{path}/{}", escape(key));
+                    operations.push(operation(JsonPatchOp::Add, path, Some(new)));
+                }
+            }
+        }
+        (Value::Array(before), Value::Array(after)) => {
+            for (index, (old, new)) in before.iter().zip(after).enumerate() {
+                diff(old, new, format!("{path}/{index}"), operations);
+            }
+            for (index, new) in after.iter().enumerate().skip(before.len()) {
+                let path = format!("{path}/{index}");
+                operations.push(operation(JsonPatchOp::Add, path, Some(new)));
+            }
+            // Highest index first, so each removal leaves the earlier indices in place
+            for index in (after.len()..before.len()).rev() {
+                let path = format!("{path}/{index}");
+                operations.push(operation(JsonPatchOp::Remove, path, None));
+            }
+        }
+        _ if before != after => {
+            operations.push(operation(JsonPatchOp::Replace, path, Some(after)));
+        }
+        _ => {}
+    }
+}
+
+fn operation(op: JsonPatchOp, path: String, value: Option<&Value>) -> JsonPatchOperation {
+    JsonPatchOperation {
+        op,
+        path,
+        value: value.cloned(),
+    }
+}
+
+/// Escape a key for use as a JSON Pointer segment
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn patch(before: Value, after: Value) -> Value {
+        let mut operations = Vec::new();
+        diff(&before, &after, String::new(), &mut operations);
+        serde_json::to_value(operations).unwrap()
+    }
+
+    #[test]
+    fn test_diff_objects_and_arrays() {
+        assert_eq!(
+            patch(
+                json!({"a/b": 1, "gone": true, "list": [1, 2, 3]}),
+                json!({"a/b": 2, "new": {"x": null}, "list": [1, 5]}),
+            ),
+            json!([
+                {"op": "replace", "path": "/a~1b", "value": 2},
+                {"op": "remove", "path": "/gone"},
+                {"op": "replace", "path": "/list/1", "value": 5},
+                {"op": "remove", "path": "/list/2"},
+                {"op": "add", "path": "/new", "value": {"x": null}},
+            ])
+        );
+        assert_eq!(
+            patch(json!({"a": 1}), json!([1])),
+            json!([{"op": "replace", "path": "", "value": [1]}])
+        );
+        assert_eq!(patch(json!({"a": [1]}), json!({"a": [1]})), json!([]));
+    }
+}
@@ -0,0 +1,253 @@
+//! # Language Detection
+//!
+//! What `language: "auto"` searches and `detect_language` make of a file: the language its
+//! extension names, else the interpreter of its `#!` line, as [`crate::dialect::detect_language`]
+//! decides for every file of an auto search. `detect_language` also guesses the language of a
+//! snippet with neither:
+//!
+//! - the snippet is parsed with every grammar, and those parsing it without errors are the
+//!   candidates, since permissive grammars such as Ruby's accept most code
+//! - each candidate is scored by the keywords it recognizes: words like `fn` or `def` are
+//!   keywords of one grammar and plain identifiers to the others
+//! - confidence grows with the keywords recognized, up to 0.8, and is split between grammars
+//!   tying for the most, such as JavaScript and TypeScript
+//!
+//! A detected extension has confidence 1 and a `#!` line 0.9.
+
+use crate::dialect::detect_language;
+use crate::extra_languages::{AnyLanguage, ExtraLanguage};
+use crate::types::{DetectLanguageResult, DetectionMethod, LanguageCandidate};
+use ast_grep_core::Language as _;
+use ast_grep_core::tree_sitter::LanguageExt;
+use ast_grep_language::SupportLang as Language;
+use std::io::Read;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Confidence of a language named by the interpreter of a `#!` line
+const SHEBANG_CONFIDENCE: f64 = 0.9;
+
+/// Highest confidence of a language guessed from content
+const CONTENT_CONFIDENCE: f64 = 0.8;
+
+/// Keywords recognized before a content guess reaches [`CONTENT_CONFIDENCE`]
+const CONFIDENT_KEYWORDS: usize = 3;
+
+/// Bytes of a file read to detect its language
+const HEAD_BYTES: u64 = 64 * 1024;
+
+/// Every language with its file extensions, built once since ast-grep compiles a glob set
+/// each time it lists a language's file types
+static LANGUAGE_EXTENSIONS: LazyLock<Vec<(AnyLanguage, Vec<String>)>> = LazyLock::new(|| {
+    let builtin = Language::all_langs().iter().map(|&lang| {
+        let extensions = lang
+            .file_types()
+            .definitions()
+            .iter()
+            .flat_map(|definition| definition.globs())
+            .filter_map(|glob| glob.strip_prefix("*."))
+            .map(str::to_string)
+            .collect();
+        (AnyLanguage::Builtin(lang), extensions)
+    });
+    let extra = ExtraLanguage::ALL.iter().map(|&lang| {
+        let extensions = lang.extensions().iter().map(|e| e.to_string()).collect();
+        (AnyLanguage::Extra(lang), extensions)
+    });
+    builtin.chain(extra).collect()
+});
+
+/// Name of `lang` as the tools take it, e.g. `javascript` or `csharp`
+pub fn language_name(lang: AnyLanguage) -> String {
+    lang.to_string().to_lowercase()
+}
+
+/// File extensions of `lang`
+pub fn language_extensions(lang: AnyLanguage) -> Vec<String> {
+    if let AnyLanguage::Dynamic(lang) = lang {
+        return lang.extensions().to_vec();
+    }
+    LANGUAGE_EXTENSIONS
+        .iter()
+        .find(|(candidate, _)| *candidate == lang)
+        .map(|(_, extensions)| extensions.clone())
+        .unwrap_or_default()
+}
+
+/// A `path_pattern` matching every file of `lang` by extension
+pub fn language_path_pattern(lang: AnyLanguage) -> Option<String> {
+    match language_extensions(lang).as_slice() {
+        [] => None,
+        [extension] => Some(format!("**/*.{extension}")),
+        extensions => Some(format!("**/*.{{{}}}", extensions.join(","))),
+    }
+}
+
+/// Detect the language of a file named `path`, or with `extension`, holding `content`.
+/// Content decides only when neither the name nor a `#!` line does
+pub fn detect(
+    path: Option<&str>,
+    extension: Option<&str>,
+    content: Option<&str>,
+) -> DetectLanguageResult {
+    if let Some(extension) = extension {
+        let extension = extension.trim_start_matches('.');
+        if let Some(lang) = AnyLanguage::from_path(Path::new(&format!("file.{extension}"))) {
+            return detected(
+                lang,
+                DetectionMethod::Extension,
+                1.0,
+                format!(
+                    "the extension .{extension} is parsed as {}",
+                    language_name(lang)
+                ),
+            );
+        }
+    }
+
+    if let Some(path) = path {
+        if let Some(lang) = AnyLanguage::from_path(Path::new(path)) {
+            return detected(
+                lang,
+                DetectionMethod::Extension,
+                1.0,
+                format!(
+                    "the extension of {path} is parsed as {}",
+                    language_name(lang)
+                ),
+            );
+        }
+        if let Some(lang) = content.and_then(|content| detect_language(path, content)) {
+            return detected(
+                lang,
+                DetectionMethod::Shebang,
+                SHEBANG_CONFIDENCE,
+                format!("the #! line runs a {} interpreter", language_name(lang)),
+            );
+        }
+    }
+
+    match content {
+        Some(content) => detect_content(content),
+        None => undetected("no extension names a language and there is no code to parse"),
+    }
+}
+
+/// The start of the file at `path`, enough for its `#!` line and a content guess
+pub fn read_head(path: &Path) -> Option<String> {
+    let mut head = Vec::new();
+    std::fs::File::open(path)
+        .ok()?
+        .take(HEAD_BYTES)
+        .read_to_end(&mut head)
+        .ok()?;
+    Some(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Guess the language of `code` by the grammars parsing it and the keywords they recognize
+fn detect_content(code: &str) -> DetectLanguageResult {
+    if let Some(lang) = detect_language("", code) {
+        return detected(
+            lang,
+            DetectionMethod::Shebang,
+            SHEBANG_CONFIDENCE,
+            format!("the #! line runs a {} interpreter", language_name(lang)),
+        );
+    }
+
+    let mut parsed: Vec<(AnyLanguage, usize)> = LANGUAGE_EXTENSIONS
+        .iter()
+        .filter_map(|&(lang, _)| {
+            let ast = lang.ast_grep(code);
+            let mut keywords = 0;
+            for node in ast.root().dfs() {
+                if node.is_error() || node.is_missing() {
+                    return None;
+                }
+                if is_keyword(node.kind().as_ref(), node.is_named(), node.is_leaf()) {
+                    keywords += 1;
+                }
+            }
+            Some((lang, keywords))
+        })
+        .collect();
+    parsed.sort_by(|a, b| {
+        b.1.cmp(&a.1)
+            .then_with(|| language_name(a.0).cmp(&language_name(b.0)))
+    });
+
+    let most = parsed.first().map_or(0, |&(_, keywords)| keywords);
+    let candidates = parsed
+        .iter()
+        .map(|&(lang, keywords)| LanguageCandidate {
+            language: language_name(lang),
+            keywords,
+            score: match most {
+                0 => 0.0,
+                most => keywords as f64 / most as f64,
+            },
+        })
+        .collect();
+    let mut result = match parsed.first() {
+        Some(&(lang, keywords)) if keywords > 0 => {
+            let ties = parsed.iter().filter(|(_, k)| *k == keywords).count();
+            let evidence = keywords.min(CONFIDENT_KEYWORDS) as f64 / CONFIDENT_KEYWORDS as f64;
+            let reason = match ties {
+                1 => format!(
+                    "{} parses the code without errors and recognizes the most keywords ({keywords})",
+                    language_name(lang)
+                ),
+                ties => format!(
+                    "{ties} grammars parse the code without errors and recognize {keywords} keywords; {} is the first of them",
+                    language_name(lang)
+                ),
+            };
+            detected(
+                lang,
+                DetectionMethod::Content,
+                CONTENT_CONFIDENCE * evidence / ties as f64,
+                reason,
+            )
+        }
+        Some(_) => undetected(
+            "the code parses in several grammars, but none recognizes a keyword in it; pass more code or a file extension",
+        ),
+        None => undetected("no grammar parses the code without errors"),
+    };
+    result.candidates = candidates;
+    result
+}
+
+/// Whether a node is a keyword: an unnamed leaf spelled as a word, like `fn` or `def`
+fn is_keyword(kind: &str, named: bool, leaf: bool) -> bool {
+    !named && leaf && kind.len() > 1 && kind.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+fn detected(
+    lang: AnyLanguage,
+    method: DetectionMethod,
+    confidence: f64,
+    reason: String,
+) -> DetectLanguageResult {
+    DetectLanguageResult {
+        language: Some(language_name(lang)),
+        method,
+        confidence,
+        extensions: language_extensions(lang),
+        path_pattern: language_path_pattern(lang),
+        candidates: Vec::new(),
+        reason,
+    }
+}
+
+fn undetected(reason: &str) -> DetectLanguageResult {
+    DetectLanguageResult {
+        language: None,
+        method: DetectionMethod::None,
+        confidence: 0.0,
+        extensions: Vec::new(),
+        path_pattern: None,
+        candidates: Vec::new(),
+        reason: reason.to_string(),
+    }
+}
@@ -3,6 +3,7 @@
 
 use super::types::*;
 use crate::errors::ServiceError;
+use crate::extra_languages::ExtraLanguage;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -90,6 +91,13 @@ impl DiscoveryService {
             self.add_language_data(python_data);
         }
 
+        // Load patterns for the extra grammars compiled into this build
+        for lang in ExtraLanguage::ALL {
+            if let Ok(data) = self.load_language_patterns(lang.name()) {
+                self.add_language_data(data);
+            }
+        }
+
         Ok(())
     }
 
@@ -98,6 +106,10 @@ impl DiscoveryService {
             "javascript" => include_str!("../data/patterns/javascript.json"),
             "rust" => include_str!("../data/patterns/rust.json"),
             "python" => include_str!("../data/patterns/python.json"),
+            #[cfg(feature = "graphql")]
+            "graphql" => include_str!("../data/patterns/graphql.json"),
+            #[cfg(feature = "protobuf")]
+            "protobuf" => include_str!("../data/patterns/protobuf.json"),
             _ => {
                 return Err(ServiceError::Internal(format!(
                     "Unsupported language: {language}"
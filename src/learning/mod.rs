@@ -1,12 +1,16 @@
 //! Learning system for ast-grep pattern education
 
 pub mod discovery;
+pub mod project_context;
 pub mod prompt_generation;
+pub mod synthesis;
 pub mod types;
 pub mod validation;
 
 pub use discovery::DiscoveryService;
+pub use project_context::ProjectContext;
 pub use prompt_generation::{GeneratePromptParam, GeneratedPrompt, PromptConfig, PromptGenerator};
+pub use synthesis::suggest_pattern;
 pub use types::*;
 pub use validation::ValidationEngine;
 
@@ -0,0 +1,178 @@
+//! Project context gathered server-side for workflow prompts
+
+use crate::pattern::PatternMatcher;
+use ast_grep_core::Language as _;
+use ast_grep_language::SupportLang as Language;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+/// Upper bound on files inspected when summarizing a project
+const MAX_FILES_SCANNED: usize = 5000;
+/// Upper bound on files parsed when counting pattern matches
+const MAX_FILES_MATCHED: usize = 500;
+const SAMPLE_FILES_PER_LANGUAGE: usize = 3;
+
+/// Summary of the workspace used to ground refactoring prompts
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectContext {
+    pub total_files: usize,
+    /// Languages present, most common first
+    pub languages: Vec<LanguageSummary>,
+    /// Match counts for the prompt's pattern, when one was supplied
+    pub pattern_matches: Option<PatternMatchSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageSummary {
+    pub language: String,
+    pub file_count: usize,
+    pub sample_files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternMatchSummary {
+    pub pattern: String,
+    pub language: String,
+    pub files_scanned: usize,
+    pub files_with_matches: usize,
+    pub total_matches: usize,
+}
+
+impl ProjectContext {
+    /// Walk `roots` and summarize the languages present. When `pattern` is given, also count
+    /// how often it matches in files of `language`.
+    pub fn collect(
+        roots: &[PathBuf],
+        pattern_matcher: &PatternMatcher,
+        pattern: Option<&str>,
+        language: Option<Language>,
+    ) -> Self {
+        let mut files_by_language: HashMap<Language, Vec<PathBuf>> = HashMap::new();
+        let mut total_files = 0;
+
+        let files = roots.iter().flat_map(|root| {
+            WalkDir::new(root)
+                .max_depth(10)
+                .into_iter()
+                .filter_entry(|entry| !is_ignored_dir(entry))
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_file())
+        });
+        for entry in files.take(MAX_FILES_SCANNED) {
+            total_files += 1;
+            if let Some(lang) = Language::from_path(entry.path()) {
+                files_by_language
+                    .entry(lang)
+                    .or_default()
+                    .push(entry.into_path());
+            }
+        }
+
+        let pattern_matches = pattern.zip(language).map(|(pattern, lang)| {
+            let files = files_by_language
+                .get(&lang)
+                .map(Vec::as_slice)
+                .unwrap_or(&[]);
+            count_matches(pattern_matcher, pattern, lang, files)
+        });
+
+        let mut languages: Vec<LanguageSummary> = files_by_language
+            .into_iter()
+            .map(|(lang, files)| LanguageSummary {
+                language: language_name(lang),
+                file_count: files.len(),
+                sample_files: files
+                    .iter()
+                    .take(SAMPLE_FILES_PER_LANGUAGE)
+                    .map(|path| path.to_string_lossy().to_string())
+                    .collect(),
+            })
+            .collect();
+        languages.sort_by(|a, b| {
+            b.file_count
+                .cmp(&a.file_count)
+                .then_with(|| a.language.cmp(&b.language))
+        });
+
+        Self {
+            total_files,
+            languages,
+            pattern_matches,
+        }
+    }
+
+    /// Render as a bullet list for inclusion in a prompt
+    pub fn to_markdown(&self) -> String {
+        let mut text = format!("- Files scanned: {}\n", self.total_files);
+        if self.languages.is_empty() {
+            text.push_str("- No source files in a supported language were found\n");
+        }
+        for summary in &self.languages {
+            text.push_str(&format!(
+                "- {}: {} files (e.g. {})\n",
+                summary.language,
+                summary.file_count,
+                summary.sample_files.join(", ")
+            ));
+        }
+        if let Some(matches) = &self.pattern_matches {
+            text.push_str(&format!(
+                "- Pattern `{}` ({}): {} matches in {} of {} files checked\n",
+                matches.pattern,
+                matches.language,
+                matches.total_matches,
+                matches.files_with_matches,
+                matches.files_scanned
+            ));
+        }
+        text
+    }
+}
+
+fn count_matches(
+    pattern_matcher: &PatternMatcher,
+    pattern: &str,
+    lang: Language,
+    files: &[PathBuf],
+) -> PatternMatchSummary {
+    let mut summary = PatternMatchSummary {
+        pattern: pattern.to_string(),
+        language: language_name(lang),
+        files_scanned: 0,
+        files_with_matches: 0,
+        total_matches: 0,
+    };
+
+    for file in files.iter().take(MAX_FILES_MATCHED) {
+        let Ok(content) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        summary.files_scanned += 1;
+        let matches = pattern_matcher
+            .search_with_options(&content, pattern, lang, None, None)
+            .map(|matches| matches.len())
+            .unwrap_or(0);
+        if matches > 0 {
+            summary.files_with_matches += 1;
+            summary.total_matches += matches;
+        }
+    }
+    summary
+}
+
+/// Hidden directories and dependency/build output aren't representative of the project
+fn is_ignored_dir(entry: &walkdir::DirEntry) -> bool {
+    entry.depth() > 0
+        && entry.file_type().is_dir()
+        && entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.starts_with('.') || name == "node_modules" || name == "target")
+}
+
+/// The lowercase name accepted by the `language` tool parameters
+fn language_name(lang: Language) -> String {
+    format!("{lang:?}").to_lowercase()
+}
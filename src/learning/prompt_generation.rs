@@ -1,5 +1,6 @@
 //! LLM prompt generation for enhanced learning assistance
 
+use super::project_context::ProjectContext;
 use super::types::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -132,9 +133,113 @@ impl PromptGenerator {
                 .to_string(),
         );
 
+        // Workflow prompt templates, filled with server-side project context
+        templates.insert(
+            "bulk_refactor_plan".to_string(),
+            "Plan a bulk refactor for this project.\n\n\
+             🎯 **Goal:** {goal}\n\n\
+             📁 **Project Context:**\n\
+             {project_context}\n\
+             🗺️ **Plan:**\n\
+             1. Pin down the code to change with `file_search`{pattern_hint} and check the match count against the numbers above\n\
+             2. Write the transformation as a `rule_replace` rule (or `file_replace` for a single pattern) and preview it with `dry_run: true`\n\
+             3. Apply in batches using `max_results` and the returned cursor, starting with the language that has the fewest files\n\
+             4. Re-run the search to confirm nothing was missed and review the changed files\n\n\
+             ⚠️ **Watch for:** generated or vendored files among the matches, and replacements that need commas or imports added by hand"
+                .to_string(),
+        );
+
+        templates.insert(
+            "codemod_rule".to_string(),
+            "Write an ast-grep YAML rule for a codemod.\n\n\
+             🎯 **Goal:** {goal}\n\
+             🌐 **Language:** {language}\n\n\
+             📁 **Project Context:**\n\
+             {project_context}\n\
+             {example}\
+             📝 **Rule Skeleton:**\n\
+             ```yaml\n\
+             id: {rule_id}\n\
+             language: {language}\n\
+             message: {goal}\n\
+             rule:\n  pattern: {pattern}\n\
+             fix: $REPLACEMENT\n\
+             ```\n\n\
+             💡 **Next Steps:** check the pattern with `validate_rule` against a sample file listed above, \
+             then preview with `rule_replace` and `dry_run: true`"
+                .to_string(),
+        );
+
         Self { templates }
     }
 
+    /// Prompt for planning a refactor across the project described by `context`
+    pub fn generate_bulk_refactor_plan(
+        &self,
+        goal: &str,
+        pattern: Option<&str>,
+        context: &ProjectContext,
+    ) -> Result<String, crate::errors::ServiceError> {
+        let pattern_hint = pattern
+            .map(|pattern| format!(" (starting from `{pattern}`)"))
+            .unwrap_or_default();
+        self.render(
+            "bulk_refactor_plan",
+            &[
+                ("{goal}", goal),
+                ("{project_context}", &context.to_markdown()),
+                ("{pattern_hint}", &pattern_hint),
+            ],
+        )
+    }
+
+    /// Prompt for writing a codemod rule grounded in the project described by `context`
+    pub fn generate_codemod_rule_prompt(
+        &self,
+        goal: &str,
+        language: &str,
+        pattern: Option<&str>,
+        example: Option<&str>,
+        context: &ProjectContext,
+    ) -> Result<String, crate::errors::ServiceError> {
+        let example = example
+            .map(|code| format!("🔬 **Example to transform:**\n```{language}\n{code}\n```\n\n"))
+            .unwrap_or_default();
+        let rule_id: String = goal
+            .to_lowercase()
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .take(5)
+            .collect::<Vec<_>>()
+            .join("-");
+        self.render(
+            "codemod_rule",
+            &[
+                ("{goal}", goal),
+                ("{language}", language),
+                ("{project_context}", &context.to_markdown()),
+                ("{example}", &example),
+                ("{rule_id}", &rule_id),
+                ("{pattern}", pattern.unwrap_or("$PATTERN")),
+            ],
+        )
+    }
+
+    fn render(
+        &self,
+        template_key: &str,
+        substitutions: &[(&str, &str)],
+    ) -> Result<String, crate::errors::ServiceError> {
+        let template = self.templates.get(template_key).ok_or_else(|| {
+            crate::errors::ServiceError::Internal(format!("Template not found: {template_key}"))
+        })?;
+        Ok(substitutions
+            .iter()
+            .fold(template.clone(), |text, (placeholder, value)| {
+                text.replace(placeholder, value)
+            }))
+    }
+
     /// Generate an LLM prompt based on validation results
     pub fn generate_prompt(
         &self,
@@ -0,0 +1,319 @@
+//! Pattern synthesis from before/after examples
+//!
+//! Each example is diffed as text to find the changed byte range, which is widened to the
+//! smallest syntax node covering it and then to a couple of its ancestors. Every such node
+//! yields a `literal` candidate (its code before and after the change) and, when some of its
+//! subtrees survive the change unmodified, a `generalized` candidate in which those subtrees
+//! are metavariables. Candidates from all examples are then run against every example, so the
+//! ones that reproduce the most `after`s come first.
+
+use super::types::*;
+use crate::errors::ServiceError;
+use crate::pattern::PatternMatcher;
+use ast_grep_core::tree_sitter::{LanguageExt, StrDoc};
+use ast_grep_core::{Node, Pattern};
+use ast_grep_language::SupportLang;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// Ancestors of the covering node tried in addition to the node itself
+const MAX_ANCESTORS: usize = 2;
+
+/// A candidate before it is validated
+struct Draft {
+    pattern: String,
+    replacement: String,
+    strategy: &'static str,
+    /// How far above the covering node it was taken from
+    level: usize,
+}
+
+/// Ranked candidate patterns turning each example's `before` into its `after`
+pub fn suggest_pattern(
+    param: &SuggestPatternParam,
+    matcher: &PatternMatcher,
+) -> Result<SuggestPatternResult, ServiceError> {
+    if param.examples.is_empty() {
+        return Err(ServiceError::Internal(
+            "At least one before/after example is required".to_string(),
+        ));
+    }
+    let lang = SupportLang::from_str(&param.language)
+        .map_err(|_| ServiceError::Internal(format!("Unsupported language: {}", param.language)))?;
+
+    let mut drafts: Vec<Draft> = Vec::new();
+    let mut notes = Vec::new();
+    for (index, example) in param.examples.iter().enumerate() {
+        match drafts_for(example, lang) {
+            Ok(found) => {
+                for draft in found {
+                    let duplicate = drafts.iter().any(|seen| {
+                        seen.pattern == draft.pattern && seen.replacement == draft.replacement
+                    });
+                    if !duplicate {
+                        drafts.push(draft);
+                    }
+                }
+            }
+            Err(note) => notes.push(format!("Example {index}: {note}")),
+        }
+    }
+
+    let mut ranked: Vec<(Draft, PatternCandidate)> = drafts
+        .into_iter()
+        .filter_map(|draft| {
+            let candidate = validate(&draft, &param.examples, lang, matcher)?;
+            Some((draft, candidate))
+        })
+        .collect();
+    ranked.sort_by(|(a_draft, a), (b_draft, b)| {
+        b.examples_reproduced
+            .cmp(&a.examples_reproduced)
+            .then(b.examples_matched.cmp(&a.examples_matched))
+            .then((a_draft.strategy == "literal").cmp(&(b_draft.strategy == "literal")))
+            .then(a_draft.level.cmp(&b_draft.level))
+    });
+    let candidates = ranked
+        .into_iter()
+        .map(|(_, candidate)| candidate)
+        .take(param.max_candidates)
+        .collect();
+    Ok(SuggestPatternResult { candidates, notes })
+}
+
+/// Candidate patterns taken from around the code one example changes
+fn drafts_for(example: &PatternExample, lang: SupportLang) -> Result<Vec<Draft>, String> {
+    let (before, after) = (example.before.as_str(), example.after.as_str());
+    if before == after {
+        return Err("before and after are identical".to_string());
+    }
+    let changed = changed_range(before, after);
+    let delta = after.len() as isize - before.len() as isize;
+
+    let ast = lang.ast_grep(before);
+    let root = ast.root();
+    let mut node = covering_node(root.clone(), &changed);
+    if node.range() == root.range() {
+        return Err("the change isn't inside a single statement or expression".to_string());
+    }
+
+    let mut drafts = Vec::new();
+    for level in 0..=MAX_ANCESTORS {
+        let range = node.range();
+        let after_end = (range.end as isize + delta) as usize;
+        let (Some(before_text), Some(after_text)) =
+            (before.get(range.clone()), after.get(range.start..after_end))
+        else {
+            break;
+        };
+        drafts.push(Draft {
+            pattern: before_text.to_string(),
+            replacement: after_text.to_string(),
+            strategy: "literal",
+            level,
+        });
+        if let Some((pattern, replacement)) = generalize(&node, before_text, after_text) {
+            drafts.push(Draft {
+                pattern,
+                replacement,
+                strategy: "generalized",
+                level,
+            });
+        }
+
+        match node.parent() {
+            Some(parent) if parent.range() != root.range() => node = parent,
+            _ => break,
+        }
+    }
+    Ok(drafts)
+}
+
+/// The byte range of `before` that differs from `after`, between their common prefix and
+/// suffix
+fn changed_range(before: &str, after: &str) -> Range<usize> {
+    let mut prefix = before
+        .bytes()
+        .zip(after.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !before.is_char_boundary(prefix) || !after.is_char_boundary(prefix) {
+        prefix -= 1;
+    }
+    let max_suffix = before.len().min(after.len()) - prefix;
+    let mut suffix = before
+        .bytes()
+        .rev()
+        .zip(after.bytes().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !before.is_char_boundary(before.len() - suffix)
+        || !after.is_char_boundary(after.len() - suffix)
+    {
+        suffix -= 1;
+    }
+    prefix..before.len() - suffix
+}
+
+/// The smallest named node under `node` whose range contains `range`
+fn covering_node<'r>(
+    node: Node<'r, StrDoc<SupportLang>>,
+    range: &Range<usize>,
+) -> Node<'r, StrDoc<SupportLang>> {
+    let child = node.children().find(|child| {
+        let child_range = child.range();
+        child.is_named() && child_range.start <= range.start && range.end <= child_range.end
+    });
+    match child {
+        Some(child) => covering_node(child, range),
+        None => node,
+    }
+}
+
+/// `before_text` and `after_text` with the subtrees of `node` that appear unchanged in
+/// `after_text` replaced by metavariables, or `None` when there are none
+fn generalize(
+    node: &Node<StrDoc<SupportLang>>,
+    before_text: &str,
+    after_text: &str,
+) -> Option<(String, String)> {
+    let mut kept = Vec::new();
+    for child in node.children() {
+        collect_kept(&child, after_text, &mut kept);
+    }
+    if kept.is_empty() {
+        return None;
+    }
+
+    let offset = node.range().start;
+    let mut names: HashMap<&str, String> = HashMap::new();
+    let mut pattern = String::with_capacity(before_text.len());
+    let mut end = 0;
+    for (range, text) in &kept {
+        if names.len() == 26 && !names.contains_key(text) {
+            continue;
+        }
+        let next = format!("${}", (b'A' + names.len() as u8) as char);
+        let name = names.entry(text).or_insert(next);
+        pattern.push_str(&before_text[end..range.start - offset]);
+        pattern.push_str(name);
+        end = range.end - offset;
+    }
+    pattern.push_str(&before_text[end..]);
+    if names.values().any(|name| name == pattern.trim()) {
+        return None;
+    }
+
+    // Longer texts first, so a kept `foo.bar` isn't split up by a kept `foo`
+    let mut by_length: Vec<(&&str, &String)> = names.iter().collect();
+    by_length.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then(a.0.cmp(b.0)));
+    let mut taken: Vec<(Range<usize>, &str)> = Vec::new();
+    for (text, name) in by_length {
+        for (start, _) in after_text.match_indices(*text) {
+            let range = start..start + text.len();
+            let overlaps = taken
+                .iter()
+                .any(|(seen, _)| seen.start < range.end && range.start < seen.end);
+            if !overlaps && on_token_boundary(after_text, &range) {
+                taken.push((range, name));
+            }
+        }
+    }
+    taken.sort_by_key(|(range, _)| range.start);
+    let mut replacement = String::with_capacity(after_text.len());
+    let mut end = 0;
+    for (range, name) in taken {
+        replacement.push_str(&after_text[end..range.start]);
+        replacement.push_str(name);
+        end = range.end;
+    }
+    replacement.push_str(&after_text[end..]);
+    Some((pattern, replacement))
+}
+
+/// The outermost named subtrees under `node` whose text appears in `after_text`, in source
+/// order. Subtrees starting with punctuation, like argument lists, are looked into instead, as
+/// a metavariable can't stand in for them
+fn collect_kept<'r>(
+    node: &Node<'r, StrDoc<SupportLang>>,
+    after_text: &str,
+    kept: &mut Vec<(Range<usize>, &'r str)>,
+) {
+    if !node.is_named() {
+        return;
+    }
+    let text = match node.text() {
+        std::borrow::Cow::Borrowed(text) => text,
+        std::borrow::Cow::Owned(_) => return,
+    };
+    let first = text.chars().next();
+    let standalone = first.is_some_and(|c| c.is_alphanumeric() || "_\"'`".contains(c));
+    let survives = after_text
+        .match_indices(text)
+        .any(|(start, _)| on_token_boundary(after_text, &(start..start + text.len())));
+    if standalone && survives && !node.kind().contains("comment") {
+        kept.push((node.range(), text));
+        return;
+    }
+    for child in node.children() {
+        collect_kept(&child, after_text, kept);
+    }
+}
+
+/// Whether `range` of `text` neither starts nor ends inside a word
+fn on_token_boundary(text: &str, range: &Range<usize>) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let inner = &text[range.clone()];
+    let starts_word = inner.chars().next().is_some_and(is_word);
+    let ends_word = inner.chars().next_back().is_some_and(is_word);
+    let before = text[..range.start].chars().next_back().is_some_and(is_word);
+    let after = text[range.end..].chars().next().is_some_and(is_word);
+    !(starts_word && before) && !(ends_word && after)
+}
+
+/// The candidate for `draft` with its results on every example, or `None` when its pattern
+/// isn't valid for `lang`
+fn validate(
+    draft: &Draft,
+    examples: &[PatternExample],
+    lang: SupportLang,
+    matcher: &PatternMatcher,
+) -> Option<PatternCandidate> {
+    let pattern = Pattern::try_new(&draft.pattern, lang).ok()?;
+    let mut validation = Vec::with_capacity(examples.len());
+    for (example_index, example) in examples.iter().enumerate() {
+        let ast = lang.ast_grep(&example.before);
+        let matches = ast.root().find_all(&pattern).count();
+        let rewritten = if matches == 0 {
+            example.before.clone()
+        } else {
+            matcher
+                .replace(&example.before, &draft.pattern, &draft.replacement, lang)
+                .ok()?
+        };
+        let reproduces_after = rewritten.trim_end() == example.after.trim_end();
+        validation.push(ExampleValidation {
+            example_index,
+            matches,
+            rewritten,
+            reproduces_after,
+        });
+    }
+
+    let examples_matched = validation.iter().filter(|v| v.matches > 0).count();
+    let examples_reproduced = validation.iter().filter(|v| v.reproduces_after).count();
+    if examples_matched == 0 {
+        return None;
+    }
+    Some(PatternCandidate {
+        pattern: draft.pattern.clone(),
+        replacement: draft.replacement.clone(),
+        strategy: draft.strategy.to_string(),
+        score: (examples_matched + examples_reproduced) as f64 / (2 * examples.len()) as f64,
+        examples_matched,
+        examples_reproduced,
+        validation,
+    })
+}
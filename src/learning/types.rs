@@ -100,3 +100,56 @@ pub struct CatalogPattern {
     pub related_patterns: Vec<String>,
     pub learning_notes: String,
 }
+
+/// A before/after pair showing one instance of the change to make
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternExample {
+    pub before: String,
+    pub after: String,
+}
+
+/// Parameters for synthesizing patterns from examples
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestPatternParam {
+    pub examples: Vec<PatternExample>,
+    pub language: String,
+    #[serde(default = "default_max_candidates")]
+    pub max_candidates: usize,
+}
+
+fn default_max_candidates() -> usize {
+    5
+}
+
+/// Candidate patterns synthesized from examples, best first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestPatternResult {
+    pub candidates: Vec<PatternCandidate>,
+    /// Why examples produced no candidates, e.g. because before and after are identical
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternCandidate {
+    pub pattern: String,
+    pub replacement: String,
+    /// `literal` for the changed code as written, `generalized` when kept subtrees became
+    /// metavariables
+    pub strategy: String,
+    /// Share of the examples the candidate matches and reproduces, from 0 to 1
+    pub score: f64,
+    pub examples_matched: usize,
+    pub examples_reproduced: usize,
+    pub validation: Vec<ExampleValidation>,
+}
+
+/// How a candidate fares on one of the examples
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExampleValidation {
+    pub example_index: usize,
+    pub matches: usize,
+    /// The example's `before` after applying the candidate
+    pub rewritten: String,
+    pub reproduces_after: bool,
+}
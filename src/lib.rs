@@ -50,25 +50,77 @@
 //! - **Struct Update Syntax**: In Rust, fields must come before `..Default::default()` in
 //!   struct literals.
 
+pub mod analysis;
+pub mod annotation;
+pub mod anonymize;
+pub mod archive;
 pub mod ast_grep_service;
 pub mod ast_utils;
+pub mod batch;
+pub mod cancellation;
+pub mod code_buffer;
+pub mod codemods;
+pub mod compression;
 pub mod config;
 pub mod context_lines;
+pub mod conventions;
+pub mod coverage;
+pub mod cursor;
+pub mod dedupe_code;
+pub mod dialect;
+pub mod directory_move;
+pub mod directory_summary;
+pub mod doc_coverage;
+pub mod dynamic_languages;
+pub mod enclosing_symbol;
+pub mod env_interpolation;
 pub mod errors;
+pub mod experiment;
+pub mod export;
+pub mod extra_languages;
+pub mod file_fetch;
+pub mod format_check;
+pub mod git;
+pub mod health_check;
+pub mod index;
+pub mod input_limits;
+pub mod json_patch;
+pub mod language_detection;
 pub mod language_injection;
 pub mod learning;
+pub mod lsp;
+pub mod merge;
+pub mod overlay;
 pub mod path_validation;
 pub mod pattern;
+pub mod playground;
+pub mod prewarm;
 pub mod refactoring;
+pub mod references;
+pub mod regex_fallback;
 pub mod replace;
+pub mod resources;
 pub mod response_formatter;
 pub mod rules;
 pub mod search;
 pub mod search_match;
 pub mod sg_config;
+pub mod similar_code;
+pub mod symbol_search;
+pub mod telemetry;
+pub mod template;
+pub mod tool_middleware;
+pub mod tool_registry;
 pub mod tool_router;
 pub mod tools;
 pub mod types;
+pub mod unified_diff;
+pub mod value_search;
+pub mod verification;
+pub mod walk;
+pub mod worker;
+pub mod wrap;
+pub mod write_lock;
 
 #[cfg(test)]
 mod test_context_integration;
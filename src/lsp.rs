@@ -0,0 +1,366 @@
+//! # Language Server Bridge
+//!
+//! Surfaces stored rule matches in editors that speak the Language Server Protocol but not MCP.
+//! The `lsp` subcommand runs [`LspServer`] over stdio. It keeps open documents in memory using
+//! full-text sync. After every open, change and save it publishes
+//! `textDocument/publishDiagnostics` for the stored rules whose language matches the document.
+//!
+//! Only the handful of messages needed for diagnostics are implemented. Other requests get a
+//! `MethodNotFound` error and other notifications are ignored.
+
+use crate::rules::suppression::filter_suppressed;
+use crate::rules::{RuleConfig, RuleEvaluator};
+use crate::types::MatchResult;
+use ast_grep_core::Language as _;
+use ast_grep_language::SupportLang as Language;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// `source` reported on every diagnostic
+pub const DIAGNOSTIC_SOURCE: &str = "ast-grep";
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_REQUEST: i64 = -32600;
+
+/// Zero-based line and UTF-16 column, as LSP counts them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// LSP `DiagnosticSeverity`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "u8", try_from = "u8")]
+pub enum DiagnosticSeverity {
+    Error = 1,
+    Warning = 2,
+    Information = 3,
+    Hint = 4,
+}
+
+impl DiagnosticSeverity {
+    /// Map a rule's `severity`; rules without one are hints, as in ast-grep
+    pub fn from_rule(severity: Option<&str>) -> Self {
+        match severity {
+            Some("error") => Self::Error,
+            Some("warning") => Self::Warning,
+            Some("info") => Self::Information,
+            _ => Self::Hint,
+        }
+    }
+}
+
+impl From<DiagnosticSeverity> for u8 {
+    fn from(severity: DiagnosticSeverity) -> Self {
+        severity as u8
+    }
+}
+
+impl TryFrom<u8> for DiagnosticSeverity {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, String> {
+        match value {
+            1 => Ok(Self::Error),
+            2 => Ok(Self::Warning),
+            3 => Ok(Self::Information),
+            4 => Ok(Self::Hint),
+            _ => Err(format!("invalid diagnostic severity {value}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub range: Range,
+    pub severity: DiagnosticSeverity,
+    /// Id of the rule that matched
+    pub code: String,
+    pub source: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PublishDiagnosticsParams {
+    pub uri: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Diagnostics for every rule in `rules` written for `lang`, ordered by position. Matches
+/// silenced by `ast-grep-ignore` comments are left out
+pub fn diagnostics_for_code(
+    rules: &[RuleConfig],
+    evaluator: &RuleEvaluator,
+    code: &str,
+    lang: Language,
+) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = code.lines().collect();
+    let mut diagnostics = Vec::new();
+    for rule in rules {
+        if Language::from_str(&rule.language).ok() != Some(lang) {
+            continue;
+        }
+        let matches = match evaluator.evaluate_rule_config(rule, code, lang) {
+            Ok(matches) => matches,
+            Err(e) => {
+                tracing::warn!("Skipping rule {}: {e}", rule.id);
+                continue;
+            }
+        };
+        let (matches, _) = filter_suppressed(code, &rule.id, matches);
+        diagnostics.extend(matches.iter().map(|m| to_diagnostic(rule, m, &lines)));
+    }
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.range.start, diagnostic.code.clone()));
+    diagnostics
+}
+
+fn to_diagnostic(rule: &RuleConfig, m: &MatchResult, lines: &[&str]) -> Diagnostic {
+    Diagnostic {
+        range: Range {
+            start: position(lines, m.start_line, m.start_col),
+            end: position(lines, m.end_line, m.end_col),
+        },
+        severity: DiagnosticSeverity::from_rule(rule.severity.as_deref()),
+        code: rule.id.clone(),
+        source: DIAGNOSTIC_SOURCE.to_string(),
+        message: rule.message.clone().unwrap_or_else(|| rule.id.clone()),
+    }
+}
+
+/// Convert a character column to the UTF-16 offset LSP expects
+fn position(lines: &[&str], line: usize, column: usize) -> Position {
+    let character = lines
+        .get(line)
+        .map(|text| text.chars().take(column).map(char::len_utf16).sum())
+        .unwrap_or(column);
+    Position {
+        line: line as u32,
+        character: character as u32,
+    }
+}
+
+/// Read one `Content-Length` framed JSON-RPC message, or `None` at end of input
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            content_length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length = content_length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    })?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Write one `Content-Length` framed JSON-RPC message
+pub fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n", body.len())?;
+    writer.write_all(&body)?;
+    writer.flush()
+}
+
+struct Document {
+    lang: Option<Language>,
+    text: String,
+}
+
+/// Minimal language server publishing rule matches as diagnostics
+pub struct LspServer {
+    rules: Vec<RuleConfig>,
+    evaluator: RuleEvaluator,
+    documents: HashMap<String, Document>,
+    shutdown_requested: bool,
+}
+
+impl LspServer {
+    pub fn new(rules: Vec<RuleConfig>) -> Self {
+        Self {
+            rules,
+            evaluator: RuleEvaluator::new(),
+            documents: HashMap::new(),
+            shutdown_requested: false,
+        }
+    }
+
+    /// Serve messages from `input` until the client sends `exit` or closes the stream
+    pub fn run(&mut self, mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        while let Some(message) = read_message(&mut input)? {
+            if message.get("method").and_then(Value::as_str) == Some("exit") {
+                break;
+            }
+            for reply in self.handle(&message) {
+                write_message(&mut output, &reply)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Responses and notifications to send for one incoming message
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        match (method, id) {
+            ("initialize", Some(id)) => vec![response(
+                id,
+                json!({
+                    "capabilities": {
+                        "textDocumentSync": { "openClose": true, "change": 1, "save": true }
+                    },
+                    "serverInfo": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION")
+                    }
+                }),
+            )],
+            ("shutdown", Some(id)) => {
+                self.shutdown_requested = true;
+                vec![response(id, Value::Null)]
+            }
+            (_, Some(id)) if self.shutdown_requested => vec![error_response(
+                id,
+                INVALID_REQUEST,
+                "Server is shutting down",
+            )],
+            (method, Some(id)) => vec![error_response(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("Unsupported method: {method}"),
+            )],
+            ("textDocument/didOpen", None) => {
+                let document = &params["textDocument"];
+                let (Some(uri), Some(text)) = (document["uri"].as_str(), document["text"].as_str())
+                else {
+                    return Vec::new();
+                };
+                let lang = document_language(uri, document["languageId"].as_str());
+                self.documents.insert(
+                    uri.to_string(),
+                    Document {
+                        lang,
+                        text: text.to_string(),
+                    },
+                );
+                self.publish(uri).into_iter().collect()
+            }
+            ("textDocument/didChange", None) => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                // Full sync: the last change carries the whole document
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                match (self.documents.get_mut(uri), text) {
+                    (Some(document), Some(text)) => {
+                        document.text = text.to_string();
+                        self.publish(uri).into_iter().collect()
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            ("textDocument/didSave", None) => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                if let (Some(document), Some(text)) =
+                    (self.documents.get_mut(uri), params["text"].as_str())
+                {
+                    document.text = text.to_string();
+                }
+                self.publish(uri).into_iter().collect()
+            }
+            ("textDocument/didClose", None) => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                match self.documents.remove(uri) {
+                    Some(_) => vec![publish_notification(uri, Vec::new())],
+                    None => Vec::new(),
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn publish(&self, uri: &str) -> Option<Value> {
+        let document = self.documents.get(uri)?;
+        let diagnostics = match document.lang {
+            Some(lang) => diagnostics_for_code(&self.rules, &self.evaluator, &document.text, lang),
+            None => Vec::new(),
+        };
+        Some(publish_notification(uri, diagnostics))
+    }
+}
+
+fn response(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn publish_notification(uri: &str, diagnostics: Vec<Diagnostic>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": PublishDiagnosticsParams {
+            uri: uri.to_string(),
+            diagnostics,
+        }
+    })
+}
+
+/// Language of a document from its file extension, falling back to the client's language id
+fn document_language(uri: &str, language_id: Option<&str>) -> Option<Language> {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    Language::from_path(Path::new(&percent_decode(path)))
+        .or_else(|| language_id.and_then(|id| Language::from_str(id).ok()))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
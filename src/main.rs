@@ -24,6 +24,27 @@
 //! ast-grep-mcp generate-ast --language javascript --code "function test() {}"
 //! ```
 //!
+//! ### Filter Mode
+//! ```bash
+//! # Rewrite code piped through stdin and print the result
+//! git show HEAD:src/app.js | ast-grep-mcp replace --stdin-files \
+//!     --pattern "var $A = $B" --replacement "let $A = $B" --language javascript
+//! ```
+//! Exits with status 0 when the code changed, 1 when it was printed unchanged, and 2 on errors.
+//!
+//! ### Batch Mode
+//! ```bash
+//! # One JSON tool call per input line, one JSON result per output line
+//! echo '{"id": 1, "tool": "list_languages"}' | ast-grep-mcp --batch
+//! ```
+//! Exits with status 1 if any call failed.
+//!
+//! ### Language Server Mode
+//! ```bash
+//! # Publish stored rule matches as LSP diagnostics over stdio
+//! ast-grep-mcp lsp
+//! ```
+//!
 //! ## Configuration
 //!
 //! The service can be configured via command-line arguments:
@@ -31,18 +52,37 @@
 //! - `--max-file-size`: Set maximum file size to process
 //! - `--max-concurrency`: Control concurrent file operations
 //! - `--rules-dir`: Directory for storing custom rules
+//! - `--rules-db`: Store custom rules in a sqlite database instead (default: ~/.ast-grep-mcp/rules.db)
+//! - `--isolate-workers`: Parse files in sandboxed worker processes (see `--worker-timeout-ms`, `--worker-memory-mb`)
+//! - `--workspace`: Add a labelled root (`NAME=PATH`) that search and replace calls can be limited to
+//! - `--verify-command`: Name a command (`NAME=COMMAND`) that `verify_refactoring` may run
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use rmcp::{ServiceExt, transport::stdio};
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
-use tracing_subscriber::{self, filter::EnvFilter};
+use std::time::Duration;
+use tracing_subscriber::{self, Layer, filter::EnvFilter, prelude::*};
 
 use ast_grep_mcp::{
     GenerateAstParam, RuleReplaceParam, RuleSearchParam, SearchParam,
-    ast_grep_service::AstGrepService, config::ServiceConfig, types::*,
+    ast_grep_service::AstGrepService,
+    batch::run_batch,
+    config::{RuleRegistryConfig, ServiceConfig},
+    env_interpolation::capture_env_vars,
+    input_limits::default_max_input_sizes,
+    lsp::LspServer,
+    playground::write_playground,
+    rules::open_rule_storage,
+    types::*,
+    worker::{WorkerConfig, run_worker},
 };
 
+/// Environment variable holding the bearer token of `--rule-registry`, kept out of the
+/// command line so it doesn't show up in process listings
+const REGISTRY_TOKEN_VAR: &str = "AST_GREP_MCP_REGISTRY_TOKEN";
+
 /// AST-Grep MCP Server - Structural code search and transformation
 #[derive(Parser, Debug)]
 #[command(name = "ast-grep-mcp")]
@@ -54,6 +94,10 @@ struct Args {
 
     #[command(flatten)]
     global: GlobalArgs,
+
+    /// Read line-delimited JSON tool calls from stdin and write one JSON result per line
+    #[arg(long)]
+    batch: bool,
 }
 
 #[derive(Parser, Debug)]
@@ -83,6 +127,13 @@ struct GlobalArgs {
     )]
     max_concurrency: usize,
 
+    /// Files parsed and matched at once
+    #[arg(
+        long = "cpu-concurrency",
+        help = "Number of files parsed and matched at once by file_replace and batch_search [default: number of CPU cores]"
+    )]
+    cpu_concurrency: Option<usize>,
+
     /// Maximum number of results to return per search
     #[arg(
         long = "limit",
@@ -99,6 +150,39 @@ struct GlobalArgs {
     )]
     rules_directory: Option<PathBuf>,
 
+    /// Sqlite database for storing custom rules
+    #[arg(
+        long = "rules-db",
+        help = "Store custom rules in a sqlite database instead of YAML files in --rules-dir (default: ~/.ast-grep-mcp/rules.db)",
+        value_name = "PATH",
+        num_args = 0..=1
+    )]
+    rules_database: Option<Option<PathBuf>>,
+
+    /// Team rule registry for push_rule and pull_rules
+    #[arg(
+        long = "rule-registry",
+        help = "Base URL of a team rule registry to share rules through with push_rule and pull_rules. The bearer token is read from AST_GREP_MCP_REGISTRY_TOKEN",
+        value_name = "URL"
+    )]
+    rule_registry: Option<String>,
+
+    /// Directory for pattern_coverage snapshots
+    #[arg(
+        long = "coverage-dir",
+        help = "Directory for pattern_coverage migration snapshots (default: ~/.ast-grep-mcp/coverage)",
+        value_name = "PATH"
+    )]
+    coverage_directory: Option<PathBuf>,
+
+    /// Directory where file_replace records files for rollback_replace
+    #[arg(
+        long = "undo-dir",
+        help = "Directory where file_replace records the previous contents of the files it writes, for rollback_replace (default: .ast-grep-undo in the first root directory)",
+        value_name = "PATH"
+    )]
+    undo_directory: Option<PathBuf>,
+
     /// Maximum number of compiled patterns to cache
     #[arg(
         long = "pattern-cache-size",
@@ -114,6 +198,160 @@ struct GlobalArgs {
         value_name = "PATH"
     )]
     sg_config_path: Option<PathBuf>,
+
+    /// Parse and match files in sandboxed worker processes
+    #[arg(
+        long = "isolate-workers",
+        help = "Parse files in worker processes so crashes and hangs skip the file instead of stopping the server"
+    )]
+    isolate_workers: bool,
+
+    /// Time limit per file for isolated workers
+    #[arg(
+        long = "worker-timeout-ms",
+        default_value = "10000",
+        help = "Time limit per file in milliseconds for isolated workers"
+    )]
+    worker_timeout_ms: u64,
+
+    /// Memory limit for isolated workers
+    #[arg(
+        long = "worker-memory-mb",
+        default_value = "1024",
+        help = "Address-space limit in megabytes for isolated workers (0 for no limit, Unix only)"
+    )]
+    worker_memory_mb: u64,
+
+    /// Time budget per rule for rule_search
+    #[arg(
+        long = "rule-timeout-ms",
+        default_value = "30000",
+        help = "Time limit in milliseconds for evaluating one rule in rule_search; results found so far are returned"
+    )]
+    rule_timeout_ms: u64,
+
+    /// Match budget per rule for rule_search
+    #[arg(
+        long = "rule-max-matches",
+        default_value = "10000",
+        help = "Maximum matches collected for one rule in rule_search; results found so far are returned"
+    )]
+    rule_max_matches: usize,
+
+    /// Formatter commands for `verify_formatting`
+    #[arg(
+        long = "formatter",
+        help = "Formatter for a language as LANG=COMMAND, e.g. rust='rustfmt --edition 2021' (repeatable)",
+        value_name = "LANG=COMMAND"
+    )]
+    formatters: Vec<String>,
+
+    /// Labelled root directories that tool calls can be limited to
+    #[arg(
+        long = "workspace",
+        help = "Root directory with a label as NAME=PATH; tools accept `workspace: NAME` to stay inside it (repeatable)",
+        value_name = "NAME=PATH"
+    )]
+    workspaces: Vec<String>,
+
+    /// Commands `verify_refactoring` may run after applying a replacement
+    #[arg(
+        long = "verify-command",
+        help = "Verification command with a name as NAME=COMMAND, e.g. check='cargo check --quiet'; run by verify_refactoring in the workspace root (repeatable)",
+        value_name = "NAME=COMMAND"
+    )]
+    verify_commands: Vec<String>,
+
+    /// Directory depth limit for file-based tools
+    #[arg(
+        long = "max-depth",
+        default_value = "10",
+        help = "How many directory levels below a root the file-based tools descend"
+    )]
+    max_depth: usize,
+
+    /// Directory names the file-based tools skip
+    #[arg(
+        long = "prune-dir",
+        help = "Directory name never to descend into, e.g. node_modules (repeatable)",
+        value_name = "NAME"
+    )]
+    prune_dirs: Vec<String>,
+
+    /// Skip hidden entries in file-based tools
+    #[arg(
+        long = "skip-hidden",
+        help = "Don't walk hidden directories and files, whose names start with a dot"
+    )]
+    skip_hidden: bool,
+
+    /// Walk files excluded by .gitignore in file-based tools
+    #[arg(
+        long = "no-gitignore",
+        help = "Also walk files excluded by .gitignore, .ignore and .git/info/exclude, and the .git directory"
+    )]
+    no_gitignore: bool,
+
+    /// Environment variables rules may interpolate
+    #[arg(
+        long = "allow-env",
+        help = "Environment variable that rule configs and path patterns may reference as ${NAME} (repeatable)",
+        value_name = "NAME"
+    )]
+    allow_env: Vec<String>,
+
+    /// Keep an in-memory file index between searches
+    #[arg(
+        long = "index",
+        help = "Keep the file list and parsed syntax trees of the root directories in memory, updated as files change, so repeated file_search calls skip the walk and unchanged files"
+    )]
+    index: bool,
+
+    /// Fill the caches in the background after startup
+    #[arg(
+        long = "prewarm",
+        help = "After startup, walk the root directories in the background to fill the file index (with --index) and the symbol cache of search_symbols, so the first calls on a large checkout don't pay for a cold start"
+    )]
+    prewarm: bool,
+
+    /// Pause of the background pre-warming
+    #[arg(
+        long = "prewarm-delay-ms",
+        default_value = "1",
+        help = "Time in milliseconds --prewarm pauses after every 100 entries walked and every file parsed, to leave I/O and CPU to tool calls"
+    )]
+    prewarm_delay_ms: u64,
+
+    /// Skip the per-root write lockfile
+    #[arg(
+        long = "no-lock",
+        help = "Apply changes without taking the .ast-grep-mcp.lock lockfile in the root directories; only safe when no other server writes to the same roots"
+    )]
+    no_lock: bool,
+
+    /// How long a write waits for another server's lock
+    #[arg(
+        long = "lock-timeout-ms",
+        default_value = "30000",
+        help = "Time in milliseconds a write waits for another server's lock on its root directories before failing"
+    )]
+    lock_timeout_ms: u64,
+
+    /// How long a pagination cursor stays valid
+    #[arg(
+        long = "cursor-ttl-secs",
+        default_value = "3600",
+        help = "Time in seconds a pagination cursor stays valid; an older cursor fails with STALE_CURSOR"
+    )]
+    cursor_ttl_secs: u64,
+
+    /// Size limits of tool arguments
+    #[arg(
+        long = "max-input-size",
+        help = "Largest size in bytes of a tool argument as NAME=BYTES, e.g. code=4194304; 0 removes the limit. Defaults: code=1048576, rule_config=262144 (repeatable)",
+        value_name = "NAME=BYTES"
+    )]
+    max_input_sizes: Vec<String>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -135,6 +373,24 @@ enum Commands {
         #[arg(short, long)]
         file: Option<PathBuf>,
     },
+    /// Rewrite code and print the result to stdout
+    Replace {
+        /// Pattern to search for
+        #[arg(short, long)]
+        pattern: String,
+        /// Replacement (may use metavariables from the pattern)
+        #[arg(short, long)]
+        replacement: String,
+        /// Programming language
+        #[arg(short, long)]
+        language: String,
+        /// Read the code from stdin (for shell pipelines and git filters)
+        #[arg(long, conflicts_with = "file", required_unless_present = "file")]
+        stdin_files: bool,
+        /// File to rewrite (left unchanged on disk)
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+    },
     /// Search files using ast-grep patterns
     FileSearch {
         /// Pattern to search for
@@ -149,6 +405,12 @@ enum Commands {
         /// Maximum results
         #[arg(long, default_value = "100")]
         max_results: usize,
+        /// Run a pattern that is a lone metavariable such as `$_`, which matches nearly every node
+        #[arg(long)]
+        allow_broad: bool,
+        /// Search files as they were at this git tag, branch or commit
+        #[arg(long)]
+        revision: Option<String>,
     },
     /// Search files using rules
     RuleSearch {
@@ -179,6 +441,9 @@ enum Commands {
         /// Maximum results
         #[arg(long, default_value = "100")]
         max_results: usize,
+        /// Apply fixes from rules declaring `fix_safety: unsafe`
+        #[arg(long)]
+        allow_unsafe: bool,
     },
     /// Generate AST for code
     GenerateAst {
@@ -210,6 +475,30 @@ enum Commands {
         #[arg(long)]
         fragment_lines: Option<String>,
     },
+    /// Write a sample project with deliberate lint findings to try the server on
+    CreatePlayground {
+        /// Directory to write it into (default: a new directory under the system temp dir)
+        directory: Option<PathBuf>,
+        /// Write into a directory that already has files, replacing the sample files
+        #[arg(long)]
+        overwrite: bool,
+    },
+    /// Check that the root directories, the directories the server writes to and the grammars
+    /// are usable; exits with status 1 when a check fails
+    Doctor {
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Run a language server that reports stored rule matches as diagnostics
+    Lsp,
+    /// Answer parse jobs from stdin (spawned by --isolate-workers)
+    #[command(hide = true)]
+    Worker {
+        /// Address-space limit in megabytes
+        #[arg(long)]
+        memory_limit_mb: Option<u64>,
+    },
 }
 
 /// Main entry point for the ast-grep MCP service.
@@ -223,38 +512,71 @@ async fn main() -> Result<()> {
     let args = Args::parse();
 
     // Initialize the tracing subscriber based on mode
-    let is_mcp_mode = matches!(args.command, Some(Commands::Serve) | None);
-
-    if is_mcp_mode {
-        // For MCP mode, disable all logging to avoid interfering with JSON protocol
-        // MCP clients expect clean JSON over stdio
-        tracing_subscriber::fmt()
-            .with_env_filter(
-                EnvFilter::from_default_env().add_directive(tracing::Level::ERROR.into()),
-            )
-            .with_writer(std::io::stderr)
-            .with_ansi(false)
-            .init();
+    let is_mcp_mode = matches!(args.command, Some(Commands::Serve) | None) && !args.batch;
+
+    // For MCP mode, disable all logging to avoid interfering with JSON protocol
+    // MCP clients expect clean JSON over stdio. For CLI mode, allow normal logging
+    let level = if is_mcp_mode {
+        tracing::Level::ERROR
     } else {
-        // For CLI mode, allow normal logging
-        tracing_subscriber::fmt()
-            .with_env_filter(
-                EnvFilter::from_default_env().add_directive(tracing::Level::WARN.into()),
-            )
-            .with_writer(std::io::stderr)
-            .with_ansi(false)
-            .init();
+        tracing::Level::WARN
+    };
+    let fmt_layer = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .with_filter(EnvFilter::from_default_env().add_directive(level.into()));
+    let subscriber = tracing_subscriber::registry().with(fmt_layer);
+
+    // Export tool call spans and metrics when an OTLP endpoint is configured
+    #[cfg(feature = "otel")]
+    let _telemetry = {
+        let (otel_layer, guard) = ast_grep_mcp::telemetry::otel::layer()
+            .map_err(|e| anyhow::anyhow!("Failed to set up OpenTelemetry export: {e}"))?
+            .unzip();
+        subscriber.with(otel_layer).init();
+        guard
+    };
+    #[cfg(not(feature = "otel"))]
+    subscriber.init();
+
+    if let Some(Commands::Worker { memory_limit_mb }) = args.command {
+        return Ok(run_worker(memory_limit_mb)?);
     }
 
     // Create a custom config from command line arguments
     let config = create_config_from_args(args.global)?;
+    // Fail now rather than on the first rule tool call when the rule database can't be opened
+    let rule_storage = open_rule_storage(&config)?;
+
+    if args.batch {
+        if args.command.is_some() {
+            anyhow::bail!("--batch cannot be combined with a subcommand");
+        }
+        let service = AstGrepService::with_config(config);
+        let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+        let summary = run_batch(&service, stdin, tokio::io::stdout()).await?;
+        if summary.failed > 0 {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
     match args.command {
         Some(Commands::Serve) | None => {
             // Default MCP server mode - no output to avoid interfering with MCP JSON protocol
-            let service = AstGrepService::with_config(config).serve(stdio()).await?;
+            let service = AstGrepService::with_config(config);
+            service.spawn_prewarm();
+            let service = service.serve(stdio()).await?;
             service.waiting().await?;
         }
+        Some(Commands::Lsp) => {
+            // Stored rules are loaded once; restart the server to pick up new ones
+            let rules = rule_storage.load_all_rules().await?;
+            tokio::task::spawn_blocking(move || {
+                LspServer::new(rules).run(std::io::stdin().lock(), std::io::stdout().lock())
+            })
+            .await??;
+        }
         Some(command) => {
             // CLI command mode
             run_cli_command(command, config).await?;
@@ -274,12 +596,32 @@ async fn main() -> Result<()> {
 ///
 /// Returns an error if the current working directory cannot be determined.
 fn create_config_from_args(args: GlobalArgs) -> Result<ServiceConfig> {
-    let root_directories = if args.root_directories.is_empty() {
+    let mut workspaces = BTreeMap::new();
+    for workspace in &args.workspaces {
+        let (label, path) = workspace
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("--workspace expects NAME=PATH, got `{workspace}`"))?;
+        if label.is_empty() || path.is_empty() {
+            anyhow::bail!("--workspace expects NAME=PATH, got `{workspace}`");
+        }
+        if workspaces
+            .insert(label.to_string(), PathBuf::from(path))
+            .is_some()
+        {
+            anyhow::bail!("--workspace `{label}` is given more than once");
+        }
+    }
+
+    let mut root_directories = args.root_directories;
+    for root in workspaces.values() {
+        if !root_directories.contains(root) {
+            root_directories.push(root.clone());
+        }
+    }
+    if root_directories.is_empty() {
         // Default to current working directory
-        vec![std::env::current_dir()?]
-    } else {
-        args.root_directories
-    };
+        root_directories.push(std::env::current_dir()?);
+    }
 
     let rules_directory = args.rules_directory.unwrap_or_else(|| {
         // Default to ~/.ast-grep-mcp/rules
@@ -288,17 +630,111 @@ fn create_config_from_args(args: GlobalArgs) -> Result<ServiceConfig> {
             .join(".ast-grep-mcp")
             .join("rules")
     });
+    let rules_database = args.rules_database.map(|path| {
+        path.unwrap_or_else(|| {
+            dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".ast-grep-mcp")
+                .join("rules.db")
+        })
+    });
+    let rule_registry = args.rule_registry.map(|url| RuleRegistryConfig {
+        url,
+        token: std::env::var(REGISTRY_TOKEN_VAR).ok(),
+    });
+    let coverage_directory = args.coverage_directory.unwrap_or_else(|| {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(".ast-grep-mcp")
+            .join("coverage")
+    });
+    let undo_directory = args
+        .undo_directory
+        .unwrap_or_else(|| root_directories[0].join(".ast-grep-undo"));
+
+    let worker_isolation = if args.isolate_workers {
+        let mut workers = WorkerConfig::new(std::env::current_exe()?);
+        workers.timeout = Duration::from_millis(args.worker_timeout_ms);
+        workers.memory_limit_mb = (args.worker_memory_mb > 0).then_some(args.worker_memory_mb);
+        Some(workers)
+    } else {
+        None
+    };
+
+    let mut formatters = HashMap::new();
+    for formatter in &args.formatters {
+        let (language, command) = formatter.split_once('=').ok_or_else(|| {
+            anyhow::anyhow!("--formatter expects LANG=COMMAND, got `{formatter}`")
+        })?;
+        let command: Vec<String> = command.split_whitespace().map(str::to_string).collect();
+        if command.is_empty() {
+            anyhow::bail!("--formatter for `{language}` has an empty command");
+        }
+        formatters.insert(language.to_string(), command);
+    }
+
+    let mut verify_commands = BTreeMap::new();
+    for verify_command in &args.verify_commands {
+        let (name, command) = verify_command.split_once('=').ok_or_else(|| {
+            anyhow::anyhow!("--verify-command expects NAME=COMMAND, got `{verify_command}`")
+        })?;
+        let command: Vec<String> = command.split_whitespace().map(str::to_string).collect();
+        if command.is_empty() {
+            anyhow::bail!("--verify-command `{name}` has an empty command");
+        }
+        verify_commands.insert(name.to_string(), command);
+    }
+
+    let mut max_input_sizes = default_max_input_sizes();
+    for max_input_size in &args.max_input_sizes {
+        let (name, bytes) = max_input_size
+            .split_once('=')
+            .and_then(|(name, bytes)| Some((name, bytes.parse::<usize>().ok()?)))
+            .ok_or_else(|| {
+                anyhow::anyhow!("--max-input-size expects NAME=BYTES, got `{max_input_size}`")
+            })?;
+        match bytes {
+            0 => max_input_sizes.remove(name),
+            _ => max_input_sizes.insert(name.to_string(), bytes),
+        };
+    }
 
     let config = ServiceConfig {
         max_file_size: args.max_file_size,
         max_concurrency: args.max_concurrency,
+        cpu_concurrency: args
+            .cpu_concurrency
+            .unwrap_or_else(|| ServiceConfig::default().cpu_concurrency),
         limit: args.limit,
         root_directories,
         rules_directory,
+        rules_database,
+        rule_registry,
         pattern_cache_size: args.pattern_cache_size,
         additional_rule_dirs: Vec::new(),
         util_dirs: Vec::new(),
         sg_config_path: None,
+        worker_isolation,
+        formatters,
+        workspaces,
+        verify_commands,
+        coverage_directory,
+        rule_timeout: Duration::from_millis(args.rule_timeout_ms),
+        rule_max_matches: args.rule_max_matches,
+        walk_max_depth: args.max_depth,
+        prune_dirs: args.prune_dirs,
+        follow_hidden: !args.skip_hidden,
+        respect_gitignore: !args.no_gitignore,
+        env_vars: capture_env_vars(&args.allow_env).map_err(anyhow::Error::msg)?,
+        undo_directory,
+        file_index: args.index,
+        prewarm: args
+            .prewarm
+            .then(|| Duration::from_millis(args.prewarm_delay_ms)),
+        write_lock: !args.no_lock,
+        write_lock_timeout: Duration::from_millis(args.lock_timeout_ms),
+        cursor_ttl: Duration::from_secs(args.cursor_ttl_secs),
+        max_input_sizes,
     };
 
     // Load sgconfig.yml if available
@@ -318,11 +754,13 @@ fn create_config_from_args(args: GlobalArgs) -> Result<ServiceConfig> {
 /// - `rule-search`: Search using YAML rule configurations
 /// - `rule-replace`: Replace using YAML rule configurations
 /// - `generate-ast`: Generate AST for understanding node structure
+/// - `create-playground`: Write a sample project to try the server on
+/// - `doctor`: Check the server's setup
 async fn run_cli_command(command: Commands, config: ServiceConfig) -> Result<()> {
     let service = AstGrepService::with_config(config);
 
     match command {
-        Commands::Serve => unreachable!(), // Handled in main
+        Commands::Serve | Commands::Lsp | Commands::Worker { .. } => unreachable!(), // Handled in main
 
         Commands::Search {
             pattern,
@@ -348,11 +786,33 @@ async fn run_cli_command(command: Commands, config: ServiceConfig) -> Result<()>
             }
         }
 
+        Commands::Replace {
+            pattern,
+            replacement,
+            language,
+            stdin_files,
+            file,
+        } => {
+            let code = stdin_files.then(|| "-".to_string());
+            let status =
+                match rewrite_code(&service, code, file, &pattern, &replacement, &language).await {
+                    Ok(true) => 0,
+                    Ok(false) => 1,
+                    Err(e) => {
+                        eprintln!("Error: {e:#}");
+                        2
+                    }
+                };
+            std::process::exit(status);
+        }
+
         Commands::FileSearch {
             pattern,
             language,
             path_pattern,
             max_results,
+            allow_broad,
+            revision,
         } => {
             let param = FileSearchParam {
                 pattern,
@@ -367,6 +827,22 @@ async fn run_cli_command(command: Commands, config: ServiceConfig) -> Result<()>
                 context_before: None,
                 context_after: None,
                 context_lines: None,
+                output_file: None,
+                overlays: Default::default(),
+                dialect: None,
+                language_patterns: Default::default(),
+                search_archives: false,
+                workspace: None,
+                regex_fallback: false,
+                include_ast_context: false,
+                allow_broad,
+                revision,
+                suggest_fixes: false,
+                timeout_ms: None,
+                walk: Default::default(),
+                stream: false,
+                context_scope: None,
+                include_leading_trivia: false,
             };
 
             let result = service.file_search(param).await?;
@@ -403,6 +879,13 @@ async fn run_cli_command(command: Commands, config: ServiceConfig) -> Result<()>
                 max_results,
                 max_file_size: 1024 * 1024, // 1MB default
                 cursor: None,
+                output_file: None,
+                workspace: None,
+                timeout_ms: None,
+                max_matches: None,
+                output_format: Default::default(),
+                walk: Default::default(),
+                pack: None,
             };
 
             let result = service.rule_search(param).await?;
@@ -436,6 +919,7 @@ async fn run_cli_command(command: Commands, config: ServiceConfig) -> Result<()>
             apply,
             summary_only,
             max_results,
+            allow_unsafe,
         } => {
             let rule_config = std::fs::read_to_string(&rule)?;
             let param = RuleReplaceParam {
@@ -446,6 +930,11 @@ async fn run_cli_command(command: Commands, config: ServiceConfig) -> Result<()>
                 dry_run: !apply,            // Invert apply flag
                 summary_only,
                 cursor: None,
+                allow_unsafe,
+                workspace: None,
+                ignore_whitespace_changes: true,
+                timeout_ms: None,
+                walk: Default::default(),
             };
 
             let result = service.rule_replace(param).await?;
@@ -472,6 +961,19 @@ async fn run_cli_command(command: Commands, config: ServiceConfig) -> Result<()>
                     }
                 }
             }
+
+            if !result.unsafe_fixes.is_empty() {
+                println!(
+                    "\nUnsafe fixes in {} files (rerun with --allow-unsafe to apply):",
+                    result.unsafe_fixes.len()
+                );
+                for file_result in &result.unsafe_fixes {
+                    println!(
+                        "  {} ({} changes)",
+                        file_result.file_path, file_result.total_changes
+                    );
+                }
+            }
         }
 
         Commands::GenerateAst {
@@ -483,6 +985,7 @@ async fn run_cli_command(command: Commands, config: ServiceConfig) -> Result<()>
             let param = GenerateAstParam {
                 code: code_content,
                 language,
+                buffer_id: None,
             };
 
             let result = service.generate_ast(param).await?;
@@ -493,6 +996,52 @@ async fn run_cli_command(command: Commands, config: ServiceConfig) -> Result<()>
             println!("{}", result.ast);
         }
         
+        Commands::CreatePlayground {
+            directory,
+            overwrite,
+        } => {
+            let directory = directory.unwrap_or_else(|| {
+                std::env::temp_dir().join(format!("ast-grep-playground-{}", std::process::id()))
+            });
+            let files = write_playground(&directory, overwrite)?;
+            println!("Wrote {} files to {}:", files.len(), directory.display());
+            for file in &files {
+                println!("  {file}");
+            }
+            println!("\nStart a server on it with:");
+            println!("  ast-grep-mcp --root-dir {}", directory.display());
+            println!("or try a search from the command line:");
+            println!(
+                "  ast-grep-mcp --root-dir {} file-search --pattern 'console.log($$$ARGS)' --language typescript --path-pattern '**/*.ts'",
+                directory.display()
+            );
+        }
+
+        Commands::Doctor { json } => {
+            let result = service.health_check(HealthCheckParam {}).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            } else {
+                for check in &result.checks {
+                    let status = match check.status {
+                        CheckStatus::Ok => "ok",
+                        CheckStatus::Warning => "warning",
+                        CheckStatus::Error => "ERROR",
+                    };
+                    match &check.target {
+                        Some(target) => {
+                            println!("[{status}] {} {target}: {}", check.check, check.message)
+                        }
+                        None => println!("[{status}] {}: {}", check.check, check.message),
+                    }
+                }
+                println!("\n{} errors, {} warnings", result.errors, result.warnings);
+            }
+            if !result.healthy {
+                std::process::exit(1);
+            }
+        }
+
         Commands::AnalyzeRefactoring {
             language,
             fragment,
@@ -716,6 +1265,28 @@ async fn run_cli_command(command: Commands, config: ServiceConfig) -> Result<()>
     Ok(())
 }
 
+/// Print `code` rewritten with `pattern` -> `replacement` to stdout and report whether it changed
+async fn rewrite_code(
+    service: &AstGrepService,
+    code: Option<String>,
+    file: Option<PathBuf>,
+    pattern: &str,
+    replacement: &str,
+    language: &str,
+) -> Result<bool> {
+    use std::io::Write;
+
+    let code = get_code_content(code, file).await?;
+    let result = service
+        .replace(ReplaceParam::new(&code, pattern, replacement, language))
+        .await?;
+
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(result.new_code.as_bytes())?;
+    stdout.flush()?;
+    Ok(result.new_code != code)
+}
+
 /// Get code content from either direct input, file, or stdin.
 ///
 /// Supports three input methods:
@@ -770,10 +1341,35 @@ mod tests {
             root_directories: vec![],
             max_file_size: 1024,
             max_concurrency: 5,
+            cpu_concurrency: None,
             limit: 100,
             rules_directory: None,
+            rules_database: None,
+            rule_registry: None,
+            coverage_directory: None,
+            undo_directory: None,
             pattern_cache_size: 500,
             sg_config_path: None,
+            isolate_workers: false,
+            worker_timeout_ms: 10000,
+            worker_memory_mb: 1024,
+            rule_timeout_ms: 30000,
+            rule_max_matches: 10000,
+            formatters: vec![],
+            workspaces: vec![],
+            verify_commands: vec![],
+            max_depth: 10,
+            prune_dirs: vec![],
+            skip_hidden: false,
+            no_gitignore: false,
+            allow_env: vec![],
+            index: false,
+            prewarm: false,
+            prewarm_delay_ms: 1,
+            no_lock: false,
+            lock_timeout_ms: 30000,
+            cursor_ttl_secs: 3600,
+            max_input_sizes: Vec::new(),
         };
 
         let config = create_config_from_args(args).unwrap();
@@ -800,21 +1396,73 @@ mod tests {
             root_directories: vec![custom_root.clone()],
             max_file_size: 2048,
             max_concurrency: 10,
+            cpu_concurrency: Some(2),
             limit: 200,
             rules_directory: Some(custom_rules.clone()),
+            rules_database: None,
+            rule_registry: None,
+            coverage_directory: None,
+            undo_directory: None,
             pattern_cache_size: 1000,
             sg_config_path: None,
+            isolate_workers: false,
+            worker_timeout_ms: 10000,
+            worker_memory_mb: 1024,
+            rule_timeout_ms: 30000,
+            rule_max_matches: 10000,
+            formatters: vec![],
+            workspaces: vec![],
+            verify_commands: vec![],
+            max_depth: 10,
+            prune_dirs: vec![],
+            skip_hidden: false,
+            no_gitignore: false,
+            allow_env: vec![],
+            index: false,
+            prewarm: false,
+            prewarm_delay_ms: 1,
+            no_lock: false,
+            lock_timeout_ms: 30000,
+            cursor_ttl_secs: 3600,
+            max_input_sizes: Vec::new(),
         };
 
         let config = create_config_from_args(args).unwrap();
         assert_eq!(config.max_file_size, 2048);
         assert_eq!(config.max_concurrency, 10);
+        assert_eq!(config.cpu_concurrency, 2);
         assert_eq!(config.limit, 200);
         assert_eq!(config.pattern_cache_size, 1000);
         assert_eq!(config.root_directories, vec![custom_root]);
         assert_eq!(config.rules_directory, custom_rules);
     }
 
+    #[test]
+    fn test_create_config_from_args_workspaces() {
+        let temp_dir = TempDir::new().unwrap();
+        let shared = temp_dir.path().join("shared");
+        let args = Args::try_parse_from([
+            "ast-grep-mcp",
+            "-d",
+            shared.to_str().unwrap(),
+            "--workspace",
+            &format!("web={}", temp_dir.path().join("web").display()),
+            "--workspace",
+            &format!("shared={}", shared.display()),
+        ])
+        .unwrap();
+
+        let config = create_config_from_args(args.global).unwrap();
+        assert_eq!(
+            config.root_directories,
+            vec![shared.clone(), temp_dir.path().join("web")]
+        );
+        assert_eq!(config.workspaces["shared"], shared);
+
+        let args = Args::try_parse_from(["ast-grep-mcp", "--workspace", "web"]).unwrap();
+        assert!(create_config_from_args(args.global).is_err());
+    }
+
     #[tokio::test]
     async fn test_get_code_content_direct() {
         let result = get_code_content(Some("test code".to_string()), None)
@@ -884,10 +1532,35 @@ ruleDirs:
             root_directories: vec![temp_dir.path().to_path_buf()],
             max_file_size: 1024,
             max_concurrency: 5,
+            cpu_concurrency: None,
             limit: 100,
             rules_directory: None,
+            rules_database: None,
+            rule_registry: None,
+            coverage_directory: None,
+            undo_directory: None,
             pattern_cache_size: 500,
             sg_config_path: Some(config_path),
+            isolate_workers: false,
+            worker_timeout_ms: 10000,
+            worker_memory_mb: 1024,
+            rule_timeout_ms: 30000,
+            rule_max_matches: 10000,
+            formatters: vec![],
+            workspaces: vec![],
+            verify_commands: vec![],
+            max_depth: 10,
+            prune_dirs: vec![],
+            skip_hidden: false,
+            no_gitignore: false,
+            allow_env: vec![],
+            index: false,
+            prewarm: false,
+            prewarm_delay_ms: 1,
+            no_lock: false,
+            lock_timeout_ms: 30000,
+            cursor_ttl_secs: 3600,
+            max_input_sizes: Vec::new(),
         };
 
         let config = create_config_from_args(args).unwrap();
@@ -944,6 +1617,44 @@ ruleDirs:
         }
     }
 
+    #[test]
+    fn test_batch_flag_parsing() {
+        use clap::Parser;
+
+        let args = Args::try_parse_from(["ast-grep-mcp", "--batch", "--root-dir", "/tmp"]).unwrap();
+        assert!(args.batch);
+        assert!(args.command.is_none());
+        assert_eq!(args.global.root_directories, vec![PathBuf::from("/tmp")]);
+    }
+
+    #[test]
+    fn test_worker_isolation_flags() {
+        use clap::Parser;
+
+        let args = Args::try_parse_from([
+            "ast-grep-mcp",
+            "--isolate-workers",
+            "--worker-timeout-ms",
+            "500",
+            "--worker-memory-mb",
+            "0",
+        ])
+        .unwrap();
+        let config = create_config_from_args(args.global).unwrap();
+        let workers = config.worker_isolation.unwrap();
+        assert_eq!(workers.timeout, std::time::Duration::from_millis(500));
+        assert_eq!(workers.memory_limit_mb, None);
+
+        let args =
+            Args::try_parse_from(["ast-grep-mcp", "worker", "--memory-limit-mb", "64"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Commands::Worker {
+                memory_limit_mb: Some(64)
+            })
+        ));
+    }
+
     #[test]
     fn test_commands_parsing() {
         use clap::Parser;
@@ -968,12 +1679,16 @@ ruleDirs:
             language,
             path_pattern,
             max_results,
+            allow_broad,
+            revision,
         }) = args.command
         {
             assert_eq!(pattern, "console.log");
             assert_eq!(language, "javascript");
             assert_eq!(path_pattern, "**/*.js");
             assert_eq!(max_results, 50);
+            assert!(!allow_broad);
+            assert_eq!(revision, None);
         } else {
             panic!("Expected FileSearch command");
         }
@@ -1021,6 +1736,7 @@ ruleDirs:
             apply,
             summary_only,
             max_results,
+            allow_unsafe,
         }) = args.command
         {
             assert_eq!(rule, PathBuf::from("/path/to/rule.yaml"));
@@ -1028,6 +1744,7 @@ ruleDirs:
             assert!(apply);
             assert!(summary_only);
             assert_eq!(max_results, 100); // default
+            assert!(!allow_unsafe);
         } else {
             panic!("Expected RuleReplace command");
         }
@@ -1055,5 +1772,24 @@ ruleDirs:
         } else {
             panic!("Expected GenerateAst command");
         }
+
+        // Test CreatePlayground command
+        let args =
+            Args::try_parse_from(["ast-grep-mcp", "create-playground", "/tmp/playground"]).unwrap();
+
+        if let Some(Commands::CreatePlayground {
+            directory,
+            overwrite,
+        }) = args.command
+        {
+            assert_eq!(directory, Some(PathBuf::from("/tmp/playground")));
+            assert!(!overwrite);
+        } else {
+            panic!("Expected CreatePlayground command");
+        }
+
+        // Test Doctor command
+        let args = Args::try_parse_from(["ast-grep-mcp", "doctor", "--json"]).unwrap();
+        assert!(matches!(args.command, Some(Commands::Doctor { json: true })));
     }
 }
@@ -0,0 +1,256 @@
+//! # Three-Way Merge
+//!
+//! Line-based diff3 merge used when a file changed between a `file_replace` dry run and the
+//! apply that follows it. The content seen by the dry run is the common ancestor, the file on
+//! disk is one side and the replacement computed from the dry-run content is the other.
+//! Hunks changed on only one side merge cleanly; hunks changed differently on both sides are
+//! wrapped in diff3-style conflict markers.
+
+use std::ops::Range;
+
+/// Marker labels, in the order they appear around a conflicting hunk
+const CURRENT_LABEL: &str = "current";
+const ORIGINAL_LABEL: &str = "dry run";
+const REPLACED_LABEL: &str = "replacement";
+
+/// Merged text and the number of hunks that needed conflict markers
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeOutcome {
+    pub text: String,
+    pub conflicts: usize,
+}
+
+impl MergeOutcome {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts == 0
+    }
+}
+
+/// Merge the edits `original -> current` and `original -> replaced`
+pub fn merge3(original: &str, current: &str, replaced: &str) -> MergeOutcome {
+    let base: Vec<&str> = original.split_inclusive('\n').collect();
+    let cur: Vec<&str> = current.split_inclusive('\n').collect();
+    let rep: Vec<&str> = replaced.split_inclusive('\n').collect();
+
+    let to_current = match_map(&base, &cur);
+    let to_replaced = match_map(&base, &rep);
+
+    let mut text = String::with_capacity(current.len().max(replaced.len()));
+    let mut conflicts = 0;
+    let (mut o, mut a, mut b) = (0, 0, 0);
+
+    loop {
+        // Lines unchanged on both sides
+        while o < base.len() && to_current[o] == Some(a) && to_replaced[o] == Some(b) {
+            text.push_str(base[o]);
+            o += 1;
+            a += 1;
+            b += 1;
+        }
+
+        let sync = (o..base.len())
+            .find_map(|i| Some((i, to_current[i]?, to_replaced[i]?)))
+            .unwrap_or((base.len(), cur.len(), rep.len()));
+        if sync == (o, a, b) {
+            break;
+        }
+
+        let (base_hunk, cur_hunk, rep_hunk) = (&base[o..sync.0], &cur[a..sync.1], &rep[b..sync.2]);
+        if cur_hunk == base_hunk {
+            rep_hunk.iter().for_each(|line| text.push_str(line));
+        } else if rep_hunk == base_hunk || rep_hunk == cur_hunk {
+            cur_hunk.iter().for_each(|line| text.push_str(line));
+        } else {
+            conflicts += 1;
+            push_marker(&mut text, &format!("<<<<<<< {CURRENT_LABEL}"));
+            cur_hunk.iter().for_each(|line| text.push_str(line));
+            push_marker(&mut text, &format!("||||||| {ORIGINAL_LABEL}"));
+            base_hunk.iter().for_each(|line| text.push_str(line));
+            push_marker(&mut text, "=======");
+            rep_hunk.iter().for_each(|line| text.push_str(line));
+            push_marker(&mut text, &format!(">>>>>>> {REPLACED_LABEL}"));
+        }
+        (o, a, b) = sync;
+    }
+
+    MergeOutcome { text, conflicts }
+}
+
+fn push_marker(text: &mut String, marker: &str) {
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str(marker);
+    text.push('\n');
+}
+
+/// For each line of `a`, the index of the line of `b` it is paired with in a longest common
+/// subsequence, if any
+fn match_map(a: &[&str], b: &[&str]) -> Vec<Option<usize>> {
+    let mut map = vec![None; a.len()];
+
+    // Trim the common prefix and suffix so the diff only covers the changed middle
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    for (i, slot) in map.iter_mut().enumerate().take(prefix) {
+        *slot = Some(i);
+    }
+    for i in 0..suffix {
+        map[a.len() - 1 - i] = Some(b.len() - 1 - i);
+    }
+
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+    for (x, y) in myers_common(a_mid, b_mid) {
+        map[prefix + x] = Some(prefix + y);
+    }
+    map
+}
+
+/// Line ranges of `a` and `b` that differ, in order, each pair replacing the `a` lines with the
+/// `b` lines
+pub(crate) fn changed_hunks(a: &[&str], b: &[&str]) -> Vec<(Range<usize>, Range<usize>)> {
+    let map = match_map(a, b);
+    let mut hunks = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    loop {
+        let (start_i, start_j) = (i, j);
+        while i < a.len() && map[i].is_none() {
+            i += 1;
+        }
+        let next_j = map.get(i).copied().flatten().unwrap_or(b.len());
+        if i > start_i || next_j > start_j {
+            hunks.push((start_i..i, start_j..next_j));
+        }
+        if i == a.len() {
+            return hunks;
+        }
+        i += 1;
+        j = next_j + 1;
+    }
+}
+
+/// Pairs of equal lines on the shortest edit script between `a` and `b` (Myers' algorithm)
+fn myers_common(a: &[&str], b: &[&str]) -> Vec<(usize, usize)> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    if n == 0 || m == 0 {
+        return Vec::new();
+    }
+
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    let mut v = vec![0isize; 2 * max + 3];
+    // trace[d] holds the furthest x reached on diagonals -d..=d before step d
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max as isize {
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let i = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) {
+                v[i + 1]
+            } else {
+                v[i - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[i] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, snapshot) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| snapshot[(k + d) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = if d == 0 { 0 } else { at(prev_k) };
+        let prev_y = if d == 0 { 0 } else { prev_x - prev_k };
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            pairs.push((x as usize, y as usize));
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    pairs.reverse();
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_hunks() {
+        let a = ["a\n", "b\n", "c\n", "d\n"];
+        let b = ["x\n", "a\n", "c\n", "d\n", "y\n"];
+        assert_eq!(
+            changed_hunks(&a, &b),
+            vec![(0..0, 0..1), (1..2, 2..2), (4..4, 4..5)]
+        );
+    }
+
+    #[test]
+    fn test_non_overlapping_edits_merge_cleanly() {
+        let original = "var a = 1;\n\nfoo();\n\nvar b = 2;\n";
+        let current = "var a = 1;\n\nfoo(42);\n\nvar b = 2;\n";
+        let replaced = "let a = 1;\n\nfoo();\n\nlet b = 2;\n";
+
+        let outcome = merge3(original, current, replaced);
+        assert!(outcome.is_clean());
+        assert_eq!(outcome.text, "let a = 1;\n\nfoo(42);\n\nlet b = 2;\n");
+    }
+
+    #[test]
+    fn test_overlapping_edits_get_diff3_markers() {
+        let original = "keep();\nvar a = 1;\n";
+        let current = "keep();\nvar a = 2;\n";
+        let replaced = "keep();\nlet a = 1;\n";
+
+        let outcome = merge3(original, current, replaced);
+        assert_eq!(outcome.conflicts, 1);
+        assert_eq!(
+            outcome.text,
+            "keep();\n<<<<<<< current\nvar a = 2;\n||||||| dry run\nvar a = 1;\n=======\nlet a = 1;\n>>>>>>> replacement\n"
+        );
+    }
+
+    #[test]
+    fn test_insertions_and_missing_trailing_newline() {
+        let original = "a\nb\nc";
+        let current = "start\na\nb\nc";
+        let replaced = "a\nB\nc";
+
+        let outcome = merge3(original, current, replaced);
+        assert!(outcome.is_clean());
+        assert_eq!(outcome.text, "start\na\nB\nc");
+    }
+
+    #[test]
+    fn test_myers_finds_longest_common_subsequence() {
+        let a = ["a", "b", "c", "a", "b", "b", "a"];
+        let b = ["c", "b", "a", "b", "a", "c"];
+        let pairs = myers_common(&a, &b);
+        assert_eq!(pairs.len(), 4);
+        assert!(pairs.iter().all(|&(x, y)| a[x] == b[y]));
+        assert!(pairs.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+    }
+}
@@ -0,0 +1,65 @@
+//! # Virtual File Overlays
+//!
+//! Editors hold unsaved buffers that differ from what's on disk. Clients pass those buffers as
+//! `overlays` (file path to content) and file-based tools match against the overlay content
+//! instead of reading the file, so results line up with what the user is looking at.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Buffer contents that take precedence over files on disk, keyed by canonical path
+#[derive(Debug, Clone, Default)]
+pub struct Overlays {
+    by_path: HashMap<PathBuf, String>,
+}
+
+impl Overlays {
+    /// Resolve overlay paths, which may be absolute or relative to one of `roots`
+    pub fn resolve(overlays: &HashMap<String, String>, roots: &[PathBuf]) -> Self {
+        let by_path = overlays
+            .iter()
+            .map(|(path, content)| (resolve_path(path, roots), content.clone()))
+            .collect();
+        Self { by_path }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_path.is_empty()
+    }
+
+    /// The overlay content for `file_path`, if any
+    pub fn get(&self, file_path: &str) -> Option<&str> {
+        if self.by_path.is_empty() {
+            return None;
+        }
+        self.by_path
+            .get(&canonical(Path::new(file_path)))
+            .map(String::as_str)
+    }
+
+    /// The overlay content for `file_path`, falling back to the file on disk
+    pub fn read(&self, file_path: &str) -> std::io::Result<String> {
+        match self.get(file_path) {
+            Some(content) => Ok(content.to_string()),
+            None => std::fs::read_to_string(file_path),
+        }
+    }
+}
+
+fn resolve_path(path: &str, roots: &[PathBuf]) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return canonical(path);
+    }
+    let candidates = roots.iter().map(|root| root.join(path));
+    let existing = candidates.clone().find(|candidate| candidate.exists());
+    existing
+        .or_else(|| candidates.clone().next())
+        .map(|candidate| canonical(&candidate))
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Canonicalize when the file exists so differently spelled paths to it compare equal
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
@@ -19,10 +19,9 @@ pub fn validate_path_within_roots(
                     } else {
                         std::env::current_dir().map(|cwd| cwd.join(path))
                     }
-                }) {
-                    if abs_path.starts_with(&canonical_root) {
-                        return Ok(abs_path);
-                    }
+                }) && abs_path.starts_with(&canonical_root)
+                {
+                    return Ok(abs_path);
                 }
             }
         }
@@ -52,6 +51,19 @@ pub fn validate_path_within_roots(
     ))
 }
 
+/// Ensures the closest existing ancestor of `path` resolves inside the root directories, so
+/// that directories created on the way to `path` can't land outside them through a symlink
+pub fn validate_creatable_path(
+    path: &Path,
+    root_directories: &[PathBuf],
+) -> Result<(), ServiceError> {
+    let existing = path
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .ok_or_else(|| ServiceError::Internal("Path is outside allowed directories".to_string()))?;
+    validate_path_within_roots(existing, root_directories).map(|_| ())
+}
+
 /// Validates a path pattern and ensures it cannot escape root directories
 pub fn validate_path_pattern(pattern: &str) -> Result<String, ServiceError> {
     // Check for directory traversal attempts
@@ -87,10 +99,10 @@ pub fn resolve_path_pattern(
             let full_path = root.join(&validated_pattern);
             if let Ok(canonical) = full_path.canonicalize() {
                 // Double-check it's still within the root after canonicalization
-                if let Ok(canonical_root) = root.canonicalize() {
-                    if canonical.starts_with(&canonical_root) {
-                        resolved_paths.push(canonical);
-                    }
+                if let Ok(canonical_root) = root.canonicalize()
+                    && canonical.starts_with(&canonical_root)
+                {
+                    resolved_paths.push(canonical);
                 }
             }
         }
@@ -99,6 +111,27 @@ pub fn resolve_path_pattern(
     Ok(resolved_paths)
 }
 
+/// Resolves a file path that will be written to, ensuring it stays within allowed roots.
+/// Relative paths are resolved against the first root directory.
+pub fn resolve_output_path(
+    output_path: &str,
+    root_directories: &[PathBuf],
+) -> Result<PathBuf, ServiceError> {
+    let validated_path = validate_path_pattern(output_path)?;
+    let path = Path::new(&validated_path);
+
+    let candidate = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        let root = root_directories
+            .first()
+            .ok_or_else(|| ServiceError::Internal("No root directories configured".to_string()))?;
+        root.join(path)
+    };
+
+    validate_path_within_roots(&candidate, root_directories)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -154,4 +187,22 @@ mod tests {
         // Invalid pattern
         assert!(resolve_path_pattern("../escape.txt", &roots).is_err());
     }
+
+    #[test]
+    fn test_resolve_output_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let roots = vec![root.clone()];
+
+        // Relative paths land under the first root, even if they don't exist yet
+        let resolved = resolve_output_path("reports/audit.json", &roots).unwrap();
+        assert!(resolved.ends_with("reports/audit.json"));
+        assert!(resolved.starts_with(root.canonicalize().unwrap()));
+
+        // Traversal and absolute paths outside the roots are rejected
+        assert!(resolve_output_path("../audit.json", &roots).is_err());
+        let other_temp = TempDir::new().unwrap();
+        let outside = other_temp.path().join("audit.json");
+        assert!(resolve_output_path(&outside.to_string_lossy(), &roots).is_err());
+    }
 }
@@ -5,13 +5,19 @@
 
 use crate::errors::ServiceError;
 use crate::search_match::SearchMatches;
-use crate::types::{CursorResult, FileMatchResult, FileSearchParam, FileSearchResult, MatchResult};
-use ast_grep_core::tree_sitter::StrDoc;
-use ast_grep_core::{AstGrep, Pattern};
+use crate::template::ReplacementTemplate;
+use crate::types::{
+    ChangeResult, CursorResult, FileMatchResult, FileSearchParam, FileSearchResult, MatchResult,
+};
+use ast_grep_core::replacer::Replacer;
+use ast_grep_core::source::Edit;
+use ast_grep_core::tree_sitter::{LanguageExt, StrDoc};
+use ast_grep_core::{AstGrep, NodeMatch, Pattern};
 use ast_grep_language::SupportLang as Language;
 use globset::Glob;
 use lru::LruCache;
 use sha2::{Digest, Sha256};
+use std::fmt::Display;
 use std::num::NonZeroUsize;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
@@ -47,24 +53,52 @@ impl PatternMatcher {
         self.pattern_cache.clone()
     }
 
-    pub fn search(
+    pub fn search<L: LanguageExt + Copy + Display>(
         &self,
         code: &str,
         pattern: &str,
-        lang: Language,
+        lang: L,
     ) -> Result<Vec<MatchResult>, ServiceError> {
         self.search_with_options(code, pattern, lang, None, None)
     }
 
-    pub fn search_with_options(
+    pub fn search_with_options<L: LanguageExt + Copy + Display>(
         &self,
         code: &str,
         pattern: &str,
-        lang: Language,
+        lang: L,
         selector: Option<&str>,
         context: Option<&str>,
+    ) -> Result<Vec<MatchResult>, ServiceError> {
+        self.search_with_ast_context(code, pattern, lang, selector, context, false)
+    }
+
+    /// [`Self::search_with_options`], attaching each match's [`AstContext`](crate::types::AstContext) when
+    /// `include_ast_context` is set
+    pub fn search_with_ast_context<L: LanguageExt + Copy + Display>(
+        &self,
+        code: &str,
+        pattern: &str,
+        lang: L,
+        selector: Option<&str>,
+        context: Option<&str>,
+        include_ast_context: bool,
     ) -> Result<Vec<MatchResult>, ServiceError> {
         let ast = AstGrep::new(code, lang);
+        self.search_parsed(&ast, pattern, lang, selector, context, include_ast_context)
+    }
+
+    /// [`Self::search_with_ast_context`] on code that has already been parsed, so several
+    /// patterns can share one parse
+    pub fn search_parsed<L: LanguageExt + Copy + Display>(
+        &self,
+        ast: &AstGrep<StrDoc<L>>,
+        pattern: &str,
+        lang: L,
+        selector: Option<&str>,
+        context: Option<&str>,
+        include_ast_context: bool,
+    ) -> Result<Vec<MatchResult>, ServiceError> {
         let pattern = if let (Some(selector), Some(context)) = (selector, context) {
             self.get_or_create_contextual_pattern(pattern, selector, context, lang)?
         } else {
@@ -74,7 +108,13 @@ impl PatternMatcher {
         let matches: Vec<MatchResult> = ast
             .root()
             .find_all(pattern)
-            .map(|node| MatchResult::from_node_match(&node))
+            .map(|node| {
+                let result = MatchResult::from_node_match(&node);
+                match include_ast_context {
+                    true => result.with_ast_context(node.get_node()),
+                    false => result,
+                }
+            })
             .collect();
 
         Ok(matches)
@@ -99,34 +139,50 @@ impl PatternMatcher {
         Ok(matches)
     }
 
-    pub fn replace(
+    pub fn replace<L: LanguageExt + Copy + Display>(
         &self,
         code: &str,
         pattern: &str,
         replacement: &str,
-        lang: Language,
+        lang: L,
     ) -> Result<String, ServiceError> {
         self.replace_with_options(code, pattern, replacement, lang, None, None)
     }
 
-    pub fn replace_with_options(
+    pub fn replace_with_options<L: LanguageExt + Copy + Display>(
         &self,
         code: &str,
         pattern: &str,
         replacement: &str,
-        lang: Language,
+        lang: L,
         selector: Option<&str>,
         context: Option<&str>,
     ) -> Result<String, ServiceError> {
-        let ast = AstGrep::new(code, lang);
-        let pattern = if let (Some(selector), Some(context)) = (selector, context) {
-            self.get_or_create_contextual_pattern(pattern, selector, context, lang)?
-        } else {
-            self.get_or_create_pattern(pattern, lang)?
-        };
+        self.replace_anchored(code, pattern, replacement, lang, selector, context, None)
+    }
 
-        // Apply replacements
-        let edits = ast.root().replace_all(pattern, replacement);
+    /// [`Self::replace_with_options`], rewriting only the node captured by the `rewrite_selector`
+    /// metavariable (e.g. `$DECORATOR`) in each match and leaving the rest of the match intact
+    #[allow(clippy::too_many_arguments)]
+    pub fn replace_anchored<L: LanguageExt + Copy + Display>(
+        &self,
+        code: &str,
+        pattern: &str,
+        replacement: &str,
+        lang: L,
+        selector: Option<&str>,
+        context: Option<&str>,
+        rewrite_selector: Option<&str>,
+    ) -> Result<String, ServiceError> {
+        let edits = self.edits(
+            code,
+            pattern,
+            replacement,
+            lang,
+            selector,
+            context,
+            rewrite_selector,
+        )?;
         let mut result = code.to_string();
 
         // Apply edits in reverse order to maintain correct offsets
@@ -142,10 +198,124 @@ impl PatternMatcher {
         Ok(result)
     }
 
-    fn get_or_create_pattern(
+    /// The individual edits `replace_with_options` would make, positioned against `code`
+    pub fn replacement_changes_with_options<L: LanguageExt + Copy + Display>(
+        &self,
+        code: &str,
+        pattern: &str,
+        replacement: &str,
+        lang: L,
+        selector: Option<&str>,
+        context: Option<&str>,
+    ) -> Result<Vec<ChangeResult>, ServiceError> {
+        self.replacement_changes_anchored(code, pattern, replacement, lang, selector, context, None)
+    }
+
+    /// The individual edits `replace_anchored` would make, positioned against `code`
+    #[allow(clippy::too_many_arguments)]
+    pub fn replacement_changes_anchored<L: LanguageExt + Copy + Display>(
+        &self,
+        code: &str,
+        pattern: &str,
+        replacement: &str,
+        lang: L,
+        selector: Option<&str>,
+        context: Option<&str>,
+        rewrite_selector: Option<&str>,
+    ) -> Result<Vec<ChangeResult>, ServiceError> {
+        let changes = self
+            .edits(
+                code,
+                pattern,
+                replacement,
+                lang,
+                selector,
+                context,
+                rewrite_selector,
+            )?
+            .into_iter()
+            .map(|edit| {
+                let end = edit.position + edit.deleted_length;
+                let (start_line, start_col) = line_col(code, edit.position);
+                let (end_line, end_col) = line_col(code, end);
+                ChangeResult {
+                    start_line,
+                    end_line,
+                    start_col,
+                    end_col,
+                    start_byte: edit.position,
+                    end_byte: end,
+                    old_text: code[edit.position..end].to_string(),
+                    new_text: String::from_utf8_lossy(&edit.inserted_text).into_owned(),
+                }
+            })
+            .collect();
+        Ok(changes)
+    }
+
+    /// Edits replacing each match of `pattern`, or only the node(s) its `rewrite_selector`
+    /// metavariable captured, in source order
+    #[allow(clippy::too_many_arguments)]
+    fn edits<L: LanguageExt + Copy + Display>(
+        &self,
+        code: &str,
+        pattern: &str,
+        replacement: &str,
+        lang: L,
+        selector: Option<&str>,
+        context: Option<&str>,
+        rewrite_selector: Option<&str>,
+    ) -> Result<Vec<Edit<String>>, ServiceError> {
+        let ast = AstGrep::new(code, lang);
+        let template = ReplacementTemplate::parse(replacement)?;
+        let matcher = if let (Some(selector), Some(context)) = (selector, context) {
+            self.get_or_create_contextual_pattern(pattern, selector, context, lang)?
+        } else {
+            self.get_or_create_pattern(pattern, lang)?
+        };
+        let Some(rewrite_selector) = rewrite_selector else {
+            return Ok(ast.root().replace_all(matcher, &template));
+        };
+
+        let source = match (selector, context) {
+            (Some(_), Some(context)) => context,
+            _ => pattern,
+        };
+        let (var, multi) = parse_rewrite_selector(rewrite_selector, source)?;
+        let mut edits: Vec<Edit<String>> = Vec::new();
+        for node_match in ast.root().find_all(matcher) {
+            let env = node_match.get_env();
+            let nodes = match multi {
+                true => env.get_multiple_matches(&var),
+                false => env.get_match(&var).into_iter().cloned().collect(),
+            };
+            // An empty $$$ capture leaves nothing to anchor the replacement to
+            let (Some(first), Some(last)) = (nodes.first(), nodes.last()) else {
+                continue;
+            };
+            let range = first.range().start..last.range().end;
+            // Nested matches capturing overlapping code are left to the outer match
+            if edits
+                .last()
+                .is_some_and(|previous| previous.position + previous.deleted_length > range.start)
+            {
+                continue;
+            }
+            // Rendered at the captured node, so multi-line replacements take its indentation
+            let anchored = NodeMatch::new(first.clone(), env.clone());
+            edits.push(Edit {
+                position: range.start,
+                deleted_length: range.len(),
+                inserted_text: Replacer::<StrDoc<L>>::generate_replacement(&template, &anchored),
+            });
+        }
+        Ok(edits)
+    }
+
+    fn get_or_create_pattern<L: LanguageExt + Copy + Display>(
         &self,
         pattern_str: &str,
-        lang: Language,
+        lang: L,
     ) -> Result<Pattern, ServiceError> {
         let cache_key = format!("{lang}:{pattern_str}");
 
@@ -169,12 +339,12 @@ impl PatternMatcher {
         Ok(pattern)
     }
 
-    fn get_or_create_contextual_pattern(
+    fn get_or_create_contextual_pattern<L: LanguageExt + Copy + Display>(
         &self,
         pattern_str: &str,
         selector: &str,
         context: &str,
-        lang: Language,
+        lang: L,
     ) -> Result<Pattern, ServiceError> {
         let cache_key = format!("{lang}:{context}:{selector}:{pattern_str}");
 
@@ -343,6 +513,9 @@ impl PatternMatcher {
                         file_size_bytes: file_size,
                         matches: file_matches,
                         file_hash,
+                        language: None,
+                        workspace: None,
+                        engine: None,
                     });
 
                     // Check overall matches count for pagination
@@ -352,6 +525,7 @@ impl PatternMatcher {
                         next_cursor = Some(CursorResult {
                             last_file_path: path.to_string_lossy().into_owned(),
                             is_complete: false,
+                            token: None,
                         });
                         break; // Stop searching if max_results reached
                     }
@@ -363,6 +537,80 @@ impl PatternMatcher {
             matches: all_matches,
             next_cursor,
             total_files_found: total_files_processed,
+            skipped_files: Vec::new(),
+            budget_exceeded: false,
+            timed_out: false,
+            stats: None,
+            warnings: Vec::new(),
         })
     }
 }
+
+/// Whether `pattern` is a lone metavariable like `$_`, `$A` or `$$$ARGS`, which matches nearly
+/// every node of a file
+pub fn is_broad_pattern(pattern: &str) -> bool {
+    let pattern = pattern.trim();
+    let pattern = pattern.strip_suffix(';').unwrap_or(pattern).trim_end();
+    let name = if let Some(name) = pattern.strip_prefix("$$$") {
+        name
+    } else {
+        let name = pattern
+            .strip_prefix("$$")
+            .or_else(|| pattern.strip_prefix('$'));
+        match name {
+            Some(name) if !name.is_empty() => name,
+            _ => return false,
+        }
+    };
+    name.chars()
+        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Refuse a broad pattern for a search or replace across files unless the caller opted in with
+/// `allow_broad`. With a `context`, the match is narrowed to the `selector` kind instead
+pub fn check_broad_pattern(
+    pattern: &str,
+    context: Option<&str>,
+    allow_broad: bool,
+) -> Result<(), ServiceError> {
+    if allow_broad || context.is_some() || !is_broad_pattern(pattern) {
+        return Ok(());
+    }
+    Err(ServiceError::Internal(format!(
+        "Pattern '{}' matches nearly every node of every file, which can take minutes and return enormous output. Use a more specific pattern or a context with a selector, or set allow_broad=true to run it anyway",
+        pattern.trim()
+    )))
+}
+
+/// The metavariable name in a `rewrite_selector` and whether it is a `$$$` capture. The
+/// variable has to appear in the pattern (or the context, for contextual patterns)
+fn parse_rewrite_selector(
+    rewrite_selector: &str,
+    pattern: &str,
+) -> Result<(String, bool), ServiceError> {
+    let (name, multi) = match rewrite_selector.strip_prefix("$$$") {
+        Some(name) => (name, true),
+        None => (
+            rewrite_selector.strip_prefix('$').unwrap_or_default(),
+            false,
+        ),
+    };
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+    if !valid || !pattern.contains(rewrite_selector) {
+        return Err(ServiceError::Internal(format!(
+            "rewrite_selector '{rewrite_selector}' must be a metavariable captured by the pattern, like $DECORATOR or $$$ARGS"
+        )));
+    }
+    Ok((name.to_string(), multi))
+}
+
+/// 0-based line and column of a byte offset, counting columns in bytes like tree-sitter
+fn line_col(code: &str, offset: usize) -> (usize, usize) {
+    let before = &code[..offset];
+    let line = before.matches('\n').count();
+    let column = offset - before.rfind('\n').map_or(0, |i| i + 1);
+    (line, column)
+}
@@ -0,0 +1,244 @@
+//! # Playground
+//!
+//! A small sample project to try the tools on before pointing the server at real code. It
+//! holds a few JavaScript, TypeScript, Python and Rust files with deliberate findings, such as
+//! `var` declarations, leftover `console.log` calls, a bare `except:` and `.unwrap()` calls,
+//! and a `rules/` directory with an `sgconfig.yml` flagging them. The `create_playground` tool
+//! writes it below a root directory, and `ast-grep-mcp create-playground` into a temporary
+//! directory to start a server on.
+
+use crate::errors::ServiceError;
+use crate::types::PlaygroundSuggestion;
+use serde_json::json;
+use std::path::Path;
+
+/// Flags `var` declarations and rewrites them to `let`
+pub const NO_VAR_RULE: &str = r#"id: no-var
+language: javascript
+severity: warning
+message: Use let or const instead of var
+rule:
+  pattern: var $NAME = $VALUE;
+fix: let $NAME = $VALUE;
+"#;
+
+/// Flags `except:` clauses that catch every exception
+pub const NO_BARE_EXCEPT_RULE: &str = r#"id: no-bare-except
+language: python
+severity: error
+message: A bare except also catches KeyboardInterrupt and SystemExit
+rule:
+  all:
+    - kind: except_clause
+    - regex: '^except\s*:'
+"#;
+
+/// Flags leftover `console.log` calls
+pub const NO_CONSOLE_LOG_RULE: &str = r#"id: no-console-log
+language: typescript
+severity: warning
+message: Remove debugging output
+rule:
+  pattern: console.log($$$ARGS)
+"#;
+
+/// Flags `.unwrap()` calls, which panic on errors
+pub const NO_UNWRAP_RULE: &str = r#"id: no-unwrap
+language: rust
+severity: warning
+message: Handle the error instead of panicking
+rule:
+  pattern: $VALUE.unwrap()
+"#;
+
+const CART_JS: &str = r#"var TAX_RATE = 0.2;
+
+function total(items) {
+  var sum = 0;
+  for (const item of items) {
+    sum += item.price * item.quantity;
+  }
+  console.log("cart total", sum);
+  return sum * (1 + TAX_RATE);
+}
+
+function discount(code) {
+  if (code == null) {
+    return 0;
+  }
+  return code === "WELCOME" ? 10 : 0;
+}
+
+module.exports = { total, discount };
+"#;
+
+const USER_TS: &str = r#"export interface User {
+  id: number;
+  name: string;
+  email?: string;
+}
+
+export function parseUser(raw: any): User {
+  console.log("parsing user", raw);
+  return { id: Number(raw.id), name: String(raw.name), email: raw.email };
+}
+
+export function displayName(user: User): string {
+  return user.name || "anonymous";
+}
+"#;
+
+const REPORT_PY: &str = r#"import json
+
+
+def load(path, cache={}):
+    if path in cache:
+        return cache[path]
+    try:
+        with open(path) as handle:
+            cache[path] = json.load(handle)
+    except:
+        print("could not read", path)
+        return None
+    return cache[path]
+
+
+def summarize(rows):
+    print("summarizing", len(rows), "rows")
+    return {"count": len(rows), "total": sum(row["amount"] for row in rows)}
+"#;
+
+const LIB_RS: &str = r#"use std::collections::HashMap;
+
+pub fn word_counts(text: &str) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for word in text.split_whitespace() {
+        *counts.entry(word.to_lowercase()).or_insert(0) += 1;
+    }
+    counts
+}
+
+pub fn first_number(text: &str) -> i64 {
+    let word = text.split_whitespace().next().unwrap();
+    println!("parsing {word}");
+    word.parse().unwrap()
+}
+"#;
+
+const SGCONFIG: &str = "ruleDirs:\n  - rules\n";
+
+const README: &str = r#"# ast-grep playground
+
+A sample project with deliberate findings to try ast-grep-mcp on:
+
+- `js/cart.js`: two `var` declarations and a `console.log` call
+- `ts/user.ts`: a `console.log` call and an `any` parameter
+- `python/report.py`: a bare `except:` and a mutable default argument
+- `rust/src/lib.rs`: two `.unwrap()` calls
+
+`rules/` holds a rule for each, listed in `sgconfig.yml` for `ast-grep scan`.
+Nothing here is used by anything else, so rewrite it freely.
+"#;
+
+/// Files of the project, relative to its directory
+pub const FILES: &[(&str, &str)] = &[
+    ("README.md", README),
+    ("sgconfig.yml", SGCONFIG),
+    ("js/cart.js", CART_JS),
+    ("ts/user.ts", USER_TS),
+    ("python/report.py", REPORT_PY),
+    ("rust/src/lib.rs", LIB_RS),
+    ("rules/no-var.yml", NO_VAR_RULE),
+    ("rules/no-console-log.yml", NO_CONSOLE_LOG_RULE),
+    ("rules/no-bare-except.yml", NO_BARE_EXCEPT_RULE),
+    ("rules/no-unwrap.yml", NO_UNWRAP_RULE),
+];
+
+/// Write the project into `directory`, returning the files written. A directory that already
+/// has files is only written to with `overwrite`, and then only the sample files are replaced
+pub fn write_playground(directory: &Path, overwrite: bool) -> Result<Vec<String>, ServiceError> {
+    let has_files = std::fs::read_dir(directory)
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    if has_files && !overwrite {
+        return Err(ServiceError::Internal(format!(
+            "{} already exists and is not empty; pick another directory or pass overwrite=true",
+            directory.display()
+        )));
+    }
+
+    let mut written = Vec::new();
+    for (name, content) in FILES {
+        let path = directory.join(name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| io_error(e, parent))?;
+        }
+        std::fs::write(&path, content).map_err(|e| io_error(e, &path))?;
+        written.push(name.to_string());
+    }
+    Ok(written)
+}
+
+/// Tool calls finding the sample findings, with path patterns below `prefix`, the project's
+/// directory relative to its root directory ("" when it is the root directory itself)
+pub fn suggestions(prefix: &str) -> Vec<PlaygroundSuggestion> {
+    let glob = |pattern: &str| match prefix {
+        "" => pattern.to_string(),
+        prefix => format!("{}/{pattern}", prefix.trim_end_matches('/')),
+    };
+    vec![
+        PlaygroundSuggestion {
+            tool: "file_search".to_string(),
+            description: "Find the leftover console.log calls".to_string(),
+            arguments: json!({
+                "path_pattern": glob("**/*.ts"),
+                "pattern": "console.log($$$ARGS)",
+                "language": "typescript",
+            }),
+        },
+        PlaygroundSuggestion {
+            tool: "file_replace".to_string(),
+            description: "Preview rewriting var declarations to let".to_string(),
+            arguments: json!({
+                "path_pattern": glob("**/*.js"),
+                "pattern": "var $NAME = $VALUE;",
+                "replacement": "let $NAME = $VALUE;",
+                "language": "javascript",
+                "dry_run": true,
+            }),
+        },
+        PlaygroundSuggestion {
+            tool: "rule_search".to_string(),
+            description: "Find the bare except clause with a rule".to_string(),
+            arguments: json!({
+                "path_pattern": glob("**/*.py"),
+                "rule_config": NO_BARE_EXCEPT_RULE,
+            }),
+        },
+        PlaygroundSuggestion {
+            tool: "rule_replace".to_string(),
+            description: "Preview the fix of the no-var rule".to_string(),
+            arguments: json!({
+                "path_pattern": glob("**/*.js"),
+                "rule_config": NO_VAR_RULE,
+                "dry_run": true,
+            }),
+        },
+        PlaygroundSuggestion {
+            tool: "file_search".to_string(),
+            description: "Find the calls that panic on errors".to_string(),
+            arguments: json!({
+                "path_pattern": glob("**/*.rs"),
+                "pattern": "$VALUE.unwrap()",
+                "language": "rust",
+            }),
+        },
+    ]
+}
+
+fn io_error(err: std::io::Error, path: &Path) -> ServiceError {
+    ServiceError::FileIoError {
+        message: err.to_string(),
+        path: path.to_string_lossy().to_string(),
+    }
+}
@@ -0,0 +1,101 @@
+//! # Cache Pre-warming
+//!
+//! On a large checkout the first `file_search` walks every root directory and the first
+//! `search_symbols` parses every source file. With `--prewarm` the server does both in the
+//! background right after startup: the file index (kept with `--index`) gets its file lists,
+//! and the symbol cache the declarations of each source file. The work runs on one blocking
+//! thread that pauses for `ServiceConfig::prewarm` after every [`WALK_BATCH`] entries walked
+//! and after every file parsed, so it gives way to the session's tool calls.
+
+use crate::directory_summary::source_files;
+use crate::index::FileIndex;
+use crate::symbol_search::SymbolIndex;
+use crate::types::{default_max_file_size, default_symbol_max_files};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Directory entries walked between two pauses
+pub const WALK_BATCH: usize = 100;
+
+/// What a pre-warming pass filled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrewarmStats {
+    /// Files listed in the file index; 0 without an index, or when a search built it first
+    pub indexed_files: usize,
+    /// Source files whose declarations were cached
+    pub symbol_files: usize,
+}
+
+/// Fill `index` and `symbols` for `roots`, pausing `delay` between batches of entries walked
+/// and between files parsed. Symbols are cached for the files `search_symbols` reads with its
+/// default limits. Blocks until done, so run it on a blocking thread
+pub fn prewarm(
+    index: Option<&FileIndex>,
+    symbols: &SymbolIndex,
+    roots: &[PathBuf],
+    delay: Duration,
+) -> PrewarmStats {
+    let mut stats = PrewarmStats::default();
+    if let Some(index) = index {
+        let mut walked = 0;
+        stats.indexed_files = index.prewarm(|| {
+            walked += 1;
+            if walked % WALK_BATCH == 0 {
+                std::thread::sleep(delay);
+            }
+        });
+    }
+
+    let mut paths = source_files(roots);
+    paths.truncate(default_symbol_max_files());
+    let max_file_size = default_max_file_size();
+    for (path, lang) in paths {
+        let small = std::fs::metadata(&path).is_ok_and(|metadata| metadata.len() <= max_file_size);
+        if small && symbols.symbols(&path, lang).is_ok() {
+            stats.symbol_files += 1;
+        }
+        std::thread::sleep(delay);
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServiceConfig;
+    use ast_grep_language::SupportLang as Language;
+
+    #[test]
+    fn test_prewarm_fills_the_index_and_symbol_cache() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("lib.rs"), "fn parse() {}\n").unwrap();
+        std::fs::write(temp_dir.path().join("notes.txt"), "not source\n").unwrap();
+        let roots = vec![temp_dir.path().to_path_buf()];
+        let index = FileIndex::new(&ServiceConfig {
+            root_directories: roots.clone(),
+            ..Default::default()
+        });
+        let symbols = SymbolIndex::new();
+
+        let stats = prewarm(Some(&index), &symbols, &roots, Duration::ZERO);
+        assert_eq!(
+            stats,
+            PrewarmStats {
+                indexed_files: 2,
+                symbol_files: 1,
+            }
+        );
+        assert!(index.status().built);
+        let (declared, cached) = symbols
+            .symbols(&temp_dir.path().join("lib.rs"), Language::Rust)
+            .unwrap();
+        assert!(cached);
+        assert_eq!(declared[0].name, "parse");
+
+        // A built index is left alone
+        assert_eq!(
+            prewarm(Some(&index), &symbols, &roots, Duration::ZERO).indexed_files,
+            0
+        );
+    }
+}
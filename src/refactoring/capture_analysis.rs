@@ -280,7 +280,27 @@ impl CommonLanguageAnalyzer {
                 }
             }
         }
-        
+
+        // Every external dependency becomes a parameter of the extracted function
+        for (var_ref, is_mutable) in analysis
+            .external_reads
+            .iter()
+            .map(|v| (v, false))
+            .chain(analysis.external_writes.iter().map(|v| (v, true)))
+        {
+            if !analysis
+                .suggested_parameters
+                .iter()
+                .any(|p| p.name == var_ref.name)
+            {
+                analysis.suggested_parameters.push(Parameter {
+                    name: var_ref.name.clone(),
+                    param_type: var_ref.var_type.clone(),
+                    is_mutable,
+                });
+            }
+        }
+
         // Analyze return values
         analysis.return_values = self.collect_return_statements(fragment_node)?;
         
@@ -422,15 +442,19 @@ impl CommonLanguageAnalyzer {
     }
     
     /// Find assignment expressions targeting a specific variable
-    fn find_assignment_to_variable(&self, node: &Node<StrDoc<Language>>, target_var: &str) -> Result<bool, ServiceError> {
-        if node.kind() == self.node_types.assignment_expression {
-            if let Some(left) = node.children().next() {
-                if left.kind() == self.node_types.identifier && left.text() == target_var {
-                    return Ok(true);
-                }
-            }
+    fn find_assignment_to_variable(
+        &self,
+        node: &Node<StrDoc<Language>>,
+        target_var: &str,
+    ) -> Result<bool, ServiceError> {
+        if node.kind() == self.node_types.assignment_expression
+            && let Some(left) = node.children().next()
+            && left.kind() == self.node_types.identifier
+            && left.text() == target_var
+        {
+            return Ok(true);
         }
-        
+
         for child in node.children() {
             if self.find_assignment_to_variable(&child, target_var)? {
                 return Ok(true);
@@ -452,19 +476,20 @@ impl CommonLanguageAnalyzer {
         if *found {
             return Ok(());
         }
-        
-        let is_declarator = node.kind() == self.node_types.variable_declarator || 
-                           node.kind() == self.node_types.function_declaration;
-        
-        if is_declarator {
-            if let Some(identifier) = node.children().find(|child| child.kind() == self.node_types.identifier) {
-                if identifier.text() == target_var {
-                    *found = true;
-                    return Ok(());
-                }
-            }
+
+        let is_declarator = node.kind() == self.node_types.variable_declarator
+            || node.kind() == self.node_types.function_declaration;
+
+        if is_declarator
+            && let Some(identifier) = node
+                .children()
+                .find(|child| child.kind() == self.node_types.identifier)
+            && identifier.text() == target_var
+        {
+            *found = true;
+            return Ok(());
         }
-        
+
         // Also check for parameter declarations in formal_parameters
         if node.kind() == self.node_types.formal_parameters {
             for child in node.children() {
@@ -703,7 +728,6 @@ impl CaptureAnalysisEngine {
             
             // Analyze the context around the variable declaration
             let lines: Vec<&str> = context_before.lines().collect();
-            let mut in_function = false;
             let mut in_if_block = false;
             let mut standalone_blocks = 0;
             
@@ -712,7 +736,6 @@ impl CaptureAnalysisEngine {
                 let trimmed = line.trim();
                 
                 if trimmed.starts_with("function") {
-                    in_function = true;
                     break;
                 }
                 
@@ -727,14 +750,11 @@ impl CaptureAnalysisEngine {
             }
             
             // Determine scope type based on the immediate context
-            if standalone_blocks > 0 {
+            // if blocks create block scope; everything else defaults to function scope
+            if standalone_blocks > 0 || in_if_block {
                 Ok(ScopeType::Block)
-            } else if in_if_block {
-                Ok(ScopeType::Block) // if blocks create block scope
-            } else if in_function {
-                Ok(ScopeType::Function)
             } else {
-                Ok(ScopeType::Function) // Default
+                Ok(ScopeType::Function)
             }
         } else {
             // Variable not found with standard patterns, assume function scope
@@ -745,8 +765,8 @@ impl CaptureAnalysisEngine {
     /// Suggest parameter names avoiding conflicts
     pub fn suggest_parameter_names(&self, scope_info: &ScopeInfo) -> HashMap<String, String> {
         let mut suggestions = HashMap::new();
-        
-        for (var_name, _var_scope) in &scope_info.external_variables {
+
+        for var_name in scope_info.external_variables.keys() {
             if scope_info.naming_conflicts.contains_key(var_name) {
                 // Suggest an alternative name
                 let suggested_name = format!("{}_param", var_name);
@@ -873,10 +893,8 @@ impl CaptureAnalysisEngine {
         } else if if_blocks > 0 && in_function {
             // We're in an if statement inside a function
             Ok(ScopeType::Conditional)
-        } else if in_function {
-            Ok(ScopeType::Function)
         } else {
-            Ok(ScopeType::Function) // Default
+            Ok(ScopeType::Function)
         }
     }
     
@@ -905,52 +923,57 @@ impl CaptureAnalysisEngine {
         scope_info: &mut ScopeInfo,
     ) -> Result<(), ServiceError> {
         // Look for function parameters in context
-        if let Some(params_start) = context.find("(") {
-            if let Some(params_end) = context[params_start..].find(")") {
-                let params_str = &context[params_start + 1..params_start + params_end];
-                let params: Vec<&str> = params_str.split(",").map(|p| p.trim()).filter(|p| !p.is_empty()).collect();
-                
-                // Check for conflicts with variables declared in fragment
-                for line in fragment.lines() {
-                    if line.contains("for (") || line.contains("for(") {
-                        // Extract loop variable declarations
-                        if let Some(let_pos) = line.find("let ") {
-                            let after_let = &line[let_pos + 4..];
-                            if let Some(of_pos) = after_let.find(" of ") {
-                                let loop_var = after_let[..of_pos].trim();
-                                if params.contains(&loop_var) {
-                                    scope_info.naming_conflicts.insert(
-                                        loop_var.to_string(),
-                                        "Variable name conflicts with function parameter".to_string()
-                                    );
-                                }
-                                
-                                // Also extract the iterable variable for suggestions
-                                let after_of = &after_let[of_pos + 4..];
-                                if let Some(close_paren) = after_of.find(")") {
-                                    let iterable = after_of[..close_paren].trim();
-                                    // Store iterable for suggestions
-                                    scope_info.external_variables.entry(iterable.to_string()).or_insert(
-                                        VariableScope {
-                                            name: iterable.to_string(),
-                                            scope_type: ScopeType::Function,
-                                            declared_at_depth: 1,
-                                            usage_type: UsageType::Read,
-                                            is_shadowed: false,
-                                            shadowed_scopes: vec![],
-                                            is_closure_captured: false,
-                                            is_nonlocal: false,
-                                            is_global: false,
-                                        }
-                                    );
-                                }
+        if let Some(params_start) = context.find("(")
+            && let Some(params_end) = context[params_start..].find(")")
+        {
+            let params_str = &context[params_start + 1..params_start + params_end];
+            let params: Vec<&str> = params_str
+                .split(",")
+                .map(|p| p.trim())
+                .filter(|p| !p.is_empty())
+                .collect();
+
+            // Check for conflicts with variables declared in fragment
+            for line in fragment.lines() {
+                if line.contains("for (") || line.contains("for(") {
+                    // Extract loop variable declarations
+                    if let Some(let_pos) = line.find("let ") {
+                        let after_let = &line[let_pos + 4..];
+                        if let Some(of_pos) = after_let.find(" of ") {
+                            let loop_var = after_let[..of_pos].trim();
+                            if params.contains(&loop_var) {
+                                scope_info.naming_conflicts.insert(
+                                    loop_var.to_string(),
+                                    "Variable name conflicts with function parameter".to_string(),
+                                );
+                            }
+
+                            // Also extract the iterable variable for suggestions
+                            let after_of = &after_let[of_pos + 4..];
+                            if let Some(close_paren) = after_of.find(")") {
+                                let iterable = after_of[..close_paren].trim();
+                                // Store iterable for suggestions
+                                scope_info
+                                    .external_variables
+                                    .entry(iterable.to_string())
+                                    .or_insert(VariableScope {
+                                        name: iterable.to_string(),
+                                        scope_type: ScopeType::Function,
+                                        declared_at_depth: 1,
+                                        usage_type: UsageType::Read,
+                                        is_shadowed: false,
+                                        shadowed_scopes: vec![],
+                                        is_closure_captured: false,
+                                        is_nonlocal: false,
+                                        is_global: false,
+                                    });
                             }
                         }
                     }
                 }
             }
         }
-        
+
         Ok(())
     }
     
@@ -1299,38 +1322,40 @@ impl CaptureAnalysisEngine {
             }
             
             // DOM manipulation detection
-            if trimmed.contains(".innerHTML") || trimmed.contains(".style.") ||
-               trimmed.contains(".appendChild") || trimmed.contains(".removeChild") ||
-               trimmed.contains(".addEventListener") {
-                if let Some(element) = self.extract_dom_target(trimmed) {
-                    let action = if trimmed.contains(".innerHTML") {
-                        "innerHTML"
-                    } else if trimmed.contains(".style.") {
-                        "style"
-                    } else if trimmed.contains(".appendChild") {
-                        "appendChild"
-                    } else if trimmed.contains(".removeChild") {
-                        "removeChild"
-                    } else if trimmed.contains(".addEventListener") {
-                        "addEventListener"
-                    } else {
-                        "modify"
-                    };
-                    
-                    side_effects.push(SideEffect::DOMManipulation {
-                        element,
-                        action: action.to_string(),
-                    });
-                }
+            if (trimmed.contains(".innerHTML")
+                || trimmed.contains(".style.")
+                || trimmed.contains(".appendChild")
+                || trimmed.contains(".removeChild")
+                || trimmed.contains(".addEventListener"))
+                && let Some(element) = self.extract_dom_target(trimmed)
+            {
+                let action = if trimmed.contains(".innerHTML") {
+                    "innerHTML"
+                } else if trimmed.contains(".style.") {
+                    "style"
+                } else if trimmed.contains(".appendChild") {
+                    "appendChild"
+                } else if trimmed.contains(".removeChild") {
+                    "removeChild"
+                } else if trimmed.contains(".addEventListener") {
+                    "addEventListener"
+                } else {
+                    "modify"
+                };
+
+                side_effects.push(SideEffect::DOMManipulation {
+                    element,
+                    action: action.to_string(),
+                });
             }
-            
+
             // Global mutation detection (variables assigned outside fragment)
-            if let Some(var_name) = self.extract_assignment_target(trimmed) {
-                if !self.is_declared_in_fragment(&var_name, fragment) {
-                    side_effects.push(SideEffect::GlobalMutation { variable: var_name });
-                }
+            if let Some(var_name) = self.extract_assignment_target(trimmed)
+                && !self.is_declared_in_fragment(&var_name, fragment)
+            {
+                side_effects.push(SideEffect::GlobalMutation { variable: var_name });
             }
-            
+
             // Await expression detection
             if trimmed.contains("await ") {
                 side_effects.push(SideEffect::AsyncOperation {
@@ -1410,11 +1435,10 @@ impl CaptureAnalysisEngine {
             };
             
             // Extract just the variable name (last word)
-            if let Some(var_name) = var_part.split_whitespace().last() {
-                Some(var_name.to_string())
-            } else {
-                None
-            }
+            var_part
+                .split_whitespace()
+                .last()
+                .map(|var_name| var_name.to_string())
         } else {
             None
         }
@@ -1425,7 +1449,7 @@ impl CaptureAnalysisEngine {
         if let Some(await_pos) = line.find("await ") {
             let after_await = &line[await_pos + 6..].trim();
             // Extract the function call or expression being awaited
-            if let Some(end_pos) = after_await.find(|c: char| c == ';' || c == ')' || c == '}') {
+            if let Some(end_pos) = after_await.find([';', ')', '}']) {
                 Some(after_await[..end_pos].trim().to_string())
             } else {
                 Some(after_await.to_string())
@@ -1436,6 +1460,7 @@ impl CaptureAnalysisEngine {
     }
     
     /// Create a new capture analysis engine
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         let mut engine = Self {
             common_analyzer: HashMap::new(),
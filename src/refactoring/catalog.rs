@@ -116,17 +116,14 @@ impl RefactoringCatalog {
             ))?;
 
         // Validate language support if specified
-        if let Some(ref options) = request.options {
-            if let Some(ref language) = options.language {
-                if !definition.supported_languages.contains(language) {
-                    return Err(ServiceError::Internal(
-                        format!(
-                            "Refactoring '{}' does not support language: {}",
-                            request.refactoring_id, language
-                        )
-                    ));
-                }
-            }
+        if let Some(ref options) = request.options
+            && let Some(ref language) = options.language
+            && !definition.supported_languages.contains(language)
+        {
+            return Err(ServiceError::Internal(format!(
+                "Refactoring '{}' does not support language: {}",
+                request.refactoring_id, language
+            )));
         }
 
         // Validate required options based on refactoring type
@@ -154,12 +151,10 @@ impl RefactoringCatalog {
                         ));
                     }
                 }
-                "extract_class" => {
-                    if options.class_name.is_none() {
-                        return Err(ServiceError::Internal(
-                            "extract_class requires class_name in options".to_string()
-                        ));
-                    }
+                "extract_class" if options.class_name.is_none() => {
+                    return Err(ServiceError::Internal(
+                        "extract_class requires class_name in options".to_string(),
+                    ));
                 }
                 _ => {}
             }
@@ -486,6 +486,12 @@ mod tests {
             vars: HashMap::new(),
             context_before: None,
             context_after: None,
+            enclosing_symbol: None,
+            ast_context: None,
+            quick_fixes: Vec::new(),
+            enclosing_node: None,
+            leading_trivia: Vec::new(),
+            rule_id: None,
         };
         let transformation = engine.build_transformation(&transform, &request, &captured_vars, &dummy_match, "javascript").unwrap();
         
@@ -6,6 +6,7 @@
 pub mod catalog;
 pub mod capture_analysis;
 pub mod engine;
+pub mod rename;
 pub mod service;
 pub mod types;
 pub mod validation;
@@ -0,0 +1,140 @@
+//! # Symbol Rename
+//!
+//! Renames a function, variable, class or type across one file or a directory. Every identifier
+//! node naming the symbol is renamed: its definitions, calls and imports, which the
+//! [`crate::references`] probes recognize and the result labels as such, and any other use, such
+//! as a read or a type annotation. Strings and comments are never touched, so neither is a
+//! module path in an import.
+//!
+//! The rename is purely syntactic: a local variable of the same name in another function is
+//! renamed along with the symbol. Before anything is written, the definitions of the new name
+//! already in scope are reported as collisions.
+
+use crate::references::file_references;
+use crate::types::{
+    FileReferences, ReferenceKind, RenameSymbolParam, RenameSymbolResult, SkippedFile,
+    SymbolReference,
+};
+use ast_grep_core::Node;
+use ast_grep_core::tree_sitter::{LanguageExt, StrDoc};
+use ast_grep_language::SupportLang as Language;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// The renames and collisions of `param` in `files`, with the new content of each file the
+/// rename changes
+pub fn plan_rename(
+    files: Vec<(PathBuf, Language)>,
+    param: &RenameSymbolParam,
+) -> (RenameSymbolResult, Vec<(PathBuf, String)>) {
+    let mut result = RenameSymbolResult {
+        old_name: param.old_name.clone(),
+        new_name: param.new_name.clone(),
+        dry_run: param.dry_run,
+        applied: false,
+        files: Vec::new(),
+        total_renames: 0,
+        collisions: Vec::new(),
+        files_searched: 0,
+        skipped_files: Vec::new(),
+    };
+    let mut rewritten = Vec::new();
+
+    for (path, lang) in files {
+        let file_path = path.to_string_lossy().to_string();
+        let too_large = std::fs::metadata(&path)
+            .map(|metadata| metadata.len() > param.max_file_size)
+            .unwrap_or(false);
+        if too_large {
+            result.skipped_files.push(SkippedFile {
+                file_path,
+                reason: format!("larger than {} bytes", param.max_file_size),
+            });
+            continue;
+        }
+        let code = match std::fs::read_to_string(&path) {
+            Ok(code) => code,
+            Err(e) => {
+                result.skipped_files.push(SkippedFile {
+                    file_path,
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+        result.files_searched += 1;
+
+        let collisions: Vec<SymbolReference> = file_references(&code, lang, &param.new_name)
+            .into_iter()
+            .filter(|reference| reference.kind == ReferenceKind::Definition)
+            .collect();
+        if !collisions.is_empty() {
+            result.collisions.push(FileReferences {
+                file_path: file_path.clone(),
+                references: collisions,
+            });
+        }
+
+        let (renames, new_code) = rename_in(&code, lang, &param.old_name, &param.new_name);
+        if renames.is_empty() {
+            continue;
+        }
+        result.total_renames += renames.len();
+        result.files.push(FileReferences {
+            file_path,
+            references: renames,
+        });
+        rewritten.push((path, new_code));
+    }
+    (result, rewritten)
+}
+
+/// The places `code` names `old_name`, in source order, and the code with each renamed
+fn rename_in(
+    code: &str,
+    lang: Language,
+    old_name: &str,
+    new_name: &str,
+) -> (Vec<SymbolReference>, String) {
+    let kinds: HashMap<(usize, usize), ReferenceKind> = file_references(code, lang, old_name)
+        .into_iter()
+        .map(|reference| ((reference.line, reference.column), reference.kind))
+        .collect();
+    let ast = lang.ast_grep(code);
+    let lines: Vec<&str> = code.lines().collect();
+
+    let mut renames = Vec::new();
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for name in ast.root().dfs().filter(|node| names_symbol(node, old_name)) {
+        let position = name.start_pos();
+        let (line, column) = (position.line(), position.column(&name));
+        renames.push(SymbolReference {
+            kind: kinds
+                .get(&(line, column))
+                .copied()
+                .unwrap_or(ReferenceKind::Usage),
+            line,
+            column,
+            text: lines.get(line).map_or("", |text| text.trim()).to_string(),
+        });
+        ranges.push(name.range());
+    }
+
+    let mut new_code = String::with_capacity(code.len());
+    let mut copied = 0;
+    for range in ranges {
+        new_code.push_str(&code[copied..range.start]);
+        new_code.push_str(new_name);
+        copied = range.end;
+    }
+    new_code.push_str(&code[copied..]);
+    (renames, new_code)
+}
+
+/// Whether `node` is an identifier spelling `symbol`. Every grammar names its identifier
+/// kinds `identifier`, `type_identifier`, `property_identifier` and the like. String contents
+/// and comments are never identifiers, though the code interpolated into a template string is
+fn names_symbol(node: &Node<StrDoc<Language>>, symbol: &str) -> bool {
+    node.is_leaf() && node.kind().contains("identifier") && node.text() == symbol
+}
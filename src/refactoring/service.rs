@@ -216,8 +216,8 @@ impl RefactoringService {
             description: definition.description.clone(),
             supported_languages: definition.supported_languages.clone(),
             complexity: format!("{:?}", definition.complexity),
-            pattern: definition.pattern.r#match.clone(),
-            transformation: definition.transform.replace.clone(),
+            pattern: definition.pattern.r#match.trim_end().to_string(),
+            transformation: definition.transform.replace.trim_end().to_string(),
             required_options,
             preconditions: definition
                 .preconditions
@@ -22,7 +22,7 @@ pub struct RefactoringRequest {
 }
 
 /// Options for customizing refactoring behavior
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RefactoringOptions {
     /// Name for extracted function/method
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -61,11 +61,29 @@ pub struct RefactoringOptions {
     pub language: Option<String>,
 }
 
+impl Default for RefactoringOptions {
+    fn default() -> Self {
+        Self {
+            function_name: None,
+            variable_name: None,
+            class_name: None,
+            new_name: None,
+            scope: default_scope(),
+            preview: default_true(),
+            max_matches: default_max_matches(),
+            path_pattern: None,
+            language: None,
+        }
+    }
+}
+
 /// Scope of refactoring operation
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
+#[derive(Default)]
 pub enum RefactoringScope {
     /// Apply to single file
+    #[default]
     File,
     /// Apply to directory and subdirectories
     Directory,
@@ -73,12 +91,6 @@ pub enum RefactoringScope {
     Project,
 }
 
-impl Default for RefactoringScope {
-    fn default() -> Self {
-        RefactoringScope::File
-    }
-}
-
 /// Response from a refactoring operation
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RefactoringResponse {
@@ -158,13 +158,13 @@ impl ValidationEngine {
 
         if let Some(name) = name {
             // Check if it's a reserved keyword
-            if let Some(keywords) = self.reserved_keywords.get(language) {
-                if keywords.contains(name) {
-                    return Ok(Some(format!(
-                        "'{}' is a reserved keyword in {}",
-                        name, language
-                    )));
-                }
+            if let Some(keywords) = self.reserved_keywords.get(language)
+                && keywords.contains(name)
+            {
+                return Ok(Some(format!(
+                    "'{}' is a reserved keyword in {}",
+                    name, language
+                )));
             }
 
             // Check if the name already exists in any of the matched files
@@ -319,11 +319,25 @@ mod tests {
                     vars: Default::default(),
                     context_before: Some(vec!["const data = getData();".to_string()]),
                     context_after: Some(vec!["return data;".to_string()]),
+                    enclosing_symbol: None,
+                    ast_context: None,
+                    quick_fixes: Vec::new(),
+                    enclosing_node: None,
+                    leading_trivia: Vec::new(),
+                    rule_id: None,
                 }],
                 file_hash: "hash".to_string(),
+                language: None,
+                workspace: None,
+                engine: None,
             }],
             next_cursor: None,
             total_files_found: 1,
+            skipped_files: Vec::new(),
+            budget_exceeded: false,
+            timed_out: false,
+            stats: None,
+            warnings: Vec::new(),
         }
     }
 
@@ -0,0 +1,358 @@
+//! # Symbol References
+//!
+//! Finds the places a symbol is named across a directory and classifies each one as a
+//! definition, a call or an import. Every supported language has a fixed set of usage probes:
+//! ast-grep patterns for calls, declaration kinds whose `name` is the symbol for definitions and
+//! import statement kinds that name it, either as an imported name or as the last segment of the
+//! module path. Each reference is reported at the position of the name itself, so a name matched
+//! by several probes (`const config = require('config')`) is reported once, by the first probe
+//! in the table.
+
+use crate::directory_summary::source_files;
+use crate::types::{
+    FileReferences, FindReferencesParam, FindReferencesResult, ReferenceKind, SkippedFile,
+    SymbolReference,
+};
+use ast_grep_core::tree_sitter::{LanguageExt, StrDoc};
+use ast_grep_core::{AstGrep, Node, Pattern};
+use ast_grep_language::SupportLang as Language;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+
+const JS_FAMILY: &[Language] = &[Language::JavaScript, Language::TypeScript, Language::Tsx];
+
+/// Placeholder for the symbol in [`Matcher::Pattern`] and [`Matcher::Contextual`] patterns
+const SYMBOL: &str = "SYMBOL";
+
+/// How a probe recognizes one kind of usage
+enum Matcher {
+    /// Matches of an ast-grep pattern
+    Pattern(&'static str),
+    /// Matches of a pattern that only parses inside the context, narrowed to the selector kind
+    Contextual(&'static str, &'static str),
+    /// Nodes of these kinds whose `name` field is the symbol
+    Named(&'static [&'static str]),
+    /// Nodes of these kinds that name the symbol or a module path ending in it
+    Import(&'static [&'static str]),
+}
+
+/// One way a symbol can be used in a family of languages
+struct Probe {
+    kind: ReferenceKind,
+    languages: &'static [Language],
+    matcher: Matcher,
+}
+
+const PROBES: &[Probe] = &[
+    // JavaScript and TypeScript
+    Probe {
+        kind: ReferenceKind::Import,
+        languages: JS_FAMILY,
+        matcher: Matcher::Import(&["import_statement"]),
+    },
+    Probe {
+        kind: ReferenceKind::Import,
+        languages: JS_FAMILY,
+        matcher: Matcher::Pattern("const SYMBOL = require($MODULE)"),
+    },
+    Probe {
+        kind: ReferenceKind::Definition,
+        languages: JS_FAMILY,
+        matcher: Matcher::Named(&[
+            "function_declaration",
+            "generator_function_declaration",
+            "class_declaration",
+            "abstract_class_declaration",
+            "method_definition",
+            "interface_declaration",
+            "type_alias_declaration",
+            "enum_declaration",
+            "variable_declarator",
+        ]),
+    },
+    Probe {
+        kind: ReferenceKind::Call,
+        languages: JS_FAMILY,
+        matcher: Matcher::Pattern("SYMBOL($$$ARGS)"),
+    },
+    Probe {
+        kind: ReferenceKind::Call,
+        languages: JS_FAMILY,
+        matcher: Matcher::Pattern("$OBJECT.SYMBOL($$$ARGS)"),
+    },
+    Probe {
+        kind: ReferenceKind::Call,
+        languages: JS_FAMILY,
+        matcher: Matcher::Pattern("new SYMBOL($$$ARGS)"),
+    },
+    // Python
+    Probe {
+        kind: ReferenceKind::Import,
+        languages: &[Language::Python],
+        matcher: Matcher::Import(&["import_statement", "import_from_statement"]),
+    },
+    Probe {
+        kind: ReferenceKind::Definition,
+        languages: &[Language::Python],
+        matcher: Matcher::Named(&["function_definition", "class_definition"]),
+    },
+    Probe {
+        kind: ReferenceKind::Definition,
+        languages: &[Language::Python],
+        matcher: Matcher::Pattern("SYMBOL = $VALUE"),
+    },
+    Probe {
+        kind: ReferenceKind::Call,
+        languages: &[Language::Python],
+        matcher: Matcher::Pattern("SYMBOL($$$ARGS)"),
+    },
+    Probe {
+        kind: ReferenceKind::Call,
+        languages: &[Language::Python],
+        matcher: Matcher::Pattern("$OBJECT.SYMBOL($$$ARGS)"),
+    },
+    // Rust
+    Probe {
+        kind: ReferenceKind::Import,
+        languages: &[Language::Rust],
+        matcher: Matcher::Import(&["use_declaration"]),
+    },
+    Probe {
+        kind: ReferenceKind::Definition,
+        languages: &[Language::Rust],
+        matcher: Matcher::Named(&[
+            "function_item",
+            "function_signature_item",
+            "struct_item",
+            "enum_item",
+            "union_item",
+            "trait_item",
+            "type_item",
+            "const_item",
+            "static_item",
+            "mod_item",
+            "macro_definition",
+        ]),
+    },
+    Probe {
+        kind: ReferenceKind::Call,
+        languages: &[Language::Rust],
+        matcher: Matcher::Pattern("SYMBOL($$$ARGS)"),
+    },
+    Probe {
+        kind: ReferenceKind::Call,
+        languages: &[Language::Rust],
+        matcher: Matcher::Pattern("$RECEIVER.SYMBOL($$$ARGS)"),
+    },
+    Probe {
+        kind: ReferenceKind::Call,
+        languages: &[Language::Rust],
+        matcher: Matcher::Pattern("$PATH::SYMBOL($$$ARGS)"),
+    },
+    // Go
+    Probe {
+        kind: ReferenceKind::Import,
+        languages: &[Language::Go],
+        matcher: Matcher::Import(&["import_spec"]),
+    },
+    Probe {
+        kind: ReferenceKind::Definition,
+        languages: &[Language::Go],
+        matcher: Matcher::Named(&[
+            "function_declaration",
+            "method_declaration",
+            "type_spec",
+            "const_spec",
+            "var_spec",
+        ]),
+    },
+    Probe {
+        kind: ReferenceKind::Call,
+        languages: &[Language::Go],
+        matcher: Matcher::Pattern("SYMBOL($$$ARGS)"),
+    },
+    Probe {
+        kind: ReferenceKind::Call,
+        languages: &[Language::Go],
+        // `$X.f()` alone parses as something other than a call in Go
+        matcher: Matcher::Contextual("func f() { $OBJECT.SYMBOL($$$ARGS) }", "call_expression"),
+    },
+];
+
+/// Whether `find_references` has probes for `lang`
+pub fn supports(lang: Language) -> bool {
+    PROBES.iter().any(|probe| probe.languages.contains(&lang))
+}
+
+/// Whether `symbol` is a plain identifier that can stand in a pattern
+pub fn is_identifier(symbol: &str) -> bool {
+    let mut chars = symbol.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Whether code in `a` can refer to symbols of `b`, as JavaScript and TypeScript can
+pub fn same_family(a: Language, b: Language) -> bool {
+    a == b || (JS_FAMILY.contains(&a) && JS_FAMILY.contains(&b))
+}
+
+/// References to `param.symbol` in the files under `directories` written in `lang` or a
+/// language of the same family
+pub fn find_references(
+    directories: &[PathBuf],
+    param: &FindReferencesParam,
+    lang: Language,
+) -> FindReferencesResult {
+    let mut result = FindReferencesResult {
+        symbol: param.symbol.clone(),
+        files: Vec::new(),
+        total_references: 0,
+        counts: BTreeMap::new(),
+        files_searched: 0,
+        truncated: false,
+        skipped_files: Vec::new(),
+    };
+
+    for (path, file_lang) in source_files(directories) {
+        if !same_family(lang, file_lang) {
+            continue;
+        }
+        let file_path = path.to_string_lossy().to_string();
+        let too_large = std::fs::metadata(&path)
+            .map(|metadata| metadata.len() > param.max_file_size)
+            .unwrap_or(false);
+        if too_large {
+            result.skipped_files.push(SkippedFile {
+                file_path,
+                reason: format!("larger than {} bytes", param.max_file_size),
+            });
+            continue;
+        }
+        let code = match std::fs::read_to_string(&path) {
+            Ok(code) => code,
+            Err(e) => {
+                result.skipped_files.push(SkippedFile {
+                    file_path,
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        result.files_searched += 1;
+        let mut references = file_references(&code, file_lang, &param.symbol);
+        if references.is_empty() {
+            continue;
+        }
+        // Once max_results is reached, the next file with references marks the result truncated
+        let room = param.max_results - result.total_references;
+        if references.len() > room {
+            references.truncate(room);
+            result.truncated = true;
+        }
+        if references.is_empty() {
+            break;
+        }
+        for reference in &references {
+            *result
+                .counts
+                .entry(reference.kind.as_str().to_string())
+                .or_default() += 1;
+        }
+        result.total_references += references.len();
+        result.files.push(FileReferences {
+            file_path,
+            references,
+        });
+    }
+    result
+}
+
+/// References to `symbol` in `code`, in source order
+pub fn file_references(code: &str, lang: Language, symbol: &str) -> Vec<SymbolReference> {
+    let ast = lang.ast_grep(code);
+    let lines: Vec<&str> = code.lines().collect();
+    let mut seen = HashSet::new();
+    let mut references = Vec::new();
+    for probe in PROBES
+        .iter()
+        .filter(|probe| probe.languages.contains(&lang))
+    {
+        for name in probe_names(&ast, &probe.matcher, lang, symbol) {
+            if !seen.insert(name.range().start) {
+                continue;
+            }
+            let position = name.start_pos();
+            let line = position.line();
+            references.push(SymbolReference {
+                kind: probe.kind,
+                line,
+                column: position.column(&name),
+                text: lines.get(line).map_or("", |text| text.trim()).to_string(),
+            });
+        }
+    }
+    references.sort_by_key(|reference| (reference.line, reference.column));
+    references
+}
+
+/// The name nodes of the usages `matcher` finds in `ast`; patterns that don't parse in `lang`
+/// find nothing
+fn probe_names<'a>(
+    ast: &'a AstGrep<StrDoc<Language>>,
+    matcher: &Matcher,
+    lang: Language,
+    symbol: &str,
+) -> Vec<Node<'a, StrDoc<Language>>> {
+    let pattern = match matcher {
+        Matcher::Named(kinds) => {
+            return ast
+                .root()
+                .dfs()
+                .filter(|node| kinds.contains(&node.kind().as_ref()))
+                .filter_map(|node| node.field("name"))
+                .filter(|name| name.text() == symbol)
+                .collect();
+        }
+        Matcher::Import(kinds) => {
+            return ast
+                .root()
+                .dfs()
+                .filter(|node| kinds.contains(&node.kind().as_ref()))
+                .filter_map(|node| node.dfs().find(|leaf| imports(leaf, symbol)))
+                .collect();
+        }
+        Matcher::Pattern(pattern) => Pattern::try_new(&pattern.replace(SYMBOL, symbol), lang),
+        Matcher::Contextual(context, selector) => {
+            Pattern::contextual(&context.replace(SYMBOL, symbol), selector, lang)
+        }
+    };
+    match pattern {
+        Ok(pattern) => ast
+            .root()
+            .find_all(pattern)
+            .filter_map(|node_match| {
+                node_match
+                    .get_node()
+                    .dfs()
+                    .find(|leaf| leaf.is_leaf() && leaf.text() == symbol)
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Whether `leaf`, inside an import, names `symbol` or a module path whose last segment is it
+fn imports(leaf: &Node<StrDoc<Language>>, symbol: &str) -> bool {
+    if !leaf.is_leaf() {
+        return false;
+    }
+    let text = leaf.text();
+    let path = text.trim_matches(|c| c == '"' || c == '\'' || c == '`');
+    path == symbol
+        || path
+            .rsplit_once('/')
+            .is_some_and(|(_, last)| last == symbol || last.split('.').next() == Some(symbol))
+}
@@ -0,0 +1,74 @@
+//! # Regex Fallback
+//!
+//! Line-based search for files whose language has no tree-sitter grammar, such as `.toml`
+//! or `.ini`. `file_search` uses it when `regex_fallback` is set and `language` is not a
+//! supported language. The pattern is a regular expression rather than an ast-grep pattern,
+//! so results are flagged with `engine: "regex"`.
+
+use crate::types::MatchResult;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// `engine` reported on files searched by this module
+pub const REGEX_ENGINE: &str = "regex";
+
+/// Every match of `regex` in `code`, one line at a time. Lines are 0-based and columns count
+/// characters, as for AST matches. Named capture groups are reported as `vars`
+pub fn find_matches(code: &str, regex: &Regex) -> Vec<MatchResult> {
+    let mut matches = Vec::new();
+    for (line_idx, line) in code.lines().enumerate() {
+        for captures in regex.captures_iter(line) {
+            let whole = captures.get(0).expect("group 0 is always present");
+            if whole.is_empty() {
+                continue;
+            }
+            let vars: HashMap<String, String> = regex
+                .capture_names()
+                .flatten()
+                .filter_map(|name| Some((name.to_string(), captures.name(name)?.as_str().into())))
+                .collect();
+            matches.push(MatchResult {
+                text: whole.as_str().to_string(),
+                start_line: line_idx,
+                end_line: line_idx,
+                start_col: line[..whole.start()].chars().count(),
+                end_col: line[..whole.end()].chars().count(),
+                vars,
+                context_before: None,
+                context_after: None,
+                enclosing_symbol: None,
+                ast_context: None,
+                quick_fixes: Vec::new(),
+                enclosing_node: None,
+                leading_trivia: Vec::new(),
+                rule_id: None,
+            });
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_matches_reports_lines_columns_and_named_groups() {
+        let code = "syntax = \"proto3\";\n\nmessage User {}\n  message Päge {}\n";
+        let regex = Regex::new(r"message (?P<NAME>\w+)").unwrap();
+        let matches = find_matches(code, &regex);
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].start_line, 2);
+        assert_eq!(matches[0].vars["NAME"], "User");
+        assert_eq!(matches[1].start_line, 3);
+        assert_eq!((matches[1].start_col, matches[1].end_col), (2, 14));
+        assert_eq!(matches[1].text, "message Päge");
+    }
+
+    #[test]
+    fn test_find_matches_skips_empty_matches() {
+        let regex = Regex::new(r"x*").unwrap();
+        assert!(find_matches("abc\n", &regex).is_empty());
+    }
+}
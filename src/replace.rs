@@ -1,21 +1,54 @@
 use crate::config::ServiceConfig;
+use crate::dialect::{detect_language, is_auto_language, language_for_file};
 use crate::errors::ServiceError;
-use crate::pattern::PatternMatcher;
-use crate::rules::{RuleEvaluator, RuleReplaceParam, RuleSearchParam, parse_rule_config};
+use crate::extra_languages::AnyLanguage;
+use crate::file_fetch::content_hash;
+use crate::format_check::{
+    FormatCheck, FormatCheckStatus, format_rewritten, formatter_for, line_edits,
+    placeholder_file_name, verify_formatting,
+};
+use crate::json_patch::document_patch;
+use crate::merge::merge3;
+use crate::overlay::Overlays;
+use crate::path_validation::resolve_output_path;
+use crate::pattern::{PatternMatcher, check_broad_pattern};
+use crate::rules::rewrite::{apply_transforms, check_transforms};
+use crate::rules::{FixSafety, RuleEvaluator, RuleReplaceParam, RuleSearchParam};
 use crate::search::SearchService;
+use crate::template::ReplacementTemplate;
 use crate::types::*;
+use crate::unified_diff::{self, file_diff};
+use crate::verification::run_verification;
 use ast_grep_language::SupportLang as Language;
-use sha2::{Digest, Sha256};
+use futures::StreamExt;
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio_util::sync::CancellationToken;
+
+/// Number of dry-run file contents kept for merging when an apply finds the file has changed
+const DRY_RUN_SNAPSHOT_CAPACITY: usize = 256;
+
+/// Number of dry runs whose full diffs are kept for `get_file_diff`
+const DRY_RUN_DIFF_CAPACITY: usize = 16;
 
 #[derive(Clone)]
 pub struct ReplaceService {
-    #[allow(dead_code)]
     config: ServiceConfig,
     pattern_matcher: PatternMatcher,
-    #[allow(dead_code)]
     rule_evaluator: RuleEvaluator,
     search_service: SearchService,
+    /// File contents seen by `file_replace` dry runs, keyed by content hash
+    dry_run_snapshots: Arc<Mutex<LruCache<String, String>>>,
+    /// Full per-file diffs of recent dry runs, keyed by operation id
+    dry_run_diffs: Arc<Mutex<LruCache<String, Vec<FileDiffResult>>>>,
+    next_operation: Arc<AtomicU64>,
 }
 
 impl ReplaceService {
@@ -29,16 +62,40 @@ impl ReplaceService {
             pattern_matcher.clone(),
             rule_evaluator.clone(),
         );
+        let capacity = NonZeroUsize::new(DRY_RUN_SNAPSHOT_CAPACITY).unwrap();
         Self {
             config,
             pattern_matcher,
             rule_evaluator,
             search_service,
+            dry_run_snapshots: Arc::new(Mutex::new(LruCache::new(capacity))),
+            dry_run_diffs: Arc::new(Mutex::new(LruCache::new(
+                NonZeroUsize::new(DRY_RUN_DIFF_CAPACITY).unwrap(),
+            ))),
+            next_operation: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// This service for one client session, whose dry run diffs are only visible to it
+    pub fn for_session(&self) -> Self {
+        Self {
+            dry_run_diffs: Arc::new(Mutex::new(LruCache::new(
+                NonZeroUsize::new(DRY_RUN_DIFF_CAPACITY).unwrap(),
+            ))),
+            ..self.clone()
+        }
+    }
+
+    /// This service for one request, whose file loops stop once `token` is cancelled
+    pub fn with_cancellation(&self, token: CancellationToken) -> Self {
+        Self {
+            search_service: self.search_service.with_cancellation(token),
+            ..self.clone()
         }
     }
 
     pub async fn replace(&self, param: ReplaceParam) -> Result<ReplaceResult, ServiceError> {
-        let lang = Language::from_str(&param.language)
+        let lang = AnyLanguage::from_str(&param.language)
             .map_err(|_| ServiceError::Internal("Failed to parse language".to_string()))?;
 
         // First, find all matches to track changes
@@ -51,64 +108,500 @@ impl ReplaceService {
         )?;
 
         // Apply the replacement
-        let new_code = self.pattern_matcher.replace_with_options(
+        let mut new_code = self.pattern_matcher.replace_anchored(
             &param.code,
             &param.pattern,
             &param.replacement,
             lang,
             param.selector.as_deref(),
             param.context.as_deref(),
+            param.rewrite_selector.as_deref(),
         )?;
+        let file_name = match lang {
+            AnyLanguage::Builtin(lang) => placeholder_file_name(lang),
+            AnyLanguage::Extra(lang) => format!("input.{}", lang.extensions()[0]),
+            AnyLanguage::Dynamic(lang) => format!("input.{}", lang.extensions()[0]),
+        };
+        let (formatted, format_error) = match param.format_after_replace && new_code != param.code {
+            true => match self
+                .format_rewrite(lang, &file_name, &param.code, &new_code)
+                .await
+            {
+                Ok(formatted) => {
+                    new_code = formatted;
+                    (true, None)
+                }
+                Err(reason) => (false, Some(reason)),
+            },
+            false => (false, None),
+        };
 
-        // Convert matches to change results
-        let changes: Vec<ChangeResult> = matches
-            .into_iter()
-            .map(|m| ChangeResult {
-                start_line: m.start_line,
-                end_line: m.end_line,
-                start_col: m.start_col,
-                end_col: m.end_col,
-                old_text: m.text,
-                new_text: param.replacement.clone(), // Simplified - in reality would need template substitution
-            })
-            .collect();
+        let json_patch = match param.json_patch {
+            true => Some(document_patch(lang, &param.code, &new_code)?),
+            false => None,
+        };
 
-        Ok(ReplaceResult { new_code, changes })
+        // Convert matches to change results; an anchored rewrite reports the captured code it
+        // replaced rather than the whole match, a formatted one the lines it changed
+        let changes: Vec<ChangeResult> = match param.rewrite_selector {
+            _ if formatted => line_edits(&param.code, &new_code),
+            Some(_) => self.pattern_matcher.replacement_changes_anchored(
+                &param.code,
+                &param.pattern,
+                &param.replacement,
+                lang,
+                param.selector.as_deref(),
+                param.context.as_deref(),
+                param.rewrite_selector.as_deref(),
+            )?,
+            None => matches
+                .into_iter()
+                // Simplified - in reality would need template substitution
+                .map(|m| match_change(&param.code, m, param.replacement.clone()))
+                .collect(),
+        };
+        let changes = match json_patch {
+            Some(_) => Vec::new(),
+            None => changes,
+        };
+
+        let format_check = match param.verify_formatting {
+            true => {
+                let edits = match formatted {
+                    true => line_edits(&param.code, &new_code),
+                    false => self.pattern_matcher.replacement_changes_anchored(
+                        &param.code,
+                        &param.pattern,
+                        &param.replacement,
+                        lang,
+                        param.selector.as_deref(),
+                        param.context.as_deref(),
+                        param.rewrite_selector.as_deref(),
+                    )?,
+                };
+                Some(
+                    self.format_check(lang, &file_name, &param.code, &new_code, &edits)
+                        .await,
+                )
+            }
+            false => None,
+        };
+
+        Ok(ReplaceResult {
+            new_code,
+            changes,
+            format_check,
+            format_error,
+            json_patch,
+        })
     }
 
+    /// Replace across files. When files are written, their previous contents are recorded in
+    /// the undo directory under the returned `apply_id`
     pub async fn file_replace(
         &self,
         param: FileReplaceParam,
     ) -> Result<FileReplaceResult, ServiceError> {
-        if let Some(ref cursor) = param.cursor {
-            if cursor.is_complete {
-                return Ok(FileReplaceResult {
-                    file_results: vec![],
-                    summary_results: vec![],
-                    next_cursor: Some(CursorResult {
-                        last_file_path: String::new(),
-                        is_complete: true,
-                    }),
-                    total_files_found: 0,
-                    dry_run: param.dry_run,
-                    total_changes: 0,
-                    files_with_changes: 0,
+        let change = AppliedChange {
+            apply_id: String::new(),
+            applied_at: 0,
+            path_pattern: param.path_pattern.clone(),
+            pattern: param.pattern.clone(),
+            replacement: param.replacement.clone(),
+            language: param.language.clone(),
+            files: Vec::new(),
+            rolled_back: false,
+        };
+        let mut written = Vec::new();
+        let mut result = self.apply_file_replace(param, &mut written).await?;
+        if !written.is_empty() {
+            // The files are written either way; a failed record only loses the undo
+            match record_applied_change(&self.config.undo_directory, change, written) {
+                Ok(apply_id) => result.apply_id = Some(apply_id),
+                Err(e) => tracing::warn!("Failed to record the replacement for rollback: {e}"),
+            }
+        }
+        Ok(result)
+    }
+
+    /// The full diff of one file from a recent `file_replace` or `rule_replace` dry run, so
+    /// clients can page through a large dry run file by file
+    pub fn get_file_diff(
+        &self,
+        param: GetFileDiffParam,
+    ) -> Result<GetFileDiffResult, ServiceError> {
+        let mut diffs = self.dry_run_diffs.lock().unwrap();
+        let files = diffs.get(&param.operation_id).ok_or_else(|| {
+            ServiceError::Internal(format!(
+                "No dry run with id '{}'; only the last {DRY_RUN_DIFF_CAPACITY} are kept, run it again",
+                param.operation_id
+            ))
+        })?;
+        let file_index = match &param.file_path {
+            Some(file_path) => files
+                .iter()
+                .position(|diff| diff.file_path == *file_path)
+                .ok_or_else(|| {
+                    ServiceError::Internal(format!(
+                        "Dry run '{}' changes no file '{file_path}'",
+                        param.operation_id
+                    ))
+                })?,
+            None => 0,
+        };
+        Ok(GetFileDiffResult {
+            diff: files[file_index].clone(),
+            file_index,
+            total_files: files.len(),
+            next_file_path: files.get(file_index + 1).map(|diff| diff.file_path.clone()),
+            operation_id: param.operation_id,
+        })
+    }
+
+    /// Keep the diffs of a dry run for `get_file_diff`, returning their operation id. Dry runs
+    /// that change nothing aren't kept
+    fn cache_dry_run(&self, diffs: Vec<FileDiffResult>) -> Option<String> {
+        if diffs.is_empty() {
+            return None;
+        }
+        let operation_id = format!(
+            "dry-run-{}",
+            self.next_operation.fetch_add(1, Ordering::Relaxed)
+        );
+        self.dry_run_diffs
+            .lock()
+            .unwrap()
+            .put(operation_id.clone(), diffs);
+        Some(operation_id)
+    }
+
+    /// Restore the files a `file_replace` wrote to their previous contents. Fails without
+    /// touching any file when one of them changed since, unless `force` is set
+    pub async fn rollback_replace(
+        &self,
+        param: RollbackReplaceParam,
+    ) -> Result<RollbackReplaceResult, ServiceError> {
+        let directory = &self.config.undo_directory;
+        let mut record = load_undo_record(directory, &param.apply_id)?;
+        if record.change.rolled_back {
+            return Err(ServiceError::Internal(format!(
+                "Change '{}' was already rolled back",
+                param.apply_id
+            )));
+        }
+
+        let mut overwritten_files = Vec::new();
+        for file in &record.originals {
+            let current = tokio::fs::read_to_string(&file.file_path).await.ok();
+            if current.as_deref().map(content_hash).as_ref() != Some(&file.written_hash) {
+                overwritten_files.push(file.file_path.clone());
+            }
+        }
+        if !overwritten_files.is_empty() && !param.force {
+            return Err(ServiceError::Internal(format!(
+                "{} changed since '{}' was applied; pass force to restore them anyway",
+                overwritten_files.join(", "),
+                param.apply_id
+            )));
+        }
+
+        let mut restored_files = Vec::new();
+        for file in &record.originals {
+            if let Some(parent) = Path::new(&file.file_path).parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(&file.file_path, &file.original)
+                .await
+                .map_err(|e| ServiceError::FileIoError {
+                    message: format!("rollback failed: {e}"),
+                    path: file.file_path.clone(),
+                })?;
+            restored_files.push(file.file_path.clone());
+        }
+        record.change.rolled_back = true;
+        save_undo_record(directory, &record)?;
+
+        Ok(RollbackReplaceResult {
+            apply_id: param.apply_id,
+            restored_files,
+            overwritten_files,
+        })
+    }
+
+    /// The recorded `file_replace` changes, newest first
+    pub fn list_applied_changes(
+        &self,
+        param: ListAppliedChangesParam,
+    ) -> Result<ListAppliedChangesResult, ServiceError> {
+        let directory = &self.config.undo_directory;
+        let entries = match std::fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(ListAppliedChangesResult {
+                    changes: Vec::new(),
+                    total: 0,
                 });
             }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut changes = Vec::new();
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            let record = std::fs::read_to_string(&path)
+                .map_err(ServiceError::from)
+                .and_then(|json| Ok(serde_json::from_str::<UndoRecord>(&json)?));
+            match record {
+                Ok(record) if param.include_rolled_back || !record.change.rolled_back => {
+                    changes.push(record.change)
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Skipping unreadable undo record {}: {e}", path.display()),
+            }
         }
+        changes.sort_by(|a, b| {
+            b.applied_at
+                .cmp(&a.applied_at)
+                .then_with(|| b.apply_id.cmp(&a.apply_id))
+        });
+        let total = changes.len();
+        changes.truncate(param.limit);
+        Ok(ListAppliedChangesResult { changes, total })
+    }
 
-        let lang = Language::from_str(&param.language)
-            .map_err(|_| ServiceError::Internal("Failed to parse language".to_string()))?;
+    /// Apply a unified diff, such as the `unified_diff` of a `file_replace` dry run. Every hunk
+    /// of every file has to apply before any file is written; the previous contents are then
+    /// recorded in the undo directory like an applied `file_replace`
+    pub async fn apply_patch(
+        &self,
+        param: ApplyPatchParam,
+    ) -> Result<ApplyPatchResult, ServiceError> {
+        let roots = self.config.workspace_roots(param.workspace.as_deref())?;
+        let mut patched = Vec::new();
+        for file in unified_diff::parse_patch(&param.patch)? {
+            let path = resolve_output_path(&file.path, &roots)?;
+            let file_path = path.to_string_lossy().into_owned();
+            let original = tokio::fs::read_to_string(&path).await.map_err(|e| {
+                ServiceError::FileIoError {
+                    message: format!("can't patch: {e}"),
+                    path: file_path.clone(),
+                }
+            })?;
+            let content = unified_diff::apply(&original, &file)?;
+            patched.push((file_path, file, original, content));
+        }
+
+        let files = patched
+            .iter()
+            .map(|(file_path, file, _, _)| PatchedFile {
+                file_path: file_path.clone(),
+                hunks: file.hunks.len(),
+                lines_added: file.hunks.iter().map(|hunk| hunk.added).sum(),
+                lines_removed: file.hunks.iter().map(|hunk| hunk.removed).sum(),
+            })
+            .collect();
+        if param.dry_run {
+            return Ok(ApplyPatchResult {
+                dry_run: true,
+                files,
+                apply_id: None,
+            });
+        }
+
+        let mut written = Vec::new();
+        for (file_path, _, original, content) in patched {
+            tokio::fs::write(&file_path, content).await.map_err(|e| {
+                ServiceError::FileIoError {
+                    message: e.to_string(),
+                    path: file_path.clone(),
+                }
+            })?;
+            written.push((file_path, original));
+        }
+        let change = AppliedChange {
+            apply_id: String::new(),
+            applied_at: 0,
+            path_pattern: String::new(),
+            pattern: String::new(),
+            replacement: String::new(),
+            language: String::new(),
+            files: Vec::new(),
+            rolled_back: false,
+        };
+        let apply_id = match record_applied_change(&self.config.undo_directory, change, written) {
+            Ok(apply_id) => Some(apply_id),
+            Err(e) => {
+                tracing::warn!("Failed to record the patch for rollback: {e}");
+                None
+            }
+        };
+        Ok(ApplyPatchResult {
+            dry_run: false,
+            files,
+            apply_id,
+        })
+    }
+
+    /// Apply `param.replace` and run the configured verification command named by `param.command`,
+    /// restoring the rewritten files if it fails and `rollback_on_failure` is set
+    pub async fn verify_refactoring(
+        &self,
+        param: VerifyRefactoringParam,
+    ) -> Result<VerifyRefactoringResult, ServiceError> {
+        let command = self
+            .config
+            .verify_commands
+            .get(&param.command)
+            .ok_or_else(|| {
+                ServiceError::Internal(match self.config.verify_commands.is_empty() {
+                    true => format!(
+                        "Unknown verification command '{}': none are configured (see --verify-command)",
+                        param.command
+                    ),
+                    false => format!(
+                        "Unknown verification command '{}'; configured commands: {}",
+                        param.command,
+                        self.config
+                            .verify_commands
+                            .keys()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                })
+            })?;
+        let working_dir = self
+            .config
+            .workspace_roots(param.replace.workspace.as_deref())?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ServiceError::Internal("No root directory configured".to_string()))?;
+
+        let mut written = Vec::new();
+        let replace = self
+            .apply_file_replace(
+                FileReplaceParam {
+                    dry_run: false,
+                    ..param.replace
+                },
+                &mut written,
+            )
+            .await?;
+
+        let verification = run_verification(
+            &param.command,
+            command,
+            &working_dir,
+            Duration::from_millis(param.timeout_ms),
+            param.tail_lines,
+        )
+        .await;
+
+        let mut restored_files = Vec::new();
+        let rolled_back = !verification.passed && param.rollback_on_failure;
+        if rolled_back {
+            for (file_path, original) in written {
+                tokio::fs::write(&file_path, original).await.map_err(|e| {
+                    ServiceError::FileIoError {
+                        message: format!("rollback failed: {e}"),
+                        path: file_path.clone(),
+                    }
+                })?;
+                restored_files.push(file_path);
+            }
+        }
+
+        Ok(VerifyRefactoringResult {
+            replace,
+            verification,
+            rolled_back,
+            restored_files,
+        })
+    }
+
+    /// [`Self::file_replace`], recording the path and previous content of each file written
+    async fn apply_file_replace(
+        &self,
+        param: FileReplaceParam,
+        written: &mut Vec<(String, String)>,
+    ) -> Result<FileReplaceResult, ServiceError> {
+        if let Some(ref cursor) = param.cursor
+            && cursor.is_complete
+        {
+            return Ok(FileReplaceResult {
+                file_results: vec![],
+                summary_results: vec![],
+                next_cursor: Some(CursorResult {
+                    last_file_path: String::new(),
+                    is_complete: true,
+                    token: None,
+                }),
+                total_files_found: 0,
+                dry_run: param.dry_run,
+                total_changes: 0,
+                files_with_changes: 0,
+                drifted_files: vec![],
+                fix_safety: None,
+                unsafe_fixes: vec![],
+                rule_stats: vec![],
+                apply_id: None,
+                operation_id: None,
+                timed_out: false,
+                warnings: Vec::new(),
+            });
+        }
+
+        check_broad_pattern(&param.pattern, param.context.as_deref(), param.allow_broad)?;
+        let lang = match is_auto_language(&param.language) {
+            true => None,
+            false => Some(
+                AnyLanguage::from_str(&param.language)
+                    .map_err(|_| ServiceError::Internal("Failed to parse language".to_string()))?,
+            ),
+        };
 
         let mut files_with_changes = 0;
         let mut total_changes = 0;
         let mut summary_results = Vec::new();
+        let mut dry_run_diffs = Vec::new();
+
+        let overlays = Overlays::resolve(&param.overlays, &self.config.root_directories);
+        let drifted = if param.dry_run {
+            Vec::new()
+        } else {
+            self.find_drifted_files(&param.expected_hashes, param.force, &overlays)
+                .await?
+        };
+        let mut drifted_files = Vec::new();
+        for (file_path, expected_hash, current) in &drifted {
+            let (drifted_file, merged_changes) = self
+                .merge_drifted_file(
+                    file_path,
+                    expected_hash,
+                    current,
+                    &param,
+                    file_language(lang, file_path, current)?,
+                )
+                .await?;
+            if let Some(changes) = merged_changes {
+                files_with_changes += 1;
+                total_changes += changes;
+            }
+            if drifted_file.written {
+                written.push((file_path.clone(), current.clone()));
+            }
+            drifted_files.push(drifted_file);
+        }
 
         // Use search service to find files that match the pattern
         let search_param = FileSearchParam {
             path_pattern: param.path_pattern.clone(),
             pattern: param.pattern.clone(),
             language: param.language.clone(),
+            dialect: None,
             max_results: param.max_results,
             max_file_size: param.max_file_size,
             cursor: param.cursor.clone(),
@@ -118,114 +611,399 @@ impl ReplaceService {
             context_before: None,
             context_after: None,
             context_lines: None,
+            output_file: None,
+            overlays: param.overlays.clone(),
+            language_patterns: Default::default(),
+            search_archives: false,
+            workspace: param.workspace.clone(),
+            regex_fallback: false,
+            include_ast_context: false,
+            allow_broad: param.allow_broad,
+            revision: None,
+            suggest_fixes: false,
+            walk: param.walk.clone(),
+            stream: false,
+            context_scope: None,
+            include_leading_trivia: false,
+            timeout_ms: param.timeout_ms,
         };
 
+        let stop = self.search_service.stop_signal(param.timeout_ms);
         let search_results = self.search_service.file_search(search_param).await?;
+        let mut next_cursor = search_results.next_cursor;
+        let mut timed_out = search_results.timed_out;
+        let mut last_processed: Option<String> = None;
+
+        // Parsing and matching are CPU-bound, so files are rewritten on the blocking pool,
+        // `cpu_concurrency` at a time, and taken in path order below
+        let shared = Arc::new(param.clone());
+        let mut rewrites = futures::stream::iter(
+            search_results
+                .matches
+                .into_iter()
+                .filter(|file| !drifted.iter().any(|(path, _, _)| *path == file.file_path))
+                .map(|file| {
+                    let overlay = overlays.get(&file.file_path).map(str::to_string);
+                    let pattern_matcher = self.pattern_matcher.clone();
+                    let param = shared.clone();
+                    let file_path = file.file_path.clone();
+                    let task = tokio::task::spawn_blocking(move || {
+                        rewrite_file(&pattern_matcher, &param, lang, &file_path, overlay)
+                    });
+                    async move { (file, task.await) }
+                }),
+        )
+        .buffered(self.config.cpu_concurrency.max(1));
 
-        for file_match_result in search_results.matches {
+        while let Some((file_match_result, rewrite)) = rewrites.next().await {
+            if stop.should_stop() {
+                timed_out = true;
+                next_cursor = Some(CursorResult {
+                    last_file_path: last_processed
+                        .or_else(|| param.cursor.as_ref().map(|c| c.last_file_path.clone()))
+                        .unwrap_or_default(),
+                    is_complete: false,
+                    token: None,
+                });
+                break;
+            }
             let file_path = file_match_result.file_path;
-            let original_content = tokio::fs::read_to_string(&file_path).await.map_err(|e| {
-                ServiceError::FileIoError {
-                    message: e.to_string(),
-                    path: file_path.clone(),
+            let workspace = file_match_result.workspace;
+            let overlay = overlays.get(&file_path);
+            let FileRewrite {
+                original_content,
+                lang,
+                mut new_code,
+                changes,
+                mut anchored_edits,
+            } = rewrite
+                .map_err(|e| ServiceError::Internal(format!("Replace task failed: {e}")))??;
+            let (mut formatted, mut format_error) = (false, None);
+            if param.format_after_replace && new_code != original_content {
+                match self
+                    .format_rewrite(lang, &file_path, &original_content, &new_code)
+                    .await
+                {
+                    Ok(code) => (new_code, formatted) = (code, true),
+                    Err(reason) => format_error = Some(reason),
                 }
-            })?;
+            }
 
-            let new_code = self.pattern_matcher.replace_with_options(
+            if is_rewrite(
                 &original_content,
-                &param.pattern,
-                &param.replacement,
-                lang,
-                param.selector.as_deref(),
-                param.context.as_deref(),
-            )?;
-            println!("Original content: '{original_content}'");
-            println!("Pattern: '{}'", param.pattern);
-            println!("Replacement: '{}'", param.replacement);
-            println!("New code: '{new_code}'");
-
-            if new_code != original_content {
-                println!("Original content: {original_content}");
-                println!("New code: {new_code}");
+                &new_code,
+                param.ignore_whitespace_changes,
+            ) {
+                // Files are rewritten as a whole, so stop before one that would push
+                // us past the change budget and let the caller resume from there
+                if total_changes > 0 && total_changes + changes.len() > param.max_results {
+                    next_cursor = last_processed.map(|last_file_path| CursorResult {
+                        last_file_path,
+                        is_complete: false,
+                        token: None,
+                    });
+                    break;
+                }
                 files_with_changes += 1;
-                // Calculate changes for summary
-                let changes = self.pattern_matcher.search_with_options(
-                    &original_content,
-                    &param.pattern,
-                    lang,
-                    param.selector.as_deref(),
-                    param.context.as_deref(),
-                )?;
                 total_changes += changes.len();
 
-                let sample_changes: Vec<ChangeResult> = changes
-                    .clone()
-                    .into_iter()
-                    .take(param.max_samples)
-                    .map(|m| ChangeResult {
-                        start_line: m.start_line,
-                        end_line: m.end_line,
-                        start_col: m.start_col,
-                        end_col: m.end_col,
-                        old_text: m.text,
-                        new_text: param.replacement.clone(), // Simplified for now
-                    })
-                    .collect();
+                let json_patch = match param.json_patch {
+                    true => Some(
+                        document_patch(lang, &original_content, &new_code)
+                            .map_err(|e| ServiceError::Internal(format!("{file_path}: {e}")))?,
+                    ),
+                    false => None,
+                };
+                let unified_diff = match param.output {
+                    ReplaceOutput::UnifiedDiff => Some(file_diff(
+                        &file_path,
+                        &original_content,
+                        &new_code,
+                        param.diff_context,
+                    )),
+                    ReplaceOutput::Changes => None,
+                };
+                let max_samples = match (&json_patch, &unified_diff) {
+                    (None, None) => param.max_samples,
+                    _ => 0,
+                };
+                // Anchored and formatted rewrites need the real edits for their samples too, and
+                // dry runs keep them for get_file_diff
+                let real_edits = match overlay.is_some()
+                    || param.verify_formatting
+                    || param.rewrite_selector.is_some()
+                    || param.dry_run
+                {
+                    true if formatted => Some(line_edits(&original_content, &new_code)),
+                    true => anchored_edits.take(),
+                    false => None,
+                };
+                let sample_changes: Vec<ChangeResult> = match &real_edits {
+                    Some(edits) if param.rewrite_selector.is_some() || formatted => {
+                        edits.iter().take(max_samples).cloned().collect()
+                    }
+                    _ => changes
+                        .clone()
+                        .into_iter()
+                        .take(max_samples)
+                        // Simplified for now
+                        .map(|m| match_change(&original_content, m, param.replacement.clone()))
+                        .collect(),
+                };
+
+                let format_check = match &real_edits {
+                    Some(edits) if param.verify_formatting => Some(
+                        self.format_check(lang, &file_path, &original_content, &new_code, edits)
+                            .await,
+                    ),
+                    _ => None,
+                };
+                let file_hash = content_hash(&original_content);
+                if param.dry_run {
+                    self.dry_run_snapshots
+                        .lock()
+                        .unwrap()
+                        .put(file_hash.clone(), original_content.clone());
+                    if let Some(edits) = &real_edits {
+                        dry_run_diffs.push(FileDiffResult {
+                            file_path: file_path.clone(),
+                            file_size_bytes: original_content.len() as u64,
+                            changes: edits.clone(),
+                            total_changes: edits.len(),
+                            file_hash: file_hash.clone(),
+                            workspace: workspace.clone(),
+                        });
+                    }
+                }
+                // Overlay buffers belong to the client, which applies the edits itself
+                let edits = real_edits.filter(|_| overlay.is_some());
 
                 summary_results.push(FileSummaryResult {
                     file_path: file_path.clone(),
                     file_size_bytes: original_content.len() as u64,
                     total_changes: changes.len(),
                     lines_changed: 0, // TODO: Calculate actual lines changed
-                    file_hash: "".to_string(), // TODO: Calculate file hash
+                    file_hash,
                     sample_changes,
+                    edits,
+                    format_check,
+                    format_error,
+                    json_patch,
+                    unified_diff,
+                    workspace,
                 });
 
-                if !param.dry_run {
+                if !param.dry_run && overlay.is_none() {
                     tokio::fs::write(&file_path, new_code).await.map_err(|e| {
                         ServiceError::FileIoError {
                             message: e.to_string(),
                             path: file_path.clone(),
                         }
                     })?;
+                    written.push((file_path.clone(), original_content));
                 }
             }
+            last_processed = Some(file_path);
         }
 
         Ok(FileReplaceResult {
             file_results: vec![], // Not used when summary_only is true
             summary_results,
-            next_cursor: search_results.next_cursor,
+            next_cursor,
             total_files_found: search_results.total_files_found,
             dry_run: param.dry_run,
             total_changes,
             files_with_changes,
+            drifted_files,
+            fix_safety: None,
+            unsafe_fixes: vec![],
+            rule_stats: vec![],
+            apply_id: None,
+            operation_id: self.cache_dry_run(dry_run_diffs),
+            timed_out,
+            warnings: self.config.overlapping_root_warnings(param.workspace.as_deref()),
         })
     }
 
+    /// Format the rewritten regions with the configured formatter for `lang`; see
+    /// [`format_rewritten`]
+    async fn format_rewrite(
+        &self,
+        lang: AnyLanguage,
+        file_name: &str,
+        original: &str,
+        rewritten: &str,
+    ) -> Result<String, String> {
+        let command = match lang {
+            AnyLanguage::Builtin(lang) => formatter_for(&self.config.formatters, lang),
+            AnyLanguage::Extra(_) | AnyLanguage::Dynamic(_) => None,
+        };
+        match command {
+            Some(command) => format_rewritten(&command, file_name, original, rewritten).await,
+            None => Err(format!("no formatter is configured for {lang}")),
+        }
+    }
+
+    /// Check `rewritten` with the configured formatter for `lang`; see [`verify_formatting`]
+    async fn format_check(
+        &self,
+        lang: AnyLanguage,
+        file_name: &str,
+        original: &str,
+        rewritten: &str,
+        edits: &[ChangeResult],
+    ) -> FormatCheck {
+        let command = match lang {
+            AnyLanguage::Builtin(lang) => formatter_for(&self.config.formatters, lang),
+            AnyLanguage::Extra(_) | AnyLanguage::Dynamic(_) => None,
+        };
+        match command {
+            Some(command) => {
+                verify_formatting(&command, file_name, original, rewritten, edits).await
+            }
+            None => FormatCheck {
+                formatter: String::new(),
+                status: FormatCheckStatus::Skipped,
+                issues: vec![format!("no formatter is configured for {lang}")],
+            },
+        }
+    }
+
+    /// Files in `expected_hashes` whose content no longer matches the hash seen by the dry run,
+    /// with their current content. Fails if any drifted and `force` is not set.
+    async fn find_drifted_files(
+        &self,
+        expected_hashes: &HashMap<String, String>,
+        force: bool,
+        overlays: &Overlays,
+    ) -> Result<Vec<(String, String, String)>, ServiceError> {
+        let mut drifted = Vec::new();
+        for (file_path, expected_hash) in expected_hashes {
+            // Overlay files are never written, so there is nothing to protect
+            if overlays.get(file_path).is_some() {
+                continue;
+            }
+            let current = tokio::fs::read_to_string(file_path).await.map_err(|e| {
+                ServiceError::FileIoError {
+                    message: e.to_string(),
+                    path: file_path.clone(),
+                }
+            })?;
+            if content_hash(&current) != *expected_hash {
+                drifted.push((file_path.clone(), expected_hash.clone(), current));
+            }
+        }
+        drifted.sort();
+
+        if !drifted.is_empty() && !force {
+            let paths: Vec<&str> = drifted.iter().map(|(path, _, _)| path.as_str()).collect();
+            return Err(ServiceError::Internal(format!(
+                "Files changed since the dry run: {}. Rerun the dry run, or set force=true to merge the replacement into the current content",
+                paths.join(", ")
+            )));
+        }
+        Ok(drifted)
+    }
+
+    /// Re-run the replacement on the content the dry run saw and three-way merge it into the
+    /// file's current content. Returns the number of replacements merged, or `None` when the
+    /// dry-run content is no longer cached and the file was left alone.
+    async fn merge_drifted_file(
+        &self,
+        file_path: &str,
+        expected_hash: &str,
+        current: &str,
+        param: &FileReplaceParam,
+        lang: AnyLanguage,
+    ) -> Result<(DriftedFile, Option<usize>), ServiceError> {
+        let mut drifted_file = DriftedFile {
+            file_path: file_path.to_string(),
+            expected_hash: expected_hash.to_string(),
+            actual_hash: content_hash(current),
+            conflicts: 0,
+            written: false,
+        };
+        let snapshot = self
+            .dry_run_snapshots
+            .lock()
+            .unwrap()
+            .get(expected_hash)
+            .cloned();
+        let Some(original) = snapshot else {
+            return Ok((drifted_file, None));
+        };
+
+        let mut replaced = self.pattern_matcher.replace_anchored(
+            &original,
+            &param.pattern,
+            &param.replacement,
+            lang,
+            param.selector.as_deref(),
+            param.context.as_deref(),
+            param.rewrite_selector.as_deref(),
+        )?;
+        let changes = self
+            .pattern_matcher
+            .search_with_options(
+                &original,
+                &param.pattern,
+                lang,
+                param.selector.as_deref(),
+                param.context.as_deref(),
+            )?
+            .len();
+        if param.format_after_replace
+            && let Ok(formatted) = self
+                .format_rewrite(lang, file_path, &original, &replaced)
+                .await
+        {
+            replaced = formatted;
+        }
+
+        let outcome = merge3(&original, current, &replaced);
+        tokio::fs::write(file_path, &outcome.text)
+            .await
+            .map_err(|e| ServiceError::FileIoError {
+                message: e.to_string(),
+                path: file_path.to_string(),
+            })?;
+        drifted_file.conflicts = outcome.conflicts;
+        drifted_file.written = true;
+        Ok((drifted_file, Some(changes)))
+    }
+
     pub async fn rule_replace(
         &self,
         param: RuleReplaceParam,
     ) -> Result<FileReplaceResult, ServiceError> {
         // Check if cursor indicates completion
-        if let Some(ref cursor) = param.cursor {
-            if cursor.is_complete {
-                return Ok(FileReplaceResult {
-                    file_results: vec![],
-                    summary_results: vec![],
-                    next_cursor: Some(CursorResult {
-                        last_file_path: String::new(),
-                        is_complete: true,
-                    }),
-                    total_files_found: 0,
-                    dry_run: param.dry_run,
-                    total_changes: 0,
-                    files_with_changes: 0,
-                });
-            }
+        if let Some(ref cursor) = param.cursor
+            && cursor.is_complete
+        {
+            return Ok(FileReplaceResult {
+                file_results: vec![],
+                summary_results: vec![],
+                next_cursor: Some(CursorResult {
+                    last_file_path: String::new(),
+                    is_complete: true,
+                    token: None,
+                }),
+                total_files_found: 0,
+                dry_run: param.dry_run,
+                total_changes: 0,
+                files_with_changes: 0,
+                drifted_files: vec![],
+                fix_safety: None,
+                unsafe_fixes: vec![],
+                rule_stats: vec![],
+                apply_id: None,
+                operation_id: None,
+                timed_out: false,
+                warnings: Vec::new(),
+            });
         }
 
-        let rule = parse_rule_config(&param.rule_config)?;
+        let rule = self.config.parse_rule_config(&param.rule_config)?;
 
         if rule.fix.is_none() {
             return Err(ServiceError::ParserError(
@@ -233,9 +1011,19 @@ impl ReplaceService {
             ));
         }
 
-        let fix_template = rule.fix.unwrap();
-        let lang = Language::from_str(&rule.language)
+        let fix_safety = rule.fix_safety.unwrap_or_default();
+        let hold_back = fix_safety == FixSafety::Unsafe && !param.allow_unsafe;
+        if hold_back && !param.dry_run {
+            return Err(ServiceError::Internal(format!(
+                "Rule '{}' declares fix_safety: unsafe; review its dry run and set allow_unsafe=true to apply it",
+                rule.id
+            )));
+        }
+
+        let fix_template = ReplacementTemplate::parse(rule.fix.as_deref().unwrap_or_default())?;
+        let rule_lang = Language::from_str(&rule.language)
             .map_err(|_| ServiceError::Internal("Failed to parse language".to_string()))?;
+        check_transforms(&rule)?;
 
         // Use path pattern or default to all files
         let path_pattern = param.path_pattern.unwrap_or_else(|| "**/*".to_string());
@@ -247,64 +1035,82 @@ impl ReplaceService {
             max_results: param.max_results,
             max_file_size: param.max_file_size,
             cursor: param.cursor.clone(),
+            output_file: None,
+            workspace: param.workspace.clone(),
+            timeout_ms: param.timeout_ms,
+            max_matches: None,
+            output_format: Default::default(),
+            walk: param.walk.clone(),
+            pack: None,
         };
 
         let search_result = self.search_service.rule_search(rule_search_param).await?;
         let next_cursor = search_result.next_cursor;
         let total_files_found = search_result.total_files_found;
+        let timed_out = search_result.timed_out;
 
         let mut file_results = Vec::new();
         let mut summary_results = Vec::new();
         let mut total_changes = 0;
         let mut files_with_changes = 0;
+        let mut unsafe_fixes = Vec::new();
+        let mut dry_run_diffs = Vec::new();
+        let mut rule_stats = RuleFixStats {
+            rule_id: rule.id.clone(),
+            matches: 0,
+            total_changes: 0,
+            files: BTreeMap::new(),
+            unfixed_matches: 0,
+        };
 
         for file_match_result in search_result.matches {
             let file_path = file_match_result.file_path;
+            let workspace = file_match_result.workspace;
             let original_content = match tokio::fs::read_to_string(&file_path).await {
                 Ok(content) => content,
                 Err(_) => continue,
             };
 
             // Use the matches already found by rule_search
-            let matches = file_match_result.matches;
+            let mut matches = file_match_result.matches;
 
             if matches.is_empty() {
                 continue;
             }
+            rule_stats.matches += matches.len();
+            apply_transforms(
+                &self.rule_evaluator,
+                &rule,
+                &original_content,
+                language_for_file(rule_lang, &file_path),
+                &mut matches,
+            )?;
 
-            // Use ast-grep's built-in replacement logic
-            let new_content =
-                self.apply_rule_replacement(&original_content, &matches, &fix_template, lang)?;
+            let (new_content, changes) = apply_rule_fix(&original_content, &matches, &fix_template);
 
-            if new_content != original_content {
-                files_with_changes += 1;
+            if is_rewrite(
+                &original_content,
+                &new_content,
+                param.ignore_whitespace_changes,
+            ) {
                 let file_size = original_content.len() as u64;
 
-                // Create changes from matches
-                let changes: Vec<ChangeResult> = matches
-                    .iter()
-                    .map(|m| {
-                        // Apply simple template substitution for the display
-                        let replacement_text =
-                            if fix_template.contains("logger") && m.text.contains("console.") {
-                                // For console.log -> logger.log transformations
-                                m.text.replace("console.", "logger.")
-                            } else {
-                                fix_template.clone()
-                            };
-
-                        ChangeResult {
-                            start_line: m.start_line,
-                            end_line: m.end_line,
-                            start_col: m.start_col,
-                            end_col: m.end_col,
-                            old_text: m.text.clone(),
-                            new_text: replacement_text,
-                        }
-                    })
-                    .collect();
+                // Unsafe fixes are listed on their own and never count as pending changes
+                if hold_back {
+                    unsafe_fixes.push(FileDiffResult {
+                        file_path,
+                        file_size_bytes: file_size,
+                        total_changes: changes.len(),
+                        changes,
+                        file_hash: content_hash(&original_content),
+                        workspace,
+                    });
+                    continue;
+                }
 
+                files_with_changes += 1;
                 total_changes += changes.len();
+                rule_stats.files.insert(file_path.clone(), changes.len());
 
                 // Write file if not dry run
                 if !param.dry_run {
@@ -316,6 +1122,17 @@ impl ReplaceService {
                         })?;
                 }
 
+                if param.dry_run {
+                    dry_run_diffs.push(FileDiffResult {
+                        file_path: file_path.clone(),
+                        file_size_bytes: file_size,
+                        changes: changes.clone(),
+                        total_changes: changes.len(),
+                        file_hash: content_hash(&original_content),
+                        workspace: workspace.clone(),
+                    });
+                }
+
                 // Determine which results to include based on summary_only
                 if param.summary_only {
                     summary_results.push(FileSummaryResult {
@@ -323,11 +1140,14 @@ impl ReplaceService {
                         file_size_bytes: file_size,
                         total_changes: changes.len(),
                         lines_changed: changes.len(), // Simplified calculation
-                        file_hash: format!(
-                            "sha256:{}",
-                            hex::encode(Sha256::digest(original_content.as_bytes()))
-                        ),
+                        file_hash: content_hash(&original_content),
                         sample_changes: changes,
+                        edits: None,
+                        format_check: None,
+                        format_error: None,
+                        json_patch: None,
+                        unified_diff: None,
+                        workspace,
                     });
                 } else {
                     file_results.push(FileDiffResult {
@@ -335,10 +1155,8 @@ impl ReplaceService {
                         file_size_bytes: file_size,
                         changes,
                         total_changes: matches.len(),
-                        file_hash: format!(
-                            "sha256:{}",
-                            hex::encode(Sha256::digest(original_content.as_bytes()))
-                        ),
+                        file_hash: content_hash(&original_content),
+                        workspace,
                     });
                 }
             }
@@ -352,43 +1170,285 @@ impl ReplaceService {
             dry_run: param.dry_run,
             total_changes,
             files_with_changes,
+            drifted_files: vec![],
+            fix_safety: Some(fix_safety),
+            unsafe_fixes,
+            rule_stats: vec![RuleFixStats {
+                total_changes,
+                unfixed_matches: rule_stats.matches - total_changes,
+                ..rule_stats
+            }],
+            apply_id: None,
+            operation_id: self.cache_dry_run(dry_run_diffs),
+            timed_out,
+            warnings: self.config.overlapping_root_warnings(param.workspace.as_deref()),
         })
     }
+}
 
-    /// Apply rule-based replacement using ast-grep's built-in functionality
-    fn apply_rule_replacement(
-        &self,
-        content: &str,
-        _matches: &[MatchResult],
-        fix_template: &str,
-        lang: Language,
-    ) -> Result<String, ServiceError> {
-        // Create a pattern from the rule that was already evaluated
-        // For the console.log -> logger.log rule, we'll use the pattern directly
-        if fix_template.contains("logger.") {
-            let pattern = "console.$METHOD($ARGS)";
-            let replacement = "logger.$METHOD($ARGS)";
-
-            match self.pattern_matcher.replace_with_options(
-                content,
-                pattern,
-                replacement,
+/// A file as `file_replace` found and rewrote it, before formatting
+struct FileRewrite {
+    original_content: String,
+    lang: AnyLanguage,
+    new_code: String,
+    /// Matches of the pattern; empty when the rewrite leaves the file unchanged
+    changes: Vec<MatchResult>,
+    /// Edits of the rewrite, when it changes the file and the edits are reported
+    anchored_edits: Option<Vec<ChangeResult>>,
+}
+
+/// Read `file_path`, or take its `overlay`, and rewrite it with `param`. Runs on the blocking
+/// pool, as parsing and matching dominate a large `file_replace`
+fn rewrite_file(
+    pattern_matcher: &PatternMatcher,
+    param: &FileReplaceParam,
+    lang: Option<AnyLanguage>,
+    file_path: &str,
+    overlay: Option<String>,
+) -> Result<FileRewrite, ServiceError> {
+    let reports_edits = overlay.is_some()
+        || param.verify_formatting
+        || param.rewrite_selector.is_some()
+        || param.dry_run;
+    let original_content = match overlay {
+        Some(content) => content,
+        None => std::fs::read_to_string(file_path).map_err(|e| ServiceError::FileIoError {
+            message: e.to_string(),
+            path: file_path.to_string(),
+        })?,
+    };
+    let lang = file_language(lang, file_path, &original_content)?;
+
+    let new_code = pattern_matcher.replace_anchored(
+        &original_content,
+        &param.pattern,
+        &param.replacement,
+        lang,
+        param.selector.as_deref(),
+        param.context.as_deref(),
+        param.rewrite_selector.as_deref(),
+    )?;
+    let (mut changes, mut anchored_edits) = (Vec::new(), None);
+    if new_code != original_content {
+        changes = pattern_matcher.search_with_options(
+            &original_content,
+            &param.pattern,
+            lang,
+            param.selector.as_deref(),
+            param.context.as_deref(),
+        )?;
+        if reports_edits {
+            anchored_edits = Some(pattern_matcher.replacement_changes_anchored(
+                &original_content,
+                &param.pattern,
+                &param.replacement,
                 lang,
-                None,
-                None,
-            ) {
-                Ok(result) => Ok(result),
-                Err(e) => {
-                    eprintln!("Pattern replace failed: {e:?}");
-                    // Fallback to no replacement
-                    Ok(content.to_string())
-                }
-            }
-        } else {
-            // For other templates, use simple replacement for now
-            // TODO: Implement proper template substitution
-            Ok(content.to_string())
+                param.selector.as_deref(),
+                param.context.as_deref(),
+                param.rewrite_selector.as_deref(),
+            )?);
         }
     }
+    Ok(FileRewrite {
+        original_content,
+        lang,
+        new_code,
+        changes,
+        anchored_edits,
+    })
 }
 
+/// The grammar to rewrite `file_path` with: `lang` as it applies to the file, or the language
+/// detected for it when the caller asked for `auto`
+fn file_language(
+    lang: Option<AnyLanguage>,
+    file_path: &str,
+    content: &str,
+) -> Result<AnyLanguage, ServiceError> {
+    match lang {
+        Some(lang) => Ok(lang.for_file(file_path)),
+        None => detect_language(file_path, content).ok_or_else(|| {
+            ServiceError::Internal(format!("Could not detect the language of {file_path}"))
+        }),
+    }
+}
+
+/// What the undo directory keeps about one applied `file_replace`
+#[derive(Debug, Serialize, Deserialize)]
+struct UndoRecord {
+    #[serde(flatten)]
+    change: AppliedChange,
+    originals: Vec<UndoFile>,
+}
+
+/// A file written by an applied `file_replace`
+#[derive(Debug, Serialize, Deserialize)]
+struct UndoFile {
+    file_path: String,
+    /// Content before the replacement
+    original: String,
+    /// Hash of the content the replacement wrote, to notice later edits
+    written_hash: String,
+}
+
+/// Record the previous contents of the `written` files under a new apply id, returned
+fn record_applied_change(
+    directory: &Path,
+    change: AppliedChange,
+    written: Vec<(String, String)>,
+) -> Result<String, ServiceError> {
+    std::fs::create_dir_all(directory)?;
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let millis = now.as_millis();
+    let apply_id = (0..)
+        .map(|n| format!("{millis}-{n}"))
+        .find(|id| !undo_record_path(directory, id).exists())
+        .expect("some suffix is free");
+
+    let originals: Vec<UndoFile> = written
+        .into_iter()
+        .map(|(file_path, original)| UndoFile {
+            written_hash: std::fs::read_to_string(&file_path)
+                .map(|content| content_hash(&content))
+                .unwrap_or_default(),
+            file_path,
+            original,
+        })
+        .collect();
+    let record = UndoRecord {
+        change: AppliedChange {
+            apply_id: apply_id.clone(),
+            applied_at: now.as_secs(),
+            files: originals
+                .iter()
+                .map(|file| file.file_path.clone())
+                .collect(),
+            ..change
+        },
+        originals,
+    };
+    save_undo_record(directory, &record)?;
+    Ok(apply_id)
+}
+
+fn load_undo_record(directory: &Path, apply_id: &str) -> Result<UndoRecord, ServiceError> {
+    // Ids name files in the undo directory, so anything else could reach outside it
+    let valid = !apply_id.is_empty() && apply_id.chars().all(|c| c.is_ascii_digit() || c == '-');
+    let path = undo_record_path(directory, apply_id);
+    if !valid || !path.is_file() {
+        return Err(ServiceError::Internal(format!(
+            "No applied change with id '{apply_id}'; see list_applied_changes"
+        )));
+    }
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+fn save_undo_record(directory: &Path, record: &UndoRecord) -> Result<(), ServiceError> {
+    let path = undo_record_path(directory, &record.change.apply_id);
+    std::fs::write(path, serde_json::to_string_pretty(record)?)?;
+    Ok(())
+}
+
+fn undo_record_path(directory: &Path, apply_id: &str) -> PathBuf {
+    directory.join(format!("{apply_id}.json"))
+}
+
+/// Apply the rendered `fix` to each match, skipping matches nested in one already fixed.
+/// Returns the new content and the changes made
+fn apply_rule_fix(
+    content: &str,
+    matches: &[MatchResult],
+    fix: &ReplacementTemplate,
+) -> (String, Vec<ChangeResult>) {
+    let mut located: Vec<(usize, &MatchResult)> = matches
+        .iter()
+        .filter_map(|m| Some((byte_offset(content, m.start_line, m.start_col)?, m)))
+        .filter(|(start, m)| content[*start..].starts_with(m.text.as_str()))
+        .collect();
+    located.sort_by_key(|(start, _)| *start);
+
+    let mut new_content = String::with_capacity(content.len());
+    let mut changes = Vec::new();
+    let mut copied = 0;
+    for (start, m) in located {
+        if start < copied {
+            continue;
+        }
+        let new_text = fix.render(&m.vars);
+        new_content.push_str(&content[copied..start]);
+        new_content.push_str(&new_text);
+        copied = start + m.text.len();
+        changes.push(ChangeResult {
+            start_line: m.start_line,
+            end_line: m.end_line,
+            start_col: m.start_col,
+            end_col: m.end_col,
+            start_byte: start,
+            end_byte: copied,
+            old_text: m.text.clone(),
+            new_text,
+        });
+    }
+    new_content.push_str(&content[copied..]);
+    (new_content, changes)
+}
+
+/// `content` with the rule `fix` applied to `matches`, and the whole-line hunks turning the
+/// one into the other, for previewing a fix without touching any file
+pub(crate) fn preview_rule_fix(
+    content: &str,
+    matches: &[MatchResult],
+    fix: &str,
+) -> Result<(String, Vec<ChangeResult>), ServiceError> {
+    let template = ReplacementTemplate::parse(fix)?;
+    let (fixed, _) = apply_rule_fix(content, matches, &template);
+    let hunks = line_edits(content, &fixed);
+    Ok((fixed, hunks))
+}
+
+/// The change replacing match `m` of `content` with `new_text`
+fn match_change(content: &str, m: MatchResult, new_text: String) -> ChangeResult {
+    let start_byte = byte_offset(content, m.start_line, m.start_col).unwrap_or_default();
+    ChangeResult {
+        start_line: m.start_line,
+        end_line: m.end_line,
+        start_col: m.start_col,
+        end_col: m.end_col,
+        start_byte,
+        end_byte: start_byte + m.text.len(),
+        old_text: m.text,
+        new_text,
+    }
+}
+
+/// Byte offset of a 0-based line and character column
+pub(crate) fn byte_offset(content: &str, line: usize, col: usize) -> Option<usize> {
+    let line_start = match line {
+        0 => 0,
+        _ => content.match_indices('\n').nth(line - 1)?.0 + 1,
+    };
+    let line_text = &content[line_start..];
+    let col_offset = line_text
+        .char_indices()
+        .map(|(offset, _)| offset)
+        .chain(std::iter::once(line_text.len()))
+        .nth(col)?;
+    Some(line_start + col_offset)
+}
+
+/// Whether `rewritten` differs from `original`; with `ignore_whitespace`, differences in
+/// trailing whitespace, trailing blank lines and line endings don't count
+fn is_rewrite(original: &str, rewritten: &str, ignore_whitespace: bool) -> bool {
+    if original == rewritten {
+        return false;
+    }
+    let normalized = |text: &'_ str| -> Vec<String> {
+        text.trim_end()
+            .lines()
+            .map(|line| line.trim_end().to_string())
+            .collect()
+    };
+    !ignore_whitespace || normalized(original) != normalized(rewritten)
+}
@@ -0,0 +1,139 @@
+//! # MCP Resources
+//!
+//! Besides tools, the server publishes read-only resources that a client can browse with
+//! `resources/list` and `resources/read` without calling a tool:
+//!
+//! - `rules://{id}`: each stored rule, as YAML
+//! - `docs://tool-usage-guide`: the tool usage guide, as Markdown
+//! - `results://latest`: the JSON of the most recent search or replace in this session
+//!
+//! The latest result is kept per session, like code buffers, so a client only sees its own.
+
+use rmcp::model::{AnnotateAble, CallToolResult, RawResource, Resource};
+use std::sync::{Arc, Mutex};
+
+/// Scheme of the stored rule resources, followed by the rule id
+pub const RULE_SCHEME: &str = "rules://";
+
+/// URI of the tool usage guide
+pub const TOOL_USAGE_GUIDE_URI: &str = "docs://tool-usage-guide";
+
+/// URI of the most recent search or replace result
+pub const LATEST_RESULT_URI: &str = "results://latest";
+
+/// The tool usage guide, as shipped with the server
+pub const TOOL_USAGE_GUIDE: &str = include_str!("../TOOL_USAGE_GUIDE.md");
+
+/// Tools whose results `results://latest` holds
+pub const RESULT_TOOLS: &[&str] = &[
+    "search",
+    "file_search",
+    "rule_search",
+    "replace",
+    "file_replace",
+    "rule_replace",
+];
+
+/// A tool result recorded for `results://latest`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedResult {
+    pub tool: String,
+    pub json: String,
+}
+
+/// The most recent search or replace result of a session
+#[derive(Debug, Clone, Default)]
+pub struct LatestResult {
+    inner: Arc<Mutex<Option<RecordedResult>>>,
+}
+
+impl LatestResult {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the JSON of a successful `tool` call when it is one of [`RESULT_TOOLS`]. The JSON
+    /// is the last text content of the response; a response without one leaves the previous
+    /// result in place
+    pub fn record(&self, tool: &str, response: &CallToolResult) {
+        if !RESULT_TOOLS.contains(&tool) || response.is_error == Some(true) {
+            return;
+        }
+        let Some(json) = response
+            .content
+            .iter()
+            .rev()
+            .find_map(|content| content.as_text())
+            .map(|text| text.text.clone())
+        else {
+            return;
+        };
+        *self.inner.lock().unwrap() = Some(RecordedResult {
+            tool: tool.to_string(),
+            json,
+        });
+    }
+
+    pub fn get(&self) -> Option<RecordedResult> {
+        self.inner.lock().unwrap().clone()
+    }
+}
+
+/// The listing entry of a stored rule
+pub fn rule_resource(id: &str, message: Option<&str>) -> Resource {
+    let mut resource = RawResource::new(format!("{RULE_SCHEME}{id}"), id.to_string());
+    resource.description = message.map(str::to_string);
+    resource.mime_type = Some("application/yaml".to_string());
+    resource.no_annotation()
+}
+
+/// The listing entry of the tool usage guide
+pub fn tool_usage_guide_resource() -> Resource {
+    let mut resource = RawResource::new(TOOL_USAGE_GUIDE_URI, "Tool usage guide");
+    resource.description =
+        Some("How and when to use each tool, with pattern and rule examples".to_string());
+    resource.mime_type = Some("text/markdown".to_string());
+    resource.size = Some(TOOL_USAGE_GUIDE.len() as u32);
+    resource.no_annotation()
+}
+
+/// The listing entry of a recorded result
+pub fn latest_result_resource(result: &RecordedResult) -> Resource {
+    let mut resource = RawResource::new(LATEST_RESULT_URI, "Latest result");
+    resource.description = Some(format!("Result of the most recent {} call", result.tool));
+    resource.mime_type = Some("application/json".to_string());
+    resource.size = Some(result.json.len() as u32);
+    resource.no_annotation()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::Content;
+
+    #[test]
+    fn test_latest_result_keeps_successful_search_and_replace_results() {
+        let latest = LatestResult::new();
+        let response = CallToolResult::success(vec![
+            Content::text("summary"),
+            Content::text("{\"matches\":[]}"),
+        ]);
+        latest.record("list_languages", &response);
+        assert_eq!(latest.get(), None);
+
+        latest.record("file_search", &response);
+        assert_eq!(
+            latest.get(),
+            Some(RecordedResult {
+                tool: "file_search".to_string(),
+                json: "{\"matches\":[]}".to_string(),
+            })
+        );
+
+        latest.record(
+            "replace",
+            &CallToolResult::error(vec![Content::text("failed")]),
+        );
+        assert_eq!(latest.get().unwrap().tool, "file_search");
+    }
+}
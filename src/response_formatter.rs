@@ -1,6 +1,10 @@
+use crate::format_check::{FormatCheck, FormatCheckStatus};
+use crate::json_patch::JsonPatchOperation;
+use crate::rules::{FixSafety, LintFinding, LintThreshold, RuleConfig};
 use crate::types::*;
 use rmcp::model::{CallToolResult, Content};
 use serde_json;
+use std::path::{Path, PathBuf};
 
 pub struct ResponseFormatter;
 
@@ -35,10 +39,7 @@ impl ResponseFormatter {
         });
 
         if let Some(cursor) = &result.next_cursor {
-            minimal_json["next_cursor"] = serde_json::json!({
-                "last_file_path": cursor.last_file_path,
-                "is_complete": cursor.is_complete
-            });
+            minimal_json["next_cursor"] = serde_json::to_value(cursor)?;
         }
 
         let contents = vec![Content::text(summary), Content::json(minimal_json)?];
@@ -66,12 +67,168 @@ impl ResponseFormatter {
         Ok(CallToolResult::success(contents))
     }
 
+    /// Format the match and file counts of each search in a batch, keyed by id
+    pub fn format_batch_search_result(result: &BatchSearchResult) -> String {
+        let mut summary = format!(
+            "🔍 **Batch Search**\n\n📁 **Files searched**: {}\n",
+            result.files_searched
+        );
+        for (id, search) in &result.results {
+            match &search.error {
+                Some(error) if search.total_matches == 0 => {
+                    summary.push_str(&format!("\n❌ **{id}**: {error}"));
+                    continue;
+                }
+                _ => summary.push_str(&format!(
+                    "\n🎯 **{id}**: {} matches in {} files",
+                    search.total_matches,
+                    search.matches.len()
+                )),
+            }
+            if search.truncated {
+                summary.push_str(" (truncated, raise max_results)");
+            }
+            for file in search.matches.iter().take(5) {
+                summary.push_str(&format!(
+                    "\n   • `{}`: {} matches",
+                    file.file_path,
+                    file.matches.len()
+                ));
+            }
+            if search.matches.len() > 5 {
+                summary.push_str(&format!(
+                    "\n   … and {} more files",
+                    search.matches.len() - 5
+                ));
+            }
+        }
+        summary
+            + &Self::format_skipped_files(&result.skipped_files)
+            + &Self::format_warnings(&result.warnings)
+    }
+
+    /// A SARIF 2.1.0 log of the findings of `rule`, for GitHub code scanning and other CI
+    /// dashboards. Files under the first root directory get URIs relative to `%SRCROOT%`, which
+    /// stands for that directory; files elsewhere get absolute `file://` URIs
+    pub fn format_sarif_log(
+        result: &FileSearchResult,
+        rule: &RuleConfig,
+        root_directories: &[PathBuf],
+    ) -> serde_json::Value {
+        let level = match rule.severity.as_deref() {
+            Some("error") => "error",
+            Some("info" | "hint") => "note",
+            _ => "warning",
+        };
+        let message = rule
+            .message
+            .clone()
+            .unwrap_or_else(|| format!("Matches rule {}", rule.id));
+        let root = root_directories.first();
+
+        let mut results = Vec::new();
+        for file in &result.matches {
+            let path = Path::new(&file.file_path);
+            let artifact_location = match root.and_then(|root| path.strip_prefix(root).ok()) {
+                Some(relative) => serde_json::json!({
+                    "uri": Self::sarif_uri_path(relative),
+                    "uriBaseId": "%SRCROOT%"
+                }),
+                None => serde_json::json!({ "uri": Self::sarif_file_uri(path) }),
+            };
+            for m in &file.matches {
+                results.push(serde_json::json!({
+                    "ruleId": rule.id,
+                    "ruleIndex": 0,
+                    "level": level,
+                    "message": { "text": message },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": artifact_location,
+                            "region": {
+                                "startLine": m.start_line + 1,
+                                "startColumn": m.start_col + 1,
+                                "endLine": m.end_line + 1,
+                                "endColumn": m.end_col + 1,
+                                "snippet": { "text": m.text }
+                            }
+                        }
+                    }]
+                }));
+            }
+        }
+
+        let mut run = serde_json::json!({
+            "tool": {
+                "driver": {
+                    "name": "ast-grep-mcp",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": [{
+                        "id": rule.id,
+                        "shortDescription": { "text": message },
+                        "defaultConfiguration": { "level": level }
+                    }]
+                }
+            },
+            "results": results
+        });
+        if let Some(root) = root {
+            run["originalUriBaseIds"] = serde_json::json!({
+                "%SRCROOT%": { "uri": format!("{}/", Self::sarif_file_uri(root)) }
+            });
+        }
+        serde_json::json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [run]
+        })
+    }
+
+    /// `path` as an absolute `file://` URI
+    fn sarif_file_uri(path: &Path) -> String {
+        let path = Self::sarif_uri_path(path);
+        match path.starts_with('/') {
+            true => format!("file://{path}"),
+            false => format!("file:///{path}"),
+        }
+    }
+
+    /// `path` with `/` separators and the characters URIs reserve percent-encoded
+    fn sarif_uri_path(path: &Path) -> String {
+        let path = path.to_string_lossy().replace('\\', "/");
+        let mut encoded = String::with_capacity(path.len());
+        for byte in path.bytes() {
+            if byte.is_ascii_alphanumeric() || b"-._~/:".contains(&byte) {
+                encoded.push(byte as char);
+            } else {
+                encoded.push_str(&format!("%{byte:02X}"));
+            }
+        }
+        encoded
+    }
+
     /// Format a file search result with a readable summary
     pub fn format_file_search_result(result: &FileSearchResult) -> String {
         let total_matches: usize = result.matches.iter().map(|f| f.matches.len()).sum();
+        let skipped = Self::format_skipped_files(&result.skipped_files)
+            + &Self::format_warnings(&result.warnings);
+        let stats = result
+            .stats
+            .as_ref()
+            .map(Self::format_scan_stats)
+            .unwrap_or_default();
+        let budget = match result.budget_exceeded {
+            true => {
+                "\n\n⏱️ **Budget exceeded**: the rule ran out of time or reached its match limit, so results are partial"
+            }
+            false => "",
+        };
+        let budget = format!("{budget}{}", Self::format_timed_out(result.timed_out));
 
         if result.matches.is_empty() {
-            return "🔍 **No matches found**\n\nNo files matched the search pattern.".to_string();
+            return format!(
+                "🔍 **No matches found**\n\nNo files matched the search pattern.{stats}{budget}{skipped}"
+            );
         }
 
         let mut summary = format!(
@@ -82,10 +239,17 @@ impl ResponseFormatter {
 
         // Add file details
         for (i, file_match) in result.matches.iter().enumerate() {
+            let language = file_match
+                .language
+                .as_ref()
+                .or(file_match.engine.as_ref())
+                .map(|language| format!(" ({language})"))
+                .unwrap_or_default();
             summary.push_str(&format!(
-                "\n📄 **File {}**: `{}`\n",
+                "\n📄 **File {}**: `{}`{language}{}\n",
                 i + 1,
-                file_match.file_path
+                file_match.file_path,
+                Self::workspace_tag(&file_match.workspace)
             ));
 
             if file_match.matches.is_empty() {
@@ -99,12 +263,26 @@ impl ResponseFormatter {
                 // Show first few matches
                 for (j, match_result) in file_match.matches.iter().take(3).enumerate() {
                     summary.push_str(&format!(
-                        "   {}. **Line {}-{}**: `{}`\n",
+                        "   {}. **Line {}-{}**{}: `{}`\n",
                         j + 1,
                         match_result.start_line,
                         match_result.end_line,
+                        Self::format_enclosing_symbol(match_result),
                         match_result.text.trim()
                     ));
+                    for quick_fix in &match_result.quick_fixes {
+                        let safety = match quick_fix.fix_safety {
+                            FixSafety::Safe => "",
+                            FixSafety::Suggestion => " (suggestion)",
+                            FixSafety::Unsafe => " (unsafe)",
+                        };
+                        summary.push_str(&format!(
+                            "      🔧 `{}`{safety}: `{}` → `{}`\n",
+                            quick_fix.rule_id,
+                            quick_fix.old_text.trim(),
+                            quick_fix.new_text.trim()
+                        ));
+                    }
                 }
 
                 if file_match.matches.len() > 3 {
@@ -116,227 +294,1557 @@ impl ResponseFormatter {
             }
         }
 
-        // Add pagination info
-        if let Some(cursor) = &result.next_cursor {
-            if !cursor.is_complete {
-                summary.push_str(&format!(
-                    "\n📄 **More results available** - use cursor for pagination:\n```json\n{{\n  \"cursor\": {{\n    \"last_file_path\": \"{}\",\n    \"is_complete\": false\n  }}\n}}\n```",
-                    cursor.last_file_path
-                ));
-            } else {
-                summary.push_str("\n✅ **Search complete** - all results have been returned");
+        summary.push_str(&Self::format_pagination(&result.next_cursor));
+        summary.push_str(&stats);
+        summary.push_str(&budget);
+        summary.push_str(&skipped);
+        summary
+    }
+
+    /// Summary of a streaming file_search, whose matches went out in progress notifications
+    pub fn format_streamed_file_search_result(
+        result: &FileSearchResult,
+        streamed_files: usize,
+    ) -> String {
+        let mut summary = format!(
+            "🔍 **Search Results**\n\n📡 **Streamed**: {streamed_files} files with matches were sent as progress notifications\n"
+        );
+        summary.push_str(&Self::format_pagination(&result.next_cursor));
+        if let Some(stats) = &result.stats {
+            summary.push_str(&Self::format_scan_stats(stats));
+        }
+        summary.push_str(Self::format_timed_out(result.timed_out));
+        summary.push_str(&Self::format_skipped_files(&result.skipped_files));
+        summary
+    }
+
+    /// Note on a file search or replacement that stopped at its timeout or on cancellation
+    fn format_timed_out(timed_out: bool) -> &'static str {
+        match timed_out {
+            true => {
+                "\n\n⏱️ **Timed out**: stopped at timeout_ms or on cancellation, so results are partial; continue with next_cursor"
             }
+            false => "",
         }
+    }
 
-        summary
+    /// Where a paginated search stopped, with the cursor resuming it
+    fn format_pagination(next_cursor: &Option<CursorResult>) -> String {
+        match next_cursor {
+            Some(cursor) if !cursor.is_complete => format!(
+                "\n📄 **More results available** - use cursor for pagination:\n```json\n{{\n  \"cursor\": {}\n}}\n```",
+                serde_json::to_string_pretty(cursor)
+                    .unwrap_or_default()
+                    .replace('\n', "\n  ")
+            ),
+            Some(_) => "\n✅ **Search complete** - all results have been returned".to_string(),
+            None => String::new(),
+        }
     }
 
-    /// Format a search result with a readable summary
-    pub fn format_search_result(result: &SearchResult) -> String {
-        if result.matches.is_empty() {
-            return "🔍 **No matches found**\n\nThe pattern did not match anything in the provided code.".to_string();
+    /// Which files a file_search looked at and why others were left out
+    fn format_scan_stats(stats: &FileScanStats) -> String {
+        if stats.files_matching_glob == 0 {
+            return "\n\n📊 **Scanned**: no files match path_pattern; check the glob".to_string();
+        }
+
+        let searched = stats.files_with_matches + stats.files_without_matches;
+        let extensions: Vec<String> = stats
+            .searched_by_extension
+            .iter()
+            .map(|(extension, count)| format!("{extension} {count}"))
+            .collect();
+        let mut text = format!(
+            "\n\n📊 **Scanned**: {searched} of {} files matching the glob",
+            stats.files_matching_glob
+        );
+        if !extensions.is_empty() {
+            text.push_str(&format!(" ({})", extensions.join(", ")));
+        }
+        let skipped: Vec<String> = [
+            (stats.skipped_too_large, "over max_file_size"),
+            (stats.skipped_unreadable, "not readable as text"),
+            (stats.skipped_other_language, "in another language"),
+            (stats.skipped_worker_failures, "failed in a worker"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, reason)| format!("{count} {reason}"))
+        .collect();
+        if !skipped.is_empty() {
+            text.push_str(&format!("; skipped {}", skipped.join(", ")));
         }
+        text
+    }
+
+    /// Describe files whose isolated worker crashed or timed out, or nothing if there were none
+    fn format_skipped_files(skipped_files: &[SkippedFile]) -> String {
+        if skipped_files.is_empty() {
+            return String::new();
+        }
+
+        let mut text = format!(
+            "\n\n⚠️ **Skipped files**: {} files could not be parsed safely",
+            skipped_files.len()
+        );
+        for file in skipped_files {
+            text.push_str(&format!("\n   - `{}`: {}", file.file_path, file.reason));
+        }
+        text
+    }
+
+    fn format_warnings(warnings: &[String]) -> String {
+        warnings
+            .iter()
+            .map(|warning| format!("\n\n⚠️ **Warning**: {warning}"))
+            .collect()
+    }
 
+    /// Format the summary of a search result that was exported to a file
+    pub fn format_exported_search_result(result: &ExportedSearchResult) -> String {
         let mut summary = format!(
-            "🔍 **Search Results**\n\n🎯 **Matches**: {} found\n",
-            result.matches.len()
+            "💾 **Results Exported**\n\n📄 **Output file**: `{}` ({} bytes)\n📁 **Files**: {} files with matches ({} searched)\n🎯 **Matches**: {} total matches\n",
+            result.output_file,
+            result.bytes_written,
+            result.files_with_matches,
+            result.total_files_found,
+            result.total_matches
         );
 
-        for (i, match_result) in result.matches.iter().enumerate() {
+        if let Some(cursor) = &result.next_cursor
+            && !cursor.is_complete
+        {
             summary.push_str(&format!(
-                "\n{}. **Line {}-{}** (Col {}-{}):\n```\n{}\n```\n",
-                i + 1,
-                match_result.start_line,
-                match_result.end_line,
-                match_result.start_col,
-                match_result.end_col,
-                match_result.text.trim()
+                "\n📄 **More results available** - continue from cursor `{}` with a new output file",
+                cursor.last_file_path
             ));
-
-            // Show captured variables if any
-            if !match_result.vars.is_empty() {
-                summary.push_str("   **Variables captured**:\n");
-                for (var, value) in &match_result.vars {
-                    summary.push_str(&format!("   - `{var}` = `{value}`\n"));
-                }
-            }
         }
 
         summary
     }
 
-    /// Format a replace result with a readable summary
-    pub fn format_replace_result(result: &ReplaceResult) -> String {
-        if result.changes.is_empty() {
-            return "🔄 **No changes made**\n\nThe pattern did not match anything in the provided code.".to_string();
+    /// Format the summary of a scan result written to a file
+    pub fn format_exported_scan_result(result: &crate::rules::ExportedScanResult) -> String {
+        let counts: Vec<String> = result
+            .severity_counts
+            .iter()
+            .map(|(severity, count)| format!("{count} {severity}"))
+            .collect();
+        format!(
+            "💾 **Scan Exported**\n\n📄 **Output file**: `{}` ({} bytes)\n🎯 **Findings**: {} ({})\n📁 **Files scanned**: {}\n📋 **Rules run**: {}\n",
+            result.output_file,
+            result.bytes_written,
+            result.total_findings,
+            counts.join(", "),
+            result.files_scanned,
+            result.rules_run
+        ) + &Self::format_truncated_rules(&result.truncated_rules)
+    }
+
+    /// Note the scan rules that stopped early, if any
+    fn format_truncated_rules(rules: &[String]) -> String {
+        if rules.is_empty() {
+            return String::new();
+        }
+        format!(
+            "\n⏱️ **Budget exceeded**: {} ran out of time or reached the match limit, so their findings are partial\n",
+            rules.join(", ")
+        )
+    }
+
+    /// Format a comment audit grouped by tag
+    pub fn format_find_comments_result(result: &FindCommentsResult) -> String {
+        if result.comments.is_empty() {
+            return "💬 **No matching comments found**".to_string();
         }
 
         let mut summary = format!(
-            "🔄 **Replace Results**\n\n✅ **Changes**: {} replacements made\n",
-            result.changes.len()
+            "💬 **Comment Audit**\n\n🎯 **Comments**: {} found in {} files searched\n",
+            result.comments.len(),
+            result.total_files_found
         );
 
-        for (i, change) in result.changes.iter().enumerate() {
+        let mut tags: Vec<_> = result.tag_counts.iter().collect();
+        tags.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        for (tag, count) in tags {
+            summary.push_str(&format!("   • **{tag}**: {count}\n"));
+        }
+
+        summary.push('\n');
+        for comment in result.comments.iter().take(10) {
+            let author = comment
+                .blame
+                .as_ref()
+                .map(|blame| format!(" ({})", blame.author))
+                .unwrap_or_default();
             summary.push_str(&format!(
-                "\n{}. **Line {}-{}**:\n",
-                i + 1,
-                change.start_line,
-                change.end_line
+                "📄 `{}:{}`{}: `{}`\n",
+                comment.file_path,
+                comment.start_line,
+                author,
+                comment.text.lines().next().unwrap_or_default().trim()
+            ));
+        }
+        if result.comments.len() > 10 {
+            summary.push_str(&format!(
+                "... and {} more comments\n",
+                result.comments.len() - 10
             ));
-            summary.push_str(&format!("   **Before**: `{}`\n", change.old_text.trim()));
-            summary.push_str(&format!("   **After**: `{}`\n", change.new_text.trim()));
+        }
+
+        if let Some(cursor) = &result.next_cursor
+            && !cursor.is_complete
+        {
+            summary.push_str("\n📄 **More results available** - use cursor to continue");
         }
 
         summary
     }
 
-    /// Format a file replace result with a readable summary
-    pub fn format_file_replace_result(result: &FileReplaceResult) -> String {
-        if result.total_changes == 0 {
-            return "🔄 **No changes made**\n\nThe pattern did not match anything in the searched files.".to_string();
+    /// Format a project overview with its languages, indented tree and path pattern suggestions
+    pub fn format_project_overview_result(result: &ProjectOverviewResult) -> String {
+        if result.total_files == 0 {
+            return "🗺️ **No files found under the root directories**".to_string();
         }
 
         let mut summary = format!(
-            "🔄 **File Replace Results**\n\n📁 **Files modified**: {}\n✅ **Total changes**: {}\n",
-            result.files_with_changes, result.total_changes
+            "🗺️ **Project Overview**\n\n📄 **Files**: {} ({} bytes, {} in no parsable language)\n",
+            result.total_files, result.total_bytes, result.other_files
         );
+        for language in &result.languages {
+            summary.push_str(&format!(
+                "   • **{}**: {} files (.{})\n",
+                language.language,
+                language.files,
+                language.extensions.join(", .")
+            ));
+        }
 
-        // Check if we're in summary mode (only summary_results populated)
-        if !result.summary_results.is_empty() && result.file_results.is_empty() {
-            summary.push_str("\n📊 **Summary mode** - detailed changes not shown");
+        summary.push_str("\n**Tree:**\n");
+        for directory in &result.tree {
+            let name = match directory.depth {
+                0 => directory.path.as_str(),
+                _ => directory.path.rsplit('/').next().unwrap_or_default(),
+            };
+            let language = directory
+                .main_language
+                .as_ref()
+                .map(|language| format!(", mostly {language}"))
+                .unwrap_or_default();
+            summary.push_str(&format!(
+                "{}• `{name}/` {} files{language}\n",
+                "  ".repeat(directory.depth),
+                directory.files
+            ));
+        }
 
-            // Show summary results
-            for (i, summary_result) in result.summary_results.iter().take(5).enumerate() {
-                if summary_result.total_changes == 0 {
-                    continue;
-                }
+        if !result.largest_files.is_empty() {
+            summary.push_str("\n**Largest files:**\n");
+            for file in &result.largest_files {
                 summary.push_str(&format!(
-                    "\n📄 **File {}**: `{}`\n   ✅ {} changes on {} lines\n",
-                    i + 1,
-                    summary_result.file_path,
-                    summary_result.total_changes,
-                    summary_result.lines_changed
+                    "   • `{}` ({} bytes)\n",
+                    file.file_path, file.bytes
                 ));
             }
+        }
 
-            if result.summary_results.len() > 5 {
+        if !result.suggested_path_patterns.is_empty() {
+            summary.push_str("\n**Suggested path patterns:**\n");
+            for suggestion in &result.suggested_path_patterns {
                 summary.push_str(&format!(
-                    "\n... and {} more files",
-                    result.summary_results.len() - 5
+                    "   • `{}` with language `{}` ({} files)\n",
+                    suggestion.path_pattern, suggestion.language, suggestion.files
                 ));
             }
-
-            return summary;
         }
 
-        // Show file details
-        for (i, file_result) in result.file_results.iter().take(5).enumerate() {
-            if file_result.total_changes == 0 {
-                continue;
-            }
+        summary
+    }
 
-            summary.push_str(&format!(
-                "\n📄 **File {}**: `{}`\n",
-                i + 1,
-                file_result.file_path
-            ));
-            summary.push_str(&format!(
-                "   ✅ {} changes made:\n",
-                file_result.total_changes
-            ));
+    /// Format a directory summary with totals and the largest functions
+    pub fn format_summarize_directory_result(result: &SummarizeDirectoryResult) -> String {
+        if result.files.is_empty() {
+            return "📁 **No source files found**".to_string();
+        }
 
-            // Show first few changes
-            for (j, change) in file_result.changes.iter().take(2).enumerate() {
-                summary.push_str(&format!(
-                    "   {}. **Line {}**: `{}` → `{}`\n",
-                    j + 1,
-                    change.start_line,
-                    change.old_text.trim(),
-                    change.new_text.trim()
-                ));
-            }
+        let mut summary = format!(
+            "📁 **Directory Summary**\n\n📄 **Files**: {} ({} from cache)\n📏 **Lines of code**: {}\n🔧 **Functions**: {}\n🏛️ **Classes**: {}\n",
+            result.files.len(),
+            result.cached_files,
+            result.total_lines_of_code,
+            result.total_functions,
+            result.total_classes
+        );
+        for (language, count) in &result.languages {
+            summary.push_str(&format!("   • **{language}**: {count} files\n"));
+        }
 
-            if file_result.changes.len() > 2 {
+        if !result.largest_functions.is_empty() {
+            summary.push_str("\n**Largest functions:**\n");
+            for function in &result.largest_functions {
                 summary.push_str(&format!(
-                    "   ... and {} more changes\n",
-                    file_result.changes.len() - 2
+                    "   • `{}` ({} lines) in `{}:{}`\n",
+                    function.name,
+                    function.lines,
+                    function.file_path.as_deref().unwrap_or_default(),
+                    function.start_line
                 ));
             }
         }
 
-        if result.file_results.len() > 5 {
-            summary.push_str(&format!(
-                "\n... and {} more files modified",
-                result.file_results.len() - 5
-            ));
+        if result.truncated {
+            summary.push_str("\n⚠️ **Truncated** - raise max_files or pick a subdirectory");
         }
 
         summary
     }
 
-    /// Format a list languages result with a readable summary
-    pub fn format_list_languages_result(result: &ListLanguagesResult) -> String {
-        format!(
-            "🔤 **Supported Languages**\n\n📝 **Total**: {} languages supported\n\n**Languages**:\n{}",
-            result.languages.len(),
-            result
-                .languages
-                .iter()
-                .enumerate()
-                .map(|(i, lang)| format!("{}. `{}`", i + 1, lang))
-                .collect::<Vec<_>>()
-                .join("\n")
-        )
+    /// Format declarations resembling a query, most similar first
+    pub fn format_symbol_search_result(result: &SymbolSearchResult) -> String {
+        let mut summary = format!(
+            "🔎 **Symbol Search**\n\n📄 **Indexed**: {} symbols in {} files ({} from cache)\n",
+            result.symbols_indexed, result.files_indexed, result.cached_files
+        );
+        if result.matches.is_empty() {
+            summary.push_str("\nNo declarations resemble the query; try lowering min_score");
+        }
+        for symbol in &result.matches {
+            summary.push_str(&format!(
+                "   • `{}` ({}, {} {:.2}) in `{}:{}`\n",
+                symbol.qualified_name,
+                symbol.kind,
+                symbol.match_kind,
+                symbol.score,
+                symbol.file_path,
+                symbol.start_line
+            ));
+        }
+        if result.truncated {
+            summary.push_str("\n⚠️ **Truncated** - raise max_files or pick a subdirectory");
+        }
+        summary
     }
 
-    /// Format a generate AST result with a readable summary
-    pub fn format_generate_ast_result(result: &GenerateAstResult) -> String {
+    /// Format the overall and per-language coverage, the least covered files and one page of
+    /// undocumented symbols
+    pub fn format_doc_coverage_result(result: &DocCoverageResult) -> String {
         let mut summary = format!(
-            "🌳 **AST Generation Results**\n\n📝 **Language**: {}\n📏 **Code length**: {} characters\n🏷️ **Node types**: {} available\n",
-            result.language,
-            result.code_length,
-            result.node_kinds.len()
+            "📚 **Doc coverage**: {}% ({} of {} public symbols documented in {} files)\n",
+            result.total.coverage_percent,
+            result.total.documented,
+            result.total.public_symbols,
+            result.files_indexed
         );
-
-        summary.push_str("\n**Available Node Types**:\n");
-        for (i, kind) in result.node_kinds.iter().take(10).enumerate() {
-            summary.push_str(&format!("{}. `{}`\n", i + 1, kind));
+        for (language, coverage) in &result.languages {
+            summary.push_str(&format!(
+                "   • {language}: {}% ({} of {})\n",
+                coverage.coverage_percent, coverage.documented, coverage.public_symbols
+            ));
         }
 
-        if result.node_kinds.len() > 10 {
+        let mut least_covered: Vec<&ModuleDocCoverage> = result
+            .modules
+            .iter()
+            .filter(|module| module.coverage.documented < module.coverage.public_symbols)
+            .collect();
+        least_covered.sort_by(|a, b| {
+            a.coverage
+                .coverage_percent
+                .total_cmp(&b.coverage.coverage_percent)
+        });
+        if !least_covered.is_empty() {
+            summary.push_str("\n📉 **Least covered**:\n");
+        }
+        for module in least_covered.iter().take(5) {
             summary.push_str(&format!(
-                "... and {} more node types\n",
-                result.node_kinds.len() - 10
+                "   • `{}`: {}% ({} of {})\n",
+                module.file_path,
+                module.coverage.coverage_percent,
+                module.coverage.documented,
+                module.coverage.public_symbols
             ));
         }
 
-        summary.push_str(&format!("\n**AST Structure**:\n```\n{}\n```", result.ast));
-
-        summary
+        if !result.undocumented.is_empty() {
+            summary.push_str("\n📝 **Undocumented**:\n");
+        }
+        for symbol in &result.undocumented {
+            summary.push_str(&format!(
+                "   • `{}` ({}) in `{}:{}`\n",
+                symbol.qualified_name, symbol.kind, symbol.file_path, symbol.start_line
+            ));
+        }
+        if let Some(next_offset) = result.next_offset {
+            summary.push_str(&format!(
+                "   ... more; pass `\"offset\": {next_offset}` for the next page\n"
+            ));
+        }
+        if result.truncated {
+            summary.push_str("\n⚠️ **Truncated** - raise max_files or pick a subdirectory");
+        }
+        summary + &Self::format_skipped_files(&result.skipped_files)
     }
 
-    /// Format rule validation result with a readable summary
-    pub fn format_rule_validate_result(result: &crate::rules::RuleValidateResult) -> String {
-        if result.valid {
-            let mut summary =
-                "✅ **Rule Validation Successful**\n\n🎯 Rule syntax is valid and ready to use."
-                    .to_string();
-
-            if let Some(test_results) = &result.test_results {
+    /// Format references grouped per file, each with its kind and line
+    pub fn format_find_references_result(result: &FindReferencesResult) -> String {
+        let mut summary = format!(
+            "🔗 **References** to `{}`\n\n📄 **Found**: {} in {} of {} files searched\n",
+            result.symbol,
+            result.total_references,
+            result.files.len(),
+            result.files_searched
+        );
+        if !result.counts.is_empty() {
+            let counts: Vec<String> = result
+                .counts
+                .iter()
+                .map(|(kind, count)| format!("{count} {kind}"))
+                .collect();
+            summary.push_str(&format!("🏷️ **Kinds**: {}\n", counts.join(", ")));
+        }
+        for file in &result.files {
+            summary.push_str(&format!("\n**{}**\n", file.file_path));
+            for reference in &file.references {
                 summary.push_str(&format!(
-                    "\n\n🧪 **Test Results**:\n📊 **Matches found**: {}\n",
-                    test_results.matches_found
+                    "   • {} line {}: `{}`\n",
+                    reference.kind.as_str(),
+                    reference.line,
+                    reference.text
                 ));
-
-                if !test_results.sample_matches.is_empty() {
-                    summary.push_str("\n**Sample matches**:\n");
-                    for (i, sample) in test_results.sample_matches.iter().take(3).enumerate() {
-                        summary.push_str(&format!("{}. `{}`\n", i + 1, sample.trim()));
-                    }
-                }
             }
+        }
+        if result.truncated {
+            summary.push_str("\n⚠️ **Truncated** - raise max_results or pick a subdirectory");
+        }
+        summary + &Self::format_skipped_files(&result.skipped_files)
+    }
 
-            summary
+    /// Format the places a rename changes, per file, and the collisions it would cause
+    pub fn format_rename_symbol_result(result: &RenameSymbolResult) -> String {
+        let verb = match result.applied {
+            true => "Renamed",
+            false => "Would rename",
+        };
+        let mut summary = format!(
+            "✏️ **{verb}** `{}` → `{}`\n\n📄 **Places**: {} in {} of {} files searched\n",
+            result.old_name,
+            result.new_name,
+            result.total_renames,
+            result.files.len(),
+            result.files_searched
+        );
+        if !result.collisions.is_empty() {
+            summary.push_str(&format!(
+                "\n⚠️ **Collisions**: `{}` is already defined in scope\n",
+                result.new_name
+            ));
+            for file in &result.collisions {
+                for reference in &file.references {
+                    summary.push_str(&format!(
+                        "   • {} line {}: `{}`\n",
+                        file.file_path, reference.line, reference.text
+                    ));
+                }
+            }
+            if result.dry_run {
+                summary.push_str("   Set force=true to rename anyway\n");
+            }
+        }
+        for file in &result.files {
+            summary.push_str(&format!("\n**{}**\n", file.file_path));
+            for reference in &file.references {
+                summary.push_str(&format!(
+                    "   • {} line {}: `{}`\n",
+                    reference.kind.as_str(),
+                    reference.line,
+                    reference.text
+                ));
+            }
+        }
+        summary + &Self::format_skipped_files(&result.skipped_files)
+    }
+
+    /// Format the derived pattern and each similar piece of code with its score and location
+    pub fn format_find_similar_code_result(result: &FindSimilarCodeResult) -> String {
+        let mut summary = format!(
+            "🧬 **Similar code** to `{}`\n\n📄 **Found**: {} in {} files searched\n",
+            result.pattern, result.total_matches, result.files_searched
+        );
+        for similar in &result.matches {
+            let first_line = similar.text.lines().next().unwrap_or("").trim();
+            summary.push_str(&format!(
+                "   • {:.0}% {}:{}: `{}`\n",
+                similar.score * 100.0,
+                similar.file_path,
+                similar.start_line,
+                first_line
+            ));
+        }
+        if result.truncated {
+            summary.push_str("\n⚠️ **Truncated** - raise max_results or min_score");
+        }
+        summary + &Self::format_skipped_files(&result.skipped_files)
+    }
+
+    /// Format each clone group with its size, similarity and the location of every copy
+    pub fn format_dedupe_code_result(result: &DedupeCodeResult) -> String {
+        let mut summary = format!(
+            "👯 **Duplicate code**: {} clone groups, {} duplicated lines in {} files scanned\n",
+            result.total_groups, result.duplicated_lines, result.files_scanned
+        );
+        for group in &result.groups {
+            summary.push_str(&format!(
+                "\n   • {} copies of {} lines ({} tokens, {:.0}% similar, {}):\n",
+                group.instances.len(),
+                group.lines,
+                group.tokens,
+                group.similarity * 100.0,
+                group.language
+            ));
+            for instance in &group.instances {
+                summary.push_str(&format!(
+                    "      - {}:{}-{} ({})\n",
+                    instance.file_path, instance.start_line, instance.end_line, instance.kind
+                ));
+            }
+        }
+        if result.truncated {
+            summary.push_str("\n⚠️ **Truncated** - raise max_groups, min_lines or min_tokens");
+        }
+        summary + &Self::format_skipped_files(&result.skipped_files)
+    }
+
+    /// Format the synthesized candidates best first, with how many examples each reproduces
+    pub fn format_suggest_pattern_result(result: &crate::learning::SuggestPatternResult) -> String {
+        let mut summary = format!(
+            "🧪 **Suggested patterns**: {} candidates\n",
+            result.candidates.len()
+        );
+        if result.candidates.is_empty() {
+            summary.push_str("\nNo candidate matched the examples; try smaller examples\n");
+        }
+        for (index, candidate) in result.candidates.iter().enumerate() {
+            summary.push_str(&format!(
+                "\n{}. `{}` → `{}` ({}, reproduces {}/{}, matches {}/{})\n",
+                index + 1,
+                candidate.pattern,
+                candidate.replacement,
+                candidate.strategy,
+                candidate.examples_reproduced,
+                candidate.validation.len(),
+                candidate.examples_matched,
+                candidate.validation.len()
+            ));
+        }
+        for note in &result.notes {
+            summary.push_str(&format!("\n⚠️ {note}"));
+        }
+        summary
+    }
+
+    /// Format each convention's dominant option with its share and the other options seen
+    pub fn format_detect_conventions_result(result: &DetectConventionsResult) -> String {
+        let mut summary = format!(
+            "🧭 **Conventions**\n\n📄 **Sampled**: {} of {} source files\n",
+            result.files_sampled, result.files_found
+        );
+        if result.conventions.is_empty() {
+            summary.push_str("\nNo probe matched; the sampled languages may not have any probes");
+        }
+        for convention in &result.conventions {
+            let others: Vec<String> = convention
+                .counts
+                .iter()
+                .filter(|(option, _)| **option != convention.dominant)
+                .map(|(option, count)| format!("{option} {count}"))
+                .collect();
+            summary.push_str(&format!(
+                "   • **{}** {}: `{}` ({:.0}% of {})",
+                convention.language,
+                convention.topic,
+                convention.dominant,
+                convention.share * 100.0,
+                convention.observations
+            ));
+            if !others.is_empty() {
+                summary.push_str(&format!("; also {}", others.join(", ")));
+            }
+            if let Some(example) = &convention.example {
+                summary.push_str(&format!(" - e.g. `{}:{}`", example.file_path, example.line));
+            }
+            summary.push('\n');
+        }
+        summary + &Self::format_skipped_files(&result.skipped_files)
+    }
+
+    /// Format migration progress with each pattern's count against its baseline
+    pub fn format_pattern_coverage_result(result: &PatternCoverageResult) -> String {
+        let mut summary = format!(
+            "📉 **Pattern Coverage** `{}`\n\n🔢 **Remaining**: {} occurrences\n",
+            result.name, result.total_remaining
+        );
+        if let Some(progress) = result.progress_percent {
+            summary.push_str(&format!(
+                "✅ **Progress**: {progress:.1}% since the baseline\n"
+            ));
+        }
+        for count in &result.patterns {
+            summary.push_str(&format!(
+                "   • `{}`: {} in {} files",
+                count.id, count.remaining, count.files
+            ));
+            match (count.baseline, count.previous) {
+                (Some(baseline), Some(previous)) => {
+                    summary.push_str(&format!(" (baseline {baseline}, previous {previous})\n"))
+                }
+                _ => summary.push_str(" (new)\n"),
+            }
+        }
+
+        if !result.complete {
+            summary.push_str("\n⚠️ **Incomplete** - timeout_ms ran out; counts were not recorded");
+        } else if result.recorded {
+            summary.push_str(&format!(
+                "\n💾 **Recorded** snapshot {} of the history",
+                result.history.len()
+            ));
+        }
+        summary
+    }
+
+    /// Format the state of a code buffer after it was opened or appended to
+    pub fn format_code_buffer_info(info: &CodeBufferInfo) -> String {
+        format!(
+            "📥 **Code buffer** `{}`\n\n📏 **Size**: {} bytes in {} chunks\n⏳ **Expires**: {}s after last use",
+            info.buffer_id, info.size_bytes, info.chunks, info.expires_in_seconds
+        )
+    }
+
+    /// Format a hash-checked file fetch
+    pub fn format_get_file_by_hash_result(result: &GetFileByHashResult) -> String {
+        match &result.content {
+            Some(content) => format!(
+                "📄 **{}** (lines {}-{} of {}, hash unchanged)\n```\n{}\n```",
+                result.file_path,
+                result.start_line,
+                result.end_line,
+                result.total_lines,
+                content.trim_end()
+            ),
+            None => format!(
+                "⚠️ **{}** changed since it was searched; its hash is now `{}`. Search again for current matches.",
+                result.file_path, result.file_hash
+            ),
+        }
+    }
+
+    /// Format the outcome of each experiment candidate, in request order
+    pub fn format_experiment_result(result: &ExperimentResult) -> String {
+        let mut summary = format!(
+            "🧪 **Experiment** with {} candidates\n",
+            result.outcomes.len()
+        );
+        for outcome in &result.outcomes {
+            summary.push_str(&format!(
+                "\n**{}** `{}` ({}): ",
+                outcome.id, outcome.pattern, outcome.language
+            ));
+            if let Some(error) = &outcome.error {
+                summary.push_str(&format!("❌ {error}\n"));
+                continue;
+            }
+            summary.push_str(&format!("{} matches\n", outcome.match_count));
+            for match_result in &outcome.matches {
+                summary.push_str(&format!(
+                    "   • Line {}: `{}`\n",
+                    match_result.start_line,
+                    match_result.text.lines().next().unwrap_or_default().trim()
+                ));
+            }
+            if outcome.match_count > outcome.matches.len() {
+                summary.push_str(&format!(
+                    "   … and {} more\n",
+                    outcome.match_count - outcome.matches.len()
+                ));
+            }
+            if let Some(new_code) = &outcome.new_code {
+                summary.push_str(&format!(
+                    "   **Rewritten**:\n```\n{}\n```\n",
+                    new_code.trim_end()
+                ));
+            }
+        }
+        summary
+    }
+
+    /// Format a search result with a readable summary
+    pub fn format_search_result(result: &SearchResult) -> String {
+        if result.matches.is_empty() {
+            return "🔍 **No matches found**\n\nThe pattern did not match anything in the provided code.".to_string();
+        }
+
+        let mut summary = format!(
+            "🔍 **Search Results**\n\n🎯 **Matches**: {} found\n",
+            result.matches.len()
+        );
+
+        for (i, match_result) in result.matches.iter().enumerate() {
+            summary.push_str(&format!(
+                "\n{}. **Line {}-{}** (Col {}-{}){}:\n```\n{}\n```\n",
+                i + 1,
+                match_result.start_line,
+                match_result.end_line,
+                match_result.start_col,
+                match_result.end_col,
+                Self::format_enclosing_symbol(match_result),
+                match_result.text.trim()
+            ));
+
+            // Show captured variables if any
+            if !match_result.vars.is_empty() {
+                summary.push_str("   **Variables captured**:\n");
+                for (var, value) in &match_result.vars {
+                    summary.push_str(&format!("   - `{var}` = `{value}`\n"));
+                }
+            }
+
+            if let Some(ast_context) = &match_result.ast_context {
+                summary.push_str(&Self::format_ast_context(ast_context));
+            }
+            if !match_result.leading_trivia.is_empty() {
+                summary.push_str(&Self::format_leading_trivia(&match_result.leading_trivia));
+            }
+        }
+
+        summary
+    }
+
+    /// " in `Owner.name`" for a match inside a named symbol, or nothing at the top level
+    fn format_enclosing_symbol(match_result: &MatchResult) -> String {
+        match &match_result.enclosing_symbol {
+            Some(symbol) => format!(" in `{}`", symbol.name),
+            None => String::new(),
+        }
+    }
+
+    /// "**AST**: `kind` in `parent`, children: ..." line for a match with include_ast_context
+    fn format_ast_context(ast_context: &AstContext) -> String {
+        let mut line = format!("   **AST**: `{}`", ast_context.kind);
+        if let Some(parent_kind) = &ast_context.parent_kind {
+            line.push_str(&format!(" in `{parent_kind}`"));
+        }
+        if !ast_context.child_kinds.is_empty() {
+            let children: Vec<String> = ast_context
+                .child_kinds
+                .iter()
+                .map(|kind| format!("`{kind}`"))
+                .collect();
+            line.push_str(&format!(", children: {}", children.join(", ")));
+        }
+        line.push('\n');
+        line
+    }
+
+    /// "**Leading**: `/// Docs`, `#[derive(Debug)]`" line for a match with include_leading_trivia,
+    /// showing the first line of each comment and attribute
+    fn format_leading_trivia(trivia: &[LeadingTrivia]) -> String {
+        let items: Vec<String> = trivia
+            .iter()
+            .map(|item| format!("`{}`", item.text.lines().next().unwrap_or_default().trim()))
+            .collect();
+        format!("   **Leading**: {}\n", items.join(", "))
+    }
+
+    /// Format a replace result with a readable summary
+    pub fn format_replace_result(result: &ReplaceResult) -> String {
+        if let Some(operations) = result.json_patch.as_ref().filter(|ops| !ops.is_empty()) {
+            return format!(
+                "🔄 **Replace Results**\n\n✅ **JSON Patch**: {} operations\n{}",
+                operations.len(),
+                Self::format_json_patch(operations)
+            );
+        }
+        if result.changes.is_empty() {
+            return "🔄 **No changes made**\n\nThe pattern did not match anything in the provided code.".to_string();
+        }
+
+        let mut summary = format!(
+            "🔄 **Replace Results**\n\n✅ **Changes**: {} replacements made\n",
+            result.changes.len()
+        );
+
+        for (i, change) in result.changes.iter().enumerate() {
+            summary.push_str(&format!(
+                "\n{}. **Line {}-{}**:\n",
+                i + 1,
+                change.start_line,
+                change.end_line
+            ));
+            summary.push_str(&format!("   **Before**: `{}`\n", change.old_text.trim()));
+            summary.push_str(&format!("   **After**: `{}`\n", change.new_text.trim()));
+        }
+
+        if let Some(check) = &result.format_check {
+            summary.push_str(&Self::format_format_check(check));
+        }
+        if let Some(error) = &result.format_error {
+            summary.push_str(&format!("\n⚠️ **Not formatted**: {error}"));
+        }
+        summary
+    }
+
+    /// One line per JSON Patch operation: "op path = value"
+    fn format_json_patch(operations: &[JsonPatchOperation]) -> String {
+        let mut text = String::new();
+        for operation in operations {
+            let op = serde_json::to_value(operation.op).unwrap_or_default();
+            let op = op.as_str().unwrap_or_default();
+            match &operation.value {
+                Some(value) => {
+                    text.push_str(&format!("   - `{op} {}` = `{value}`\n", operation.path))
+                }
+                None => text.push_str(&format!("   - `{op} {}`\n", operation.path)),
+            }
+        }
+        text
+    }
+
+    /// One line for a passed or skipped formatter check, or the issues of a failed one
+    fn format_format_check(check: &FormatCheck) -> String {
+        match check.status {
+            FormatCheckStatus::Passed => {
+                format!("\n✅ **Formatting verified** with `{}`", check.formatter)
+            }
+            FormatCheckStatus::Skipped => format!(
+                "\n⏭️ **Formatting check skipped**: {}",
+                check
+                    .issues
+                    .first()
+                    .map_or("no formatter available", |issue| issue.as_str())
+            ),
+            FormatCheckStatus::Failed => {
+                let mut text = format!("\n⚠️ **Formatting check failed** (`{}`):", check.formatter);
+                for issue in &check.issues {
+                    text.push_str(&format!("\n   - {issue}"));
+                }
+                text
+            }
+        }
+    }
+
+    /// ` [label]` for files in a labelled workspace
+    fn workspace_tag(workspace: &Option<String>) -> String {
+        workspace
+            .as_ref()
+            .map(|workspace| format!(" [{workspace}]"))
+            .unwrap_or_default()
+    }
+
+    /// Files whose formatter check failed, or nothing if every checked file passed
+    fn format_file_format_checks(summary_results: &[FileSummaryResult]) -> String {
+        let failed: Vec<&FileSummaryResult> = summary_results
+            .iter()
+            .filter(|file| {
+                file.format_check
+                    .as_ref()
+                    .is_some_and(|check| check.status == FormatCheckStatus::Failed)
+            })
+            .collect();
+        if failed.is_empty() {
+            return String::new();
+        }
+
+        let mut text = format!(
+            "\n\n⚠️ **Formatting check failed** in {} files",
+            failed.len()
+        );
+        for file in failed.iter().take(5) {
+            text.push_str(&format!("\n   `{}`:", file.file_path));
+            for issue in file.format_check.iter().flat_map(|check| &check.issues) {
+                text.push_str(&format!("\n   - {issue}"));
+            }
+        }
+        text
+    }
+
+    /// Files `format_after_replace` left unformatted, with the reason
+    fn format_file_format_errors(summary_results: &[FileSummaryResult]) -> String {
+        let unformatted: Vec<(&str, &str)> = summary_results
+            .iter()
+            .filter_map(|file| Some((file.file_path.as_str(), file.format_error.as_deref()?)))
+            .collect();
+        if unformatted.is_empty() {
+            return String::new();
+        }
+
+        let mut text = format!("\n\n⚠️ **Not formatted**: {} files", unformatted.len());
+        for (file_path, error) in unformatted.iter().take(5) {
+            text.push_str(&format!("\n   - `{file_path}`: {error}"));
+        }
+        text
+    }
+
+    /// Format a verify_refactoring result: the replacement, the command's outcome and any rollback
+    pub fn format_verify_refactoring_result(result: &VerifyRefactoringResult) -> String {
+        let verification = &result.verification;
+        let mut summary = Self::format_file_replace_result(&result.replace);
+        summary.push_str(&format!(
+            "
+
+🧪 **Verification** (`{}`): {} in {}ms",
+            verification.command,
+            match (verification.passed, verification.timed_out) {
+                (true, _) => "✅ passed".to_string(),
+                (false, true) => "⏱️ timed out".to_string(),
+                (false, false) => match verification.exit_code {
+                    Some(code) => format!("❌ failed with exit code {code}"),
+                    None => "❌ failed".to_string(),
+                },
+            },
+            verification.duration_ms
+        ));
+        if let Some(error) = &verification.error {
+            summary.push_str(&format!(
+                "
+   {error}"
+            ));
+        }
+        if !verification.passed {
+            for tail in [&verification.stdout_tail, &verification.stderr_tail] {
+                if !tail.is_empty() {
+                    summary.push_str(&format!("\n```\n{tail}\n```"));
+                }
+            }
+        }
+        if result.rolled_back {
+            summary.push_str(&format!(
+                "
+
+↩️ **Rolled back** {} files",
+                result.restored_files.len()
+            ));
+        }
+        summary
+    }
+
+    /// Format a rollback_replace result: the restored files and any later edits it overwrote
+    pub fn format_rollback_replace_result(result: &RollbackReplaceResult) -> String {
+        let mut summary = format!(
+            "↩️ **Rolled back** `{}`: {} files restored",
+            result.apply_id,
+            result.restored_files.len()
+        );
+        for file_path in &result.restored_files {
+            summary.push_str(&format!("\n   - `{file_path}`"));
+        }
+        if !result.overwritten_files.is_empty() {
+            summary.push_str(&format!(
+                "\n\n⚠️ **Overwritten**: {} files had changed since the replacement was applied",
+                result.overwritten_files.len()
+            ));
+        }
+        summary
+    }
+
+    /// Format an apply_patch result: the files patched, or that would be in a dry run
+    pub fn format_apply_patch_result(result: &ApplyPatchResult) -> String {
+        let mut summary = match result.dry_run {
+            true => format!(
+                "🩹 **Patch applies** to {} files (dry run, nothing written)",
+                result.files.len()
+            ),
+            false => format!("🩹 **Patch applied** to {} files", result.files.len()),
+        };
+        for file in &result.files {
+            summary.push_str(&format!(
+                "\n   - `{}`: {} hunks, +{} -{}",
+                file.file_path, file.hunks, file.lines_added, file.lines_removed
+            ));
+        }
+        if let Some(apply_id) = &result.apply_id {
+            summary.push_str(&format!("\n\n↩️ **Undo** with rollback_replace: `{apply_id}`"));
+        }
+        summary
+    }
+
+    /// Format a list_applied_changes result, one line per recorded replacement
+    pub fn format_list_applied_changes_result(result: &ListAppliedChangesResult) -> String {
+        if result.changes.is_empty() {
+            return "📜 **No applied changes recorded**".to_string();
+        }
+        let mut summary = format!(
+            "📜 **Applied changes**: showing {} of {}\n",
+            result.changes.len(),
+            result.total
+        );
+        for change in &result.changes {
+            // Patches applied with apply_patch have no pattern
+            let description = match change.pattern.is_empty() {
+                true => format!("patch of {} files", change.files.len()),
+                false => format!(
+                    "`{}` → `{}` ({}) in {} files matching `{}`",
+                    change.pattern,
+                    change.replacement,
+                    change.language,
+                    change.files.len(),
+                    change.path_pattern
+                ),
+            };
+            summary.push_str(&format!(
+                "\n- `{}`{}: {description}",
+                change.apply_id,
+                if change.rolled_back {
+                    " (rolled back)"
+                } else {
+                    ""
+                },
+            ));
+        }
+        summary
+    }
+
+    /// Format a file replace result with a readable summary
+    pub fn format_file_replace_result(result: &FileReplaceResult) -> String {
+        let drift = Self::format_drifted_files(&result.drifted_files)
+            + Self::format_timed_out(result.timed_out)
+            + &Self::format_warnings(&result.warnings)
+            + &Self::format_fix_safety(result.fix_safety, &result.unsafe_fixes)
+            + &Self::format_file_format_checks(&result.summary_results)
+            + &Self::format_file_format_errors(&result.summary_results)
+            + &result
+                .apply_id
+                .as_ref()
+                .map(|apply_id| format!("\n\n↩️ **Undo** with rollback_replace: `{apply_id}`"))
+                .unwrap_or_default()
+            + &result
+                .operation_id
+                .as_ref()
+                .map(|id| format!("\n\n🔍 **Full diffs** per file with get_file_diff: `{id}`"))
+                .unwrap_or_default();
+        if result.total_changes == 0 && result.unsafe_fixes.is_empty() {
+            return format!(
+                "🔄 **No changes made**\n\nThe pattern did not match anything in the searched files.{drift}"
+            );
+        }
+
+        let mut summary = format!(
+            "🔄 **File Replace Results**\n\n📁 **Files modified**: {}\n✅ **Total changes**: {}\n",
+            result.files_with_changes, result.total_changes
+        );
+        for stats in result
+            .rule_stats
+            .iter()
+            .filter(|stats| stats.unfixed_matches > 0)
+        {
+            summary.push_str(&format!(
+                "⏭️ **Unfixed**: {} of {} matches of `{}` were left as they were\n",
+                stats.unfixed_matches, stats.matches, stats.rule_id
+            ));
+        }
+
+        // Check if we're in summary mode (only summary_results populated)
+        if !result.summary_results.is_empty() && result.file_results.is_empty() {
+            summary.push_str("\n📊 **Summary mode** - detailed changes not shown");
+
+            // Show summary results
+            for (i, summary_result) in result.summary_results.iter().take(5).enumerate() {
+                if summary_result.total_changes == 0 {
+                    continue;
+                }
+                summary.push_str(&format!(
+                    "\n📄 **File {}**: `{}`{}\n   ✅ {} changes on {} lines\n",
+                    i + 1,
+                    summary_result.file_path,
+                    Self::workspace_tag(&summary_result.workspace),
+                    summary_result.total_changes,
+                    summary_result.lines_changed
+                ));
+                if let Some(operations) = &summary_result.json_patch {
+                    summary.push_str(&Self::format_json_patch(operations));
+                }
+                if let Some(diff) = &summary_result.unified_diff {
+                    summary.push_str(&format!("```diff\n{diff}```\n"));
+                }
+            }
+
+            if result.summary_results.len() > 5 {
+                summary.push_str(&format!(
+                    "\n... and {} more files",
+                    result.summary_results.len() - 5
+                ));
+            }
+
+            summary.push_str(&drift);
+            return summary;
+        }
+
+        // Show file details
+        for (i, file_result) in result.file_results.iter().take(5).enumerate() {
+            if file_result.total_changes == 0 {
+                continue;
+            }
+
+            summary.push_str(&format!(
+                "\n📄 **File {}**: `{}`{}\n",
+                i + 1,
+                file_result.file_path,
+                Self::workspace_tag(&file_result.workspace)
+            ));
+            summary.push_str(&format!(
+                "   ✅ {} changes made:\n",
+                file_result.total_changes
+            ));
+
+            // Show first few changes
+            for (j, change) in file_result.changes.iter().take(2).enumerate() {
+                summary.push_str(&format!(
+                    "   {}. **Line {}**: `{}` → `{}`\n",
+                    j + 1,
+                    change.start_line,
+                    change.old_text.trim(),
+                    change.new_text.trim()
+                ));
+            }
+
+            if file_result.changes.len() > 2 {
+                summary.push_str(&format!(
+                    "   ... and {} more changes\n",
+                    file_result.changes.len() - 2
+                ));
+            }
+        }
+
+        if result.file_results.len() > 5 {
+            summary.push_str(&format!(
+                "\n... and {} more files modified",
+                result.file_results.len() - 5
+            ));
+        }
+
+        summary.push_str(&drift);
+        summary
+    }
+
+    /// Format one file's diff from a dry run, listing every change
+    pub fn format_get_file_diff_result(result: &GetFileDiffResult) -> String {
+        let diff = &result.diff;
+        let mut summary = format!(
+            "🔍 **File {} of {}**: `{}`{}\n   ✅ {} changes:\n",
+            result.file_index + 1,
+            result.total_files,
+            diff.file_path,
+            Self::workspace_tag(&diff.workspace),
+            diff.total_changes
+        );
+        for (i, change) in diff.changes.iter().enumerate() {
+            summary.push_str(&format!(
+                "   {}. **Line {}**: `{}` → `{}`\n",
+                i + 1,
+                change.start_line,
+                change.old_text.trim(),
+                change.new_text.trim()
+            ));
+        }
+        if let Some(next_file_path) = &result.next_file_path {
+            summary.push_str(&format!("\n➡️ **Next file**: `{next_file_path}`"));
+        }
+        summary
+    }
+
+    /// Format the annotated files, each with its first annotated lines
+    pub fn format_add_annotation_result(result: &AddAnnotationResult) -> String {
+        let verb = match result.dry_run {
+            true => "would be added",
+            false => "added",
+        };
+        let mut summary = format!(
+            "💬 **Annotations**\n\n✅ **{}** {verb} in {} files\n",
+            result.total_annotations, result.files_with_changes
+        );
+        if result.already_annotated > 0 {
+            summary.push_str(&format!(
+                "⏭️ **Already annotated**: {} matched lines\n",
+                result.already_annotated
+            ));
+        }
+        for file_result in result.file_results.iter().take(5) {
+            summary.push_str(&format!(
+                "\n📄 `{}`{}\n",
+                file_result.file_path,
+                Self::workspace_tag(&file_result.workspace)
+            ));
+            for change in file_result.changes.iter().take(3) {
+                summary.push_str(&format!(
+                    "   • **Line {}**: `{}`\n",
+                    change.start_line,
+                    change.old_text.trim()
+                ));
+            }
+        }
+        if result.file_results.len() > 5 {
+            summary.push_str(&format!(
+                "\n... and {} more files",
+                result.file_results.len() - 5
+            ));
+        }
+        if result.truncated {
+            summary.push_str("\n⚠️ **Truncated** - raise max_results to annotate the other files");
+        }
+        if result.dry_run && result.total_annotations > 0 {
+            summary.push_str("\n\n🔍 Dry run: call again with dry_run false to write the files");
+        }
+        summary
+    }
+
+    /// Format a wrap_matches result, showing the first wrapped matches of each file
+    pub fn format_wrap_matches_result(result: &WrapMatchesResult) -> String {
+        let verb = match result.dry_run {
+            true => "would be wrapped",
+            false => "wrapped",
+        };
+        let mut summary = format!(
+            "🎁 **Wrapped Matches**\n\n✅ **{}** {verb} in {} files\n",
+            result.total_wraps, result.files_with_changes
+        );
+        if result.nested_matches > 0 {
+            summary.push_str(&format!(
+                "⏭️ **Nested**: {} matches inside a wrapped match were left as they are\n",
+                result.nested_matches
+            ));
+        }
+        for file_result in result.file_results.iter().take(5) {
+            summary.push_str(&format!(
+                "\n📄 `{}`{}\n",
+                file_result.file_path,
+                Self::workspace_tag(&file_result.workspace)
+            ));
+            for change in file_result.changes.iter().take(3) {
+                summary.push_str(&format!(
+                    "   • **Line {}**: `{}` → `{}`\n",
+                    change.start_line,
+                    change.old_text.trim(),
+                    change.new_text.trim()
+                ));
+            }
+        }
+        if result.file_results.len() > 5 {
+            summary.push_str(&format!(
+                "\n... and {} more files",
+                result.file_results.len() - 5
+            ));
+        }
+        if result.truncated {
+            summary.push_str(
+                "\n⚠️ **Truncated** - raise max_results to wrap the matches in the other files",
+            );
+        }
+        if result.dry_run && result.total_wraps > 0 {
+            summary.push_str("\n\n🔍 Dry run: call again with dry_run false to write the files");
+        }
+        summary
+    }
+
+    /// Describe files that changed between the dry run and apply, or nothing if there were none
+    fn format_drifted_files(drifted_files: &[DriftedFile]) -> String {
+        let unresolved: Vec<&DriftedFile> = drifted_files
+            .iter()
+            .filter(|file| file.needs_resolution())
+            .collect();
+        if drifted_files.is_empty() {
+            return String::new();
+        }
+
+        let mut text = format!(
+            "\n\n🔀 **Changed since dry run**: {} files merged, {} need manual resolution",
+            drifted_files.len() - unresolved.len(),
+            unresolved.len()
+        );
+        for file in unresolved {
+            if file.written {
+                text.push_str(&format!(
+                    "\n   ⚠️ `{}`: {} conflicting hunks marked with <<<<<<< / >>>>>>>",
+                    file.file_path, file.conflicts
+                ));
+            } else {
+                text.push_str(&format!(
+                    "\n   ⚠️ `{}`: dry-run content unavailable, file left unchanged",
+                    file.file_path
+                ));
+            }
+        }
+        text
+    }
+
+    /// Describe the rule's fix safety and any unsafe fixes held back, or nothing for safe fixes
+    fn format_fix_safety(fix_safety: Option<FixSafety>, unsafe_fixes: &[FileDiffResult]) -> String {
+        match fix_safety {
+            Some(FixSafety::Suggestion) => {
+                "\n\n🟡 **Fix safety**: suggestion - review these changes before relying on them"
+                    .to_string()
+            }
+            Some(FixSafety::Unsafe) if !unsafe_fixes.is_empty() => {
+                let total: usize = unsafe_fixes.iter().map(|file| file.total_changes).sum();
+                let mut text = format!(
+                    "\n\n🛑 **Unsafe fixes held back**: {total} changes in {} files; set allow_unsafe=true to apply them",
+                    unsafe_fixes.len()
+                );
+                for file in unsafe_fixes.iter().take(5) {
+                    text.push_str(&format!(
+                        "\n   ⚠️ `{}`: {} changes",
+                        file.file_path, file.total_changes
+                    ));
+                }
+                text
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// Format a list languages result with a readable summary
+    pub fn format_list_languages_result(result: &ListLanguagesResult) -> String {
+        format!(
+            "🔤 **Supported Languages**\n\n📝 **Total**: {} languages supported\n\n**Languages**:\n{}",
+            result.languages.len(),
+            result
+                .languages
+                .iter()
+                .enumerate()
+                .map(|(i, lang)| format!("{}. `{}`", i + 1, lang))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    }
+
+    /// Format a created playground with its files and the calls to try on it
+    pub fn format_create_playground_result(result: &CreatePlaygroundResult) -> String {
+        let mut summary = format!(
+            "🛝 **Playground**: {} files written to `{}`\n\n**Try**:\n",
+            result.files.len(),
+            result.directory
+        );
+        for (i, suggestion) in result.suggestions.iter().enumerate() {
+            summary.push_str(&format!(
+                "{}. `{}`: {}\n```json\n{}\n```\n",
+                i + 1,
+                suggestion.tool,
+                suggestion.description,
+                serde_json::to_string_pretty(&suggestion.arguments).unwrap_or_default()
+            ));
+        }
+        summary
+    }
+
+    /// Format a detected language with how it was decided and the files it covers
+    pub fn format_detect_language_result(result: &DetectLanguageResult) -> String {
+        let mut summary = match &result.language {
+            Some(language) => format!(
+                "🔎 **Language**: `{language}` ({:?}, confidence {:.2})\n",
+                result.method, result.confidence
+            ),
+            None => "🔎 **Language**: not detected\n".to_string(),
+        };
+        summary.push_str(&format!("**Reason**: {}\n", result.reason));
+        if let Some(path_pattern) = &result.path_pattern {
+            summary.push_str(&format!("**Files**: `{path_pattern}`\n"));
+        }
+        if !result.candidates.is_empty() {
+            summary.push_str("\n**Candidates**:\n");
+            for candidate in result.candidates.iter().take(5) {
+                summary.push_str(&format!(
+                    "- `{}`: {} keywords (score {:.2})\n",
+                    candidate.language, candidate.keywords, candidate.score
+                ));
+            }
+            if result.candidates.len() > 5 {
+                summary.push_str(&format!("- ... and {} more\n", result.candidates.len() - 5));
+            }
+        }
+        summary
+    }
+
+    /// Format a generate AST result with a readable summary
+    pub fn format_generate_ast_result(result: &GenerateAstResult) -> String {
+        let mut summary = format!(
+            "🌳 **AST Generation Results**\n\n📝 **Language**: {}\n📏 **Code length**: {} characters\n🏷️ **Node types**: {} available\n",
+            result.language,
+            result.code_length,
+            result.node_kinds.len()
+        );
+
+        summary.push_str("\n**Available Node Types**:\n");
+        for (i, kind) in result.node_kinds.iter().take(10).enumerate() {
+            let stat = result
+                .node_kind_stats
+                .iter()
+                .find(|stat| stat.kind == *kind);
+            let count = stat.map(|stat| format!(" × {}", stat.count));
+            let category = stat
+                .and_then(|stat| stat.category)
+                .map(|category| format!(" ({})", category.as_str()));
+            summary.push_str(&format!(
+                "{}. `{}`{}{}\n",
+                i + 1,
+                kind,
+                count.unwrap_or_default(),
+                category.unwrap_or_default()
+            ));
+        }
+
+        if result.node_kinds.len() > 10 {
+            summary.push_str(&format!(
+                "... and {} more node types\n",
+                result.node_kinds.len() - 10
+            ));
+        }
+
+        summary.push_str(&format!("\n**AST Structure**:\n```\n{}\n```", result.ast));
+
+        summary
+    }
+
+    /// Format the number of placeholders of each sort an anonymized result uses
+    pub fn format_anonymize_result(result: &AnonymizeResultResult) -> String {
+        let mut summary = format!(
+            "🕶️ **Anonymized Result**\n\n🔁 **Replaced**: {} identifiers, {} strings, {} comments, {} file paths\n",
+            result.identifiers, result.strings, result.comments, result.files
+        );
+        if result.mapping.is_some() {
+            summary.push_str(
+                "\n⚠️ The result includes `mapping` with the original text; remove it before sharing",
+            );
+        }
+        summary
+    }
+
+    /// Format the size and cache use of the file index
+    /// Format each check with its status, failures first
+    pub fn format_health_check_result(result: &HealthCheckResult) -> String {
+        let mut summary = match result.healthy {
+            true => format!("🩺 **Healthy**: {} warnings\n", result.warnings),
+            false => format!(
+                "🩺 **Unhealthy**: {} errors, {} warnings\n",
+                result.errors, result.warnings
+            ),
+        };
+        let mut checks: Vec<&HealthCheck> = result.checks.iter().collect();
+        checks.sort_by_key(|check| match check.status {
+            CheckStatus::Error => 0,
+            CheckStatus::Warning => 1,
+            CheckStatus::Ok => 2,
+        });
+        for check in checks {
+            let icon = match check.status {
+                CheckStatus::Ok => "✅",
+                CheckStatus::Warning => "⚠️",
+                CheckStatus::Error => "❌",
+            };
+            let target = check
+                .target
+                .as_ref()
+                .map(|target| format!(" `{target}`"))
+                .unwrap_or_default();
+            summary.push_str(&format!(
+                "   {icon} {}{target}: {}\n",
+                check.check, check.message
+            ));
+        }
+        summary
+    }
+
+    pub fn format_index_status(status: &IndexStatus) -> String {
+        if !status.enabled {
+            return "🗂️ **File index**: off; start the server with `--index` to keep one"
+                .to_string();
+        }
+        let mut summary = format!(
+            "🗂️ **File index**: {} files, {} syntax trees kept ({})\n",
+            status.files,
+            status.parsed_files,
+            match status.watching {
+                true => "watching for changes",
+                false => "not watching; use rebuild_index after changes",
+            }
+        );
+        if !status.built {
+            summary.push_str("   • The file list is walked again on the next search\n");
+        }
+        if let Some(last_build_ms) = status.last_build_ms {
+            summary.push_str(&format!(
+                "   • Walked {} times, last in {last_build_ms} ms\n",
+                status.builds
+            ));
+        }
+        summary.push_str(&format!(
+            "   • Trees reused {} times, parsed {} times; {} file changes seen\n",
+            status.cache_hits, status.cache_misses, status.file_events
+        ));
+        summary
+    }
+
+    /// Format rule validation result with a readable summary
+    pub fn format_rule_validate_result(result: &crate::rules::RuleValidateResult) -> String {
+        if result.valid {
+            let mut summary =
+                "✅ **Rule Validation Successful**\n\n🎯 Rule syntax is valid and ready to use."
+                    .to_string();
+
+            if let Some(test_results) = &result.test_results {
+                summary.push_str(&format!(
+                    "\n\n🧪 **Test Results**:\n📊 **Matches found**: {}\n",
+                    test_results.matches_found
+                ));
+
+                if !test_results.sample_matches.is_empty() {
+                    summary.push_str("\n**Sample matches**:\n");
+                    for (i, sample) in test_results.sample_matches.iter().take(3).enumerate() {
+                        summary.push_str(&format!("{}. `{}`\n", i + 1, sample.trim()));
+                    }
+                }
+
+                if !test_results.constraint_violations.is_empty() {
+                    summary.push_str(&format!(
+                        "\n🚫 **Dropped by constraints**: {}\n",
+                        test_results.constraint_violations.len()
+                    ));
+                    for violation in test_results.constraint_violations.iter().take(3) {
+                        summary.push_str(&format!(
+                            "   • `{}` (line {}): ${} captured `{}`\n",
+                            violation.text.trim(),
+                            violation.start_line,
+                            violation.metavariable,
+                            violation.captured
+                        ));
+                    }
+                }
+
+                if test_results.fixed_code.is_some() {
+                    summary.push_str(&format!(
+                        "\n🔧 **Fix preview**: {} hunks\n",
+                        test_results.fix_hunks.len()
+                    ));
+                    for hunk in test_results.fix_hunks.iter().take(5) {
+                        summary.push_str(&format!(
+                            "\n**Line {}**:\n```diff\n",
+                            hunk.start_line
+                        ));
+                        for line in hunk.old_text.lines() {
+                            summary.push_str(&format!("-{line}\n"));
+                        }
+                        for line in hunk.new_text.lines() {
+                            summary.push_str(&format!("+{line}\n"));
+                        }
+                        summary.push_str("```\n");
+                    }
+                    if test_results.fix_hunks.len() > 5 {
+                        summary.push_str(&format!(
+                            "\n... and {} more hunks\n",
+                            test_results.fix_hunks.len() - 5
+                        ));
+                    }
+                }
+            }
+
+            summary
         } else {
             let mut summary =
                 "❌ **Rule Validation Failed**\n\n🚨 The rule configuration has errors:\n\n"
@@ -353,6 +1861,344 @@ impl ResponseFormatter {
         }
     }
 
+    /// Format a batch rule validation, listing only the rules with errors or warnings
+    pub fn format_validate_rules_result(result: &crate::rules::ValidateRulesResult) -> String {
+        let mut summary = format!(
+            "🩺 **Rule Health Check**\n\n✅ **Valid**: {}\n❌ **Invalid**: {}\n⚠️ **Warnings**: {}\n",
+            result.valid_count, result.invalid_count, result.warning_count
+        );
+
+        for rule in &result.rules {
+            if rule.errors.is_empty() && rule.warnings.is_empty() && rule.test_results.is_none() {
+                continue;
+            }
+            let icon = match (rule.valid, rule.warnings.is_empty()) {
+                (false, _) => "❌",
+                (true, false) => "⚠️",
+                (true, true) => "✅",
+            };
+            let id = rule.id.as_deref().unwrap_or("(unparsed)");
+            summary.push_str(&format!("\n{icon} **{id}** ({})\n", rule.source));
+            for error in &rule.errors {
+                summary.push_str(&format!("   - error: {error}\n"));
+            }
+            for warning in &rule.warnings {
+                summary.push_str(&format!("   - warning: {warning}\n"));
+            }
+            if let Some(test_results) = &rule.test_results {
+                summary.push_str(&format!(
+                    "   - 🧪 {} matches in test code\n",
+                    test_results.matches_found
+                ));
+                if !test_results.constraint_violations.is_empty() {
+                    summary.push_str(&format!(
+                        "   - 🚫 {} more dropped by constraints\n",
+                        test_results.constraint_violations.len()
+                    ));
+                }
+            }
+        }
+
+        summary
+    }
+
+    /// Format a scan as a severity summary header followed by each bucket's page of findings
+    pub fn format_scan_result(result: &crate::rules::ScanResult) -> String {
+        let counts: Vec<String> = result
+            .buckets
+            .iter()
+            .map(|bucket| format!("{} {}", bucket.count, bucket.severity))
+            .collect();
+        let mut summary = format!(
+            "🛡️ **Scan Results**\n\n🎯 **Findings**: {} ({})\n📁 **Files scanned**: {}\n📋 **Rules run**: {}\n",
+            result.total_findings,
+            counts.join(", "),
+            result.files_scanned,
+            result.rules_run
+        );
+        summary.push_str(&Self::format_truncated_rules(&result.truncated_rules));
+
+        for bucket in &result.buckets {
+            if bucket.findings.is_empty() {
+                continue;
+            }
+            let icon = match bucket.severity.as_str() {
+                "error" => "❌",
+                "warning" => "⚠️",
+                _ => "ℹ️",
+            };
+            let rules: Vec<String> = bucket
+                .rule_counts
+                .iter()
+                .map(|(id, count)| format!("{id} ×{count}"))
+                .collect();
+            summary.push_str(&format!(
+                "\n{icon} **{}** ({}): {}\n",
+                bucket.severity,
+                bucket.count,
+                rules.join(", ")
+            ));
+            for finding in &bucket.findings {
+                summary.push_str(&format!(
+                    "   - `{}:{}` [{}] {}\n",
+                    finding.file_path, finding.start_line, finding.rule_id, finding.message
+                ));
+            }
+            if let Some(next_offset) = bucket.next_offset {
+                summary.push_str(&format!(
+                    "   ... {} more; pass `\"severity\": \"{}\", \"offset\": {next_offset}` for the next page\n",
+                    bucket.count - next_offset,
+                    bucket.severity
+                ));
+            }
+        }
+
+        summary
+    }
+
+    /// Format a workspace lint as its verdict, the counts per severity and rule, and a page
+    /// of findings
+    pub fn format_lint_workspace_result(result: &crate::rules::LintWorkspaceResult) -> String {
+        let threshold = match result.fail_on {
+            LintThreshold::Error => "error",
+            LintThreshold::Warning => "warning",
+        };
+        let verdict = if result.passed {
+            format!("✅ **Lint passed**: no findings at or above {threshold}")
+        } else {
+            format!(
+                "❌ **Lint failed**: {} findings at or above {threshold}",
+                result.failing_findings
+            )
+        };
+        let mut summary = format!(
+            "{verdict}\n\n🎯 **Findings**: {} ({} error, {} warning, {} info)\n📁 **Files scanned**: {}\n📋 **Rules run**: {}\n",
+            result.total_findings,
+            result.error_count,
+            result.warning_count,
+            result.info_count,
+            result.files_scanned,
+            result.rules_run
+        );
+
+        if !result.rule_counts.is_empty() {
+            summary.push_str("\n**Rules**:\n");
+            for rule in &result.rule_counts {
+                summary.push_str(&format!(
+                    "- `{}` ({}): {}\n",
+                    rule.rule_id, rule.severity, rule.count
+                ));
+            }
+        }
+        if !result.findings.is_empty() {
+            summary.push_str("\n**Findings**:\n");
+            for LintFinding { severity, finding } in &result.findings {
+                summary.push_str(&format!(
+                    "- `{}:{}` {severity} [{}] {}\n",
+                    finding.file_path, finding.start_line, finding.rule_id, finding.message
+                ));
+            }
+        }
+        if let Some(next_offset) = result.next_offset {
+            summary.push_str(&format!(
+                "... {} more; pass `\"offset\": {next_offset}` for the next page\n",
+                result.total_findings - next_offset
+            ));
+        }
+        summary
+    }
+
+    pub fn format_run_codemod_result(result: &RunCodemodResult) -> String {
+        let mode = if result.dry_run {
+            "preview, nothing written; pass `\"dry_run\": false` to apply"
+        } else {
+            "applied"
+        };
+        let mut summary = format!(
+            "🧰 **Codemod `{}`** ({mode})\n\n📁 **Files changed**: {}\n✅ **Total changes**: {}\n",
+            result.codemod, result.files_with_changes, result.total_changes
+        );
+
+        for step in &result.steps {
+            summary.push_str(&format!(
+                "\n🔧 **{}**: {} changes in {} files{}{}\n",
+                step.rule_id,
+                step.total_changes,
+                step.files_with_changes,
+                match step.unfixed_matches {
+                    0 => String::new(),
+                    unfixed => format!(", {unfixed} matches left unfixed"),
+                },
+                if step.incomplete {
+                    " (stopped at max_results; run again to continue)"
+                } else {
+                    ""
+                }
+            ));
+            for file in step.file_results.iter().take(5) {
+                summary.push_str(&format!("   📄 `{}`\n", file.file_path));
+                for change in file.changes.iter().take(2) {
+                    summary.push_str(&format!(
+                        "      **Line {}**: `{}` → `{}`\n",
+                        change.start_line,
+                        change.old_text.trim(),
+                        change.new_text.trim()
+                    ));
+                }
+            }
+            if step.file_results.len() > 5 {
+                summary.push_str(&format!(
+                    "   ... and {} more files\n",
+                    step.file_results.len() - 5
+                ));
+            }
+        }
+
+        summary
+    }
+
+    /// Format a directory move plan: the files that move and the import paths rewritten per file
+    pub fn format_edit_plan(plan: &EditPlan) -> String {
+        let total_changes: usize = plan.edits.iter().map(|edit| edit.changes.len()).sum();
+        let mut summary = format!(
+            "🗺️ **Move plan** `{}` → `{}` (nothing written yet; pass this plan to `apply_plan`)\n\n📦 **Files to move**: {}\n📁 **Files to edit**: {}\n✏️ **Import paths to rewrite**: {}\n",
+            plan.source,
+            plan.destination,
+            plan.moves.len(),
+            plan.edits.len(),
+            total_changes
+        );
+
+        for edit in plan.edits.iter().take(10) {
+            summary.push_str(&format!("\n📄 `{}`\n", edit.file_path));
+            for change in edit.changes.iter().take(5) {
+                summary.push_str(&format!(
+                    "   **Line {}**: `{}` → `{}`\n",
+                    change.start_line + 1,
+                    change.old_text,
+                    change.new_text
+                ));
+            }
+            if edit.changes.len() > 5 {
+                summary.push_str(&format!("   ... and {} more\n", edit.changes.len() - 5));
+            }
+        }
+        if plan.edits.len() > 10 {
+            summary.push_str(&format!("\n... and {} more files\n", plan.edits.len() - 10));
+        }
+        if !plan.skipped_files.is_empty() {
+            summary.push_str(&format!(
+                "\n⚠️ **Not scanned**: {} files\n",
+                plan.skipped_files.len()
+            ));
+        }
+
+        summary
+    }
+
+    pub fn format_apply_plan_result(result: &ApplyPlanResult) -> String {
+        format!(
+            "✅ **Moved** `{}` → `{}`\n\n📦 **Files moved**: {}\n📁 **Files edited**: {}\n✏️ **Import paths rewritten**: {}\n🧹 **Directories removed**: {}\n",
+            result.source,
+            result.destination,
+            result.files_moved,
+            result.files_edited,
+            result.total_changes,
+            result.removed_directories.len()
+        )
+    }
+
+    /// Format a rule evaluation trace as an indented list of steps
+    pub fn format_explain_rule_result(result: &crate::rules::ExplainRuleResult) -> String {
+        let mut summary = format!(
+            "🔬 **Rule Evaluation: {}**\n\n📄 `{}`\n🎯 **Final matches**: {}\n",
+            result.rule_id,
+            result.file_path,
+            result.matches.len()
+        );
+
+        summary.push_str("\n**Steps:**\n");
+        for step in &result.steps {
+            let depth = step.path.matches('.').count();
+            let argument = step
+                .argument
+                .as_ref()
+                .map(|argument| format!(" `{argument}`"))
+                .unwrap_or_default();
+            let counts = match step.candidates {
+                Some(candidates) => format!("{} of {candidates} kept", step.matched),
+                None => format!("{} matched", step.matched),
+            };
+            summary.push_str(&format!(
+                "{}• `{}` {}{}: {} - {}\n",
+                "  ".repeat(depth),
+                step.path,
+                step.operator,
+                argument,
+                counts,
+                step.note
+            ));
+        }
+
+        summary.push_str("\n**Why:**\n");
+        for line in &result.explanation {
+            summary.push_str(&format!("   • {line}\n"));
+        }
+
+        summary
+    }
+
+    /// Format a push to the rule registry, showing the registry's copy on a conflict
+    pub fn format_push_rule_result(result: &crate::rules::PushRuleResult) -> String {
+        match (&result.version, &result.conflict) {
+            (Some(version), _) => format!(
+                "📤 **Rule Pushed**\n\n✅ `{}` is now version {version} in the registry",
+                result.rule_id
+            ),
+            (None, Some(remote)) => format!(
+                "⚠️ **Push Conflict**\n\n`{}` was changed in the registry (version {}) since it was last synced:\n\n```yaml\n{}\n```\n\n💡 **Tip**: Merge it into the local rule and use pull_rules with on_conflict=keep_local, or push with force=true to replace it.",
+                result.rule_id,
+                remote.version,
+                remote.rule_config.trim_end()
+            ),
+            (None, None) => format!("❌ **Push Failed**\n\n`{}` was not pushed", result.rule_id),
+        }
+    }
+
+    /// Format a pull from the rule registry, listing every conflict
+    pub fn format_pull_rules_result(result: &crate::rules::PullRulesResult) -> String {
+        let mut summary = format!(
+            "📥 **Rules Pulled**\n\n➕ **Added**: {}\n🔄 **Updated**: {}\n✅ **Unchanged**: {}\n⚠️ **Conflicts**: {}\n",
+            result.added.len(),
+            result.updated.len(),
+            result.unchanged,
+            result.conflicts.len()
+        );
+
+        for (label, ids) in [("Added", &result.added), ("Updated", &result.updated)] {
+            if !ids.is_empty() {
+                summary.push_str(&format!("\n**{label}:** {}\n", ids.join(", ")));
+            }
+        }
+
+        if !result.conflicts.is_empty() {
+            summary.push_str("\n**Changed locally and in the registry:**\n");
+            for conflict in &result.conflicts {
+                let resolution = match conflict.resolution {
+                    crate::rules::ConflictResolution::Report => "left unchanged",
+                    crate::rules::ConflictResolution::KeepLocal => "kept local, push to publish",
+                    crate::rules::ConflictResolution::TakeRemote => "replaced by the registry's",
+                };
+                summary.push_str(&format!(
+                    "   • `{}` (registry version {}): {resolution}\n",
+                    conflict.rule_id, conflict.remote.version
+                ));
+            }
+        }
+
+        summary
+    }
+
     /// Format rule management results (create, list, get, delete)
     pub fn format_rule_management_result(operation: &str, success: bool, details: &str) -> String {
         let emoji = if success { "✅" } else { "❌" };
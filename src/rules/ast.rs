@@ -23,27 +23,65 @@ pub enum Rule {
         rule: Box<Rule>,
         #[serde(rename = "inside")]
         inside_of: Box<Rule>,
+        #[serde(rename = "stopBy")]
+        stop_by: StopBy,
+        field: Option<String>,
     },
     /// Relational rule - match nodes that contain another pattern
     Has {
         rule: Box<Rule>,
         #[serde(rename = "has")]
         contains: Box<Rule>,
+        #[serde(rename = "stopBy")]
+        stop_by: StopBy,
+        field: Option<String>,
     },
     /// Relational rule - match nodes that follow another pattern
     Follows {
         rule: Box<Rule>,
         #[serde(rename = "follows")]
         after: Box<Rule>,
+        #[serde(rename = "stopBy")]
+        stop_by: StopBy,
+        field: Option<String>,
     },
     /// Relational rule - match nodes that precede another pattern
     Precedes {
         rule: Box<Rule>,
         #[serde(rename = "precedes")]
         before: Box<Rule>,
+        #[serde(rename = "stopBy")]
+        stop_by: StopBy,
+        field: Option<String>,
     },
 }
 
+/// How far a relational rule searches for its target, ast-grep's `stopBy`
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StopBy {
+    /// Only the parent, the direct children or the adjacent sibling
+    #[default]
+    Neighbor,
+    /// Every ancestor, descendant or sibling in that direction
+    End,
+    /// Up to and including the first node matching the rule
+    #[serde(untagged)]
+    Rule(Box<Rule>),
+}
+
+impl StopBy {
+    fn replace_matches<E>(
+        &self,
+        resolve: &mut impl FnMut(&str) -> Result<Rule, E>,
+    ) -> Result<StopBy, E> {
+        Ok(match self {
+            StopBy::Rule(stop) => StopBy::Rule(Box::new(stop.replace_matches(resolve)?)),
+            _ => self.clone(),
+        })
+    }
+}
+
 /// Pattern rule with optional advanced features
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PatternRule {
@@ -88,6 +126,151 @@ impl Rule {
         matches!(self, Rule::Pattern(_))
     }
 
+    /// Check if this rule or any of its sub-rules is an inside, has, follows or precedes rule
+    pub fn has_relation(&self) -> bool {
+        match self {
+            Rule::Inside { .. }
+            | Rule::Has { .. }
+            | Rule::Follows { .. }
+            | Rule::Precedes { .. } => true,
+            Rule::All(rules) | Rule::Any(rules) => rules.iter().any(Rule::has_relation),
+            Rule::Not(rule) => rule.has_relation(),
+            Rule::Pattern(_) | Rule::Kind(_) | Rule::Regex(_) | Rule::Matches(_) => false,
+        }
+    }
+
+    /// Check if this rule or any of its sub-rules matches nodes by `kind`
+    pub fn has_kind(&self) -> bool {
+        match self {
+            Rule::Kind(_) => true,
+            Rule::Pattern(_) | Rule::Regex(_) | Rule::Matches(_) => false,
+            Rule::All(rules) | Rule::Any(rules) => rules.iter().any(Rule::has_kind),
+            Rule::Not(rule) => rule.has_kind(),
+            Rule::Inside {
+                rule,
+                inside_of: target,
+                ..
+            }
+            | Rule::Has {
+                rule,
+                contains: target,
+                ..
+            }
+            | Rule::Follows {
+                rule,
+                after: target,
+                ..
+            }
+            | Rule::Precedes {
+                rule,
+                before: target,
+                ..
+            } => rule.has_kind() || target.has_kind(),
+        }
+    }
+
+    /// Check if this rule or any of its sub-rules refers to a utility rule with `matches`
+    pub fn has_matches(&self) -> bool {
+        match self {
+            Rule::Matches(_) => true,
+            Rule::Pattern(_) | Rule::Kind(_) | Rule::Regex(_) => false,
+            Rule::All(rules) | Rule::Any(rules) => rules.iter().any(Rule::has_matches),
+            Rule::Not(rule) => rule.has_matches(),
+            Rule::Inside {
+                rule,
+                inside_of: target,
+                stop_by,
+                ..
+            }
+            | Rule::Has {
+                rule,
+                contains: target,
+                stop_by,
+                ..
+            }
+            | Rule::Follows {
+                rule,
+                after: target,
+                stop_by,
+                ..
+            }
+            | Rule::Precedes {
+                rule,
+                before: target,
+                stop_by,
+                ..
+            } => {
+                rule.has_matches()
+                    || target.has_matches()
+                    || matches!(stop_by, StopBy::Rule(stop) if stop.has_matches())
+            }
+        }
+    }
+
+    /// A copy of this rule with every `matches` reference replaced by `resolve(name)`
+    pub fn replace_matches<E>(
+        &self,
+        resolve: &mut impl FnMut(&str) -> Result<Rule, E>,
+    ) -> Result<Rule, E> {
+        let mut replace_all = |rules: &[Rule]| -> Result<Vec<Rule>, E> {
+            rules
+                .iter()
+                .map(|rule| rule.replace_matches(resolve))
+                .collect()
+        };
+        Ok(match self {
+            Rule::Matches(name) => resolve(name)?,
+            Rule::Pattern(_) | Rule::Kind(_) | Rule::Regex(_) => self.clone(),
+            Rule::All(rules) => Rule::All(replace_all(rules)?),
+            Rule::Any(rules) => Rule::Any(replace_all(rules)?),
+            Rule::Not(rule) => Rule::Not(Box::new(rule.replace_matches(resolve)?)),
+            Rule::Inside {
+                rule,
+                inside_of,
+                stop_by,
+                field,
+            } => Rule::Inside {
+                rule: Box::new(rule.replace_matches(resolve)?),
+                inside_of: Box::new(inside_of.replace_matches(resolve)?),
+                stop_by: stop_by.replace_matches(resolve)?,
+                field: field.clone(),
+            },
+            Rule::Has {
+                rule,
+                contains,
+                stop_by,
+                field,
+            } => Rule::Has {
+                rule: Box::new(rule.replace_matches(resolve)?),
+                contains: Box::new(contains.replace_matches(resolve)?),
+                stop_by: stop_by.replace_matches(resolve)?,
+                field: field.clone(),
+            },
+            Rule::Follows {
+                rule,
+                after,
+                stop_by,
+                field,
+            } => Rule::Follows {
+                rule: Box::new(rule.replace_matches(resolve)?),
+                after: Box::new(after.replace_matches(resolve)?),
+                stop_by: stop_by.replace_matches(resolve)?,
+                field: field.clone(),
+            },
+            Rule::Precedes {
+                rule,
+                before,
+                stop_by,
+                field,
+            } => Rule::Precedes {
+                rule: Box::new(rule.replace_matches(resolve)?),
+                before: Box::new(before.replace_matches(resolve)?),
+                stop_by: stop_by.replace_matches(resolve)?,
+                field: field.clone(),
+            },
+        })
+    }
+
     /// Recursively extract all patterns from composite rules
     pub fn extract_all_patterns(&self) -> Vec<String> {
         let mut patterns = Vec::new();
@@ -117,12 +300,16 @@ impl Rule {
     }
 }
 
-/// Convert from the old RuleObject structure to the new Rule enum
+/// Convert from the old RuleObject structure to the new Rule enum. A rule object setting
+/// several conditions, e.g. `pattern` and `inside`, matches nodes satisfying all of them, as in
+/// ast-grep
 impl From<super::types::RuleObject> for Rule {
     fn from(obj: super::types::RuleObject) -> Self {
+        let mut conditions = Vec::new();
+
         // Pattern rule
         if let Some(pattern_spec) = obj.pattern {
-            return match pattern_spec {
+            conditions.push(match pattern_spec {
                 super::types::PatternSpec::Simple(pattern) => {
                     Rule::Pattern(PatternRule::Simple { pattern })
                 }
@@ -136,82 +323,102 @@ impl From<super::types::RuleObject> for Rule {
                     selector,
                     strictness,
                 }),
-            };
+            });
         }
 
         // Kind rule
         if let Some(kind) = obj.kind {
-            return Rule::Kind(kind);
+            conditions.push(Rule::Kind(kind));
         }
 
         // Regex rule
         if let Some(regex) = obj.regex {
-            return Rule::Regex(regex);
+            conditions.push(Rule::Regex(regex));
         }
 
         // Matches rule
         if let Some(matches) = obj.matches {
-            return Rule::Matches(matches);
+            conditions.push(Rule::Matches(matches));
         }
 
         // Composite rules
         if let Some(all_rules) = obj.all {
-            return Rule::All(all_rules.into_iter().map(Rule::from).collect());
+            conditions.push(Rule::All(all_rules.into_iter().map(Rule::from).collect()));
         }
 
         if let Some(any_rules) = obj.any {
-            return Rule::Any(any_rules.into_iter().map(Rule::from).collect());
+            conditions.push(Rule::Any(any_rules.into_iter().map(Rule::from).collect()));
         }
 
         if let Some(not_rule) = obj.not {
-            return Rule::Not(Box::new(Rule::from(*not_rule)));
+            conditions.push(Rule::Not(Box::new(Rule::from(*not_rule))));
         }
 
-        // Inside relational rule - for standalone relational rules in 'all' arrays
+        // Relational rules, matched against any node unless combined with other conditions
+        let any_node = || {
+            Box::new(Rule::Pattern(PatternRule::Simple {
+                pattern: "$_".to_string(),
+            }))
+        };
         if let Some(inside_rule) = obj.inside {
-            return Rule::Inside {
-                rule: Box::new(Rule::Pattern(PatternRule::Simple {
-                    pattern: "$_".to_string(),
-                })),
-                inside_of: Box::new(Rule::from(*inside_rule)),
-            };
+            let (target, stop_by, field) = relation_target(*inside_rule);
+            conditions.push(Rule::Inside {
+                rule: any_node(),
+                inside_of: target,
+                stop_by,
+                field,
+            });
         }
 
-        // Has relational rule - for standalone relational rules in 'all' arrays
         if let Some(has_rule) = obj.has {
-            return Rule::Has {
-                rule: Box::new(Rule::Pattern(PatternRule::Simple {
-                    pattern: "$_".to_string(),
-                })),
-                contains: Box::new(Rule::from(*has_rule)),
-            };
+            let (target, stop_by, field) = relation_target(*has_rule);
+            conditions.push(Rule::Has {
+                rule: any_node(),
+                contains: target,
+                stop_by,
+                field,
+            });
         }
 
-        // Follows relational rule
         if let Some(follows_rule) = obj.follows {
-            return Rule::Follows {
-                rule: Box::new(Rule::Pattern(PatternRule::Simple {
-                    pattern: "$_".to_string(),
-                })),
-                after: Box::new(Rule::from(*follows_rule)),
-            };
+            let (target, stop_by, field) = relation_target(*follows_rule);
+            conditions.push(Rule::Follows {
+                rule: any_node(),
+                after: target,
+                stop_by,
+                field,
+            });
         }
 
-        // Precedes relational rule
         if let Some(precedes_rule) = obj.precedes {
-            return Rule::Precedes {
-                rule: Box::new(Rule::Pattern(PatternRule::Simple {
-                    pattern: "$_".to_string(),
-                })),
-                before: Box::new(Rule::from(*precedes_rule)),
-            };
+            let (target, stop_by, field) = relation_target(*precedes_rule);
+            conditions.push(Rule::Precedes {
+                rule: any_node(),
+                before: target,
+                stop_by,
+                field,
+            });
         }
 
-        // Default to an empty All rule if nothing matches
-        Rule::All(vec![])
+        // A single condition stands alone; none or several become an All rule
+        if conditions.len() == 1 {
+            conditions.remove(0)
+        } else {
+            Rule::All(conditions)
+        }
     }
 }
 
+/// The target rule of a relational sub-rule object with its `stopBy` and `field` options
+fn relation_target(mut obj: super::types::RuleObject) -> (Box<Rule>, StopBy, Option<String>) {
+    let stop_by = match obj.stop_by.take() {
+        None | Some(super::types::StopBySpec::Neighbor) => StopBy::Neighbor,
+        Some(super::types::StopBySpec::End) => StopBy::End,
+        Some(super::types::StopBySpec::Rule(stop)) => StopBy::Rule(Box::new(Rule::from(*stop))),
+    };
+    let field = obj.field.take();
+    (Box::new(Rule::from(obj)), stop_by, field)
+}
 /// Convert back to RuleObject for compatibility
 impl From<Rule> for super::types::RuleObject {
     fn from(rule: Rule) -> Self {
@@ -227,6 +434,8 @@ impl From<Rule> for super::types::RuleObject {
             any: None,
             not: None,
             matches: None,
+            stop_by: None,
+            field: None,
         };
 
         match rule {
@@ -86,22 +86,22 @@ impl From<RuleDeserializer> for Rule {
                 }
 
                 // Check for composite rules
-                if let Some(all_val) = map.remove("all") {
-                    if let Ok(rules) = serde_json::from_value::<Vec<RuleDeserializer>>(all_val) {
-                        return Rule::All(rules.into_iter().map(Rule::from).collect());
-                    }
+                if let Some(all_val) = map.remove("all")
+                    && let Ok(rules) = serde_json::from_value::<Vec<RuleDeserializer>>(all_val)
+                {
+                    return Rule::All(rules.into_iter().map(Rule::from).collect());
                 }
 
-                if let Some(any_val) = map.remove("any") {
-                    if let Ok(rules) = serde_json::from_value::<Vec<RuleDeserializer>>(any_val) {
-                        return Rule::Any(rules.into_iter().map(Rule::from).collect());
-                    }
+                if let Some(any_val) = map.remove("any")
+                    && let Ok(rules) = serde_json::from_value::<Vec<RuleDeserializer>>(any_val)
+                {
+                    return Rule::Any(rules.into_iter().map(Rule::from).collect());
                 }
 
-                if let Some(not_val) = map.remove("not") {
-                    if let Ok(rule) = serde_json::from_value::<RuleDeserializer>(not_val) {
-                        return Rule::Not(Box::new(Rule::from(rule)));
-                    }
+                if let Some(not_val) = map.remove("not")
+                    && let Ok(rule) = serde_json::from_value::<RuleDeserializer>(not_val)
+                {
+                    return Rule::Not(Box::new(Rule::from(rule)));
                 }
 
                 // Default to empty All rule
@@ -0,0 +1,473 @@
+//! # Rule Conversion
+//!
+//! Best-effort translation of existing lint rules into ast-grep rule configurations, to ease
+//! migrating a lint suite. Two source formats are understood:
+//!
+//! - **Semgrep** YAML (`rules:` list). Pattern operators (`pattern`, `patterns`,
+//!   `pattern-either`, `pattern-not`, `pattern-inside`, `pattern-not-inside`, `pattern-regex`,
+//!   `pattern-not-regex`) map onto `pattern`/`all`/`any`/`not`/`inside`/`regex`. Ellipses
+//!   become `$$$` and `$...ARGS` becomes `$$$ARGS`.
+//! - **ESLint** configs (`rules:` mapping). Only core rules with a structural equivalent are
+//!   converted; everything else is reported as unconvertible.
+//!
+//! Constructs without an equivalent (metavariable conditions, taint mode, deep expression
+//! operators, esquery selectors...) are reported per rule rather than silently dropped.
+
+use super::types::{ConvertRuleParam, ConvertRuleResult, ConvertedRule, UnconvertedRule};
+use crate::errors::ServiceError;
+use ast_grep_language::SupportLang as Language;
+use regex::Regex;
+use serde_yaml::{Mapping, Value};
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+const SEMGREP: &str = "semgrep";
+const ESLINT: &str = "eslint";
+
+static NAMED_ELLIPSIS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$\.\.\.([A-Z_][A-Z0-9_]*)").unwrap());
+
+/// Convert every rule in `param.source`
+pub fn convert_rules(param: &ConvertRuleParam) -> Result<ConvertRuleResult, ServiceError> {
+    let source: Value = serde_yaml::from_str(&param.source)?;
+    let format = match param.format.as_deref() {
+        Some(format) => format.to_ascii_lowercase(),
+        None => detect_format(&source)?.to_string(),
+    };
+
+    let mut result = ConvertRuleResult {
+        format: format.clone(),
+        rules: Vec::new(),
+        unconverted: Vec::new(),
+    };
+    match format.as_str() {
+        SEMGREP => convert_semgrep(&source, &mut result)?,
+        ESLINT => {
+            let language = param.language.as_deref().unwrap_or("javascript");
+            convert_eslint(&source, language, &mut result)?
+        }
+        other => {
+            return Err(ServiceError::ParserError(format!(
+                "Unknown rule format '{other}'. Expected 'semgrep' or 'eslint'"
+            )));
+        }
+    }
+    Ok(result)
+}
+
+fn detect_format(source: &Value) -> Result<&'static str, ServiceError> {
+    match source.get("rules") {
+        Some(Value::Sequence(_)) => Ok(SEMGREP),
+        Some(Value::Mapping(_)) => Ok(ESLINT),
+        _ => Err(ServiceError::ParserError(
+            "Could not detect rule format: expected a Semgrep `rules:` list or an ESLint `rules:` mapping".to_string(),
+        )),
+    }
+}
+
+/// Serialize a rule configuration with keys in the conventional order
+fn rule_yaml(
+    id: &str,
+    language: &str,
+    severity: Option<&str>,
+    message: Option<&str>,
+    rule: Value,
+    fix: Option<String>,
+) -> Result<String, ServiceError> {
+    let mut config = Mapping::new();
+    config.insert("id".into(), id.into());
+    config.insert("language".into(), language.into());
+    if let Some(severity) = severity {
+        config.insert("severity".into(), severity.into());
+    }
+    if let Some(message) = message {
+        config.insert("message".into(), message.trim().into());
+    }
+    config.insert("rule".into(), rule);
+    if let Some(fix) = fix {
+        config.insert("fix".into(), fix.into());
+    }
+    Ok(serde_yaml::to_string(&config)?)
+}
+
+fn single(key: &str, value: impl Into<Value>) -> Value {
+    let mut mapping = Mapping::new();
+    mapping.insert(key.into(), value.into());
+    Value::Mapping(mapping)
+}
+
+// ---------------------------------------------------------------------------------------------
+// Semgrep
+// ---------------------------------------------------------------------------------------------
+
+fn convert_semgrep(source: &Value, result: &mut ConvertRuleResult) -> Result<(), ServiceError> {
+    let Some(Value::Sequence(rules)) = source.get("rules") else {
+        return Err(ServiceError::ParserError(
+            "Semgrep source must have a `rules:` list".to_string(),
+        ));
+    };
+
+    for (index, rule) in rules.iter().enumerate() {
+        let id = rule
+            .get("id")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("rule-{}", index + 1));
+        match convert_semgrep_rule(&id, rule) {
+            Ok(converted) => result.rules.push(converted),
+            Err(reason) => result.unconverted.push(UnconvertedRule { id, reason }),
+        }
+    }
+    Ok(())
+}
+
+fn convert_semgrep_rule(id: &str, rule: &Value) -> Result<ConvertedRule, String> {
+    let mut notes = Vec::new();
+
+    if let Some(mode) = rule.get("mode").and_then(Value::as_str)
+        && mode != "search"
+    {
+        return Err(format!("`mode: {mode}` has no ast-grep equivalent"));
+    }
+
+    let languages: Vec<&str> = match rule.get("languages") {
+        Some(Value::Sequence(languages)) => languages.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    let language = languages
+        .iter()
+        .find_map(|name| semgrep_language(name))
+        .ok_or_else(|| match languages.as_slice() {
+            [] => "no `languages` given".to_string(),
+            names => format!("unsupported languages: {}", names.join(", ")),
+        })?;
+    if languages.len() > 1 {
+        notes.push(format!(
+            "Semgrep rule targets {}; converted for {language} only",
+            languages.join(", ")
+        ));
+    }
+
+    let formula = semgrep_formula(rule)?;
+
+    let severity = rule
+        .get("severity")
+        .and_then(Value::as_str)
+        .and_then(|severity| match severity.to_ascii_uppercase().as_str() {
+            "ERROR" => Some("error"),
+            "WARNING" => Some("warning"),
+            "INFO" => Some("info"),
+            _ => None,
+        });
+
+    let fix = match rule.get("fix").and_then(Value::as_str) {
+        Some(fix) => Some(semgrep_pattern(fix)?),
+        None => None,
+    };
+    if rule.get("fix-regex").is_some() {
+        notes.push("`fix-regex` was dropped; ast-grep fixes are templates".to_string());
+    }
+
+    let message = rule.get("message").and_then(Value::as_str);
+    let rule_config =
+        rule_yaml(id, &language, severity, message, formula, fix).map_err(|e| e.to_string())?;
+    Ok(ConvertedRule {
+        id: id.to_string(),
+        rule_config,
+        notes,
+    })
+}
+
+fn semgrep_language(name: &str) -> Option<String> {
+    let name = match name.to_ascii_lowercase().as_str() {
+        "c++" => "cpp".to_string(),
+        "c#" => "csharp".to_string(),
+        "sh" => "bash".to_string(),
+        other => other.to_string(),
+    };
+    Language::from_str(&name).ok().map(|_| name)
+}
+
+/// The top-level search formula of a Semgrep rule
+fn semgrep_formula(rule: &Value) -> Result<Value, String> {
+    const TOP_LEVEL: [&str; 4] = ["pattern", "patterns", "pattern-either", "pattern-regex"];
+    let mut operators = TOP_LEVEL.iter().filter(|key| rule.get(**key).is_some());
+    match (operators.next(), operators.next()) {
+        (Some(key), None) => semgrep_operator(key, &rule[*key]),
+        (None, _) => {
+            if rule.get("match").is_some() {
+                Err(
+                    "Semgrep `match:` syntax is not supported; use `pattern`/`patterns`"
+                        .to_string(),
+                )
+            } else {
+                Err("no pattern operator found".to_string())
+            }
+        }
+        (Some(first), Some(second)) => Err(format!(
+            "rule has both `{first}` and `{second}` at the top level"
+        )),
+    }
+}
+
+fn semgrep_operator(key: &str, value: &Value) -> Result<Value, String> {
+    let text = || {
+        value
+            .as_str()
+            .ok_or_else(|| format!("`{key}` must be a string"))
+    };
+    let list = || match value {
+        Value::Sequence(items) => Ok(items),
+        _ => Err(format!("`{key}` must be a list")),
+    };
+    let inside = |pattern: &str| -> Result<Value, String> {
+        let mut mapping = Mapping::new();
+        mapping.insert("pattern".into(), semgrep_pattern(pattern)?.into());
+        mapping.insert("stopBy".into(), "end".into());
+        Ok(single("inside", Value::Mapping(mapping)))
+    };
+
+    match key {
+        "pattern" => Ok(single("pattern", semgrep_pattern(text()?)?)),
+        "pattern-regex" => Ok(single("regex", text()?)),
+        "pattern-not" => Ok(single("not", single("pattern", semgrep_pattern(text()?)?))),
+        "pattern-not-regex" => Ok(single("not", single("regex", text()?))),
+        "pattern-inside" => inside(text()?),
+        "pattern-not-inside" => Ok(single("not", inside(text()?)?)),
+        "pattern-either" => {
+            let alternatives = list()?
+                .iter()
+                .map(semgrep_item)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(single("any", Value::Sequence(alternatives)))
+        }
+        "patterns" => {
+            let mut conditions = list()?
+                .iter()
+                .map(semgrep_item)
+                .collect::<Result<Vec<_>, _>>()?;
+            if conditions.len() == 1 {
+                return Ok(conditions.remove(0));
+            }
+            Ok(single("all", Value::Sequence(conditions)))
+        }
+        other => Err(format!("`{other}` has no ast-grep equivalent")),
+    }
+}
+
+/// One entry of a `patterns` or `pattern-either` list: a single-operator mapping
+fn semgrep_item(item: &Value) -> Result<Value, String> {
+    let Value::Mapping(mapping) = item else {
+        return Err("pattern list entries must be mappings".to_string());
+    };
+    let mut entries = mapping.iter();
+    match (entries.next(), entries.next()) {
+        (Some((Value::String(key), value)), None) => semgrep_operator(key, value),
+        _ => Err("pattern list entries must have exactly one operator".to_string()),
+    }
+}
+
+/// Translate Semgrep pattern syntax into ast-grep pattern syntax
+fn semgrep_pattern(pattern: &str) -> Result<String, String> {
+    if pattern.contains("<...") || pattern.contains("...>") {
+        return Err("deep expression operator `<... ...>` has no pattern equivalent".to_string());
+    }
+    if pattern.contains("\"...\"") || pattern.contains("'...'") {
+        return Err("string ellipsis `\"...\"` has no pattern equivalent".to_string());
+    }
+    let pattern = NAMED_ELLIPSIS.replace_all(pattern, "$$$$$$$1");
+    Ok(pattern.replace("...", "$$$").trim().to_string())
+}
+
+// ---------------------------------------------------------------------------------------------
+// ESLint
+// ---------------------------------------------------------------------------------------------
+
+fn convert_eslint(
+    source: &Value,
+    language: &str,
+    result: &mut ConvertRuleResult,
+) -> Result<(), ServiceError> {
+    let Some(Value::Mapping(rules)) = source.get("rules") else {
+        return Err(ServiceError::ParserError(
+            "ESLint source must have a `rules:` mapping".to_string(),
+        ));
+    };
+    if Language::from_str(language).is_err() {
+        return Err(ServiceError::ParserError(format!(
+            "Unsupported language: {language}"
+        )));
+    }
+
+    for (name, setting) in rules {
+        let Some(name) = name.as_str() else { continue };
+        let (level, options) = match setting {
+            Value::Sequence(items) if !items.is_empty() => (&items[0], &items[1..]),
+            other => (other, &[][..]),
+        };
+        let severity = match level {
+            Value::String(level) if level == "error" => "error",
+            Value::String(level) if level == "warn" => "warning",
+            Value::Number(level) if level.as_u64() == Some(2) => "error",
+            Value::Number(level) if level.as_u64() == Some(1) => "warning",
+            // "off" / 0
+            _ => continue,
+        };
+
+        let id = name.replace('/', "-");
+        match eslint_rule(name, options) {
+            Ok((rule, message, notes)) => {
+                let rule_config =
+                    rule_yaml(&id, language, Some(severity), Some(&message), rule, None)?;
+                result.rules.push(ConvertedRule {
+                    id,
+                    rule_config,
+                    notes,
+                });
+            }
+            Err(reason) => result.unconverted.push(UnconvertedRule { id, reason }),
+        }
+    }
+    Ok(())
+}
+
+fn any_pattern(patterns: &[String]) -> Value {
+    match patterns {
+        [pattern] => single("pattern", pattern.as_str()),
+        _ => single(
+            "any",
+            Value::Sequence(
+                patterns
+                    .iter()
+                    .map(|pattern| single("pattern", pattern.as_str()))
+                    .collect(),
+            ),
+        ),
+    }
+}
+
+/// The rule, message and notes for a core ESLint rule
+fn eslint_rule(name: &str, options: &[Value]) -> Result<(Value, String, Vec<String>), String> {
+    let mut notes = Vec::new();
+    let option = options.first();
+
+    let (rule, message) = match name {
+        "no-console" => {
+            let pattern = single("pattern", "console.$METHOD($$$ARGS)");
+            let allowed: Vec<String> = option
+                .and_then(|option| option.get("allow"))
+                .and_then(Value::as_sequence)
+                .map(|allow| {
+                    allow
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(|method| format!("console.{method}($$$ARGS)"))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let rule = if allowed.is_empty() {
+                pattern
+            } else {
+                single(
+                    "all",
+                    Value::Sequence(vec![pattern, single("not", any_pattern(&allowed))]),
+                )
+            };
+            (rule, "Unexpected console statement.")
+        }
+        "no-debugger" => (
+            single("kind", "debugger_statement"),
+            "Unexpected 'debugger' statement.",
+        ),
+        "no-eval" => (single("pattern", "eval($$$ARGS)"), "eval can be harmful."),
+        "no-alert" => (
+            any_pattern(&[
+                "alert($$$ARGS)".to_string(),
+                "confirm($$$ARGS)".to_string(),
+                "prompt($$$ARGS)".to_string(),
+            ]),
+            "Unexpected alert, confirm or prompt.",
+        ),
+        "no-var" => (
+            single("kind", "variable_declaration"),
+            "Unexpected var, use let or const instead.",
+        ),
+        "no-with" => (
+            single("kind", "with_statement"),
+            "Unexpected use of 'with' statement.",
+        ),
+        "no-new-func" => (
+            any_pattern(&[
+                "new Function($$$ARGS)".to_string(),
+                "Function($$$ARGS)".to_string(),
+            ]),
+            "The Function constructor is eval.",
+        ),
+        "eqeqeq" => {
+            if option.and_then(Value::as_str) == Some("smart") {
+                notes.push(
+                    "`smart` exceptions (typeof, null, literals) are not modelled".to_string(),
+                );
+            }
+            (
+                any_pattern(&["$A == $B".to_string(), "$A != $B".to_string()]),
+                "Expected '===' and '!==' instead of '==' and '!='.",
+            )
+        }
+        "no-restricted-properties" => {
+            let patterns = options
+                .iter()
+                .map(|restriction| {
+                    let object = restriction.get("object").and_then(Value::as_str);
+                    let property = restriction.get("property").and_then(Value::as_str);
+                    match (object, property) {
+                        (Some(object), Some(property)) => Ok(format!("{object}.{property}")),
+                        (Some(object), None) => Ok(format!("{object}.$PROP")),
+                        (None, Some(property)) => Ok(format!("$OBJ.{property}")),
+                        (None, None) => {
+                            Err("restriction without `object` or `property`".to_string())
+                        }
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if patterns.is_empty() {
+                return Err("no restricted properties listed".to_string());
+            }
+            (
+                any_pattern(&patterns),
+                "Use of this property is restricted.",
+            )
+        }
+        "no-restricted-globals" => {
+            let names: Vec<String> = options
+                .iter()
+                .filter_map(|global| {
+                    global
+                        .as_str()
+                        .or_else(|| global.get("name").and_then(Value::as_str))
+                })
+                .map(regex::escape)
+                .collect();
+            if names.is_empty() {
+                return Err("no restricted globals listed".to_string());
+            }
+            notes.push("also matches local bindings that shadow the global".to_string());
+            let mut rule = Mapping::new();
+            rule.insert("kind".into(), "identifier".into());
+            rule.insert("regex".into(), format!("^({})$", names.join("|")).into());
+            (Value::Mapping(rule), "Use of this global is restricted.")
+        }
+        "no-restricted-syntax" => {
+            return Err(
+                "esquery selectors are not converted; rewrite as a pattern or kind rule"
+                    .to_string(),
+            );
+        }
+        other => {
+            return Err(format!(
+                "no structural equivalent known for ESLint rule `{other}`"
+            ));
+        }
+    };
+    Ok((rule, message.to_string(), notes))
+}
@@ -1,22 +1,32 @@
 use super::ast::{PatternRule, Rule};
-use super::types::{PatternSpec, RuleObject};
+use super::matcher::{Budgeted, NodeRule};
+use super::types::{ConstraintViolation, PatternSpec, RuleConfig, RuleObject};
 use crate::errors::ServiceError;
 use crate::types::MatchResult;
+use ast_grep_core::matcher::MatcherExt;
 use ast_grep_core::{AstGrep, Pattern};
 use ast_grep_language::SupportLang as Language;
 use regex::Regex;
-use std::collections::HashMap;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 #[derive(Clone)]
 pub struct RuleEvaluator {
     pattern_cache: Arc<Mutex<HashMap<String, Pattern>>>,
+    /// Evaluations fail with [`ServiceError::BudgetExceeded`] once this passes
+    deadline: Option<Instant>,
+    /// Utility rules that `matches` refers to, with their own references already resolved
+    utils: Arc<HashMap<String, Rule>>,
 }
 
 impl Default for RuleEvaluator {
     fn default() -> Self {
         Self {
             pattern_cache: Arc::new(Mutex::new(HashMap::new())),
+            deadline: None,
+            utils: Arc::new(HashMap::new()),
         }
     }
 }
@@ -26,6 +36,166 @@ impl RuleEvaluator {
         Self::default()
     }
 
+    /// An evaluator sharing this one's pattern cache whose evaluations give up at `deadline`.
+    /// The deadline is checked between sub-rules, while filtering candidates and before each
+    /// node a relational rule is matched against, so a rule with expensive relational checks
+    /// stops part way through a file
+    pub fn with_deadline(&self, deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..self.clone()
+        }
+    }
+
+    /// An evaluator that also resolves `matches` references to `utils`, the `utils` section
+    /// of a rule config. Utils may refer to each other but not, directly or not, to themselves
+    pub fn with_utils(&self, utils: &BTreeMap<String, RuleObject>) -> Result<Self, ServiceError> {
+        if utils.is_empty() {
+            return Ok(self.clone());
+        }
+        let declared: HashMap<&str, Rule> = utils
+            .iter()
+            .map(|(name, rule)| (name.as_str(), Rule::from(rule.clone())))
+            .collect();
+        let mut resolved = (*self.utils).clone();
+        for name in utils.keys() {
+            resolve_util(name, &declared, &mut resolved, &mut Vec::new())?;
+        }
+        Ok(Self {
+            utils: Arc::new(resolved),
+            ..self.clone()
+        })
+    }
+
+    /// Evaluate the rule of `config` against code, with the utility rules and the constraints
+    /// it declares
+    pub fn evaluate_rule_config(
+        &self,
+        config: &RuleConfig,
+        code: &str,
+        lang: Language,
+    ) -> Result<Vec<MatchResult>, ServiceError> {
+        Ok(self.evaluate_with_violations(config, code, lang)?.0)
+    }
+
+    /// [`Self::evaluate_rule_config`], also returning the matches its constraints dropped
+    pub fn evaluate_with_violations(
+        &self,
+        config: &RuleConfig,
+        code: &str,
+        lang: Language,
+    ) -> Result<(Vec<MatchResult>, Vec<ConstraintViolation>), ServiceError> {
+        let evaluator = self.with_utils(&config.utils)?;
+        let matches = evaluator.evaluate_rule_against_code(&config.rule, code, lang)?;
+        evaluator.check_constraints(matches, &config.constraints, code, lang)
+    }
+
+    /// The `matches` whose metavariable captures satisfy `constraints`, the `constraints`
+    /// section of a rule config
+    pub fn apply_constraints(
+        &self,
+        matches: Vec<MatchResult>,
+        constraints: &BTreeMap<String, RuleObject>,
+        code: &str,
+        lang: Language,
+    ) -> Result<Vec<MatchResult>, ServiceError> {
+        Ok(self.check_constraints(matches, constraints, code, lang)?.0)
+    }
+
+    /// Split `matches` into those whose metavariable captures satisfy `constraints` and the
+    /// violations of the others. A capture is checked as the node it was taken from, so
+    /// `kind`, `pattern` and relational constraints work as well as `regex`. A constraint on a
+    /// metavariable that a match didn't capture doesn't apply to it
+    pub fn check_constraints(
+        &self,
+        matches: Vec<MatchResult>,
+        constraints: &BTreeMap<String, RuleObject>,
+        code: &str,
+        lang: Language,
+    ) -> Result<(Vec<MatchResult>, Vec<ConstraintViolation>), ServiceError> {
+        if constraints.is_empty() || matches.is_empty() {
+            return Ok((matches, Vec::new()));
+        }
+        let compiled = constraints
+            .iter()
+            .map(|(name, rule)| {
+                let rule = self.resolve_matches(&Rule::from(rule.clone()))?;
+                Ok((name.as_str(), self.compile_node_rule(&rule, lang)?))
+            })
+            .collect::<Result<Vec<_>, ServiceError>>()?;
+        let ast = AstGrep::new(code, lang);
+        let root = ast.root();
+
+        let mut kept = Vec::new();
+        let mut violations = Vec::new();
+        for candidate in matches {
+            self.check_budget()?;
+            let node = root.dfs().find(|node| {
+                let (start, end) = (node.start_pos(), node.end_pos());
+                start.line() == candidate.start_line
+                    && start.column(node) == candidate.start_col
+                    && end.line() == candidate.end_line
+                    && end.column(node) == candidate.end_col
+            });
+            // The captured node is the one below the match with the captured text
+            let violation = compiled.iter().find_map(|(name, rule)| {
+                let captured = candidate.vars.get(*name)?;
+                let satisfied = node.as_ref().is_some_and(|node| {
+                    node.dfs().any(|var| {
+                        var.text() == captured.as_str() && rule.match_node(var).is_some()
+                    })
+                });
+                (!satisfied).then(|| ConstraintViolation {
+                    text: candidate.text.clone(),
+                    start_line: candidate.start_line,
+                    metavariable: name.to_string(),
+                    captured: captured.clone(),
+                })
+            });
+            match violation {
+                Some(violation) => violations.push(violation),
+                None => kept.push(candidate),
+            }
+        }
+        Ok((kept, violations))
+    }
+
+    /// `rule` with its `matches` references replaced by the registered utility rules
+    pub(crate) fn resolve_matches(&self, rule: &Rule) -> Result<Rule, ServiceError> {
+        rule.replace_matches(&mut |name| {
+            self.utils.get(name).cloned().ok_or_else(|| {
+                ServiceError::ParserError(format!(
+                    "'matches: {name}' refers to a util rule that is not defined in utils"
+                ))
+            })
+        })
+    }
+
+    fn check_budget(&self) -> Result<(), ServiceError> {
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(ServiceError::BudgetExceeded(
+                "rule evaluation ran past its time budget".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The `candidates` that `keep` accepts, checking the time budget for each one
+    fn retain_within_budget(
+        &self,
+        candidates: Vec<MatchResult>,
+        mut keep: impl FnMut(&MatchResult) -> bool,
+    ) -> Result<Vec<MatchResult>, ServiceError> {
+        let mut kept = Vec::new();
+        for candidate in candidates {
+            self.check_budget()?;
+            if keep(&candidate) {
+                kept.push(candidate);
+            }
+        }
+        Ok(kept)
+    }
+
     /// Evaluate a Rule enum against code
     pub fn evaluate_rule(
         &self,
@@ -33,31 +203,31 @@ impl RuleEvaluator {
         code: &str,
         lang: Language,
     ) -> Result<Vec<MatchResult>, ServiceError> {
+        self.check_budget()?;
+        if rule.has_matches() {
+            return self.evaluate_rule(&self.resolve_matches(rule)?, code, lang);
+        }
+        // Relations need the syntax tree around each node, and a kind combined with other rules
+        // has to hold for the same node they match, so such rules are matched node by node; the
+        // other rules keep comparing the positions of their sub-rules' matches. A lone `not`
+        // still takes its candidates from every named node
+        if rule.has_relation() || (rule.has_kind() && !matches!(rule, Rule::Not(_))) {
+            return self.evaluate_node_rule(rule, code, lang);
+        }
         match rule {
             Rule::Pattern(pattern_rule) => {
                 self.evaluate_pattern_rule_enum(pattern_rule, code, lang)
             }
             Rule::Kind(kind) => self.evaluate_kind_rule(kind, code, lang),
             Rule::Regex(regex) => self.evaluate_regex_rule(regex, code, lang),
-            Rule::Matches(_matches) => {
-                // TODO: Implement matches rule - needs access to rule storage
-                Err(ServiceError::ParserError(
-                    "Matches rule evaluation not yet implemented".into(),
-                ))
-            }
+            Rule::Matches(_) => unreachable!("matches rules are resolved above"),
             Rule::All(rules) => self.evaluate_all_rule_enum(rules, code, lang),
             Rule::Any(rules) => self.evaluate_any_rule_enum(rules, code, lang),
             Rule::Not(rule) => self.evaluate_not_rule_enum(rule, code, lang),
-            Rule::Inside { rule, inside_of } => {
-                self.evaluate_inside_rule_enum(rule, inside_of, code, lang)
-            }
-            Rule::Has { rule, contains } => self.evaluate_has_rule_enum(rule, contains, code, lang),
-            Rule::Follows { rule, after } => {
-                self.evaluate_follows_rule_enum(rule, after, code, lang)
-            }
-            Rule::Precedes { rule, before } => {
-                self.evaluate_precedes_rule_enum(rule, before, code, lang)
-            }
+            Rule::Inside { .. }
+            | Rule::Has { .. }
+            | Rule::Follows { .. }
+            | Rule::Precedes { .. } => self.evaluate_node_rule(rule, code, lang),
         }
     }
 
@@ -248,32 +418,15 @@ impl RuleEvaluator {
         Ok(filtered_matches)
     }
 
+    /// Every node of `kind`, a tree-sitter node kind of `lang` such as `call_expression` (see
+    /// generate_ast). Fails for a kind the language doesn't have
     fn evaluate_kind_rule(
         &self,
         kind: &str,
         code: &str,
         lang: Language,
     ) -> Result<Vec<MatchResult>, ServiceError> {
-        let ast = AstGrep::new(code, lang);
-
-        // Use a catch-all pattern to find all nodes, then filter by kind
-        let catch_all_pattern = "$_";
-        match self.get_or_create_pattern(catch_all_pattern, lang) {
-            Ok(pattern) => {
-                let all_matches: Vec<MatchResult> = ast
-                    .root()
-                    .find_all(pattern)
-                    .filter(|node| node.get_node().kind() == kind)
-                    .map(|node| MatchResult::from_node_match(&node))
-                    .collect();
-
-                Ok(all_matches)
-            }
-            Err(_) => {
-                // If that fails, return empty results
-                Ok(vec![])
-            }
-        }
+        self.evaluate_node_rule(&Rule::Kind(kind.to_string()), code, lang)
     }
 
     fn evaluate_regex_rule(
@@ -297,6 +450,12 @@ impl RuleEvaluator {
                     vars: HashMap::new(),
                     context_before: None,
                     context_after: None,
+                    enclosing_symbol: None,
+                    ast_context: None,
+                    quick_fixes: Vec::new(),
+                    enclosing_node: None,
+                    leading_trivia: Vec::new(),
+                    rule_id: None,
                 });
             }
         }
@@ -480,84 +639,31 @@ impl RuleEvaluator {
     }
 
     /// Filter candidates based on a rule constraint
-    fn filter_candidates_by_rule(
+    pub(crate) fn filter_candidates_by_rule(
         &self,
         candidates: Vec<MatchResult>,
         rule: &Rule,
         code: &str,
         lang: Language,
     ) -> Result<Vec<MatchResult>, ServiceError> {
+        if rule.has_matches() {
+            let rule = self.resolve_matches(rule)?;
+            return self.filter_candidates_by_rule(candidates, &rule, code, lang);
+        }
         match rule {
-            // For Has rules: keep candidates that contain the pattern
-            Rule::Has { rule: _, contains } => {
-                let mut filtered = Vec::new();
-                let contains_matches = self.evaluate_rule(contains, code, lang)?;
-
-                for candidate in candidates {
-                    // Check if any contains_match is within this candidate
-                    let has_match = contains_matches
-                        .iter()
-                        .any(|m| self.is_match_contained_in(&candidate, m));
-
-                    if has_match {
-                        filtered.push(candidate);
-                    }
-                }
-                Ok(filtered)
-            }
-
-            // For Inside rules: keep candidates that are inside the pattern
-            Rule::Inside { rule: _, inside_of } => {
-                let mut filtered = Vec::new();
-                let container_matches = self.evaluate_rule(inside_of, code, lang)?;
-
-                for candidate in candidates {
-                    // Check if this candidate is inside any container
-                    let is_inside = container_matches
-                        .iter()
-                        .any(|container| self.is_match_contained_in(container, &candidate));
-
-                    if is_inside {
-                        filtered.push(candidate);
-                    }
-                }
-                Ok(filtered)
+            // For rules with relations or kinds: check each candidate's node in the syntax tree
+            _ if rule.has_relation() || rule.has_kind() => {
+                self.filter_candidates_by_node_rule(candidates, rule, code, lang)
             }
 
-            // For Not rules: keep candidates that don't match the sub-rule
+            // For Not rules: keep candidates that don't overlap a match of the sub-rule
             Rule::Not(not_rule) => {
-                match not_rule.as_ref() {
-                    // Special handling for "not has" pattern
-                    Rule::Has { rule: _, contains } => {
-                        let mut filtered = Vec::new();
-                        let contains_matches = self.evaluate_rule(contains, code, lang)?;
-
-                        for candidate in candidates {
-                            // Check if any contains_match is within this candidate
-                            let has_match = contains_matches
-                                .iter()
-                                .any(|m| self.is_match_contained_in(&candidate, m));
-
-                            // Keep candidates that DON'T have the match
-                            if !has_match {
-                                filtered.push(candidate);
-                            }
-                        }
-                        Ok(filtered)
-                    }
-                    _ => {
-                        // For other not rules, evaluate normally
-                        let not_matches = self.evaluate_rule(not_rule, code, lang)?;
-                        Ok(candidates
-                            .into_iter()
-                            .filter(|candidate| {
-                                !not_matches
-                                    .iter()
-                                    .any(|m| self.matches_overlap(candidate, m))
-                            })
-                            .collect())
-                    }
-                }
+                let not_matches = self.evaluate_rule(not_rule, code, lang)?;
+                self.retain_within_budget(candidates, |candidate| {
+                    !not_matches
+                        .iter()
+                        .any(|m| self.matches_overlap(candidate, m))
+                })
             }
 
             // For Regex rules: check if candidate text matches the regex
@@ -572,14 +678,11 @@ impl RuleEvaluator {
             // For other rules: use overlap logic
             _ => {
                 let rule_matches = self.evaluate_rule(rule, code, lang)?;
-                Ok(candidates
-                    .into_iter()
-                    .filter(|candidate| {
-                        rule_matches
-                            .iter()
-                            .any(|m| self.matches_overlap(candidate, m))
-                    })
-                    .collect())
+                self.retain_within_budget(candidates, |candidate| {
+                    rule_matches
+                        .iter()
+                        .any(|m| self.matches_overlap(candidate, m))
+                })
             }
         }
     }
@@ -627,163 +730,109 @@ impl RuleEvaluator {
 
         // Return nodes that don't match the NOT rule
         // We exclude both exact matches and nodes that overlap with NOT match text
-        let filtered_matches: Vec<MatchResult> = all_nodes
-            .into_iter()
-            .filter(|node| {
-                !not_matches.iter().any(|not_match| {
-                    // Exact match (same position and text)
-                    (node.start_line == not_match.start_line
-                        && node.start_col == not_match.start_col
-                        && node.text == not_match.text)
-                    // Or NOT match contains the node text (node is a substring of what we want to exclude)
-                    || not_match.text.contains(&node.text)
-                    // Or node contains the NOT match text (node is a parent of what we want to exclude)
-                    || node.text.contains(&not_match.text)
-                })
+        self.retain_within_budget(all_nodes, |node| {
+            !not_matches.iter().any(|not_match| {
+                // Exact match (same position and text)
+                (node.start_line == not_match.start_line
+                    && node.start_col == not_match.start_col
+                    && node.text == not_match.text)
+                // Or NOT match contains the node text (node is a substring of what we want to exclude)
+                || not_match.text.contains(&node.text)
+                // Or node contains the NOT match text (node is a parent of what we want to exclude)
+                || node.text.contains(&not_match.text)
             })
-            .collect();
-
-        Ok(filtered_matches)
+        })
     }
 
-    fn evaluate_inside_rule_enum(
-        &self,
-        rule: &Rule,
-        inside_of: &Rule,
-        code: &str,
-        lang: Language,
-    ) -> Result<Vec<MatchResult>, ServiceError> {
-        // First, find all nodes that match the container rule
-        let container_matches = self.evaluate_rule(inside_of, code, lang)?;
-
-        // For each container, find nodes inside it that match the main rule
-        let mut results = Vec::new();
-        let ast = AstGrep::new(code, lang);
-
-        for container in &container_matches {
-            // Find the AST node for this container match
-            // We need to search for nodes within the container's range
-            if let Some(pattern) = rule.extract_pattern() {
-                let pattern_obj = self.get_or_create_pattern(&pattern, lang)?;
-
-                // Search within the container's text range
-                let container_start = container.start_line;
-                let container_end = container.end_line;
-
-                // Find all matches of the pattern
-                let all_matches: Vec<MatchResult> = ast
-                    .root()
-                    .find_all(pattern_obj)
-                    .filter_map(|node| {
-                        let match_result = MatchResult::from_node_match(&node);
-                        // Check if this match is within the container's range
-                        if match_result.start_line >= container_start
-                            && match_result.end_line <= container_end
-                        {
-                            Some(match_result)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-
-                results.extend(all_matches);
-            }
-        }
+    // Node-based evaluation, for rules with relations
 
-        // Remove duplicates
-        results.sort_by_key(|m| (m.start_line, m.start_col, m.text.clone()));
-        results.dedup_by_key(|m| (m.start_line, m.start_col, m.text.clone()));
-
-        Ok(results)
-    }
-
-    fn evaluate_has_rule_enum(
+    pub(crate) fn compile_node_rule(
         &self,
         rule: &Rule,
-        contains: &Rule,
-        code: &str,
         lang: Language,
-    ) -> Result<Vec<MatchResult>, ServiceError> {
-        // First, find all nodes that match the main rule
-        let main_matches = self.evaluate_rule(rule, code, lang)?;
-
-        // Then find all nodes that match the contains rule
-        let contained_matches = self.evaluate_rule(contains, code, lang)?;
-
-        // Filter main matches to only those that contain at least one of the contained matches
-        let filtered_matches: Vec<MatchResult> = main_matches
-            .into_iter()
-            .filter(|main_match| {
-                // Check if any contained match is within this main match's range
-                contained_matches.iter().any(|contained| {
-                    self.is_match_contained_in(main_match, contained)
-                        || self.matches_overlap(main_match, contained)
-                })
-            })
-            .collect();
-
-        Ok(filtered_matches)
+    ) -> Result<NodeRule, ServiceError> {
+        NodeRule::compile(rule, lang, &|pattern| {
+            self.get_or_create_pattern(pattern, lang)
+        })
     }
 
-    fn evaluate_follows_rule_enum(
+    /// Match `rule` against every node of `code` with [`NodeRule`]
+    fn evaluate_node_rule(
         &self,
         rule: &Rule,
-        after: &Rule,
         code: &str,
         lang: Language,
     ) -> Result<Vec<MatchResult>, ServiceError> {
-        // Find all nodes that match the main rule
-        let main_matches = self.evaluate_rule(rule, code, lang)?;
-
-        // Find all nodes that match the "after" rule
-        let after_matches = self.evaluate_rule(after, code, lang)?;
-
-        // Filter main matches to only those that follow at least one "after" match
-        let filtered_matches: Vec<MatchResult> = main_matches
-            .into_iter()
-            .filter(|main_match| {
-                // Check if this main match follows any "after" match
-                after_matches.iter().any(|after_match| {
-                    // The main match follows if it starts after the "after" match ends
-                    main_match.start_line > after_match.end_line
-                        || (main_match.start_line == after_match.end_line
-                            && main_match.start_col > after_match.end_col)
-                })
-            })
+        let node_rule = self.compile_node_rule(rule, lang)?;
+        let matcher = Budgeted {
+            rule: &node_rule,
+            deadline: self.deadline,
+            expired: Cell::new(false),
+        };
+        let ast = AstGrep::new(code, lang);
+        let matches: Vec<MatchResult> = ast
+            .root()
+            .find_all(&matcher)
+            .map(|node| MatchResult::from_node_match(&node))
             .collect();
-
-        Ok(filtered_matches)
+        if matcher.expired.get() {
+            self.check_budget()?;
+        }
+        Ok(matches)
     }
 
-    fn evaluate_precedes_rule_enum(
+    /// The `candidates` whose node satisfies `rule`. Candidates are found in the tree by their
+    /// position, so metavariables they captured don't constrain `rule`
+    fn filter_candidates_by_node_rule(
         &self,
+        candidates: Vec<MatchResult>,
         rule: &Rule,
-        before: &Rule,
         code: &str,
         lang: Language,
     ) -> Result<Vec<MatchResult>, ServiceError> {
-        // Find all nodes that match the main rule
-        let main_matches = self.evaluate_rule(rule, code, lang)?;
-
-        // Find all nodes that match the "before" rule
-        let before_matches = self.evaluate_rule(before, code, lang)?;
-
-        // Filter main matches to only those that precede at least one "before" match
-        let filtered_matches: Vec<MatchResult> = main_matches
-            .into_iter()
-            .filter(|main_match| {
-                // Check if this main match precedes any "before" match
-                before_matches.iter().any(|before_match| {
-                    // The main match precedes if it ends before the "before" match starts
-                    main_match.end_line < before_match.start_line
-                        || (main_match.end_line == before_match.start_line
-                            && main_match.end_col < before_match.start_col)
-                })
+        let node_rule = self.compile_node_rule(rule, lang)?;
+        let ast = AstGrep::new(code, lang);
+        let root = ast.root();
+        self.retain_within_budget(candidates, |candidate| {
+            root.dfs().any(|node| {
+                let (start, end) = (node.start_pos(), node.end_pos());
+                start.line() == candidate.start_line
+                    && start.column(&node) == candidate.start_col
+                    && end.line() == candidate.end_line
+                    && end.column(&node) == candidate.end_col
+                    && node_rule.match_node(node).is_some()
             })
-            .collect();
-
-        Ok(filtered_matches)
+        })
     }
 }
 
+/// Resolve the `matches` references of the util `name` into `resolved`, following references
+/// to other utils first. `visiting` holds the utils being resolved, to report cycles
+fn resolve_util(
+    name: &str,
+    declared: &HashMap<&str, Rule>,
+    resolved: &mut HashMap<String, Rule>,
+    visiting: &mut Vec<String>,
+) -> Result<Rule, ServiceError> {
+    if let Some(rule) = resolved.get(name) {
+        return Ok(rule.clone());
+    }
+    if visiting.iter().any(|util| util == name) {
+        visiting.push(name.to_string());
+        return Err(ServiceError::ParserError(format!(
+            "util rules refer to each other in a cycle: {}",
+            visiting.join(" -> ")
+        )));
+    }
+    let Some(rule) = declared.get(name) else {
+        return Err(ServiceError::ParserError(format!(
+            "'matches: {name}' refers to a util rule that is not defined in utils"
+        )));
+    };
+    visiting.push(name.to_string());
+    let rule =
+        rule.replace_matches(&mut |name| resolve_util(name, declared, resolved, visiting))?;
+    visiting.pop();
+    resolved.insert(name.to_string(), rule.clone());
+    Ok(rule)
+}
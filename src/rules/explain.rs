@@ -0,0 +1,413 @@
+//! Step-by-step traces of rule evaluation
+//!
+//! Mirrors [`RuleEvaluator::evaluate_rule`] while recording what every sub-rule matched and
+//! which candidates each condition of an `all` rule pruned, so the final match set can be
+//! explained. Results are computed by the evaluator itself, so the trace always agrees with
+//! `rule_search`.
+
+use super::ast::Rule;
+use super::evaluation::RuleEvaluator;
+use super::types::{EvaluationStep, PatternSpec, RuleObject, TracedNode};
+use crate::errors::ServiceError;
+use crate::types::MatchResult;
+use ast_grep_language::SupportLang as Language;
+
+/// Longest node text shown in a trace
+const MAX_TRACED_TEXT: usize = 80;
+
+/// Conditions of a rule object in the order the evaluator checks them. A rule object with
+/// several of them is evaluated as an `all` of one sub-rule per condition
+const CONDITION_ORDER: &[&str] = &[
+    "pattern", "kind", "regex", "matches", "all", "any", "not", "inside", "has", "follows",
+    "precedes",
+];
+
+pub struct RuleTracer<'a> {
+    evaluator: &'a RuleEvaluator,
+    code: &'a str,
+    lang: Language,
+    max_nodes: usize,
+    steps: Vec<EvaluationStep>,
+}
+
+impl<'a> RuleTracer<'a> {
+    pub fn new(
+        evaluator: &'a RuleEvaluator,
+        code: &'a str,
+        lang: Language,
+        max_nodes: usize,
+    ) -> Self {
+        Self {
+            evaluator,
+            code,
+            lang,
+            max_nodes,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Evaluate `rule`, recording a step for it and every sub-rule
+    pub fn trace(
+        &mut self,
+        rule: &RuleObject,
+        path: &str,
+    ) -> Result<Vec<MatchResult>, ServiceError> {
+        let evaluated = Rule::from(rule.clone());
+        let conditions = present_conditions(rule);
+        let operator = conditions.first().copied().unwrap_or("all");
+
+        let (results, argument, candidates, pruned, note) = match (&evaluated, operator) {
+            (Rule::All(rules), _) if conditions.len() > 1 => {
+                let children: Vec<RuleObject> = conditions
+                    .iter()
+                    .map(|c| single_condition(rule, c))
+                    .collect();
+                let paths: Vec<String> =
+                    conditions.iter().map(|c| format!("{path}[{c}]")).collect();
+                let (results, note) = self.trace_all(rules, &children, &paths)?;
+                (results, None, None, Vec::new(), note)
+            }
+            (Rule::All(rules), "all") => {
+                let children = rule.all.as_deref().unwrap_or_default();
+                let paths: Vec<String> = (0..children.len())
+                    .map(|i| format!("{path}.all[{i}]"))
+                    .collect();
+                let (results, note) = self.trace_all(rules, children, &paths)?;
+                (results, None, None, Vec::new(), note)
+            }
+            (_, "any") => {
+                let mut found = 0;
+                for (i, child) in rule.any.iter().flatten().enumerate() {
+                    found += self.trace(child, &format!("{path}.any[{i}]"))?.len();
+                }
+                let results = self
+                    .evaluator
+                    .evaluate_rule(&evaluated, self.code, self.lang)?;
+                let note = format!(
+                    "union of the sub-rules: {found} matches, {} distinct nodes",
+                    results.len()
+                );
+                (results, None, None, Vec::new(), note)
+            }
+            (_, "not") => {
+                let negated = self.trace(rule.not.as_deref().unwrap(), &format!("{path}.not"))?;
+                let results = self
+                    .evaluator
+                    .evaluate_rule(&evaluated, self.code, self.lang)?;
+                let note = format!(
+                    "every node except those overlapping one of the {} negated matches",
+                    negated.len()
+                );
+                (results, None, None, Vec::new(), note)
+            }
+            (_, relation @ ("inside" | "has" | "follows" | "precedes")) => {
+                let related = match relation {
+                    "inside" => &rule.inside,
+                    "has" => &rule.has,
+                    "follows" => &rule.follows,
+                    _ => &rule.precedes,
+                };
+                let targets =
+                    self.trace(related.as_deref().unwrap(), &format!("{path}.{relation}"))?;
+                let results = self
+                    .evaluator
+                    .evaluate_rule(&evaluated, self.code, self.lang)?;
+                let note = format!(
+                    "nodes {} one of the {} `{relation}` matches",
+                    relation_phrase(relation),
+                    targets.len()
+                );
+                (results, None, None, Vec::new(), note)
+            }
+            _ => {
+                let results = self
+                    .evaluator
+                    .evaluate_rule(&evaluated, self.code, self.lang)?;
+                let note = match results.len() {
+                    0 => format!("no node in the file matches this {operator}"),
+                    n => format!("{n} nodes match this {operator}"),
+                };
+                (results, leaf_argument(rule), None, Vec::new(), note)
+            }
+        };
+
+        let operator = if conditions.len() > 1 {
+            "all"
+        } else {
+            operator
+        };
+        self.push_step(
+            path, operator, argument, candidates, &results, &pruned, note,
+        );
+        Ok(results)
+    }
+
+    /// The steps recorded so far
+    pub fn into_steps(self) -> Vec<EvaluationStep> {
+        self.steps
+    }
+
+    /// Trace an `all` rule, explicit or made of the conditions of one rule object, with the
+    /// sub-rules traced at `paths`: the first sub-rule yields candidates and each following one
+    /// filters them, exactly as the evaluator does
+    fn trace_all(
+        &mut self,
+        rules: &[Rule],
+        children: &[RuleObject],
+        paths: &[String],
+    ) -> Result<(Vec<MatchResult>, String), ServiceError> {
+        let Some(first) = children.first() else {
+            return Ok((Vec::new(), "an empty `all` matches nothing".to_string()));
+        };
+        let mut candidates = self.trace(first, &paths[0])?;
+        let initial = candidates.len();
+
+        for ((rule, child), child_path) in rules.iter().zip(children).zip(paths).skip(1) {
+            let operator = present_conditions(child).first().copied().unwrap_or("all");
+            if candidates.is_empty() {
+                self.push_step(
+                    child_path,
+                    operator,
+                    leaf_argument(child),
+                    Some(0),
+                    &[],
+                    &[],
+                    "skipped: no candidates left".to_string(),
+                );
+                continue;
+            }
+
+            let note = match rule {
+                Rule::Has { .. } => {
+                    let has = child.has.as_deref().unwrap();
+                    self.trace(has, &format!("{child_path}.has"))?;
+                    "kept candidates with a node matching `has` below them"
+                }
+                Rule::Inside { .. } => {
+                    let inside = child.inside.as_deref().unwrap();
+                    self.trace(inside, &format!("{child_path}.inside"))?;
+                    "kept candidates with an ancestor matching `inside`"
+                }
+                Rule::Not(negated) => {
+                    let not = child.not.as_deref().unwrap();
+                    match negated.as_ref() {
+                        Rule::Has { .. } => {
+                            let has = not.has.as_deref().unwrap();
+                            self.trace(has, &format!("{child_path}.not.has"))?;
+                            "dropped candidates with a node matching `not.has` below them"
+                        }
+                        negated => {
+                            self.trace(not, &format!("{child_path}.not"))?;
+                            if negated.has_relation() {
+                                "dropped candidates the negated rule holds for"
+                            } else {
+                                "dropped candidates overlapping a match of the negated rule"
+                            }
+                        }
+                    }
+                }
+                Rule::Regex(_) => "kept candidates whose text matches the regex",
+                _ => {
+                    self.trace(child, child_path)?;
+                    if rule.has_relation() {
+                        "kept candidates this sub-rule holds for"
+                    } else {
+                        "kept candidates overlapping a match of this sub-rule"
+                    }
+                }
+            };
+
+            let before = candidates.clone();
+            candidates = self
+                .evaluator
+                .filter_candidates_by_rule(candidates, rule, self.code, self.lang)?;
+            let pruned: Vec<MatchResult> = before
+                .into_iter()
+                .filter(|candidate| !candidates.iter().any(|kept| same_node(kept, candidate)))
+                .collect();
+            let incoming = candidates.len() + pruned.len();
+            self.push_step(
+                child_path,
+                operator,
+                leaf_argument(child),
+                Some(incoming),
+                &candidates,
+                &pruned,
+                note.to_string(),
+            );
+        }
+
+        let note = format!(
+            "{} of the {initial} candidates from `{}` satisfied every condition",
+            candidates.len(),
+            paths[0]
+        );
+        Ok((candidates, note))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn push_step(
+        &mut self,
+        path: &str,
+        operator: &str,
+        argument: Option<String>,
+        candidates: Option<usize>,
+        matched: &[MatchResult],
+        pruned: &[MatchResult],
+        note: String,
+    ) {
+        let traced = |nodes: &[MatchResult]| {
+            nodes
+                .iter()
+                .take(self.max_nodes)
+                .map(traced_node)
+                .collect::<Vec<_>>()
+        };
+        self.steps.push(EvaluationStep {
+            path: path.to_string(),
+            operator: operator.to_string(),
+            argument,
+            candidates,
+            matched: matched.len(),
+            matched_nodes: traced(matched),
+            pruned: pruned.len(),
+            pruned_nodes: traced(pruned),
+            note,
+        });
+    }
+}
+
+/// Plain-language account of a finished trace
+pub fn explain_steps(steps: &[EvaluationStep], matches: usize, suppressed: usize) -> Vec<String> {
+    let mut explanation = Vec::new();
+    if matches == 0 && suppressed == 0 {
+        // The earliest leaf that came up empty is usually the culprit
+        match steps
+            .iter()
+            .find(|step| step.matched == 0 && step.candidates.is_none())
+        {
+            Some(step) => explanation.push(format!(
+                "No matches: `{}` ({}{}) matched nothing",
+                step.path,
+                step.operator,
+                step.argument
+                    .as_ref()
+                    .map(|argument| format!(" `{argument}`"))
+                    .unwrap_or_default()
+            )),
+            None => explanation.push("No matches".to_string()),
+        }
+    }
+    for step in steps {
+        if let Some(incoming) = step.candidates
+            && step.pruned > 0
+        {
+            explanation.push(format!(
+                "`{}` ({}) removed {} of {incoming} candidates",
+                step.path, step.operator, step.pruned
+            ));
+        }
+    }
+    if suppressed > 0 {
+        explanation.push(format!(
+            "{suppressed} matches were suppressed by `ast-grep-ignore` comments"
+        ));
+    }
+    if matches > 0 {
+        explanation.push(format!("{matches} matches remain"));
+    }
+    explanation
+}
+
+/// Conditions set on `rule`, in evaluation order
+fn present_conditions(rule: &RuleObject) -> Vec<&'static str> {
+    let present = [
+        rule.pattern.is_some(),
+        rule.kind.is_some(),
+        rule.regex.is_some(),
+        rule.matches.is_some(),
+        rule.all.is_some(),
+        rule.any.is_some(),
+        rule.not.is_some(),
+        rule.inside.is_some(),
+        rule.has.is_some(),
+        rule.follows.is_some(),
+        rule.precedes.is_some(),
+    ];
+    CONDITION_ORDER
+        .iter()
+        .zip(present)
+        .filter(|(_, present)| *present)
+        .map(|(condition, _)| *condition)
+        .collect()
+}
+
+/// A copy of `rule` keeping only `condition`, one of [`CONDITION_ORDER`]
+fn single_condition(rule: &RuleObject, condition: &str) -> RuleObject {
+    let mut single = RuleObject {
+        pattern: None,
+        kind: None,
+        regex: None,
+        inside: None,
+        has: None,
+        follows: None,
+        precedes: None,
+        all: None,
+        any: None,
+        not: None,
+        matches: None,
+        stop_by: None,
+        field: None,
+    };
+    match condition {
+        "pattern" => single.pattern = rule.pattern.clone(),
+        "kind" => single.kind = rule.kind.clone(),
+        "regex" => single.regex = rule.regex.clone(),
+        "matches" => single.matches = rule.matches.clone(),
+        "all" => single.all = rule.all.clone(),
+        "any" => single.any = rule.any.clone(),
+        "not" => single.not = rule.not.clone(),
+        "inside" => single.inside = rule.inside.clone(),
+        "has" => single.has = rule.has.clone(),
+        "follows" => single.follows = rule.follows.clone(),
+        _ => single.precedes = rule.precedes.clone(),
+    }
+    single
+}
+
+fn leaf_argument(rule: &RuleObject) -> Option<String> {
+    match (&rule.pattern, &rule.kind, &rule.regex, &rule.matches) {
+        (Some(PatternSpec::Simple(pattern)), ..) => Some(pattern.clone()),
+        (Some(PatternSpec::Advanced { context, .. }), ..) => Some(context.clone()),
+        (None, Some(kind), ..) => Some(kind.clone()),
+        (None, None, Some(regex), _) => Some(regex.clone()),
+        (None, None, None, Some(matches)) => Some(matches.clone()),
+        _ => None,
+    }
+}
+
+fn relation_phrase(relation: &str) -> &'static str {
+    match relation {
+        "inside" => "inside",
+        "has" => "containing",
+        "follows" => "after",
+        _ => "before",
+    }
+}
+
+fn same_node(a: &MatchResult, b: &MatchResult) -> bool {
+    a.start_line == b.start_line && a.start_col == b.start_col && a.text == b.text
+}
+
+fn traced_node(node: &MatchResult) -> TracedNode {
+    let first_line = node.text.lines().next().unwrap_or_default();
+    let mut text: String = first_line.chars().take(MAX_TRACED_TEXT).collect();
+    if text.len() < node.text.len() {
+        text.push('…');
+    }
+    TracedNode {
+        text,
+        start_line: node.start_line,
+        start_col: node.start_col,
+    }
+}
@@ -0,0 +1,64 @@
+//! # Workspace Lint
+//!
+//! `lint_workspace` runs every stored rule through [`super::scan`] and turns its severity
+//! buckets into a pass/fail report. The findings of all buckets are paged as one list, most
+//! severe first, so the findings failing the lint come before the ones that only inform.
+
+use super::scan::SEVERITIES;
+use super::types::{LintFinding, LintRuleCount, LintThreshold, LintWorkspaceResult, ScanResult};
+
+/// Build the report of a scan whose buckets list all of their findings
+pub fn lint_report(
+    scan: ScanResult,
+    fail_on: LintThreshold,
+    offset: usize,
+    page_size: usize,
+) -> LintWorkspaceResult {
+    let count = |severity: &str| {
+        scan.buckets
+            .iter()
+            .find(|bucket| bucket.severity == severity)
+            .map_or(0, |bucket| bucket.count)
+    };
+    let [error_count, warning_count, info_count] = SEVERITIES.map(count);
+    let failing_findings = scan
+        .buckets
+        .iter()
+        .filter(|bucket| fail_on.fails(&bucket.severity))
+        .map(|bucket| bucket.count)
+        .sum();
+
+    // Buckets come most severe first, each ordered by file and position
+    let mut rule_counts = Vec::new();
+    let mut findings = Vec::new();
+    for bucket in scan.buckets {
+        let mut counts: Vec<_> = bucket.rule_counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        rule_counts.extend(counts.into_iter().map(|(rule_id, count)| LintRuleCount {
+            rule_id,
+            severity: bucket.severity.clone(),
+            count,
+        }));
+        findings.extend(bucket.findings.into_iter().map(|finding| LintFinding {
+            severity: bucket.severity.clone(),
+            finding,
+        }));
+    }
+
+    let start = offset.min(findings.len());
+    let end = start.saturating_add(page_size).min(findings.len());
+    LintWorkspaceResult {
+        passed: failing_findings == 0,
+        fail_on,
+        failing_findings,
+        total_findings: scan.total_findings,
+        error_count,
+        warning_count,
+        info_count,
+        files_scanned: scan.files_scanned,
+        rules_run: scan.rules_run,
+        rule_counts,
+        next_offset: (end < findings.len()).then_some(end),
+        findings: findings.drain(start..end).collect(),
+    }
+}
@@ -0,0 +1,305 @@
+//! # Node Rules
+//!
+//! Compiles a [`Rule`] into an ast-grep-core [`Matcher`] that decides whether one syntax tree
+//! node satisfies it. The evaluator uses it for rules with `inside`, `has`, `follows` or
+//! `precedes`, which need the tree around a node rather than the positions of separately found
+//! matches, so rules from the ast-grep catalog behave as they do in `ast-grep scan`:
+//!
+//! - every condition of a rule object applies to the same node, and metavariables captured by
+//!   the pattern must have the same value in the related nodes
+//! - `inside` looks at ancestors, `has` at descendants, `follows` at earlier siblings and
+//!   `precedes` at later ones; `stopBy: neighbor` (the default) only checks the nearest one,
+//!   `stopBy: end` checks all of them and a rule checks up to and including the first node
+//!   matching it
+//! - `field` requires the node (`inside`) or the target (`has`) to sit in that field of its
+//!   parent, e.g. `field: body`
+
+use super::ast::{PatternRule, Rule, StopBy};
+use crate::errors::ServiceError;
+use ast_grep_core::matcher::{KindMatcher, Matcher, MatcherExt, RegexMatcher};
+use ast_grep_core::meta_var::MetaVarEnv;
+use ast_grep_core::{Doc, Language as _, Node, Pattern};
+use ast_grep_language::SupportLang as Language;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::time::Instant;
+
+/// A rule compiled for matching single nodes
+pub(crate) enum NodeRule {
+    Pattern(Pattern),
+    Kind(KindMatcher),
+    Regex(RegexMatcher),
+    All(Vec<NodeRule>),
+    Any(Vec<NodeRule>),
+    Not(Box<NodeRule>),
+    /// A node matching `rule` with a `target` node in the given relation to it
+    Relation {
+        relation: Relation,
+        rule: Box<NodeRule>,
+        target: Box<NodeRule>,
+        stop_by: Stop,
+        field: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum Relation {
+    Inside,
+    Has,
+    Follows,
+    Precedes,
+}
+
+pub(crate) enum Stop {
+    Neighbor,
+    End,
+    Rule(Box<NodeRule>),
+}
+
+impl NodeRule {
+    /// Compile `rule`, building patterns with `pattern`
+    pub(crate) fn compile(
+        rule: &Rule,
+        lang: Language,
+        pattern: &dyn Fn(&str) -> Result<Pattern, ServiceError>,
+    ) -> Result<Self, ServiceError> {
+        let compile = |rule: &Rule| Self::compile(rule, lang, pattern);
+        let compile_all = |rules: &[Rule]| rules.iter().map(compile).collect::<Result<Vec<_>, _>>();
+        let relation =
+            |relation, rule: &Rule, target: &Rule, stop_by: &StopBy, field: &Option<String>| {
+                if let Some(field) = field
+                    && lang.field_to_id(field).is_none()
+                {
+                    return Err(ServiceError::ParserError(format!(
+                        "'{field}' is not a field of any {lang} node"
+                    )));
+                }
+                Ok(NodeRule::Relation {
+                    relation,
+                    rule: Box::new(compile(rule)?),
+                    target: Box::new(compile(target)?),
+                    stop_by: match stop_by {
+                        StopBy::Neighbor => Stop::Neighbor,
+                        StopBy::End => Stop::End,
+                        StopBy::Rule(stop) => Stop::Rule(Box::new(compile(stop)?)),
+                    },
+                    field: field.clone(),
+                })
+            };
+
+        Ok(match rule {
+            Rule::Pattern(PatternRule::Advanced {
+                pattern: context,
+                selector: Some(selector),
+                ..
+            }) => NodeRule::Pattern(Pattern::contextual(context, selector, lang).map_err(|e| {
+                ServiceError::ParserError(format!("Invalid pattern '{context}': {e}"))
+            })?),
+            Rule::Pattern(PatternRule::Simple { pattern: source })
+            | Rule::Pattern(PatternRule::Advanced {
+                pattern: source, ..
+            }) => NodeRule::Pattern(pattern(source)?),
+            Rule::Kind(kind) => NodeRule::Kind(KindMatcher::try_new(kind, lang).map_err(|_| {
+                ServiceError::ParserError(format!("'{kind}' is not a node kind of {lang}"))
+            })?),
+            Rule::Regex(regex) => NodeRule::Regex(
+                RegexMatcher::try_new(regex)
+                    .map_err(|e| ServiceError::ParserError(format!("Invalid regex: {e}")))?,
+            ),
+            Rule::Matches(name) => {
+                return Err(ServiceError::ParserError(format!(
+                    "'matches: {name}' must be resolved to its util rule before compiling"
+                )));
+            }
+            Rule::All(rules) => NodeRule::All(compile_all(rules)?),
+            Rule::Any(rules) => NodeRule::Any(compile_all(rules)?),
+            Rule::Not(rule) => NodeRule::Not(Box::new(compile(rule)?)),
+            Rule::Inside {
+                rule,
+                inside_of,
+                stop_by,
+                field,
+            } => relation(Relation::Inside, rule, inside_of, stop_by, field)?,
+            Rule::Has {
+                rule,
+                contains,
+                stop_by,
+                field,
+            } => relation(Relation::Has, rule, contains, stop_by, field)?,
+            Rule::Follows {
+                rule,
+                after,
+                stop_by,
+                field,
+            } => relation(Relation::Follows, rule, after, stop_by, field)?,
+            Rule::Precedes {
+                rule,
+                before,
+                stop_by,
+                field,
+            } => relation(Relation::Precedes, rule, before, stop_by, field)?,
+        })
+    }
+}
+
+/// Match `rule` against `node`, keeping the metavariables it captures only if it matches
+fn try_match<'tree, D: Doc>(
+    rule: &NodeRule,
+    node: Node<'tree, D>,
+    env: &mut Cow<MetaVarEnv<'tree, D>>,
+) -> Option<Node<'tree, D>> {
+    let mut attempt = Cow::Borrowed(env.as_ref());
+    let matched = rule.match_node_with_env(node, &mut attempt)?;
+    *env = Cow::Owned(attempt.into_owned());
+    Some(matched)
+}
+
+impl NodeRule {
+    /// The first node in `relation` to `node`, within `stop_by` and `field`, matching `target`
+    fn find_related<'tree, D: Doc>(
+        &self,
+        node: &Node<'tree, D>,
+        env: &mut Cow<MetaVarEnv<'tree, D>>,
+    ) -> Option<Node<'tree, D>> {
+        let NodeRule::Relation {
+            relation,
+            target,
+            stop_by,
+            field,
+            ..
+        } = self
+        else {
+            return None;
+        };
+        // Whether the search goes on past `candidate`
+        let go_on = |candidate: &Node<'tree, D>| match stop_by {
+            Stop::Neighbor => false,
+            Stop::End => true,
+            Stop::Rule(stop) => stop.match_node(candidate.clone()).is_none(),
+        };
+
+        match relation {
+            Relation::Inside => {
+                let mut child = node.clone();
+                for ancestor in node.ancestors() {
+                    let in_field = field.as_deref().is_none_or(|field| {
+                        ancestor
+                            .field(field)
+                            .is_some_and(|in_field| in_field.node_id() == child.node_id())
+                    });
+                    if in_field && let Some(found) = try_match(target, ancestor.clone(), env) {
+                        return Some(found);
+                    }
+                    if !go_on(&ancestor) {
+                        return None;
+                    }
+                    child = ancestor;
+                }
+                None
+            }
+            Relation::Has => {
+                let children: Vec<Node<'tree, D>> = match field {
+                    Some(field) => node.field_children(field).collect(),
+                    None => node.children().collect(),
+                };
+                // Depth first, not descending below nodes the search stops at
+                let mut pending: Vec<Node<'tree, D>> = children.into_iter().rev().collect();
+                while let Some(candidate) = pending.pop() {
+                    if let Some(found) = try_match(target, candidate.clone(), env) {
+                        return Some(found);
+                    }
+                    if go_on(&candidate) {
+                        let descendants: Vec<Node<'tree, D>> = candidate.children().collect();
+                        pending.extend(descendants.into_iter().rev());
+                    }
+                }
+                None
+            }
+            Relation::Follows | Relation::Precedes => {
+                let mut sibling = match relation {
+                    Relation::Follows => node.prev(),
+                    _ => node.next(),
+                };
+                while let Some(candidate) = sibling {
+                    if let Some(found) = try_match(target, candidate.clone(), env) {
+                        return Some(found);
+                    }
+                    if !go_on(&candidate) {
+                        return None;
+                    }
+                    sibling = match relation {
+                        Relation::Follows => candidate.prev(),
+                        _ => candidate.next(),
+                    };
+                }
+                None
+            }
+        }
+    }
+}
+
+impl Matcher for NodeRule {
+    fn match_node_with_env<'tree, D: Doc>(
+        &self,
+        node: Node<'tree, D>,
+        env: &mut Cow<MetaVarEnv<'tree, D>>,
+    ) -> Option<Node<'tree, D>> {
+        match self {
+            NodeRule::Pattern(pattern) => pattern.match_node_with_env(node, env),
+            NodeRule::Kind(kind) => kind.match_node_with_env(node, env),
+            NodeRule::Regex(regex) => regex.match_node_with_env(node, env),
+            NodeRule::All(rules) => {
+                let mut attempt = Cow::Borrowed(env.as_ref());
+                for rule in rules {
+                    rule.match_node_with_env(node.clone(), &mut attempt)?;
+                }
+                *env = Cow::Owned(attempt.into_owned());
+                Some(node)
+            }
+            NodeRule::Any(rules) => rules
+                .iter()
+                .find_map(|rule| try_match(rule, node.clone(), env).map(|_| node.clone())),
+            NodeRule::Not(rule) => {
+                let mut scratch = Cow::Borrowed(env.as_ref());
+                match rule.match_node_with_env(node.clone(), &mut scratch) {
+                    Some(_) => None,
+                    None => Some(node),
+                }
+            }
+            NodeRule::Relation { rule, .. } => {
+                // The pattern binds metavariables before the related node is looked for
+                let mut attempt = Cow::Borrowed(env.as_ref());
+                let matched = rule.match_node_with_env(node, &mut attempt)?;
+                self.find_related(&matched, &mut attempt)?;
+                *env = Cow::Owned(attempt.into_owned());
+                Some(matched)
+            }
+        }
+    }
+}
+
+/// A [`NodeRule`] that stops matching once `deadline` passes, so one file cannot hold a search
+/// past its time budget
+pub(crate) struct Budgeted<'a> {
+    pub rule: &'a NodeRule,
+    pub deadline: Option<Instant>,
+    pub expired: Cell<bool>,
+}
+
+impl Matcher for Budgeted<'_> {
+    fn match_node_with_env<'tree, D: Doc>(
+        &self,
+        node: Node<'tree, D>,
+        env: &mut Cow<MetaVarEnv<'tree, D>>,
+    ) -> Option<Node<'tree, D>> {
+        if self.expired.get()
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.expired.set(true);
+            return None;
+        }
+        self.rule.match_node_with_env(node, env)
+    }
+}
@@ -1,15 +1,38 @@
 pub mod ast;
 pub mod ast_serde;
+pub mod convert;
 pub mod evaluation;
+pub mod explain;
+pub mod lint;
+pub mod matcher;
+pub mod pack;
 pub mod parser;
+pub mod quick_fix;
+pub mod registry;
+pub mod rewrite;
+pub mod scan;
 pub mod service;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_storage;
+pub mod stats;
 pub mod storage;
+pub mod suppression;
 pub mod types;
+pub mod validation;
 
 // Re-export commonly used types
 pub use ast::{PatternRule, Rule};
+pub use convert::convert_rules;
 pub use evaluation::RuleEvaluator;
-pub use parser::{parse_rule_config, validate_rule, validate_rule_config};
+pub use explain::{RuleTracer, explain_steps};
+pub use parser::{
+    is_metavariable_name, join_rule_documents, parse_rule_config, split_rule_documents,
+    validate_rule, validate_rule_config,
+};
 pub use service::RuleService;
-pub use storage::RuleStorage;
+#[cfg(feature = "sqlite")]
+pub use sqlite_storage::SqliteRuleStorage;
+pub use stats::{RuleStats, RuleStatsStore};
+pub use storage::{FileRuleStorage, RuleStorage, StoredRule, open_rule_storage};
 pub use types::*;
+pub use validation::validate_rules;
@@ -0,0 +1,270 @@
+//! # Rule Packs
+//!
+//! A rule pack is a set of stored rules that is imported, exported and run together. A rule
+//! file may hold several `---` separated rules; in a rules directory, such a file is a pack named
+//! after the file. The sqlite storage keeps a pack name per rule instead.
+//!
+//! `export_rules` writes stored rules as they were written, ids and `${NAME}` placeholders
+//! included, either as one multi-document file or as a directory with one file per rule.
+//! `import_rules` reads both forms back, so a pack exported from one project can be imported
+//! into another unchanged. `rule_search` runs every rule of a pack when given its name.
+
+use super::parser::{join_rule_documents, split_rule_documents};
+use super::storage::{RuleStorage, StoredRule, validate_pack_name};
+use super::types::{
+    ExportRulesParam, ExportRulesResult, ImportRulesParam, ImportRulesResult, SkippedRule,
+};
+use crate::errors::ServiceError;
+use crate::path_validation::{
+    resolve_output_path, resolve_path_pattern, validate_creatable_path, validate_path_within_roots,
+};
+use crate::types::{FileMatchResult, FileSearchResult};
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The stored rules of `pack`, which must have at least one
+pub fn pack_rules(storage: &dyn RuleStorage, pack: &str) -> Result<Vec<StoredRule>, ServiceError> {
+    let rules: Vec<StoredRule> = storage
+        .stored_rules()?
+        .into_iter()
+        .filter(|rule| rule.pack.as_deref() == Some(pack))
+        .collect();
+    match rules.is_empty() {
+        true => Err(ServiceError::Internal(format!(
+            "Rule pack '{pack}' not found"
+        ))),
+        false => Ok(rules),
+    }
+}
+
+/// Drop the matches in files under `rule_directories`, so a pack never reports on rule files
+pub fn drop_rule_files(result: &mut FileSearchResult, rule_directories: &[PathBuf]) {
+    let directories: Vec<PathBuf> = rule_directories
+        .iter()
+        .flat_map(|dir| [Some(dir.clone()), dir.canonicalize().ok()])
+        .flatten()
+        .collect();
+    result.matches.retain(|file| {
+        let path = Path::new(&file.file_path);
+        !directories.iter().any(|dir| path.starts_with(dir))
+    });
+}
+
+/// One search result for the results of a pack's rules over the same files. When a rule stopped
+/// before the last file, the page ends at the earliest place a rule stopped and matches in later
+/// files are left for the next page
+pub fn merge_pack_results(results: Vec<FileSearchResult>) -> FileSearchResult {
+    let next_cursor = results
+        .iter()
+        .filter_map(|result| result.next_cursor.as_ref())
+        .filter(|cursor| !cursor.is_complete)
+        .min_by(|a, b| a.last_file_path.cmp(&b.last_file_path))
+        .or_else(|| {
+            results
+                .iter()
+                .find_map(|result| result.next_cursor.as_ref())
+        })
+        .cloned();
+    let stop_after = next_cursor
+        .as_ref()
+        .filter(|cursor| !cursor.is_complete)
+        .map(|cursor| cursor.last_file_path.clone());
+
+    let mut merged = FileSearchResult {
+        matches: Vec::new(),
+        next_cursor,
+        total_files_found: results
+            .iter()
+            .map(|result| result.total_files_found)
+            .max()
+            .unwrap_or(0),
+        skipped_files: Vec::new(),
+        budget_exceeded: results.iter().any(|result| result.budget_exceeded),
+        timed_out: results.iter().any(|result| result.timed_out),
+        stats: None,
+        warnings: Vec::new(),
+    };
+    let mut files: BTreeMap<String, FileMatchResult> = BTreeMap::new();
+    for result in results {
+        for file in result.matches {
+            if stop_after
+                .as_ref()
+                .is_some_and(|last| file.file_path > *last)
+            {
+                continue;
+            }
+            match files.entry(file.file_path.clone()) {
+                Entry::Vacant(entry) => {
+                    entry.insert(file);
+                }
+                Entry::Occupied(mut entry) => entry.get_mut().matches.extend(file.matches),
+            }
+        }
+        for skipped in result.skipped_files {
+            if !merged
+                .skipped_files
+                .iter()
+                .any(|other| other.file_path == skipped.file_path)
+            {
+                merged.skipped_files.push(skipped);
+            }
+        }
+        for warning in result.warnings {
+            if !merged.warnings.contains(&warning) {
+                merged.warnings.push(warning);
+            }
+        }
+    }
+    for file in files.values_mut() {
+        file.matches
+            .sort_by_key(|found| (found.start_line, found.start_col));
+    }
+    merged.matches = files.into_values().collect();
+    merged
+}
+
+/// Write the stored rules selected by `param` to its output path, inside `root_directories`
+pub fn export_rules(
+    storage: &dyn RuleStorage,
+    param: ExportRulesParam,
+    root_directories: &[PathBuf],
+) -> Result<ExportRulesResult, ServiceError> {
+    let mut rules = match &param.pack {
+        Some(pack) => pack_rules(storage, pack)?,
+        None => storage.stored_rules()?,
+    };
+    let mut skipped = Vec::new();
+    if let Some(ids) = &param.rule_ids {
+        skipped.extend(
+            ids.iter()
+                .filter(|id| !rules.iter().any(|rule| rule.id == **id))
+                .map(|id| SkippedRule {
+                    rule_id: id.clone(),
+                    reason: match &param.pack {
+                        Some(pack) => format!("no rule with this id in pack '{pack}'"),
+                        None => "no stored rule with this id".to_string(),
+                    },
+                }),
+        );
+        rules.retain(|rule| ids.contains(&rule.id));
+    }
+    if rules.is_empty() {
+        return Err(ServiceError::Internal("No rules to export".to_string()));
+    }
+
+    let output_path = resolve_output_path(&param.output_path, root_directories)?;
+    let mut exported = Vec::new();
+    if is_rule_file(&output_path) {
+        if output_path.exists() && !param.overwrite {
+            return Err(ServiceError::Internal(format!(
+                "{} already exists; pass overwrite to replace it",
+                output_path.display()
+            )));
+        }
+        if let Some(parent) = output_path.parent() {
+            validate_creatable_path(parent, root_directories)?;
+            fs::create_dir_all(parent).map_err(|e| io_error(e, parent))?;
+            // Re-check now that the directory exists, in case it changed in the meantime
+            validate_path_within_roots(parent, root_directories)?;
+        }
+        let documents: Vec<&str> = rules.iter().map(|rule| rule.content.as_str()).collect();
+        fs::write(&output_path, join_rule_documents(&documents))
+            .map_err(|e| io_error(e, &output_path))?;
+        exported.extend(rules.into_iter().map(|rule| rule.id));
+    } else {
+        validate_creatable_path(&output_path, root_directories)?;
+        fs::create_dir_all(&output_path).map_err(|e| io_error(e, &output_path))?;
+        validate_path_within_roots(&output_path, root_directories)?;
+        for rule in rules {
+            let target = output_path.join(format!("{}.yaml", rule.id));
+            if !param.overwrite
+                && let Ok(existing) = fs::read_to_string(&target)
+                && existing != rule.content
+            {
+                skipped.push(SkippedRule {
+                    rule_id: rule.id,
+                    reason: format!(
+                        "{} already exists with different content; pass overwrite to replace it",
+                        target.display()
+                    ),
+                });
+                continue;
+            }
+            fs::write(&target, &rule.content).map_err(|e| io_error(e, &target))?;
+            exported.push(rule.id);
+        }
+    }
+
+    Ok(ExportRulesResult {
+        output_path: output_path.to_string_lossy().to_string(),
+        exported,
+        skipped,
+    })
+}
+
+/// Store the rules in the file or directory `param.path`, inside `root_directories`
+pub async fn import_rules(
+    storage: &dyn RuleStorage,
+    param: ImportRulesParam,
+    root_directories: &[PathBuf],
+) -> Result<ImportRulesResult, ServiceError> {
+    if let Some(pack) = &param.pack {
+        validate_pack_name(pack)?;
+    }
+    let path = resolve_path_pattern(&param.path, root_directories)?
+        .into_iter()
+        .find(|path| path.exists())
+        .ok_or_else(|| ServiceError::FileIoError {
+            message: "File not found under the root directories".to_string(),
+            path: param.path.clone(),
+        })?;
+
+    let files = match path.is_dir() {
+        true => {
+            let mut files = Vec::new();
+            for entry in fs::read_dir(&path).map_err(|e| io_error(e, &path))? {
+                let file = entry.map_err(|e| io_error(e, &path))?.path();
+                if is_rule_file(&file) {
+                    files.push(file);
+                }
+            }
+            files.sort();
+            files
+        }
+        false => vec![path],
+    };
+    let mut rule_configs = Vec::new();
+    for file in files {
+        let content = fs::read_to_string(&file).map_err(|e| io_error(e, &file))?;
+        rule_configs.extend(
+            split_rule_documents(&content)
+                .into_iter()
+                .map(str::to_string),
+        );
+    }
+
+    let imported = storage
+        .import_rules(rule_configs, param.pack.clone(), param.overwrite)
+        .await?;
+    Ok(ImportRulesResult {
+        imported,
+        pack: param.pack,
+    })
+}
+
+/// Whether `path` names a YAML rule file rather than a directory of them
+fn is_rule_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|s| s.to_str()),
+        Some("yaml" | "yml")
+    )
+}
+
+fn io_error(err: std::io::Error, path: &Path) -> ServiceError {
+    ServiceError::FileIoError {
+        message: err.to_string(),
+        path: path.to_string_lossy().to_string(),
+    }
+}
@@ -1,4 +1,6 @@
+use super::RuleEvaluator;
 use super::ast::Rule;
+use super::rewrite::check_transforms;
 use super::types::{RuleConfig, RuleTestResult, RuleValidateParam, RuleValidateResult};
 use crate::errors::ServiceError;
 // Removed unused import
@@ -50,6 +52,38 @@ pub fn parse_rule_config(content: &str) -> Result<RuleConfig, ServiceError> {
     ))
 }
 
+/// The documents of a rule file, split at its `---` lines. Documents holding only comments and
+/// blank lines are left out, so a leading `---` doesn't count as an empty rule
+pub fn split_rule_documents(content: &str) -> Vec<&str> {
+    let mut documents = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let marker = line.trim_end();
+        if marker == "---" || marker.starts_with("--- ") {
+            documents.push(&content[start..offset]);
+            start = offset + line.len();
+        }
+        offset += line.len();
+    }
+    documents.push(&content[start..]);
+    documents.retain(|document| {
+        document
+            .lines()
+            .any(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+    });
+    documents
+}
+
+/// One multi-document rule file holding `documents`, in order
+pub fn join_rule_documents<S: AsRef<str>>(documents: &[S]) -> String {
+    documents
+        .iter()
+        .map(|document| format!("{}\n", document.as_ref().trim_end()))
+        .collect::<Vec<_>>()
+        .join("---\n")
+}
+
 pub fn validate_rule_config(content: &str) -> Result<Vec<String>, ServiceError> {
     let mut errors = Vec::new();
 
@@ -70,14 +104,58 @@ pub fn validate_rule_config(content: &str) -> Result<Vec<String>, ServiceError>
                 );
             }
 
-            // Validate severity if present
-            if let Some(ref severity) = rule.severity {
-                if !matches!(severity.as_str(), "error" | "warning" | "info") {
+            // Validate utility rules and the references to them
+            for (name, util) in &rule.utils {
+                if !has_valid_rule_condition(util) {
+                    errors.push(format!(
+                        "Util rule '{name}' must have at least one valid condition"
+                    ));
+                }
+            }
+            if let Err(e) = RuleEvaluator::new().with_utils(&rule.utils) {
+                errors.push(e.to_string());
+            }
+            for checked in std::iter::once(&rule.rule).chain(rule.constraints.values()) {
+                let unknown_util = Rule::from(checked.clone()).replace_matches(&mut |name| {
+                    if rule.utils.contains_key(name) {
+                        Ok(Rule::Matches(name.to_string()))
+                    } else {
+                        Err(name.to_string())
+                    }
+                });
+                if let Err(name) = unknown_util {
+                    errors.push(format!(
+                        "'matches: {name}' refers to a util rule that is not defined in utils"
+                    ));
+                }
+            }
+
+            // Validate metavariable constraints
+            for (name, constraint) in &rule.constraints {
+                if !is_metavariable_name(name) {
+                    errors.push(format!(
+                        "Constraint '{name}' must name a metavariable without the $, e.g. VAR for $VAR"
+                    ));
+                }
+                if !has_valid_rule_condition(constraint) {
                     errors.push(format!(
-                        "Invalid severity '{severity}'. Must be 'error', 'warning', or 'info'"
+                        "Constraint on ${name} must have at least one valid condition"
                     ));
                 }
             }
+
+            if let Err(e) = check_transforms(&rule) {
+                errors.push(e.to_string());
+            }
+
+            // Validate severity if present
+            if let Some(ref severity) = rule.severity
+                && !matches!(severity.as_str(), "error" | "warning" | "info")
+            {
+                errors.push(format!(
+                    "Invalid severity '{severity}'. Must be 'error', 'warning', or 'info'"
+                ));
+            }
         }
         Err(e) => {
             errors.push(format!("Failed to parse rule configuration: {e}"));
@@ -91,11 +169,10 @@ pub async fn validate_rule(param: RuleValidateParam) -> Result<RuleValidateResul
     let errors = validate_rule_config(&param.rule_config)?;
     let valid = errors.is_empty();
 
-    let test_results = if valid && param.test_code.is_some() {
+    let test_results = if let (true, Some(_test_code)) = (valid, param.test_code) {
         // If rule is valid and test code is provided, test it
         match parse_rule_config(&param.rule_config) {
             Ok(rule) => {
-                let _test_code = param.test_code.unwrap();
                 let _lang = Language::from_str(&rule.language)
                     .map_err(|_| ServiceError::ParserError("Invalid language".to_string()))?;
 
@@ -104,6 +181,9 @@ pub async fn validate_rule(param: RuleValidateParam) -> Result<RuleValidateResul
                 Some(RuleTestResult {
                     matches_found: 0,
                     sample_matches: vec![],
+                    constraint_violations: vec![],
+                    fixed_code: None,
+                    fix_hunks: vec![],
                 })
             }
             Err(_) => None,
@@ -133,3 +213,12 @@ fn has_valid_rule_condition(rule: &super::types::RuleObject) -> bool {
         || rule.matches.is_some()
 }
 
+/// Whether `name` can follow the `$` of a single metavariable, like `VAR` or `_ARG1`
+pub fn is_metavariable_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_uppercase() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
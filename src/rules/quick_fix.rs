@@ -0,0 +1,87 @@
+//! # Quick Fixes
+//!
+//! Bridges ad-hoc searches with the stored rule library: every stored rule that carries a `fix`
+//! is run over a file with search matches, and each search match is annotated with the fixes of
+//! rule matches that overlap it.
+
+use super::evaluation::RuleEvaluator;
+use super::suppression::filter_suppressed;
+use super::types::RuleConfig;
+use crate::dialect::language_for_file;
+use crate::template::ReplacementTemplate;
+use crate::types::{MatchResult, QuickFix};
+use ast_grep_language::SupportLang as Language;
+use std::str::FromStr;
+
+/// A stored rule with a fix, parsed once per search
+pub struct FixRule {
+    rule: RuleConfig,
+    language: Language,
+    fix: ReplacementTemplate,
+}
+
+/// Keep the rules that have a fix in a supported language. Rules whose fix doesn't parse are
+/// left out rather than failing the search
+pub fn fix_rules(rules: Vec<RuleConfig>) -> Vec<FixRule> {
+    rules
+        .into_iter()
+        .filter_map(|rule| {
+            let language = Language::from_str(&rule.language).ok()?;
+            let fix = ReplacementTemplate::parse(rule.fix.as_deref()?).ok()?;
+            Some(FixRule {
+                rule,
+                language,
+                fix,
+            })
+        })
+        .collect()
+}
+
+/// Attach to each of `matches` the fixes of rules for `lang` whose matches in `content`
+/// overlap it
+pub fn annotate_matches(
+    evaluator: &RuleEvaluator,
+    rules: &[FixRule],
+    content: &str,
+    file_path: &str,
+    lang: Language,
+    matches: &mut [MatchResult],
+) {
+    for fix_rule in rules {
+        if language_for_file(fix_rule.language, file_path) != lang {
+            continue;
+        }
+        let rule_matches = match evaluator.evaluate_rule_config(&fix_rule.rule, content, lang) {
+            Ok(rule_matches) => rule_matches,
+            Err(e) => {
+                tracing::warn!("Skipping rule {} on {file_path}: {e}", fix_rule.rule.id);
+                continue;
+            }
+        };
+        let (rule_matches, _) = filter_suppressed(content, &fix_rule.rule.id, rule_matches);
+
+        for rule_match in &rule_matches {
+            let quick_fix = QuickFix {
+                rule_id: fix_rule.rule.id.clone(),
+                message: fix_rule.rule.message.clone(),
+                fix_safety: fix_rule.rule.fix_safety.unwrap_or_default(),
+                start_line: rule_match.start_line,
+                end_line: rule_match.end_line,
+                start_col: rule_match.start_col,
+                end_col: rule_match.end_col,
+                old_text: rule_match.text.clone(),
+                new_text: fix_rule.fix.render(&rule_match.vars),
+            };
+            for search_match in matches.iter_mut().filter(|m| overlaps(m, rule_match)) {
+                search_match.quick_fixes.push(quick_fix.clone());
+            }
+        }
+    }
+}
+
+/// Whether two matches share at least one character
+fn overlaps(a: &MatchResult, b: &MatchResult) -> bool {
+    let start = |m: &MatchResult| (m.start_line, m.start_col);
+    let end = |m: &MatchResult| (m.end_line, m.end_col);
+    start(a) < end(b) && start(b) < end(a)
+}
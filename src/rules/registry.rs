@@ -0,0 +1,337 @@
+//! # Rule Registry
+//!
+//! Shares stored rules through a team rule registry: a small HTTP API holding one versioned
+//! copy of each rule.
+//!
+//! - `GET {url}/rules` returns `{"rules": [{"id", "rule_config", "version"}]}`
+//! - `PUT {url}/rules/{id}` with `{"rule_config", "base_version"}` stores a rule and returns it
+//!   as `{"id", "rule_config", "version"}`. When `base_version` is not the registry's current
+//!   version (`0` for a rule it doesn't have yet), it answers `409 Conflict` with its own copy
+//!   instead. Without `base_version` the rule is replaced unconditionally
+//!
+//! Requests carry `Authorization: Bearer <token>` when a token is configured.
+//!
+//! The registry version each rule was last synced at, and the content it had then, are kept in
+//! `.rule-registry.json` in the rules directory. A rule changed on one side since then is
+//! pushed or pulled; a rule changed on both sides is a conflict.
+
+use super::parser::parse_rule_config;
+use super::storage::RuleStorage;
+use super::types::{
+    ConflictResolution, CreateRuleParam, PullRulesParam, PullRulesResult, PushRuleParam,
+    PushRuleResult, RemoteRule, RuleConflict,
+};
+use crate::config::RuleRegistryConfig;
+use crate::errors::ServiceError;
+use crate::file_fetch::content_hash;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of the sync state inside the rules directory
+pub const REGISTRY_STATE_FILE: &str = ".rule-registry.json";
+
+/// What the registry answered to a `PUT`
+pub enum PutOutcome {
+    Stored(RemoteRule),
+    /// The registry's copy changed since `base_version`
+    Conflict(RemoteRule),
+}
+
+/// HTTP client of a rule registry
+pub struct RegistryClient {
+    url: String,
+    #[cfg(feature = "registry")]
+    token: Option<String>,
+    #[cfg(feature = "registry")]
+    http: reqwest::Client,
+}
+
+#[cfg(feature = "registry")]
+#[derive(Deserialize)]
+struct RemoteRuleList {
+    rules: Vec<RemoteRule>,
+}
+
+#[cfg(feature = "registry")]
+#[derive(Serialize)]
+struct PutRule<'a> {
+    rule_config: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base_version: Option<u64>,
+}
+
+impl RegistryClient {
+    pub fn new(config: &RuleRegistryConfig) -> Self {
+        Self {
+            url: config.url.trim_end_matches('/').to_string(),
+            #[cfg(feature = "registry")]
+            token: config.token.clone(),
+            #[cfg(feature = "registry")]
+            http: reqwest::Client::new(),
+        }
+    }
+}
+
+#[cfg(feature = "registry")]
+impl RegistryClient {
+    /// Every rule in the registry
+    pub async fn list(&self) -> Result<Vec<RemoteRule>, ServiceError> {
+        let request = self.authorized(self.http.get(format!("{}/rules", self.url)));
+        let response = request.send().await.map_err(registry_error)?;
+        let listed: RemoteRuleList = response
+            .error_for_status()
+            .map_err(registry_error)?
+            .json()
+            .await
+            .map_err(registry_error)?;
+        Ok(listed.rules)
+    }
+
+    /// Store `rule_config` as rule `rule_id`, if the registry's copy is still at `base_version`
+    pub async fn put(
+        &self,
+        rule_id: &str,
+        rule_config: &str,
+        base_version: Option<u64>,
+    ) -> Result<PutOutcome, ServiceError> {
+        let url = format!("{}/rules/{}", self.url, encode_path_segment(rule_id));
+        let request = self.authorized(self.http.put(url)).json(&PutRule {
+            rule_config,
+            base_version,
+        });
+        let response = request.send().await.map_err(registry_error)?;
+        if response.status() == reqwest::StatusCode::CONFLICT {
+            let remote = response.json().await.map_err(registry_error)?;
+            return Ok(PutOutcome::Conflict(remote));
+        }
+        let stored = response
+            .error_for_status()
+            .map_err(registry_error)?
+            .json()
+            .await
+            .map_err(registry_error)?;
+        Ok(PutOutcome::Stored(stored))
+    }
+
+    fn authorized(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+#[cfg(not(feature = "registry"))]
+impl RegistryClient {
+    pub async fn list(&self) -> Result<Vec<RemoteRule>, ServiceError> {
+        Err(unsupported(&self.url))
+    }
+
+    pub async fn put(
+        &self,
+        _rule_id: &str,
+        _rule_config: &str,
+        _base_version: Option<u64>,
+    ) -> Result<PutOutcome, ServiceError> {
+        Err(unsupported(&self.url))
+    }
+}
+
+#[cfg(feature = "registry")]
+fn registry_error(err: reqwest::Error) -> ServiceError {
+    ServiceError::Internal(format!("Rule registry error: {err}"))
+}
+
+#[cfg(not(feature = "registry"))]
+fn unsupported(url: &str) -> ServiceError {
+    ServiceError::Internal(format!(
+        "Cannot reach {url}: this build has no rule registry support"
+    ))
+}
+
+/// Percent-encode everything but unreserved characters, so any rule id is one path segment
+#[cfg(feature = "registry")]
+fn encode_path_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// A rule as it was when last pushed or pulled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SyncedRule {
+    version: u64,
+    hash: String,
+}
+
+/// Sync state of every rule, read from and written back to `path`
+struct SyncState {
+    path: PathBuf,
+    rules: BTreeMap<String, SyncedRule>,
+}
+
+impl SyncState {
+    fn load(rules_directory: &Path) -> Result<Self, ServiceError> {
+        let path = rules_directory.join(REGISTRY_STATE_FILE);
+        let rules = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, rules })
+    }
+
+    fn record(&mut self, rule_id: &str, version: u64, rule_config: &str) {
+        self.rules.insert(
+            rule_id.to_string(),
+            SyncedRule {
+                version,
+                hash: content_hash(rule_config),
+            },
+        );
+    }
+
+    fn save(&self) -> Result<(), ServiceError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.rules)?)?;
+        Ok(())
+    }
+}
+
+/// The configuration of every stored rule by id, as written (before `${NAME}` interpolation).
+/// The first rule with an id wins, as in `list_rules`
+fn stored_rules(storage: &dyn RuleStorage) -> Result<HashMap<String, String>, ServiceError> {
+    let mut rules = HashMap::new();
+    for (_, content) in storage.rule_sources()? {
+        if let Ok(rule) = parse_rule_config(&content) {
+            rules.entry(rule.id).or_insert(content);
+        }
+    }
+    Ok(rules)
+}
+
+/// Publish a stored rule. It is refused with the registry's copy when someone else changed the
+/// rule since it was last synced, unless `force` is set
+pub async fn push_rule(
+    storage: &dyn RuleStorage,
+    client: &RegistryClient,
+    rules_directory: &Path,
+    param: PushRuleParam,
+) -> Result<PushRuleResult, ServiceError> {
+    let rule_config = stored_rules(storage)?
+        .remove(&param.rule_id)
+        .ok_or_else(|| ServiceError::Internal(format!("Rule '{}' not found", param.rule_id)))?;
+    let mut state = SyncState::load(rules_directory)?;
+    let base_version = match param.force {
+        true => None,
+        false => Some(
+            state
+                .rules
+                .get(&param.rule_id)
+                .map_or(0, |synced| synced.version),
+        ),
+    };
+
+    match client
+        .put(&param.rule_id, &rule_config, base_version)
+        .await?
+    {
+        PutOutcome::Stored(remote) => {
+            state.record(&param.rule_id, remote.version, &rule_config);
+            state.save()?;
+            Ok(PushRuleResult {
+                rule_id: param.rule_id,
+                pushed: true,
+                version: Some(remote.version),
+                conflict: None,
+            })
+        }
+        PutOutcome::Conflict(remote) => Ok(PushRuleResult {
+            rule_id: param.rule_id,
+            pushed: false,
+            version: None,
+            conflict: Some(remote),
+        }),
+    }
+}
+
+/// Store the registry's rules locally. Rules changed only in the registry replace the local
+/// ones, rules changed only locally are left for `push_rule`, and rules changed on both sides
+/// are settled by `on_conflict`
+pub async fn pull_rules(
+    storage: &dyn RuleStorage,
+    client: &RegistryClient,
+    rules_directory: &Path,
+    param: PullRulesParam,
+) -> Result<PullRulesResult, ServiceError> {
+    let remote_rules = client.list().await?;
+    let local_rules = stored_rules(storage)?;
+    let mut state = SyncState::load(rules_directory)?;
+    let mut result = PullRulesResult {
+        added: Vec::new(),
+        updated: Vec::new(),
+        unchanged: 0,
+        conflicts: Vec::new(),
+    };
+
+    for remote in remote_rules {
+        let synced = state.rules.get(&remote.id);
+        let remote_changed = synced.is_none_or(|synced| synced.version != remote.version);
+        let take_remote = match local_rules.get(&remote.id) {
+            None => {
+                result.added.push(remote.id.clone());
+                true
+            }
+            Some(local) if content_hash(local) == content_hash(&remote.rule_config) => {
+                result.unchanged += 1;
+                state.record(&remote.id, remote.version, local);
+                false
+            }
+            // Only changed locally; the next push publishes it
+            Some(_) if !remote_changed => {
+                result.unchanged += 1;
+                false
+            }
+            Some(local) if synced.is_some_and(|synced| synced.hash == content_hash(local)) => {
+                result.updated.push(remote.id.clone());
+                true
+            }
+            Some(_) => {
+                // Syncing to the registry's version lets the next push replace it, while the
+                // local rule still differs from what was synced
+                if param.on_conflict == ConflictResolution::KeepLocal {
+                    state.record(&remote.id, remote.version, &remote.rule_config);
+                }
+                result.conflicts.push(RuleConflict {
+                    rule_id: remote.id.clone(),
+                    resolution: param.on_conflict,
+                    remote: remote.clone(),
+                });
+                param.on_conflict == ConflictResolution::TakeRemote
+            }
+        };
+
+        if take_remote {
+            storage
+                .create_rule(CreateRuleParam {
+                    rule_config: remote.rule_config.clone(),
+                    overwrite: true,
+                })
+                .await?;
+            state.record(&remote.id, remote.version, &remote.rule_config);
+        }
+    }
+
+    state.save()?;
+    Ok(result)
+}
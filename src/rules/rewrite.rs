@@ -0,0 +1,318 @@
+//! # Rewriters
+//!
+//! A rule's `transform` section computes metavariables for its `fix` from the captured ones.
+//! The `rewrite` transformation applies the rule's `rewriters` inside a captured node, for
+//! changes a single fix template can't express, such as rewriting every element of a list:
+//!
+//! ```yaml
+//! rewriters:
+//!   - id: concat-to-string
+//!     rule: { pattern: "'' + $A" }
+//!     fix: String($A)
+//! transform:
+//!   STRINGS:
+//!     rewrite:
+//!       source: $$$ITEMS
+//!       rewriters: [concat-to-string]
+//!       joinBy: ", "
+//! fix: "[$STRINGS]"
+//! ```
+//!
+//! Each source node is visited from the top down, and the first rewriter matching a node
+//! replaces it; nodes below a replaced one are left alone. With `joinBy`, the rewritten named
+//! nodes of a `$$$` capture are joined by it, otherwise the text between them is kept.
+//! Rewriters can declare transforms of their own, using the rule's rewriters.
+
+use super::RuleEvaluator;
+use super::ast::Rule;
+use super::matcher::NodeRule;
+use super::parser::is_metavariable_name;
+use super::types::{RewriteTransform, RewriterConfig, RuleConfig, Transformation};
+use crate::errors::ServiceError;
+use crate::template::ReplacementTemplate;
+use crate::types::MatchResult;
+use ast_grep_core::matcher::MatcherExt;
+use ast_grep_core::meta_var::MetaVarEnv;
+use ast_grep_core::tree_sitter::StrDoc;
+use ast_grep_core::{AstGrep, Node, NodeMatch};
+use ast_grep_language::SupportLang as Language;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Range;
+
+/// Rewriters applying transforms of their own, nested deeper than this, are taken to loop
+const MAX_DEPTH: usize = 32;
+
+type Env<'t> = MetaVarEnv<'t, StrDoc<Language>>;
+
+/// Check that transforms name metavariables, read a captured one and refer to rewriters the
+/// rule declares, and that rewriter ids are unique
+pub fn check_transforms(config: &RuleConfig) -> Result<(), ServiceError> {
+    let mut ids = HashSet::new();
+    for rewriter in &config.rewriters {
+        if !ids.insert(rewriter.id.as_str()) {
+            return Err(ServiceError::ParserError(format!(
+                "Rewriter '{}' is declared more than once",
+                rewriter.id
+            )));
+        }
+    }
+    let transforms = std::iter::once(&config.transform)
+        .chain(config.rewriters.iter().map(|rewriter| &rewriter.transform));
+    for transform in transforms {
+        for (name, transformation) in transform {
+            if !is_metavariable_name(name) {
+                return Err(ServiceError::ParserError(format!(
+                    "Transform '{name}' must name a metavariable without the $, e.g. NEW for $NEW"
+                )));
+            }
+            let rewrite = transformation.rewrite.as_ref().ok_or_else(|| {
+                ServiceError::ParserError(format!(
+                    "Transform '{name}' must be a rewrite; other transformations are not supported"
+                ))
+            })?;
+            source_var(rewrite)?;
+            if let Some(unknown) = rewrite
+                .rewriters
+                .iter()
+                .find(|id| !ids.contains(id.as_str()))
+            {
+                return Err(ServiceError::ParserError(format!(
+                    "Transform '{name}' uses rewriter '{unknown}', which is not declared in rewriters"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Add the metavariables of `config.transform` to the vars of each of `matches`, found in
+/// `content` by `config`. Matches are located again in the syntax tree, since rewriters work on
+/// the captured nodes rather than on their text
+pub fn apply_transforms(
+    evaluator: &RuleEvaluator,
+    config: &RuleConfig,
+    content: &str,
+    lang: Language,
+    matches: &mut [MatchResult],
+) -> Result<(), ServiceError> {
+    if config.transform.is_empty() || matches.is_empty() {
+        return Ok(());
+    }
+    check_transforms(config)?;
+    let evaluator = evaluator.with_utils(&config.utils)?;
+    let rule = compile(&evaluator, &config.rule.clone().into(), lang)?;
+    let rewriters = Rewriters {
+        content,
+        compiled: config
+            .rewriters
+            .iter()
+            .map(|rewriter| {
+                Ok((
+                    rewriter.id.as_str(),
+                    compile_rewriter(&evaluator, rewriter, lang)?,
+                ))
+            })
+            .collect::<Result<_, ServiceError>>()?,
+    };
+
+    let ast = AstGrep::new(content, lang);
+    let root = ast.root();
+    for m in matches.iter_mut() {
+        let node_match = root
+            .dfs()
+            .filter(|node| {
+                let (start, end) = (node.start_pos(), node.end_pos());
+                start.line() == m.start_line
+                    && start.column(node) == m.start_col
+                    && end.line() == m.end_line
+                    && end.column(node) == m.end_col
+            })
+            .find_map(|node| rule.match_node(node))
+            .ok_or_else(|| {
+                ServiceError::Internal(format!(
+                    "Could not find the match on line {} again to apply the transforms of rule '{}'",
+                    m.start_line + 1,
+                    config.id
+                ))
+            })?;
+        let transformed = rewriters.transform(node_match.get_env(), &config.transform, 0)?;
+        m.vars.extend(transformed);
+    }
+    Ok(())
+}
+
+/// A rewriter compiled for one language
+struct CompiledRewriter<'a> {
+    rule: NodeRule,
+    constraints: Vec<(&'a str, NodeRule)>,
+    fix: ReplacementTemplate,
+    transform: &'a BTreeMap<String, Transformation>,
+}
+
+/// The rewriters of a rule, by id, and the code they rewrite
+struct Rewriters<'a> {
+    content: &'a str,
+    compiled: HashMap<&'a str, CompiledRewriter<'a>>,
+}
+
+impl Rewriters<'_> {
+    /// The value of each of `transform`'s metavariables, from the captures in `env`
+    fn transform(
+        &self,
+        env: &Env<'_>,
+        transform: &BTreeMap<String, Transformation>,
+        depth: usize,
+    ) -> Result<HashMap<String, String>, ServiceError> {
+        if depth > MAX_DEPTH {
+            return Err(ServiceError::Internal(format!(
+                "Rewriters were nested more than {MAX_DEPTH} levels deep; a rewriter probably rewrites its own match"
+            )));
+        }
+        let mut vars = HashMap::new();
+        for (name, rewrite) in transform
+            .iter()
+            .filter_map(|(name, t)| Some((name, t.rewrite.as_ref()?)))
+        {
+            let (var, multi) = source_var(rewrite)?;
+            let nodes = match multi {
+                true => env.get_multiple_matches(var),
+                false => env.get_match(var).cloned().into_iter().collect(),
+            };
+            let rewriters: Vec<&CompiledRewriter> = rewrite
+                .rewriters
+                .iter()
+                .filter_map(|id| self.compiled.get(id.as_str()))
+                .collect();
+
+            let text = match (&rewrite.join_by, nodes.first(), nodes.last()) {
+                (_, None, _) | (_, _, None) => String::new(),
+                (Some(join_by), _, _) => nodes
+                    .iter()
+                    .filter(|node| node.is_named())
+                    .map(|node| self.rewrite(node, &rewriters, depth))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(join_by),
+                (None, Some(first), Some(last)) => {
+                    let range = first.range().start..last.range().end;
+                    let mut edits = Vec::new();
+                    for node in &nodes {
+                        self.collect_edits(node.clone(), &rewriters, depth, &mut edits)?;
+                    }
+                    splice(self.content, range, edits)
+                }
+            };
+            vars.insert(name.clone(), text);
+        }
+        Ok(vars)
+    }
+
+    /// The text of `node` with the rewriters applied inside it
+    fn rewrite(
+        &self,
+        node: &Node<'_, StrDoc<Language>>,
+        rewriters: &[&CompiledRewriter],
+        depth: usize,
+    ) -> Result<String, ServiceError> {
+        let mut edits = Vec::new();
+        self.collect_edits(node.clone(), rewriters, depth, &mut edits)?;
+        Ok(splice(self.content, node.range(), edits))
+    }
+
+    /// Replacements of the topmost nodes within `node` that one of `rewriters` matches
+    fn collect_edits(
+        &self,
+        node: Node<'_, StrDoc<Language>>,
+        rewriters: &[&CompiledRewriter],
+        depth: usize,
+        edits: &mut Vec<(Range<usize>, String)>,
+    ) -> Result<(), ServiceError> {
+        for rewriter in rewriters {
+            if let Some(node_match) = rewriter.match_node(node.clone()) {
+                let mut vars: HashMap<String, String> = node_match.get_env().clone().into();
+                vars.extend(self.transform(node_match.get_env(), rewriter.transform, depth + 1)?);
+                edits.push((node.range(), rewriter.fix.render(&vars)));
+                return Ok(());
+            }
+        }
+        for child in node.children() {
+            self.collect_edits(child, rewriters, depth, edits)?;
+        }
+        Ok(())
+    }
+}
+
+impl CompiledRewriter<'_> {
+    /// The match of the rewriter on `node`, when its captures also satisfy its constraints
+    fn match_node<'t>(
+        &self,
+        node: Node<'t, StrDoc<Language>>,
+    ) -> Option<NodeMatch<'t, StrDoc<Language>>> {
+        let node_match = self.rule.match_node(node)?;
+        let satisfied = self.constraints.iter().all(|(name, constraint)| {
+            match node_match.get_env().get_match(name) {
+                Some(captured) => constraint.match_node(captured.clone()).is_some(),
+                None => true,
+            }
+        });
+        satisfied.then_some(node_match)
+    }
+}
+
+fn compile_rewriter<'a>(
+    evaluator: &RuleEvaluator,
+    rewriter: &'a RewriterConfig,
+    lang: Language,
+) -> Result<CompiledRewriter<'a>, ServiceError> {
+    let evaluator = evaluator.with_utils(&rewriter.utils)?;
+    Ok(CompiledRewriter {
+        rule: compile(&evaluator, &rewriter.rule.clone().into(), lang)?,
+        constraints: rewriter
+            .constraints
+            .iter()
+            .map(|(name, rule)| {
+                Ok((
+                    name.as_str(),
+                    compile(&evaluator, &rule.clone().into(), lang)?,
+                ))
+            })
+            .collect::<Result<_, ServiceError>>()?,
+        fix: ReplacementTemplate::parse(&rewriter.fix)?,
+        transform: &rewriter.transform,
+    })
+}
+
+fn compile(
+    evaluator: &RuleEvaluator,
+    rule: &Rule,
+    lang: Language,
+) -> Result<NodeRule, ServiceError> {
+    evaluator.compile_node_rule(&evaluator.resolve_matches(rule)?, lang)
+}
+
+/// The metavariable a rewrite reads, and whether it is a `$$$` one
+fn source_var(rewrite: &RewriteTransform) -> Result<(&str, bool), ServiceError> {
+    let source = rewrite.source.trim();
+    let (var, multi) = match source.strip_prefix("$$$") {
+        Some(var) => (var, true),
+        None => (source.strip_prefix('$').unwrap_or_default(), false),
+    };
+    match is_metavariable_name(var) {
+        true => Ok((var, multi)),
+        false => Err(ServiceError::ParserError(format!(
+            "Rewrite source '{source}' must be a metavariable like $ARG or $$$ARGS"
+        ))),
+    }
+}
+
+/// The text of `range` with `edits`, non-overlapping and in order, applied
+fn splice(content: &str, range: Range<usize>, edits: Vec<(Range<usize>, String)>) -> String {
+    let mut text = String::new();
+    let mut copied = range.start;
+    for (edit, replacement) in edits {
+        text.push_str(&content[copied..edit.start]);
+        text.push_str(&replacement);
+        copied = edit.end;
+    }
+    text.push_str(&content[copied..range.end]);
+    text
+}
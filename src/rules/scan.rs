@@ -0,0 +1,125 @@
+//! # Severity Buckets
+//!
+//! `scan` runs the stored rules over a set of files and groups what they find by the rule's
+//! severity. Each bucket reports its total and per-rule counts up front and lists one page of
+//! findings, so a client can render a summary header and then page through a single bucket
+//! with `severity` and `offset`.
+
+use super::types::{RuleConfig, ScanFinding, SeverityBucket};
+use crate::errors::ServiceError;
+use std::collections::BTreeMap;
+
+/// Bucket names, most severe first
+pub const SEVERITIES: [&str; 3] = ["error", "warning", "info"];
+
+/// The bucket a rule's findings go into; rules without a severity are informational
+pub fn severity_of(rule: &RuleConfig) -> &'static str {
+    match rule.severity.as_deref() {
+        Some("error") => "error",
+        Some("warning") => "warning",
+        _ => "info",
+    }
+}
+
+/// Check a requested bucket name
+pub fn validate_severity(severity: Option<&str>) -> Result<(), ServiceError> {
+    match severity {
+        Some(severity) if !SEVERITIES.contains(&severity) => Err(ServiceError::Internal(format!(
+            "Invalid severity '{severity}'. Must be 'error', 'warning', or 'info'"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Group `findings` (paired with their bucket) into one bucket per severity. With `selected`,
+/// only that bucket lists findings, starting at `offset`; otherwise every bucket lists its
+/// first page
+pub fn bucket_findings(
+    findings: Vec<(&'static str, ScanFinding)>,
+    selected: Option<&str>,
+    offset: usize,
+    page_size: usize,
+) -> Vec<SeverityBucket> {
+    let mut grouped: BTreeMap<&str, Vec<ScanFinding>> = BTreeMap::new();
+    for (severity, finding) in findings {
+        grouped.entry(severity).or_default().push(finding);
+    }
+
+    SEVERITIES
+        .iter()
+        .map(|&severity| {
+            let mut all = grouped.remove(severity).unwrap_or_default();
+            all.sort_by(|a, b| {
+                (&a.file_path, a.start_line, a.start_col, &a.rule_id).cmp(&(
+                    &b.file_path,
+                    b.start_line,
+                    b.start_col,
+                    &b.rule_id,
+                ))
+            });
+            let mut rule_counts = BTreeMap::new();
+            for finding in &all {
+                *rule_counts.entry(finding.rule_id.clone()).or_insert(0) += 1;
+            }
+
+            let start = match selected {
+                Some(selected) if selected == severity => offset.min(all.len()),
+                Some(_) => all.len(),
+                None => 0,
+            };
+            let end = start.saturating_add(page_size).min(all.len());
+            SeverityBucket {
+                severity: severity.to_string(),
+                count: all.len(),
+                rule_counts,
+                next_offset: (end < all.len()).then_some(end),
+                findings: all.drain(start..end).collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(rule_id: &str, line: usize) -> ScanFinding {
+        ScanFinding {
+            rule_id: rule_id.to_string(),
+            message: rule_id.to_string(),
+            file_path: "a.js".to_string(),
+            start_line: line,
+            end_line: line,
+            start_col: 0,
+            end_col: 1,
+            text: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_bucket_findings_pages_the_selected_bucket() {
+        let findings = vec![
+            ("warning", finding("w", 3)),
+            ("error", finding("e", 2)),
+            ("warning", finding("w", 1)),
+            ("warning", finding("v", 5)),
+        ];
+
+        let buckets = bucket_findings(findings.clone(), None, 0, 2);
+        let counts: Vec<_> = buckets
+            .iter()
+            .map(|b| (b.count, b.findings.len()))
+            .collect();
+        assert_eq!(counts, [(1, 1), (3, 2), (0, 0)]);
+        assert_eq!(buckets[1].findings[0].start_line, 1);
+        assert_eq!(buckets[1].next_offset, Some(2));
+        assert_eq!(buckets[1].rule_counts["w"], 2);
+
+        let buckets = bucket_findings(findings, Some("warning"), 2, 2);
+        assert!(buckets[0].findings.is_empty());
+        assert_eq!(buckets[0].count, 1);
+        assert_eq!(buckets[1].findings.len(), 1);
+        assert_eq!(buckets[1].findings[0].rule_id, "v");
+        assert_eq!(buckets[1].next_offset, None);
+    }
+}
@@ -1,20 +1,28 @@
-use super::{RuleEvaluator, RuleStorage};
+use super::{RuleEvaluator, RuleStatsStore, RuleStorage};
 use crate::config::ServiceConfig;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct RuleService {
     #[allow(dead_code)]
     config: ServiceConfig,
     evaluator: RuleEvaluator,
-    storage: RuleStorage,
+    storage: Arc<dyn RuleStorage>,
+    stats: RuleStatsStore,
 }
 
 impl RuleService {
-    pub fn new(config: ServiceConfig, evaluator: RuleEvaluator, storage: RuleStorage) -> Self {
+    pub fn new(
+        config: ServiceConfig,
+        evaluator: RuleEvaluator,
+        storage: Arc<dyn RuleStorage>,
+    ) -> Self {
+        let stats = RuleStatsStore::new(&config.rules_directory);
         Self {
             config,
             evaluator,
             storage,
+            stats,
         }
     }
 
@@ -22,7 +30,11 @@ impl RuleService {
         &self.evaluator
     }
 
-    pub fn storage(&self) -> &RuleStorage {
-        &self.storage
+    pub fn storage(&self) -> &dyn RuleStorage {
+        self.storage.as_ref()
+    }
+
+    pub fn stats(&self) -> &RuleStatsStore {
+        &self.stats
     }
 }
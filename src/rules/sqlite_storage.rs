@@ -0,0 +1,393 @@
+//! # SQLite Rule Storage
+//!
+//! Keeps created rules in one sqlite database file instead of one YAML file per rule. Every
+//! write runs in a single transaction, so concurrent `create_rule` calls from several clients
+//! cannot leave a half-written rule behind, and `list_rules` filters by language, severity and
+//! tag with indexed queries instead of parsing every stored rule.
+//!
+//! Rule packs are kept as a pack name per rule, so `import_rules` can group rules whatever file
+//! they came from.
+//!
+//! The rule directories from `sgconfig.yml` are still read as YAML files. Their rules are listed
+//! after the database's, which wins when both have a rule with the same id.
+
+use super::storage::{FileRuleStorage, RuleStorage, StoredRule, parse_import, validate_pack_name};
+use super::types::{
+    CreateRuleParam, CreateRuleResult, DeleteRuleParam, DeleteRuleResult, GetRuleParam,
+    GetRuleResult, ListRulesParam, ListRulesResult, RuleConfig, RuleInfo,
+};
+use crate::env_interpolation::parse_interpolated_rule;
+use crate::errors::ServiceError;
+use async_trait::async_trait;
+use rusqlite::{Connection, OptionalExtension, Transaction, TransactionBehavior, params};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS rules (
+    id TEXT PRIMARY KEY,
+    language TEXT NOT NULL,
+    severity TEXT,
+    message TEXT,
+    has_fix INTEGER NOT NULL,
+    config TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS rules_by_language ON rules (language);
+CREATE INDEX IF NOT EXISTS rules_by_severity ON rules (severity);
+CREATE TABLE IF NOT EXISTS rule_tags (
+    rule_id TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (tag, rule_id)
+);
+CREATE INDEX IF NOT EXISTS rule_tags_by_rule ON rule_tags (rule_id);
+CREATE TABLE IF NOT EXISTS rule_packs (
+    rule_id TEXT PRIMARY KEY,
+    pack TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS rule_packs_by_pack ON rule_packs (pack);
+";
+
+/// Separates the tags of a rule in the `group_concat` of `list_rules`
+const TAG_SEPARATOR: char = '\u{1f}';
+
+/// How long a write waits for another process holding the database lock
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Rules kept in a sqlite database, with read access to YAML rule directories
+pub struct SqliteRuleStorage {
+    path: PathBuf,
+    connection: Mutex<Connection>,
+    env_vars: BTreeMap<String, String>,
+    directories: Option<FileRuleStorage>,
+}
+
+impl SqliteRuleStorage {
+    /// Open the database at `path`, creating it and its parent directory if needed
+    pub fn open(path: &Path) -> Result<Self, ServiceError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let connection = Connection::open(path)?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        // Readers don't block the writer, so listing rules stays fast while LLMs create them
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            connection: Mutex::new(connection),
+            env_vars: BTreeMap::new(),
+            directories: None,
+        })
+    }
+
+    /// Interpolate these environment variables into stored rules when they are loaded. The
+    /// database keeps their `${NAME}` placeholders
+    pub fn with_env_vars(mut self, env_vars: BTreeMap<String, String>) -> Self {
+        self.directories = self
+            .directories
+            .map(|directories| directories.with_env_vars(env_vars.clone()));
+        self.env_vars = env_vars;
+        self
+    }
+
+    /// Also read rules from the YAML files in `directories`, such as the `ruleDirs` of
+    /// `sgconfig.yml`. New rules always go to the database
+    pub fn with_directories(mut self, directories: Vec<PathBuf>) -> Self {
+        self.directories = match directories.is_empty() {
+            true => None,
+            false => Some(
+                FileRuleStorage::with_directories(directories).with_env_vars(self.env_vars.clone()),
+            ),
+        };
+        self
+    }
+
+    /// Where a rule is reported to live, in place of a file path
+    fn location(&self, rule_id: &str) -> String {
+        format!("{}#{rule_id}", self.path.display())
+    }
+
+    fn parse(&self, content: &str) -> Result<RuleConfig, ServiceError> {
+        parse_interpolated_rule(content, &self.env_vars)
+    }
+
+    /// Insert or replace `rule`, returning whether a rule with its id already existed
+    fn store(
+        &self,
+        rule: &RuleConfig,
+        content: &str,
+        overwrite: bool,
+    ) -> Result<bool, ServiceError> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let exists = Self::check_new(&transaction, rule, overwrite)?;
+        Self::insert(&transaction, rule, content)?;
+        transaction.commit()?;
+        Ok(exists)
+    }
+
+    /// Whether a rule with `rule`'s id is stored, refused unless `overwrite` is set
+    fn check_new(
+        transaction: &Transaction,
+        rule: &RuleConfig,
+        overwrite: bool,
+    ) -> Result<bool, ServiceError> {
+        let exists: bool = transaction.query_row(
+            "SELECT EXISTS (SELECT 1 FROM rules WHERE id = ?1)",
+            [&rule.id],
+            |row| row.get(0),
+        )?;
+        if exists && !overwrite {
+            return Err(ServiceError::Internal(format!(
+                "Rule '{}' already exists. Use overwrite=true to replace it.",
+                rule.id
+            )));
+        }
+        Ok(exists)
+    }
+
+    /// Insert or replace `rule` and its tags; its pack is left as it was
+    fn insert(
+        transaction: &Transaction,
+        rule: &RuleConfig,
+        content: &str,
+    ) -> Result<(), ServiceError> {
+        transaction.execute(
+            "INSERT OR REPLACE INTO rules (id, language, severity, message, has_fix, config)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                rule.id,
+                rule.language,
+                rule.severity,
+                rule.message,
+                rule.fix.is_some(),
+                content
+            ],
+        )?;
+        transaction.execute("DELETE FROM rule_tags WHERE rule_id = ?1", [&rule.id])?;
+        for tag in &rule.tags {
+            transaction.execute(
+                "INSERT OR IGNORE INTO rule_tags (rule_id, tag) VALUES (?1, ?2)",
+                params![rule.id, tag],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Rules in the database matching the filters in `param`, ordered by id
+    fn query(&self, param: &ListRulesParam) -> Result<Vec<RuleInfo>, ServiceError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT id, message, language, severity, has_fix,
+                    (SELECT group_concat(tag, char(31)) FROM rule_tags WHERE rule_id = rules.id),
+                    (SELECT pack FROM rule_packs WHERE rule_id = rules.id)
+             FROM rules
+             WHERE (?1 IS NULL OR language = ?1)
+               AND (?2 IS NULL OR severity = ?2)
+               AND (?3 IS NULL OR EXISTS
+                    (SELECT 1 FROM rule_tags WHERE rule_id = rules.id AND tag = ?3))
+             ORDER BY id",
+        )?;
+        let rows =
+            statement.query_map(params![param.language, param.severity, param.tag], |row| {
+                let id: String = row.get(0)?;
+                let tags: Option<String> = row.get(5)?;
+                let mut tags: Vec<String> = tags
+                    .map(|tags| tags.split(TAG_SEPARATOR).map(str::to_string).collect())
+                    .unwrap_or_default();
+                tags.sort();
+                Ok(RuleInfo {
+                    file_path: self.location(&id),
+                    id,
+                    message: row.get(1)?,
+                    language: row.get(2)?,
+                    severity: row.get(3)?,
+                    has_fix: row.get(4)?,
+                    tags,
+                    pack: row.get(6)?,
+                })
+            })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// The configuration of the rule `rule_id` as it was stored
+    fn content(&self, rule_id: &str) -> Result<Option<String>, ServiceError> {
+        let connection = self.connection.lock().unwrap();
+        Ok(connection
+            .query_row("SELECT config FROM rules WHERE id = ?1", [rule_id], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    }
+
+    /// Every rule in the database, ordered by id
+    fn contents(&self) -> Result<Vec<StoredRule>, ServiceError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT id, config, (SELECT pack FROM rule_packs WHERE rule_id = rules.id)
+             FROM rules ORDER BY id",
+        )?;
+        let rows = statement.query_map([], |row| {
+            let id: String = row.get(0)?;
+            Ok(StoredRule {
+                location: self.location(&id),
+                id,
+                content: row.get(1)?,
+                pack: row.get(2)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Remove the rule `rule_id`, returning whether it was in the database
+    fn remove(&self, rule_id: &str) -> Result<bool, ServiceError> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        transaction.execute("DELETE FROM rule_tags WHERE rule_id = ?1", [rule_id])?;
+        transaction.execute("DELETE FROM rule_packs WHERE rule_id = ?1", [rule_id])?;
+        let deleted = transaction.execute("DELETE FROM rules WHERE id = ?1", [rule_id])? > 0;
+        transaction.commit()?;
+        Ok(deleted)
+    }
+}
+
+#[async_trait]
+impl RuleStorage for SqliteRuleStorage {
+    async fn create_rule(&self, param: CreateRuleParam) -> Result<CreateRuleResult, ServiceError> {
+        let rule = self.parse(&param.rule_config)?;
+        let existed = self.store(&rule, &param.rule_config, param.overwrite)?;
+        Ok(CreateRuleResult {
+            file_path: self.location(&rule.id),
+            rule_id: rule.id,
+            created: !existed,
+        })
+    }
+
+    async fn list_rules(&self, param: ListRulesParam) -> Result<ListRulesResult, ServiceError> {
+        let mut rules = self.query(&param)?;
+        if let Some(directories) = &self.directories {
+            let ids: HashSet<String> = self
+                .query(&ListRulesParam {
+                    language: None,
+                    severity: None,
+                    tag: None,
+                })?
+                .into_iter()
+                .map(|rule| rule.id)
+                .collect();
+            let listed = directories.list_rules(param).await?;
+            rules.extend(
+                listed
+                    .rules
+                    .into_iter()
+                    .filter(|rule| !ids.contains(&rule.id)),
+            );
+        }
+        Ok(ListRulesResult { rules })
+    }
+
+    async fn load_all_rules(&self) -> Result<Vec<RuleConfig>, ServiceError> {
+        let mut rules = self
+            .contents()?
+            .iter()
+            .map(|stored| self.parse(&stored.content))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(directories) = &self.directories {
+            let ids: HashSet<String> = rules.iter().map(|rule| rule.id.clone()).collect();
+            let loaded = directories.load_all_rules().await?;
+            rules.extend(loaded.into_iter().filter(|rule| !ids.contains(&rule.id)));
+        }
+        Ok(rules)
+    }
+
+    async fn get_rule(&self, param: GetRuleParam) -> Result<GetRuleResult, ServiceError> {
+        if let Some(content) = self.content(&param.rule_id)? {
+            return Ok(GetRuleResult {
+                rule_config: self.parse(&content)?,
+                file_path: self.location(&param.rule_id),
+            });
+        }
+        match &self.directories {
+            Some(directories) => directories.get_rule(param).await,
+            None => Err(ServiceError::Internal(format!(
+                "Rule '{}' not found",
+                param.rule_id
+            ))),
+        }
+    }
+
+    async fn delete_rule(&self, param: DeleteRuleParam) -> Result<DeleteRuleResult, ServiceError> {
+        if self.remove(&param.rule_id)? {
+            return Ok(DeleteRuleResult {
+                rule_id: param.rule_id,
+                deleted: true,
+                message: "Rule deleted successfully".to_string(),
+            });
+        }
+        match &self.directories {
+            Some(directories) => directories.delete_rule(param).await,
+            None => Ok(DeleteRuleResult {
+                rule_id: param.rule_id,
+                deleted: false,
+                message: "Rule not found".to_string(),
+            }),
+        }
+    }
+
+    fn rule_sources(&self) -> Result<Vec<(String, String)>, ServiceError> {
+        let mut sources: Vec<(String, String)> = self
+            .contents()?
+            .into_iter()
+            .map(|stored| (stored.location, stored.content))
+            .collect();
+        if let Some(directories) = &self.directories {
+            sources.extend(directories.rule_sources()?);
+        }
+        Ok(sources)
+    }
+
+    fn stored_rules(&self) -> Result<Vec<StoredRule>, ServiceError> {
+        let mut rules = self.contents()?;
+        if let Some(directories) = &self.directories {
+            let ids: HashSet<String> = rules.iter().map(|rule| rule.id.clone()).collect();
+            let stored = directories.stored_rules()?;
+            rules.extend(stored.into_iter().filter(|rule| !ids.contains(&rule.id)));
+        }
+        Ok(rules)
+    }
+
+    async fn import_rules(
+        &self,
+        rule_configs: Vec<String>,
+        pack: Option<String>,
+        overwrite: bool,
+    ) -> Result<Vec<CreateRuleResult>, ServiceError> {
+        let rules = parse_import(&rule_configs, |content| self.parse(content))?;
+        if let Some(pack) = &pack {
+            validate_pack_name(pack)?;
+        }
+
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let mut imported = Vec::with_capacity(rules.len());
+        for (rule, content) in rules.iter().zip(&rule_configs) {
+            let existed = Self::check_new(&transaction, rule, overwrite)?;
+            Self::insert(&transaction, rule, content)?;
+            if let Some(pack) = &pack {
+                transaction.execute(
+                    "INSERT OR REPLACE INTO rule_packs (rule_id, pack) VALUES (?1, ?2)",
+                    params![rule.id, pack],
+                )?;
+            }
+            imported.push(CreateRuleResult {
+                rule_id: rule.id.clone(),
+                created: !existed,
+                file_path: self.location(&rule.id),
+            });
+        }
+        transaction.commit()?;
+        Ok(imported)
+    }
+}
@@ -0,0 +1,154 @@
+//! Per-rule effectiveness statistics persisted next to the stored rules
+//!
+//! Every rule search records how many matches the rule produced and how many were suppressed,
+//! and applied rule replacements record the number of fixes. The totals survive restarts so the
+//! `rule_stats` tool can point out rules that never fire or generate excessive noise.
+
+use crate::errors::ServiceError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name of the statistics store inside the rules directory
+pub const RULE_STATS_FILE: &str = ".rule-stats.json";
+/// Number of per-scan match counts kept for each rule
+const RECENT_SCANS_KEPT: usize = 20;
+
+/// Accumulated statistics for one rule id
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleStats {
+    pub rule_id: String,
+    /// Number of rule searches run with this rule
+    pub scans: u64,
+    /// Scans that reported at least one match
+    pub scans_with_matches: u64,
+    /// Matches reported across all scans, excluding suppressed ones
+    pub total_matches: u64,
+    /// Replacements written to disk by `rule_replace`
+    pub fixes_applied: u64,
+    /// Matches hidden by `ast-grep-ignore` comments
+    pub suppressions: u64,
+    /// Unix timestamp of the most recent scan
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_scan: Option<u64>,
+    /// Unix timestamp of the most recent scan that found matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_match: Option<u64>,
+    /// Match counts of the most recent scans, oldest first
+    #[serde(default)]
+    pub recent_matches: Vec<u64>,
+}
+
+impl RuleStats {
+    pub fn average_matches_per_scan(&self) -> f64 {
+        if self.scans == 0 {
+            0.0
+        } else {
+            self.total_matches as f64 / self.scans as f64
+        }
+    }
+
+    /// Share of all matches, reported or suppressed, that were suppressed
+    pub fn suppression_rate(&self) -> f64 {
+        let seen = self.total_matches + self.suppressions;
+        if seen == 0 {
+            0.0
+        } else {
+            self.suppressions as f64 / seen as f64
+        }
+    }
+}
+
+/// JSON-file backed store shared between clones of a service
+#[derive(Clone)]
+pub struct RuleStatsStore {
+    path: PathBuf,
+    lock: Arc<Mutex<()>>,
+}
+
+impl RuleStatsStore {
+    pub fn new(rules_directory: &Path) -> Self {
+        Self {
+            path: rules_directory.join(RULE_STATS_FILE),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// All recorded statistics keyed by rule id; empty when nothing has been recorded yet
+    pub fn load(&self) -> Result<BTreeMap<String, RuleStats>, ServiceError> {
+        let _guard = self.lock.lock().unwrap();
+        self.read()
+    }
+
+    /// Record one scan of `rule_id` that reported `matches` and suppressed `suppressions`
+    pub fn record_scan(
+        &self,
+        rule_id: &str,
+        matches: usize,
+        suppressions: usize,
+    ) -> Result<(), ServiceError> {
+        let now = unix_now();
+        self.update(rule_id, |stats| {
+            stats.scans += 1;
+            stats.total_matches += matches as u64;
+            stats.suppressions += suppressions as u64;
+            stats.last_scan = Some(now);
+            if matches > 0 {
+                stats.scans_with_matches += 1;
+                stats.last_match = Some(now);
+            }
+            stats.recent_matches.push(matches as u64);
+            if stats.recent_matches.len() > RECENT_SCANS_KEPT {
+                stats.recent_matches.remove(0);
+            }
+        })
+    }
+
+    /// Record `fixes` replacements applied by `rule_id`
+    pub fn record_fixes(&self, rule_id: &str, fixes: usize) -> Result<(), ServiceError> {
+        self.update(rule_id, |stats| stats.fixes_applied += fixes as u64)
+    }
+
+    fn update(
+        &self,
+        rule_id: &str,
+        apply: impl FnOnce(&mut RuleStats),
+    ) -> Result<(), ServiceError> {
+        let _guard = self.lock.lock().unwrap();
+        let mut all = self.read()?;
+        let stats = all.entry(rule_id.to_string()).or_insert_with(|| RuleStats {
+            rule_id: rule_id.to_string(),
+            ..Default::default()
+        });
+        apply(stats);
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&all)?;
+        fs::write(&self.path, json)?;
+        Ok(())
+    }
+
+    fn read(&self) -> Result<BTreeMap<String, RuleStats>, ServiceError> {
+        match fs::read_to_string(&self.path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
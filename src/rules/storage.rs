@@ -1,138 +1,448 @@
-use super::parser::parse_rule_config;
+use super::parser::{join_rule_documents, split_rule_documents};
 use super::types::{
     CreateRuleParam, CreateRuleResult, DeleteRuleParam, DeleteRuleResult, GetRuleParam,
     GetRuleResult, ListRulesParam, ListRulesResult, RuleConfig, RuleInfo,
 };
+use crate::config::ServiceConfig;
+use crate::env_interpolation::parse_interpolated_rule;
 use crate::errors::ServiceError;
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+/// Where created rules are kept and stored rules are loaded from
+// `async_trait` re-adds `#[must_use]` to the boxed future it returns
+#[allow(clippy::double_must_use)]
+#[async_trait]
+pub trait RuleStorage: Send + Sync {
+    /// Store a rule, replacing one with the same id only when `overwrite` is set
+    async fn create_rule(&self, param: CreateRuleParam) -> Result<CreateRuleResult, ServiceError>;
+
+    /// Stored rules matching the filters in `param`; rule ids are unique, the first one wins
+    async fn list_rules(&self, param: ListRulesParam) -> Result<ListRulesResult, ServiceError>;
+
+    /// Every stored rule, deduplicated by id the same way as `list_rules`
+    async fn load_all_rules(&self) -> Result<Vec<RuleConfig>, ServiceError>;
+
+    async fn get_rule(&self, param: GetRuleParam) -> Result<GetRuleResult, ServiceError>;
+
+    async fn delete_rule(&self, param: DeleteRuleParam) -> Result<DeleteRuleResult, ServiceError>;
+
+    /// Location and content of every stored rule, including ones that don't parse
+    fn rule_sources(&self) -> Result<Vec<(String, String)>, ServiceError>;
+
+    /// Every stored rule as written, before `${NAME}` interpolation, deduplicated by id the same
+    /// way as `list_rules`. Rules that don't parse are left out
+    fn stored_rules(&self) -> Result<Vec<StoredRule>, ServiceError>;
+
+    /// Store all of `rule_configs`, together as the rule pack `pack` when it is set. Nothing is
+    /// stored when one of them doesn't parse, two share an id, or one would replace a stored
+    /// rule without `overwrite`
+    async fn import_rules(
+        &self,
+        rule_configs: Vec<String>,
+        pack: Option<String>,
+        overwrite: bool,
+    ) -> Result<Vec<CreateRuleResult>, ServiceError>;
+}
+
+/// A stored rule as it was written
+#[derive(Debug, Clone)]
+pub struct StoredRule {
+    pub id: String,
+    /// The file, or database entry, the rule was read from
+    pub location: String,
+    pub pack: Option<String>,
+    /// The configuration before `${NAME}` interpolation
+    pub content: String,
+}
+
+/// Refuse pack names that can't be used as a file name
+pub fn validate_pack_name(pack: &str) -> Result<(), ServiceError> {
+    let valid = !pack.is_empty()
+        && !pack.starts_with('.')
+        && pack
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    match valid {
+        true => Ok(()),
+        false => Err(ServiceError::Internal(format!(
+            "Invalid rule pack name '{pack}': use letters, digits, '-', '_' and '.'"
+        ))),
+    }
+}
+
+/// Parse every configuration of an import with `parse`, refusing the import when one doesn't
+/// parse or two share an id
+pub(crate) fn parse_import(
+    rule_configs: &[String],
+    parse: impl Fn(&str) -> Result<RuleConfig, ServiceError>,
+) -> Result<Vec<RuleConfig>, ServiceError> {
+    let mut rules: Vec<RuleConfig> = Vec::with_capacity(rule_configs.len());
+    for (index, content) in rule_configs.iter().enumerate() {
+        let rule = parse(content).map_err(|e| match e {
+            ServiceError::ParserError(message) => {
+                ServiceError::ParserError(format!("rule {} of the import: {message}", index + 1))
+            }
+            e => e,
+        })?;
+        if rules.iter().any(|other| other.id == rule.id) {
+            return Err(ServiceError::Internal(format!(
+                "Rule id '{}' is imported more than once",
+                rule.id
+            )));
+        }
+        rules.push(rule);
+    }
+    match rules.is_empty() {
+        true => Err(ServiceError::Internal("No rules to import".to_string())),
+        false => Ok(rules),
+    }
+}
+
+fn already_exists(rule_id: &str) -> ServiceError {
+    ServiceError::Internal(format!(
+        "Rule '{rule_id}' already exists. Use overwrite=true to replace it."
+    ))
+}
+
+/// The storage `config` asks for: the sqlite database in `rules_database` when it is set,
+/// otherwise YAML files in the rule directories
+pub fn open_rule_storage(config: &ServiceConfig) -> Result<Arc<dyn RuleStorage>, ServiceError> {
+    let Some(database) = &config.rules_database else {
+        return Ok(Arc::new(
+            FileRuleStorage::with_directories(config.all_rule_directories())
+                .with_env_vars(config.env_vars.clone()),
+        ));
+    };
+    #[cfg(feature = "sqlite")]
+    {
+        Ok(Arc::new(
+            super::sqlite_storage::SqliteRuleStorage::open(database)?
+                .with_directories(config.additional_rule_dirs.clone())
+                .with_env_vars(config.env_vars.clone()),
+        ))
+    }
+    #[cfg(not(feature = "sqlite"))]
+    Err(ServiceError::Internal(format!(
+        "Cannot open {}: this build has no sqlite support",
+        database.display()
+    )))
+}
+
+/// Rules kept as YAML files in a list of directories. New rules go to the first one, one file per
+/// rule. A file may hold several `---` separated rules; it is then a rule pack named after the
+/// file
 #[derive(Clone)]
-pub struct RuleStorage {
+pub struct FileRuleStorage {
     rule_directories: Vec<PathBuf>,
+    env_vars: BTreeMap<String, String>,
+}
+
+/// One rule of a rule file
+struct RuleDocument {
+    path: PathBuf,
+    /// Position of the rule in its file
+    index: usize,
+    /// Number of rules in the file
+    count: usize,
+    content: String,
 }
 
-impl RuleStorage {
+impl RuleDocument {
+    /// The file, followed by the rule's position when the file holds several
+    fn location(&self) -> String {
+        match self.count {
+            1 => self.path.display().to_string(),
+            _ => format!("{}#{}", self.path.display(), self.index + 1),
+        }
+    }
+
+    /// The name of the file, unless it holds only this rule and is named after it
+    fn pack(&self, rule_id: &str) -> Option<String> {
+        let stem = self.path.file_stem()?.to_str()?;
+        (self.count > 1 || stem != rule_id).then(|| stem.to_string())
+    }
+}
+
+impl FileRuleStorage {
     pub fn new(rules_directory: PathBuf) -> Self {
         Self {
             rule_directories: vec![rules_directory],
+            env_vars: BTreeMap::new(),
         }
     }
 
     pub fn with_directories(directories: Vec<PathBuf>) -> Self {
         Self {
             rule_directories: directories,
+            env_vars: BTreeMap::new(),
         }
     }
 
-    pub async fn create_rule(
-        &self,
-        param: CreateRuleParam,
-    ) -> Result<CreateRuleResult, ServiceError> {
-        // Parse and validate the rule config
-        let rule = parse_rule_config(&param.rule_config)?;
+    /// Interpolate these environment variables into stored rules when they are loaded. The
+    /// files keep their `${NAME}` placeholders
+    pub fn with_env_vars(mut self, env_vars: BTreeMap<String, String>) -> Self {
+        self.env_vars = env_vars;
+        self
+    }
+
+    fn parse(&self, content: &str) -> Result<RuleConfig, ServiceError> {
+        parse_interpolated_rule(content, &self.env_vars)
+    }
 
-        // Use the first directory for creating new rules
-        let primary_dir = self
-            .rule_directories
+    fn primary_dir(&self) -> Result<&PathBuf, ServiceError> {
+        self.rule_directories
             .first()
-            .ok_or_else(|| ServiceError::Internal("No rule directories configured".to_string()))?;
+            .ok_or_else(|| ServiceError::Internal("No rule directories configured".to_string()))
+    }
 
-        // Ensure rules directory exists
-        fs::create_dir_all(primary_dir)?;
+    /// Every rule in the YAML files of `directories`, in directory order and then by file name.
+    /// A file without any rule is kept as one, so that it is reported as not parsing
+    fn documents(directories: &[PathBuf]) -> Result<Vec<RuleDocument>, ServiceError> {
+        let mut documents = Vec::new();
+        for directory in directories {
+            if !directory.exists() {
+                continue;
+            }
+            let mut paths = Vec::new();
+            for entry in fs::read_dir(directory)? {
+                let path = entry?.path();
+                if matches!(
+                    path.extension().and_then(|s| s.to_str()),
+                    Some("yaml" | "yml")
+                ) {
+                    paths.push(path);
+                }
+            }
+            paths.sort();
+            for path in paths {
+                let content = fs::read_to_string(&path)?;
+                let mut split = split_rule_documents(&content);
+                if split.is_empty() {
+                    split.push(&content);
+                }
+                let count = split.len();
+                documents.extend(split.into_iter().enumerate().map(|(index, document)| {
+                    RuleDocument {
+                        path: path.clone(),
+                        index,
+                        count,
+                        content: document.to_string(),
+                    }
+                }));
+            }
+        }
+        Ok(documents)
+    }
 
-        let file_path = primary_dir.join(format!("{}.yaml", rule.id));
+    /// The rules in `directories` that parse, each with the document it was read from
+    fn parsed(
+        &self,
+        directories: &[PathBuf],
+    ) -> Result<Vec<(RuleDocument, RuleConfig)>, ServiceError> {
+        Ok(Self::documents(directories)?
+            .into_iter()
+            .filter_map(|document| {
+                let rule = self.parse(&document.content).ok()?;
+                Some((document, rule))
+            })
+            .collect())
+    }
 
-        // Check if file already exists
-        let is_update = file_path.exists();
+    /// The first document in `directories` holding the rule `rule_id`
+    fn find(
+        &self,
+        directories: &[PathBuf],
+        rule_id: &str,
+    ) -> Result<Option<RuleDocument>, ServiceError> {
+        Ok(self
+            .parsed(directories)?
+            .into_iter()
+            .find(|(_, rule)| rule.id == rule_id)
+            .map(|(document, _)| document))
+    }
 
-        if is_update && !param.overwrite {
-            return Err(ServiceError::Internal(format!(
-                "Rule '{}' already exists. Use overwrite=true to replace it.",
-                rule.id
-            )));
+    /// The rules of `path` as written
+    fn read_documents(path: &Path) -> Result<Vec<String>, ServiceError> {
+        Ok(split_rule_documents(&fs::read_to_string(path)?)
+            .into_iter()
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Replace the rules of `path` with `documents`, removing the file when none are left
+    fn rewrite(path: &Path, documents: &[String]) -> Result<(), ServiceError> {
+        match documents.is_empty() {
+            true => fs::remove_file(path)?,
+            false => fs::write(path, join_rule_documents(documents))?,
         }
+        Ok(())
+    }
 
-        // Write the rule to file
-        fs::write(&file_path, &param.rule_config)?;
+    /// Write `content`, the configuration of `rule`, in place of the rule with its id in the
+    /// primary directory, or to a new `<id>.yaml` there
+    fn write_rule(
+        &self,
+        rule: &RuleConfig,
+        content: &str,
+        overwrite: bool,
+    ) -> Result<CreateRuleResult, ServiceError> {
+        let primary_dir = self.primary_dir()?;
+        if let Some(document) = self.find(std::slice::from_ref(primary_dir), &rule.id)? {
+            if !overwrite {
+                return Err(already_exists(&rule.id));
+            }
+            if document.count == 1 {
+                fs::write(&document.path, content)?;
+            } else {
+                let mut documents = Self::read_documents(&document.path)?;
+                documents[document.index] = content.to_string();
+                Self::rewrite(&document.path, &documents)?;
+            }
+            return Ok(CreateRuleResult {
+                rule_id: rule.id.clone(),
+                created: false,
+                file_path: document.path.to_string_lossy().to_string(),
+            });
+        }
 
+        let file_path = primary_dir.join(format!("{}.yaml", rule.id));
+        let is_update = file_path.exists();
+        if is_update && !overwrite {
+            return Err(already_exists(&rule.id));
+        }
+        fs::write(&file_path, content)?;
         Ok(CreateRuleResult {
-            rule_id: rule.id,
-            created: !is_update, // false if updating existing rule
+            rule_id: rule.id.clone(),
+            created: !is_update,
             file_path: file_path.to_string_lossy().to_string(),
         })
     }
 
-    pub async fn list_rules(&self, param: ListRulesParam) -> Result<ListRulesResult, ServiceError> {
+    /// Store `rules`, whose configurations are `rule_configs`, together in `<pack>.yaml` in the
+    /// primary directory. Rules of the pack that are not imported are kept, and imported rules
+    /// stored in other files of the primary directory are moved into the pack
+    fn write_pack(
+        &self,
+        rules: &[RuleConfig],
+        rule_configs: &[String],
+        pack: &str,
+        stored: &[(RuleDocument, RuleConfig)],
+    ) -> Result<Vec<CreateRuleResult>, ServiceError> {
+        let target = self.primary_dir()?.join(format!("{pack}.yaml"));
+        let ids: HashSet<&str> = rules.iter().map(|rule| rule.id.as_str()).collect();
+
+        let mut moved: BTreeMap<&Path, Vec<usize>> = BTreeMap::new();
+        for (document, rule) in stored {
+            if ids.contains(rule.id.as_str()) && document.path != target {
+                moved
+                    .entry(&document.path)
+                    .or_default()
+                    .push(document.index);
+            }
+        }
+        for (path, indexes) in moved {
+            let documents: Vec<String> = Self::read_documents(path)?
+                .into_iter()
+                .enumerate()
+                .filter(|(index, _)| !indexes.contains(index))
+                .map(|(_, document)| document)
+                .collect();
+            Self::rewrite(path, &documents)?;
+        }
+
+        let mut documents: Vec<String> = match target.exists() {
+            true => Self::read_documents(&target)?
+                .into_iter()
+                .filter(|document| {
+                    self.parse(document)
+                        .map_or(true, |rule| !ids.contains(rule.id.as_str()))
+                })
+                .collect(),
+            false => Vec::new(),
+        };
+        documents.extend(rule_configs.iter().cloned());
+        Self::rewrite(&target, &documents)?;
+
+        Ok(rules
+            .iter()
+            .map(|rule| CreateRuleResult {
+                rule_id: rule.id.clone(),
+                created: !stored.iter().any(|(_, stored)| stored.id == rule.id),
+                file_path: target.to_string_lossy().to_string(),
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl RuleStorage for FileRuleStorage {
+    async fn create_rule(&self, param: CreateRuleParam) -> Result<CreateRuleResult, ServiceError> {
+        // Parse and validate the rule config
+        let rule = self.parse(&param.rule_config)?;
+
+        // Ensure rules directory exists
+        fs::create_dir_all(self.primary_dir()?)?;
+
+        self.write_rule(&rule, &param.rule_config, param.overwrite)
+    }
+
+    async fn list_rules(&self, param: ListRulesParam) -> Result<ListRulesResult, ServiceError> {
         let mut rules = Vec::new();
-        let mut seen_rule_ids = std::collections::HashMap::new();
+        let mut seen_rule_ids = HashMap::new();
 
-        // Search in all rule directories
-        for directory in &self.rule_directories {
-            if !directory.exists() {
-                continue;
+        // Search in all rule directories, skipping rules that don't parse
+        for (document, rule) in self.parsed(&self.rule_directories)? {
+            // Apply filters
+            let mut include = true;
+
+            if let Some(ref lang) = param.language
+                && rule.language != *lang
+            {
+                include = false;
             }
 
-            // Read all .yaml files in the rules directory
-            for entry in fs::read_dir(directory)? {
-                let entry = entry?;
-                let path = entry.path();
-
-                if path.extension().and_then(|s| s.to_str()) == Some("yaml")
-                    || path.extension().and_then(|s| s.to_str()) == Some("yml")
-                {
-                    match self.load_rule_from_file(&path) {
-                        Ok(rule) => {
-                            // Apply filters
-                            let mut include = true;
-
-                            if let Some(ref lang) = param.language {
-                                if rule.language != *lang {
-                                    include = false;
-                                }
-                            }
-
-                            if let Some(ref severity) = param.severity {
-                                if rule.severity.as_ref() != Some(severity) {
-                                    include = false;
-                                }
-                            }
-
-                            // NOTE: We currently deduplicate rules by ID (first wins)
-                            // This differs from ast-grep CLI which loads ALL rules including duplicates
-                            if include {
-                                if let Some(first_path) = seen_rule_ids.get(&rule.id) {
-                                    // Emit warning for duplicate rule ID
-                                    eprintln!(
-                                        "Warning: Duplicate rule ID '{}' found in:\n  \
-                                     - Current: {}\n  \
-                                     - First loaded from: {}\n  \
-                                     The rule from the current file will be ignored.",
-                                        rule.id,
-                                        path.display(),
-                                        first_path
-                                    );
-                                } else {
-                                    seen_rule_ids.insert(
-                                        rule.id.clone(),
-                                        path.to_string_lossy().to_string(),
-                                    );
-                                    rules.push(RuleInfo {
-                                        id: rule.id,
-                                        message: rule.message,
-                                        language: rule.language,
-                                        severity: rule.severity,
-                                        file_path: path.to_string_lossy().to_string(),
-                                        has_fix: rule.fix.is_some(),
-                                    });
-                                }
-                            }
-                        }
-                        Err(_) => {
-                            // Skip invalid rule files
-                            continue;
-                        }
-                    }
+            if let Some(ref severity) = param.severity
+                && rule.severity.as_ref() != Some(severity)
+            {
+                include = false;
+            }
+
+            if let Some(ref tag) = param.tag
+                && !rule.tags.contains(tag)
+            {
+                include = false;
+            }
+
+            // NOTE: We currently deduplicate rules by ID (first wins)
+            // This differs from ast-grep CLI which loads ALL rules including duplicates
+            if include {
+                if let Some(first_path) = seen_rule_ids.get(&rule.id) {
+                    // Emit warning for duplicate rule ID
+                    eprintln!(
+                        "Warning: Duplicate rule ID '{}' found in:\n  \
+                     - Current: {}\n  \
+                     - First loaded from: {}\n  \
+                     The rule from the current file will be ignored.",
+                        rule.id,
+                        document.location(),
+                        first_path
+                    );
+                } else {
+                    seen_rule_ids.insert(rule.id.clone(), document.location());
+                    rules.push(RuleInfo {
+                        pack: document.pack(&rule.id),
+                        id: rule.id,
+                        message: rule.message,
+                        language: rule.language,
+                        severity: rule.severity,
+                        file_path: document.path.to_string_lossy().to_string(),
+                        has_fix: rule.fix.is_some(),
+                        tags: rule.tags,
+                    });
                 }
             }
         }
@@ -140,55 +450,121 @@ impl RuleStorage {
         Ok(ListRulesResult { rules })
     }
 
-    pub async fn get_rule(&self, param: GetRuleParam) -> Result<GetRuleResult, ServiceError> {
-        // Search for the rule in all directories
-        for directory in &self.rule_directories {
-            let file_path = directory.join(format!("{}.yaml", param.rule_id));
+    async fn load_all_rules(&self) -> Result<Vec<RuleConfig>, ServiceError> {
+        let mut seen = HashSet::new();
+        Ok(self
+            .parsed(&self.rule_directories)?
+            .into_iter()
+            .filter(|(_, rule)| seen.insert(rule.id.clone()))
+            .map(|(_, rule)| rule)
+            .collect())
+    }
 
-            if file_path.exists() {
-                let rule = self.load_rule_from_file(&file_path)?;
+    fn rule_sources(&self) -> Result<Vec<(String, String)>, ServiceError> {
+        Ok(Self::documents(&self.rule_directories)?
+            .into_iter()
+            .map(|document| (document.location(), document.content))
+            .collect())
+    }
 
-                return Ok(GetRuleResult {
-                    rule_config: rule,
-                    file_path: file_path.to_string_lossy().to_string(),
-                });
-            }
+    fn stored_rules(&self) -> Result<Vec<StoredRule>, ServiceError> {
+        let mut seen = HashSet::new();
+        Ok(self
+            .parsed(&self.rule_directories)?
+            .into_iter()
+            .filter(|(_, rule)| seen.insert(rule.id.clone()))
+            .map(|(document, rule)| StoredRule {
+                location: document.location(),
+                pack: document.pack(&rule.id),
+                id: rule.id,
+                content: document.content,
+            })
+            .collect())
+    }
+
+    async fn import_rules(
+        &self,
+        rule_configs: Vec<String>,
+        pack: Option<String>,
+        overwrite: bool,
+    ) -> Result<Vec<CreateRuleResult>, ServiceError> {
+        let rules = parse_import(&rule_configs, |content| self.parse(content))?;
+        if let Some(pack) = &pack {
+            validate_pack_name(pack)?;
+        }
+        let primary_dir = self.primary_dir()?;
+        fs::create_dir_all(primary_dir)?;
+
+        // Check every rule before writing any
+        let stored = self.parsed(std::slice::from_ref(primary_dir))?;
+        if !overwrite
+            && let Some(rule) = rules.iter().find(|rule| {
+                stored.iter().any(|(_, stored)| stored.id == rule.id)
+                    || (pack.is_none() && primary_dir.join(format!("{}.yaml", rule.id)).exists())
+            })
+        {
+            return Err(already_exists(&rule.id));
         }
 
-        // Rule not found in any directory
-        Err(ServiceError::Internal(format!(
-            "Rule '{}' not found",
-            param.rule_id
-        )))
+        match pack {
+            Some(pack) => self.write_pack(&rules, &rule_configs, &pack, &stored),
+            None => rules
+                .iter()
+                .zip(&rule_configs)
+                .map(|(rule, content)| self.write_rule(rule, content, overwrite))
+                .collect(),
+        }
     }
 
-    pub async fn delete_rule(
-        &self,
-        param: DeleteRuleParam,
-    ) -> Result<DeleteRuleResult, ServiceError> {
+    async fn get_rule(&self, param: GetRuleParam) -> Result<GetRuleResult, ServiceError> {
         // Search for the rule in all directories
-        for directory in &self.rule_directories {
-            let file_path = directory.join(format!("{}.yaml", param.rule_id));
-
-            if file_path.exists() {
-                fs::remove_file(&file_path)?;
-                return Ok(DeleteRuleResult {
-                    rule_id: param.rule_id,
-                    deleted: true,
-                    message: "Rule deleted successfully".to_string(),
-                });
-            }
+        match self.find(&self.rule_directories, &param.rule_id)? {
+            Some(document) => Ok(GetRuleResult {
+                rule_config: self.parse(&document.content)?,
+                file_path: document.path.to_string_lossy().to_string(),
+            }),
+            // Rule not found in any directory
+            None => Err(ServiceError::Internal(format!(
+                "Rule '{}' not found",
+                param.rule_id
+            ))),
         }
+    }
+
+    async fn delete_rule(&self, param: DeleteRuleParam) -> Result<DeleteRuleResult, ServiceError> {
+        let deleted = match self.find(&self.rule_directories, &param.rule_id)? {
+            Some(document) if document.count == 1 => {
+                fs::remove_file(&document.path)?;
+                true
+            }
+            Some(document) => {
+                let mut documents = Self::read_documents(&document.path)?;
+                documents.remove(document.index);
+                Self::rewrite(&document.path, &documents)?;
+                true
+            }
+            // A rule file that no longer parses can still be deleted by its name
+            None => match self
+                .rule_directories
+                .iter()
+                .map(|directory| directory.join(format!("{}.yaml", param.rule_id)))
+                .find(|file_path| file_path.exists())
+            {
+                Some(file_path) => {
+                    fs::remove_file(&file_path)?;
+                    true
+                }
+                None => false,
+            },
+        };
 
         Ok(DeleteRuleResult {
             rule_id: param.rule_id,
-            deleted: false,
-            message: "Rule not found".to_string(),
+            deleted,
+            message: match deleted {
+                true => "Rule deleted successfully".to_string(),
+                false => "Rule not found".to_string(),
+            },
         })
     }
-
-    fn load_rule_from_file(&self, path: &PathBuf) -> Result<RuleConfig, ServiceError> {
-        let content = fs::read_to_string(path)?;
-        parse_rule_config(&content)
-    }
 }
@@ -0,0 +1,88 @@
+//! `ast-grep-ignore` suppression comments
+//!
+//! A comment containing `ast-grep-ignore` suppresses matches that start on the same line or the
+//! line directly below it. `ast-grep-ignore: rule-a, rule-b` limits the suppression to the
+//! listed rule ids; without a list every rule is suppressed.
+
+use crate::types::MatchResult;
+
+const IGNORE_MARKER: &str = "ast-grep-ignore";
+
+/// Remove matches of `rule_id` suppressed by an ignore comment in `code`, returning the kept
+/// matches and the number suppressed
+pub fn filter_suppressed(
+    code: &str,
+    rule_id: &str,
+    matches: Vec<MatchResult>,
+) -> (Vec<MatchResult>, usize) {
+    if !code.contains(IGNORE_MARKER) {
+        return (matches, 0);
+    }
+
+    let lines: Vec<&str> = code.lines().collect();
+    let suppresses = |line: Option<&&str>| line.is_some_and(|line| ignores_rule(line, rule_id));
+
+    let before = matches.len();
+    let kept: Vec<MatchResult> = matches
+        .into_iter()
+        .filter(|m| {
+            let previous = m.start_line.checked_sub(1).and_then(|i| lines.get(i));
+            !suppresses(lines.get(m.start_line)) && !suppresses(previous)
+        })
+        .collect();
+    let suppressed = before - kept.len();
+    (kept, suppressed)
+}
+
+fn ignores_rule(line: &str, rule_id: &str) -> bool {
+    let Some(index) = line.find(IGNORE_MARKER) else {
+        return false;
+    };
+    let rest = &line[index + IGNORE_MARKER.len()..];
+    match rest.trim_start().strip_prefix(':') {
+        Some(ids) => ids
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .any(|id| id == rule_id),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn match_at(line: usize) -> MatchResult {
+        MatchResult {
+            text: String::new(),
+            start_line: line,
+            end_line: line,
+            start_col: 0,
+            end_col: 0,
+            vars: HashMap::new(),
+            context_before: None,
+            context_after: None,
+            enclosing_symbol: None,
+            ast_context: None,
+            quick_fixes: Vec::new(),
+            enclosing_node: None,
+            leading_trivia: Vec::new(),
+            rule_id: None,
+        }
+    }
+
+    #[test]
+    fn test_ignore_comment_suppresses_next_line() {
+        let code = "a();\n// ast-grep-ignore\nb();\nc(); // ast-grep-ignore: no-c\n";
+        let matches = vec![match_at(0), match_at(2), match_at(3)];
+
+        let (kept, suppressed) = filter_suppressed(code, "no-c", matches.clone());
+        assert_eq!(suppressed, 2);
+        assert_eq!(kept[0].start_line, 0);
+
+        // The trailing comment only names `no-c`
+        let (kept, suppressed) = filter_suppressed(code, "other", matches);
+        assert_eq!(suppressed, 1);
+        assert_eq!(kept.len(), 2);
+    }
+}
@@ -1,5 +1,7 @@
-use crate::types::CursorParam;
+use super::stats::RuleStats;
+use crate::types::{ChangeResult, CursorParam, MatchResult, WalkOptions};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 // Rule configuration types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,6 +12,76 @@ pub struct RuleConfig {
     pub severity: Option<String>,
     pub rule: RuleObject,
     pub fix: Option<String>,
+    /// How safe `fix` is to apply without review; rules without it are treated as `safe`
+    pub fix_safety: Option<FixSafety>,
+    /// Labels for grouping stored rules, which `list_rules` can filter by
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Utility rules local to this rule, which `matches: <name>` refers to by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub utils: BTreeMap<String, RuleObject>,
+    /// Rules the nodes captured by metavariables must match, keyed by metavariable name without
+    /// the `$`, e.g. `VAR: { regex: ^use }`. A match is dropped when one of its captures fails
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub constraints: BTreeMap<String, RuleObject>,
+    /// Named rewrites that `rewrite` transforms apply inside captured nodes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rewriters: Vec<RewriterConfig>,
+    /// Metavariables computed from the captured ones, keyed by name without the `$`, which
+    /// `fix` can use like captured ones
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub transform: BTreeMap<String, Transformation>,
+}
+
+/// A rewrite that a `rewrite` transform applies to the nodes matching `rule`, replacing each
+/// with `fix`. It has its own metavariables, utils, constraints and transforms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RewriterConfig {
+    pub id: String,
+    pub rule: RuleObject,
+    pub fix: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub utils: BTreeMap<String, RuleObject>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub constraints: BTreeMap<String, RuleObject>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub transform: BTreeMap<String, Transformation>,
+}
+
+/// How a `transform` metavariable is computed. ast-grep's other transformations (`replace`,
+/// `substring`, `convert`) are not supported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transformation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rewrite: Option<RewriteTransform>,
+}
+
+/// The text of a captured node, or of each node of a `$$$` capture, with the first of
+/// `rewriters` matching each node inside it applied
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RewriteTransform {
+    /// The capture to rewrite, e.g. `$$$ITEMS`
+    pub source: String,
+    /// Ids of the rule's rewriters, tried in order on each node
+    pub rewriters: Vec<String>,
+    /// Separator joining the rewritten nodes of a `$$$` capture; without it the text between
+    /// them is kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub join_by: Option<String>,
+}
+
+/// Applicability of a rule's fix, modelled on clippy's applicability levels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FixSafety {
+    /// The fix preserves behavior and can be applied automatically
+    #[default]
+    Safe,
+    /// The fix is probably right but its result should be reviewed
+    Suggestion,
+    /// The fix may change behavior; rule_replace only applies it with `allow_unsafe`
+    Unsafe,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +97,25 @@ pub struct RuleObject {
     pub any: Option<Vec<RuleObject>>,
     pub not: Option<Box<RuleObject>>,
     pub matches: Option<String>,
+    /// On the target of `inside`, `has`, `follows` or `precedes`: how far to search for it
+    #[serde(default, rename = "stopBy", skip_serializing_if = "Option::is_none")]
+    pub stop_by: Option<StopBySpec>,
+    /// On the target of `inside` or `has`: the field the matched node must be in, e.g. `body`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+/// ast-grep's `stopBy` option of a relational rule; unset means `neighbor`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StopBySpec {
+    /// Only the parent, the direct children or the adjacent sibling
+    Neighbor,
+    /// Every ancestor, descendant or sibling in that direction
+    End,
+    /// Search up to and including the first node matching this rule
+    #[serde(untagged)]
+    Rule(Box<RuleObject>),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,13 +132,50 @@ pub enum PatternSpec {
 // Rule operation parameters and results
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RuleSearchParam {
+    /// Rule configuration (YAML or JSON); leave empty to run the rules of `pack` instead
+    #[serde(default)]
     pub rule_config: String,
+    /// Run every stored rule of this rule pack instead of `rule_config`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack: Option<String>,
     pub path_pattern: Option<String>,
     #[serde(default = "default_max_results")]
     pub max_results: usize,
     #[serde(default = "default_max_file_size")]
     pub max_file_size: u64,
     pub cursor: Option<CursorParam>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_file: Option<String>,
+    /// Limit the call to the root directory with this workspace label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    /// Stop evaluating the rule after this many milliseconds; can only lower the server's limit.
+    /// Files not reached by then are left for `next_cursor` and the result is marked `timed_out`.
+    /// The first file is searched in full however small this is, so each page makes progress
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Stop once the rule has matched this many times; can only lower the server's limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_matches: Option<usize>,
+    /// How the findings are returned (default: text)
+    #[serde(default)]
+    pub output_format: RuleOutputFormat,
+    /// Walk depth, pruned directories and hidden entries; unset options use the server's defaults
+    #[serde(flatten)]
+    pub walk: WalkOptions,
+}
+
+/// Output of `rule_search`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleOutputFormat {
+    /// A readable summary followed by the result JSON
+    #[default]
+    Text,
+    /// Only the result JSON
+    Json,
+    /// A SARIF 2.1.0 log, as read by GitHub code scanning and other CI dashboards
+    Sarif,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,6 +191,23 @@ pub struct RuleReplaceParam {
     #[serde(default = "default_false")]
     pub summary_only: bool,
     pub cursor: Option<CursorParam>,
+    /// Apply fixes from rules declaring `fix_safety: unsafe`
+    #[serde(default = "default_false")]
+    pub allow_unsafe: bool,
+    /// Limit the call to the root directory with this workspace label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    /// Treat files whose fix only changes trailing whitespace, trailing blank lines or line
+    /// endings as unchanged, so they are neither reported nor written
+    #[serde(default = "default_true")]
+    pub ignore_whitespace_changes: bool,
+    /// Stop after this many milliseconds, or when the client cancels the request, and return
+    /// the fixes made so far with `timed_out` set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Walk depth, pruned directories and hidden entries; unset options use the server's defaults
+    #[serde(flatten)]
+    pub walk: WalkOptions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,6 +227,62 @@ pub struct RuleValidateResult {
 pub struct RuleTestResult {
     pub matches_found: usize,
     pub sample_matches: Vec<String>,
+    /// Matches of the rule that its `constraints` dropped
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constraint_violations: Vec<ConstraintViolation>,
+    /// The test code with the rule's `fix` applied to every match, when the rule has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fixed_code: Option<String>,
+    /// Whole-line hunks turning the test code into `fixed_code`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fix_hunks: Vec<ChangeResult>,
+}
+
+/// A match dropped because a metavariable's capture doesn't satisfy its constraint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConstraintViolation {
+    /// Text of the dropped match
+    pub text: String,
+    pub start_line: usize,
+    /// Name of the constrained metavariable, without the `$`
+    pub metavariable: String,
+    /// What the metavariable captured
+    pub captured: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidateRulesParam {
+    /// YAML or JSON rule configurations to check
+    #[serde(default)]
+    pub rule_configs: Vec<String>,
+    /// Also check every rule file in the rule directories
+    #[serde(default = "default_false")]
+    pub all_stored: bool,
+    /// Sample code to run rules against, keyed by rule id
+    #[serde(default)]
+    pub test_code: std::collections::HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidateRulesResult {
+    pub rules: Vec<RuleValidation>,
+    pub valid_count: usize,
+    pub invalid_count: usize,
+    pub warning_count: usize,
+}
+
+/// Health of one rule checked by `validate_rules`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RuleValidation {
+    /// Rule file path, or `rule_configs[N]` for inline configurations
+    pub source: String,
+    /// Rule id, absent when the configuration does not parse
+    pub id: Option<String>,
+    pub valid: bool,
+    pub errors: Vec<String>,
+    /// Problems that don't stop the rule from running but likely make it miss matches
+    pub warnings: Vec<String>,
+    pub test_results: Option<RuleTestResult>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -102,6 +303,8 @@ pub struct CreateRuleResult {
 pub struct ListRulesParam {
     pub language: Option<String>,
     pub severity: Option<String>,
+    /// Only list rules with this tag
+    pub tag: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -117,6 +320,11 @@ pub struct RuleInfo {
     pub severity: Option<String>,
     pub file_path: String,
     pub has_fix: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// The rule pack the rule was stored in, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -142,7 +350,485 @@ pub struct DeleteRuleResult {
     pub message: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleStatsParam {
+    /// Only report this rule
+    pub rule_id: Option<String>,
+    /// Scans without a single match before a rule is reported as never firing
+    #[serde(default = "default_min_scans")]
+    pub min_scans: u64,
+    /// Average matches per scan above which a rule is reported as noisy
+    #[serde(default = "default_noise_threshold")]
+    pub noise_threshold: f64,
+}
+
+impl Default for RuleStatsParam {
+    fn default() -> Self {
+        Self {
+            rule_id: None,
+            min_scans: default_min_scans(),
+            noise_threshold: default_noise_threshold(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RuleStatsResult {
+    /// Recorded statistics, sorted by rule id
+    pub rules: Vec<RuleStats>,
+    /// Rules scanned at least `min_scans` times without ever matching
+    pub never_fired: Vec<String>,
+    /// Rules averaging more than `noise_threshold` matches per scan
+    pub noisy: Vec<String>,
+    /// Stored rules that have no recorded scans
+    pub never_scanned: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushRuleParam {
+    /// Id of the stored rule to publish
+    pub rule_id: String,
+    /// Replace the registry's copy even if someone changed it since it was last pulled
+    #[serde(default = "default_false")]
+    pub force: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PushRuleResult {
+    pub rule_id: String,
+    /// Whether the registry accepted the rule
+    pub pushed: bool,
+    /// Registry version of the rule after the push
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+    /// The registry's copy, when it changed since this server last pulled it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflict: Option<RemoteRule>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PullRulesParam {
+    /// What to do with rules changed both locally and in the registry since the last sync
+    #[serde(default)]
+    pub on_conflict: ConflictResolution,
+}
+
+/// How `pull_rules` settles a rule changed both locally and in the registry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    /// Leave the local rule alone and report the conflict
+    #[default]
+    Report,
+    /// Keep the local rule; the next `push_rule` replaces the registry's copy
+    KeepLocal,
+    /// Replace the local rule with the registry's copy
+    TakeRemote,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PullRulesResult {
+    /// Rules that were not stored locally
+    pub added: Vec<String>,
+    /// Rules replaced by a newer registry version
+    pub updated: Vec<String>,
+    /// Rules already the same as in the registry
+    pub unchanged: usize,
+    /// Rules changed on both sides, with how each was settled
+    pub conflicts: Vec<RuleConflict>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RuleConflict {
+    pub rule_id: String,
+    pub resolution: ConflictResolution,
+    pub remote: RemoteRule,
+}
+
+/// A rule as the registry stores it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteRule {
+    pub id: String,
+    pub rule_config: String,
+    /// Incremented by the registry on every change
+    pub version: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConvertRuleParam {
+    /// Semgrep rule YAML or ESLint config (YAML or JSON)
+    pub source: String,
+    /// "semgrep" or "eslint"; detected from the shape of `rules` when omitted
+    pub format: Option<String>,
+    /// Target language for ESLint rules (default: javascript)
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConvertRuleResult {
+    /// Format the source was read as
+    pub format: String,
+    /// Rules that were converted
+    pub rules: Vec<ConvertedRule>,
+    /// Rules that could not be converted, with the reason
+    pub unconverted: Vec<UnconvertedRule>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConvertedRule {
+    pub id: String,
+    /// ast-grep rule configuration (YAML), ready for create_rule or rule_search
+    pub rule_config: String,
+    /// Approximations made while converting
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnconvertedRule {
+    pub id: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportToSgconfigParam {
+    /// Workspace directory to write sgconfig.yml and rules/ into (default: first root directory)
+    #[serde(default = "default_export_dir")]
+    pub output_dir: String,
+    /// Only export these rules (default: all stored rules)
+    pub rule_ids: Option<Vec<String>>,
+    /// Replace rule files that differ and add rules/ to an existing sgconfig.yml
+    #[serde(default = "default_false")]
+    pub overwrite: bool,
+}
+
+impl Default for ExportToSgconfigParam {
+    fn default() -> Self {
+        Self {
+            output_dir: default_export_dir(),
+            rule_ids: None,
+            overwrite: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportToSgconfigResult {
+    /// Path of the sgconfig.yml listing the rules directory
+    pub sgconfig_path: String,
+    /// Directory the rule files were written to
+    pub rules_dir: String,
+    /// Rules written, with their file paths
+    pub exported: Vec<RuleInfo>,
+    /// Rules left out, with the reason
+    pub skipped: Vec<SkippedRule>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SkippedRule {
+    pub rule_id: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportRulesParam {
+    /// Where to write the rules, inside a root directory: a `.yaml` or `.yml` path gets one
+    /// multi-document file, any other path a directory with one file per rule
+    pub output_path: String,
+    /// Only export these rules (default: all stored rules)
+    pub rule_ids: Option<Vec<String>>,
+    /// Only export the rules of this pack
+    pub pack: Option<String>,
+    /// Replace an existing output file, or rule files in the output directory that differ
+    #[serde(default = "default_false")]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportRulesResult {
+    /// The file or directory the rules were written to
+    pub output_path: String,
+    /// Ids of the rules written, in the order they were written
+    pub exported: Vec<String>,
+    /// Rules left out, with the reason
+    pub skipped: Vec<SkippedRule>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportRulesParam {
+    /// Rule file, which may hold several `---` separated rules, or directory of rule files,
+    /// inside a root directory
+    pub path: String,
+    /// Store the rules together as this rule pack (default: each rule on its own)
+    pub pack: Option<String>,
+    /// Replace stored rules with the same ids
+    #[serde(default = "default_false")]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportRulesResult {
+    /// Every imported rule, in the order it was read, with where it was stored
+    pub imported: Vec<CreateRuleResult>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplainRuleParam {
+    /// Rule configuration (YAML or JSON), as passed to rule_search
+    pub rule_config: String,
+    /// File to evaluate the rule against, absolute or relative to a root directory
+    pub file_path: String,
+    /// Nodes listed per step before the rest are only counted
+    #[serde(default = "default_max_trace_nodes")]
+    pub max_nodes_per_step: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExplainRuleResult {
+    pub rule_id: String,
+    pub file_path: String,
+    /// Evaluation steps in the order they ran; sub-rules come before the rule combining them
+    pub steps: Vec<EvaluationStep>,
+    /// The matches rule_search reports for this file
+    pub matches: Vec<MatchResult>,
+    /// Matches dropped by `ast-grep-ignore` comments
+    pub suppressed: usize,
+    /// How the final match set came about, in plain language
+    pub explanation: Vec<String>,
+}
+
+/// One sub-rule evaluation, or one filter applied by an `all` rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvaluationStep {
+    /// Location of the sub-rule, e.g. `rule.all[1].has`
+    pub path: String,
+    /// pattern, kind, regex, matches, all, any, not, inside, has, follows or precedes
+    pub operator: String,
+    /// Pattern, kind or regex of a leaf rule
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub argument: Option<String>,
+    /// Number of candidates coming in, for steps that narrow an earlier result
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub candidates: Option<usize>,
+    /// Number of nodes the step produced or kept
+    pub matched: usize,
+    pub matched_nodes: Vec<TracedNode>,
+    /// Number of candidates the step removed
+    pub pruned: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pruned_nodes: Vec<TracedNode>,
+    pub note: String,
+}
+
+/// A node in an evaluation trace, shortened to its first line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracedNode {
+    pub text: String,
+    pub start_line: usize,
+    pub start_col: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanParam {
+    /// Glob pattern for files to scan; every file under the roots when absent
+    pub path_pattern: Option<String>,
+    /// Stored rules to run; every stored rule when empty
+    #[serde(default)]
+    pub rule_ids: Vec<String>,
+    /// Only list the findings of this bucket (error, warning or info); the other buckets
+    /// still report their counts
+    pub severity: Option<String>,
+    /// Findings listed per bucket
+    #[serde(default = "default_scan_page_size")]
+    pub page_size: usize,
+    /// Findings of the `severity` bucket to skip, as given by its `next_offset`
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+    /// Limit the call to the root directory with this workspace label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    /// Workspace path to write the full result to; only a summary is returned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_file: Option<String>,
+    /// Walk depth, pruned directories and hidden entries; unset options use the server's defaults
+    #[serde(flatten)]
+    pub walk: WalkOptions,
+}
+
+impl Default for ScanParam {
+    fn default() -> Self {
+        Self {
+            path_pattern: None,
+            rule_ids: Vec::new(),
+            severity: None,
+            page_size: default_scan_page_size(),
+            offset: 0,
+            max_file_size: default_max_file_size(),
+            workspace: None,
+            output_file: None,
+            walk: WalkOptions::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScanResult {
+    pub total_findings: usize,
+    pub files_scanned: usize,
+    pub rules_run: usize,
+    /// Rules that ran out of time or reached their match limit part way through the scan, so
+    /// their findings are partial
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub truncated_rules: Vec<String>,
+    /// The error, warning and info buckets, in that order, including empty ones
+    pub buckets: Vec<SeverityBucket>,
+}
+
+/// Summary of a [`ScanResult`] written to a file with `output_file`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedScanResult {
+    /// Absolute path of the written JSON file
+    pub output_file: String,
+    /// Size of the written file in bytes
+    pub bytes_written: u64,
+    pub total_findings: usize,
+    pub files_scanned: usize,
+    pub rules_run: usize,
+    /// Rules whose findings are partial, see [`ScanResult::truncated_rules`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub truncated_rules: Vec<String>,
+    /// Findings per severity bucket
+    pub severity_counts: BTreeMap<String, usize>,
+}
+
+/// Findings of rules sharing a severity; rules without one count as `info`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeverityBucket {
+    pub severity: String,
+    /// Findings in the bucket, including ones not listed on this page
+    pub count: usize,
+    /// Findings per rule id
+    pub rule_counts: BTreeMap<String, usize>,
+    /// One page of findings, ordered by file and position
+    pub findings: Vec<ScanFinding>,
+    /// Offset of the next page of this bucket, when there is one
+    pub next_offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanFinding {
+    pub rule_id: String,
+    /// The rule's message, or its id when it has none
+    pub message: String,
+    pub file_path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub start_col: usize,
+    pub end_col: usize,
+    pub text: String,
+}
+
+/// Least severe findings that fail `lint_workspace`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintThreshold {
+    /// Only error findings fail the lint
+    #[default]
+    Error,
+    /// Warning and error findings fail the lint
+    Warning,
+}
+
+impl LintThreshold {
+    /// Whether findings of `severity` fail the lint
+    pub fn fails(self, severity: &str) -> bool {
+        match self {
+            LintThreshold::Error => severity == "error",
+            LintThreshold::Warning => matches!(severity, "error" | "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintWorkspaceParam {
+    /// Glob pattern for files to lint; every file under the roots when absent
+    pub path_pattern: Option<String>,
+    #[serde(default)]
+    pub fail_on: LintThreshold,
+    /// Findings listed per page
+    #[serde(default = "default_scan_page_size")]
+    pub page_size: usize,
+    /// Findings to skip, as given by `next_offset`
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+    /// Limit the call to the root directory with this workspace label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    /// Walk depth, pruned directories and hidden entries; unset options use the server's defaults
+    #[serde(flatten)]
+    pub walk: WalkOptions,
+}
+
+impl Default for LintWorkspaceParam {
+    fn default() -> Self {
+        Self {
+            path_pattern: None,
+            fail_on: LintThreshold::default(),
+            page_size: default_scan_page_size(),
+            offset: 0,
+            max_file_size: default_max_file_size(),
+            workspace: None,
+            walk: WalkOptions::default(),
+        }
+    }
+}
+
+/// Every stored rule run over the workspace, aggregated by severity
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LintWorkspaceResult {
+    /// Whether no finding is at least as severe as `fail_on`
+    pub passed: bool,
+    pub fail_on: LintThreshold,
+    /// Findings at least as severe as `fail_on`
+    pub failing_findings: usize,
+    pub total_findings: usize,
+    pub error_count: usize,
+    pub warning_count: usize,
+    pub info_count: usize,
+    pub files_scanned: usize,
+    pub rules_run: usize,
+    /// Rules with findings, most severe first and then by count
+    pub rule_counts: Vec<LintRuleCount>,
+    /// One page of findings, most severe first and then by file and position
+    pub findings: Vec<LintFinding>,
+    /// Offset of the next page, when there is one
+    pub next_offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintRuleCount {
+    pub rule_id: String,
+    pub severity: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintFinding {
+    pub severity: String,
+    #[serde(flatten)]
+    pub finding: ScanFinding,
+}
+
 // Default functions for serde
+fn default_export_dir() -> String {
+    ".".to_string()
+}
 fn default_max_results() -> usize {
     10000
 }
@@ -171,3 +857,15 @@ pub struct RelationalRule {
     pub not: Option<Box<RelationalRule>>,
     pub matches: Option<String>,
 }
+fn default_min_scans() -> u64 {
+    5
+}
+fn default_noise_threshold() -> f64 {
+    50.0
+}
+fn default_max_trace_nodes() -> usize {
+    10
+}
+fn default_scan_page_size() -> usize {
+    20
+}
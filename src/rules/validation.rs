@@ -0,0 +1,242 @@
+//! # Batch Rule Validation
+//!
+//! `validate_rules` health-checks a rule library in one call: each rule is parsed and
+//! validated the way `validate_rule` does, its patterns are compiled for the rule's language,
+//! and a few lint checks flag rules that load fine but are likely to miss matches. Rules with
+//! sample code in `test_code` are also run against it.
+//!
+//! With `all_stored`, every stored rule is checked, including rule files that `list_rules`
+//! skips because they don't parse.
+
+use super::evaluation::RuleEvaluator;
+use super::parser::{parse_rule_config, validate_rule_config};
+use super::storage::RuleStorage;
+use super::types::{
+    PatternSpec, RuleConfig, RuleObject, RuleTestResult, RuleValidation, ValidateRulesParam,
+    ValidateRulesResult,
+};
+use crate::env_interpolation::interpolate;
+use crate::errors::ServiceError;
+use ast_grep_core::Pattern;
+use ast_grep_language::SupportLang as Language;
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+/// Sample matches kept per test run, as in `validate_rule`
+const SAMPLE_MATCHES: usize = 5;
+
+static LOWERCASE_METAVAR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$\$?\$?[a-z][A-Za-z0-9_]*").unwrap());
+static METAVAR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$(?:\$\$)?([A-Z_][A-Z0-9_]*)").unwrap());
+
+/// Check the inline configurations in `param` and, with `all_stored`, the stored rule files
+pub fn validate_rules(
+    storage: &dyn RuleStorage,
+    evaluator: &RuleEvaluator,
+    env_vars: &BTreeMap<String, String>,
+    param: ValidateRulesParam,
+) -> Result<ValidateRulesResult, ServiceError> {
+    let mut sources: Vec<(String, String)> = param
+        .rule_configs
+        .into_iter()
+        .enumerate()
+        .map(|(index, config)| (format!("rule_configs[{index}]"), config))
+        .collect();
+    if param.all_stored {
+        sources.extend(storage.rule_sources()?);
+    }
+    if sources.is_empty() {
+        return Err(ServiceError::Internal(
+            "validate_rules needs rule_configs or all_stored: true".to_string(),
+        ));
+    }
+
+    let mut first_source: HashMap<String, String> = HashMap::new();
+    let mut rules = Vec::with_capacity(sources.len());
+    for (source, content) in sources {
+        // Checked as the rule is run, with its allowlisted placeholders filled in
+        let content = interpolate(&content, env_vars);
+        let mut validation = validate_one(evaluator, &source, &content, &param.test_code)?;
+        if let Some(id) = &validation.id {
+            match first_source.get(id) {
+                Some(first) => validation
+                    .warnings
+                    .push(format!("Rule id '{id}' is already used by {first}")),
+                None => {
+                    first_source.insert(id.clone(), source);
+                }
+            }
+        }
+        rules.push(validation);
+    }
+
+    let valid_count = rules.iter().filter(|rule| rule.valid).count();
+    Ok(ValidateRulesResult {
+        invalid_count: rules.len() - valid_count,
+        valid_count,
+        warning_count: rules.iter().map(|rule| rule.warnings.len()).sum(),
+        rules,
+    })
+}
+
+fn validate_one(
+    evaluator: &RuleEvaluator,
+    source: &str,
+    content: &str,
+    test_code: &HashMap<String, String>,
+) -> Result<RuleValidation, ServiceError> {
+    let mut errors = validate_rule_config(content)?;
+    let mut warnings = Vec::new();
+    let config = parse_rule_config(content).ok();
+    let lang = config
+        .as_ref()
+        .and_then(|config| Language::from_str(&config.language).ok());
+
+    if let (Some(config), Some(lang)) = (&config, lang) {
+        lint_rule(config, lang, &mut errors, &mut warnings);
+    }
+
+    let test_results = match (&config, lang) {
+        (Some(config), Some(lang)) => match test_code.get(&config.id) {
+            Some(_) if !errors.is_empty() => {
+                warnings.push("Test code provided but rule has errors".to_string());
+                None
+            }
+            Some(code) => match evaluator.evaluate_with_violations(config, code, lang) {
+                Ok((matches, constraint_violations)) => Some(RuleTestResult {
+                    matches_found: matches.len(),
+                    sample_matches: matches
+                        .into_iter()
+                        .take(SAMPLE_MATCHES)
+                        .map(|m| m.text)
+                        .collect(),
+                    constraint_violations,
+                    fixed_code: None,
+                    fix_hunks: vec![],
+                }),
+                Err(e) => {
+                    warnings.push(format!("Test run failed: {e}"));
+                    None
+                }
+            },
+            None => None,
+        },
+        _ => None,
+    };
+
+    Ok(RuleValidation {
+        source: source.to_string(),
+        id: config.map(|config| config.id),
+        valid: errors.is_empty(),
+        errors,
+        warnings,
+        test_results,
+    })
+}
+
+/// Compile the rule's patterns and flag the mistakes that make a rule quietly match nothing
+fn lint_rule(
+    config: &RuleConfig,
+    lang: Language,
+    errors: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) {
+    let mut patterns = Vec::new();
+    collect_patterns(&config.rule, &mut patterns);
+
+    let mut captured = HashSet::new();
+    for spec in patterns {
+        let (source, compiled) = match spec {
+            PatternSpec::Simple(pattern) => (pattern, Pattern::try_new(pattern, lang)),
+            PatternSpec::Advanced {
+                context,
+                selector: Some(selector),
+                ..
+            } => (context, Pattern::contextual(context, selector, lang)),
+            PatternSpec::Advanced { context, .. } => (context, Pattern::try_new(context, lang)),
+        };
+        for metavar in LOWERCASE_METAVAR.find_iter(source) {
+            warnings.push(format!(
+                "`{}` in pattern `{source}` is matched literally; metavariable names are uppercase",
+                metavar.as_str()
+            ));
+        }
+        match compiled {
+            Ok(pattern) => {
+                if pattern.has_error() {
+                    warnings.push(format!(
+                        "Pattern `{source}` does not parse cleanly as {}; it may match nothing",
+                        config.language
+                    ));
+                }
+                captured.extend(pattern.defined_vars().into_iter().map(str::to_string));
+            }
+            Err(e) => errors.push(format!("Invalid pattern `{source}`: {e}")),
+        }
+    }
+
+    for name in config.constraints.keys() {
+        if !captured.contains(name) {
+            warnings.push(format!(
+                "constraints restrict ${name}, which no pattern in the rule captures"
+            ));
+        }
+    }
+
+    if let Some(fix) = &config.fix {
+        let mut reported = HashSet::new();
+        for name in METAVAR.captures_iter(fix).map(|c| c[1].to_string()) {
+            if !captured.contains(&name) && reported.insert(name.clone()) {
+                warnings.push(format!(
+                    "fix uses ${name}, which no pattern in the rule captures"
+                ));
+            }
+        }
+    }
+}
+
+fn collect_patterns<'a>(rule: &'a RuleObject, patterns: &mut Vec<&'a PatternSpec>) {
+    patterns.extend(&rule.pattern);
+    for nested in [
+        &rule.inside,
+        &rule.has,
+        &rule.follows,
+        &rule.precedes,
+        &rule.not,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        collect_patterns(nested, patterns);
+    }
+    for nested in [&rule.all, &rule.any].into_iter().flatten().flatten() {
+        collect_patterns(nested, patterns);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(yaml: &str) -> (Vec<String>, Vec<String>) {
+        let config = parse_rule_config(yaml).unwrap();
+        let lang = Language::from_str(&config.language).unwrap();
+        let (mut errors, mut warnings) = (Vec::new(), Vec::new());
+        lint_rule(&config, lang, &mut errors, &mut warnings);
+        (errors, warnings)
+    }
+
+    #[test]
+    fn test_lint_flags_literal_metavariables_and_uncaptured_fix_vars() {
+        let (errors, warnings) = lint(
+            "id: r\nlanguage: javascript\nrule:\n  all:\n    - pattern: console.log($msg)\n    - not:\n        pattern: debug($$$ARGS)\nfix: logger.info($MSG, $$$ARGS)\n",
+        );
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(warnings.len(), 2, "{warnings:?}");
+        assert!(warnings[0].contains("`$msg`"));
+        assert!(warnings[1].contains("fix uses $MSG"));
+    }
+}
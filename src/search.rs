@@ -1,20 +1,68 @@
+use crate::archive;
+use crate::cancellation::StopSignal;
 use crate::config::ServiceConfig;
+use crate::context_lines::{attach_leading_trivia, extract_context_lines, extract_context_scope};
+use crate::dialect::{
+    LanguagePattern, detect_language, is_auto_language, language_for_file, resolve_language,
+    resolve_language_patterns,
+};
 use crate::errors::ServiceError;
-use crate::path_validation::validate_path_pattern;
-use crate::pattern::PatternMatcher;
-use crate::rules::{RuleEvaluator, RuleSearchParam, parse_rule_config};
+use crate::extra_languages::AnyLanguage;
+use crate::file_fetch::content_hash;
+use crate::git::{blame_file, list_files_at, read_file_at, resolve_revision};
+use crate::index::FileIndex;
+use crate::overlay::Overlays;
+use crate::path_validation::{resolve_path_pattern, validate_path_pattern};
+use crate::pattern::{PatternMatcher, check_broad_pattern};
+use crate::regex_fallback;
+use crate::rules::quick_fix::{FixRule, annotate_matches};
+use crate::rules::scan::{bucket_findings, severity_of, validate_severity};
+use crate::rules::suppression::filter_suppressed;
+use crate::rules::{
+    ExplainRuleParam, ExplainRuleResult, RuleConfig, RuleEvaluator, RuleSearchParam, RuleTracer,
+    ScanFinding, ScanParam, ScanResult, explain_steps,
+};
 use crate::types::*;
+use crate::value_search::{
+    DEFAULT_COMMENT_TAG_REGEX, ValueMatcher, ValueNodeKind, find_value_nodes,
+};
+use crate::walk::{dedupe_canonical, walk_files};
+use crate::worker::{IsolatedOutcome, MatchJob, WorkerPool};
 
+use ast_grep_core::AstGrep;
+use ast_grep_core::Language as _;
+use ast_grep_core::tree_sitter::StrDoc;
 use ast_grep_language::SupportLang as Language;
-use globset::{Glob, GlobSetBuilder};
+use futures::StreamExt;
+use globset::{Glob, GlobMatcher, GlobSetBuilder};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 use std::str::FromStr;
-use walkdir::WalkDir;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
 
 #[derive(Clone)]
 pub struct SearchService {
     config: ServiceConfig,
     pattern_matcher: PatternMatcher,
     rule_evaluator: RuleEvaluator,
+    workers: Option<WorkerPool>,
+    /// File lists and syntax trees kept between searches, when `config.file_index` is set
+    index: Option<FileIndex>,
+    /// Cancelled when the client cancels the request this service is handling
+    cancellation: CancellationToken,
+}
+
+/// Files matching a glob before pagination, and how many of them are over the size limit
+#[derive(Debug, Default, Clone, Copy)]
+struct GlobCounts {
+    matching: usize,
+    too_large: usize,
+    /// The walk was stopped early, so no files were returned
+    stopped: bool,
 }
 
 impl SearchService {
@@ -23,43 +71,79 @@ impl SearchService {
         pattern_matcher: PatternMatcher,
         rule_evaluator: RuleEvaluator,
     ) -> Self {
+        let workers = config.worker_isolation.clone().map(WorkerPool::new);
+        let index = config.file_index.then(|| FileIndex::new(&config));
         Self {
             config,
             pattern_matcher,
             rule_evaluator,
+            workers,
+            index,
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    /// This service for one request, whose file loops stop once `token` is cancelled
+    pub fn with_cancellation(&self, token: CancellationToken) -> Self {
+        Self {
+            cancellation: token,
+            ..self.clone()
         }
     }
 
+    /// When an operation of this service's request given `timeout_ms` should stop
+    pub(crate) fn stop_signal(&self, timeout_ms: Option<u64>) -> StopSignal {
+        StopSignal::new(self.cancellation.clone(), timeout_ms)
+    }
+
+    /// The file index, when the server keeps one
+    pub fn index(&self) -> Option<&FileIndex> {
+        self.index.as_ref()
+    }
+
     /// Discovers and filters files based on a path pattern, size limits, and pagination cursor.
-    /// Returns a tuple of (filtered_file_paths, next_cursor, total_files_found).
+    /// Only the root labelled `workspace` is searched when one is given, and directories are
+    /// walked as `walk` allows. A walk cut short by `stop` returns no files, `glob_counts.stopped`
+    /// and a cursor that starts over from `cursor`.
+    /// Returns a tuple of (filtered_file_paths, next_cursor, total_files_found, glob_counts).
+    #[allow(clippy::too_many_arguments)]
     async fn find_and_filter_files(
         &self,
         path_pattern: &str,
         max_file_size: u64,
         max_results: usize,
         cursor: Option<CursorParam>,
-    ) -> Result<(Vec<(String, u64)>, Option<CursorResult>, usize), ServiceError> {
+        include_archives: bool,
+        workspace: Option<&str>,
+        walk: &WalkOptions,
+        stop: &StopSignal,
+    ) -> Result<(Vec<(String, u64)>, Option<CursorResult>, usize, GlobCounts), ServiceError> {
         // Early return if cursor indicates completion
-        if let Some(ref c) = cursor {
-            if c.is_complete {
-                return Ok((
-                    vec![],
-                    Some(CursorResult {
-                        last_file_path: String::new(),
-                        is_complete: true,
-                    }),
-                    0,
-                ));
-            }
+        if let Some(ref c) = cursor
+            && c.is_complete
+        {
+            return Ok((
+                vec![],
+                Some(CursorResult {
+                    last_file_path: String::new(),
+                    is_complete: true,
+                    token: None,
+                }),
+                0,
+                GlobCounts::default(),
+            ));
         }
 
-        let validated_pattern = validate_path_pattern(path_pattern)?;
+        let path_pattern = self.config.interpolate_env(path_pattern);
+        let validated_pattern = validate_path_pattern(&path_pattern)?;
+        let roots = self.config.workspace_roots(workspace)?;
+        let walk = self.config.walk_settings(walk);
 
         // Check if this is a direct file path (not a glob pattern)
         let path = std::path::Path::new(&validated_pattern);
         if path.is_file() {
             // Validate the file is under a root directory
-            self.validate_file_under_roots(&validated_pattern)?;
+            validate_file_under_roots(&validated_pattern, &roots)?;
 
             // Get file metadata
             let metadata = std::fs::metadata(&validated_pattern).map_err(|e| {
@@ -76,9 +160,15 @@ impl SearchService {
             let next_cursor = Some(CursorResult {
                 last_file_path: validated_pattern.clone(),
                 is_complete: true,
+                token: None,
             });
+            let counts = GlobCounts {
+                matching: 1,
+                too_large: usize::from(file_paths.is_empty()),
+                stopped: false,
+            };
 
-            return Ok((file_paths, next_cursor, total_files));
+            return Ok((file_paths, next_cursor, total_files, counts));
         }
 
         let glob = Glob::new(&validated_pattern)
@@ -125,7 +215,7 @@ impl SearchService {
                     dir_path
                 };
 
-            for root in &self.config.root_directories {
+            for root in &roots {
                 if let Ok(canonical_root) = root.canonicalize() {
                     let root_str = canonical_root.to_string_lossy();
                     if canonical_pattern_dir.starts_with(root_str.as_ref()) {
@@ -156,121 +246,197 @@ impl SearchService {
                 }
                 _ => {
                     // Absolute path is not within any allowed root
-                    return Err(ServiceError::Internal(
-                        "Path is outside allowed directories".to_string(),
-                    ));
+                    return Err(ServiceError::Internal(match workspace {
+                        Some(workspace) => format!("Path is outside workspace '{workspace}'"),
+                        None => "Path is outside allowed directories".to_string(),
+                    }));
                 }
             }
         } else {
             // Relative pattern - search in all roots
-            (
-                self.config.root_directories.clone(),
-                validated_pattern.clone(),
-            )
+            (roots, validated_pattern.clone())
         };
 
-        // Collect all potential files first
-        let all_files: Vec<(String, u64)> = search_roots
-            .iter()
-            .flat_map(|root_dir| {
-                let root_dir_clone = root_dir.clone();
-                let pattern_clone = effective_glob_pattern.clone();
-                let glob_set_clone = glob_set.clone();
-                WalkDir::new(root_dir)
-                    .max_depth(10)
-                    .into_iter()
-                    .filter_map(|e| e.ok())
-                    .filter(|e| e.file_type().is_file())
-                    .filter_map(move |entry| {
-                        let path = entry.path();
-                        let path_str = path.to_string_lossy().to_string();
-
-                        // For relative patterns, check against relative path
-                        let matches =
-                            if pattern_clone.starts_with("**") || pattern_clone.contains('/') {
-                                // Try matching against relative path from root
-                                if let Ok(rel_path) = path.strip_prefix(&root_dir_clone) {
-                                    glob_set_clone.is_match(rel_path.to_string_lossy().as_ref())
-                                } else {
-                                    glob_set_clone.is_match(&path_str)
-                                }
-                            } else {
-                                // For simple patterns like "*.js", match against filename
-                                if let Some(file_name) = path.file_name() {
-                                    glob_set_clone.is_match(file_name.to_string_lossy().as_ref())
-                                } else {
-                                    false
-                                }
-                            };
-
-                        if !matches {
-                            return None;
-                        }
-
-                        // Check file size
-                        entry
-                            .metadata()
-                            .ok()
-                            .filter(|m| m.len() <= max_file_size)
-                            .map(|m| (path_str, m.len()))
-                    })
-            })
-            .collect();
-
-        // Sort files for consistent pagination
-        let mut sorted_files = all_files;
-        sorted_files.sort_by(|a, b| a.0.cmp(&b.0));
-
-        // Apply cursor filtering and max_results limit
-        let cursor_filter = cursor.as_ref().map(|c| c.last_file_path.clone());
-        let mut paginated_files = Vec::new();
-        let mut files_processed_count = 0;
-
-        for (path_str, file_size) in sorted_files.into_iter() {
-            if let Some(ref start_path) = cursor_filter {
-                if path_str.as_str() <= start_path.as_str() {
-                    continue;
+        let glob_matches = |root_dir: &std::path::Path, path: &std::path::Path| {
+            // For relative patterns, check against relative path
+            if effective_glob_pattern.starts_with("**") || effective_glob_pattern.contains('/') {
+                // Try matching against relative path from root
+                if let Ok(rel_path) = path.strip_prefix(root_dir) {
+                    glob_set.is_match(rel_path.to_string_lossy().as_ref())
+                } else {
+                    glob_set.is_match(path.to_string_lossy().as_ref())
+                }
+            } else {
+                // For simple patterns like "*.js", match against filename
+                if let Some(file_name) = path.file_name() {
+                    glob_set.is_match(file_name.to_string_lossy().as_ref())
+                } else {
+                    false
                 }
             }
+        };
 
-            if files_processed_count >= max_results {
-                // We've reached the limit for this page, set cursor for next page
-                let next_cursor = Some(CursorResult {
-                    last_file_path: path_str,
+        // Collect all potential files first, from the index when it was walked the same way
+        let index = self.index.as_ref().filter(|index| *index.walk() == walk);
+        let mut all_files: Vec<(String, u64)> = Vec::new();
+        let mut stopped = false;
+        for root_dir in &search_roots {
+            match index.and_then(|index| index.files_under(root_dir)) {
+                Some(files) => all_files.extend(
+                    files
+                        .into_iter()
+                        .filter(|(path, _)| glob_matches(root_dir, path))
+                        .map(|(path, size)| (path.to_string_lossy().to_string(), size)),
+                ),
+                None => all_files.extend(
+                    walk_files(root_dir, &walk)
+                        .take_while(|_| {
+                            stopped = stop.should_stop();
+                            !stopped
+                        })
+                        .filter_map(|entry| {
+                            if !glob_matches(root_dir, entry.path()) {
+                                return None;
+                            }
+                            let path_str = entry.path().to_string_lossy().to_string();
+                            entry.metadata().ok().map(|m| (path_str, m.len()))
+                        }),
+                ),
+            }
+            if stopped {
+                // Files are paged in path order, which a partial walk can't give
+                let next_cursor = CursorResult {
+                    last_file_path: cursor.map(|c| c.last_file_path).unwrap_or_default(),
                     is_complete: false,
-                });
-                let files_count = paginated_files.len();
-                return Ok((paginated_files, next_cursor, files_count));
+                    token: None,
+                };
+                let counts = GlobCounts {
+                    stopped: true,
+                    ..Default::default()
+                };
+                return Ok((Vec::new(), Some(next_cursor), 0, counts));
             }
+        }
+        // Overlapping or symlinked roots list the same files under each of them
+        if search_roots.len() > 1 {
+            dedupe_canonical(&mut all_files, |(path, _)| path);
+        }
+        let mut counts = GlobCounts {
+            matching: all_files.len(),
+            too_large: 0,
+            stopped: false,
+        };
+        all_files.retain(|(_, size)| *size <= max_file_size);
+        counts.too_large = counts.matching - all_files.len();
 
-            paginated_files.push((path_str, file_size));
-            files_processed_count += 1;
+        if include_archives {
+            // Paths inside an archive are matched the same way as paths under a root
+            let match_full_path =
+                effective_glob_pattern.starts_with("**") || effective_glob_pattern.contains('/');
+            all_files.extend(archive::find_entries(
+                &search_roots,
+                &walk,
+                |name| match match_full_path {
+                    true => glob_set.is_match(name),
+                    false => glob_set.is_match(name.rsplit('/').next().unwrap_or(name)),
+                },
+                max_file_size,
+            ));
+            counts.matching = counts.too_large + all_files.len();
         }
 
-        // If we reached here, all matching files have been processed
-        let files_count = paginated_files.len();
-        Ok((
-            paginated_files,
-            Some(CursorResult {
-                last_file_path: String::new(),
-                is_complete: true,
-            }),
-            files_count,
-        ))
+        Ok(paginate(all_files, cursor, max_results, counts))
+    }
+
+    /// Lists the files matching a relative path pattern as they were at a past commit, paginated
+    /// like `find_and_filter_files`. Each file is reported at its working tree path, and the
+    /// commit resolved in each searched root is returned for reading the files back.
+    #[allow(clippy::type_complexity)]
+    fn find_revision_files(
+        &self,
+        path_pattern: &str,
+        revision: &str,
+        max_file_size: u64,
+        max_results: usize,
+        cursor: Option<CursorParam>,
+        workspace: Option<&str>,
+    ) -> Result<
+        (
+            Vec<(String, u64)>,
+            Option<CursorResult>,
+            usize,
+            GlobCounts,
+            Vec<(PathBuf, String)>,
+        ),
+        ServiceError,
+    > {
+        let path_pattern = self.config.interpolate_env(path_pattern);
+        let validated_pattern = validate_path_pattern(&path_pattern)?;
+        if validated_pattern.starts_with('/') {
+            return Err(ServiceError::Internal(
+                "A revision search takes a path_pattern relative to the root directories"
+                    .to_string(),
+            ));
+        }
+        let glob_set = Glob::new(&validated_pattern)
+            .map_err(|e| ServiceError::Internal(format!("Invalid glob pattern: {e}")))?
+            .compile_matcher();
+        let match_full_path =
+            validated_pattern.starts_with("**") || validated_pattern.contains('/');
+
+        let mut commits = Vec::new();
+        let mut all_files = Vec::new();
+        for root in self.config.workspace_roots(workspace)? {
+            let commit = resolve_revision(&root, revision)?;
+            for (relative, size) in list_files_at(&root, &commit)? {
+                let matches = match match_full_path {
+                    true => glob_set.is_match(&relative),
+                    false => glob_set.is_match(relative.rsplit('/').next().unwrap_or(&relative)),
+                };
+                if matches {
+                    all_files.push((root.join(&relative).to_string_lossy().to_string(), size));
+                }
+            }
+            commits.push((root, commit));
+        }
+
+        let mut counts = GlobCounts {
+            matching: all_files.len(),
+            too_large: 0,
+            stopped: false,
+        };
+        all_files.retain(|(_, size)| *size <= max_file_size);
+        counts.too_large = counts.matching - all_files.len();
+
+        let (files, next_cursor, total, counts) = paginate(all_files, cursor, max_results, counts);
+        Ok((files, next_cursor, total, counts, commits))
     }
 
     pub async fn search(&self, param: SearchParam) -> Result<SearchResult, ServiceError> {
-        let lang = Language::from_str(&param.language)
-            .map_err(|_| ServiceError::Internal("Failed to parse language".to_string()))?;
+        let lang = AnyLanguage::resolve(&param.language, param.dialect.as_deref())?;
 
         // Regular search
-        let matches = self.pattern_matcher.search_with_options(
+        let matches = self.pattern_matcher.search_with_ast_context(
             &param.code,
             &param.pattern,
             lang,
             param.selector.as_deref(),
             param.context.as_deref(),
+            param.include_ast_context,
         )?;
+        let mut matches = match param.context_scope {
+            Some(scope) => extract_context_scope(&param.code, lang, &matches, scope),
+            None => extract_context_lines(
+                &param.code,
+                &matches,
+                param.context_before,
+                param.context_after,
+                param.context_lines,
+            ),
+        };
+        if param.include_leading_trivia {
+            attach_leading_trivia(&param.code, lang, &mut matches);
+        }
 
         Ok(SearchResult {
             matches,
@@ -281,120 +447,1027 @@ impl SearchService {
     pub async fn file_search(
         &self,
         param: FileSearchParam,
+    ) -> Result<FileSearchResult, ServiceError> {
+        self.file_search_with_fixes(param, &[], None).await
+    }
+
+    /// Search files like `file_search`, annotating matches with overlapping `fix_rules` matches
+    /// when `suggest_fixes` is set. Extra grammars and the regex fallback get no suggestions.
+    /// Each file with matches is also sent to `progress` as soon as it has been searched
+    pub async fn file_search_with_fixes(
+        &self,
+        param: FileSearchParam,
+        fix_rules: &[FixRule],
+        progress: Option<&UnboundedSender<FileSearchProgress>>,
     ) -> Result<FileSearchResult, ServiceError> {
         // Early return if cursor indicates completion
-        if let Some(ref cursor) = param.cursor {
-            if cursor.is_complete {
-                return Ok(FileSearchResult {
-                    matches: vec![],
-                    next_cursor: Some(CursorResult {
-                        last_file_path: String::new(),
-                        is_complete: true,
-                    }),
-                    total_files_found: 0,
+        if let Some(ref cursor) = param.cursor
+            && cursor.is_complete
+        {
+            return Ok(FileSearchResult {
+                matches: vec![],
+                next_cursor: Some(CursorResult {
+                    last_file_path: String::new(),
+                    is_complete: true,
+                    token: None,
+                }),
+                total_files_found: 0,
+                skipped_files: Vec::new(),
+                budget_exceeded: false,
+                timed_out: false,
+                stats: None,
+                warnings: Vec::new(),
+            });
+        }
+
+        let language_patterns = resolve_language_patterns(&param.language_patterns)?;
+        if !param.regex_fallback || Language::from_str(&param.language).is_ok() {
+            for pattern in std::iter::once(&param.pattern).chain(param.language_patterns.values()) {
+                check_broad_pattern(pattern, param.context.as_deref(), param.allow_broad)?;
+            }
+        }
+        // Without a language each file is searched in the language detected for it
+        let auto_detect = language_patterns.is_empty() && is_auto_language(&param.language);
+        if auto_detect && param.dialect.is_some() {
+            return Err(ServiceError::ParserError(
+                "dialect needs a language; with language auto the grammar follows each file"
+                    .to_string(),
+            ));
+        }
+        let extra_lang = match language_patterns.is_empty() && param.dialect.is_none() {
+            true => Language::from_str(&param.language)
+                .err()
+                .and_then(|_| AnyLanguage::from_str(&param.language).ok()),
+            false => None,
+        };
+        // Languages without a grammar are searched line by line when the caller opted in
+        let fallback_regex = match param.regex_fallback
+            && language_patterns.is_empty()
+            && !auto_detect
+            && extra_lang.is_none()
+            && Language::from_str(&param.language).is_err()
+        {
+            true => Some(Regex::new(&param.pattern)?),
+            false => None,
+        };
+        let requested_lang = if fallback_regex.is_some() || extra_lang.is_some() || auto_detect {
+            None
+        } else if language_patterns.is_empty() {
+            Some(resolve_language(&param.language, param.dialect.as_deref())?)
+        } else if param.dialect.is_some() {
+            return Err(ServiceError::ParserError(
+                "dialect cannot be combined with language_patterns; the grammar follows each file's extension".to_string(),
+            ));
+        } else {
+            None
+        };
+
+        let path_pattern = &param.path_pattern;
+        let overlays = Overlays::resolve(&param.overlays, &self.config.root_directories);
+        let mut file_results = Vec::new();
+        let mut skipped_files = Vec::new();
+        let stop = self.stop_signal(param.timeout_ms);
+        // Where a search stopped before its first file resumes
+        let mut resume_after = param
+            .cursor
+            .as_ref()
+            .map(|cursor| cursor.last_file_path.clone())
+            .unwrap_or_default();
+
+        let (file_paths, mut next_cursor, total_files_found, glob_counts, revision_commits) =
+            match &param.revision {
+                Some(_) if param.search_archives || !param.overlays.is_empty() => {
+                    return Err(ServiceError::Internal(
+                        "revision cannot be combined with overlays or search_archives; both read the working tree".to_string(),
+                    ));
+                }
+                Some(revision) => self.find_revision_files(
+                    path_pattern,
+                    revision,
+                    param.max_file_size,
+                    param.max_results,
+                    param.cursor,
+                    param.workspace.as_deref(),
+                )?,
+                None => {
+                    let (file_paths, next_cursor, total_files_found, glob_counts) = self
+                        .find_and_filter_files(
+                            path_pattern,
+                            param.max_file_size,
+                            param.max_results,
+                            param.cursor,
+                            param.search_archives,
+                            param.workspace.as_deref(),
+                            &param.walk,
+                            &stop,
+                        )
+                        .await?;
+                    (
+                        file_paths,
+                        next_cursor,
+                        total_files_found,
+                        glob_counts,
+                        Vec::new(),
+                    )
+                }
+            };
+        let mut stats = FileScanStats {
+            files_matching_glob: glob_counts.matching,
+            skipped_too_large: glob_counts.too_large,
+            ..Default::default()
+        };
+        let mut timed_out = glob_counts.stopped;
+
+        let archive_contents = match param.search_archives {
+            true => archive::read_entry_paths(
+                file_paths.iter().map(|(path, _)| path.as_str()),
+                param.max_file_size,
+            ),
+            false => HashMap::new(),
+        };
+        for (archive_path, contents) in &archive_contents {
+            skipped_files.extend(contents.skipped.iter().map(|(entry, reason)| SkippedFile {
+                file_path: archive::entry_path(archive_path, entry),
+                reason: reason.clone(),
+            }));
+        }
+
+        let total_files = file_paths.len();
+        let report = |files_searched: usize, file: &FileMatchResult| {
+            if let Some(progress) = progress {
+                // The receiver is gone once the client stopped listening; the search goes on
+                let _ = progress.send(FileSearchProgress {
+                    files_searched,
+                    total_files,
+                    file: file.clone(),
                 });
             }
+        };
+
+        for (index, (file_path, _)) in file_paths.into_iter().enumerate() {
+            if stop.should_stop() {
+                timed_out = true;
+                next_cursor = Some(CursorResult {
+                    last_file_path: resume_after,
+                    is_complete: false,
+                    token: None,
+                });
+                break;
+            }
+            resume_after.clone_from(&file_path);
+            let archive_entry = archive::split_entry_path(&file_path)
+                .filter(|_| param.search_archives)
+                .map(|(archive_path, entry)| {
+                    archive_contents
+                        .get(archive_path)
+                        .and_then(|contents| contents.entries.get(entry))
+                });
+            let content = match archive_entry {
+                Some(Some(content)) => content.clone(),
+                Some(None) => {
+                    stats.skipped_unreadable += 1;
+                    continue;
+                }
+                None => {
+                    let content = match param.revision {
+                        Some(_) => read_revision_file(&revision_commits, &file_path),
+                        None => overlays.read(&file_path).map_err(ServiceError::from),
+                    };
+                    match content {
+                        Ok(content) => content,
+                        Err(_) => {
+                            stats.skipped_unreadable += 1;
+                            continue;
+                        }
+                    }
+                }
+            };
+            let detected_lang = match auto_detect {
+                true => match detect_language(&file_path, &content) {
+                    Some(lang) => Some(lang),
+                    None => {
+                        stats.skipped_other_language += 1;
+                        continue;
+                    }
+                },
+                false => None,
+            };
+            let extra_lang = match detected_lang {
+                Some(AnyLanguage::Builtin(_)) | None => extra_lang,
+                other => other,
+            };
+            // Extra and custom grammars and the regex fallback skip worker isolation and per-file
+            // dialects
+            let alternative = match (extra_lang, &fallback_regex) {
+                (Some(lang), _) if !lang.matches_path(&file_path) => {
+                    stats.skipped_other_language += 1;
+                    continue;
+                }
+                (Some(lang), _) => {
+                    let matches = self.pattern_matcher.search_with_ast_context(
+                        &content,
+                        &param.pattern,
+                        lang,
+                        param.selector.as_deref(),
+                        param.context.as_deref(),
+                        param.include_ast_context,
+                    )?;
+                    let mut matches = match param.context_scope {
+                        Some(scope) => extract_context_scope(&content, lang, &matches, scope),
+                        None => matches,
+                    };
+                    if param.include_leading_trivia {
+                        attach_leading_trivia(&content, lang, &mut matches);
+                    }
+                    Some((matches, None))
+                }
+                (None, Some(regex)) => Some((
+                    regex_fallback::find_matches(&content, regex),
+                    Some(regex_fallback::REGEX_ENGINE.to_string()),
+                )),
+                (None, None) => None,
+            };
+            if let Some((matches, engine)) = alternative {
+                // Regex matches have no syntax tree to take a scope from
+                let matches = match param.context_scope {
+                    Some(_) => matches,
+                    None => extract_context_lines(
+                        &content,
+                        &matches,
+                        param.context_before,
+                        param.context_after,
+                        param.context_lines,
+                    ),
+                };
+                stats.record_searched(&file_path, !matches.is_empty());
+                if !matches.is_empty() {
+                    let file = FileMatchResult {
+                        file_path: file_path.clone(),
+                        file_size_bytes: content.len() as u64,
+                        matches,
+                        file_hash: content_hash(&content),
+                        language: detected_lang.map(|lang| lang.to_string().to_lowercase()),
+                        workspace: self.config.workspace_label(&file_path),
+                        engine,
+                    };
+                    report(index + 1, &file);
+                    file_results.push(file);
+                }
+                continue;
+            }
+
+            let (lang, pattern, language_key) = match requested_lang {
+                Some(requested_lang) => {
+                    let lang = match param.dialect {
+                        Some(_) => requested_lang,
+                        None => language_for_file(requested_lang, &file_path),
+                    };
+                    (lang, &param.pattern, None)
+                }
+                None => match detected_lang {
+                    Some(AnyLanguage::Builtin(lang)) => {
+                        (lang, &param.pattern, Some(lang.to_string().to_lowercase()))
+                    }
+                    _ => match LanguagePattern::for_file(&language_patterns, &file_path) {
+                        Some((lang, entry)) => (lang, &entry.pattern, Some(entry.key.clone())),
+                        None => {
+                            stats.skipped_other_language += 1;
+                            continue;
+                        }
+                    },
+                },
+            };
+
+            let matches = match &self.workers {
+                Some(workers) => {
+                    let job = MatchJob {
+                        code: content.clone(),
+                        pattern: pattern.clone(),
+                        language: lang.to_string(),
+                        selector: param.selector.clone(),
+                        context: param.context.clone(),
+                        include_ast_context: param.include_ast_context,
+                    };
+                    match workers.search(&job).await? {
+                        IsolatedOutcome::Matches(matches) => matches,
+                        IsolatedOutcome::Failed(reason) => {
+                            tracing::warn!("Skipping {file_path}: {reason}");
+                            stats.skipped_worker_failures += 1;
+                            skipped_files.push(SkippedFile { file_path, reason });
+                            continue;
+                        }
+                    }
+                }
+                // Working tree files reuse the syntax tree the index kept for them
+                None => match &self.index {
+                    Some(index) if archive_entry.is_none() && param.revision.is_none() => {
+                        let ast = index.parse(&file_path, &content, lang);
+                        self.pattern_matcher.search_parsed(
+                            &ast,
+                            pattern,
+                            lang,
+                            param.selector.as_deref(),
+                            param.context.as_deref(),
+                            param.include_ast_context,
+                        )?
+                    }
+                    // Regular search
+                    _ => self.pattern_matcher.search_with_ast_context(
+                        &content,
+                        pattern,
+                        lang,
+                        param.selector.as_deref(),
+                        param.context.as_deref(),
+                        param.include_ast_context,
+                    )?,
+                },
+            };
+            let mut matches = match param.context_scope {
+                Some(scope) => extract_context_scope(&content, lang, &matches, scope),
+                None => extract_context_lines(
+                    &content,
+                    &matches,
+                    param.context_before,
+                    param.context_after,
+                    param.context_lines,
+                ),
+            };
+            if param.include_leading_trivia && !matches.is_empty() {
+                attach_leading_trivia(&content, lang, &mut matches);
+            }
+            if param.suggest_fixes && !matches.is_empty() {
+                annotate_matches(
+                    &self.rule_evaluator,
+                    fix_rules,
+                    &content,
+                    &file_path,
+                    lang,
+                    &mut matches,
+                );
+            }
+
+            stats.record_searched(&file_path, !matches.is_empty());
+            if !matches.is_empty() {
+                let file = FileMatchResult {
+                    file_path: file_path.clone(),
+                    file_size_bytes: content.len() as u64,
+                    matches,
+                    file_hash: content_hash(&content),
+                    language: language_key,
+                    workspace: self.config.workspace_label(&file_path),
+                    engine: None,
+                };
+                report(index + 1, &file);
+                file_results.push(file);
+            }
         }
 
-        let lang = Language::from_str(&param.language)
+        Ok(FileSearchResult {
+            matches: file_results,
+            next_cursor,
+            total_files_found,
+            skipped_files,
+            budget_exceeded: false,
+            timed_out,
+            stats: Some(stats),
+            warnings: self.config.overlapping_root_warnings(param.workspace.as_deref()),
+        })
+    }
+
+    /// Run several searches over one walk of the root directories. Each file is read once and
+    /// parsed once per language, and files are searched concurrently. A search whose language,
+    /// pattern or path pattern is invalid reports its error and takes no part in the walk
+    pub async fn batch_search(
+        &self,
+        param: BatchSearchParam,
+    ) -> Result<BatchSearchResult, ServiceError> {
+        if param.searches.is_empty() {
+            return Err(ServiceError::Internal(
+                "batch_search needs at least one search".to_string(),
+            ));
+        }
+        let mut results = BTreeMap::new();
+        let mut entries = Vec::new();
+        for search in &param.searches {
+            if results.contains_key(&search.id) {
+                return Err(ServiceError::Internal(format!(
+                    "batch_search ids must be unique; '{}' is used twice",
+                    search.id
+                )));
+            }
+            let result = match self.prepare_batch_entry(search, param.allow_broad) {
+                Ok(entry) => {
+                    entries.push(entry);
+                    BatchSearchEntryResult::default()
+                }
+                Err(e) => BatchSearchEntryResult {
+                    error: Some(e.to_string()),
+                    ..Default::default()
+                },
+            };
+            results.insert(search.id.clone(), result);
+        }
+
+        let walk = self.config.walk_settings(&param.walk);
+        let mut skipped_files = Vec::new();
+        let mut files = Vec::new();
+        let roots = self.config.workspace_roots(param.workspace.as_deref())?;
+        for root in &roots {
+            for entry in walk_files(root, &walk) {
+                let path = entry.path();
+                let relative = path.strip_prefix(root).unwrap_or(path).to_string_lossy();
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                let searches: Vec<usize> = entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| match entry.match_full_path {
+                        true => entry.glob.is_match(relative.as_ref()),
+                        false => entry.glob.is_match(file_name.as_ref()),
+                    })
+                    .map(|(index, _)| index)
+                    .collect();
+                if searches.is_empty() {
+                    continue;
+                }
+                let file_path = path.to_string_lossy().to_string();
+                let size = entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+                if size > param.max_file_size {
+                    skipped_files.push(SkippedFile {
+                        file_path,
+                        reason: format!("larger than {} bytes", param.max_file_size),
+                    });
+                    continue;
+                }
+                files.push((file_path, searches));
+            }
+        }
+        // Overlapping or symlinked roots list the same files under each of them
+        if roots.len() > 1 {
+            dedupe_canonical(&mut files, |(file_path, _)| file_path);
+        }
+
+        let entries = Arc::new(entries);
+        let mut searched = futures::stream::iter(files.into_iter().map(|(file_path, searches)| {
+            let entries = entries.clone();
+            let pattern_matcher = self.pattern_matcher.clone();
+            tokio::task::spawn_blocking(move || {
+                search_batch_file(&pattern_matcher, &entries, file_path, &searches)
+            })
+        }))
+        .buffered(self.config.cpu_concurrency.max(1));
+
+        let mut files_searched = 0;
+        while let Some(file) = searched.next().await {
+            let file =
+                file.map_err(|e| ServiceError::Internal(format!("Search task failed: {e}")))?;
+            let file = match file {
+                Ok(file) => file,
+                Err(skipped) => {
+                    skipped_files.push(skipped);
+                    continue;
+                }
+            };
+            files_searched += 1;
+            for (index, matches) in file.matches {
+                let entry = &entries[index];
+                let result = results
+                    .get_mut(&entry.id)
+                    .expect("every entry has a result");
+                let mut matches = match matches {
+                    Ok(matches) if matches.is_empty() => continue,
+                    Ok(matches) => matches,
+                    Err(e) => {
+                        result
+                            .error
+                            .get_or_insert(format!("{}: {e}", file.file_path));
+                        continue;
+                    }
+                };
+                let room = entry.max_results.saturating_sub(result.total_matches);
+                result.total_matches += matches.len();
+                if matches.len() > room {
+                    matches.truncate(room);
+                    result.truncated = true;
+                }
+                if matches.is_empty() {
+                    continue;
+                }
+                result.matches.push(FileMatchResult {
+                    file_path: file.file_path.clone(),
+                    file_size_bytes: file.size,
+                    matches,
+                    file_hash: file.file_hash.clone(),
+                    language: None,
+                    workspace: self.config.workspace_label(&file.file_path),
+                    engine: None,
+                });
+            }
+        }
+
+        Ok(BatchSearchResult {
+            results,
+            files_searched,
+            skipped_files,
+            warnings: self
+                .config
+                .overlapping_root_warnings(param.workspace.as_deref()),
+        })
+    }
+
+    /// Check a `batch_search` search before the walk, compiling its pattern into the cache
+    fn prepare_batch_entry(
+        &self,
+        search: &BatchSearchEntry,
+        allow_broad: bool,
+    ) -> Result<BatchEntry, ServiceError> {
+        check_broad_pattern(&search.pattern, search.context.as_deref(), allow_broad)?;
+        let lang = resolve_language(&search.language, None)?;
+        self.pattern_matcher.search_with_options(
+            "",
+            &search.pattern,
+            lang,
+            search.selector.as_deref(),
+            search.context.as_deref(),
+        )?;
+        let path_pattern = self.config.interpolate_env(&search.path_pattern);
+        let path_pattern = validate_path_pattern(&path_pattern)?;
+        if path_pattern.starts_with('/') {
+            return Err(ServiceError::Internal(
+                "batch_search takes a path_pattern relative to the root directories".to_string(),
+            ));
+        }
+        let glob = Glob::new(&path_pattern)
+            .map_err(|e| ServiceError::Internal(format!("Invalid glob pattern: {e}")))?
+            .compile_matcher();
+        Ok(BatchEntry {
+            id: search.id.clone(),
+            lang,
+            glob,
+            match_full_path: path_pattern.starts_with("**") || path_pattern.contains('/'),
+            pattern: search.pattern.clone(),
+            selector: search.selector.clone(),
+            context: search.context.clone(),
+            max_results: search.max_results,
+        })
+    }
+
+    pub async fn rule_search(
+        &self,
+        param: RuleSearchParam,
+    ) -> Result<FileSearchResult, ServiceError> {
+        let (result, _suppressions) = self.rule_search_with_suppressions(param).await?;
+        Ok(result)
+    }
+
+    /// Run a rule search, dropping matches silenced by `ast-grep-ignore` comments and returning
+    /// how many were dropped alongside the result
+    pub(crate) async fn rule_search_with_suppressions(
+        &self,
+        param: RuleSearchParam,
+    ) -> Result<(FileSearchResult, usize), ServiceError> {
+        // Check if cursor indicates completion
+        if let Some(ref cursor) = param.cursor
+            && cursor.is_complete
+        {
+            let result = FileSearchResult {
+                matches: vec![],
+                next_cursor: Some(CursorResult {
+                    last_file_path: String::new(),
+                    is_complete: true,
+                    token: None,
+                }),
+                total_files_found: 0,
+                skipped_files: Vec::new(),
+                budget_exceeded: false,
+                timed_out: false,
+                stats: None,
+                warnings: Vec::new(),
+            };
+            return Ok((result, 0));
+        }
+
+        let rule = self.config.parse_rule_config(&param.rule_config)?;
+        let lang = Language::from_str(&rule.language)
             .map_err(|_| ServiceError::Internal("Failed to parse language".to_string()))?;
 
-        let path_pattern = &param.path_pattern;
+        let path_pattern = param.path_pattern.as_deref().unwrap_or("**/*");
         let mut file_results = Vec::new();
+        let mut suppressions = 0;
+
+        let timeout = match param.timeout_ms {
+            Some(ms) => self.config.rule_timeout.min(Duration::from_millis(ms)),
+            None => self.config.rule_timeout,
+        };
+        let max_matches = param
+            .max_matches
+            .map_or(self.config.rule_max_matches, |max| {
+                max.min(self.config.rule_max_matches)
+            });
+        let unbounded = self.rule_evaluator.with_utils(&rule.utils)?;
+        let evaluator = unbounded.with_deadline(Instant::now() + timeout);
+        let mut total_matches = 0;
+        let mut budget_exceeded = false;
+        let stop = self.stop_signal(param.timeout_ms);
+        let mut resume_after = param
+            .cursor
+            .as_ref()
+            .map(|cursor| cursor.last_file_path.clone())
+            .unwrap_or_default();
 
-        let (file_paths, next_cursor, total_files_found) = self
+        let (file_paths, mut next_cursor, total_files_found, glob_counts) = self
             .find_and_filter_files(
                 path_pattern,
                 param.max_file_size,
                 param.max_results,
                 param.cursor,
+                false,
+                param.workspace.as_deref(),
+                &param.walk,
+                // A walk cut short has no cursor to resume from, so only cancellation stops it
+                &self.stop_signal(None),
             )
             .await?;
+        // Running out of time is part of a rule's budget, so it is reported both ways
+        let mut timed_out = glob_counts.stopped;
+        budget_exceeded |= timed_out;
 
-        for (file_path, _) in file_paths {
+        for (index, (file_path, _)) in file_paths.into_iter().enumerate() {
+            // The first file is searched whatever is left of the budget, so that the cursor
+            // always moves on and a client resuming with the same budget gets further
+            let evaluator = if index == 0 { &unbounded } else { &evaluator };
+            if index > 0 && stop.should_stop() {
+                timed_out = true;
+                budget_exceeded = true;
+                next_cursor = Some(CursorResult {
+                    last_file_path: resume_after,
+                    is_complete: false,
+                    token: None,
+                });
+                break;
+            }
+            let previous = std::mem::replace(&mut resume_after, file_path.clone());
             let content = match std::fs::read_to_string(&file_path) {
                 Ok(content) => content,
                 Err(_) => continue,
             };
 
-            // Regular search
-            let matches = self.pattern_matcher.search_with_options(
-                &content,
-                &param.pattern,
-                lang,
-                param.selector.as_deref(),
-                param.context.as_deref(),
-            )?;
+            // TODO: Check if file language matches
+
+            let matches = evaluator
+                .evaluate_rule_against_code(&rule.rule, &content, lang)
+                .and_then(|matches| {
+                    evaluator.apply_constraints(matches, &rule.constraints, &content, lang)
+                });
+            let matches = match matches {
+                Ok(matches) => matches,
+                Err(ServiceError::BudgetExceeded(_)) => {
+                    // Resume from the file before, so this one is searched again in full
+                    tracing::warn!("Rule '{}' ran out of time in {file_path}", rule.id);
+                    budget_exceeded = true;
+                    next_cursor = Some(CursorResult {
+                        last_file_path: previous,
+                        is_complete: false,
+                        token: None,
+                    });
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            let (mut matches, suppressed) = filter_suppressed(&content, &rule.id, matches);
+            suppressions += suppressed;
+            let remaining = max_matches - total_matches;
+            if matches.len() >= remaining {
+                matches.truncate(remaining);
+                budget_exceeded = true;
+            }
+            total_matches += matches.len();
 
             if !matches.is_empty() {
                 file_results.push(FileMatchResult {
                     file_path: file_path.clone(),
                     file_size_bytes: content.len() as u64,
                     matches,
-                    file_hash: String::new(),
+                    file_hash: content_hash(&content),
+                    language: None,
+                    workspace: self.config.workspace_label(&file_path),
+                    engine: None,
+                });
+            }
+
+            if budget_exceeded {
+                next_cursor = Some(CursorResult {
+                    last_file_path: file_path,
+                    is_complete: false,
+                    token: None,
                 });
+                break;
             }
         }
 
-        Ok(FileSearchResult {
+        let result = FileSearchResult {
             matches: file_results,
             next_cursor,
             total_files_found,
-        })
+            skipped_files: Vec::new(),
+            budget_exceeded,
+            timed_out,
+            stats: None,
+            warnings: self.config.overlapping_root_warnings(param.workspace.as_deref()),
+        };
+        Ok((result, suppressions))
     }
 
-    pub async fn rule_search(
+    /// Run `rules` over the files matching `param.path_pattern`, grouping the findings by the
+    /// severity of the rule that produced them. Error-severity findings are also sent to
+    /// `errors` as soon as they are found
+    pub async fn scan(
         &self,
-        param: RuleSearchParam,
-    ) -> Result<FileSearchResult, ServiceError> {
-        // Check if cursor indicates completion
-        if let Some(ref cursor) = param.cursor {
-            if cursor.is_complete {
-                return Ok(FileSearchResult {
-                    matches: vec![],
-                    next_cursor: Some(CursorResult {
-                        last_file_path: String::new(),
-                        is_complete: true,
-                    }),
-                    total_files_found: 0,
-                });
+        rules: &[RuleConfig],
+        param: ScanParam,
+        errors: Option<&UnboundedSender<ScanFinding>>,
+    ) -> Result<ScanResult, ServiceError> {
+        validate_severity(param.severity.as_deref())?;
+        let rule_languages = rules
+            .iter()
+            .map(|rule| {
+                Language::from_str(&rule.language).map_err(|_| {
+                    ServiceError::Internal(format!(
+                        "Rule '{}' has unsupported language '{}'",
+                        rule.id, rule.language
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let path_pattern = param.path_pattern.as_deref().unwrap_or("**/*");
+        let (file_paths, _, files_scanned, _) = self
+            .find_and_filter_files(
+                path_pattern,
+                param.max_file_size,
+                usize::MAX,
+                None,
+                false,
+                param.workspace.as_deref(),
+                &param.walk,
+                &self.stop_signal(None),
+            )
+            .await?;
+
+        // Each rule gets the budget rule_search gives it: its time adds up over the files it
+        // runs on, and it is dropped from the scan once either limit is reached
+        let mut spent = vec![Duration::ZERO; rules.len()];
+        let mut found = vec![0; rules.len()];
+        let mut truncated = vec![false; rules.len()];
+        let mut findings = Vec::new();
+        for (file_path, _) in file_paths {
+            let Some(file_lang) = Language::from_path(std::path::Path::new(&file_path)) else {
+                continue;
+            };
+            let content = match std::fs::read_to_string(&file_path) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+
+            for (index, (rule, &rule_lang)) in rules.iter().zip(&rule_languages).enumerate() {
+                let lang = language_for_file(rule_lang, &file_path);
+                if lang != file_lang || truncated[index] {
+                    continue;
+                }
+                let started = Instant::now();
+                let matches = self
+                    .rule_evaluator
+                    .with_deadline(started + self.config.rule_timeout.saturating_sub(spent[index]))
+                    .evaluate_rule_config(rule, &content, lang);
+                spent[index] += started.elapsed();
+                let matches = match matches {
+                    Ok(matches) => matches,
+                    Err(ServiceError::BudgetExceeded(_)) => {
+                        tracing::warn!("Rule '{}' ran out of time in {file_path}", rule.id);
+                        truncated[index] = true;
+                        continue;
+                    }
+                    Err(e) => {
+                        tracing::warn!("Skipping rule {} on {file_path}: {e}", rule.id);
+                        continue;
+                    }
+                };
+                let (mut matches, _) = filter_suppressed(&content, &rule.id, matches);
+                let remaining = self.config.rule_max_matches - found[index];
+                if matches.len() >= remaining {
+                    matches.truncate(remaining);
+                    truncated[index] = true;
+                }
+                found[index] += matches.len();
+                let severity = severity_of(rule);
+                for m in matches {
+                    let finding = ScanFinding {
+                        rule_id: rule.id.clone(),
+                        message: rule.message.clone().unwrap_or_else(|| rule.id.clone()),
+                        file_path: file_path.clone(),
+                        start_line: m.start_line,
+                        end_line: m.end_line,
+                        start_col: m.start_col,
+                        end_col: m.end_col,
+                        text: m.text,
+                    };
+                    if severity == "error"
+                        && let Some(errors) = errors
+                    {
+                        // The scan goes on if the receiver has gone away
+                        let _ = errors.send(finding.clone());
+                    }
+                    findings.push((severity, finding));
+                }
             }
         }
 
-        let rule = parse_rule_config(&param.rule_config)?;
+        Ok(ScanResult {
+            total_findings: findings.len(),
+            files_scanned,
+            rules_run: rules.len(),
+            truncated_rules: rules
+                .iter()
+                .zip(truncated)
+                .filter(|(_, truncated)| *truncated)
+                .map(|(rule, _)| rule.id.clone())
+                .collect(),
+            buckets: bucket_findings(
+                findings,
+                param.severity.as_deref(),
+                param.offset,
+                param.page_size,
+            ),
+        })
+    }
+
+    /// Evaluate a rule against one file, recording what each sub-rule matched and which
+    /// candidates were pruned along the way
+    pub async fn explain_rule_evaluation(
+        &self,
+        param: ExplainRuleParam,
+    ) -> Result<ExplainRuleResult, ServiceError> {
+        let rule = self.config.parse_rule_config(&param.rule_config)?;
         let lang = Language::from_str(&rule.language)
             .map_err(|_| ServiceError::Internal("Failed to parse language".to_string()))?;
 
-        let path_pattern = param.path_pattern.as_deref().unwrap_or("**/*");
-        let mut file_results = Vec::new();
+        let file_path = resolve_path_pattern(&param.file_path, &self.config.root_directories)?
+            .into_iter()
+            .find(|path| path.is_file())
+            .ok_or_else(|| ServiceError::FileIoError {
+                message: "File not found under the root directories".to_string(),
+                path: param.file_path.clone(),
+            })?;
+        let content =
+            std::fs::read_to_string(&file_path).map_err(|e| ServiceError::FileIoError {
+                message: e.to_string(),
+                path: file_path.to_string_lossy().to_string(),
+            })?;
+
+        let evaluator = self.rule_evaluator.with_utils(&rule.utils)?;
+        let mut tracer = RuleTracer::new(&evaluator, &content, lang, param.max_nodes_per_step);
+        let matches = tracer.trace(&rule.rule, "rule")?;
+        let matches = evaluator.apply_constraints(matches, &rule.constraints, &content, lang)?;
+        let (matches, suppressed) = filter_suppressed(&content, &rule.id, matches);
+        let steps = tracer.into_steps();
+        let explanation = explain_steps(&steps, matches.len(), suppressed);
+
+        Ok(ExplainRuleResult {
+            rule_id: rule.id,
+            file_path: file_path.to_string_lossy().to_string(),
+            steps,
+            matches,
+            suppressed,
+            explanation,
+        })
+    }
+
+    /// Find string literals whose unquoted contents match `param.value` or `param.regex`
+    pub async fn search_strings(
+        &self,
+        param: ValueSearchParam,
+    ) -> Result<FileSearchResult, ServiceError> {
+        self.search_values(param, ValueNodeKind::StringLiteral)
+            .await
+    }
+
+    /// Find identifiers whose name matches `param.value` or `param.regex`
+    pub async fn search_identifiers(
+        &self,
+        param: ValueSearchParam,
+    ) -> Result<FileSearchResult, ServiceError> {
+        self.search_values(param, ValueNodeKind::Identifier).await
+    }
+
+    /// Find comments matching `param.regex` and count them per tag
+    pub async fn find_comments(
+        &self,
+        param: FindCommentsParam,
+    ) -> Result<FindCommentsResult, ServiceError> {
+        let pattern = param.regex.as_deref().unwrap_or(DEFAULT_COMMENT_TAG_REGEX);
+        let regex = Regex::new(pattern)
+            .map_err(|e| ServiceError::ParserError(format!("Invalid regex '{pattern}': {e}")))?;
+        let matcher = ValueMatcher::Regex(regex.clone());
+        let lang = Language::from_str(&param.language)
+            .map_err(|_| ServiceError::Internal("Failed to parse language".to_string()))?;
 
-        let (file_paths, next_cursor, total_files_found) = self
+        let (file_paths, next_cursor, total_files_found, _) = self
             .find_and_filter_files(
-                path_pattern,
+                &param.path_pattern,
                 param.max_file_size,
                 param.max_results,
                 param.cursor,
+                false,
+                None,
+                &WalkOptions::default(),
+                &self.stop_signal(None),
             )
             .await?;
 
+        let mut comments = Vec::new();
+        let mut tag_counts = HashMap::new();
         for (file_path, _) in file_paths {
             let content = match std::fs::read_to_string(&file_path) {
                 Ok(content) => content,
                 Err(_) => continue,
             };
 
-            // TODO: Check if file language matches
+            let matches = find_value_nodes(&content, lang, ValueNodeKind::Comment, &matcher);
+            if matches.is_empty() {
+                continue;
+            }
+            let blame = if param.blame {
+                blame_file(std::path::Path::new(&file_path))
+            } else {
+                None
+            };
 
-            let matches = self
-                .rule_evaluator
-                .evaluate_rule_against_code(&rule.rule, &content, lang)?;
+            for m in matches {
+                let tag = regex
+                    .captures(&m.text)
+                    .and_then(|caps| caps.get(1).or_else(|| caps.get(0)))
+                    .map(|tag| tag.as_str().to_string())
+                    .unwrap_or_default();
+                *tag_counts.entry(tag.clone()).or_insert(0) += 1;
+                comments.push(CommentMatch {
+                    file_path: file_path.clone(),
+                    tag,
+                    blame: blame
+                        .as_ref()
+                        .and_then(|lines| lines.get(m.start_line).cloned()),
+                    text: m.text,
+                    start_line: m.start_line,
+                    end_line: m.end_line,
+                });
+            }
+        }
 
+        Ok(FindCommentsResult {
+            comments,
+            tag_counts,
+            total_files_found,
+            next_cursor,
+        })
+    }
+
+    async fn search_values(
+        &self,
+        param: ValueSearchParam,
+        kind: ValueNodeKind,
+    ) -> Result<FileSearchResult, ServiceError> {
+        let matcher = ValueMatcher::new(param.value.as_deref(), param.regex.as_deref())?;
+        let lang = Language::from_str(&param.language)
+            .map_err(|_| ServiceError::Internal("Failed to parse language".to_string()))?;
+
+        let (file_paths, next_cursor, total_files_found, _) = self
+            .find_and_filter_files(
+                &param.path_pattern,
+                param.max_file_size,
+                param.max_results,
+                param.cursor,
+                false,
+                None,
+                &WalkOptions::default(),
+                &self.stop_signal(None),
+            )
+            .await?;
+
+        let mut file_results = Vec::new();
+        for (file_path, _) in file_paths {
+            let content = match std::fs::read_to_string(&file_path) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+
+            let matches = find_value_nodes(&content, lang, kind, &matcher);
             if !matches.is_empty() {
                 file_results.push(FileMatchResult {
                     file_path: file_path.clone(),
                     file_size_bytes: content.len() as u64,
                     matches,
-                    file_hash: String::new(),
+                    file_hash: content_hash(&content),
+                    language: None,
+                    workspace: self.config.workspace_label(&file_path),
+                    engine: None,
                 });
             }
         }
@@ -403,26 +1476,169 @@ impl SearchService {
             matches: file_results,
             next_cursor,
             total_files_found,
+            skipped_files: Vec::new(),
+            budget_exceeded: false,
+            timed_out: false,
+            stats: None,
+            warnings: self.config.overlapping_root_warnings(None),
         })
     }
+}
 
-    fn validate_file_under_roots(&self, file_path: &str) -> Result<(), ServiceError> {
-        let path = std::path::Path::new(file_path);
-        let canonical_path = path
-            .canonicalize()
-            .map_err(|e| ServiceError::Internal(format!("Failed to canonicalize path: {e}")))?;
+/// Sort files by path and cut out the page after `cursor`, returning
+/// (page, next_cursor, files_on_page, glob_counts)
+fn paginate(
+    mut files: Vec<(String, u64)>,
+    cursor: Option<CursorParam>,
+    max_results: usize,
+    counts: GlobCounts,
+) -> (Vec<(String, u64)>, Option<CursorResult>, usize, GlobCounts) {
+    files.sort_by(|a, b| a.0.cmp(&b.0));
 
-        for root in &self.config.root_directories {
-            if let Ok(canonical_root) = root.canonicalize() {
-                if canonical_path.starts_with(&canonical_root) {
-                    return Ok(());
-                }
-            }
+    // Apply cursor filtering and max_results limit
+    let cursor_filter = cursor.as_ref().map(|c| c.last_file_path.clone());
+    let mut paginated_files: Vec<(String, u64)> = Vec::new();
+
+    for (path_str, file_size) in files {
+        if let Some(ref start_path) = cursor_filter
+            && path_str.as_str() <= start_path.as_str()
+        {
+            continue;
         }
 
-        Err(ServiceError::Internal(
-            "File is outside allowed directories".to_string(),
-        ))
+        if paginated_files.len() >= max_results {
+            // We've reached the limit for this page; the next page resumes after the
+            // last file on this one
+            let next_cursor = Some(CursorResult {
+                last_file_path: paginated_files
+                    .last()
+                    .map(|(path, _)| path.clone())
+                    .unwrap_or_default(),
+                is_complete: false,
+                token: None,
+            });
+            let files_count = paginated_files.len();
+            return (paginated_files, next_cursor, files_count, counts);
+        }
+
+        paginated_files.push((path_str, file_size));
     }
+
+    // If we reached here, all matching files have been processed
+    let files_count = paginated_files.len();
+    (
+        paginated_files,
+        Some(CursorResult {
+            last_file_path: String::new(),
+            is_complete: true,
+            token: None,
+        }),
+        files_count,
+        counts,
+    )
 }
 
+/// Read a file listed by `find_revision_files` from the commit resolved for the innermost root
+/// containing it
+/// A `batch_search` search that passed [`SearchService::prepare_batch_entry`]
+struct BatchEntry {
+    id: String,
+    lang: Language,
+    glob: GlobMatcher,
+    /// Whether the glob is matched against the path below the root rather than the file name
+    match_full_path: bool,
+    pattern: String,
+    selector: Option<String>,
+    context: Option<String>,
+    max_results: usize,
+}
+
+/// The matches of the `batch_search` searches that apply to one file
+struct BatchFile {
+    file_path: String,
+    size: u64,
+    file_hash: String,
+    /// Index of each search in the entries with its matches
+    matches: Vec<(usize, Result<Vec<MatchResult>, ServiceError>)>,
+}
+
+/// Run the searches at `searches` over one file, parsing it once per language they need
+fn search_batch_file(
+    pattern_matcher: &PatternMatcher,
+    entries: &[BatchEntry],
+    file_path: String,
+    searches: &[usize],
+) -> Result<BatchFile, SkippedFile> {
+    let content = match std::fs::read_to_string(&file_path) {
+        Ok(content) => content,
+        Err(e) => {
+            return Err(SkippedFile {
+                file_path,
+                reason: e.to_string(),
+            });
+        }
+    };
+    let mut parsed: Vec<(Language, AstGrep<StrDoc<Language>>)> = Vec::new();
+    let mut matches = Vec::with_capacity(searches.len());
+    for &index in searches {
+        let entry = &entries[index];
+        let lang = language_for_file(entry.lang, &file_path);
+        let ast = match parsed
+            .iter()
+            .position(|(parsed_lang, _)| *parsed_lang == lang)
+        {
+            Some(position) => &parsed[position].1,
+            None => {
+                parsed.push((lang, AstGrep::new(&content, lang)));
+                &parsed[parsed.len() - 1].1
+            }
+        };
+        let found = pattern_matcher.search_parsed(
+            ast,
+            &entry.pattern,
+            lang,
+            entry.selector.as_deref(),
+            entry.context.as_deref(),
+            false,
+        );
+        matches.push((index, found));
+    }
+    Ok(BatchFile {
+        size: content.len() as u64,
+        file_hash: content_hash(&content),
+        file_path,
+        matches,
+    })
+}
+
+fn read_revision_file(
+    commits: &[(PathBuf, String)],
+    file_path: &str,
+) -> Result<String, ServiceError> {
+    let path = std::path::Path::new(file_path);
+    let (root, commit, relative) = commits
+        .iter()
+        .filter_map(|(root, commit)| Some((root, commit, path.strip_prefix(root).ok()?)))
+        .max_by_key(|(root, _, _)| root.as_os_str().len())
+        .ok_or_else(|| ServiceError::FileNotFound(path.to_path_buf()))?;
+    read_file_at(root, commit, &relative.to_string_lossy())
+}
+
+fn validate_file_under_roots(file_path: &str, roots: &[PathBuf]) -> Result<(), ServiceError> {
+    let path = std::path::Path::new(file_path);
+    let canonical_path = path
+        .canonicalize()
+        .map_err(|e| ServiceError::Internal(format!("Failed to canonicalize path: {e}")))?;
+
+    for root in roots {
+        if let Ok(canonical_root) = root.canonicalize()
+            && canonical_path.starts_with(&canonical_root)
+        {
+            return Ok(());
+        }
+    }
+
+    Err(ServiceError::Internal(
+        "File is outside allowed directories".to_string(),
+    ))
+}
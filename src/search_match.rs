@@ -18,7 +18,7 @@ pub enum SearchMatch<'a> {
     /// A native ast-grep NodeMatch with full AST access
     Native(NodeMatch<'a, StrDoc<Language>>),
     /// A converted, serializable match result
-    Converted(MatchResult),
+    Converted(Box<MatchResult>),
 }
 
 // Manual Debug implementation since NodeMatch doesn't implement Debug
@@ -50,7 +50,7 @@ impl<'a> SearchMatch<'a> {
     pub fn to_match_result(&self) -> MatchResult {
         match self {
             SearchMatch::Native(n) => MatchResult::from_node_match(n),
-            SearchMatch::Converted(m) => m.clone(),
+            SearchMatch::Converted(m) => m.as_ref().clone(),
         }
     }
 
@@ -63,7 +63,7 @@ impl<'a> SearchMatch<'a> {
     }
 
     /// Get the matched text
-    pub fn text(&self) -> Cow<str> {
+    pub fn text(&self) -> Cow<'_, str> {
         match self {
             SearchMatch::Native(n) => {
                 let text = n.get_node().text();
@@ -114,7 +114,7 @@ impl<'a> SearchMatch<'a> {
     }
 
     /// Get the node kind if this is a native match
-    pub fn kind(&self) -> Option<Cow<str>> {
+    pub fn kind(&self) -> Option<Cow<'_, str>> {
         self.get_node().map(|n| n.kind())
     }
 
@@ -204,9 +204,15 @@ mod tests {
             vars: HashMap::new(),
             context_before: None,
             context_after: None,
+            enclosing_symbol: None,
+            ast_context: None,
+            quick_fixes: Vec::new(),
+            enclosing_node: None,
+            leading_trivia: Vec::new(),
+            rule_id: None,
         };
 
-        let search_match = SearchMatch::Converted(match_result.clone());
+        let search_match = SearchMatch::Converted(Box::new(match_result.clone()));
 
         assert_eq!(search_match.text(), "test");
         assert_eq!(search_match.start_line(), 1);
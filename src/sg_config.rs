@@ -66,6 +66,10 @@ pub struct CustomLanguage {
     /// Character to use instead of $ for metavariables
     #[serde(skip_serializing_if = "Option::is_none")]
     pub expando_char: Option<char>,
+
+    /// Language function the library exports; `tree_sitter_<name>` when not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language_symbol: Option<String>,
 }
 
 impl SgConfig {
@@ -208,6 +212,7 @@ customLanguages:
     libraryPath: ./mylang.so
     extensions: [ml, mli]
     expandoChar: _
+    languageSymbol: tree_sitter_ocaml
 "#;
 
         let config = SgConfig::from_yaml(yaml).unwrap();
@@ -219,6 +224,7 @@ customLanguages:
         let mylang = &config.custom_languages.languages["mylang"];
         assert_eq!(mylang.extensions, vec!["ml", "mli"]);
         assert_eq!(mylang.expando_char, Some('_'));
+        assert_eq!(mylang.language_symbol.as_deref(), Some("tree_sitter_ocaml"));
     }
 
     #[test]
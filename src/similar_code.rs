@@ -0,0 +1,196 @@
+//! # Similar Code
+//!
+//! Finds code shaped like an example snippet. The snippet is turned into a pattern by replacing
+//! every identifier and literal with the anonymous wildcard `$_`, so the pattern keeps only the
+//! snippet's structure: its keywords, operators and nesting. Every match of that pattern is then
+//! scored by how many of the snippet's tokens it shares, in order, so copies that kept the
+//! original names rank above code that only does the same dance with other names.
+
+use crate::directory_summary::source_files;
+use crate::types::{FindSimilarCodeParam, FindSimilarCodeResult, SimilarCode, SkippedFile};
+use ast_grep_core::tree_sitter::{LanguageExt, StrDoc};
+use ast_grep_core::{Node, Pattern};
+use ast_grep_language::SupportLang as Language;
+use std::path::PathBuf;
+
+/// Literal kinds that don't end in `_literal`
+const LITERAL_KINDS: &[&str] = &[
+    "string",
+    "template_string",
+    "concatenated_string",
+    "regex",
+    "number",
+    "integer",
+    "float",
+    "true",
+    "false",
+    "null",
+    "none",
+];
+
+/// The pattern `find_similar_code` searches with for `code`: the snippet with its comments
+/// removed and its identifiers and literals replaced by `$_`. Fails when the snippet doesn't
+/// parse or isn't a single statement, expression or declaration
+pub fn derive_pattern(code: &str, lang: Language) -> Result<String, String> {
+    let ast = lang.ast_grep(code);
+    let root = ast.root();
+    if root.dfs().any(|node| node.is_error()) {
+        return Err(format!("The snippet doesn't parse as {lang}"));
+    }
+    let items = root
+        .children()
+        .filter(|node| node.is_named() && !is_comment(node))
+        .count();
+    if items != 1 {
+        return Err(format!(
+            "The snippet must be a single statement, expression or declaration, found {items}; \
+             wrap several statements in their enclosing function or block"
+        ));
+    }
+
+    let mut replacements = Vec::new();
+    collect_replacements(&root, &mut replacements);
+    let mut pattern = String::with_capacity(code.len());
+    let mut end = 0;
+    for (range, replacement) in replacements {
+        pattern.push_str(&code[end..range.start]);
+        pattern.push_str(replacement);
+        end = range.end;
+    }
+    pattern.push_str(&code[end..]);
+    Ok(pattern.trim().to_string())
+}
+
+/// Code under `directories` written in `lang` that matches the snippet's pattern, most similar
+/// first
+pub fn find_similar_code(
+    directories: &[PathBuf],
+    param: &FindSimilarCodeParam,
+    lang: Language,
+) -> Result<FindSimilarCodeResult, String> {
+    let pattern_text = derive_pattern(&param.code, lang)?;
+    let pattern = Pattern::try_new(&pattern_text, lang)
+        .map_err(|e| format!("The derived pattern '{pattern_text}' is invalid: {e}"))?;
+    let snippet_ast = lang.ast_grep(&param.code);
+    let snippet_tokens = tokens(&snippet_ast.root());
+
+    let mut matches = Vec::new();
+    let mut files_searched = 0;
+    let mut skipped_files = Vec::new();
+    for (path, file_lang) in source_files(directories) {
+        if file_lang != lang {
+            continue;
+        }
+        let file_path = path.to_string_lossy().to_string();
+        let too_large = std::fs::metadata(&path)
+            .map(|metadata| metadata.len() > param.max_file_size)
+            .unwrap_or(false);
+        if too_large {
+            skipped_files.push(SkippedFile {
+                file_path,
+                reason: format!("larger than {} bytes", param.max_file_size),
+            });
+            continue;
+        }
+        let code = match std::fs::read_to_string(&path) {
+            Ok(code) => code,
+            Err(e) => {
+                skipped_files.push(SkippedFile {
+                    file_path,
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        files_searched += 1;
+        let ast = lang.ast_grep(&code);
+        for node_match in ast.root().find_all(&pattern) {
+            let node = node_match.get_node();
+            let score = similarity(&snippet_tokens, &tokens(node));
+            if score < param.min_score {
+                continue;
+            }
+            matches.push(SimilarCode {
+                file_path: file_path.clone(),
+                start_line: node.start_pos().line(),
+                end_line: node.end_pos().line(),
+                score,
+                text: node.text().to_string(),
+            });
+        }
+    }
+
+    // Files are visited in path order, so ties stay in source order
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let total_matches = matches.len();
+    let truncated = total_matches > param.max_results;
+    matches.truncate(param.max_results);
+    Ok(FindSimilarCodeResult {
+        pattern: pattern_text,
+        matches,
+        total_matches,
+        files_searched,
+        truncated,
+        skipped_files,
+    })
+}
+
+/// Byte ranges of `node`'s descendants to replace, in source order: comments by nothing,
+/// identifiers and literals by `$_`
+fn collect_replacements(
+    node: &Node<StrDoc<Language>>,
+    replacements: &mut Vec<(std::ops::Range<usize>, &'static str)>,
+) {
+    for child in node.children() {
+        if is_comment(&child) {
+            replacements.push((child.range(), ""));
+        } else if is_identifier(&child) || is_literal(&child) {
+            replacements.push((child.range(), "$_"));
+        } else {
+            collect_replacements(&child, replacements);
+        }
+    }
+}
+
+pub(crate) fn is_comment(node: &Node<StrDoc<Language>>) -> bool {
+    node.kind().contains("comment")
+}
+
+pub(crate) fn is_identifier(node: &Node<StrDoc<Language>>) -> bool {
+    node.is_named() && node.kind().ends_with("identifier")
+}
+
+pub(crate) fn is_literal(node: &Node<StrDoc<Language>>) -> bool {
+    let kind = node.kind();
+    node.is_named() && (kind.ends_with("_literal") || LITERAL_KINDS.contains(&kind.as_ref()))
+}
+
+/// The leaf texts of `node`, comments left out
+pub(crate) fn tokens(node: &Node<StrDoc<Language>>) -> Vec<String> {
+    node.dfs()
+        .filter(|leaf| leaf.is_leaf() && !is_comment(leaf) && !leaf.text().is_empty())
+        .map(|leaf| leaf.text().to_string())
+        .collect()
+}
+
+/// Share of the tokens two token sequences have in common, in order, from 0 to 1
+pub(crate) fn similarity(a: &[String], b: &[String]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    // Longest common subsequence, one row at a time
+    let mut previous = vec![0usize; b.len() + 1];
+    let mut current = vec![0usize; b.len() + 1];
+    for token in a {
+        for (j, other) in b.iter().enumerate() {
+            current[j + 1] = if token == other {
+                previous[j] + 1
+            } else {
+                previous[j + 1].max(current[j])
+            };
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    2.0 * previous[b.len()] as f64 / (a.len() + b.len()) as f64
+}
@@ -0,0 +1,320 @@
+//! # Symbol Search by Similarity
+//!
+//! Experimental search for declarations whose name is only half remembered. Every function,
+//! method, class and other declaration under a directory is indexed once, cached the same way
+//! as directory summaries, and compared with the query in increasingly loose ways:
+//!
+//! - **exact**: the same name
+//! - **case**: the same name ignoring case (`getuser` finds `getUser`)
+//! - **words**: the same words in another naming style (`get_user_name` finds `getUserName`)
+//! - **partial**: every word of the query appears in the name (`user name` finds
+//!   `formatUserDisplayName`)
+//! - **fuzzy**: a small edit distance between the words (`recieve` finds `receive`)
+//!
+//! Matches are ranked by a score between 0 and 1 that reflects how loose the comparison was.
+
+use crate::directory_summary::source_files;
+use crate::doc_coverage::{is_documented, is_public};
+use crate::enclosing_symbol::{TYPE_KINDS, enclosing_symbol, symbol_kind, symbol_name};
+use crate::types::{SkippedFile, SymbolMatch, SymbolSearchParam, SymbolSearchResult};
+use ast_grep_core::AstGrep;
+use ast_grep_language::SupportLang as Language;
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Number of files whose declarations are kept between calls
+const CACHE_CAPACITY: usize = 20_000;
+
+/// A named declaration found while indexing a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeclaredSymbol {
+    pub name: String,
+    pub qualified_name: String,
+    pub kind: String,
+    pub start_line: usize,
+    /// Whether the declaration is part of its module's public API, in the languages
+    /// `doc_coverage` audits
+    pub public: bool,
+    /// Whether a doc comment or docstring documents it, in those same languages
+    pub documented: bool,
+}
+
+/// Every named declaration in `code`, in source order
+pub fn declared_symbols(code: &str, lang: Language) -> Vec<DeclaredSymbol> {
+    let ast = AstGrep::new(code, lang);
+    ast.root()
+        .dfs()
+        .filter_map(|node| {
+            let kind = symbol_kind(&node.kind())?;
+            let name = symbol_name(&node)?;
+            let outer = enclosing_symbol(&node);
+            let in_type = outer
+                .as_ref()
+                .is_some_and(|outer| TYPE_KINDS.contains(&outer.kind.as_str()));
+            Some(DeclaredSymbol {
+                qualified_name: match outer {
+                    Some(outer) => format!("{}.{name}", outer.name),
+                    None => name.clone(),
+                },
+                name,
+                kind: if in_type && kind == "function" {
+                    "method"
+                } else {
+                    kind
+                }
+                .to_string(),
+                start_line: node.start_pos().line(),
+                public: is_public(&node, lang),
+                documented: is_documented(&node, lang),
+            })
+        })
+        .collect()
+}
+
+/// Split an identifier into lowercase words at separators and case changes, so
+/// `parseHTTPResponse`, `parse_http_response` and `ParseHttpResponse` give the same words
+pub fn split_words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        let boundary = i > 0 && c.is_uppercase() && {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower)
+        };
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// How closely `name` resembles `query`, from 0 to 1, and which comparison matched
+pub fn similarity(query: &str, name: &str) -> Option<(f64, &'static str)> {
+    if query == name {
+        return Some((1.0, "exact"));
+    }
+    if query.to_lowercase() == name.to_lowercase() {
+        return Some((0.95, "case"));
+    }
+    let query_words = split_words(query);
+    let name_words = split_words(name);
+    if query_words.is_empty() || name_words.is_empty() {
+        return None;
+    }
+    let (query_joined, name_joined) = (query_words.concat(), name_words.concat());
+    if query_joined == name_joined {
+        return Some((0.9, "words"));
+    }
+
+    // Shorter names covering the query are closer to what was meant
+    let partial = query_words
+        .iter()
+        .all(|word| name_words.contains(word))
+        .then(|| 0.6 + 0.25 * query_words.len() as f64 / name_words.len() as f64);
+    let longest = query_joined
+        .chars()
+        .count()
+        .max(name_joined.chars().count());
+    let fuzzy = 0.85 * (1.0 - edit_distance(&query_joined, &name_joined) as f64 / longest as f64);
+
+    match partial {
+        Some(partial) if partial >= fuzzy => Some((partial, "partial")),
+        _ => Some((fuzzy, "fuzzy")),
+    }
+}
+
+/// Levenshtein distance between `a` and `b`, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Index the declarations under `directories` and rank those resembling `param.query`
+pub fn search_symbols(
+    index: &SymbolIndex,
+    directories: &[PathBuf],
+    param: &SymbolSearchParam,
+) -> SymbolSearchResult {
+    let mut paths = source_files(directories);
+    let truncated = paths.len() > param.max_files;
+    paths.truncate(param.max_files);
+
+    let mut result = SymbolSearchResult {
+        matches: Vec::new(),
+        symbols_indexed: 0,
+        files_indexed: 0,
+        cached_files: 0,
+        truncated,
+        skipped_files: Vec::new(),
+    };
+
+    for (path, lang) in paths {
+        let file_path = path.to_string_lossy().to_string();
+        let too_large = std::fs::metadata(&path)
+            .map(|metadata| metadata.len() > param.max_file_size)
+            .unwrap_or(false);
+        if too_large {
+            result.skipped_files.push(SkippedFile {
+                file_path,
+                reason: format!("larger than {} bytes", param.max_file_size),
+            });
+            continue;
+        }
+        let (symbols, cached) = match index.symbols(&path, lang) {
+            Ok(indexed) => indexed,
+            Err(e) => {
+                result.skipped_files.push(SkippedFile {
+                    file_path,
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        result.files_indexed += 1;
+        result.cached_files += usize::from(cached);
+        result.symbols_indexed += symbols.len();
+        for symbol in symbols.iter() {
+            if !param.kinds.is_empty() && !param.kinds.contains(&symbol.kind) {
+                continue;
+            }
+            let Some((score, match_kind)) = similarity(&param.query, &symbol.name) else {
+                continue;
+            };
+            if score < param.min_score {
+                continue;
+            }
+            result.matches.push(SymbolMatch {
+                name: symbol.name.clone(),
+                qualified_name: symbol.qualified_name.clone(),
+                kind: symbol.kind.clone(),
+                file_path: file_path.clone(),
+                start_line: symbol.start_line,
+                score: (score * 1000.0).round() / 1000.0,
+                match_kind: match_kind.to_string(),
+            });
+        }
+    }
+
+    result.matches.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.name.len().cmp(&b.name.len()))
+            .then_with(|| a.file_path.cmp(&b.file_path))
+            .then_with(|| a.start_line.cmp(&b.start_line))
+    });
+    result.matches.truncate(param.max_results);
+    result
+}
+
+#[derive(Clone)]
+struct CachedSymbols {
+    modified: SystemTime,
+    len: u64,
+    symbols: Arc<Vec<DeclaredSymbol>>,
+}
+
+/// Declarations keyed by file path, shared between clones of the service
+#[derive(Clone)]
+pub struct SymbolIndex {
+    entries: Arc<Mutex<LruCache<PathBuf, CachedSymbols>>>,
+}
+
+impl Default for SymbolIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SymbolIndex {
+    pub fn new() -> Self {
+        let capacity = NonZeroUsize::new(CACHE_CAPACITY).unwrap();
+        Self {
+            entries: Arc::new(Mutex::new(LruCache::new(capacity))),
+        }
+    }
+
+    /// The declarations in the file at `path`, and whether they came from the cache
+    pub fn symbols(
+        &self,
+        path: &Path,
+        lang: Language,
+    ) -> std::io::Result<(Arc<Vec<DeclaredSymbol>>, bool)> {
+        let metadata = std::fs::metadata(path)?;
+        let modified = metadata.modified()?;
+        if let Some(cached) = self.entries.lock().unwrap().get(path)
+            && cached.modified == modified
+            && cached.len == metadata.len()
+        {
+            return Ok((cached.symbols.clone(), true));
+        }
+
+        let code = std::fs::read_to_string(path)?;
+        let symbols = Arc::new(declared_symbols(&code, lang));
+        self.entries.lock().unwrap().put(
+            path.to_path_buf(),
+            CachedSymbols {
+                modified,
+                len: metadata.len(),
+                symbols: symbols.clone(),
+            },
+        );
+        Ok((symbols, false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_words_across_naming_styles() {
+        for name in [
+            "parseHTTPResponse",
+            "parse_http_response",
+            "ParseHttpResponse",
+        ] {
+            assert_eq!(split_words(name), ["parse", "http", "response"], "{name}");
+        }
+        assert_eq!(split_words("utf8Decode"), ["utf8", "decode"]);
+    }
+
+    #[test]
+    fn test_similarity_ranks_looser_comparisons_lower() {
+        assert_eq!(similarity("getUser", "getUser"), Some((1.0, "exact")));
+        assert_eq!(similarity("getuser", "getUser"), Some((0.95, "case")));
+        assert_eq!(similarity("get_user", "getUser"), Some((0.9, "words")));
+
+        let (partial, kind) = similarity("user name", "formatUserDisplayName").unwrap();
+        assert_eq!(kind, "partial");
+        let (fuzzy, kind) = similarity("recieve", "receive").unwrap();
+        assert_eq!(kind, "fuzzy");
+        assert!(partial < 0.9 && fuzzy < 0.9);
+        assert!(similarity("recieve", "transmit").unwrap().0 < 0.6);
+    }
+}
@@ -0,0 +1,257 @@
+//! # Telemetry
+//!
+//! Every tool call runs inside a `tool_call` span. When the call finishes, [`record_tool_call`]
+//! adds its outcome, duration and the number of files it touched to that span, so any tracing
+//! subscriber can see them.
+//!
+//! With the `otel` cargo feature, `otel::layer` also exports these spans over OTLP. It
+//! publishes two per-tool metrics:
+//! - `ast_grep_mcp.tool.duration`, a histogram in seconds
+//! - `ast_grep_mcp.tool.files`, a histogram of files touched
+//!
+//! Configuration uses the standard `OTEL_*` environment variables.
+
+use rmcp::model::{CallToolResult, ErrorData};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Record the result of a tool call on the current `tool_call` span and, with the `otel`
+/// feature, in the exported metrics
+pub fn record_tool_call(
+    tool_name: &str,
+    result: &Result<CallToolResult, ErrorData>,
+    elapsed: Duration,
+) {
+    let outcome = outcome(result);
+    let files = result.as_ref().ok().and_then(files_touched);
+
+    let span = tracing::Span::current();
+    span.record("outcome", outcome);
+    span.record("duration_ms", elapsed.as_millis() as u64);
+    if let Some(files) = files {
+        span.record("files", files as u64);
+    }
+
+    #[cfg(feature = "otel")]
+    otel::record(tool_name, outcome, files, elapsed);
+    #[cfg(not(feature = "otel"))]
+    let _ = tool_name;
+}
+
+/// `ok`, `error` for calls rejected with an MCP error, or `tool_error` for results flagged as
+/// errors
+fn outcome(result: &Result<CallToolResult, ErrorData>) -> &'static str {
+    match result {
+        Ok(result) if result.is_error == Some(true) => "tool_error",
+        Ok(_) => "ok",
+        Err(_) => "error",
+    }
+}
+
+/// Number of files a tool reported on, read from the JSON part of its response
+fn files_touched(result: &CallToolResult) -> Option<usize> {
+    result
+        .content
+        .iter()
+        .filter_map(|content| content.as_text())
+        .filter(|text| text.text.starts_with('{'))
+        .filter_map(|text| serde_json::from_str::<Value>(&text.text).ok())
+        .find_map(|json| file_count(&json))
+}
+
+fn file_count(json: &Value) -> Option<usize> {
+    if let Some(total) = json.get("total_files_found").and_then(Value::as_u64) {
+        return Some(total as usize);
+    }
+    ["files", "file_results"]
+        .iter()
+        .find_map(|key| json.get(*key).and_then(Value::as_array))
+        .map(Vec::len)
+}
+
+#[cfg(feature = "otel")]
+pub mod otel {
+    //! OTLP export of tool call spans and metrics
+    //!
+    //! Export starts only when an endpoint is configured through `OTEL_EXPORTER_OTLP_ENDPOINT`
+    //! or one of its per-signal variants. `OTEL_EXPORTER_OTLP_PROTOCOL=grpc` switches from
+    //! HTTP/protobuf to gRPC. `OTEL_SERVICE_NAME` and `OTEL_RESOURCE_ATTRIBUTES` label the
+    //! process, and `OTEL_SDK_DISABLED=true` turns export off.
+
+    use opentelemetry::metrics::Histogram;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::{KeyValue, global};
+    use opentelemetry_otlp::{MetricExporter, Protocol, SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::Resource;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use std::env;
+    use std::sync::OnceLock;
+    use std::time::Duration;
+    use tracing::Subscriber;
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::{EnvFilter, Layer};
+
+    const SERVICE_NAME: &str = "ast-grep-mcp";
+
+    /// Spans from this crate only; the exporter's own HTTP and gRPC clients are left out so
+    /// exporting never feeds back into itself
+    const SPAN_FILTER: &str = "ast_grep_mcp=info";
+
+    struct ToolMetrics {
+        duration: Histogram<f64>,
+        files: Histogram<u64>,
+    }
+
+    static METRICS: OnceLock<ToolMetrics> = OnceLock::new();
+
+    /// Flushes and shuts down the exporters when dropped. Keep it alive for the life of the
+    /// process.
+    pub struct TelemetryGuard {
+        tracer_provider: SdkTracerProvider,
+        meter_provider: SdkMeterProvider,
+    }
+
+    impl Drop for TelemetryGuard {
+        fn drop(&mut self) {
+            if let Err(e) = self.tracer_provider.shutdown() {
+                eprintln!("Failed to flush OpenTelemetry traces: {e}");
+            }
+            if let Err(e) = self.meter_provider.shutdown() {
+                eprintln!("Failed to flush OpenTelemetry metrics: {e}");
+            }
+        }
+    }
+
+    /// Whether the environment asks for OTLP export
+    pub fn enabled() -> bool {
+        let disabled = env::var("OTEL_SDK_DISABLED").is_ok_and(|v| v.eq_ignore_ascii_case("true"));
+        let configured = [
+            "OTEL_EXPORTER_OTLP_ENDPOINT",
+            "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+            "OTEL_EXPORTER_OTLP_METRICS_ENDPOINT",
+        ]
+        .iter()
+        .any(|var| env::var_os(var).is_some_and(|value| !value.is_empty()));
+        !disabled && configured
+    }
+
+    /// Build the OTLP exporters and a tracing layer feeding them, or `None` when export isn't
+    /// configured. Must be called inside a Tokio runtime.
+    pub fn layer<S>() -> Result<Option<(impl Layer<S>, TelemetryGuard)>, Box<dyn std::error::Error>>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        if !enabled() {
+            return Ok(None);
+        }
+
+        let grpc = env::var("OTEL_EXPORTER_OTLP_PROTOCOL").is_ok_and(|p| p == "grpc");
+        let (span_exporter, metric_exporter) = if grpc {
+            (
+                SpanExporter::builder().with_tonic().build()?,
+                MetricExporter::builder().with_tonic().build()?,
+            )
+        } else {
+            (
+                SpanExporter::builder()
+                    .with_http()
+                    .with_protocol(Protocol::HttpBinary)
+                    .build()?,
+                MetricExporter::builder()
+                    .with_http()
+                    .with_protocol(Protocol::HttpBinary)
+                    .build()?,
+            )
+        };
+
+        let resource = resource();
+        let tracer_provider = SdkTracerProvider::builder()
+            .with_batch_exporter(span_exporter)
+            .with_resource(resource.clone())
+            .build();
+        let meter_provider = SdkMeterProvider::builder()
+            .with_periodic_exporter(metric_exporter)
+            .with_resource(resource)
+            .build();
+        global::set_meter_provider(meter_provider.clone());
+
+        let layer = tracing_opentelemetry::layer()
+            .with_tracer(tracer_provider.tracer(SERVICE_NAME))
+            .with_filter(EnvFilter::new(SPAN_FILTER));
+        let guard = TelemetryGuard {
+            tracer_provider,
+            meter_provider,
+        };
+        Ok(Some((layer, guard)))
+    }
+
+    fn resource() -> Resource {
+        // Resource::builder reads OTEL_SERVICE_NAME and OTEL_RESOURCE_ATTRIBUTES; only fill in a
+        // name when neither sets one
+        let builder = Resource::builder();
+        let named = env::var_os("OTEL_SERVICE_NAME").is_some()
+            || env::var("OTEL_RESOURCE_ATTRIBUTES").is_ok_and(|a| a.contains("service.name="));
+        if named {
+            builder.build()
+        } else {
+            builder.with_service_name(SERVICE_NAME).build()
+        }
+    }
+
+    pub(super) fn record(tool_name: &str, outcome: &str, files: Option<usize>, elapsed: Duration) {
+        let metrics = METRICS.get_or_init(|| {
+            let meter = global::meter(SERVICE_NAME);
+            ToolMetrics {
+                duration: meter
+                    .f64_histogram("ast_grep_mcp.tool.duration")
+                    .with_unit("s")
+                    .with_description("Duration of MCP tool calls")
+                    .build(),
+                files: meter
+                    .u64_histogram("ast_grep_mcp.tool.files")
+                    .with_unit("{file}")
+                    .with_description("Files touched by MCP tool calls")
+                    .build(),
+            }
+        });
+        let attributes = [
+            KeyValue::new("tool", tool_name.to_string()),
+            KeyValue::new("outcome", outcome.to_string()),
+        ];
+        metrics.duration.record(elapsed.as_secs_f64(), &attributes);
+        if let Some(files) = files {
+            metrics.files.record(files as u64, &attributes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::Content;
+    use serde_json::json;
+
+    #[test]
+    fn test_outcome_and_file_count() {
+        let result = CallToolResult::success(vec![
+            Content::text("summary"),
+            Content::json(json!({"matches": [], "total_files_found": 7})).unwrap(),
+        ]);
+        assert_eq!(outcome(&Ok(result.clone())), "ok");
+        assert_eq!(files_touched(&result), Some(7));
+
+        let summary = CallToolResult::success(vec![
+            Content::json(json!({"files": [{}, {}], "total_lines_of_code": 10})).unwrap(),
+        ]);
+        assert_eq!(files_touched(&summary), Some(2));
+
+        let failed = CallToolResult::error(vec![Content::text("boom")]);
+        assert_eq!(outcome(&Ok(failed.clone())), "tool_error");
+        assert_eq!(files_touched(&failed), None);
+        assert_eq!(
+            outcome(&Err(ErrorData::invalid_params("bad", None))),
+            "error"
+        );
+    }
+}
@@ -0,0 +1,375 @@
+//! # Replacement Templates
+//!
+//! Replacement strings and rule `fix` fields are ast-grep templates: `$VAR` and `$$$VARS` are
+//! replaced with the captured code. On top of that, a template can call a built-in function on
+//! a captured variable to compute the inserted text:
+//!
+//! | Call                               | `getUserName` becomes |
+//! |------------------------------------|-----------------------|
+//! | `${upper($NAME)}`                  | `GETUSERNAME`         |
+//! | `${lower($NAME)}`                  | `getusername`         |
+//! | `${snake_case($NAME)}`             | `get_user_name`       |
+//! | `${camelCase($NAME)}`              | `getUserName`         |
+//! | `${strip_prefix($NAME, "get")}`    | `UserName`            |
+//!
+//! `${...}` with any other content is left as written, so JavaScript template literals such as
+//! `` `${name}` `` keep working.
+
+use crate::errors::ServiceError;
+use ast_grep_core::meta_var::Underlying;
+use ast_grep_core::replacer::{Content, Replacer};
+use ast_grep_core::{Doc, NodeMatch};
+use std::collections::HashMap;
+
+/// A function applied to a captured variable inside `${...}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateFunction {
+    Upper,
+    Lower,
+    SnakeCase,
+    CamelCase,
+    /// Removes the prefix if the text starts with it
+    StripPrefix(String),
+}
+
+impl TemplateFunction {
+    /// Names accepted in templates
+    pub const NAMES: &'static [&'static str] =
+        &["upper", "lower", "snake_case", "camelCase", "strip_prefix"];
+
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            TemplateFunction::Upper => text.to_uppercase(),
+            TemplateFunction::Lower => text.to_lowercase(),
+            TemplateFunction::SnakeCase => words(text)
+                .iter()
+                .map(|word| word.to_lowercase())
+                .collect::<Vec<_>>()
+                .join("_"),
+            TemplateFunction::CamelCase => words(text)
+                .iter()
+                .enumerate()
+                .map(|(i, word)| match i {
+                    0 => word.to_lowercase(),
+                    _ => capitalize(word),
+                })
+                .collect(),
+            TemplateFunction::StripPrefix(prefix) => text
+                .strip_prefix(prefix.as_str())
+                .unwrap_or(text)
+                .to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// Plain template text, which may still contain `$VAR` references
+    Text(String),
+    Call {
+        function: TemplateFunction,
+        var: String,
+        multi: bool,
+    },
+}
+
+/// A replacement template with its function calls parsed out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplacementTemplate {
+    segments: Vec<Segment>,
+}
+
+impl ReplacementTemplate {
+    pub fn parse(template: &str) -> Result<Self, ServiceError> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("${") {
+            text.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            match parse_call(after)? {
+                Some((segment, consumed)) => {
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(segment);
+                    rest = &after[consumed..];
+                }
+                None => {
+                    text.push_str("${");
+                    rest = after;
+                }
+            }
+        }
+        text.push_str(rest);
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Whether the template calls any function
+    pub fn has_functions(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::Call { .. }))
+    }
+
+    /// Render the template from captured variables, as reported in `MatchResult::vars`
+    pub fn render(&self, vars: &HashMap<String, String>) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => substitute_vars(text, vars),
+                Segment::Call {
+                    function,
+                    var,
+                    multi,
+                } => function.apply(&captured_text(vars, var, *multi)),
+            })
+            .collect()
+    }
+}
+
+impl<D: Doc> Replacer<D> for ReplacementTemplate {
+    fn generate_replacement(&self, nm: &NodeMatch<'_, D>) -> Underlying<D> {
+        let mut replacement = Vec::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => {
+                    replacement.extend(Replacer::<D>::generate_replacement(text.as_str(), nm))
+                }
+                Segment::Call {
+                    function,
+                    var,
+                    multi,
+                } => {
+                    let env = nm.get_env();
+                    let captured = match multi {
+                        false => env
+                            .get_match(var)
+                            .map(|node| node.text().to_string())
+                            .unwrap_or_default(),
+                        true => {
+                            let nodes = env.get_multiple_matches(var);
+                            match (nodes.first(), nodes.last()) {
+                                (Some(first), Some(last)) => {
+                                    let source = first.get_doc().get_source();
+                                    let bytes =
+                                        source.get_range(first.range().start..last.range().end);
+                                    <D::Source as Content>::encode_bytes(bytes).into_owned()
+                                }
+                                _ => String::new(),
+                            }
+                        }
+                    };
+                    let computed = function.apply(&captured);
+                    replacement.extend_from_slice(&<D::Source as Content>::decode_str(&computed));
+                }
+            }
+        }
+        replacement
+    }
+}
+
+/// Parse `name($VAR)` or `name($VAR, "arg")}` at the start of `text`, returning the call and
+/// the number of bytes it used. Returns `None` when `text` is not a call to a known function
+fn parse_call(text: &str) -> Result<Option<(Segment, usize)>, ServiceError> {
+    let name_len = text
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    let name = &text[..name_len];
+    if !TemplateFunction::NAMES.contains(&name) || !text[name_len..].starts_with('(') {
+        return Ok(None);
+    }
+    let invalid = |reason: &str| {
+        ServiceError::ParserError(format!(
+            "Invalid template call `${{{name}(...)}}`: {reason}"
+        ))
+    };
+
+    let close = text
+        .find(")}")
+        .ok_or_else(|| invalid("expected `)}` after the arguments"))?;
+    let args = &text[name_len + 1..close];
+    let (var_arg, extra_arg) = match args.split_once(',') {
+        Some((var_arg, extra_arg)) => (var_arg.trim(), Some(extra_arg.trim())),
+        None => (args.trim(), None),
+    };
+
+    let (var, multi) = match var_arg.strip_prefix("$$$") {
+        Some(var) => (var, true),
+        None => (var_arg.strip_prefix('$').unwrap_or_default(), false),
+    };
+    if var.is_empty() || !var.chars().all(is_meta_var_char) {
+        return Err(invalid(
+            "the first argument must be a metavariable like $NAME",
+        ));
+    }
+
+    let function = match (name, extra_arg) {
+        ("strip_prefix", Some(arg)) => TemplateFunction::StripPrefix(
+            unquote(arg).ok_or_else(|| invalid("the prefix must be a quoted string"))?,
+        ),
+        ("strip_prefix", None) => return Err(invalid("missing the prefix argument")),
+        (_, Some(_)) => return Err(invalid("takes a single argument")),
+        ("upper", None) => TemplateFunction::Upper,
+        ("lower", None) => TemplateFunction::Lower,
+        ("snake_case", None) => TemplateFunction::SnakeCase,
+        ("camelCase", None) => TemplateFunction::CamelCase,
+        _ => return Ok(None),
+    };
+
+    let segment = Segment::Call {
+        function,
+        var: var.to_string(),
+        multi,
+    };
+    Ok(Some((segment, close + 2)))
+}
+
+fn unquote(arg: &str) -> Option<String> {
+    ['"', '\'']
+        .iter()
+        .find_map(|quote| arg.strip_prefix(*quote)?.strip_suffix(*quote))
+        .map(str::to_string)
+}
+
+fn is_meta_var_char(c: char) -> bool {
+    c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'
+}
+
+/// Replace `$VAR` and `$$$VARS` in `text` with their captured values. References to variables
+/// that were not captured are kept as written
+fn substitute_vars(text: &str, vars: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start..];
+        let (sigil, multi) = match after.starts_with("$$$") {
+            true => ("$$$", true),
+            false => ("$", false),
+        };
+        let name_len = after[sigil.len()..]
+            .find(|c: char| !is_meta_var_char(c))
+            .unwrap_or(after.len() - sigil.len());
+        let name = &after[sigil.len()..sigil.len() + name_len];
+        match vars.contains_key(name) && !name.is_empty() {
+            true => result.push_str(&captured_text(vars, name, multi)),
+            false => result.push_str(&after[..sigil.len() + name_len]),
+        }
+        rest = &after[sigil.len() + name_len..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// The text captured for `var`. Multiple-node captures are reported as `[a, b]`
+fn captured_text(vars: &HashMap<String, String>, var: &str, multi: bool) -> String {
+    let value = vars.get(var).map(String::as_str).unwrap_or_default();
+    match multi {
+        true => value
+            .strip_prefix('[')
+            .and_then(|inner| inner.strip_suffix(']'))
+            .unwrap_or(value)
+            .to_string(),
+        false => value.to_string(),
+    }
+}
+
+/// Split an identifier into words at `_`, `-`, spaces and case changes: `parseHTTPRequest`
+/// gives `parse`, `HTTP`, `Request`
+fn words(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        let prev = i.checked_sub(1).map(|j| chars[j]);
+        let next = chars.get(i + 1);
+        let boundary = c.is_uppercase()
+            && prev.is_some_and(|p| {
+                p.is_lowercase()
+                    || p.is_ascii_digit()
+                    || (p.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+            });
+        if boundary && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_functions() {
+        assert_eq!(
+            TemplateFunction::SnakeCase.apply("parseHTTPRequest"),
+            "parse_http_request"
+        );
+        assert_eq!(
+            TemplateFunction::SnakeCase.apply("UserId2Name"),
+            "user_id2_name"
+        );
+        assert_eq!(
+            TemplateFunction::CamelCase.apply("user_name-field"),
+            "userNameField"
+        );
+        assert_eq!(TemplateFunction::CamelCase.apply("XMLParser"), "xmlParser");
+        assert_eq!(TemplateFunction::Upper.apply("max_size"), "MAX_SIZE");
+        assert_eq!(
+            TemplateFunction::StripPrefix("get".to_string()).apply("getName"),
+            "Name"
+        );
+    }
+
+    #[test]
+    fn test_parse_keeps_unknown_placeholders() {
+        let template = ReplacementTemplate::parse("`${name}: ${upper($A)}`").unwrap();
+        assert!(template.has_functions());
+        let vars = HashMap::from([("A".to_string(), "value".to_string())]);
+        assert_eq!(template.render(&vars), "`${name}: VALUE`");
+    }
+
+    #[test]
+    fn test_render_substitutes_vars() {
+        let template =
+            ReplacementTemplate::parse("set${strip_prefix($P, 'get')}($$$ARGS, $MISSING)").unwrap();
+        let vars = HashMap::from([
+            ("P".to_string(), "getValue".to_string()),
+            ("ARGS".to_string(), "[a, b]".to_string()),
+        ]);
+        assert_eq!(template.render(&vars), "setValue(a, b, $MISSING)");
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_calls() {
+        assert!(ReplacementTemplate::parse("${strip_prefix($A)}").is_err());
+        assert!(ReplacementTemplate::parse("${upper(A)}").is_err());
+        assert!(ReplacementTemplate::parse("${upper($A)").is_err());
+    }
+}
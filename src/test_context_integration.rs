@@ -33,6 +33,11 @@ line5"#;
             context_before: Some(1),
             context_after: Some(1),
             context_lines: None,
+            dialect: None,
+            buffer_id: None,
+            include_ast_context: false,
+            context_scope: None,
+            include_leading_trivia: false,
         };
 
         let result = search_service.search(param).await.unwrap();
@@ -97,6 +102,11 @@ line 7
             context_before: None,
             context_after: None,
             context_lines: Some(2),
+            dialect: None,
+            buffer_id: None,
+            include_ast_context: false,
+            context_scope: None,
+            include_leading_trivia: false,
         };
 
         let result = search_service.search(param).await.unwrap();
@@ -0,0 +1,106 @@
+//! # Tool Middleware
+//!
+//! Hooks that run around every tool call, registered programmatically on an
+//! [`AstGrepService`](crate::ast_grep_service::AstGrepService). Deployments use them to enforce
+//! policies (for example rejecting writes under certain paths), rewrite results, or record
+//! metrics and audit logs without patching individual handlers.
+//!
+//! Layers see requests in registration order and results in reverse order, like nested
+//! wrappers around the handler.
+
+use rmcp::model::{CallToolRequestParam, CallToolResult, ErrorData};
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// A layer wrapped around tool calls. Both hooks default to passing values through unchanged.
+pub trait ToolMiddleware: Send + Sync {
+    /// Validate or rewrite a request before its handler runs. Returning an error rejects the
+    /// call; later layers and the handler are skipped.
+    fn before_call(
+        &self,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolRequestParam, ErrorData> {
+        Ok(request)
+    }
+
+    /// Observe or transform the outcome of a call, including calls rejected by a later layer
+    fn after_call(
+        &self,
+        _tool_name: &str,
+        result: Result<CallToolResult, ErrorData>,
+        _elapsed: Duration,
+    ) -> Result<CallToolResult, ErrorData> {
+        result
+    }
+}
+
+/// Ordered middleware layers, shared between clones of a service
+#[derive(Clone, Default)]
+pub struct MiddlewareStack {
+    layers: Arc<RwLock<Vec<Arc<dyn ToolMiddleware>>>>,
+}
+
+impl MiddlewareStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a layer inside all previously added layers
+    pub fn push(&self, middleware: impl ToolMiddleware + 'static) {
+        self.layers.write().unwrap().push(Arc::new(middleware));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.read().unwrap().is_empty()
+    }
+
+    /// Run `handler` for `request` with every layer applied around it
+    pub async fn call<F, Fut>(
+        &self,
+        request: CallToolRequestParam,
+        handler: F,
+    ) -> Result<CallToolResult, ErrorData>
+    where
+        F: FnOnce(CallToolRequestParam) -> Fut,
+        Fut: Future<Output = Result<CallToolResult, ErrorData>>,
+    {
+        // Snapshot the layers so the lock isn't held across the handler's awaits
+        let layers: Vec<Arc<dyn ToolMiddleware>> = self.layers.read().unwrap().clone();
+        if layers.is_empty() {
+            return handler(request).await;
+        }
+
+        let tool_name = request.name.to_string();
+        let started = Instant::now();
+
+        let mut request = request;
+        let mut entered = 0;
+        let mut rejection = None;
+        for layer in &layers {
+            match layer.before_call(request.clone()) {
+                Ok(next) => {
+                    request = next;
+                    entered += 1;
+                }
+                Err(e) => {
+                    rejection = Some(e);
+                    break;
+                }
+            }
+        }
+
+        let mut result = match rejection {
+            Some(e) => Err(e),
+            None => handler(request).await,
+        };
+
+        // Unwind through every layer that saw the request, innermost first; the layer that
+        // rejected the call is included so it can audit its own decision
+        let unwound = (entered + 1).min(layers.len());
+        for layer in layers[..unwound].iter().rev() {
+            result = layer.after_call(&tool_name, result, started.elapsed());
+        }
+        result
+    }
+}
@@ -0,0 +1,124 @@
+//! # Custom Tool Registry
+//!
+//! Lets crates that embed [`AstGrepService`] contribute their own MCP tools. Registered tools
+//! are advertised next to the built-in ones and reached through [`ToolRouter`], so consumers
+//! don't need to fork the tools module to extend the server.
+//!
+//! ```rust,no_run
+//! use ast_grep_mcp::ast_grep_service::AstGrepService;
+//! use ast_grep_mcp::tool_registry::CustomTool;
+//! use async_trait::async_trait;
+//! use rmcp::model::{CallToolRequestParam, CallToolResult, Content, ErrorData, Tool};
+//! use std::sync::Arc;
+//!
+//! struct CacheStats;
+//!
+//! #[async_trait]
+//! impl CustomTool for CacheStats {
+//!     fn definition(&self) -> Tool {
+//!         Tool {
+//!             name: "cache_stats".into(),
+//!             description: Some("Report pattern cache usage".into()),
+//!             input_schema: Arc::new(serde_json::Map::new()),
+//!             annotations: None,
+//!         }
+//!     }
+//!
+//!     async fn call(
+//!         &self,
+//!         service: &AstGrepService,
+//!         _request: CallToolRequestParam,
+//!     ) -> Result<CallToolResult, ErrorData> {
+//!         let (used, capacity) = service.get_cache_stats();
+//!         Ok(CallToolResult::success(vec![Content::text(format!("{used}/{capacity}"))]))
+//!     }
+//! }
+//!
+//! let service = AstGrepService::new();
+//! service.register_tool(CacheStats).unwrap();
+//! ```
+//!
+//! [`ToolRouter`]: crate::tool_router::ToolRouter
+
+use crate::ast_grep_service::AstGrepService;
+use crate::errors::ServiceError;
+use crate::tools::ToolService;
+
+use async_trait::async_trait;
+use rmcp::model::{CallToolRequestParam, CallToolResult, ErrorData, Tool};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// A tool provided by a library consumer
+// `async_trait` re-adds `#[must_use]` to the boxed future it returns
+#[allow(clippy::double_must_use)]
+#[async_trait]
+pub trait CustomTool: Send + Sync {
+    /// Name, description and input schema advertised to clients
+    fn definition(&self) -> Tool;
+
+    /// Handle a call to this tool
+    async fn call(
+        &self,
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData>;
+}
+
+/// Custom tools registered on a service, shared between its clones
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: Arc<RwLock<HashMap<String, Arc<dyn CustomTool>>>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a tool. Names must not clash with built-in tools or earlier registrations.
+    pub fn register(&self, tool: impl CustomTool + 'static) -> Result<(), ServiceError> {
+        let name = tool.definition().name.to_string();
+        if ToolService::list_tools()
+            .tools
+            .iter()
+            .any(|builtin| builtin.name == name)
+        {
+            return Err(ServiceError::Internal(format!(
+                "Tool '{name}' conflicts with a built-in tool"
+            )));
+        }
+
+        let mut tools = self.tools.write().unwrap();
+        if tools.contains_key(&name) {
+            return Err(ServiceError::Internal(format!(
+                "Tool '{name}' is already registered"
+            )));
+        }
+        tools.insert(name, Arc::new(tool));
+        Ok(())
+    }
+
+    /// Remove a tool, returning whether it was registered
+    pub fn unregister(&self, name: &str) -> bool {
+        self.tools.write().unwrap().remove(name).is_some()
+    }
+
+    /// Look up a registered tool by name
+    pub fn get(&self, name: &str) -> Option<Arc<dyn CustomTool>> {
+        self.tools.read().unwrap().get(name).cloned()
+    }
+
+    /// Definitions of all registered tools, sorted by name
+    pub fn definitions(&self) -> Vec<Tool> {
+        let mut definitions: Vec<Tool> = self
+            .tools
+            .read()
+            .unwrap()
+            .values()
+            .map(|tool| tool.definition())
+            .collect();
+        definitions.sort_by(|a, b| a.name.cmp(&b.name));
+        definitions
+    }
+}
@@ -40,36 +40,112 @@ impl ToolRouter {
         ResponseFormatter::create_formatted_response(result, summary)
             .map_err(|e| ErrorData::internal_error(Cow::Owned(e.to_string()), None))
     }
-    /// Route a tool call to the appropriate service method
+
+    /// Helper function to write a search result to `output_file` and respond with its summary
+    fn create_exported_response(
+        service: &AstGrepService,
+        result: &FileSearchResult,
+        output_file: &str,
+    ) -> Result<CallToolResult, ErrorData> {
+        let exported = service
+            .export_search_result(result, output_file)
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_exported_search_result(&exported);
+        Self::create_formatted_response(&exported, summary)
+    }
+
+    /// Route a tool call to the appropriate service method, applying the service's middleware
     pub async fn route_tool_call(
         service: &AstGrepService,
         request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        service
+            .middleware
+            .call(request, |request| Self::dispatch(service, request))
+            .await
+    }
+
+    /// Route a tool call to its handler without running middleware
+    pub(crate) async fn dispatch(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
     ) -> Result<CallToolResult, ErrorData> {
         match request.name.as_ref() {
             // Search operations
             "search" => Self::handle_search(service, request).await,
             "file_search" => Self::handle_file_search(service, request).await,
+            "batch_search" => Self::handle_batch_search(service, request).await,
+            "get_file_by_hash" => Self::handle_get_file_by_hash(service, request).await,
+            "search_strings" => Self::handle_search_strings(service, request).await,
+            "search_identifiers" => Self::handle_search_identifiers(service, request).await,
+            "find_comments" => Self::handle_find_comments(service, request).await,
+            "project_overview" => Self::handle_project_overview(service, request).await,
+            "summarize_directory" => Self::handle_summarize_directory(service, request).await,
+            "search_symbols" => Self::handle_search_symbols(service, request).await,
+            "doc_coverage" => Self::handle_doc_coverage(service, request).await,
+            "find_references" => Self::handle_find_references(service, request).await,
+            "rename_symbol" => Self::handle_rename_symbol(service, request).await,
+            "find_similar_code" => Self::handle_find_similar_code(service, request).await,
+            "dedupe_code" => Self::handle_dedupe_code(service, request).await,
+            "detect_conventions" => Self::handle_detect_conventions(service, request).await,
+            "pattern_coverage" => Self::handle_pattern_coverage(service, request).await,
+            "begin_code_buffer" => Self::handle_begin_code_buffer(service, request).await,
+            "append_code_buffer" => Self::handle_append_code_buffer(service, request).await,
+            "search_buffer" => Self::handle_search_buffer(service, request).await,
+            "experiment" => Self::handle_experiment(service, request).await,
 
             // Replace operations
             "replace" => Self::handle_replace(service, request).await,
             "file_replace" => Self::handle_file_replace(service, request).await,
+            "get_file_diff" => Self::handle_get_file_diff(service, request).await,
+            "add_annotation" => Self::handle_add_annotation(service, request).await,
+            "wrap_matches" => Self::handle_wrap_matches(service, request).await,
+            "verify_refactoring" => Self::handle_verify_refactoring(service, request).await,
+            "apply_patch" => Self::handle_apply_patch(service, request).await,
+            "rollback_replace" => Self::handle_rollback_replace(service, request).await,
+            "list_applied_changes" => Self::handle_list_applied_changes(service, request).await,
 
             // Rule operations
             "rule_search" => Self::handle_rule_search(service, request).await,
             "rule_replace" => Self::handle_rule_replace(service, request).await,
+            "scan" => Self::handle_scan(service, request).await,
+            "lint_workspace" => Self::handle_lint_workspace(service, request).await,
+            "run_codemod" => Self::handle_run_codemod(service, request).await,
+            "plan_directory_move" => Self::handle_plan_directory_move(service, request).await,
+            "apply_plan" => Self::handle_apply_plan(service, request).await,
             "create_rule" => Self::handle_create_rule(service, request).await,
             "get_rule" => Self::handle_get_rule(service, request).await,
             "list_rules" => Self::handle_list_rules(service, request).await,
+            "rule_stats" => Self::handle_rule_stats(service, request).await,
             "delete_rule" => Self::handle_delete_rule(service, request).await,
-            "rule_validate" => Self::handle_rule_validate(service, request).await,
+            "push_rule" => Self::handle_push_rule(service, request).await,
+            "pull_rules" => Self::handle_pull_rules(service, request).await,
+            "validate_rule" | "rule_validate" => {
+                Self::handle_rule_validate(service, request).await
+            }
+            "validate_rules" => Self::handle_validate_rules(service, request).await,
+            "explain_rule_evaluation" => {
+                Self::handle_explain_rule_evaluation(service, request).await
+            }
+            "convert_rule" => Self::handle_convert_rule(service, request).await,
+            "export_to_sgconfig" => Self::handle_export_to_sgconfig(service, request).await,
+            "export_rules" => Self::handle_export_rules(service, request).await,
+            "import_rules" => Self::handle_import_rules(service, request).await,
 
             // Utility operations
             "generate_ast" => Self::handle_generate_ast(service, request).await,
             "list_languages" => Self::handle_list_languages(service, request).await,
+            "create_playground" => Self::handle_create_playground(service, request).await,
+            "detect_language" => Self::handle_detect_language(service, request).await,
+            "anonymize_result" => Self::handle_anonymize_result(service, request).await,
+            "index_status" => Self::handle_index_status(service, request).await,
+            "health_check" => Self::handle_health_check(service, request).await,
+            "rebuild_index" => Self::handle_rebuild_index(service, request).await,
 
             // Learning operations
             "validate_pattern" => Self::handle_validate_pattern(service, request).await,
             "explore_patterns" => Self::handle_explore_patterns(service, request).await,
+            "suggest_pattern" => Self::handle_suggest_pattern(service, request).await,
 
             // Refactoring operations
             "analyze_refactoring" => Self::handle_analyze_refactoring(service, request).await,
@@ -79,9 +155,13 @@ impl ToolRouter {
             "list_refactorings" => Self::handle_list_refactorings(service, request).await,
             "get_refactoring_info" => Self::handle_get_refactoring_info(service, request).await,
 
-            _ => Err(ErrorData::method_not_found::<
-                rmcp::model::CallToolRequestMethod,
-            >()),
+            // Tools registered by library consumers
+            name => match service.tool_registry.get(name) {
+                Some(tool) => tool.call(service, request).await,
+                None => Err(ErrorData::method_not_found::<
+                    rmcp::model::CallToolRequestMethod,
+                >()),
+            },
         }
     }
 
@@ -93,25 +173,25 @@ impl ToolRouter {
         let param: SearchParam = Self::parse_params(&request)?;
 
         // Error handling for common LLM misuse patterns
-        if param.code.is_empty() {
+        if param.code.is_empty() && param.buffer_id.is_none() {
             return Err(ErrorData::invalid_params(
                 Cow::Borrowed(
-                    "The 'search' tool requires the 'code' parameter. If you intend to search across files, please use the 'file_search' tool and provide a 'path_pattern'.",
+                    "The 'search' tool requires the 'code' parameter, or a 'buffer_id' for code sent with begin_code_buffer. If you intend to search across files, please use the 'file_search' tool and provide a 'path_pattern'.",
                 ),
                 None,
             ));
         }
         // Although SearchParam does not have path_pattern, an LLM might mistakenly pass it.
         // We check raw arguments to provide a more helpful error.
-        if let Some(args) = &request.arguments {
-            if args.contains_key("path_pattern") {
-                return Err(ErrorData::invalid_params(
-                    Cow::Borrowed(
-                        "The 'search' tool operates on code snippets and does not accept 'path_pattern'. If you intend to search across files, please use the 'file_search' tool.",
-                    ),
-                    None,
-                ));
-            }
+        if let Some(args) = &request.arguments
+            && args.contains_key("path_pattern")
+        {
+            return Err(ErrorData::invalid_params(
+                Cow::Borrowed(
+                    "The 'search' tool operates on code snippets and does not accept 'path_pattern'. If you intend to search across files, please use the 'file_search' tool.",
+                ),
+                None,
+            ));
         }
 
         let result = service.search(param).await.map_err(ErrorData::from)?;
@@ -119,6 +199,59 @@ impl ToolRouter {
         Self::create_formatted_response(&result, summary)
     }
 
+    async fn handle_begin_code_buffer(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: BeginCodeBufferParam = Self::parse_params(&request)?;
+        let result = service.begin_code_buffer(param).map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_code_buffer_info(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_append_code_buffer(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: AppendCodeBufferParam = Self::parse_params(&request)?;
+        let result = service.append_code_buffer(param).map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_code_buffer_info(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_search_buffer(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: SearchParam = Self::parse_params(&request)?;
+        let result = service
+            .search_buffer(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_search_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_experiment(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: ExperimentParam = Self::parse_params(&request)?;
+        let result = service.experiment(param).await.map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_experiment_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_get_file_by_hash(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: GetFileByHashParam = Self::parse_params(&request)?;
+        let result = service.get_file_by_hash(param).map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_get_file_by_hash_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
     async fn handle_file_search(
         service: &AstGrepService,
         request: CallToolRequestParam,
@@ -136,23 +269,200 @@ impl ToolRouter {
         }
         // Although FileSearchParam does not have code, an LLM might mistakenly pass it.
         // We check raw arguments to provide a more helpful error.
-        if let Some(args) = &request.arguments {
-            if args.contains_key("code") {
-                return Err(ErrorData::invalid_params(
-                    Cow::Borrowed(
-                        "The 'file_search' tool operates on files and does not accept a 'code' parameter. If you intend to search a code snippet, please use the 'search' tool.",
-                    ),
-                    None,
-                ));
-            }
+        if let Some(args) = &request.arguments
+            && args.contains_key("code")
+        {
+            return Err(ErrorData::invalid_params(
+                Cow::Borrowed(
+                    "The 'file_search' tool operates on files and does not accept a 'code' parameter. If you intend to search a code snippet, please use the 'search' tool.",
+                ),
+                None,
+            ));
         }
 
+        let output_file = param.output_file.clone();
         let result = service.file_search(param).await.map_err(ErrorData::from)?;
+        if let Some(output_file) = output_file {
+            return Self::create_exported_response(service, &result, &output_file);
+        }
         let summary = ResponseFormatter::format_file_search_result(&result);
         Self::create_formatted_response(&result, summary)
     }
 
+    async fn handle_batch_search(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: BatchSearchParam = Self::parse_params(&request)?;
+        let result = service.batch_search(param).await.map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_batch_search_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
     // Replace operations
+    async fn handle_search_strings(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: ValueSearchParam = Self::parse_params(&request)?;
+        let result = service
+            .search_strings(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_file_search_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_search_identifiers(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: ValueSearchParam = Self::parse_params(&request)?;
+        let result = service
+            .search_identifiers(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_file_search_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_find_comments(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: FindCommentsParam = Self::parse_params(&request)?;
+        let result = service
+            .find_comments(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_find_comments_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_project_overview(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: ProjectOverviewParam = Self::parse_params(&request)?;
+        let result = service
+            .project_overview(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_project_overview_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_summarize_directory(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: SummarizeDirectoryParam = Self::parse_params(&request)?;
+        let result = service
+            .summarize_directory(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_summarize_directory_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_search_symbols(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: SymbolSearchParam = Self::parse_params(&request)?;
+        let result = service
+            .search_symbols(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_symbol_search_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_doc_coverage(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: DocCoverageParam = Self::parse_params(&request)?;
+        let result = service.doc_coverage(param).await.map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_doc_coverage_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_find_references(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: FindReferencesParam = Self::parse_params(&request)?;
+        let result = service
+            .find_references(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_find_references_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_rename_symbol(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: RenameSymbolParam = Self::parse_params(&request)?;
+        let result = service
+            .rename_symbol(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_rename_symbol_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_find_similar_code(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: FindSimilarCodeParam = Self::parse_params(&request)?;
+        let result = service
+            .find_similar_code(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_find_similar_code_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_dedupe_code(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: DedupeCodeParam = Self::parse_params(&request)?;
+        let result = service.dedupe_code(param).await.map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_dedupe_code_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_detect_conventions(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: DetectConventionsParam = Self::parse_params(&request)?;
+        let result = service
+            .detect_conventions(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_detect_conventions_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_pattern_coverage(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: PatternCoverageParam = Self::parse_params(&request)?;
+        let result = service
+            .pattern_coverage(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_pattern_coverage_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
     async fn handle_replace(
         service: &AstGrepService,
         request: CallToolRequestParam,
@@ -160,23 +470,23 @@ impl ToolRouter {
         let param: ReplaceParam = Self::parse_params(&request)?;
 
         // Error handling for common LLM misuse patterns
-        if param.code.is_empty() {
+        if param.code.is_empty() && param.buffer_id.is_none() {
             return Err(ErrorData::invalid_params(
                 Cow::Borrowed(
-                    "The 'replace' tool requires the 'code' parameter. If you intend to replace across files, please use the 'file_replace' tool and provide a 'path_pattern'.",
+                    "The 'replace' tool requires the 'code' parameter, or a 'buffer_id' for code sent with begin_code_buffer. If you intend to replace across files, please use the 'file_replace' tool and provide a 'path_pattern'.",
                 ),
                 None,
             ));
         }
-        if let Some(args) = &request.arguments {
-            if args.contains_key("path_pattern") {
-                return Err(ErrorData::invalid_params(
-                    Cow::Borrowed(
-                        "The 'replace' tool operates on code snippets and does not accept 'path_pattern'. If you intend to replace across files, please use the 'file_replace' tool.",
-                    ),
-                    None,
-                ));
-            }
+        if let Some(args) = &request.arguments
+            && args.contains_key("path_pattern")
+        {
+            return Err(ErrorData::invalid_params(
+                Cow::Borrowed(
+                    "The 'replace' tool operates on code snippets and does not accept 'path_pattern'. If you intend to replace across files, please use the 'file_replace' tool.",
+                ),
+                None,
+            ));
         }
 
         let result = service.replace(param).await.map_err(ErrorData::from)?;
@@ -184,6 +494,91 @@ impl ToolRouter {
         Self::create_formatted_response(&result, summary)
     }
 
+    async fn handle_verify_refactoring(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: VerifyRefactoringParam = Self::parse_params(&request)?;
+        let result = service
+            .verify_refactoring(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_verify_refactoring_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_get_file_diff(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: GetFileDiffParam = Self::parse_params(&request)?;
+        let result = service
+            .get_file_diff(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_get_file_diff_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_apply_patch(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: ApplyPatchParam = Self::parse_params(&request)?;
+        let result = service.apply_patch(param).await.map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_apply_patch_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_rollback_replace(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: RollbackReplaceParam = Self::parse_params(&request)?;
+        let result = service
+            .rollback_replace(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_rollback_replace_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_list_applied_changes(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: ListAppliedChangesParam = Self::parse_params(&request)?;
+        let result = service
+            .list_applied_changes(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_list_applied_changes_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_add_annotation(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: AddAnnotationParam = Self::parse_params(&request)?;
+        let result = service
+            .add_annotation(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_add_annotation_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_wrap_matches(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: WrapMatchesParam = Self::parse_params(&request)?;
+        let result = service.wrap_matches(param).await.map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_wrap_matches_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
     async fn handle_file_replace(
         service: &AstGrepService,
         request: CallToolRequestParam,
@@ -230,9 +625,44 @@ impl ToolRouter {
         request: CallToolRequestParam,
     ) -> Result<CallToolResult, ErrorData> {
         let param: RuleSearchParam = Self::parse_params(&request)?;
+        let output_file = param.output_file.clone();
+        let output_format = param.output_format;
+        let rule_config = param.rule_config.clone();
+        if param.pack.is_some() && output_format == RuleOutputFormat::Sarif {
+            return Err(ErrorData::invalid_params(
+                Cow::Borrowed(
+                    "The sarif output format describes a single rule; use the 'scan' tool to get the findings of several rules, or search the pack's rules one at a time.",
+                ),
+                None,
+            ));
+        }
         let result = service.rule_search(param).await.map_err(ErrorData::from)?;
-        let summary = ResponseFormatter::format_file_search_result(&result);
-        Self::create_formatted_response(&result, summary)
+
+        if output_format == RuleOutputFormat::Sarif {
+            let sarif = service
+                .sarif_log(&result, &rule_config)
+                .map_err(ErrorData::from)?;
+            return match output_file {
+                Some(output_file) => {
+                    let exported = service
+                        .export_search_document(&result, &sarif, &output_file)
+                        .map_err(ErrorData::from)?;
+                    let summary = ResponseFormatter::format_exported_search_result(&exported);
+                    Self::create_formatted_response(&exported, summary)
+                }
+                None => Self::create_json_response(sarif),
+            };
+        }
+        if let Some(output_file) = output_file {
+            return Self::create_exported_response(service, &result, &output_file);
+        }
+        match output_format {
+            RuleOutputFormat::Json => Self::create_json_response(result),
+            _ => {
+                let summary = ResponseFormatter::format_file_search_result(&result);
+                Self::create_formatted_response(&result, summary)
+            }
+        }
     }
 
     async fn handle_rule_replace(
@@ -245,6 +675,70 @@ impl ToolRouter {
         Self::create_formatted_response(&result, summary)
     }
 
+    async fn handle_scan(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: ScanParam = Self::parse_params(&request)?;
+        let output_file = param.output_file.clone();
+        let result = service.scan(param).await.map_err(ErrorData::from)?;
+        if let Some(output_file) = output_file {
+            let exported = service
+                .export_scan_result(&result, &output_file)
+                .map_err(ErrorData::from)?;
+            let summary = ResponseFormatter::format_exported_scan_result(&exported);
+            return Self::create_formatted_response(&exported, summary);
+        }
+        let summary = ResponseFormatter::format_scan_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_lint_workspace(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: LintWorkspaceParam = Self::parse_params(&request)?;
+        let result = service
+            .lint_workspace(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_lint_workspace_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_run_codemod(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: RunCodemodParam = Self::parse_params(&request)?;
+        let result = service.run_codemod(param).await.map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_run_codemod_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_plan_directory_move(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: PlanDirectoryMoveParam = Self::parse_params(&request)?;
+        let result = service
+            .plan_directory_move(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_edit_plan(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_apply_plan(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: ApplyPlanParam = Self::parse_params(&request)?;
+        let result = service.apply_plan(param).await.map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_apply_plan_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
     async fn handle_create_rule(
         service: &AstGrepService,
         request: CallToolRequestParam,
@@ -272,6 +766,54 @@ impl ToolRouter {
         Self::create_json_response(result)
     }
 
+    async fn handle_rule_stats(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: RuleStatsParam = Self::parse_params(&request)?;
+        let result = service.rule_stats(param).await.map_err(ErrorData::from)?;
+        Self::create_json_response(result)
+    }
+
+    async fn handle_convert_rule(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: ConvertRuleParam = Self::parse_params(&request)?;
+        let result = service.convert_rule(param).await.map_err(ErrorData::from)?;
+        Self::create_json_response(result)
+    }
+
+    async fn handle_export_to_sgconfig(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: ExportToSgconfigParam = Self::parse_params(&request)?;
+        let result = service
+            .export_to_sgconfig(param)
+            .await
+            .map_err(ErrorData::from)?;
+        Self::create_json_response(result)
+    }
+
+    async fn handle_export_rules(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: ExportRulesParam = Self::parse_params(&request)?;
+        let result = service.export_rules(param).await.map_err(ErrorData::from)?;
+        Self::create_json_response(result)
+    }
+
+    async fn handle_import_rules(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: ImportRulesParam = Self::parse_params(&request)?;
+        let result = service.import_rules(param).await.map_err(ErrorData::from)?;
+        Self::create_json_response(result)
+    }
+
     async fn handle_delete_rule(
         service: &AstGrepService,
         request: CallToolRequestParam,
@@ -281,6 +823,39 @@ impl ToolRouter {
         Self::create_json_response(result)
     }
 
+    async fn handle_push_rule(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: PushRuleParam = Self::parse_params(&request)?;
+        let result = service.push_rule(param).await.map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_push_rule_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_pull_rules(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: PullRulesParam = Self::parse_params(&request)?;
+        let result = service.pull_rules(param).await.map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_pull_rules_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_explain_rule_evaluation(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: ExplainRuleParam = Self::parse_params(&request)?;
+        let result = service
+            .explain_rule_evaluation(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_explain_rule_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
     async fn handle_rule_validate(
         service: &AstGrepService,
         request: CallToolRequestParam,
@@ -293,6 +868,19 @@ impl ToolRouter {
         Self::create_json_response(result)
     }
 
+    async fn handle_validate_rules(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: ValidateRulesParam = Self::parse_params(&request)?;
+        let result = service
+            .validate_rules(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_validate_rules_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
     // Debug operations
     async fn handle_generate_ast(
         service: &AstGrepService,
@@ -318,6 +906,75 @@ impl ToolRouter {
         Self::create_json_response(result)
     }
 
+    async fn handle_create_playground(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: CreatePlaygroundParam = Self::parse_params(&request)?;
+        let result = service
+            .create_playground(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_create_playground_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_detect_language(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: DetectLanguageParam = Self::parse_params(&request)?;
+        let result = service
+            .detect_language(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_detect_language_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_index_status(
+        service: &AstGrepService,
+        _request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param = IndexStatusParam {};
+        let result = service.index_status(param).await.map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_index_status(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_health_check(
+        service: &AstGrepService,
+        _request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param = HealthCheckParam {};
+        let result = service.health_check(param).await.map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_health_check_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_rebuild_index(
+        service: &AstGrepService,
+        _request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param = RebuildIndexParam {};
+        let result = service
+            .rebuild_index(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_index_status(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
+    async fn handle_anonymize_result(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: AnonymizeResultParam = Self::parse_params(&request)?;
+        let result = service.anonymize_result(param).map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_anonymize_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
     // Learning operations
     async fn handle_validate_pattern(
         service: &AstGrepService,
@@ -355,6 +1012,19 @@ impl ToolRouter {
         Self::create_formatted_response(&result, summary)
     }
 
+    async fn handle_suggest_pattern(
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let param: crate::learning::SuggestPatternParam = Self::parse_params(&request)?;
+        let result = service
+            .suggest_pattern(param)
+            .await
+            .map_err(ErrorData::from)?;
+        let summary = ResponseFormatter::format_suggest_pattern_result(&result);
+        Self::create_formatted_response(&result, summary)
+    }
+
     // Refactoring operations
     async fn handle_analyze_refactoring(
         service: &AstGrepService,
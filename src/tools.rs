@@ -20,16 +20,22 @@ impl ToolService {
                         "type": "object",
                         "properties": {
                             "code": { "type": "string", "description": "Source code to search in" },
+                            "buffer_id": { "type": "string", "description": "Search a code buffer from begin_code_buffer instead of code, for code too large for one message" },
                             "pattern": { "type": "string", "description": "AST pattern to search for. Use $VAR for single captures, $$$ for multiple captures" },
-                            "language": { "type": "string", "description": "Programming language (javascript, typescript, python, rust, java, go, cpp, etc.)" },
+                            "language": { "type": "string", "description": "Programming language (javascript, typescript, python, rust, java, go, cpp, graphql, protobuf, etc.)" },
+                            "dialect": { "type": "string", "description": "Grammar variant to parse with, e.g. 'tsx' for TypeScript with JSX or 'typescript' for JavaScript with type annotations" },
                             "strictness": { "type": "string", "enum": ["cst", "smart", "ast", "relaxed", "signature"], "description": "Match strictness level" },
                             "selector": { "type": "string", "description": "CSS-like selector for matching specific node types" },
                             "context": { "type": "string", "description": "Context pattern to match surrounding code" },
                             "context_before": { "type": "integer", "minimum": 0, "description": "Number of lines to show before each match" },
                             "context_after": { "type": "integer", "minimum": 0, "description": "Number of lines to show after each match" },
-                            "context_lines": { "type": "integer", "minimum": 0, "description": "Number of lines to show before and after each match (equivalent to grep -C)" }
+                            "context_lines": { "type": "integer", "minimum": 0, "description": "Number of lines to show before and after each match (equivalent to grep -C)" },
+                            "context_scope": { "type": "string", "enum": ["statement", "function", "class"], "description": "Instead of context lines, return the smallest enclosing statement, function or class of each match in enclosing_node" },
+                            "include_ast_context": { "type": "boolean", "default": false, "description": "Attach each match's node kind, parent node kind and immediate named child kinds, to tell apart look-alike nodes (e.g. call vs new expression) without a generate_ast call" },
+                            "include_leading_trivia": { "type": "boolean", "default": false, "description": "Attach the comments and attributes (doc comments, #[attributes], decorators, annotations) directly above each match as leading_trivia, e.g. to audit documentation coverage of matched declarations" }
                         },
-                        "required": ["code", "pattern", "language"]
+                        "required": ["pattern", "language"],
+                        "anyOf": [{ "required": ["code"] }, { "required": ["buffer_id"] }]
                     })).unwrap()),
                     annotations: None,
                 },
@@ -41,7 +47,9 @@ impl ToolService {
                         "properties": {
                             "path_pattern": { "type": "string", "description": "Glob pattern for files to search (e.g., '**/*.js', 'src/**/*.{ts,tsx}')" },
                             "pattern": { "type": "string", "description": "AST pattern to search for. Use $VAR for single captures, $$$ for multiple captures" },
-                            "language": { "type": "string", "description": "Programming language of target files" },
+                            "language": { "type": "string", "description": "Programming language of target files. Omit it or pass \"auto\" to detect each file's language from its extension, or from its #! line when the extension names none; matches then report the detected language" },
+                            "dialect": { "type": "string", "description": "Grammar variant to parse every file with, e.g. 'tsx'. When omitted, .ts/.tsx files use the grammar their extension implies" },
+                            "language_patterns": { "type": "object", "additionalProperties": { "type": "string" }, "description": "One pattern per language for polyglot searches, e.g. {\"python\": \"print($$$)\", \"javascript\": \"console.log($$$)\"}. Replaces pattern and language; each file is matched with the pattern for its extension's language and matches report the language key" },
                             "max_results": { "type": "integer", "minimum": 1, "maximum": 50, "default": 20, "description": "Maximum number of matches to return" },
                             "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to search in bytes" },
                             "cursor": {
@@ -57,9 +65,412 @@ impl ToolService {
                             "context": { "type": "string", "description": "Context pattern to match surrounding code" },
                             "context_before": { "type": "integer", "minimum": 0, "description": "Number of lines to show before each match" },
                             "context_after": { "type": "integer", "minimum": 0, "description": "Number of lines to show after each match" },
-                            "context_lines": { "type": "integer", "minimum": 0, "description": "Number of lines to show before and after each match (equivalent to grep -C)" }
+                            "context_lines": { "type": "integer", "minimum": 0, "description": "Number of lines to show before and after each match (equivalent to grep -C)" },
+                            "context_scope": { "type": "string", "enum": ["statement", "function", "class"], "description": "Instead of context lines, return the smallest enclosing statement, function or class of each match in enclosing_node" },
+                            "include_ast_context": { "type": "boolean", "default": false, "description": "Attach each match's node kind, parent node kind and immediate named child kinds, to tell apart look-alike nodes (e.g. call vs new expression) without a generate_ast call" },
+                            "include_leading_trivia": { "type": "boolean", "default": false, "description": "Attach the comments and attributes (doc comments, #[attributes], decorators, annotations) directly above each match as leading_trivia, e.g. to audit documentation coverage of matched declarations" },
+                            "output_file": { "type": "string", "description": "Workspace path to write the full JSON result to; only a summary and the path are returned" },
+                            "overlays": { "type": "object", "additionalProperties": { "type": "string" }, "description": "Unsaved editor buffers keyed by file path (absolute or relative to a root directory). Their content is searched instead of the file on disk." },
+                            "search_archives": { "type": "boolean", "default": false, "description": "Also search files inside zip, jar, war, tar and tar.gz archives under the roots. Entries are extracted in memory, matched against the glob by their path inside the archive, and reported as archive.zip!path/inside.js. Search only: replace tools never modify archives." },
+                            "workspace": { "type": "string", "description": "Only search the root directory with this workspace label (see --workspace). Results label each file with its workspace." },
+                            "max_depth": { "type": "integer", "minimum": 0, "description": "How many directory levels below each root to walk (default: the server's --max-depth)" },
+                            "prune_dirs": { "type": "array", "items": { "type": "string" }, "description": "Directory names never descended into, e.g. [\"node_modules\", \"target\"] (default: the server's --prune-dir list)" },
+                            "follow_hidden": { "type": "boolean", "description": "Whether to walk hidden files and directories whose names start with a dot (default: true unless the server runs with --skip-hidden)" },
+                            "respect_gitignore": { "type": "boolean", "description": "Whether to leave out files excluded by .gitignore, .ignore and .git/info/exclude, and the .git directory (default: true unless the server runs with --no-gitignore)" },
+                            "compress": { "type": "string", "enum": ["gzip+base64"], "description": "Return the full result JSON gzipped and base64-encoded as one blob next to the summary, instead of a truncated or paginated JSON result. Accepted by every tool" },
+                            "regex_fallback": { "type": "boolean", "default": false, "description": "When language has no tree-sitter grammar (e.g. toml or ini), treat pattern as a regular expression and match it line by line instead of failing. Named groups are returned as vars and each file is flagged with engine: \"regex\"." },
+                            "allow_broad": { "type": "boolean", "default": false, "description": "Run a pattern that is a lone metavariable such as $_, $A or $$$ARGS. It matches nearly every node, so it is refused unless this is set; prefer a more specific pattern or a context with a selector" },
+                            "revision": { "type": "string", "description": "Git tag, branch or commit to search instead of the working tree, e.g. \"v1.2.0\" or \"HEAD~10\". Files are read from git objects without checking anything out and are reported at their working tree paths. path_pattern must be relative; cannot be combined with overlays or search_archives." },
+                            "suggest_fixes": { "type": "boolean", "default": false, "description": "Run the stored rules that carry a fix over each file with matches and attach to every match the fixes of rule matches overlapping it (rule id, fix safety, old and new text), so a search can be followed by rule_replace with a curated rule" },
+                            "stream": { "type": "boolean", "default": false, "description": "Send each file's matches as soon as it has been searched, in MCP progress notifications whose message is a JSON object with files_searched, total_files and file. Needs a progressToken in the request's _meta; the final response then holds the summary, stats and cursor without the streamed matches" },
+                            "timeout_ms": { "type": "integer", "minimum": 0, "description": "Stop after this many milliseconds, or when the client cancels the request, and return the matches found so far marked timed_out. next_cursor then resumes with the files not yet searched" }
+                        },
+                        "required": ["path_pattern"],
+                        "anyOf": [
+                            { "required": ["pattern"] },
+                            { "required": ["language_patterns"] }
+                        ]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "batch_search".into(),
+                    description: Some("Run several file searches in one call, e.g. to check ten patterns at once. Each search has an id, a pattern, a language and a path_pattern relative to the root directories. The roots are walked once, every file is read once and parsed once per language, and files are searched concurrently. Results are keyed by id; a search with an invalid pattern, language or path_pattern reports its error without failing the others.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "searches": {
+                                "type": "array",
+                                "minItems": 1,
+                                "items": {
+                                    "type": "object",
+                                    "properties": {
+                                        "id": { "type": "string", "description": "Key of this search's results; unique within the call" },
+                                        "pattern": { "type": "string", "description": "The ast-grep pattern to search for" },
+                                        "language": { "type": "string", "description": "Programming language of the pattern" },
+                                        "path_pattern": { "type": "string", "description": "Glob relative to the root directories, e.g. src/**/*.ts or *.py" },
+                                        "selector": { "type": "string", "description": "Kind of the node to match when the pattern only parses inside context" },
+                                        "context": { "type": "string", "description": "Surrounding code the pattern is parsed in, used together with selector" },
+                                        "max_results": { "type": "integer", "minimum": 1, "default": 100, "description": "Maximum number of matches to return for this search; truncated is set when there were more" }
+                                    },
+                                    "required": ["id", "pattern", "language", "path_pattern"]
+                                }
+                            },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to search in bytes; larger files are listed in skipped_files" },
+                            "workspace": { "type": "string", "description": "Only search the root directory with this workspace label (see --workspace)" },
+                            "allow_broad": { "type": "boolean", "default": false, "description": "Run patterns that are a lone metavariable such as $_ or $A, which are refused otherwise" },
+                            "max_depth": { "type": "integer", "minimum": 0, "description": "How many directory levels below each root to walk (default: the server's --max-depth)" },
+                            "prune_dirs": { "type": "array", "items": { "type": "string" }, "description": "Directory names never descended into, e.g. [\"node_modules\", \"target\"] (default: the server's --prune-dir list)" },
+                            "follow_hidden": { "type": "boolean", "description": "Whether to walk hidden files and directories whose names start with a dot (default: true unless the server runs with --skip-hidden)" },
+                            "respect_gitignore": { "type": "boolean", "description": "Whether to leave out files excluded by .gitignore, .ignore and .git/info/exclude, and the .git directory (default: true unless the server runs with --no-gitignore)" }
+                        },
+                        "required": ["searches"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "get_file_by_hash".into(),
+                    description: Some("Fetch a file seen in a search result, but only if it is unchanged: pass the file_path and file_hash reported with the matches. Returns the content (or just start_line..end_line, 0-based and inclusive) when the current content still has that hash; otherwise returns unchanged: false with the new hash and no content, so you can search again instead of reading an edited file.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "file_path": { "type": "string", "description": "Path of the file as reported in the search result" },
+                            "file_hash": { "type": "string", "description": "The file_hash reported with the file, e.g. 'sha256:…'" },
+                            "start_line": { "type": "integer", "minimum": 0, "description": "First line to return, 0-based like match positions (default: the first line)" },
+                            "end_line": { "type": "integer", "minimum": 0, "description": "Last line to return, inclusive (default: the last line)" }
+                        },
+                        "required": ["file_path", "file_hash"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "search_strings".into(),
+                    description: Some("Find string literals whose contents (without quotes) equal 'value' or match 'regex' across files. Handy for hard-coded URLs, keys, or messages. The unquoted contents are returned as the VALUE variable.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "path_pattern": { "type": "string", "description": "Glob pattern for files to search (e.g., '**/*.js', 'src/**/*.{ts,tsx}')" },
+                            "language": { "type": "string", "description": "Programming language of target files" },
+                            "value": { "type": "string", "description": "Exact string contents to find (mutually exclusive with regex)" },
+                            "regex": { "type": "string", "description": "Regex the string contents must match, e.g. '^https?://' (mutually exclusive with value)" },
+                            "max_results": { "type": "integer", "minimum": 1, "maximum": 50, "default": 20, "description": "Maximum number of files to return matches from" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to search in bytes" },
+                            "cursor": {
+                                "type": "object",
+                                "properties": {
+                                    "last_file_path": { "type": "string" },
+                                    "is_complete": { "type": "boolean" }
+                                },
+                                "required": ["last_file_path", "is_complete"]
+                            }
+                        },
+                        "required": ["path_pattern", "language"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "search_identifiers".into(),
+                    description: Some("Find identifiers (variables, functions, properties, types, fields) whose name equals 'value' or matches 'regex' across files. Useful for finding every use of a constant or naming convention violations.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "path_pattern": { "type": "string", "description": "Glob pattern for files to search (e.g., '**/*.js', 'src/**/*.{ts,tsx}')" },
+                            "language": { "type": "string", "description": "Programming language of target files" },
+                            "value": { "type": "string", "description": "Exact identifier name to find (mutually exclusive with regex)" },
+                            "regex": { "type": "string", "description": "Regex the identifier name must match, e.g. '^MAX_' (mutually exclusive with value)" },
+                            "max_results": { "type": "integer", "minimum": 1, "maximum": 50, "default": 20, "description": "Maximum number of files to return matches from" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to search in bytes" },
+                            "cursor": {
+                                "type": "object",
+                                "properties": {
+                                    "last_file_path": { "type": "string" },
+                                    "is_complete": { "type": "boolean" }
+                                },
+                                "required": ["last_file_path", "is_complete"]
+                            }
+                        },
+                        "required": ["path_pattern", "language"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "find_comments".into(),
+                    description: Some("Audit comments (TODO, FIXME, HACK, ticket IDs) across files using comment syntax nodes rather than text grep. Returns each comment with its tag and counts per tag; set blame=true to attach git author info.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "path_pattern": { "type": "string", "description": "Glob pattern for files to search (e.g., '**/*.js', 'src/**/*.{ts,tsx}')" },
+                            "language": { "type": "string", "description": "Programming language of target files" },
+                            "regex": { "type": "string", "description": "Regex a comment must match; the first capture group is the tag. Default: '\\b(TODO|FIXME|HACK|XXX)\\b'" },
+                            "blame": { "type": "boolean", "default": false, "description": "Attach git blame author info to each comment" },
+                            "max_results": { "type": "integer", "minimum": 1, "maximum": 50, "default": 20, "description": "Maximum number of files to return comments from" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to search in bytes" },
+                            "cursor": {
+                                "type": "object",
+                                "properties": {
+                                    "last_file_path": { "type": "string" },
+                                    "is_complete": { "type": "boolean" }
+                                },
+                                "required": ["last_file_path", "is_complete"]
+                            }
                         },
-                        "required": ["path_pattern", "pattern", "language"]
+                        "required": ["path_pattern", "language"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "project_overview".into(),
+                    description: Some("Get oriented in an unfamiliar project with one call: the languages under the root directories with file counts and extensions, the directory tree down to tree_depth with file counts and each directory's main language, the largest files, and a path_pattern glob per language to scope follow-up file_search calls. Only file names and sizes are read.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "tree_depth": { "type": "integer", "minimum": 0, "default": 2, "description": "Directory levels below each root to list in the tree" },
+                            "largest_files": { "type": "integer", "minimum": 0, "default": 10, "description": "Number of largest files to list" },
+                            "max_depth": { "type": "integer", "minimum": 0, "description": "How many directory levels below each root to walk (default: the server's --max-depth)" },
+                            "prune_dirs": { "type": "array", "items": { "type": "string" }, "description": "Directory names never descended into, e.g. [\"node_modules\", \"target\"] (default: the server's --prune-dir list)" },
+                            "follow_hidden": { "type": "boolean", "description": "Whether to walk hidden files and directories whose names start with a dot (default: true unless the server runs with --skip-hidden)" },
+                            "respect_gitignore": { "type": "boolean", "description": "Whether to leave out files excluded by .gitignore, .ignore and .git/info/exclude, and the .git directory (default: true unless the server runs with --no-gitignore)" }
+                        }
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "summarize_directory".into(),
+                    description: Some("Summarize the source files in a directory for quick triage: per-file language, lines of code, function and class counts and the largest functions, plus directory totals. Built from one syntax-tree pass per file; unchanged files are served from a cache on repeat calls.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "directory": { "type": "string", "default": ".", "description": "Directory to summarize, absolute or relative to the root directories" },
+                            "max_files": { "type": "integer", "minimum": 1, "default": 500, "description": "Maximum number of files to summarize; truncated is set when more were found" },
+                            "largest_functions": { "type": "integer", "minimum": 0, "default": 5, "description": "Number of largest functions to list per file and for the whole directory" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to summarize in bytes; larger files are listed in skipped_files" }
+                        }
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "search_symbols".into(),
+                    description: Some("(Experimental) Find declarations whose name you only half remember. Functions, methods, classes and other declarations under a directory are indexed and ranked by similarity to the query: exact, case-insensitive, the same words in another naming style (get_user_name ~ getUserName), every query word present (user name ~ formatUserDisplayName) or a small edit distance (recieve ~ receive). Unchanged files are served from a cache on repeat calls.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "query": { "type": "string", "description": "Name to look for, as well as you remember it" },
+                            "directory": { "type": "string", "default": ".", "description": "Directory to search, absolute or relative to the root directories" },
+                            "kinds": { "type": "array", "items": { "type": "string", "enum": ["function", "method", "class", "struct", "enum", "trait", "interface", "impl", "module"] }, "description": "Only report these kinds of declarations (default: all)" },
+                            "min_score": { "type": "number", "minimum": 0, "maximum": 1, "default": 0.6, "description": "Lowest similarity to report" },
+                            "max_results": { "type": "integer", "minimum": 1, "default": 20 },
+                            "max_files": { "type": "integer", "minimum": 1, "default": 5000, "description": "Maximum number of files to index; truncated is set when more were found" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to index in bytes; larger files are listed in skipped_files" }
+                        },
+                        "required": ["query"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "doc_coverage".into(),
+                    description: Some("Audit doc comments: which public functions, methods and types under a directory lack them. Rust items declared pub need a /// comment, Python functions and classes not named with a leading underscore need a docstring, and exported JavaScript/TypeScript declarations and members of exported classes need a /** */ JSDoc comment. Reports the coverage percentage overall, per language and per file, and pages through the undocumented symbols. Uses the same cached declaration index as search_symbols.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "directory": { "type": "string", "default": ".", "description": "Directory to audit, absolute or relative to the root directories" },
+                            "page_size": { "type": "integer", "minimum": 1, "default": 50, "description": "Undocumented symbols listed per page" },
+                            "offset": { "type": "integer", "minimum": 0, "default": 0, "description": "Undocumented symbols to skip, from the previous page's next_offset" },
+                            "max_files": { "type": "integer", "minimum": 1, "default": 5000, "description": "Maximum number of files to audit; truncated is set when more were found" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to audit in bytes; larger files are listed in skipped_files" }
+                        }
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "find_references".into(),
+                    description: Some("Find every place a symbol is named under a directory, grouped per file. Each reference is classified as a definition (function, class, method, type or variable declaration), a call (plain, method or constructor call) or an import (an import naming the symbol or a module path ending in it). Supports JavaScript/TypeScript (searched together), Python, Rust and Go. Matching is syntactic: same-named symbols in different scopes are all reported.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "symbol": { "type": "string", "description": "Identifier to look for, e.g. loadConfig" },
+                            "language": { "type": "string", "enum": ["javascript", "typescript", "tsx", "python", "rust", "go"], "description": "Language of the files to search" },
+                            "directory": { "type": "string", "default": ".", "description": "Directory to search, absolute or relative to the root directories" },
+                            "max_results": { "type": "integer", "minimum": 1, "default": 500, "description": "Maximum number of references to return; truncated is set when there were more" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to search in bytes; larger files are listed in skipped_files" }
+                        },
+                        "required": ["symbol", "language"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "rename_symbol".into(),
+                    description: Some("Rename a function, variable, class or type in one file or under a directory. Every identifier spelling old_name is renamed: definitions, calls and imports (classified as in find_references) and any other use, such as a read or a type annotation. Strings and comments are left alone. Existing definitions of new_name in scope are reported as collisions, and a rename with collisions is only applied with force=true. Supports JavaScript/TypeScript (renamed together), Python, Rust and Go. Matching is syntactic: same-named symbols in different scopes are renamed too. Dry run by default.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "old_name": { "type": "string", "description": "Identifier to rename, e.g. loadConfig" },
+                            "new_name": { "type": "string", "description": "Identifier to rename it to" },
+                            "language": { "type": "string", "enum": ["javascript", "typescript", "tsx", "python", "rust", "go"], "description": "Language of the files to rename in" },
+                            "scope": { "type": "string", "enum": ["file", "project"], "default": "project", "description": "Rename in the file at path, or in every file under the directory at path" },
+                            "path": { "type": "string", "default": ".", "description": "File (scope file) or directory (scope project), absolute or relative to the root directories" },
+                            "dry_run": { "type": "boolean", "default": true, "description": "Only report the renames and collisions" },
+                            "force": { "type": "boolean", "default": false, "description": "Apply the rename even though new_name is already defined in scope" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to rename in, in bytes; larger files are listed in skipped_files" }
+                        },
+                        "required": ["old_name", "new_name", "language"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "find_similar_code".into(),
+                    description: Some("Find code shaped like an example snippet, e.g. other places that do the same dance. The snippet's identifiers and literals are replaced with the wildcard $_ to derive a structural pattern, which is returned with the results. Every match of that pattern in files of the same language is scored by the share of the snippet's tokens it repeats in order, so code that kept the original names ranks first. The snippet must be a single statement, expression or declaration.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "code": { "type": "string", "description": "Example snippet to find similar code for" },
+                            "language": { "type": "string", "description": "Language of the snippet and of the files to search" },
+                            "directory": { "type": "string", "default": ".", "description": "Directory to search, absolute or relative to the root directories" },
+                            "min_score": { "type": "number", "minimum": 0, "maximum": 1, "default": 0.5, "description": "Lowest similarity to report" },
+                            "max_results": { "type": "integer", "minimum": 1, "default": 50, "description": "Maximum number of matches to return; truncated is set when there were more" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to search in bytes; larger files are listed in skipped_files" }
+                        },
+                        "required": ["code", "language"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "dedupe_code".into(),
+                    description: Some("Find duplicated code across files, e.g. before extracting a shared helper. Every function, method, closure and block of at least min_lines lines and min_tokens tokens is reduced to its normalized shape: its tokens without comments, with identifiers and literals replaced by placeholders unless ignore_identifiers or ignore_literals is false. Units of the same language with the same shape form a clone group, reported with the file and line range of each copy and a similarity score, the share of tokens the copies repeat verbatim. Blocks inside a larger reported clone are not reported again. Groups are ordered by duplicated tokens.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "directory": { "type": "string", "default": ".", "description": "Directory to scan, absolute or relative to the root directories" },
+                            "language": { "type": "string", "description": "Only scan files in this language; every supported language when omitted" },
+                            "ignore_identifiers": { "type": "boolean", "default": true, "description": "Treat copies that renamed identifiers as clones" },
+                            "ignore_literals": { "type": "boolean", "default": true, "description": "Treat copies that changed literals as clones" },
+                            "min_lines": { "type": "integer", "minimum": 1, "default": 5, "description": "Fewest lines a function or block needs to be compared" },
+                            "min_tokens": { "type": "integer", "minimum": 1, "default": 30, "description": "Fewest tokens a function or block needs to be compared" },
+                            "max_groups": { "type": "integer", "minimum": 1, "default": 50, "description": "Maximum number of clone groups to return; truncated is set when there were more" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to scan in bytes; larger files are listed in skipped_files" }
+                        }
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "detect_conventions".into(),
+                    description: Some("Infer the conventions of a codebase before proposing changes to it. A sample of the source files is measured with fixed structural probes: quote style (JS/TS, Python), const/let/var adoption (JS/TS), error-handling idioms (try/catch vs promise .catch(), try/except, ? vs unwrap/expect, if err != nil) and the test framework in use (jest, vitest, mocha, node:test, pytest, unittest, #[test], #[tokio::test], testing, testify). Each convention reports its dominant option, that option's share of the observations, per-option counts and an example location.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "directory": { "type": "string", "default": ".", "description": "Directory to sample, absolute or relative to the root directories" },
+                            "max_files": { "type": "integer", "minimum": 1, "default": 200, "description": "Maximum number of files to sample; larger codebases are sampled evenly across directories" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to sample in bytes; larger files are listed in skipped_files" }
+                        }
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "pattern_coverage".into(),
+                    description: Some("Track a migration: count the remaining occurrences of each pattern (e.g. calls to an old API) under the root directories and record the counts as a snapshot under the migration's name. Returns each pattern's count next to its first (baseline) and previous recorded count, the overall progress in percent and the recent snapshot history. Counts that run past timeout_ms are returned but not recorded.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string", "description": "Migration name snapshots are stored under (letters, digits, '-', '_' and '.')" },
+                            "patterns": {
+                                "type": "array",
+                                "minItems": 1,
+                                "items": {
+                                    "type": "object",
+                                    "properties": {
+                                        "id": { "type": "string", "description": "Stable name the pattern's counts are recorded under" },
+                                        "pattern": { "type": "string", "description": "AST pattern to count" },
+                                        "language": { "type": "string", "description": "Programming language; only files of this language are counted" }
+                                    },
+                                    "required": ["id", "pattern", "language"]
+                                }
+                            },
+                            "path_pattern": { "type": "string", "default": "**/*", "description": "Glob selecting the files to count in" },
+                            "workspace": { "type": "string", "description": "Only count under the root directory with this workspace label" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to count in bytes" },
+                            "timeout_ms": { "type": "integer", "minimum": 1, "default": 60000, "description": "Time budget for the count; unfinished counts are returned with complete: false and not recorded" },
+                            "record": { "type": "boolean", "default": true, "description": "Store the counts as a new snapshot; false previews the counts against the history" },
+                            "history_limit": { "type": "integer", "minimum": 0, "default": 20, "description": "Number of most recent snapshots to return" }
+                        },
+                        "required": ["name", "patterns"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "begin_code_buffer".into(),
+                    description: Some("Open a server-side buffer for code too large to send in one message. Send the code in chunks with append_code_buffer, then pass the returned buffer_id to search_buffer, search, replace or generate_ast instead of code. Buffers expire ttl_seconds after their last use.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "content": { "type": "string", "default": "", "description": "First chunk of code" },
+                            "ttl_seconds": { "type": "integer", "minimum": 1, "default": 900, "description": "Seconds the buffer stays open after it was last used" }
+                        }
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "append_code_buffer".into(),
+                    description: Some("Add the next chunk of code to a buffer opened with begin_code_buffer. Chunks are concatenated exactly as sent, so split anywhere, including mid-line. The total size is limited like a file (--max-file-size).".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "buffer_id": { "type": "string", "description": "Id returned by begin_code_buffer" },
+                            "content": { "type": "string", "description": "Code to add to the end of the buffer" }
+                        },
+                        "required": ["buffer_id", "content"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "search_buffer".into(),
+                    description: Some("Search the code accumulated in a buffer (see begin_code_buffer) for an AST pattern. Takes the same options as search, with buffer_id in place of code.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "buffer_id": { "type": "string", "description": "Id returned by begin_code_buffer" },
+                            "pattern": { "type": "string", "description": "AST pattern to search for. Use $VAR for single captures, $$$ for multiple captures" },
+                            "language": { "type": "string", "description": "Programming language of the buffered code" },
+                            "dialect": { "type": "string", "description": "Grammar variant to parse with, e.g. 'tsx'" },
+                            "strictness": { "type": "string", "enum": ["cst", "smart", "ast", "relaxed", "signature"], "description": "Match strictness level" },
+                            "selector": { "type": "string", "description": "CSS-like selector for matching specific node types" },
+                            "context": { "type": "string", "description": "Context pattern to match surrounding code" },
+                            "context_lines": { "type": "integer", "minimum": 0, "description": "Number of lines to show before and after each match" },
+                            "context_scope": { "type": "string", "enum": ["statement", "function", "class"], "description": "Instead of context lines, return the smallest enclosing statement, function or class of each match in enclosing_node" },
+                            "include_ast_context": { "type": "boolean", "default": false, "description": "Attach each match's node kind, parent node kind and immediate named child kinds, to tell apart look-alike nodes (e.g. call vs new expression) without a generate_ast call" },
+                            "include_leading_trivia": { "type": "boolean", "default": false, "description": "Attach the comments and attributes (doc comments, #[attributes], decorators, annotations) directly above each match as leading_trivia, e.g. to audit documentation coverage of matched declarations" }
+                        },
+                        "required": ["buffer_id", "pattern", "language"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "experiment".into(),
+                    description: Some("Try several candidate patterns on the same code in one call. For each candidate returns the match count, the first matches and, when a replacement is given, the rewritten code. Use it to compare pattern variants while writing a search or rewrite instead of calling search once per attempt. A candidate that fails to parse reports an error without affecting the others.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "code": { "type": "string", "description": "Source code to try the candidates on" },
+                            "buffer_id": { "type": "string", "description": "Use the code in this buffer (see begin_code_buffer) instead of code" },
+                            "language": { "type": "string", "description": "Programming language of the code" },
+                            "candidates": {
+                                "type": "array",
+                                "minItems": 1,
+                                "maxItems": 20,
+                                "items": {
+                                    "type": "object",
+                                    "properties": {
+                                        "id": { "type": "string", "description": "Label for the candidate in the result (default: its 1-based position)" },
+                                        "pattern": { "type": "string", "description": "AST pattern to try" },
+                                        "replacement": { "type": "string", "description": "Rewrite applied to every match; the result includes the rewritten code" },
+                                        "language": { "type": "string", "description": "Parse the code as this language for this candidate, e.g. 'tsx'" },
+                                        "strictness": { "type": "string", "enum": ["cst", "smart", "ast", "relaxed", "signature"], "description": "Match strictness level" },
+                                        "selector": { "type": "string", "description": "CSS-like selector for matching specific node types" }
+                                    },
+                                    "required": ["pattern"]
+                                }
+                            },
+                            "max_matches": { "type": "integer", "minimum": 0, "default": 5, "description": "Matches returned per candidate; match_count always counts all of them" }
+                        },
+                        "required": ["language", "candidates"]
                     })).unwrap()),
                     annotations: None,
                 },
@@ -70,11 +481,17 @@ impl ToolService {
                         "type": "object",
                         "properties": {
                             "code": { "type": "string", "description": "Source code to modify" },
+                            "buffer_id": { "type": "string", "description": "Replace in a code buffer from begin_code_buffer instead of code. The buffer is not modified; new_code holds the result" },
                             "pattern": { "type": "string", "description": "AST pattern to find and replace" },
-                            "replacement": { "type": "string", "description": "Replacement pattern with captured variables (e.g., use $VAR from pattern)" },
-                            "language": { "type": "string", "description": "Programming language of the code" }
+                            "replacement": { "type": "string", "description": "Replacement pattern with captured variables (e.g., use $VAR from pattern). ${upper($VAR)}, ${lower($VAR)}, ${snake_case($VAR)}, ${camelCase($VAR)} and ${strip_prefix($VAR, \"prefix\")} insert computed text" },
+                            "language": { "type": "string", "description": "Programming language of the code" },
+                            "verify_formatting": { "type": "boolean", "default": false, "description": "Run the language's formatter (rustfmt, prettier, black, gofmt, clang-format) over the old and new code and report in format_check whether anything changed outside the replaced regions" },
+                            "format_after_replace": { "type": "boolean", "default": false, "description": "Run the language's formatter over the rewritten regions before returning new_code, so generated edits match project style; changes then lists the rewritten lines. format_error says why when the rewrite could not be formatted" },
+                            "rewrite_selector": { "type": "string", "description": "Metavariable of the pattern (e.g. $DECORATOR or $$$ARGS) whose captured code the replacement rewrites, leaving the rest of each match intact. Lets a large pattern anchor the match while only a sub-part changes; the replacement can still use every captured variable" },
+                            "json_patch": { "type": "boolean", "default": false, "description": "For json and yaml code, return the edit as an RFC 6902 JSON Patch (json_patch) computed from the parsed documents instead of text changes. Fails if the replacement leaves an invalid document" }
                         },
-                        "required": ["code", "pattern", "replacement", "language"]
+                        "required": ["pattern", "replacement", "language"],
+                        "anyOf": [{ "required": ["code"] }, { "required": ["buffer_id"] }]
                     })).unwrap()),
                     annotations: None,
                 },
@@ -86,14 +503,32 @@ impl ToolService {
                         "properties": {
                             "path_pattern": { "type": "string", "description": "Glob pattern for files to modify (e.g., '**/*.js', 'src/**/*.{ts,tsx}')" },
                             "pattern": { "type": "string", "description": "AST pattern to find and replace" },
-                            "replacement": { "type": "string", "description": "Replacement pattern with captured variables" },
-                            "language": { "type": "string", "description": "Programming language of target files" },
+                            "replacement": { "type": "string", "description": "Replacement pattern with captured variables; supports the same ${function($VAR)} calls as replace" },
+                            "language": { "type": "string", "description": "Programming language of target files. Omit it or pass \"auto\" to detect each file's language from its extension, or from its #! line when the extension names none; matches then report the detected language" },
                             "max_results": { "type": "integer", "minimum": 1, "maximum": 10000, "description": "Maximum number of matches to process" },
                             "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to process in bytes" },
                             "dry_run": { "type": "boolean", "default": true, "description": "If true (default), only show preview. If false, actually modify files." },
                             "summary_only": { "type": "boolean", "default": false, "description": "If true, only return summary statistics (change counts per file)" },
                             "include_samples": { "type": "boolean", "default": false, "description": "If true, include sample changes in the response (first few changes per file)" },
                             "max_samples": { "type": "integer", "default": 3, "minimum": 1, "maximum": 20, "description": "Maximum number of sample changes to show per file" },
+                            "expected_hashes": { "type": "object", "additionalProperties": { "type": "string" }, "description": "file_hash values from the dry run keyed by file path. Applying fails if any of these files changed since the dry run, unless force is set." },
+                            "force": { "type": "boolean", "default": false, "description": "Merge the replacement into files that changed since the dry run instead of failing. Unmergeable hunks get diff3-style conflict markers and are reported in drifted_files." },
+                            "overlays": { "type": "object", "additionalProperties": { "type": "string" }, "description": "Unsaved editor buffers keyed by file path (absolute or relative to a root directory). Replacements are computed against this content and returned as per-file edits; overlay files are never written to disk." },
+                            "verify_formatting": { "type": "boolean", "default": false, "description": "Run the language's formatter over each file before and after the replacement and report in format_check whether anything changed outside the replaced regions. Files are still written when the check fails." },
+                            "format_after_replace": { "type": "boolean", "default": false, "description": "Run the language's formatter (see verify_formatting) over each rewritten file before changes are reported and written. Only the rewritten regions are reformatted; code the replacement did not touch keeps its formatting. Files whose rewrite can't be formatted are written unformatted and listed with format_error" },
+                            "rewrite_selector": { "type": "string", "description": "Metavariable of the pattern (e.g. $DECORATOR or $$$ARGS) whose captured code the replacement rewrites, leaving the rest of each match intact. Lets a large pattern anchor the match while only a sub-part changes; the replacement can still use every captured variable" },
+                            "json_patch": { "type": "boolean", "default": false, "description": "For json and yaml files, return each file's edit as an RFC 6902 JSON Patch (json_patch) instead of sample text changes, for pipelines that apply document edits themselves. Fails if a replacement leaves an invalid document" },
+                            "output": { "type": "string", "enum": ["changes", "unified_diff"], "default": "changes", "description": "How each file's rewrite is reported: sample changes, or a standard unified diff of the whole file (unified_diff) that git apply, patch or the apply_patch tool can apply after review" },
+                            "diff_context": { "type": "integer", "minimum": 0, "default": 3, "description": "Unchanged lines shown around each hunk of a unified diff" },
+                            "timeout_ms": { "type": "integer", "minimum": 0, "description": "Stop after this many milliseconds, or when the client cancels the request, and return the changes made so far marked timed_out. Only the files reported were written; next_cursor resumes with the rest" },
+                            "ignore_whitespace_changes": { "type": "boolean", "default": true, "description": "Treat files whose rewrite only changes trailing whitespace, trailing blank lines or line endings as unchanged: they are not reported and not written, so formatting-only churn stays out of diffs. Set to false to keep such rewrites" },
+                            "allow_broad": { "type": "boolean", "default": false, "description": "Rewrite with a pattern that is a lone metavariable such as $_ or $A. It matches nearly every node, so it is refused unless this is set" },
+                            "workspace": { "type": "string", "description": "Only modify files under the root directory with this workspace label (see --workspace), so an edit can't reach another project. Results label each file with its workspace." },
+                            "max_depth": { "type": "integer", "minimum": 0, "description": "How many directory levels below each root to walk (default: the server's --max-depth)" },
+                            "prune_dirs": { "type": "array", "items": { "type": "string" }, "description": "Directory names never descended into, e.g. [\"node_modules\", \"target\"] (default: the server's --prune-dir list)" },
+                            "follow_hidden": { "type": "boolean", "description": "Whether to walk hidden files and directories whose names start with a dot (default: true unless the server runs with --skip-hidden)" },
+                            "respect_gitignore": { "type": "boolean", "description": "Whether to leave out files excluded by .gitignore, .ignore and .git/info/exclude, and the .git directory (default: true unless the server runs with --no-gitignore)" },
+                            "compress": { "type": "string", "enum": ["gzip+base64"], "description": "Return the full result JSON gzipped and base64-encoded as one blob next to the summary, instead of a truncated or paginated JSON result. Accepted by every tool" },
                             "cursor": {
                                 "type": "object",
                                 "properties": {
@@ -103,7 +538,131 @@ impl ToolService {
                                 "required": ["last_file_path", "is_complete"]
                             }
                         },
-                        "required": ["path_pattern", "pattern", "replacement", "language"]
+                        "required": ["path_pattern", "pattern", "replacement"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "get_file_diff".into(),
+                    description: Some("Get every change a file_replace or rule_replace dry run would make to one file. A dry run that would change files returns an operation_id; the server keeps its full diffs for the last few dry runs, so a large dry run can be reviewed file by file instead of in one response. Each result names the next file to ask for.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "operation_id": { "type": "string", "description": "The operation_id returned by the dry run" },
+                            "file_path": { "type": "string", "description": "File to return, as reported by the dry run (default: the first changed file)" }
+                        },
+                        "required": ["operation_id"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "apply_patch".into(),
+                    description: Some("Apply a unified diff to files in the root directories, such as the unified_diff a file_replace dry run returns with output=\"unified_diff\". Review the patch, then apply it. Hunks apply where their headers say, or nearby if lines above them moved; nothing is written unless every hunk of every file applies. Applied patches can be undone with rollback_replace. Dry run by default.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "patch": { "type": "string", "description": "Unified diff of one or more existing files. Paths are absolute or relative to the first root directory; git's a/ and b/ prefixes are accepted" },
+                            "dry_run": { "type": "boolean", "default": true, "description": "If true (default), only check that the patch applies. If false, write the files." },
+                            "workspace": { "type": "string", "description": "Only patch files under the root directory with this workspace label (see --workspace)" }
+                        },
+                        "required": ["patch"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "add_annotation".into(),
+                    description: Some("Insert a comment on its own line above every match of a pattern, such as a deprecation notice, a suppression marker or a TODO with a ticket number. The comment is written in each file's comment syntax (// for JavaScript, Rust, Go and the like, # for Python, Ruby and YAML, -- for Lua and Haskell, /* */ for CSS, <!-- --> for HTML) and indented like the matched line. Lines that already have the comment right above them are skipped. Dry run by default: each change shows the matched line before and after.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "path_pattern": { "type": "string", "description": "Glob pattern or direct file path to annotate" },
+                            "pattern": { "type": "string", "description": "The ast-grep pattern whose matches get the comment" },
+                            "language": { "type": "string", "description": "Programming language" },
+                            "comment": { "type": "string", "description": "Comment text without comment markers, e.g. \"TODO(PROJ-123): remove after the migration\"; each line becomes one comment line" },
+                            "selector": { "type": "string", "description": "CSS-like selector for matching specific node types" },
+                            "context": { "type": "string", "description": "Context pattern to match surrounding code" },
+                            "dry_run": { "type": "boolean", "default": true, "description": "Preview the annotations without modifying files" },
+                            "max_results": { "type": "integer", "minimum": 1, "default": 10000, "description": "Maximum number of files to annotate; truncated is set when more had matches" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to process in bytes" },
+                            "workspace": { "type": "string", "description": "Only annotate files in the root directory with this workspace label (see --workspace)" },
+                            "allow_broad": { "type": "boolean", "default": false, "description": "Annotate the matches of a pattern that is a lone metavariable such as $_ or $A, which is refused otherwise" }
+                        },
+                        "required": ["path_pattern", "pattern", "language", "comment"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "wrap_matches".into(),
+                    description: Some("Wrap every match of a pattern in a template that contains $MATCH where the matched code goes, e.g. 'measure(() => $MATCH)' in JavaScript or 'instrument!($MATCH)' in Rust. A match that is a whole statement keeps its ';' after the wrapper, and a trailing ';' of the template is dropped, so the same template wraps statements and expressions. Matches inside another match are left alone. Dry run by default: each change shows the match before and after.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "path_pattern": { "type": "string", "description": "Glob pattern or direct file path to rewrite" },
+                            "pattern": { "type": "string", "description": "The ast-grep pattern whose matches get wrapped" },
+                            "language": { "type": "string", "description": "Programming language" },
+                            "template": { "type": "string", "description": "Code around each match with $MATCH as the placeholder, e.g. \"measure(() => $MATCH)\"; lines after the first are indented like the match" },
+                            "selector": { "type": "string", "description": "CSS-like selector for matching specific node types" },
+                            "context": { "type": "string", "description": "Context pattern to match surrounding code" },
+                            "dry_run": { "type": "boolean", "default": true, "description": "Preview the rewrites without modifying files" },
+                            "max_results": { "type": "integer", "minimum": 1, "default": 10000, "description": "Maximum number of files to rewrite; truncated is set when more had matches" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824, "description": "Maximum file size to process in bytes" },
+                            "workspace": { "type": "string", "description": "Only rewrite files in the root directory with this workspace label (see --workspace)" },
+                            "allow_broad": { "type": "boolean", "default": false, "description": "Wrap the matches of a pattern that is a lone metavariable such as $_ or $A, which is refused otherwise" }
+                        },
+                        "required": ["path_pattern", "pattern", "language", "template"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "verify_refactoring".into(),
+                    description: Some("Apply a file_replace and then run a verification command configured on the server with --verify-command (e.g. 'cargo check', 'npm test -- --filter x') in the workspace root. Returns the replacement result, the command's exit code, whether it timed out and the tail of its output. With rollback_on_failure, the rewritten files are restored when the command fails.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "replace": {
+                                "type": "object",
+                                "description": "file_replace parameters for the replacement to apply. Files are always written; dry_run is ignored. Preview with file_replace first and pass its expected_hashes to guard against concurrent edits.",
+                                "properties": {
+                                    "path_pattern": { "type": "string" },
+                                    "pattern": { "type": "string" },
+                                    "replacement": { "type": "string" },
+                                    "language": { "type": "string" },
+                                    "expected_hashes": { "type": "object", "additionalProperties": { "type": "string" } },
+                                    "workspace": { "type": "string", "description": "The command also runs in this workspace's root" }
+                                },
+                                "required": ["path_pattern", "pattern", "replacement"]
+                            },
+                            "command": { "type": "string", "description": "Name of a verification command configured with --verify-command" },
+                            "timeout_ms": { "type": "integer", "minimum": 1, "default": 300000, "description": "Time the command may run before it is killed and counted as failed" },
+                            "rollback_on_failure": { "type": "boolean", "default": false, "description": "Restore the rewritten files to their previous content when the command fails or times out" },
+                            "tail_lines": { "type": "integer", "minimum": 0, "default": 40, "description": "Number of trailing lines of stdout and stderr to return" }
+                        },
+                        "required": ["replace", "command"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "rollback_replace".into(),
+                    description: Some("Undo a file_replace applied with dry_run=false. Every applied file_replace records the previous content of the files it wrote in the server's undo directory and returns an apply_id; this restores those files. Fails without touching anything when a file changed after the replacement, unless force is set.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "apply_id": { "type": "string", "description": "The apply_id returned by file_replace or listed by list_applied_changes" },
+                            "force": { "type": "boolean", "default": false, "description": "Restore the files even if they changed after the replacement, discarding those edits; they are listed in overwritten_files" }
+                        },
+                        "required": ["apply_id"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "list_applied_changes".into(),
+                    description: Some("List the file_replace operations recorded in the undo directory, newest first, with their apply_id, pattern, replacement and the files they wrote. Use with rollback_replace.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "limit": { "type": "integer", "minimum": 1, "default": 20, "description": "Maximum number of changes to return" },
+                            "include_rolled_back": { "type": "boolean", "default": false, "description": "Also list changes that were already rolled back" }
+                        }
                     })).unwrap()),
                     annotations: None,
                 },
@@ -113,13 +672,39 @@ impl ToolService {
                     input_schema: Arc::new(serde_json::from_value(serde_json::json!({ "type": "object", "properties": {} })).unwrap()),
                     annotations: None,
                 },
+                Tool {
+                    name: "create_playground".into(),
+                    description: Some("Write a small sample project (JavaScript, TypeScript, Python and Rust files with deliberate lint findings, plus rules and an sgconfig.yml) below a root directory, to try search, replace and rules safely before touching real code. Returns the files written and ready-to-run tool calls that each find something in it.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "directory": { "type": "string", "default": "ast-grep-playground", "description": "Directory for the project (relative to the first root directory)" },
+                            "overwrite": { "type": "boolean", "default": false, "description": "Write into a directory that already has files, replacing the sample files" }
+                        }
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "detect_language".into(),
+                    description: Some("Detect the language a file path, extension or code snippet is parsed as: by extension, else by the interpreter of a #! line, else by which grammars parse the code without errors and recognize its keywords. Returns the language, the method and a confidence, plus the extensions and the path_pattern covering the language's files. Use it to see what language=\"auto\" searches make of a file.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "path": { "type": "string", "description": "File path; a file below a root directory is read for its #! line when the extension decides nothing" },
+                            "extension": { "type": "string", "description": "File extension, with or without the dot (e.g. \"tsx\")" },
+                            "code": { "type": "string", "description": "Code snippet to detect the language of" }
+                        }
+                    })).unwrap()),
+                    annotations: None,
+                },
                 Tool {
                     name: "rule_search".into(),
-                    description: Some("Search using ast-grep YAML rule configurations. Rules support complex patterns with conditions, constraints, and relational matching. More powerful than simple patterns - use for advanced searches requiring logical conditions or multiple pattern combinations.".into()),
+                    description: Some("Search using ast-grep YAML rule configurations. Rules support complex patterns with conditions, constraints, and relational matching. More powerful than simple patterns - use for advanced searches requiring logical conditions or multiple pattern combinations. Pass a rule pack name instead of a rule_config to run every rule of the pack.".into()),
                     input_schema: Arc::new(serde_json::from_value(serde_json::json!({
                         "type": "object",
                         "properties": {
-                            "rule_config": { "type": "string", "description": "YAML rule configuration with id, language, rule (pattern/kind/regex), and optional constraints" },
+                            "rule_config": { "type": "string", "description": "YAML rule configuration with id, language, rule (pattern/kind/regex), and optional constraints. Required unless pack is given" },
+                            "pack": { "type": "string", "description": "Run every stored rule of this rule pack (see import_rules) instead of rule_config. Each match carries the rule_id of the rule that found it. Without path_pattern, each rule searches the files of its own language; rule directories are never searched. The sarif output format is not available" },
                             "path_pattern": { "type": "string", "description": "Glob pattern for files to search (optional, searches all files if not provided)" },
                             "max_results": { "type": "integer", "minimum": 1, "maximum": 10000 },
                             "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824 },
@@ -130,9 +715,18 @@ impl ToolService {
                                     "is_complete": { "type": "boolean" }
                                 },
                                 "required": ["last_file_path", "is_complete"]
-                            }
-                        },
-                        "required": ["rule_config"]
+                            },
+                            "output_file": { "type": "string", "description": "Workspace path to write the full JSON result to; only a summary and the path are returned" },
+                            "workspace": { "type": "string", "description": "Only search the root directory with this workspace label (see --workspace). Results label each file with its workspace." },
+                            "max_depth": { "type": "integer", "minimum": 0, "description": "How many directory levels below each root to walk (default: the server's --max-depth)" },
+                            "prune_dirs": { "type": "array", "items": { "type": "string" }, "description": "Directory names never descended into, e.g. [\"node_modules\", \"target\"] (default: the server's --prune-dir list)" },
+                            "follow_hidden": { "type": "boolean", "description": "Whether to walk hidden files and directories whose names start with a dot (default: true unless the server runs with --skip-hidden)" },
+                            "respect_gitignore": { "type": "boolean", "description": "Whether to leave out files excluded by .gitignore, .ignore and .git/info/exclude, and the .git directory (default: true unless the server runs with --no-gitignore)" },
+                            "compress": { "type": "string", "enum": ["gzip+base64"], "description": "Return the full result JSON gzipped and base64-encoded as one blob next to the summary, instead of a truncated or paginated JSON result. Accepted by every tool" },
+                            "timeout_ms": { "type": "integer", "minimum": 0, "description": "Stop evaluating the rule after this many milliseconds (can only lower the server's --rule-timeout-ms). The result is then marked budget_exceeded and next_cursor resumes after the file the search stopped in. Files not reached in time, or after the client cancels the request, are left for next_cursor and the result is marked timed_out" },
+                            "max_matches": { "type": "integer", "minimum": 0, "description": "Stop once the rule has matched this many times (can only lower the server's --rule-max-matches). Matches past the limit are dropped and the result is marked budget_exceeded" },
+                            "output_format": { "type": "string", "enum": ["text", "json", "sarif"], "default": "text", "description": "text: a summary and the result JSON. json: only the result JSON. sarif: a SARIF 2.1.0 log with the rule id, level, message, file URI and region of each finding, for GitHub code scanning and CI dashboards; with output_file the SARIF log is what gets written" }
+                        }
                     })).unwrap()),
                     annotations: None,
                 },
@@ -142,12 +736,21 @@ impl ToolService {
                     input_schema: Arc::new(serde_json::from_value(serde_json::json!({
                         "type": "object",
                         "properties": {
-                            "rule_config": { "type": "string", "description": "YAML rule configuration with id, language, rule, and fix field for replacements" },
+                            "rule_config": { "type": "string", "description": "YAML rule configuration with id, language, rule, and fix field for replacements. An optional fix_safety (safe | suggestion | unsafe, default safe) declares how safe the fix is to apply automatically" },
                             "path_pattern": { "type": "string", "description": "Glob pattern for files to modify (optional, processes all files if not provided)" },
                             "max_results": { "type": "integer", "minimum": 1, "maximum": 10000 },
                             "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824 },
                             "dry_run": { "type": "boolean", "default": true, "description": "If true (default), only show preview. If false, actually modify files." },
                             "summary_only": { "type": "boolean", "default": false, "description": "If true, only return summary statistics" },
+                            "allow_unsafe": { "type": "boolean", "default": false, "description": "Apply fixes from rules declaring fix_safety: unsafe. Without it, their dry run lists the changes under unsafe_fixes and applying is refused." },
+                            "ignore_whitespace_changes": { "type": "boolean", "default": true, "description": "Treat files whose fix only changes trailing whitespace, trailing blank lines or line endings as unchanged: they are not reported and not written, so formatting-only churn stays out of diffs. Set to false to keep such rewrites" },
+                            "timeout_ms": { "type": "integer", "minimum": 0, "description": "Stop after this many milliseconds, or when the client cancels the request, and return the fixes made so far marked timed_out. next_cursor resumes with the files not yet reached" },
+                            "workspace": { "type": "string", "description": "Only modify files under the root directory with this workspace label (see --workspace), so an edit can't reach another project. Results label each file with its workspace." },
+                            "max_depth": { "type": "integer", "minimum": 0, "description": "How many directory levels below each root to walk (default: the server's --max-depth)" },
+                            "prune_dirs": { "type": "array", "items": { "type": "string" }, "description": "Directory names never descended into, e.g. [\"node_modules\", \"target\"] (default: the server's --prune-dir list)" },
+                            "follow_hidden": { "type": "boolean", "description": "Whether to walk hidden files and directories whose names start with a dot (default: true unless the server runs with --skip-hidden)" },
+                            "respect_gitignore": { "type": "boolean", "description": "Whether to leave out files excluded by .gitignore, .ignore and .git/info/exclude, and the .git directory (default: true unless the server runs with --no-gitignore)" },
+                            "compress": { "type": "string", "enum": ["gzip+base64"], "description": "Return the full result JSON gzipped and base64-encoded as one blob next to the summary, instead of a truncated or paginated JSON result. Accepted by every tool" },
                             "cursor": {
                                 "type": "object",
                                 "properties": {
@@ -161,9 +764,111 @@ impl ToolService {
                     })).unwrap()),
                     annotations: None,
                 },
+                Tool {
+                    name: "scan".into(),
+                    description: Some("Run the stored rules over a codebase and group the findings by rule severity (error, warning, info; rules without a severity count as info). Each bucket reports its total and per-rule counts up front and lists one page of findings; pass severity and offset to page through a single bucket. Error findings are also sent to MCP clients as error-level log messages while the scan runs.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "path_pattern": { "type": "string", "description": "Glob pattern for files to scan (optional, scans all files if not provided)" },
+                            "rule_ids": { "type": "array", "items": { "type": "string" }, "description": "Stored rules to run (default: all stored rules)" },
+                            "severity": { "type": "string", "enum": ["error", "warning", "info"], "description": "Only list findings of this bucket; the other buckets still report their counts" },
+                            "page_size": { "type": "integer", "minimum": 1, "default": 20, "description": "Findings listed per bucket" },
+                            "offset": { "type": "integer", "minimum": 0, "default": 0, "description": "Findings of the severity bucket to skip, from its next_offset" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824 },
+                            "workspace": { "type": "string", "description": "Only scan the root directory with this workspace label (see --workspace)" },
+                            "output_file": { "type": "string", "description": "Workspace path to write the full JSON result to; only a summary and the path are returned" },
+                            "max_depth": { "type": "integer", "minimum": 0, "description": "How many directory levels below each root to walk (default: the server's --max-depth)" },
+                            "prune_dirs": { "type": "array", "items": { "type": "string" }, "description": "Directory names never descended into, e.g. [\"node_modules\", \"target\"] (default: the server's --prune-dir list)" },
+                            "follow_hidden": { "type": "boolean", "description": "Whether to walk hidden files and directories whose names start with a dot (default: true unless the server runs with --skip-hidden)" },
+                            "respect_gitignore": { "type": "boolean", "description": "Whether to leave out files excluded by .gitignore, .ignore and .git/info/exclude, and the .git directory (default: true unless the server runs with --no-gitignore)" },
+                            "compress": { "type": "string", "enum": ["gzip+base64"], "description": "Return the full result JSON gzipped and base64-encoded as one blob next to the summary, instead of a truncated or paginated JSON result. Accepted by every tool" }
+                        }
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "lint_workspace".into(),
+                    description: Some("Run every stored rule over the workspace in one call and report whether it passes: the lint fails when a finding is at least as severe as fail_on (error by default, or warning). Returns counts per severity and per rule, and one page of findings across all severities, errors first; pass offset from next_offset for the next page.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "path_pattern": { "type": "string", "description": "Glob pattern for files to lint (optional, lints all files if not provided)" },
+                            "fail_on": { "type": "string", "enum": ["error", "warning"], "default": "error", "description": "Least severe findings that fail the lint" },
+                            "page_size": { "type": "integer", "minimum": 1, "default": 20, "description": "Findings listed per page" },
+                            "offset": { "type": "integer", "minimum": 0, "default": 0, "description": "Findings to skip, from next_offset" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824 },
+                            "workspace": { "type": "string", "description": "Only lint the root directory with this workspace label (see --workspace)" },
+                            "max_depth": { "type": "integer", "minimum": 0, "description": "How many directory levels below each root to walk (default: the server's --max-depth)" },
+                            "prune_dirs": { "type": "array", "items": { "type": "string" }, "description": "Directory names never descended into, e.g. [\"node_modules\", \"target\"] (default: the server's --prune-dir list)" },
+                            "follow_hidden": { "type": "boolean", "description": "Whether to walk hidden files and directories whose names start with a dot (default: true unless the server runs with --skip-hidden)" },
+                            "respect_gitignore": { "type": "boolean", "description": "Whether to leave out files excluded by .gitignore, .ignore and .git/info/exclude, and the .git directory (default: true unless the server runs with --no-gitignore)" }
+                        }
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "run_codemod".into(),
+                    description: Some("Run a built-in JavaScript/TypeScript codemod selected by name and configured with params. Codemods: rename_import_source {from, to} rewrites imports, re-exports, require() and import() of a module; wrap_calls_with_try_catch {callee, handler?} wraps call statements of a function in try/catch (the caught value is `error`); add_argument_with_default {function, arity, default} appends `default` to calls passing exactly `arity` arguments; convert_default_export_to_named {name?} turns `export default` of a named function, class or identifier into a named export. Each codemod runs as generated rules through rule_replace, which are returned per step. Dry run by default.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "codemod": { "type": "string", "enum": ["rename_import_source", "wrap_calls_with_try_catch", "add_argument_with_default", "convert_default_export_to_named"], "description": "Codemod to run" },
+                            "params": {
+                                "type": "object",
+                                "description": "Parameters of the codemod, as listed in the tool description ({} when it needs none)",
+                                "properties": {
+                                    "from": { "type": "string", "description": "rename_import_source: module specifier to replace" },
+                                    "to": { "type": "string", "description": "rename_import_source: module specifier to use instead" },
+                                    "callee": { "type": "string", "description": "wrap_calls_with_try_catch: function whose calls are wrapped, e.g. 'api.save'" },
+                                    "handler": { "type": "string", "default": "console.error(error);", "description": "wrap_calls_with_try_catch: statements for the catch block" },
+                                    "function": { "type": "string", "description": "add_argument_with_default: function whose calls get the new argument" },
+                                    "arity": { "type": "integer", "minimum": 0, "description": "add_argument_with_default: number of arguments the calls pass today" },
+                                    "default": { "type": "string", "description": "add_argument_with_default: expression passed as the new last argument" },
+                                    "name": { "type": "string", "description": "convert_default_export_to_named: only convert the default export of this identifier" }
+                                }
+                            },
+                            "language": { "type": "string", "enum": ["javascript", "typescript", "tsx"], "default": "typescript" },
+                            "path_pattern": { "type": "string", "description": "Glob pattern for files to rewrite (default: the file extensions of language)" },
+                            "dry_run": { "type": "boolean", "default": true, "description": "Preview the changes without writing them" },
+                            "max_results": { "type": "integer", "minimum": 1, "default": 10000, "description": "Maximum changes per generated rule" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824 },
+                            "workspace": { "type": "string", "description": "Only rewrite the root directory with this workspace label (see --workspace)" }
+                        },
+                        "required": ["codemod", "params"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "plan_directory_move".into(),
+                    description: Some("Plan moving a directory without touching any file. Returns every file to move and every relative import path the move would break, with its new spelling: JavaScript/TypeScript import, export-from, require() and import() specifiers starting with ./ or ../, and C/C++ #include \"...\" paths next to the including file. Imports between moved files are unaffected and not listed. Review the plan, then pass it unchanged to apply_plan.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "source": { "type": "string", "description": "Directory to move, absolute or relative to the root directories" },
+                            "destination": { "type": "string", "description": "New location, absolute or relative to the first root directory; must not exist yet" },
+                            "max_file_size": { "type": "integer", "minimum": 1024, "maximum": 1073741824 },
+                            "workspace": { "type": "string", "description": "Only look at the root directory with this workspace label (see --workspace)" }
+                        },
+                        "required": ["source", "destination"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "apply_plan".into(),
+                    description: Some("Execute a plan returned by plan_directory_move: rewrite the planned import paths, move the files and remove the directories left empty. The plan is checked against the files first, so nothing is written if any file changed since planning; if a write or move fails partway, the steps already taken are undone.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "plan": { "type": "object", "description": "The plan returned by plan_directory_move, unchanged" }
+                        },
+                        "required": ["plan"]
+                    })).unwrap()),
+                    annotations: None,
+                },
                 Tool {
                     name: "validate_rule".into(),
-                    description: Some("Validate ast-grep YAML rule syntax and test against sample code. Use this to verify rule configurations before using them in rule_search or rule_replace. Returns validation errors or successful match results.".into()),
+                    description: Some("Validate ast-grep YAML rule syntax and test against sample code. Use this to verify rule configurations before using them in rule_search or rule_replace. Returns validation errors or successful match results; for rules with a fix, also the test code as the fix would rewrite it, with hunk diffs.".into()),
                     input_schema: Arc::new(serde_json::from_value(serde_json::json!({
                         "type": "object",
                         "properties": {
@@ -174,13 +879,40 @@ impl ToolService {
                     })).unwrap()),
                     annotations: None,
                 },
+                Tool {
+                    name: "validate_rules".into(),
+                    description: Some("Health-check many rules in one call. Checks each configuration in rule_configs, or every stored rule file with all_stored: true (including files that fail to parse), and returns per-rule validity, errors and lint warnings: patterns that don't compile or parse cleanly, lowercase metavariables that match literally, fix templates using uncaptured metavariables and duplicate rule ids. Rules with sample code in test_code are also run against it.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "rule_configs": { "type": "array", "items": { "type": "string" }, "description": "YAML or JSON rule configurations to check" },
+                            "all_stored": { "type": "boolean", "default": false, "description": "Also check every rule file in the rule directories" },
+                            "test_code": { "type": "object", "additionalProperties": { "type": "string" }, "description": "Sample code to run rules against, keyed by rule id" }
+                        }
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "explain_rule_evaluation".into(),
+                    description: Some("Debug a rule against one file: returns a step-by-step trace of what each sub-rule matched, which candidates every condition of an `all` pruned, conditions ignored because a rule object only evaluates its first one, and a plain-language explanation of the final match set.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "rule_config": { "type": "string", "description": "YAML or JSON rule configuration, as passed to rule_search" },
+                            "file_path": { "type": "string", "description": "File to evaluate the rule against, absolute or relative to a root directory" },
+                            "max_nodes_per_step": { "type": "integer", "minimum": 0, "default": 10, "description": "Nodes listed per step; the rest are only counted" }
+                        },
+                        "required": ["rule_config", "file_path"]
+                    })).unwrap()),
+                    annotations: None,
+                },
                 Tool {
                     name: "create_rule".into(),
                     description: Some("Create and store a new ast-grep rule configuration for reuse. Build a library of custom rules for common patterns. Stored rules can be retrieved with get_rule and deleted with delete_rule.".into()),
                     input_schema: Arc::new(serde_json::from_value(serde_json::json!({
                         "type": "object",
                         "properties": {
-                            "rule_config": { "type": "string", "description": "Complete YAML rule configuration with id, language, rule, and optional fix/constraints" },
+                            "rule_config": { "type": "string", "description": "Complete YAML rule configuration with id, language, rule, and optional fix/constraints/tags" },
                             "overwrite": { "type": "boolean", "default": false, "description": "Whether to overwrite existing rule with same ID" }
                         },
                         "required": ["rule_config"]
@@ -194,11 +926,81 @@ impl ToolService {
                         "type": "object",
                         "properties": {
                             "language": { "type": "string", "description": "Filter rules by programming language" },
-                            "severity": { "type": "string", "description": "Filter rules by severity level (info, warning, error)" }
+                            "severity": { "type": "string", "description": "Filter rules by severity level (info, warning, error)" },
+                            "tag": { "type": "string", "description": "Only list rules whose `tags` include this tag" }
                         }
                     })).unwrap()),
                     annotations: None,
                 },
+                Tool {
+                    name: "rule_stats".into(),
+                    description: Some("Report per-rule effectiveness statistics recorded across rule_search and rule_replace runs: scans, matches, applied fixes and matches suppressed with `ast-grep-ignore` comments. Flags rules that never fire, rules generating excessive matches, and stored rules that were never run.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "rule_id": { "type": "string", "description": "Only report statistics for this rule" },
+                            "min_scans": { "type": "integer", "minimum": 1, "default": 5, "description": "Scans without a match before a rule is flagged as never firing" },
+                            "noise_threshold": { "type": "number", "minimum": 0, "default": 50, "description": "Average matches per scan above which a rule is flagged as noisy" }
+                        }
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "convert_rule".into(),
+                    description: Some("Convert existing Semgrep rules or ESLint configs into ast-grep rule configurations to ease migrating a lint suite. Pattern-based rules are translated (ellipses become $$$); constructs without an equivalent are listed under `unconverted` with the reason. Review the output with validate_rule, then store it with create_rule.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "source": { "type": "string", "description": "Semgrep rule YAML (with a `rules:` list) or ESLint config YAML/JSON (with a `rules:` mapping)" },
+                            "format": { "type": "string", "enum": ["semgrep", "eslint"], "description": "Source format; detected when omitted" },
+                            "language": { "type": "string", "description": "Target language for ESLint rules (default: javascript)" }
+                        },
+                        "required": ["source"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "export_to_sgconfig".into(),
+                    description: Some("Write stored rules into a standard ast-grep project inside the workspace: an sgconfig.yml listing a rules/ directory with one YAML file per rule, ready for `ast-grep scan` in CI.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "output_dir": { "type": "string", "default": ".", "description": "Workspace directory for sgconfig.yml and rules/ (relative to the first root directory)" },
+                            "rule_ids": { "type": "array", "items": { "type": "string" }, "description": "Only export these rules (default: all stored rules)" },
+                            "overwrite": { "type": "boolean", "default": false, "description": "Replace rule files with different content and add rules/ to an existing sgconfig.yml" }
+                        }
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "export_rules".into(),
+                    description: Some("Write stored rules, as written and with their ids, to one multi-document YAML file (rules separated by ---) or to a directory with one file per rule, for sharing a rule pack or importing it into another project with import_rules.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "output_path": { "type": "string", "description": "Workspace path to write to (relative to the first root directory). A .yaml or .yml path gets a single multi-document file, any other path a directory of rule files" },
+                            "rule_ids": { "type": "array", "items": { "type": "string" }, "description": "Only export these rules (default: all stored rules)" },
+                            "pack": { "type": "string", "description": "Only export the rules of this rule pack" },
+                            "overwrite": { "type": "boolean", "default": false, "description": "Replace an existing output file, or rule files in the output directory with different content" }
+                        },
+                        "required": ["output_path"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "import_rules".into(),
+                    description: Some("Store the rules of a YAML file, which may hold several rules separated by ---, or of a directory of rule files, keeping their ids. With pack, the rules are stored together as a rule pack that rule_search and export_rules can refer to by name. Nothing is stored when a rule doesn't parse or already exists without overwrite.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "path": { "type": "string", "description": "Rule file or directory inside a root directory (absolute or relative to a root directory)" },
+                            "pack": { "type": "string", "description": "Store the rules as this rule pack; letters, digits, '-', '_' and '.' (default: each rule on its own)" },
+                            "overwrite": { "type": "boolean", "default": false, "description": "Replace stored rules with the same ids" }
+                        },
+                        "required": ["path"]
+                    })).unwrap()),
+                    annotations: None,
+                },
                 Tool {
                     name: "get_rule".into(),
                     description: Some("Retrieve a specific stored rule configuration by its ID. Returns the complete YAML rule configuration that can be used directly with rule_search or rule_replace tools.".into()),
@@ -223,6 +1025,30 @@ impl ToolService {
                     })).unwrap()),
                     annotations: None,
                 },
+                Tool {
+                    name: "push_rule".into(),
+                    description: Some("Publish a stored rule to the team rule registry the server was started with (--rule-registry). If a teammate changed the rule in the registry since it was last pulled, nothing is written and the registry's copy is returned as a conflict.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "rule_id": { "type": "string", "description": "ID of the stored rule to publish" },
+                            "force": { "type": "boolean", "default": false, "description": "Replace the registry's copy even if it changed since it was last pulled" }
+                        },
+                        "required": ["rule_id"]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "pull_rules".into(),
+                    description: Some("Store the team rule registry's rules locally. Rules changed only in the registry replace the local ones; rules changed only locally are kept for push_rule. Rules changed on both sides since the last sync are conflicts, settled by on_conflict.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "on_conflict": { "type": "string", "enum": ["report", "keep_local", "take_remote"], "default": "report", "description": "report: leave conflicting rules alone; keep_local: keep them so the next push_rule replaces the registry's copy; take_remote: replace them with the registry's copy" }
+                        }
+                    })).unwrap()),
+                    annotations: None,
+                },
                 Tool {
                     name: "generate_ast".into(),
                     description: Some("Generate Abstract Syntax Tree for code and discover Tree-sitter node kinds. Essential for writing Kind-based rules - shows exact node types like function_declaration, identifier, call_expression. Use when you need to know the precise AST structure for advanced pattern matching.".into()),
@@ -230,12 +1056,47 @@ impl ToolService {
                         "type": "object",
                         "properties": {
                             "code": { "type": "string", "description": "Source code to parse and generate AST for" },
-                            "language": { "type": "string", "description": "Programming language for correct AST generation" }
+                            "language": { "type": "string", "description": "Programming language for correct AST generation" },
+                            "buffer_id": { "type": "string", "description": "Parse a code buffer from begin_code_buffer instead of code" }
                         },
-                        "required": ["code", "language"]
+                        "required": ["language"],
+                        "anyOf": [{ "required": ["code"] }, { "required": ["buffer_id"] }]
+                    })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "anonymize_result".into(),
+                    description: Some("Anonymize a result from another tool (e.g. search or file_search) before sharing it in a bug report or with an external service. Identifiers, string literal contents and file paths are replaced by consistent placeholders (the same name always gets the same placeholder) and comments by stubs, while the surrounding syntax is kept, so `user.save(id)` becomes `id1.id2(id3)`. Match texts, captured variables, context lines, enclosing symbol names and replacement texts are rewritten; file hashes are dropped.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "result": { "type": "object", "description": "The result JSON returned by another tool" },
+                            "language": { "type": "string", "description": "Language of the code; needed only for code that isn't under a file_path" },
+                            "keep_identifiers": { "type": "array", "items": { "type": "string" }, "description": "Identifiers to leave as they are, such as public API names" },
+                            "include_mapping": { "type": "boolean", "default": false, "description": "Also return each placeholder's original text, to read replies about the anonymized result" }
+                        },
+                        "required": ["result"]
                     })).unwrap()),
                     annotations: None,
                 },
+                Tool {
+                    name: "index_status".into(),
+                    description: Some("Report on the file index that a server started with --index keeps between searches: how many files it lists, how many parsed syntax trees it holds, whether it watches the root directories for changes, and how often file_search reused a kept tree. With the index, file_search takes its file list from memory instead of walking the root directories, and skips parsing files that haven't changed since they were last searched.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({ "type": "object", "properties": {} })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "health_check".into(),
+                    description: Some("Troubleshoot the server's setup. Checks that every root directory exists and can be listed, that the rules directory (or rules database), the pattern coverage directory and the undo directory can be written, and that every language list_languages advertises has a grammar that loads. Returns healthy, the error and warning counts, and each check with its status (ok, warning or error), the path checked and what was found. Directories that don't exist yet are a warning when they can be created on first use. Nothing is created or changed.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({ "type": "object", "properties": {} })).unwrap()),
+                    annotations: None,
+                },
+                Tool {
+                    name: "rebuild_index".into(),
+                    description: Some("Drop the file lists and syntax trees of the file index and walk the root directories again. Needed only when files changed without the watcher noticing, e.g. on a network file system or in a build without file watching. Fails when the server was started without --index.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({ "type": "object", "properties": {} })).unwrap()),
+                    annotations: None,
+                },
                 Tool {
                     name: "validate_pattern".into(),
                     description: Some("Validate AST patterns with intelligent learning hints and insights. Provides dynamic error messages that help LLMs understand pattern syntax and offers guided learning suggestions for pattern improvement.".into()),
@@ -266,6 +1127,32 @@ impl ToolService {
                     })).unwrap()),
                     annotations: None,
                 },
+                Tool {
+                    name: "suggest_pattern".into(),
+                    description: Some("Synthesize search and replacement patterns from before/after code examples. Each example's change is located in its syntax tree, and code it keeps becomes metavariables. Returns ranked candidates with how each one fares on every example, to refine and pass to file_replace.".into()),
+                    input_schema: Arc::new(serde_json::from_value(serde_json::json!({
+                        "type": "object",
+                        "properties": {
+                            "examples": {
+                                "type": "array",
+                                "minItems": 1,
+                                "items": {
+                                    "type": "object",
+                                    "properties": {
+                                        "before": { "type": "string", "description": "Code before the change" },
+                                        "after": { "type": "string", "description": "The same code after the change" }
+                                    },
+                                    "required": ["before", "after"]
+                                },
+                                "description": "Instances of the change to make; several examples let candidates generalize over what differs between them"
+                            },
+                            "language": { "type": "string", "description": "Programming language of the examples" },
+                            "max_candidates": { "type": "integer", "minimum": 1, "default": 5, "description": "Maximum number of candidates to return" }
+                        },
+                        "required": ["examples", "language"]
+                    })).unwrap()),
+                    annotations: None,
+                },
                 Tool {
                     name: "analyze_refactoring".into(),
                     description: Some("Analyze code fragments for extract-function refactoring potential. Performs comprehensive dependency analysis including variable capture, return value inference, side effect detection, and generates suggested function signatures. Essential for safe code extraction and refactoring planning.".into()),
@@ -402,7 +1289,6 @@ impl ToolService {
             "cpp",
             "csharp",
             "css",
-            "dart",
             "elixir",
             "go",
             "haskell",
@@ -18,11 +18,17 @@
 //! - Commas must be explicitly included in replacement patterns
 //! - Struct update syntax (`..Default::default()`) must come last in Rust patterns
 
-use ast_grep_core::NodeMatch;
-use ast_grep_core::tree_sitter::StrDoc;
-use ast_grep_language::SupportLang as Language;
+use crate::codemods::Codemod;
+use crate::coverage::CoverageSnapshot;
+use crate::enclosing_symbol::{EnclosingSymbol, enclosing_symbol};
+use crate::format_check::FormatCheck;
+use crate::json_patch::JsonPatchOperation;
+use crate::rules::FixSafety;
+use crate::verification::VerificationOutcome;
+use ast_grep_core::tree_sitter::{LanguageExt, StrDoc};
+use ast_grep_core::{Node, NodeMatch};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// Controls how strictly patterns match against the syntax tree.
 ///
@@ -79,12 +85,19 @@ impl From<MatchStrictness> for ast_grep_core::MatchStrictness {
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct SearchParam {
-    /// The source code to search in
+    /// The source code to search in; empty when `buffer_id` is given
+    #[serde(default)]
     pub code: String,
+    /// Search the content of a code buffer (see `begin_code_buffer`) instead of `code`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_id: Option<String>,
     /// The ast-grep pattern to match (e.g., "console.log($VAR)")
     pub pattern: String,
     /// Programming language (e.g., "javascript", "rust", "python")
     pub language: String,
+    /// Grammar variant to parse with, e.g. "tsx" for TypeScript containing JSX
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dialect: Option<String>,
     /// How strictly to match the pattern (default: Smart)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub strictness: Option<MatchStrictness>,
@@ -103,6 +116,16 @@ pub struct SearchParam {
     /// Number of lines to include both before and after each match
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context_lines: Option<usize>,
+    /// Return the enclosing statement, function or class of each match in `enclosing_node`
+    /// instead of context lines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_scope: Option<ContextScope>,
+    /// Attach the kinds of each match's node, parent and named children (default: false)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_ast_context: bool,
+    /// Attach the comments and attributes directly above each match (default: false)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_leading_trivia: bool,
 }
 
 impl SearchParam {
@@ -153,6 +176,105 @@ pub struct MatchResult {
     /// Lines of context after the match (if requested)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context_after: Option<Vec<String>>,
+    /// The function, method or class the match is inside
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enclosing_symbol: Option<EnclosingSymbol>,
+    /// Node kinds around the match (if requested with `include_ast_context`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ast_context: Option<AstContext>,
+    /// Comments and attributes directly above the match, in source order (if requested with
+    /// `include_leading_trivia`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub leading_trivia: Vec<LeadingTrivia>,
+    /// The statement, function or class containing the match (if requested with
+    /// `context_scope`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enclosing_node: Option<EnclosingNode>,
+    /// Fixes from stored rules that match overlapping code (if requested with `suggest_fixes`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quick_fixes: Vec<QuickFix>,
+    /// The stored rule that found the match, when `rule_search` ran a rule pack
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_id: Option<String>,
+}
+
+/// A stored rule's fix for code overlapping a search match. The rule's own match may be larger
+/// or smaller than the search match, so its span is given separately.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuickFix {
+    pub rule_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub fix_safety: FixSafety,
+    /// Span of the rule's match (0-based lines and columns)
+    pub start_line: usize,
+    pub end_line: usize,
+    pub start_col: usize,
+    pub end_col: usize,
+    /// The code the rule matched
+    pub old_text: String,
+    /// The code after applying the rule's fix
+    pub new_text: String,
+}
+
+/// Syntax-tree neighbourhood of a match, for telling apart nodes a pattern can't, such as a
+/// call and a `new` expression.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AstContext {
+    /// Kind of the matched node
+    pub kind: String,
+    /// Kind of its parent node; absent for the root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_kind: Option<String>,
+    /// Kinds of its named children, in source order
+    pub child_kinds: Vec<String>,
+}
+
+/// Which enclosing node `context_scope` returns for each match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextScope {
+    /// The statement or declaration containing the match
+    Statement,
+    /// The function, method or closure containing the match
+    Function,
+    /// The class, struct, enum, trait, interface or impl block containing the match
+    Class,
+}
+
+/// The smallest node of a `context_scope` that contains a match, possibly the match itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnclosingNode {
+    /// Node kind, e.g. `expression_statement` or `function_declaration`
+    pub kind: String,
+    /// Starting line number (0-based)
+    pub start_line: usize,
+    /// Ending line number (0-based)
+    pub end_line: usize,
+    pub text: String,
+}
+
+/// A comment or attribute attached above a match, such as a doc comment, a Rust attribute or
+/// a decorator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeadingTrivia {
+    pub kind: TriviaKind,
+    /// Node kind, e.g. `line_comment` or `attribute_item`
+    pub node_kind: String,
+    /// Starting line number (0-based)
+    pub start_line: usize,
+    /// Ending line number (0-based)
+    pub end_line: usize,
+    pub text: String,
+}
+
+/// What a [`LeadingTrivia`] node is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriviaKind {
+    Comment,
+    /// An attribute, annotation or decorator
+    Attribute,
 }
 
 impl MatchResult {
@@ -160,7 +282,7 @@ impl MatchResult {
     ///
     /// Extracts position information, matched text, and captured metavariables
     /// from the ast-grep NodeMatch structure.
-    pub fn from_node_match(node: &NodeMatch<StrDoc<Language>>) -> Self {
+    pub fn from_node_match<L: LanguageExt>(node: &NodeMatch<StrDoc<L>>) -> Self {
         let vars: HashMap<String, String> = node.get_env().clone().into();
         let start_pos = node.get_node().start_pos();
         let end_pos = node.get_node().end_pos();
@@ -174,144 +296,1749 @@ impl MatchResult {
             vars,
             context_before: None,
             context_after: None,
+            enclosing_symbol: enclosing_symbol(node.get_node()),
+            ast_context: None,
+            enclosing_node: None,
+            quick_fixes: Vec::new(),
+            leading_trivia: Vec::new(),
+            rule_id: None,
+        }
+    }
+
+    /// Attach the kinds of `node`, its parent and its named children
+    pub fn with_ast_context<L: LanguageExt>(mut self, node: &Node<StrDoc<L>>) -> Self {
+        self.ast_context = Some(AstContext {
+            kind: node.kind().to_string(),
+            parent_kind: node.parent().map(|parent| parent.kind().to_string()),
+            child_kinds: node
+                .children()
+                .filter(|child| child.is_named())
+                .map(|child| child.kind().to_string())
+                .collect(),
+        });
+        self
+    }
+
+    /// Add context lines before and after this match.
+    ///
+    /// This is used by the context lines feature to provide surrounding code
+    /// for better understanding of the match location.
+    pub fn with_context(
+        mut self,
+        context_before: Option<Vec<String>>,
+        context_after: Option<Vec<String>>,
+    ) -> Self {
+        self.context_before = context_before;
+        self.context_after = context_after;
+        self
+    }
+}
+
+/// Parameters for searching patterns across multiple files.
+///
+/// Supports both glob patterns (e.g., `**/*.js`) and direct file paths.
+/// Results are paginated using cursor-based pagination for large result sets.
+///
+/// # Example
+///
+/// ```rust
+/// use ast_grep_mcp::FileSearchParam;
+///
+/// let param = FileSearchParam {
+///     path_pattern: "src/**/*.js".to_string(),
+///     pattern: "console.log($VAR)".to_string(),
+///     language: "javascript".to_string(),
+///     max_results: 10,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSearchParam {
+    /// Glob pattern ("src/**/*.js") or direct file path ("/path/to/file.js")
+    pub path_pattern: String,
+    /// The ast-grep pattern to match
+    #[serde(default)]
+    pub pattern: String,
+    /// Programming language; empty or `auto` detects each file's language from its extension
+    /// or `#!` line
+    #[serde(default)]
+    pub language: String,
+    /// Grammar variant to parse every file with. When unset, TypeScript-family files are
+    /// parsed with the grammar their extension implies (`.tsx` with TSX)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dialect: Option<String>,
+    /// One pattern per language, used instead of `pattern` and `language`. Each file is
+    /// matched with the pattern for the language its extension implies
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub language_patterns: HashMap<String, String>,
+    /// Maximum number of matches to return (default: 20)
+    #[serde(default = "default_max_results")]
+    pub max_results: usize,
+    /// Maximum file size to process in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+    /// Pagination cursor for continuing previous search
+    pub cursor: Option<CursorParam>,
+    /// How strictly to match the pattern
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strictness: Option<MatchStrictness>,
+    /// CSS-like selector to filter matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// Additional rule context (YAML)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// Number of lines to include before each match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_before: Option<usize>,
+    /// Number of lines to include after each match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_after: Option<usize>,
+    /// Number of lines to include both before and after each match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_lines: Option<usize>,
+    /// Return the enclosing statement, function or class of each match in `enclosing_node`
+    /// instead of context lines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_scope: Option<ContextScope>,
+    /// Write the full JSON result to this workspace path and return only a summary
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_file: Option<String>,
+    /// Unsaved editor buffers keyed by file path (absolute or relative to a root directory).
+    /// Their content is matched instead of the file on disk
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub overlays: HashMap<String, String>,
+    /// Also search files inside zip, jar and tar archives matching the glob. Entries are
+    /// reported as `archive.zip!path/inside.js` and are never replaced (default: false)
+    #[serde(default = "default_false")]
+    pub search_archives: bool,
+    /// Limit the call to the root directory with this workspace label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    /// When `language` has no tree-sitter grammar, match `pattern` as a regular expression
+    /// line by line instead of failing (default: false)
+    #[serde(default = "default_false")]
+    pub regex_fallback: bool,
+    /// Attach the kinds of each match's node, parent and named children (default: false)
+    #[serde(default = "default_false")]
+    pub include_ast_context: bool,
+    /// Attach the comments and attributes directly above each match (default: false)
+    #[serde(default = "default_false")]
+    pub include_leading_trivia: bool,
+    /// Run a pattern that is a lone metavariable such as `$_` or `$A`, which matches nearly
+    /// every node; without it such patterns are refused (default: false)
+    #[serde(default = "default_false")]
+    pub allow_broad: bool,
+    /// Search files as they were at this git commit-ish (tag, branch or hash) instead of the
+    /// working tree. `path_pattern` must be relative to the root directories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+    /// Annotate each match with the fixes of stored rules that match overlapping code
+    /// (default: false)
+    #[serde(default = "default_false")]
+    pub suggest_fixes: bool,
+    /// Send each file's matches to the client in an MCP progress notification as soon as the
+    /// file has been searched, when the request carries a `progressToken`. The final response
+    /// then leaves out the matches already sent (default: false)
+    #[serde(default = "default_false")]
+    pub stream: bool,
+    /// Stop after this many milliseconds, or when the client cancels the request, and return
+    /// the matches found so far with `timed_out` set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Walk depth, pruned directories and hidden entries; unset options use the server's defaults
+    #[serde(flatten)]
+    pub walk: WalkOptions,
+}
+
+impl Default for FileSearchParam {
+    fn default() -> Self {
+        Self {
+            path_pattern: "**/*".to_string(),
+            pattern: String::new(),
+            language: String::new(),
+            dialect: None,
+            language_patterns: Default::default(),
+            max_results: default_max_results(),
+            max_file_size: default_max_file_size(),
+            cursor: None,
+            strictness: None,
+            selector: None,
+            context: None,
+            context_before: None,
+            context_after: None,
+            context_lines: None,
+            context_scope: None,
+            output_file: None,
+            overlays: HashMap::new(),
+            search_archives: false,
+            workspace: None,
+            regex_fallback: false,
+            include_ast_context: false,
+            include_leading_trivia: false,
+            allow_broad: false,
+            revision: None,
+            suggest_fixes: false,
+            stream: false,
+            timeout_ms: None,
+            walk: WalkOptions::default(),
+        }
+    }
+}
+
+/// How the file-based tools walk the root directories. Each option left unset falls back to the
+/// server's configuration (`--max-depth`, `--prune-dir`, `--skip-hidden` and `--no-gitignore`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalkOptions {
+    /// How many directory levels below a root to descend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+    /// Directory names never to descend into, e.g. `["node_modules", "target", "dist"]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prune_dirs: Option<Vec<String>>,
+    /// Walk hidden directories and files, whose names start with a dot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_hidden: Option<bool>,
+    /// Leave out files excluded by `.gitignore`, `.ignore` and `.git/info/exclude`, and the
+    /// `.git` directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub respect_gitignore: Option<bool>,
+}
+
+/// Pagination cursor for continuing file-based operations.
+///
+/// Used internally to track progress through large file sets. Pass back the
+/// `next_cursor` of the previous page unchanged, `token` included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CursorParam {
+    /// Last file processed in the previous page
+    pub last_file_path: String,
+    /// Whether the operation has completed (no more results)
+    pub is_complete: bool,
+    /// Checksum of the call's parameters and root directories, with the time the cursor was
+    /// issued, to catch a cursor reused with another call; see [`crate::cursor`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+/// Result of a file-based pattern search operation.
+///
+/// Contains matches organized by file, with optional pagination cursor for large result sets.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileSearchResult {
+    /// Matches organized by file
+    pub matches: Vec<FileMatchResult>,
+    /// Cursor for fetching next page of results (if any)
+    pub next_cursor: Option<CursorResult>,
+    /// Total number of files searched
+    pub total_files_found: usize,
+    /// Files that crashed or timed out in an isolated worker and were not searched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_files: Vec<SkippedFile>,
+    /// The search stopped early because a rule ran out of time or reached its match limit;
+    /// `next_cursor` resumes after the file it stopped in
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub budget_exceeded: bool,
+    /// The search stopped at `timeout_ms` or because the client cancelled it; `matches` holds
+    /// what was found so far and `next_cursor`, when set, resumes where it stopped
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+    /// Which files were searched and why the others were not (file_search only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<FileScanStats>,
+    /// Configuration problems that affect the results, such as overlapping root directories
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// What a `file_search` looked at, to tell a wrong glob from skipped or unmatched files.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileScanStats {
+    /// Files matching the glob across all pages, including skipped ones
+    pub files_matching_glob: usize,
+    /// Files searched in this call, keyed by extension (`(none)` for files without one)
+    pub searched_by_extension: BTreeMap<String, usize>,
+    /// Searched files with at least one match
+    pub files_with_matches: usize,
+    /// Searched files without a match
+    pub files_without_matches: usize,
+    /// Files matching the glob that are larger than `max_file_size`
+    pub skipped_too_large: usize,
+    /// Files that could not be read as UTF-8 text
+    pub skipped_unreadable: usize,
+    /// Files in a language the search has no pattern or grammar for
+    pub skipped_other_language: usize,
+    /// Files whose isolated worker crashed or timed out, listed in `skipped_files`
+    pub skipped_worker_failures: usize,
+}
+
+impl FileScanStats {
+    /// Count a searched file under its extension
+    pub fn record_searched(&mut self, file_path: &str, matched: bool) {
+        let extension = std::path::Path::new(file_path)
+            .extension()
+            .map(|extension| format!(".{}", extension.to_string_lossy()))
+            .unwrap_or_else(|| "(none)".to_string());
+        *self.searched_by_extension.entry(extension).or_default() += 1;
+        match matched {
+            true => self.files_with_matches += 1,
+            false => self.files_without_matches += 1,
+        }
+    }
+}
+
+/// A file left out of a search because its worker process failed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedFile {
+    /// Path to the file
+    pub file_path: String,
+    /// Why the worker failed, e.g. a timeout or crash
+    pub reason: String,
+}
+
+/// Matches found in a single file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMatchResult {
+    /// Path to the file containing matches
+    pub file_path: String,
+    /// File size in bytes
+    pub file_size_bytes: u64,
+    /// All pattern matches found in this file
+    pub matches: Vec<MatchResult>,
+    /// SHA-256 hash of the searched content (`sha256:<hex>`); `get_file_by_hash` fetches the
+    /// file only while it still has this hash
+    pub file_hash: String,
+    /// The `language_patterns` entry that matched this file, or the language detected for it
+    /// when the search's language was `auto`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Label of the workspace containing this file, when workspaces are configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    /// `regex` when the file was searched by the regex fallback instead of a grammar
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+}
+
+/// The matches of one file, sent while a streaming `file_search` is still running.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSearchProgress {
+    /// Files searched so far, including this one
+    pub files_searched: usize,
+    /// Files this call searches
+    pub total_files: usize,
+    /// The file and its matches
+    pub file: FileMatchResult,
+}
+
+/// Parameters for the `batch_search` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSearchParam {
+    /// Searches to run over the same walk of the root directories
+    pub searches: Vec<BatchSearchEntry>,
+    /// Maximum file size to process in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+    /// Limit the call to the root directory with this workspace label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    /// Run patterns that are a lone metavariable such as `$_` or `$A` (default: false)
+    #[serde(default = "default_false")]
+    pub allow_broad: bool,
+    /// Walk depth, pruned directories and hidden entries; unset options use the server's defaults
+    #[serde(flatten)]
+    pub walk: WalkOptions,
+}
+
+impl Default for BatchSearchParam {
+    fn default() -> Self {
+        Self {
+            searches: Vec::new(),
+            max_file_size: default_max_file_size(),
+            workspace: None,
+            allow_broad: false,
+            walk: WalkOptions::default(),
+        }
+    }
+}
+
+/// One search of a `batch_search` call.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchSearchEntry {
+    /// Key of this search's results; unique within the call
+    pub id: String,
+    /// The ast-grep pattern to search for
+    pub pattern: String,
+    /// Programming language of the pattern
+    pub language: String,
+    /// Glob relative to the root directories, e.g. `src/**/*.ts` or `*.py`
+    pub path_pattern: String,
+    /// Kind of the node to match when the pattern only parses inside `context`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// Surrounding code the pattern is parsed in, used together with `selector`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// Maximum number of matches to return for this search (default: 100)
+    #[serde(default = "default_batch_max_results")]
+    pub max_results: usize,
+}
+
+/// Result of `batch_search`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSearchResult {
+    /// Results of each search, keyed by its id
+    pub results: BTreeMap<String, BatchSearchEntryResult>,
+    /// Files read for at least one search
+    pub files_searched: usize,
+    /// Files that could not be searched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_files: Vec<SkippedFile>,
+    /// Configuration problems that affect the results, such as overlapping root directories
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Matches of one `batch_search` search.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchSearchEntryResult {
+    /// Files with matches, in path order
+    pub matches: Vec<FileMatchResult>,
+    /// Matches found, including those cut by `max_results`
+    pub total_matches: usize,
+    /// Whether `max_results` cut the matches short
+    pub truncated: bool,
+    /// Why the search could not run, e.g. an invalid pattern or language
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Pagination cursor returned in API responses.
+///
+/// Used to continue fetching more results from where the previous request left off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CursorResult {
+    /// Last file processed in this page
+    pub last_file_path: String,
+    /// Whether there are more results available
+    pub is_complete: bool,
+    /// Staleness check to pass back with the cursor; only a cursor for another page has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+/// Summary returned when a search result was exported with `output_file`.
+///
+/// The full [`FileSearchResult`] lives in the written file; only the counts and
+/// the pagination cursor are sent back to the client.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedSearchResult {
+    /// Absolute path of the written JSON file
+    pub output_file: String,
+    /// Size of the written file in bytes
+    pub bytes_written: u64,
+    /// Total number of files searched
+    pub total_files_found: usize,
+    /// Number of files containing at least one match
+    pub files_with_matches: usize,
+    /// Total number of matches across all files
+    pub total_matches: usize,
+    /// Cursor for fetching next page of results (if any)
+    pub next_cursor: Option<CursorResult>,
+}
+
+/// Encoding for the opt-in `compress` argument accepted by every tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResponseCompression {
+    /// Gzip the result JSON and base64-encode the compressed bytes
+    #[serde(rename = "gzip+base64")]
+    GzipBase64,
+}
+
+/// A tool's full JSON result packed into one blob by the `compress` argument.
+///
+/// Sent instead of the result JSON, next to the tool's usual text summary. Decoding `data`
+/// yields exactly the JSON the tool would have returned uncompressed, without truncation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressedResult {
+    /// How `data` is encoded
+    pub compression: ResponseCompression,
+    /// Size of the result JSON before compression
+    pub original_bytes: usize,
+    /// Size of the gzip stream before base64 encoding
+    pub compressed_bytes: usize,
+    /// The encoded result JSON
+    pub data: String,
+}
+
+/// Parameters for the `anonymize_result` tool.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnonymizeResultParam {
+    /// A result returned by another tool, such as `search` or `file_search`
+    pub result: serde_json::Value,
+    /// Language of the code in the result (default: inferred from each `file_path`)
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Identifiers to keep as they are, such as public API names
+    #[serde(default)]
+    pub keep_identifiers: Vec<String>,
+    /// Return the placeholders with the originals they replace (default: false)
+    #[serde(default)]
+    pub include_mapping: bool,
+}
+
+/// Result of `anonymize_result`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnonymizeResultResult {
+    /// The input result with its code, paths and symbol names replaced
+    pub result: serde_json::Value,
+    /// Number of distinct identifiers replaced
+    pub identifiers: usize,
+    /// Number of distinct string literal contents replaced
+    pub strings: usize,
+    /// Number of comments replaced, counting each occurrence
+    pub comments: usize,
+    /// Number of distinct file paths replaced
+    pub files: usize,
+    /// Original text of each placeholder, when requested with `include_mapping`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mapping: Option<BTreeMap<String, String>>,
+}
+
+/// Parameters for the `get_file_by_hash` tool.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GetFileByHashParam {
+    /// Path of the file, as reported in a search result
+    pub file_path: String,
+    /// The `file_hash` reported with the file
+    pub file_hash: String,
+    /// First line to return (0-based, like match positions; default: the first line)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<usize>,
+    /// Last line to return, inclusive (default: the last line)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+}
+
+/// Result of `get_file_by_hash`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetFileByHashResult {
+    /// Absolute path of the file that was read
+    pub file_path: String,
+    /// Hash of the file's current content
+    pub file_hash: String,
+    /// Whether the current content still has the requested hash
+    pub unchanged: bool,
+    /// The requested lines; absent when the file changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// First line of `content` (0-based)
+    pub start_line: usize,
+    /// Last line of `content`, inclusive
+    pub end_line: usize,
+    /// Number of lines in the whole file
+    pub total_lines: usize,
+}
+
+/// Parameters for `search_strings` and `search_identifiers`.
+///
+/// Exactly one of `value` (exact comparison) or `regex` must be set. String literals are
+/// compared by their contents without quotes.
+///
+/// # Example
+///
+/// ```rust
+/// use ast_grep_mcp::ValueSearchParam;
+///
+/// let param = ValueSearchParam {
+///     path_pattern: "src/**/*.ts".to_string(),
+///     language: "typescript".to_string(),
+///     regex: Some("^https?://".to_string()),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueSearchParam {
+    /// Glob pattern ("src/**/*.js") or direct file path ("/path/to/file.js")
+    pub path_pattern: String,
+    /// Programming language
+    pub language: String,
+    /// Exact value the node must have
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Regex the node's value must match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+    /// Maximum number of files to return matches from (default: 20)
+    #[serde(default = "default_max_results")]
+    pub max_results: usize,
+    /// Maximum file size to process in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+    /// Pagination cursor for continuing previous search
+    pub cursor: Option<CursorParam>,
+}
+
+impl Default for ValueSearchParam {
+    fn default() -> Self {
+        Self {
+            path_pattern: "**/*".to_string(),
+            language: String::new(),
+            value: None,
+            regex: None,
+            max_results: default_max_results(),
+            max_file_size: default_max_file_size(),
+            cursor: None,
+        }
+    }
+}
+
+/// Parameters for the `find_comments` tool.
+///
+/// Comments are located by their syntax node kind, so commented-out text inside strings is
+/// never reported. The tag of each comment is the first capture group of `regex` (or the
+/// whole match when the regex has no groups).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindCommentsParam {
+    /// Glob pattern ("src/**/*.js") or direct file path ("/path/to/file.js")
+    pub path_pattern: String,
+    /// Programming language
+    pub language: String,
+    /// Regex a comment must match (default: `\b(TODO|FIXME|HACK|XXX)\b`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+    /// Attach `git blame` author information to each comment
+    #[serde(default = "default_false")]
+    pub blame: bool,
+    /// Maximum number of files to return comments from (default: 20)
+    #[serde(default = "default_max_results")]
+    pub max_results: usize,
+    /// Maximum file size to process in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+    /// Pagination cursor for continuing previous search
+    pub cursor: Option<CursorParam>,
+}
+
+impl Default for FindCommentsParam {
+    fn default() -> Self {
+        Self {
+            path_pattern: "**/*".to_string(),
+            language: String::new(),
+            regex: None,
+            blame: false,
+            max_results: default_max_results(),
+            max_file_size: default_max_file_size(),
+            cursor: None,
+        }
+    }
+}
+
+/// Result of a `find_comments` audit.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FindCommentsResult {
+    /// Matching comments in file order
+    pub comments: Vec<CommentMatch>,
+    /// Number of comments found per tag
+    pub tag_counts: HashMap<String, usize>,
+    /// Total number of files searched
+    pub total_files_found: usize,
+    /// Cursor for fetching next page of results (if any)
+    pub next_cursor: Option<CursorResult>,
+}
+
+/// A single comment matched by `find_comments`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentMatch {
+    /// Path to the file containing the comment
+    pub file_path: String,
+    /// Tag extracted from the comment (e.g. "TODO")
+    pub tag: String,
+    /// Full comment text
+    pub text: String,
+    /// Starting line number (0-based)
+    pub start_line: usize,
+    /// Ending line number (0-based)
+    pub end_line: usize,
+    /// Last commit that touched the comment's first line (when `blame` was requested)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blame: Option<BlameInfo>,
+}
+
+/// Authorship of a line as reported by `git blame`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlameInfo {
+    /// Commit hash
+    pub commit: String,
+    /// Author name
+    pub author: String,
+    /// Author email, without angle brackets
+    pub author_mail: String,
+}
+
+/// Parameters for `begin_code_buffer`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BeginCodeBufferParam {
+    /// First chunk of code (default: empty)
+    #[serde(default)]
+    pub content: String,
+    /// Seconds the buffer stays open after its last use (default: 900)
+    #[serde(default = "default_buffer_ttl_seconds")]
+    pub ttl_seconds: u64,
+}
+
+/// Parameters for `append_code_buffer`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppendCodeBufferParam {
+    /// Id returned by `begin_code_buffer`
+    pub buffer_id: String,
+    /// Code to add to the end of the buffer
+    pub content: String,
+}
+
+/// State of a code buffer after `begin_code_buffer` or `append_code_buffer`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeBufferInfo {
+    /// Id to pass as `buffer_id` to `search_buffer`, `search`, `replace` or `generate_ast`
+    pub buffer_id: String,
+    /// Bytes held so far
+    pub size_bytes: u64,
+    /// Number of non-empty chunks received
+    pub chunks: usize,
+    /// Seconds until the buffer expires unless it is used again
+    pub expires_in_seconds: u64,
+}
+
+/// Parameters for the `summarize_directory` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummarizeDirectoryParam {
+    /// Directory to summarize, absolute or relative to the root directories (default: ".")
+    #[serde(default = "default_summary_directory")]
+    pub directory: String,
+    /// Maximum number of files to summarize (default: 500)
+    #[serde(default = "default_summary_max_files")]
+    pub max_files: usize,
+    /// Number of largest functions to list per file and for the whole directory (default: 5)
+    #[serde(default = "default_largest_functions")]
+    pub largest_functions: usize,
+    /// Maximum file size to process in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+}
+
+impl Default for SummarizeDirectoryParam {
+    fn default() -> Self {
+        Self {
+            directory: default_summary_directory(),
+            max_files: default_summary_max_files(),
+            largest_functions: default_largest_functions(),
+            max_file_size: default_max_file_size(),
+        }
+    }
+}
+
+/// Result of `summarize_directory`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummarizeDirectoryResult {
+    /// One summary per source file, in path order
+    pub files: Vec<FileSummary>,
+    /// Number of files per language
+    pub languages: BTreeMap<String, usize>,
+    /// Non-blank lines across all files
+    pub total_lines_of_code: usize,
+    pub total_functions: usize,
+    pub total_classes: usize,
+    /// The largest functions in the directory, longest first
+    pub largest_functions: Vec<FunctionSize>,
+    /// Files whose summary was reused because they haven't changed since the last call
+    pub cached_files: usize,
+    /// Whether `max_files` cut the listing short
+    pub truncated: bool,
+    /// Files that could not be summarized
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_files: Vec<SkippedFile>,
+}
+
+/// Size statistics for one source file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSummary {
+    pub file_path: String,
+    pub language: String,
+    /// Non-blank lines
+    pub lines_of_code: usize,
+    /// Named functions and methods
+    pub functions: usize,
+    /// Classes, structs, enums, interfaces and traits
+    pub classes: usize,
+    /// The largest functions in the file, longest first
+    pub largest_functions: Vec<FunctionSize>,
+}
+
+/// A function and the number of lines it spans.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionSize {
+    /// Qualified name, e.g. `UserService.login`
+    pub name: String,
+    /// "function" or "method"
+    pub kind: String,
+    /// Starting line number (0-based)
+    pub start_line: usize,
+    pub lines: usize,
+    /// File containing the function, in directory-wide listings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+}
+
+/// Parameters for the `search_symbols` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolSearchParam {
+    /// Name to look for, as well as it is remembered
+    pub query: String,
+    /// Directory to search, absolute or relative to the root directories (default: ".")
+    #[serde(default = "default_summary_directory")]
+    pub directory: String,
+    /// Only report these kinds of declarations, e.g. `["function", "method"]` (default: all)
+    #[serde(default)]
+    pub kinds: Vec<String>,
+    /// Lowest similarity to report, from 0 to 1 (default: 0.6)
+    #[serde(default = "default_min_similarity")]
+    pub min_score: f64,
+    /// Maximum number of symbols to return (default: 20)
+    #[serde(default = "default_symbol_max_results")]
+    pub max_results: usize,
+    /// Maximum number of files to index (default: 5000)
+    #[serde(default = "default_symbol_max_files")]
+    pub max_files: usize,
+    /// Maximum file size to process in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+}
+
+impl Default for SymbolSearchParam {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            directory: default_summary_directory(),
+            kinds: Vec::new(),
+            min_score: default_min_similarity(),
+            max_results: default_symbol_max_results(),
+            max_files: default_symbol_max_files(),
+            max_file_size: default_max_file_size(),
+        }
+    }
+}
+
+/// Result of `search_symbols`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolSearchResult {
+    /// Declarations resembling the query, most similar first
+    pub matches: Vec<SymbolMatch>,
+    /// Declarations compared against the query
+    pub symbols_indexed: usize,
+    pub files_indexed: usize,
+    /// Files whose declarations were reused because they haven't changed since the last call
+    pub cached_files: usize,
+    /// Whether `max_files` cut the index short
+    pub truncated: bool,
+    /// Files that could not be indexed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_files: Vec<SkippedFile>,
+}
+
+/// A declaration whose name resembles a `search_symbols` query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolMatch {
+    /// Declared name
+    pub name: String,
+    /// Name including the enclosing declarations, e.g. `UserService.login`
+    pub qualified_name: String,
+    /// function, method, class, struct, enum, trait, interface, impl or module
+    pub kind: String,
+    pub file_path: String,
+    /// Starting line number (0-based)
+    pub start_line: usize,
+    /// Similarity to the query, from 0 to 1
+    pub score: f64,
+    /// How the name resembles the query: exact, case, words, partial or fuzzy
+    pub match_kind: String,
+}
+
+/// Parameters for the `doc_coverage` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocCoverageParam {
+    /// Directory to audit, absolute or relative to the root directories (default: ".")
+    #[serde(default = "default_summary_directory")]
+    pub directory: String,
+    /// Undocumented symbols listed per page (default: 50)
+    #[serde(default = "default_doc_coverage_page_size")]
+    pub page_size: usize,
+    /// Undocumented symbols to skip, as given by `next_offset`
+    #[serde(default)]
+    pub offset: usize,
+    /// Maximum number of files to audit (default: 5000)
+    #[serde(default = "default_symbol_max_files")]
+    pub max_files: usize,
+    /// Maximum file size to process in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+}
+
+impl Default for DocCoverageParam {
+    fn default() -> Self {
+        Self {
+            directory: default_summary_directory(),
+            page_size: default_doc_coverage_page_size(),
+            offset: 0,
+            max_files: default_symbol_max_files(),
+            max_file_size: default_max_file_size(),
+        }
+    }
+}
+
+/// Result of `doc_coverage`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocCoverageResult {
+    /// Public symbols across every audited file
+    #[serde(flatten)]
+    pub total: DocCoverage,
+    /// Coverage per language
+    pub languages: BTreeMap<String, DocCoverage>,
+    /// Coverage per file with public symbols, in path order
+    pub modules: Vec<ModuleDocCoverage>,
+    /// One page of the public symbols without a doc comment, in path and line order
+    pub undocumented: Vec<UndocumentedSymbol>,
+    /// Offset of the next page of undocumented symbols, when there is one
+    pub next_offset: Option<usize>,
+    pub files_indexed: usize,
+    /// Files whose declarations were reused because they haven't changed since the last call
+    pub cached_files: usize,
+    /// Whether `max_files` cut the audit short
+    pub truncated: bool,
+    /// Files that could not be indexed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_files: Vec<SkippedFile>,
+}
+
+/// How many public symbols have doc comments.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocCoverage {
+    pub public_symbols: usize,
+    pub documented: usize,
+    /// Share of the public symbols with doc comments; 100 when there are none
+    pub coverage_percent: f64,
+}
+
+/// Doc comment coverage of one file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleDocCoverage {
+    pub file_path: String,
+    pub language: String,
+    #[serde(flatten)]
+    pub coverage: DocCoverage,
+}
+
+/// A public function, method or type without a doc comment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UndocumentedSymbol {
+    pub name: String,
+    /// Name including the enclosing declarations, e.g. `UserService.login`
+    pub qualified_name: String,
+    /// function, method, class, struct, enum, trait or interface
+    pub kind: String,
+    pub file_path: String,
+    /// Starting line number (0-based)
+    pub start_line: usize,
+}
+
+/// Parameters for the `find_references` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindReferencesParam {
+    /// Identifier to look for, e.g. `loadConfig`
+    pub symbol: String,
+    /// Programming language; JavaScript and TypeScript also search each other's files
+    pub language: String,
+    /// Directory to search, absolute or relative to the root directories (default: ".")
+    #[serde(default = "default_summary_directory")]
+    pub directory: String,
+    /// Maximum number of references to return (default: 500)
+    #[serde(default = "default_reference_max_results")]
+    pub max_results: usize,
+    /// Maximum file size to process in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+}
+
+impl Default for FindReferencesParam {
+    fn default() -> Self {
+        Self {
+            symbol: String::new(),
+            language: String::new(),
+            directory: default_summary_directory(),
+            max_results: default_reference_max_results(),
+            max_file_size: default_max_file_size(),
+        }
+    }
+}
+
+/// Result of `find_references`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindReferencesResult {
+    pub symbol: String,
+    /// Files with at least one reference, in path order
+    pub files: Vec<FileReferences>,
+    pub total_references: usize,
+    /// Number of references of each kind
+    pub counts: BTreeMap<String, usize>,
+    pub files_searched: usize,
+    /// Whether `max_results` cut the search short
+    pub truncated: bool,
+    /// Files that could not be searched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_files: Vec<SkippedFile>,
+}
+
+/// References to a symbol within one file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileReferences {
+    pub file_path: String,
+    /// References in source order
+    pub references: Vec<SymbolReference>,
+}
+
+/// One place a symbol is named.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolReference {
+    pub kind: ReferenceKind,
+    /// Position of the symbol's name (0-based)
+    pub line: usize,
+    pub column: usize,
+    /// The source line, without surrounding whitespace
+    pub text: String,
+}
+
+/// How a reference uses its symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReferenceKind {
+    /// Declares the symbol: a function, class, type or variable
+    Definition,
+    /// Calls or constructs the symbol
+    Call,
+    /// Brings the symbol, or a module of that name, into scope
+    Import,
+    /// Any other use of the name, such as reading a variable or naming a type. Only
+    /// `rename_symbol` reports these
+    Usage,
+}
+
+impl ReferenceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReferenceKind::Definition => "definition",
+            ReferenceKind::Call => "call",
+            ReferenceKind::Import => "import",
+            ReferenceKind::Usage => "usage",
+        }
+    }
+}
+
+/// Where `rename_symbol` renames.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenameScope {
+    /// Only the file at `path`
+    File,
+    /// Every file under the directory at `path` in the language's family
+    #[default]
+    Project,
+}
+
+/// Parameters for the `rename_symbol` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameSymbolParam {
+    /// Identifier to rename, e.g. `loadConfig`
+    pub old_name: String,
+    /// Identifier to rename it to
+    pub new_name: String,
+    /// Programming language; JavaScript and TypeScript also rename in each other's files
+    pub language: String,
+    /// Rename in one file or in a whole directory (default: project)
+    #[serde(default)]
+    pub scope: RenameScope,
+    /// The file to rename in with `scope: file`, or the directory with `scope: project`,
+    /// absolute or relative to the root directories (default: ".")
+    #[serde(default = "default_summary_directory")]
+    pub path: String,
+    /// Only report the renames and collisions (default: true)
+    #[serde(default = "default_true")]
+    pub dry_run: bool,
+    /// Apply the rename even though `new_name` is already defined in scope (default: false)
+    #[serde(default = "default_false")]
+    pub force: bool,
+    /// Maximum file size to process in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+}
+
+impl Default for RenameSymbolParam {
+    fn default() -> Self {
+        Self {
+            old_name: String::new(),
+            new_name: String::new(),
+            language: String::new(),
+            scope: RenameScope::default(),
+            path: default_summary_directory(),
+            dry_run: true,
+            force: false,
+            max_file_size: default_max_file_size(),
+        }
+    }
+}
+
+/// Result of `rename_symbol`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameSymbolResult {
+    pub old_name: String,
+    pub new_name: String,
+    pub dry_run: bool,
+    /// Whether the files were rewritten
+    pub applied: bool,
+    /// Files naming `old_name`, each with the places renamed, in path order
+    pub files: Vec<FileReferences>,
+    pub total_renames: usize,
+    /// Existing definitions of `new_name` in scope, which the rename would clash with
+    pub collisions: Vec<FileReferences>,
+    pub files_searched: usize,
+    /// Files that could not be searched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_files: Vec<SkippedFile>,
+}
+
+/// Parameters for the `find_similar_code` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindSimilarCodeParam {
+    /// Example snippet: one statement, expression or declaration
+    pub code: String,
+    /// Programming language of the snippet and of the files to search
+    pub language: String,
+    /// Directory to search, absolute or relative to the root directories (default: ".")
+    #[serde(default = "default_summary_directory")]
+    pub directory: String,
+    /// Lowest similarity to report, from 0 to 1 (default: 0.5)
+    #[serde(default = "default_similar_code_min_score")]
+    pub min_score: f64,
+    /// Maximum number of matches to return (default: 50)
+    #[serde(default = "default_similar_code_max_results")]
+    pub max_results: usize,
+    /// Maximum file size to process in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+}
+
+impl Default for FindSimilarCodeParam {
+    fn default() -> Self {
+        Self {
+            code: String::new(),
+            language: String::new(),
+            directory: default_summary_directory(),
+            min_score: default_similar_code_min_score(),
+            max_results: default_similar_code_max_results(),
+            max_file_size: default_max_file_size(),
+        }
+    }
+}
+
+/// Result of `find_similar_code`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindSimilarCodeResult {
+    /// Pattern derived from the snippet, with identifiers and literals replaced by `$_`
+    pub pattern: String,
+    /// Matches of the pattern, most similar first
+    pub matches: Vec<SimilarCode>,
+    /// Matches at or above `min_score`, including those cut by `max_results`
+    pub total_matches: usize,
+    pub files_searched: usize,
+    /// Whether `max_results` cut the matches short
+    pub truncated: bool,
+    /// Files that could not be searched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_files: Vec<SkippedFile>,
+}
+
+/// Code shaped like a `find_similar_code` snippet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimilarCode {
+    pub file_path: String,
+    /// Starting line number (0-based)
+    pub start_line: usize,
+    /// Ending line number (0-based)
+    pub end_line: usize,
+    /// Share of the snippet's tokens found in the same order, from 0 to 1
+    pub score: f64,
+    pub text: String,
+}
+
+/// Parameters for the `dedupe_code` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupeCodeParam {
+    /// Directory to scan, absolute or relative to the root directories (default: ".")
+    #[serde(default = "default_summary_directory")]
+    pub directory: String,
+    /// Only scan files in this language; every supported language when not given
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Treat copies that renamed identifiers as clones (default: true)
+    #[serde(default = "default_true")]
+    pub ignore_identifiers: bool,
+    /// Treat copies that changed literals as clones (default: true)
+    #[serde(default = "default_true")]
+    pub ignore_literals: bool,
+    /// Fewest lines a function or block needs to be compared (default: 5)
+    #[serde(default = "default_dedupe_min_lines")]
+    pub min_lines: usize,
+    /// Fewest tokens a function or block needs to be compared (default: 30)
+    #[serde(default = "default_dedupe_min_tokens")]
+    pub min_tokens: usize,
+    /// Maximum number of clone groups to return (default: 50)
+    #[serde(default = "default_dedupe_max_groups")]
+    pub max_groups: usize,
+    /// Maximum file size to process in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+}
+
+impl Default for DedupeCodeParam {
+    fn default() -> Self {
+        Self {
+            directory: default_summary_directory(),
+            language: None,
+            ignore_identifiers: true,
+            ignore_literals: true,
+            min_lines: default_dedupe_min_lines(),
+            min_tokens: default_dedupe_min_tokens(),
+            max_groups: default_dedupe_max_groups(),
+            max_file_size: default_max_file_size(),
+        }
+    }
+}
+
+/// Result of `dedupe_code`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupeCodeResult {
+    /// Clone groups, the most duplicated tokens first
+    pub groups: Vec<CloneGroup>,
+    /// Clone groups found, including those cut by `max_groups`
+    pub total_groups: usize,
+    /// Lines that repeat an earlier copy, over every group
+    pub duplicated_lines: usize,
+    pub files_scanned: usize,
+    /// Whether `max_groups` cut the groups short
+    pub truncated: bool,
+    /// Files that could not be scanned
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_files: Vec<SkippedFile>,
+}
+
+/// Functions or blocks with the same normalized shape.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CloneGroup {
+    pub language: String,
+    /// Tokens in the shared shape
+    pub tokens: usize,
+    /// Lines of the first copy
+    pub lines: usize,
+    /// Lines of every copy but the first
+    pub duplicated_lines: usize,
+    /// Share of the first copy's tokens the least similar copy repeats verbatim and in order,
+    /// from 0 to 1; 1 when every copy is identical
+    pub similarity: f64,
+    /// The copies, in path and line order
+    pub instances: Vec<CloneInstance>,
+}
+
+/// One copy in a `dedupe_code` clone group.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CloneInstance {
+    pub file_path: String,
+    /// Starting line number (0-based)
+    pub start_line: usize,
+    /// Ending line number (0-based)
+    pub end_line: usize,
+    /// Node kind of the copy, e.g. `function_declaration` or `block`
+    pub kind: String,
+}
+
+/// Parameters for the `detect_conventions` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectConventionsParam {
+    /// Directory to sample, absolute or relative to the root directories (default: ".")
+    #[serde(default = "default_summary_directory")]
+    pub directory: String,
+    /// Maximum number of files to sample, spread evenly over the directory (default: 200)
+    #[serde(default = "default_convention_max_files")]
+    pub max_files: usize,
+    /// Maximum file size to process in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+}
+
+impl Default for DetectConventionsParam {
+    fn default() -> Self {
+        Self {
+            directory: default_summary_directory(),
+            max_files: default_convention_max_files(),
+            max_file_size: default_max_file_size(),
+        }
+    }
+}
+
+/// Result of `detect_conventions`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectConventionsResult {
+    /// Conventions with at least one observation, by language family and topic
+    pub conventions: Vec<Convention>,
+    /// Files the probes ran over
+    pub files_sampled: usize,
+    /// Source files found under the directory, sampled or not
+    pub files_found: usize,
+    /// Number of sampled files per language
+    pub languages: BTreeMap<String, usize>,
+    /// Files that could not be sampled
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_files: Vec<SkippedFile>,
+}
+
+/// How a codebase handles one topic, such as `quote_style`, in one language family.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Convention {
+    /// quote_style, variable_declarations, error_handling or test_framework
+    pub topic: String,
+    /// Language family the observations are pooled under: javascript (including TypeScript),
+    /// python, rust or go
+    pub language: String,
+    /// The most frequently observed option
+    pub dominant: String,
+    /// Fraction of observations that are the dominant option, from 0 to 1
+    pub share: f64,
+    pub observations: usize,
+    /// Observations per option
+    pub counts: BTreeMap<String, usize>,
+    /// The first place the dominant option was seen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub example: Option<ConventionExample>,
+}
+
+/// Where a convention was observed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConventionExample {
+    pub file_path: String,
+    /// Line number (0-based)
+    pub line: usize,
+    /// First line of the observed code
+    pub text: String,
+}
+
+/// Parameters for the `project_overview` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectOverviewParam {
+    /// Directory levels below each root listed in `tree` (default: 2)
+    #[serde(default = "default_overview_tree_depth")]
+    pub tree_depth: usize,
+    /// Number of largest files to list (default: 10)
+    #[serde(default = "default_overview_largest_files")]
+    pub largest_files: usize,
+    /// Walk depth, pruned directories and hidden entries; unset options use the server's defaults
+    #[serde(flatten)]
+    pub walk: WalkOptions,
+}
+
+impl Default for ProjectOverviewParam {
+    fn default() -> Self {
+        Self {
+            tree_depth: default_overview_tree_depth(),
+            largest_files: default_overview_largest_files(),
+            walk: WalkOptions::default(),
+        }
+    }
+}
+
+/// Result of `project_overview`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectOverviewResult {
+    pub total_files: usize,
+    pub total_bytes: u64,
+    /// Languages ast-grep can parse, most files first
+    pub languages: Vec<LanguageOverview>,
+    /// Files in no language ast-grep can parse, such as docs and images
+    pub other_files: usize,
+    /// Each root and its directories down to `tree_depth`, parents before their subdirectories
+    pub tree: Vec<DirectoryOverview>,
+    /// Largest files first
+    pub largest_files: Vec<FileSize>,
+    /// Globs for follow-up `file_search` calls, one per language, most files first
+    pub suggested_path_patterns: Vec<PathPatternSuggestion>,
+}
+
+/// Files of one language.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguageOverview {
+    pub language: String,
+    pub files: usize,
+    pub bytes: u64,
+    /// File extensions seen, without the dot
+    pub extensions: Vec<String>,
+}
+
+/// Files below one directory of the tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectoryOverview {
+    pub path: String,
+    /// Levels below its root; roots are 0
+    pub depth: usize,
+    /// Files anywhere below the directory
+    pub files: usize,
+    pub bytes: u64,
+    /// The language most of the files below are in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main_language: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileSize {
+    pub file_path: String,
+    pub bytes: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathPatternSuggestion {
+    /// Glob to pass as `path_pattern`, e.g. `src/**/*.rs`
+    pub path_pattern: String,
+    /// Language to pass with it
+    pub language: String,
+    /// Files the glob matches
+    pub files: usize,
+}
+
+/// Parameters for the `run_codemod` tool.
+///
+/// The codemod is selected with `codemod` and configured with `params`, e.g.
+/// `{"codemod": "rename_import_source", "params": {"from": "lodash", "to": "lodash-es"}}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunCodemodParam {
+    #[serde(flatten)]
+    pub codemod: Codemod,
+    /// javascript, typescript or tsx (default: typescript)
+    #[serde(default = "default_codemod_language")]
+    pub language: String,
+    /// Glob pattern for files to rewrite (default: the extensions of `language`)
+    pub path_pattern: Option<String>,
+    /// Preview the changes without writing them (default: true)
+    #[serde(default = "default_true")]
+    pub dry_run: bool,
+    /// Maximum number of changes each generated rule makes (default: 10000)
+    #[serde(default = "default_max_results_large")]
+    pub max_results: usize,
+    /// Maximum file size to process in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+    /// Limit the call to the root directory with this workspace label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+}
+
+impl RunCodemodParam {
+    pub fn new(codemod: Codemod) -> Self {
+        Self {
+            codemod,
+            language: default_codemod_language(),
+            path_pattern: None,
+            dry_run: true,
+            max_results: default_max_results_large(),
+            max_file_size: default_max_file_size(),
+            workspace: None,
         }
     }
+}
 
-    /// Add context lines before and after this match.
-    ///
-    /// This is used by the context lines feature to provide surrounding code
-    /// for better understanding of the match location.
-    pub fn with_context(
-        mut self,
-        context_before: Option<Vec<String>>,
-        context_after: Option<Vec<String>>,
-    ) -> Self {
-        self.context_before = context_before;
-        self.context_after = context_after;
-        self
-    }
+/// Result of `run_codemod`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunCodemodResult {
+    /// Name of the codemod that ran
+    pub codemod: String,
+    pub dry_run: bool,
+    /// One step per generated rule, in the order they were applied
+    pub steps: Vec<CodemodStep>,
+    /// Changes across all steps
+    pub total_changes: usize,
+    /// Files changed by at least one step
+    pub files_with_changes: usize,
 }
 
-/// Parameters for searching patterns across multiple files.
-///
-/// Supports both glob patterns (e.g., `**/*.js`) and direct file paths.
-/// Results are paginated using cursor-based pagination for large result sets.
-///
-/// # Example
-///
-/// ```rust
-/// use ast_grep_mcp::FileSearchParam;
+/// A generated rule and what it changed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CodemodStep {
+    pub rule_id: String,
+    /// The generated rule configuration, usable with `rule_replace` on its own
+    pub rule_config: String,
+    pub total_changes: usize,
+    pub files_with_changes: usize,
+    pub file_results: Vec<FileDiffResult>,
+    /// Matches the rule's fix left as they were, e.g. because they were nested in another
+    #[serde(default)]
+    pub unfixed_matches: usize,
+    /// Whether `max_results` stopped the rule before it reached every file; run the codemod
+    /// again to continue
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub incomplete: bool,
+}
+
+/// Parameters for the `plan_directory_move` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanDirectoryMoveParam {
+    /// Directory to move, absolute or relative to the root directories
+    pub source: String,
+    /// New location of the directory, absolute or relative to the first root directory; it
+    /// must not exist yet
+    pub destination: String,
+    /// Maximum file size to scan for imports in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+    /// Limit the call to the root directory with this workspace label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+}
+
+/// Multi-file edit plan produced by `plan_directory_move` and executed by `apply_plan`.
 ///
-/// let param = FileSearchParam {
-///     path_pattern: "src/**/*.js".to_string(),
-///     pattern: "console.log($VAR)".to_string(),
-///     language: "javascript".to_string(),
-///     max_results: 10,
-///     ..Default::default()
-/// };
-/// ```
+/// Paths are absolute; edits refer to files by the path they have before the move.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FileSearchParam {
-    /// Glob pattern ("src/**/*.js") or direct file path ("/path/to/file.js")
-    pub path_pattern: String,
-    /// The ast-grep pattern to match
+pub struct EditPlan {
+    pub source: String,
+    pub destination: String,
+    /// Every file under `source` and where it goes, in path order
+    pub moves: Vec<PlannedMove>,
+    /// Import paths that the move would break, grouped by file
+    pub edits: Vec<PlannedFileEdit>,
+    /// Files that could not be scanned for imports
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_files: Vec<SkippedFile>,
+}
+
+/// A file moved by an [`EditPlan`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedMove {
+    pub from: String,
+    pub to: String,
+}
+
+/// The import paths rewritten in one file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedFileEdit {
+    pub file_path: String,
+    pub changes: Vec<PlannedImportEdit>,
+}
+
+/// One import path and its new spelling; the byte range covers the path without its quotes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedImportEdit {
+    /// Line of the import (0-based)
+    pub start_line: usize,
+    pub start_byte: usize,
+    pub end_byte: usize,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+/// Parameters for the `apply_plan` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplyPlanParam {
+    /// Plan returned by `plan_directory_move`
+    pub plan: EditPlan,
+}
+
+/// Result of `apply_plan`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplyPlanResult {
+    pub source: String,
+    pub destination: String,
+    pub files_edited: usize,
+    /// Import paths rewritten across all files
+    pub total_changes: usize,
+    pub files_moved: usize,
+    /// Directories left empty by the move and removed, deepest first
+    pub removed_directories: Vec<String>,
+}
+
+/// A pattern counted by `pattern_coverage`, typically a use of an API being migrated away from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoveragePattern {
+    /// Stable name the pattern's counts are recorded under
+    pub id: String,
+    /// The ast-grep pattern to count
     pub pattern: String,
     /// Programming language
     pub language: String,
-    /// Maximum number of matches to return (default: 20)
-    #[serde(default = "default_max_results")]
-    pub max_results: usize,
+}
+
+/// Parameters for the `pattern_coverage` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternCoverageParam {
+    /// Name of the migration; snapshots are stored and compared under it
+    pub name: String,
+    /// Patterns whose remaining occurrences are counted
+    pub patterns: Vec<CoveragePattern>,
+    /// Glob selecting the files to count in (default: "**/*")
+    #[serde(default = "default_coverage_path_pattern")]
+    pub path_pattern: String,
+    /// Limit the count to the root directory with this workspace label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
     /// Maximum file size to process in bytes (default: 50MB)
     #[serde(default = "default_max_file_size")]
     pub max_file_size: u64,
-    /// Pagination cursor for continuing previous search
-    pub cursor: Option<CursorParam>,
-    /// How strictly to match the pattern
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub strictness: Option<MatchStrictness>,
-    /// CSS-like selector to filter matches
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub selector: Option<String>,
-    /// Additional rule context (YAML)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub context: Option<String>,
-    /// Number of lines to include before each match
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub context_before: Option<usize>,
-    /// Number of lines to include after each match
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub context_after: Option<usize>,
-    /// Number of lines to include both before and after each match
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub context_lines: Option<usize>,
+    /// Time budget for the whole count; an unfinished count is returned but not recorded
+    /// (default: 60000)
+    #[serde(default = "default_coverage_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Store the counts as a new snapshot (default: true)
+    #[serde(default = "default_true")]
+    pub record: bool,
+    /// Number of most recent snapshots to return (default: 20)
+    #[serde(default = "default_coverage_history_limit")]
+    pub history_limit: usize,
 }
 
-impl Default for FileSearchParam {
+impl Default for PatternCoverageParam {
     fn default() -> Self {
         Self {
-            path_pattern: "**/*".to_string(),
-            pattern: String::new(),
-            language: String::new(),
-            max_results: default_max_results(),
+            name: String::new(),
+            patterns: Vec::new(),
+            path_pattern: default_coverage_path_pattern(),
+            workspace: None,
             max_file_size: default_max_file_size(),
-            cursor: None,
-            strictness: None,
-            selector: None,
-            context: None,
-            context_before: None,
-            context_after: None,
-            context_lines: None,
+            timeout_ms: default_coverage_timeout_ms(),
+            record: default_true(),
+            history_limit: default_coverage_history_limit(),
         }
     }
 }
 
-/// Pagination cursor for continuing file-based operations.
-///
-/// Used internally to track progress through large file sets. The cursor is opaque
-/// and base64-encoded for API responses.
+/// Result of `pattern_coverage`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CursorParam {
-    /// Last file processed in the previous page
-    pub last_file_path: String,
-    /// Whether the operation has completed (no more results)
-    pub is_complete: bool,
+pub struct PatternCoverageResult {
+    pub name: String,
+    /// Counts per pattern, in request order
+    pub patterns: Vec<PatternCoverageCount>,
+    /// Occurrences left across all patterns
+    pub total_remaining: usize,
+    /// Share of the baseline occurrences that are gone, in percent; absent until a snapshot
+    /// with occurrences of the current patterns has been recorded. Negative when the count grew
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress_percent: Option<f64>,
+    /// Whether every file was counted before `timeout_ms` ran out
+    pub complete: bool,
+    /// Whether the counts were stored as a new snapshot
+    pub recorded: bool,
+    pub duration_ms: u64,
+    /// Most recent snapshots, oldest first, including the one just recorded
+    pub history: Vec<CoverageSnapshot>,
 }
 
-/// Result of a file-based pattern search operation.
+/// Remaining occurrences of one coverage pattern.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatternCoverageCount {
+    pub id: String,
+    pub pattern: String,
+    pub language: String,
+    /// Occurrences found by this call
+    pub remaining: usize,
+    /// Files containing at least one occurrence
+    pub files: usize,
+    /// Occurrences in the first snapshot that counted this pattern
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<usize>,
+    /// Occurrences in the latest snapshot before this call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<usize>,
+}
+
+/// Parameters for the `experiment` tool.
 ///
-/// Contains matches organized by file, with optional pagination cursor for large result sets.
-#[derive(Debug, Serialize, Deserialize)]
-pub struct FileSearchResult {
-    /// Matches organized by file
-    pub matches: Vec<FileMatchResult>,
-    /// Cursor for fetching next page of results (if any)
-    pub next_cursor: Option<CursorResult>,
-    /// Total number of files searched
-    pub total_files_found: usize,
+/// Runs several candidate patterns over the same code so they can be compared in one call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentParam {
+    /// The source code to try the candidates on; empty when `buffer_id` is given
+    #[serde(default)]
+    pub code: String,
+    /// Use the content of a code buffer (see `begin_code_buffer`) instead of `code`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_id: Option<String>,
+    /// Language the code is parsed as, unless a candidate names its own
+    pub language: String,
+    /// Patterns to try, in the order they are reported
+    pub candidates: Vec<ExperimentCandidate>,
+    /// Matches returned per candidate; `match_count` always counts all of them (default: 5)
+    #[serde(default = "default_experiment_max_matches")]
+    pub max_matches: usize,
 }
 
-/// Matches found in a single file.
+impl Default for ExperimentParam {
+    fn default() -> Self {
+        Self {
+            code: String::new(),
+            buffer_id: None,
+            language: String::new(),
+            candidates: Vec::new(),
+            max_matches: default_experiment_max_matches(),
+        }
+    }
+}
+
+/// One pattern tried by `experiment`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExperimentCandidate {
+    /// Label for the candidate in the result (default: its 1-based position)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub pattern: String,
+    /// Rewrite applied to every match; the rewritten code is returned as `new_code`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
+    /// Parse the code as this language instead of the experiment's, e.g. "tsx"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strictness: Option<MatchStrictness>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+}
+
+/// Result of `experiment`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FileMatchResult {
-    /// Path to the file containing matches
-    pub file_path: String,
-    /// File size in bytes
-    pub file_size_bytes: u64,
-    /// All pattern matches found in this file
-    pub matches: Vec<MatchResult>,
-    /// SHA-256 hash of the file content for change detection
-    pub file_hash: String,
+pub struct ExperimentResult {
+    /// One outcome per candidate, in request order
+    pub outcomes: Vec<ExperimentOutcome>,
 }
 
-/// Pagination cursor returned in API responses.
-///
-/// Used to continue fetching more results from where the previous request left off.
+/// What one candidate matched and, with a replacement, produced.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CursorResult {
-    /// Last file processed in this page
-    pub last_file_path: String,
-    /// Whether there are more results available
-    pub is_complete: bool,
+pub struct ExperimentOutcome {
+    pub id: String,
+    pub pattern: String,
+    pub language: String,
+    /// Number of matches in the code
+    pub match_count: usize,
+    /// The first `max_matches` matches
+    pub matches: Vec<MatchResult>,
+    /// The code after applying the replacement to every match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_code: Option<String>,
+    /// Why the candidate could not run, e.g. a pattern that doesn't parse. The other
+    /// candidates are unaffected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 /// Parameters for replacing patterns in code strings.
@@ -322,7 +2049,8 @@ pub struct CursorResult {
 /// # Important Notes
 ///
 /// - **Manual Comma Handling**: You must include commas explicitly in replacement patterns
-/// - **Literal Replacement**: ast-grep does exact text replacement, no automatic formatting
+/// - **Literal Replacement**: ast-grep does exact text replacement; set `format_after_replace`
+///   to run the language's formatter over the result
 /// - **Syntax Responsibility**: Ensure replacement patterns produce valid syntax
 ///
 /// # Example
@@ -335,15 +2063,25 @@ pub struct CursorResult {
 ///     pattern: "var $VAR = $VAL;".to_string(),
 ///     replacement: "let $VAR = $VAL;".to_string(),
 ///     language: "javascript".to_string(),
+///     buffer_id: None,
 ///     strictness: None,
 ///     selector: None,
 ///     context: None,
+///     rewrite_selector: None,
+///     verify_formatting: false,
+///     format_after_replace: false,
+///     json_patch: false,
 /// };
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReplaceParam {
-    /// The source code to search and replace in
+    /// The source code to search and replace in; empty when `buffer_id` is given
+    #[serde(default)]
     pub code: String,
+    /// Replace in the content of a code buffer (see `begin_code_buffer`) instead of `code`.
+    /// The buffer itself is left unchanged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_id: Option<String>,
     /// The ast-grep pattern to match
     pub pattern: String,
     /// The replacement text (may include metavariables like $VAR)
@@ -359,6 +2097,22 @@ pub struct ReplaceParam {
     /// Additional rule context (YAML)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context: Option<String>,
+    /// Metavariable of the pattern (e.g. `$DECORATOR` or `$$$ARGS`) whose captured code the
+    /// replacement rewrites; the rest of each match is left as it is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rewrite_selector: Option<String>,
+    /// Run the language's formatter over the old and new code and report changes outside the
+    /// replaced regions (default: false)
+    #[serde(default = "default_false")]
+    pub verify_formatting: bool,
+    /// Run the language's formatter over the rewritten regions before reporting them
+    /// (default: false)
+    #[serde(default = "default_false")]
+    pub format_after_replace: bool,
+    /// For json and yaml code, report the edit as an RFC 6902 JSON Patch in `json_patch`
+    /// instead of text changes (default: false)
+    #[serde(default = "default_false")]
+    pub json_patch: bool,
 }
 
 impl ReplaceParam {
@@ -369,9 +2123,14 @@ impl ReplaceParam {
             pattern: pattern.to_string(),
             replacement: replacement.to_string(),
             language: language.to_string(),
+            buffer_id: None,
             strictness: None,
             selector: None,
             context: None,
+            rewrite_selector: None,
+            verify_formatting: false,
+            format_after_replace: false,
+            json_patch: false,
         }
     }
 }
@@ -383,6 +2142,15 @@ pub struct ReplaceResult {
     pub new_code: String,
     /// List of all changes made (for diff visualization)
     pub changes: Vec<ChangeResult>,
+    /// Formatter verification, present when `verify_formatting` was requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format_check: Option<FormatCheck>,
+    /// Why `format_after_replace` left the rewrite unformatted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format_error: Option<String>,
+    /// Document edits, present when `json_patch` was requested; `changes` is then empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_patch: Option<Vec<JsonPatchOperation>>,
 }
 
 /// A single change made during replacement.
@@ -399,6 +2167,11 @@ pub struct ChangeResult {
     pub start_col: usize,
     /// Ending column number (0-based)
     pub end_col: usize,
+    /// Byte offset of the replaced text in the original content
+    pub start_byte: usize,
+    /// Byte offset just past the replaced text in the original content; equal to `start_byte`
+    /// for an insertion
+    pub end_byte: usize,
     /// Original text that was replaced
     pub old_text: String,
     /// New text after replacement
@@ -444,7 +2217,8 @@ pub struct FileReplaceParam {
     pub pattern: String,
     /// The replacement text (may include metavariables)
     pub replacement: String,
-    /// Programming language
+    /// Programming language; empty or `auto` detects each file's language
+    #[serde(default)]
     pub language: String,
     /// Maximum number of changes to process (default: 10000)
     #[serde(default = "default_max_results_large")]
@@ -475,56 +2249,355 @@ pub struct FileReplaceParam {
     /// Additional rule context (YAML)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context: Option<String>,
+    /// Metavariable of the pattern (e.g. `$DECORATOR` or `$$$ARGS`) whose captured code the
+    /// replacement rewrites; the rest of each match is left as it is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rewrite_selector: Option<String>,
+    /// `file_hash` values reported by the dry run, keyed by file path. Applying fails if any of
+    /// these files changed since, unless `force` is set
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub expected_hashes: HashMap<String, String>,
+    /// Merge replacements into files that changed since the dry run instead of failing,
+    /// leaving diff3-style conflict markers around hunks that can't be merged (default: false)
+    #[serde(default = "default_false")]
+    pub force: bool,
+    /// Unsaved editor buffers keyed by file path (absolute or relative to a root directory).
+    /// Replacements are computed against this content and returned as `edits`; overlay files
+    /// are never written to disk
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub overlays: HashMap<String, String>,
+    /// Run the language's formatter over each file before and after the replacement and report
+    /// changes outside the replaced regions; files are written either way (default: false)
+    #[serde(default = "default_false")]
+    pub verify_formatting: bool,
+    /// Run the language's formatter over the rewritten regions of each file before the changes
+    /// are reported and written (default: false)
+    #[serde(default = "default_false")]
+    pub format_after_replace: bool,
+    /// Limit the call to the root directory with this workspace label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    /// For json and yaml files, report each file's edit as an RFC 6902 JSON Patch in
+    /// `json_patch` instead of sample text changes (default: false)
+    #[serde(default = "default_false")]
+    pub json_patch: bool,
+    /// Treat files whose rewrite only changes trailing whitespace, trailing blank lines or line
+    /// endings as unchanged, so they are neither reported nor written (default: true)
+    #[serde(default = "default_true")]
+    pub ignore_whitespace_changes: bool,
+    /// Rewrite with a pattern that is a lone metavariable such as `$_` or `$A`, which matches
+    /// nearly every node; without it such patterns are refused (default: false)
+    #[serde(default = "default_false")]
+    pub allow_broad: bool,
+    /// How each file's rewrite is reported (default: sample changes)
+    #[serde(default)]
+    pub output: ReplaceOutput,
+    /// Unchanged lines around each hunk of a unified diff (default: 3)
+    #[serde(default = "default_diff_context")]
+    pub diff_context: usize,
+    /// Stop after this many milliseconds, or when the client cancels the request, and return
+    /// the changes made so far with `timed_out` set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Walk depth, pruned directories and hidden entries; unset options use the server's defaults
+    #[serde(flatten)]
+    pub walk: WalkOptions,
+}
+
+/// How `file_replace` reports each file's rewrite
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplaceOutput {
+    /// Sample changes, as many as `max_samples`
+    #[default]
+    Changes,
+    /// A unified diff of the whole file in `unified_diff`, which `apply_patch` can apply
+    UnifiedDiff,
+}
+
+impl Default for FileReplaceParam {
+    fn default() -> Self {
+        Self {
+            path_pattern: "**/*".to_string(),
+            pattern: String::new(),
+            replacement: String::new(),
+            language: String::new(),
+            max_results: default_max_results_large(),
+            max_file_size: default_max_file_size(),
+            dry_run: default_true(),
+            summary_only: default_false(),
+            include_samples: default_false(),
+            max_samples: default_max_samples(),
+            cursor: None,
+            strictness: None,
+            selector: None,
+            context: None,
+            rewrite_selector: None,
+            expected_hashes: HashMap::new(),
+            force: default_false(),
+            overlays: HashMap::new(),
+            verify_formatting: default_false(),
+            format_after_replace: default_false(),
+            workspace: None,
+            json_patch: default_false(),
+            ignore_whitespace_changes: default_true(),
+            allow_broad: default_false(),
+            output: ReplaceOutput::default(),
+            diff_context: default_diff_context(),
+            timeout_ms: None,
+            walk: WalkOptions::default(),
+        }
+    }
+}
+
+/// Result of a file-based replacement operation.
+///
+/// Contains either detailed diffs or summary statistics, depending on the request parameters.
+/// Uses token-efficient representation to minimize API response size.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileReplaceResult {
+    /// Detailed file-by-file diff results (when summary_only=false)
+    pub file_results: Vec<FileDiffResult>,
+    /// Summary statistics per file (when summary_only=true)
+    pub summary_results: Vec<FileSummaryResult>,
+    /// Cursor for fetching next page of results
+    pub next_cursor: Option<CursorResult>,
+    /// Total number of files processed
+    pub total_files_found: usize,
+    /// Whether this was a dry run (no actual changes made)
+    pub dry_run: bool,
+    /// Total number of individual changes across all files
+    pub total_changes: usize,
+    /// Number of files that had at least one change
+    pub files_with_changes: usize,
+    /// Files that changed between the dry run and this apply and were merged with `force`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drifted_files: Vec<DriftedFile>,
+    /// Fix safety declared by the rule (rule_replace only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix_safety: Option<FixSafety>,
+    /// Dry-run changes from an `unsafe` rule; they are not applied without `allow_unsafe`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unsafe_fixes: Vec<FileDiffResult>,
+    /// How the rule's fix was applied, by file (rule_replace only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rule_stats: Vec<RuleFixStats>,
+    /// Id under which the written files' previous contents were recorded, for
+    /// `rollback_replace` (file_replace with dry_run false only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apply_id: Option<String>,
+    /// Id under which a dry run's full per-file diffs are kept, for `get_file_diff`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation_id: Option<String>,
+    /// The replacement stopped at `timeout_ms` or because the client cancelled it; only the
+    /// files reported were changed, and `next_cursor`, when set, resumes where it stopped
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+    /// Configuration problems that affect the results, such as overlapping root directories
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Parameters for the `get_file_diff` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetFileDiffParam {
+    /// `operation_id` returned by the `file_replace` or `rule_replace` dry run
+    pub operation_id: String,
+    /// File whose diff to return, as reported by the dry run (default: the first file)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+}
+
+/// Result of `get_file_diff`: every change a dry run would make to one file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetFileDiffResult {
+    pub operation_id: String,
+    pub diff: FileDiffResult,
+    /// Position of this file among the dry run's changed files, from 0
+    pub file_index: usize,
+    /// Number of files the dry run would change
+    pub total_files: usize,
+    /// File to ask for next, absent for the last one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_file_path: Option<String>,
+}
+
+/// Parameters for the `apply_patch` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplyPatchParam {
+    /// Unified diff of one or more files, such as the `unified_diff` of a `file_replace` dry
+    /// run; paths are absolute or relative to the first root directory
+    pub patch: String,
+    /// If true, check that every hunk applies without modifying files (default: true)
+    #[serde(default = "default_true")]
+    pub dry_run: bool,
+    /// Limit the call to the root directory with this workspace label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+}
+
+/// Result of `apply_patch`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplyPatchResult {
+    pub dry_run: bool,
+    /// Each file of the patch, in patch order
+    pub files: Vec<PatchedFile>,
+    /// Id under which the written files' previous contents were recorded, for
+    /// `rollback_replace` (dry_run false only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apply_id: Option<String>,
+}
+
+/// A file changed by `apply_patch`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchedFile {
+    pub file_path: String,
+    pub hunks: usize,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+}
+
+/// Parameters for the `rollback_replace` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollbackReplaceParam {
+    /// `apply_id` returned by the `file_replace` to undo
+    pub apply_id: String,
+    /// Restore files that changed since the replacement was applied, discarding those changes
+    /// (default: false)
+    #[serde(default = "default_false")]
+    pub force: bool,
+}
+
+/// Result of `rollback_replace`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollbackReplaceResult {
+    pub apply_id: String,
+    /// Files restored to their content from before the replacement
+    pub restored_files: Vec<String>,
+    /// Restored files that had changed since the replacement was applied (with `force` only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overwritten_files: Vec<String>,
 }
 
-impl Default for FileReplaceParam {
+/// Parameters for the `list_applied_changes` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListAppliedChangesParam {
+    /// Maximum number of changes to list, newest first (default: 20)
+    #[serde(default = "default_applied_changes_limit")]
+    pub limit: usize,
+    /// Also list changes that were rolled back (default: false)
+    #[serde(default = "default_false")]
+    pub include_rolled_back: bool,
+}
+
+impl Default for ListAppliedChangesParam {
     fn default() -> Self {
         Self {
-            path_pattern: "**/*".to_string(),
-            pattern: String::new(),
-            replacement: String::new(),
-            language: String::new(),
-            max_results: default_max_results_large(),
-            max_file_size: default_max_file_size(),
-            dry_run: default_true(),
-            summary_only: default_false(),
-            include_samples: default_false(),
-            max_samples: default_max_samples(),
-            cursor: None,
-            strictness: None,
-            selector: None,
-            context: None,
+            limit: default_applied_changes_limit(),
+            include_rolled_back: false,
         }
     }
 }
 
-/// Result of a file-based replacement operation.
-///
-/// Contains either detailed diffs or summary statistics, depending on the request parameters.
-/// Uses token-efficient representation to minimize API response size.
+/// Result of `list_applied_changes`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListAppliedChangesResult {
+    /// Recorded changes, newest first
+    pub changes: Vec<AppliedChange>,
+    /// Recorded changes matching the filter, including those cut by `limit`
+    pub total: usize,
+}
+
+/// A `file_replace` that was applied and can be rolled back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppliedChange {
+    pub apply_id: String,
+    /// Seconds since the Unix epoch
+    pub applied_at: u64,
+    pub path_pattern: String,
+    pub pattern: String,
+    pub replacement: String,
+    pub language: String,
+    /// Files the replacement wrote
+    pub files: Vec<String>,
+    pub rolled_back: bool,
+}
+
+/// A file whose content changed after the dry run that previewed its replacement.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct FileReplaceResult {
-    /// Detailed file-by-file diff results (when summary_only=false)
-    pub file_results: Vec<FileDiffResult>,
-    /// Summary statistics per file (when summary_only=true)
-    pub summary_results: Vec<FileSummaryResult>,
-    /// Cursor for fetching next page of results
-    pub next_cursor: Option<CursorResult>,
-    /// Total number of files processed
-    pub total_files_found: usize,
-    /// Whether this was a dry run (no actual changes made)
-    pub dry_run: bool,
-    /// Total number of individual changes across all files
+pub struct DriftedFile {
+    /// Path to the file
+    pub file_path: String,
+    /// Hash the dry run saw
+    pub expected_hash: String,
+    /// Hash of the content found when applying
+    pub actual_hash: String,
+    /// Number of hunks wrapped in conflict markers
+    pub conflicts: usize,
+    /// Whether the merged result was written. False when the dry-run content is no longer
+    /// available to merge against, in which case the file was left untouched
+    pub written: bool,
+}
+
+/// How many of a rule's matches its fix changed, so a report can attribute changes to rules.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleFixStats {
+    pub rule_id: String,
+    /// Matches the rule found
+    pub matches: usize,
+    /// Changes made, or previewed in a dry run
     pub total_changes: usize,
-    /// Number of files that had at least one change
-    pub files_with_changes: usize,
+    /// Changes per file, for files with at least one
+    pub files: BTreeMap<String, usize>,
+    /// Matches left as they were: nested in a match that was already fixed, fixed to the same
+    /// text or held back as an unsafe fix
+    pub unfixed_matches: usize,
+}
+
+impl DriftedFile {
+    /// Whether the file needs manual attention after the apply
+    pub fn needs_resolution(&self) -> bool {
+        self.conflicts > 0 || !self.written
+    }
+}
+
+/// Parameters for `verify_refactoring`: apply a replacement, then run a configured command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyRefactoringParam {
+    /// The replacement to apply. It is always written: `dry_run` is ignored
+    pub replace: FileReplaceParam,
+    /// Name of a command configured with `--verify-command`
+    pub command: String,
+    /// Time the command may run before it is killed and counted as failed (default: 300000)
+    #[serde(default = "default_verify_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Restore the rewritten files when the command fails (default: false)
+    #[serde(default = "default_false")]
+    pub rollback_on_failure: bool,
+    /// Number of trailing output lines to keep from each stream (default: 40)
+    #[serde(default = "default_verify_tail_lines")]
+    pub tail_lines: usize,
+}
+
+/// Result of `verify_refactoring`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyRefactoringResult {
+    /// The applied replacement
+    pub replace: FileReplaceResult,
+    /// How the verification command went
+    pub verification: VerificationOutcome,
+    /// Whether the rewritten files were restored because verification failed
+    pub rolled_back: bool,
+    /// Files restored to their content from before the replacement
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub restored_files: Vec<String>,
 }
 
 /// Detailed diff information for a single file.
 ///
 /// Contains line-by-line changes for token-efficient diff visualization.
 /// Used when `summary_only=false` in FileReplaceParam.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileDiffResult {
     /// Path to the modified file
     pub file_path: String,
@@ -536,6 +2609,9 @@ pub struct FileDiffResult {
     pub total_changes: usize,
     /// SHA-256 hash of the original file content
     pub file_hash: String,
+    /// Label of the workspace containing this file, when workspaces are configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
 }
 
 /// A single line change within a file diff.
@@ -551,6 +2627,150 @@ pub struct FileDiffChange {
     pub new_content: String,
 }
 
+/// Parameters for the `add_annotation` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddAnnotationParam {
+    /// Glob pattern or direct file path to annotate
+    pub path_pattern: String,
+    /// The ast-grep pattern whose matches get the comment
+    pub pattern: String,
+    /// Programming language
+    pub language: String,
+    /// Comment text without comment markers, e.g. `TODO(PROJ-123): remove`; each line becomes
+    /// one comment line
+    pub comment: String,
+    /// CSS-like selector to filter matches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// Additional rule context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// If true, preview the annotations without modifying files (default: true)
+    #[serde(default = "default_true")]
+    pub dry_run: bool,
+    /// Maximum number of files to annotate (default: 10000)
+    #[serde(default = "default_max_results_large")]
+    pub max_results: usize,
+    /// Maximum file size to process in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+    /// Limit the call to the root directory with this workspace label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    /// Annotate the matches of a pattern that is a lone metavariable such as `$_` or `$A`
+    /// (default: false)
+    #[serde(default = "default_false")]
+    pub allow_broad: bool,
+    /// Walk depth, pruned directories and hidden entries; unset options use the server's defaults
+    #[serde(flatten)]
+    pub walk: WalkOptions,
+}
+
+impl Default for AddAnnotationParam {
+    fn default() -> Self {
+        Self {
+            path_pattern: String::new(),
+            pattern: String::new(),
+            language: String::new(),
+            comment: String::new(),
+            selector: None,
+            context: None,
+            dry_run: default_true(),
+            max_results: default_max_results_large(),
+            max_file_size: default_max_file_size(),
+            workspace: None,
+            allow_broad: false,
+            walk: WalkOptions::default(),
+        }
+    }
+}
+
+/// Result of `add_annotation`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddAnnotationResult {
+    /// Files given annotations, each change being the annotated line before and after
+    pub file_results: Vec<FileDiffResult>,
+    pub total_annotations: usize,
+    pub files_with_changes: usize,
+    /// Matched lines that already had the comment right above them
+    pub already_annotated: usize,
+    /// Whether `max_results` left files with matches unannotated
+    pub truncated: bool,
+    pub dry_run: bool,
+}
+
+/// Parameters for the `wrap_matches` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrapMatchesParam {
+    /// Glob pattern or direct file path to rewrite
+    pub path_pattern: String,
+    /// The ast-grep pattern whose matches get wrapped
+    pub pattern: String,
+    /// Programming language
+    pub language: String,
+    /// Code around each match, with `$MATCH` where the matched code goes, e.g.
+    /// `measure(() => $MATCH)`
+    pub template: String,
+    /// CSS-like selector to filter matches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// Additional rule context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// If true, preview the rewrites without modifying files (default: true)
+    #[serde(default = "default_true")]
+    pub dry_run: bool,
+    /// Maximum number of files to rewrite (default: 10000)
+    #[serde(default = "default_max_results_large")]
+    pub max_results: usize,
+    /// Maximum file size to process in bytes (default: 50MB)
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+    /// Limit the call to the root directory with this workspace label
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
+    /// Wrap the matches of a pattern that is a lone metavariable such as `$_` or `$A`
+    /// (default: false)
+    #[serde(default = "default_false")]
+    pub allow_broad: bool,
+    /// Walk depth, pruned directories and hidden entries; unset options use the server's defaults
+    #[serde(flatten)]
+    pub walk: WalkOptions,
+}
+
+impl Default for WrapMatchesParam {
+    fn default() -> Self {
+        Self {
+            path_pattern: String::new(),
+            pattern: String::new(),
+            language: String::new(),
+            template: String::new(),
+            selector: None,
+            context: None,
+            dry_run: default_true(),
+            max_results: default_max_results_large(),
+            max_file_size: default_max_file_size(),
+            workspace: None,
+            allow_broad: false,
+            walk: WalkOptions::default(),
+        }
+    }
+}
+
+/// Result of `wrap_matches`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WrapMatchesResult {
+    /// Rewritten files, each change being a match before and after wrapping
+    pub file_results: Vec<FileDiffResult>,
+    pub total_wraps: usize,
+    pub files_with_changes: usize,
+    /// Matches left alone because they sit inside another match that was wrapped
+    pub nested_matches: usize,
+    /// Whether `max_results` left files with matches unwrapped
+    pub truncated: bool,
+    pub dry_run: bool,
+}
+
 /// Summary statistics for a single file's changes.
 ///
 /// Used when `summary_only=true` to provide change counts without full diff details.
@@ -569,6 +2789,25 @@ pub struct FileSummaryResult {
     pub file_hash: String,
     /// Sample changes for preview (limited by max_samples)
     pub sample_changes: Vec<ChangeResult>,
+    /// Every edit against the overlay content, present when the file came from `overlays`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edits: Option<Vec<ChangeResult>>,
+    /// Formatter verification, present when `verify_formatting` was requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format_check: Option<FormatCheck>,
+    /// Why `format_after_replace` left this file's rewrite unformatted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format_error: Option<String>,
+    /// Document edits, present when `json_patch` was requested; `sample_changes` is then empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_patch: Option<Vec<JsonPatchOperation>>,
+    /// Unified diff of the file, present when `output` is `unified_diff`; `sample_changes` is
+    /// then empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unified_diff: Option<String>,
+    /// Label of the workspace containing this file, when workspaces are configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<String>,
 }
 
 /// Parameters for listing supported programming languages.
@@ -584,6 +2823,181 @@ pub struct ListLanguagesResult {
     pub languages: Vec<String>,
 }
 
+/// Parameters for `create_playground`, which writes a sample project to try the tools on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatePlaygroundParam {
+    /// Directory to write the project into, absolute or relative to the first root directory
+    /// (default: "ast-grep-playground")
+    #[serde(default = "default_playground_directory")]
+    pub directory: String,
+    /// Write into a directory that already has files, replacing the sample files in it
+    #[serde(default = "default_false")]
+    pub overwrite: bool,
+}
+
+impl Default for CreatePlaygroundParam {
+    fn default() -> Self {
+        Self {
+            directory: default_playground_directory(),
+            overwrite: false,
+        }
+    }
+}
+
+/// The sample project written by `create_playground`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatePlaygroundResult {
+    /// Absolute path of the project
+    pub directory: String,
+    /// Files written, relative to `directory`
+    pub files: Vec<String>,
+    /// Tool calls to try on the project, each finding something in it
+    pub suggestions: Vec<PlaygroundSuggestion>,
+}
+
+/// A tool call to try on the sample project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaygroundSuggestion {
+    pub tool: String,
+    /// What the call finds or changes
+    pub description: String,
+    /// Arguments of the call, with path patterns pointing into the project
+    pub arguments: serde_json::Value,
+}
+
+/// Parameters for `detect_language`. At least one of them is required; a `path` naming a file
+/// below a root directory is read when there is no `code`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DetectLanguageParam {
+    /// File path, decided by its extension or else its `#!` line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// File extension, with or without the leading dot (e.g. "tsx")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extension: Option<String>,
+    /// Code to detect the language of when neither the path nor the extension decides
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+/// What decided the language in `detect_language`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DetectionMethod {
+    /// The path or extension given
+    Extension,
+    /// The interpreter of a `#!` line
+    Shebang,
+    /// The grammars parsing the code
+    Content,
+    /// Nothing did
+    None,
+}
+
+/// Language detected by `detect_language`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectLanguageResult {
+    /// Language as the tools take it (e.g. "typescript"); missing when nothing decides
+    pub language: Option<String>,
+    pub method: DetectionMethod,
+    /// From 0 to 1: 1 for an extension, 0.9 for a `#!` line and at most 0.8 for content
+    pub confidence: f64,
+    /// Extensions of the language's files, as searches with `language: "auto"` pick them
+    pub extensions: Vec<String>,
+    /// A `path_pattern` matching every file of the language (e.g. "**/*.{cts,mts,ts}")
+    pub path_pattern: Option<String>,
+    /// For content detection, the grammars parsing the code without errors, best first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<LanguageCandidate>,
+    /// Why the language was picked, or why none was
+    pub reason: String,
+}
+
+/// A grammar parsing the code given to `detect_language` without errors
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageCandidate {
+    pub language: String,
+    /// Keywords of the grammar found in the code
+    pub keywords: usize,
+    /// Keywords relative to the best candidate's, from 0 to 1
+    pub score: f64,
+}
+
+/// Parameters for `index_status`, which takes none
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IndexStatusParam {}
+
+/// Parameters for `health_check`, which takes none
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthCheckParam {}
+
+/// Report of `health_check` and the `doctor` subcommand.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheckResult {
+    /// Whether no check failed; warnings don't count
+    pub healthy: bool,
+    pub errors: usize,
+    pub warnings: usize,
+    /// Every check, root directories first
+    pub checks: Vec<HealthCheck>,
+}
+
+/// One check of `health_check`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthCheck {
+    /// What was checked: `root_directory`, `rules_directory`, `rules_database`,
+    /// `coverage_directory`, `undo_directory` or `grammars`
+    pub check: String,
+    /// The path checked, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub status: CheckStatus,
+    /// What was found, and what failed
+    pub message: String,
+}
+
+/// Outcome of a `health_check` check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    /// Usable, but not set up yet, such as a directory created on first use
+    Warning,
+    Error,
+}
+
+/// Parameters for `rebuild_index`, which takes none
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RebuildIndexParam {}
+
+/// State of the file index that `file_search` lists files from and keeps syntax trees in
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexStatus {
+    /// Whether the server was started with the index (`--index`)
+    pub enabled: bool,
+    /// Whether changes under the root directories are applied as they happen
+    pub watching: bool,
+    /// Whether the file lists are current; a created or renamed file has them walked again on
+    /// the next search
+    pub built: bool,
+    pub root_directories: Vec<String>,
+    /// Files in the file lists
+    pub files: usize,
+    /// Files whose syntax tree is kept
+    pub parsed_files: usize,
+    /// Times the root directories were walked
+    pub builds: usize,
+    /// How long the last walk took
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_build_ms: Option<u64>,
+    /// Changes under the root directories seen by the watcher
+    pub file_events: usize,
+    /// Searches of a file that reused its kept syntax tree
+    pub cache_hits: usize,
+    /// Searches of a file that had to parse it
+    pub cache_misses: usize,
+}
+
 /// Parameters for generating syntax tree representations.
 ///
 /// Essential for LLM users to understand Tree-sitter node structure and discover
@@ -597,14 +3011,19 @@ pub struct ListLanguagesResult {
 /// let param = GenerateAstParam {
 ///     code: "function test() { return 42; }".to_string(),
 ///     language: "javascript".to_string(),
+///     buffer_id: None,
 /// };
 /// ```
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GenerateAstParam {
-    /// Source code to parse into AST
+    /// Source code to parse into AST; empty when `buffer_id` is given
+    #[serde(default)]
     pub code: String,
     /// Programming language for parsing
     pub language: String,
+    /// Parse the content of a code buffer (see `begin_code_buffer`) instead of `code`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_id: Option<String>,
 }
 
 /// Result containing AST representation and discovered node kinds.
@@ -619,8 +3038,91 @@ pub struct GenerateAstResult {
     pub language: String,
     /// Length of the input code in characters
     pub code_length: usize,
-    /// All Tree-sitter node kinds found (e.g., ["function_declaration", "identifier"])
+    /// All Tree-sitter node kinds found (e.g., ["identifier", "function_declaration"]), most
+    /// frequent first and ties in alphabetical order
     pub node_kinds: Vec<String>,
+    /// The kinds in `node_kinds` order with their counts and category
+    #[serde(default)]
+    pub node_kind_stats: Vec<NodeKindCount>,
+    /// Number of nodes of each kind, keyed by kind
+    #[serde(default)]
+    pub kind_counts: BTreeMap<String, usize>,
+}
+
+/// How often a node kind occurs in the parsed code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeKindCount {
+    pub kind: String,
+    pub count: usize,
+    /// Rough role of the kind, guessed from its name; absent for punctuation and kinds that
+    /// don't follow the usual naming
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<NodeCategory>,
+}
+
+/// Rough syntactic role of a node kind, for grouping kinds in pattern-authoring UIs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeCategory {
+    Declaration,
+    Statement,
+    Expression,
+    Literal,
+    Identifier,
+    Comment,
+}
+
+impl NodeCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NodeCategory::Declaration => "declaration",
+            NodeCategory::Statement => "statement",
+            NodeCategory::Expression => "expression",
+            NodeCategory::Literal => "literal",
+            NodeCategory::Identifier => "identifier",
+            NodeCategory::Comment => "comment",
+        }
+    }
+
+    /// Guess the category from Tree-sitter naming conventions shared by the bundled grammars
+    pub fn for_kind(kind: &str) -> Option<Self> {
+        const LITERALS: &[&str] = &[
+            "string",
+            "number",
+            "integer",
+            "float",
+            "true",
+            "false",
+            "null",
+            "none",
+            "nil",
+            "undefined",
+            "boolean",
+            "template_string",
+            "string_fragment",
+            "escape_sequence",
+        ];
+        let category = if kind.contains("comment") {
+            NodeCategory::Comment
+        } else if kind.ends_with("literal") || LITERALS.contains(&kind) {
+            NodeCategory::Literal
+        } else if kind == "identifier" || kind.ends_with("_identifier") {
+            NodeCategory::Identifier
+        } else if kind.ends_with("declaration")
+            || kind.ends_with("definition")
+            || kind.ends_with("_item")
+            || kind.ends_with("_specifier")
+        {
+            NodeCategory::Declaration
+        } else if kind.contains("statement") {
+            NodeCategory::Statement
+        } else if kind.contains("expression") || kind == "call" || kind == "attribute" {
+            NodeCategory::Expression
+        } else {
+            return None;
+        };
+        Some(category)
+    }
 }
 
 /// Parameters for analyzing code fragments for refactoring potential.
@@ -798,6 +3300,16 @@ pub fn default_max_results() -> usize {
     20
 }
 
+/// Default number of changes listed by `list_applied_changes` (20)
+pub fn default_applied_changes_limit() -> usize {
+    20
+}
+
+/// Default maximum matches returned per `batch_search` search (100)
+pub fn default_batch_max_results() -> usize {
+    100
+}
+
 /// Default maximum results for large operations like file replacement (10,000)
 pub fn default_max_results_large() -> usize {
     10000
@@ -818,11 +3330,141 @@ pub fn default_false() -> bool {
     false
 }
 
+/// Default directory for `summarize_directory` (the root directories)
+pub fn default_summary_directory() -> String {
+    ".".to_string()
+}
+
+/// Default maximum number of files for `summarize_directory` (500)
+pub fn default_summary_max_files() -> usize {
+    500
+}
+
+/// Default number of largest functions to list (5)
+pub fn default_largest_functions() -> usize {
+    5
+}
+
+/// Default lowest similarity reported by `search_symbols`
+pub fn default_min_similarity() -> f64 {
+    0.6
+}
+
+/// Default number of symbols returned by `search_symbols`
+pub fn default_symbol_max_results() -> usize {
+    20
+}
+
+/// Default number of files indexed by `search_symbols`
+pub fn default_symbol_max_files() -> usize {
+    5000
+}
+
+/// Default number of undocumented symbols listed per `doc_coverage` page (50)
+pub fn default_doc_coverage_page_size() -> usize {
+    50
+}
+
+/// Default number of files sampled by `detect_conventions` (200)
+pub fn default_convention_max_files() -> usize {
+    200
+}
+
+/// Default number of references returned by `find_references` (500)
+pub fn default_reference_max_results() -> usize {
+    500
+}
+
+/// Default lowest similarity reported by `find_similar_code`
+pub fn default_similar_code_min_score() -> f64 {
+    0.5
+}
+
+/// Default number of matches returned by `find_similar_code` (50)
+pub fn default_similar_code_max_results() -> usize {
+    50
+}
+
+/// Default fewest lines of a function or block compared by `dedupe_code` (5)
+pub fn default_dedupe_min_lines() -> usize {
+    5
+}
+
+/// Default fewest tokens of a function or block compared by `dedupe_code` (30)
+pub fn default_dedupe_min_tokens() -> usize {
+    30
+}
+
+/// Default number of clone groups returned by `dedupe_code` (50)
+pub fn default_dedupe_max_groups() -> usize {
+    50
+}
+
+/// Default number of matches `experiment` returns per candidate (5)
+pub fn default_experiment_max_matches() -> usize {
+    5
+}
+
+/// Default time a `verify_refactoring` command may run (5 minutes)
+pub fn default_verify_timeout_ms() -> u64 {
+    300_000
+}
+
+/// Default number of output lines kept from a verification command (40)
+pub fn default_verify_tail_lines() -> usize {
+    40
+}
+
+/// Default time a code buffer stays open after its last use (15 minutes)
+pub fn default_buffer_ttl_seconds() -> u64 {
+    900
+}
+
+/// Default glob for `pattern_coverage` (every file under the roots)
+pub fn default_coverage_path_pattern() -> String {
+    "**/*".to_string()
+}
+
+/// Default time budget for a `pattern_coverage` count (1 minute)
+pub fn default_coverage_timeout_ms() -> u64 {
+    60_000
+}
+
+/// Default number of snapshots returned by `pattern_coverage` (20)
+pub fn default_coverage_history_limit() -> usize {
+    20
+}
+
+/// Default number of unchanged lines around each unified diff hunk (3)
+pub fn default_diff_context() -> usize {
+    3
+}
+
 /// Default maximum number of sample changes to include (3)
 pub fn default_max_samples() -> usize {
     3
 }
 
+/// Default language of `run_codemod` ("typescript")
+pub fn default_codemod_language() -> String {
+    "typescript".to_string()
+}
+
+/// Default directory levels listed by `project_overview` (2)
+pub fn default_overview_tree_depth() -> usize {
+    2
+}
+
+/// Default number of largest files listed by `project_overview` (10)
+pub fn default_overview_largest_files() -> usize {
+    10
+}
+
+/// Default directory of the `create_playground` project ("ast-grep-playground")
+pub fn default_playground_directory() -> String {
+    "ast-grep-playground".to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -917,6 +3559,7 @@ mod tests {
         let cursor = CursorParam {
             last_file_path: "test/file.js".to_string(),
             is_complete: false,
+            token: None,
         };
 
         let serialized = serde_json::to_string(&cursor).unwrap();
@@ -933,6 +3576,8 @@ mod tests {
             end_line: 1,
             start_col: 0,
             end_col: 10,
+            start_byte: 0,
+            end_byte: 10,
             old_text: "var x = 1;".to_string(),
             new_text: "let x = 1;".to_string(),
         };
@@ -950,6 +3595,8 @@ mod tests {
                 end_line: 1,
                 start_col: 0,
                 end_col: 10,
+                start_byte: 0,
+                end_byte: 10,
                 old_text: "var x = 1;".to_string(),
                 new_text: "let x = 1;".to_string(),
             },
@@ -958,6 +3605,8 @@ mod tests {
                 end_line: 2,
                 start_col: 0,
                 end_col: 10,
+                start_byte: 11,
+                end_byte: 21,
                 old_text: "var y = 2;".to_string(),
                 new_text: "let y = 2;".to_string(),
             },
@@ -969,6 +3618,7 @@ mod tests {
             changes: changes.clone(),
             total_changes: changes.len(),
             file_hash: "abc123".to_string(),
+            workspace: None,
         };
 
         assert_eq!(diff_result.file_path, "test.js");
@@ -983,6 +3633,8 @@ mod tests {
             end_line: 1,
             start_col: 0,
             end_col: 10,
+            start_byte: 0,
+            end_byte: 10,
             old_text: "var x = 1;".to_string(),
             new_text: "let x = 1;".to_string(),
         }];
@@ -994,6 +3646,12 @@ mod tests {
             lines_changed: 3,
             file_hash: "abc123".to_string(),
             sample_changes,
+            edits: None,
+            format_check: None,
+            format_error: None,
+            workspace: None,
+            json_patch: None,
+            unified_diff: None,
         };
 
         assert_eq!(summary_result.file_path, "test.js");
@@ -1007,6 +3665,7 @@ mod tests {
         let param = GenerateAstParam {
             code: "function test() {}".to_string(),
             language: "javascript".to_string(),
+            buffer_id: None,
         };
 
         let serialized = serde_json::to_string(&param).unwrap();
@@ -0,0 +1,366 @@
+//! # Unified Diffs
+//!
+//! `file_replace` with `output: "unified_diff"` reports each file's rewrite as a standard
+//! unified diff, the format of `diff -u` and `git diff`, so it can be reviewed or applied with
+//! ordinary tools. `apply_patch` applies such a patch later, for a review-then-apply workflow.
+//!
+//! File headers carry the path as `file_replace` reports it, with no `a/` or `b/` prefix.
+//! When applying, `a/` and `b/` prefixes are stripped if both headers have them, as `git diff`
+//! writes them. A hunk applies where its header says, or at the nearest place its context and
+//! removed lines match if the lines above it moved, like `patch` does. Patches that create or
+//! delete files are refused.
+
+use crate::errors::ServiceError;
+use crate::merge::changed_hunks;
+use std::ops::Range;
+
+/// Marks a line without a newline at the end of the file
+const NO_NEWLINE: &str = "\\ No newline at end of file";
+
+/// The unified diff turning `old` into `new`, with `context` unchanged lines around each
+/// change and `path` in both file headers. Empty when nothing changed
+pub fn file_diff(path: &str, old: &str, new: &str, context: usize) -> String {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let changes = changed_hunks(&old_lines, &new_lines);
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    let mut diff = format!("--- {path}\n+++ {path}\n");
+    let mut group_start = 0;
+    for i in 1..=changes.len() {
+        // Changes closer than twice the context share a hunk
+        let joined = i < changes.len() && changes[i].0.start - changes[i - 1].0.end <= 2 * context;
+        if !joined {
+            push_hunk(
+                &mut diff,
+                &old_lines,
+                &new_lines,
+                &changes[group_start..i],
+                context,
+            );
+            group_start = i;
+        }
+    }
+    diff
+}
+
+fn push_hunk(
+    diff: &mut String,
+    old_lines: &[&str],
+    new_lines: &[&str],
+    changes: &[(Range<usize>, Range<usize>)],
+    context: usize,
+) {
+    let (first, last) = (&changes[0], &changes[changes.len() - 1]);
+    let old_start = first.0.start.saturating_sub(context);
+    let old_end = (last.0.end + context).min(old_lines.len());
+    let new_start = first.1.start - (first.0.start - old_start);
+    let new_end = last.1.end + (old_end - last.0.end);
+    diff.push_str(&format!(
+        "@@ -{} +{} @@\n",
+        hunk_range(old_start, old_end - old_start),
+        hunk_range(new_start, new_end - new_start)
+    ));
+
+    let mut old_line = old_start;
+    for (removed, added) in changes {
+        for line in &old_lines[old_line..removed.start] {
+            push_line(diff, ' ', line);
+        }
+        for line in &old_lines[removed.clone()] {
+            push_line(diff, '-', line);
+        }
+        for line in &new_lines[added.clone()] {
+            push_line(diff, '+', line);
+        }
+        old_line = removed.end;
+    }
+    for line in &old_lines[old_line..old_end] {
+        push_line(diff, ' ', line);
+    }
+}
+
+/// `start,count` of a hunk header; an empty range names the line before it
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{count}", start + 1),
+    }
+}
+
+fn push_line(diff: &mut String, marker: char, line: &str) {
+    diff.push(marker);
+    diff.push_str(line);
+    if !line.ends_with('\n') {
+        diff.push('\n');
+        diff.push_str(NO_NEWLINE);
+        diff.push('\n');
+    }
+}
+
+/// The hunks of one file in a patch
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilePatch {
+    pub path: String,
+    pub hunks: Vec<Hunk>,
+}
+
+/// One `@@` section: the lines it expects, from line `old_start` (0-based), and their
+/// replacement. Lines keep their newlines
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_lines: Vec<String>,
+    pub new_lines: Vec<String>,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Parse every file of a unified diff. Text outside file sections, like `diff --git` lines,
+/// is ignored
+pub fn parse_patch(patch: &str) -> Result<Vec<FilePatch>, ServiceError> {
+    let invalid = |message: String| ServiceError::ParserError(format!("Invalid patch: {message}"));
+    let mut files = Vec::new();
+    // Unlike str::lines, keep the \r of CRLF files
+    let mut lines = patch
+        .split_inclusive('\n')
+        .map(|line| line.strip_suffix('\n').unwrap_or(line))
+        .peekable();
+    while let Some(line) = lines.next() {
+        let Some(old_path) = line.strip_prefix("--- ") else {
+            continue;
+        };
+        let new_path = lines
+            .next()
+            .and_then(|line| line.strip_prefix("+++ "))
+            .ok_or_else(|| invalid(format!("'{line}' is not followed by a '+++' line")))?;
+        let (old_path, new_path) = (header_path(old_path), header_path(new_path));
+        if old_path == "/dev/null" || new_path == "/dev/null" {
+            let path = if new_path == "/dev/null" {
+                old_path
+            } else {
+                new_path
+            };
+            return Err(ServiceError::ParserError(format!(
+                "The patch creates or deletes {path}; only changes to existing files can be applied"
+            )));
+        }
+        let path = match (old_path.strip_prefix("a/"), new_path.strip_prefix("b/")) {
+            (Some(_), Some(path)) => path,
+            _ => new_path,
+        };
+
+        let mut hunks = Vec::new();
+        while let Some(header) = lines.next_if(|line| line.starts_with("@@ ")) {
+            let (old_start, old_count, new_count) = parse_hunk_header(header)
+                .ok_or_else(|| invalid(format!("bad hunk header '{header}' in {path}")))?;
+            let mut hunk = Hunk {
+                old_start: match old_count {
+                    0 => old_start,
+                    _ => old_start.saturating_sub(1),
+                },
+                old_lines: Vec::new(),
+                new_lines: Vec::new(),
+                added: 0,
+                removed: 0,
+            };
+            let mut last_marker = ' ';
+            loop {
+                let complete =
+                    hunk.old_lines.len() >= old_count && hunk.new_lines.len() >= new_count;
+                let Some(line) = lines.next_if(|line| !complete || line.starts_with('\\')) else {
+                    match complete {
+                        true => break,
+                        false => {
+                            return Err(invalid(format!("hunk '{header}' of {path} is cut short")));
+                        }
+                    }
+                };
+                // Some tools drop the space of blank context lines
+                let marker = line.chars().next().unwrap_or(' ');
+                let text = format!("{}\n", line.get(1..).unwrap_or_default());
+                match marker {
+                    ' ' => {
+                        hunk.old_lines.push(text.clone());
+                        hunk.new_lines.push(text);
+                    }
+                    '-' => {
+                        hunk.old_lines.push(text);
+                        hunk.removed += 1;
+                    }
+                    '+' => {
+                        hunk.new_lines.push(text);
+                        hunk.added += 1;
+                    }
+                    '\\' => {
+                        if matches!(last_marker, ' ' | '-') {
+                            strip_newline(hunk.old_lines.last_mut());
+                        }
+                        if matches!(last_marker, ' ' | '+') {
+                            strip_newline(hunk.new_lines.last_mut());
+                        }
+                        continue;
+                    }
+                    _ => {
+                        return Err(invalid(format!(
+                            "unexpected line '{line}' in hunk '{header}' of {path}"
+                        )));
+                    }
+                }
+                last_marker = marker;
+            }
+            if hunk.old_lines.len() != old_count || hunk.new_lines.len() != new_count {
+                return Err(invalid(format!(
+                    "the lines of hunk '{header}' of {path} don't add up to its header"
+                )));
+            }
+            hunks.push(hunk);
+        }
+        if hunks.is_empty() {
+            return Err(invalid(format!("{path} has no hunks")));
+        }
+        files.push(FilePatch {
+            path: path.to_string(),
+            hunks,
+        });
+    }
+    match files.is_empty() {
+        true => Err(invalid(
+            "it has no '---' and '+++' file headers".to_string(),
+        )),
+        false => Ok(files),
+    }
+}
+
+/// The path of a `---` or `+++` header, without the timestamp `diff -u` appends after a tab
+fn header_path(header: &str) -> &str {
+    header.split('\t').next().unwrap_or_default().trim_end()
+}
+
+/// Start line and line counts of a `@@ -start,count +start,count @@` header
+fn parse_hunk_header(header: &str) -> Option<(usize, usize, usize)> {
+    let ranges = header.strip_prefix("@@ -")?.split(" @@").next()?;
+    let (old, new) = ranges.split_once(" +")?;
+    let parse = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let ((old_start, old_count), (_, new_count)) = (parse(old)?, parse(new)?);
+    Some((old_start, old_count, new_count))
+}
+
+fn strip_newline(line: Option<&mut String>) {
+    if let Some(line) = line
+        && line.ends_with('\n')
+    {
+        line.pop();
+    }
+}
+
+/// `content` with the hunks of `patch` applied. Fails when a hunk's context and removed lines
+/// are found nowhere after the previous hunk
+pub fn apply(content: &str, patch: &FilePatch) -> Result<String, ServiceError> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut patched = String::with_capacity(content.len());
+    let mut copied = 0;
+    // How far the hunks applied so far were from where their headers put them
+    let mut offset = 0isize;
+    for (index, hunk) in patch.hunks.iter().enumerate() {
+        let expected = hunk.old_start.saturating_add_signed(offset);
+        let start = find_lines(&lines, &hunk.old_lines, expected, copied).ok_or_else(|| {
+            ServiceError::Internal(format!(
+                "Hunk {} of {} does not apply: the lines it changes at line {} are not in the file",
+                index + 1,
+                patch.path,
+                hunk.old_start + 1
+            ))
+        })?;
+        patched.extend(lines[copied..start].iter().copied());
+        patched.extend(hunk.new_lines.iter().map(String::as_str));
+        copied = start + hunk.old_lines.len();
+        offset = start as isize - hunk.old_start as isize;
+    }
+    patched.extend(lines[copied..].iter().copied());
+    Ok(patched)
+}
+
+/// The start of the run of `wanted` lines, at or after `from`, nearest to `expected`
+fn find_lines(lines: &[&str], wanted: &[String], expected: usize, from: usize) -> Option<usize> {
+    let last_start = lines.len().checked_sub(wanted.len())?;
+    (from..=last_start)
+        .filter(|&start| {
+            lines[start..start + wanted.len()]
+                .iter()
+                .zip(wanted)
+                .all(|(line, wanted)| line == wanted)
+        })
+        .min_by_key(|&start| start.abs_diff(expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+
+    #[test]
+    fn test_file_diff_groups_nearby_changes() {
+        let new = OLD
+            .replace("b\n", "B\n")
+            .replace("d\n", "D\n")
+            .replace("j\n", "J\n");
+        assert_eq!(
+            file_diff("src/x.txt", OLD, &new, 1),
+            "--- src/x.txt\n+++ src/x.txt\n\
+             @@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n-d\n+D\n e\n\
+             @@ -9,2 +9,2 @@\n i\n-j\n+J\n"
+        );
+        assert_eq!(file_diff("x", OLD, OLD, 3), "");
+    }
+
+    #[test]
+    fn test_patches_round_trip() {
+        let cases = [
+            (OLD, OLD.replace("e\n", "e\nE\n")),
+            (OLD, OLD.replace("a\nb\n", "")),
+            ("one\ntwo", "one\nTWO".to_string()),
+            ("one\ntwo\n", "one\ntwo".to_string()),
+            ("", "new\n".to_string()),
+            ("x\r\ny\r\n", "x\r\nz\r\n".to_string()),
+        ];
+        for (old, new) in cases {
+            for context in [0, 3] {
+                let diff = file_diff("f", old, &new, context);
+                let files = parse_patch(&diff).unwrap();
+                assert_eq!(apply(old, &files[0]).unwrap(), new, "{diff}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_hunks_apply_after_lines_moved() {
+        let new = OLD.replace("h\n", "H\n");
+        let patch = &parse_patch(&file_diff("f", OLD, &new, 2)).unwrap()[0];
+        let moved = format!("0\n1\n{OLD}");
+        assert_eq!(apply(&moved, patch).unwrap(), format!("0\n1\n{new}"));
+        let error = apply(&OLD.replace("g\n", "G\n"), patch).unwrap_err();
+        assert!(
+            error.to_string().contains("Hunk 1 of f does not apply"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_git_prefixes_and_new_files() {
+        let diff = "diff --git a/src/x b/src/x\n--- a/src/x\n+++ b/src/x\n@@ -1 +1 @@\n-a\n+b\n";
+        assert_eq!(parse_patch(diff).unwrap()[0].path, "src/x");
+        let diff = "--- /dev/null\n+++ b/new\n@@ -0,0 +1 @@\n+a\n";
+        assert!(parse_patch(diff).is_err());
+        assert!(parse_patch("no patch here").is_err());
+    }
+}
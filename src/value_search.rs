@@ -0,0 +1,196 @@
+//! # Literal, Identifier and Comment Search
+//!
+//! Finds string literal, identifier or comment nodes whose value equals a given string or
+//! matches a regex. Quoting rules and identifier node kinds differ between grammars, which makes these
+//! searches awkward to write as raw patterns.
+
+use crate::errors::ServiceError;
+use crate::types::MatchResult;
+use ast_grep_core::tree_sitter::StrDoc;
+use ast_grep_core::{AstGrep, Node, NodeMatch};
+use ast_grep_language::SupportLang as Language;
+use regex::Regex;
+
+/// Tags reported by `find_comments` when no regex is given
+pub const DEFAULT_COMMENT_TAG_REGEX: &str = r"\b(TODO|FIXME|HACK|XXX)\b";
+
+/// Which family of nodes a value search looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueNodeKind {
+    /// String literals, compared by their unquoted contents
+    StringLiteral,
+    /// Identifiers of any flavor (variables, properties, types, fields, ...)
+    Identifier,
+    /// Line, block and doc comments, compared by their full text
+    Comment,
+}
+
+impl ValueNodeKind {
+    fn accepts(&self, node: &Node<StrDoc<Language>>) -> bool {
+        if !node.is_named() {
+            return false;
+        }
+        let kind = node.kind();
+        match self {
+            Self::StringLiteral => matches!(
+                kind.as_ref(),
+                "string"
+                    | "string_literal"
+                    | "raw_string_literal"
+                    | "interpreted_string_literal"
+                    | "template_string"
+                    | "string_value"
+                    | "line_string_literal"
+                    | "multi_line_string_literal"
+                    | "verbatim_string_literal"
+                    | "encapsed_string"
+                    | "raw_string"
+                    | "double_quote_scalar"
+                    | "single_quote_scalar"
+            ),
+            // Qualified names like `std::mem` are made of smaller identifiers, so only leaves count
+            Self::Identifier => {
+                node.is_leaf()
+                    && (matches!(
+                        kind.as_ref(),
+                        "identifier"
+                            | "constant"
+                            | "name"
+                            | "shorthand_property_identifier_pattern"
+                    ) || kind.ends_with("_identifier"))
+            }
+            // Some grammars nest doc comment nodes inside the comment that carries them
+            Self::Comment => {
+                kind.ends_with("comment")
+                    && !node
+                        .parent()
+                        .is_some_and(|parent| parent.kind().ends_with("comment"))
+            }
+        }
+    }
+
+    fn value<'a>(&self, text: &'a str) -> &'a str {
+        match self {
+            Self::StringLiteral => unquote(text),
+            Self::Identifier | Self::Comment => text,
+        }
+    }
+}
+
+/// How a node's value is compared
+#[derive(Debug, Clone)]
+pub enum ValueMatcher {
+    Exact(String),
+    Regex(Regex),
+}
+
+impl ValueMatcher {
+    /// Build a matcher from the mutually exclusive `value` and `regex` parameters
+    pub fn new(value: Option<&str>, regex: Option<&str>) -> Result<Self, ServiceError> {
+        match (value, regex) {
+            (Some(value), None) => Ok(Self::Exact(value.to_string())),
+            (None, Some(regex)) => Regex::new(regex)
+                .map(Self::Regex)
+                .map_err(|e| ServiceError::ParserError(format!("Invalid regex '{regex}': {e}"))),
+            _ => Err(ServiceError::ParserError(
+                "Exactly one of 'value' or 'regex' must be provided".to_string(),
+            )),
+        }
+    }
+
+    pub fn is_match(&self, value: &str) -> bool {
+        match self {
+            Self::Exact(expected) => value == expected,
+            Self::Regex(regex) => regex.is_match(value),
+        }
+    }
+}
+
+/// Find all nodes of `kind` in `code` whose value satisfies `matcher`.
+///
+/// The compared value (unquoted for string literals) is reported as the `VALUE` variable.
+pub fn find_value_nodes(
+    code: &str,
+    lang: Language,
+    kind: ValueNodeKind,
+    matcher: &ValueMatcher,
+) -> Vec<MatchResult> {
+    let ast = AstGrep::new(code, lang);
+    let root = ast.root();
+
+    root.dfs()
+        .filter(|node| kind.accepts(node))
+        .filter_map(|node| {
+            let text = node.text();
+            let value = kind.value(&text);
+            if !matcher.is_match(value) {
+                return None;
+            }
+            let value = value.to_string();
+            let mut result = MatchResult::from_node_match(&NodeMatch::from(node));
+            result.vars.insert("VALUE".to_string(), value);
+            Some(result)
+        })
+        .collect()
+}
+
+/// Strip the quotes (and any prefix such as `r#`, `b`, `f` or `@`) from a string literal
+fn unquote(text: &str) -> &str {
+    let Some(open) = text.find(['"', '\'', '`']) else {
+        return text;
+    };
+    let prefix = &text[..open];
+    if !prefix
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '#' | '@' | '$'))
+    {
+        return text;
+    }
+
+    // Rust raw strings close with as many `#` as they opened with
+    let hashes = prefix.chars().filter(|&c| c == '#').count();
+    let quote = text[open..].chars().next().unwrap_or('"');
+    let Some(body) = text[open..].strip_suffix(&"#".repeat(hashes)) else {
+        return text;
+    };
+
+    let triple = quote.to_string().repeat(3);
+    let quote_len = if body.len() >= 6 && body.starts_with(&triple) && body.ends_with(&triple) {
+        3
+    } else {
+        1
+    };
+    if body.len() < quote_len * 2 || !body.ends_with(quote) {
+        return text;
+    }
+    &body[quote_len..body.len() - quote_len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unquote() {
+        assert_eq!(unquote("\"hello\""), "hello");
+        assert_eq!(unquote("'hello'"), "hello");
+        assert_eq!(unquote("`hello ${x}`"), "hello ${x}");
+        assert_eq!(unquote("r#\"raw\"#"), "raw");
+        assert_eq!(unquote("f\"{x}\""), "{x}");
+        assert_eq!(unquote("\"\"\"doc\"\"\""), "doc");
+        assert_eq!(unquote("\"\""), "");
+        assert_eq!(unquote("plain"), "plain");
+    }
+
+    #[test]
+    fn test_value_matcher_requires_one_criterion() {
+        assert!(ValueMatcher::new(None, None).is_err());
+        assert!(ValueMatcher::new(Some("a"), Some("b")).is_err());
+        assert!(ValueMatcher::new(None, Some("(")).is_err());
+        assert!(
+            ValueMatcher::new(None, Some("^https?://"))
+                .unwrap()
+                .is_match("https://x")
+        );
+    }
+}
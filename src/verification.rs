@@ -0,0 +1,143 @@
+//! # Refactoring Verification
+//!
+//! `verify_refactoring` applies a `file_replace` and then runs one of the verification commands
+//! configured with `--verify-command` (`cargo check`, `npm test -- --filter x`, ...) to check
+//! that the project still builds and passes. Tool calls pick a command by name and cannot run
+//! anything else.
+//!
+//! The command runs without a shell in the root directory of the call's workspace, with stdin
+//! closed and under a timeout. On Unix it gets its own process group, so a timeout kills the
+//! test processes it started as well. Only the exit code and the last lines of its output are
+//! kept for the result.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+/// Outcome of running a verification command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationOutcome {
+    /// Name the command is configured under
+    pub name: String,
+    /// Command line that was run
+    pub command: String,
+    /// Whether the command exited with status 0 before the timeout
+    pub passed: bool,
+    /// Exit code; `None` when the command timed out, was killed by a signal or didn't start
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// Whether the command was killed for running past the timeout
+    #[serde(default)]
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    /// Last lines the command wrote to stdout
+    #[serde(default)]
+    pub stdout_tail: String,
+    /// Last lines the command wrote to stderr
+    #[serde(default)]
+    pub stderr_tail: String,
+    /// Why the command could not be run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Run `command` in `working_dir`, killing it after `timeout`, and keep the last `tail_lines`
+/// lines of each output stream
+pub async fn run_verification(
+    name: &str,
+    command: &[String],
+    working_dir: &Path,
+    timeout: Duration,
+    tail_lines: usize,
+) -> VerificationOutcome {
+    let mut outcome = VerificationOutcome {
+        name: name.to_string(),
+        command: command.join(" "),
+        passed: false,
+        exit_code: None,
+        timed_out: false,
+        duration_ms: 0,
+        stdout_tail: String::new(),
+        stderr_tail: String::new(),
+        error: None,
+    };
+    let Some((program, args)) = command.split_first() else {
+        outcome.error = Some(format!("verification command `{name}` is empty"));
+        return outcome;
+    };
+
+    let mut process = tokio::process::Command::new(program);
+    process
+        .args(args)
+        .current_dir(working_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    process.process_group(0);
+
+    let started = Instant::now();
+    let child = match process.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            outcome.error = Some(format!("could not run `{program}`: {e}"));
+            return outcome;
+        }
+    };
+    let pid = child.id();
+
+    let result = tokio::time::timeout(timeout, child.wait_with_output()).await;
+    outcome.duration_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok(Ok(output)) => {
+            outcome.passed = output.status.success();
+            outcome.exit_code = output.status.code();
+            outcome.stdout_tail = tail(&String::from_utf8_lossy(&output.stdout), tail_lines);
+            outcome.stderr_tail = tail(&String::from_utf8_lossy(&output.stderr), tail_lines);
+        }
+        Ok(Err(e)) => outcome.error = Some(format!("`{program}` failed: {e}")),
+        Err(_) => {
+            // Dropping the wait already killed the command itself; take its children with it
+            if let Some(pid) = pid {
+                kill_process_group(pid);
+            }
+            outcome.timed_out = true;
+            outcome.error = Some(format!(
+                "`{program}` timed out after {}ms",
+                timeout.as_millis()
+            ));
+        }
+    }
+    outcome
+}
+
+/// The last `lines` lines of `output`
+fn tail(output: &str, lines: usize) -> String {
+    let all: Vec<&str> = output.lines().collect();
+    all[all.len().saturating_sub(lines)..].join("\n")
+}
+
+#[cfg(unix)]
+fn kill_process_group(pid: u32) {
+    // SAFETY: kill only sends a signal; a negative pid addresses the group the command leads
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_pid: u32) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail_keeps_last_lines() {
+        assert_eq!(tail("a\nb\nc\n", 2), "b\nc");
+        assert_eq!(tail("a\nb", 5), "a\nb");
+        assert_eq!(tail("a\nb", 0), "");
+    }
+}
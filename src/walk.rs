@@ -0,0 +1,181 @@
+//! # Directory Walking
+//!
+//! The one place the file-based tools descend into the root directories. Pruned directory
+//! names, hidden entries and paths excluded by `.gitignore` are filtered while walking, so
+//! nothing below an excluded directory is read, stat'ed or matched against a glob.
+
+use crate::config::ServiceConfig;
+use crate::types::WalkOptions;
+use ignore::{DirEntry, WalkBuilder};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Walk options with the server's defaults filled in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalkSettings {
+    pub max_depth: usize,
+    pub prune_dirs: Vec<String>,
+    pub follow_hidden: bool,
+    pub respect_gitignore: bool,
+}
+
+impl ServiceConfig {
+    /// Resolve per-call walk options against the configured defaults
+    pub fn walk_settings(&self, options: &WalkOptions) -> WalkSettings {
+        WalkSettings {
+            max_depth: options.max_depth.unwrap_or(self.walk_max_depth),
+            prune_dirs: options
+                .prune_dirs
+                .clone()
+                .unwrap_or_else(|| self.prune_dirs.clone()),
+            follow_hidden: options.follow_hidden.unwrap_or(self.follow_hidden),
+            respect_gitignore: options.respect_gitignore.unwrap_or(self.respect_gitignore),
+        }
+    }
+}
+
+impl WalkSettings {
+    /// Whether the walk descends into or yields `entry`. The root itself is always kept, even
+    /// when its own name is hidden or pruned
+    fn keeps(&self, entry: &DirEntry) -> bool {
+        if entry.depth() == 0 {
+            return true;
+        }
+        let name = entry.file_name().to_string_lossy();
+        if !self.follow_hidden && name.starts_with('.') {
+            return false;
+        }
+        let is_dir = entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_dir());
+        // Git's own metadata is never ignored by a .gitignore, but it is never source either
+        if is_dir && self.respect_gitignore && name == ".git" {
+            return false;
+        }
+        !(is_dir && self.prune_dirs.iter().any(|pruned| *pruned == name))
+    }
+}
+
+/// Every file under `root` the settings allow, in directory order. Unreadable entries are
+/// skipped. With `respect_gitignore`, `.gitignore`, `.ignore` and `.git/info/exclude` files are
+/// honored the way git does, also outside a git checkout
+pub fn walk_files(root: &Path, settings: &WalkSettings) -> impl Iterator<Item = DirEntry> + use<> {
+    let keep = settings.clone();
+    WalkBuilder::new(root)
+        .max_depth(Some(settings.max_depth))
+        .standard_filters(settings.respect_gitignore)
+        .require_git(false)
+        // Hidden entries are filtered by name in `keeps`, like pruned directories
+        .hidden(false)
+        .filter_entry(move |entry| keep.keeps(entry))
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+        })
+}
+
+/// Drop the files already listed under another path, such as a file reached through two
+/// overlapping roots or through a symlinked root, keeping the first. Paths that don't resolve,
+/// such as archive entries, are compared as they are
+pub fn dedupe_canonical<T>(files: &mut Vec<T>, path: impl Fn(&T) -> &str) {
+    let mut seen = HashSet::new();
+    files.retain(|file| {
+        let path = Path::new(path(file));
+        seen.insert(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn walked(root: &Path, settings: &WalkSettings) -> Vec<String> {
+        let mut names: Vec<String> = walk_files(root, settings)
+            .map(|entry| {
+                entry
+                    .path()
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_walk_prunes_hidden_and_deep_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in [
+            "a.js",
+            ".env.js",
+            "src/b.js",
+            "src/deep/c.js",
+            "node_modules/lib/d.js",
+            ".cache/e.js",
+            "node_modules.js",
+        ] {
+            let path = temp_dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let everything = WalkSettings {
+            max_depth: 10,
+            prune_dirs: Vec::new(),
+            follow_hidden: true,
+            respect_gitignore: false,
+        };
+        assert_eq!(walked(temp_dir.path(), &everything).len(), 7);
+
+        let pruned = WalkSettings {
+            max_depth: 2,
+            prune_dirs: vec!["node_modules".to_string()],
+            follow_hidden: false,
+            respect_gitignore: false,
+        };
+        assert_eq!(
+            walked(temp_dir.path(), &pruned),
+            vec!["a.js", "node_modules.js", "src/b.js"]
+        );
+    }
+
+    #[test]
+    fn test_walk_leaves_out_gitignored_files() {
+        let temp_dir = TempDir::new().unwrap();
+        for (name, content) in [
+            (".gitignore", "target/\n*.log\n"),
+            ("a.js", ""),
+            ("debug.log", ""),
+            ("target/out.js", ""),
+            ("src/.gitignore", "generated.js\n"),
+            ("src/generated.js", ""),
+            ("src/b.js", ""),
+            (".git/HEAD", ""),
+        ] {
+            let path = temp_dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        let mut settings = WalkSettings {
+            max_depth: 10,
+            prune_dirs: Vec::new(),
+            follow_hidden: true,
+            respect_gitignore: true,
+        };
+        assert_eq!(
+            walked(temp_dir.path(), &settings),
+            vec![".gitignore", "a.js", "src/.gitignore", "src/b.js"]
+        );
+
+        settings.respect_gitignore = false;
+        assert_eq!(walked(temp_dir.path(), &settings).len(), 8);
+    }
+}
@@ -0,0 +1,279 @@
+//! # Worker Process Isolation
+//!
+//! Pathological input can make tree-sitter crash, hang or allocate without bound. With
+//! isolation enabled, file searches parse and match each file in a child process running the
+//! hidden `worker` subcommand of this binary. Each worker runs under an address-space limit
+//! (Unix only) and every job under a timeout. A worker that crashes or times out is killed and
+//! replaced on the next job, and the file is reported as skipped instead of taking down the
+//! server.
+//!
+//! Parent and worker talk line-delimited JSON over stdin/stdout: one [`MatchJob`] in, one
+//! [`MatchReply`] out.
+
+use crate::errors::ServiceError;
+use crate::pattern::PatternMatcher;
+use crate::types::MatchResult;
+use ast_grep_language::SupportLang as Language;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+/// Name of the subcommand that runs a worker
+pub const WORKER_SUBCOMMAND: &str = "worker";
+
+/// Settings for sandboxed parsing
+#[derive(Debug, Clone)]
+pub struct WorkerConfig {
+    /// Executable to launch workers from; must accept the `worker` subcommand
+    pub program: PathBuf,
+    /// Maximum time a worker may spend on one file
+    pub timeout: Duration,
+    /// Address-space limit for each worker in megabytes (Unix only)
+    pub memory_limit_mb: Option<u64>,
+    /// Number of idle workers kept alive between jobs
+    pub max_idle_workers: usize,
+}
+
+impl WorkerConfig {
+    /// Default settings for workers launched from `program`
+    ///
+    /// Default values:
+    /// - `timeout`: 10 seconds per file
+    /// - `memory_limit_mb`: 1024MB
+    /// - `max_idle_workers`: 4
+    pub fn new(program: PathBuf) -> Self {
+        Self {
+            program,
+            timeout: Duration::from_secs(10),
+            memory_limit_mb: Some(1024),
+            max_idle_workers: 4,
+        }
+    }
+}
+
+/// A single file to match in a worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchJob {
+    pub code: String,
+    pub pattern: String,
+    pub language: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    #[serde(default)]
+    pub include_ast_context: bool,
+}
+
+/// A worker's answer to a [`MatchJob`]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MatchReply {
+    #[serde(default)]
+    pub matches: Vec<MatchResult>,
+    /// Set when the job itself was invalid, e.g. a pattern that fails to compile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of running a job in a worker
+#[derive(Debug)]
+pub enum IsolatedOutcome {
+    Matches(Vec<MatchResult>),
+    /// The worker crashed or timed out; holds the reason
+    Failed(String),
+}
+
+/// Pool of worker processes shared by clones of the owning service
+#[derive(Clone)]
+pub struct WorkerPool {
+    config: Arc<WorkerConfig>,
+    idle: Arc<Mutex<Vec<Worker>>>,
+}
+
+impl WorkerPool {
+    pub fn new(config: WorkerConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            idle: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn config(&self) -> &WorkerConfig {
+        &self.config
+    }
+
+    /// Run `job` in a worker. Errors reported by the worker (such as an invalid pattern) are
+    /// returned as `Err`; a crash or timeout is returned as [`IsolatedOutcome::Failed`]
+    pub async fn search(&self, job: &MatchJob) -> Result<IsolatedOutcome, ServiceError> {
+        let idle = self.idle.lock().unwrap().pop();
+        let mut worker = match idle {
+            Some(worker) => worker,
+            None => Worker::spawn(&self.config).map_err(|e| {
+                ServiceError::Internal(format!(
+                    "Failed to start worker {}: {e}",
+                    self.config.program.display()
+                ))
+            })?,
+        };
+
+        let reply = match tokio::time::timeout(self.config.timeout, worker.run(job)).await {
+            Ok(Ok(reply)) => reply,
+            Ok(Err(reason)) => return Ok(IsolatedOutcome::Failed(reason)),
+            Err(_) => {
+                return Ok(IsolatedOutcome::Failed(format!(
+                    "worker timed out after {}ms",
+                    self.config.timeout.as_millis()
+                )));
+            }
+        };
+
+        let mut idle = self.idle.lock().unwrap();
+        if idle.len() < self.config.max_idle_workers {
+            idle.push(worker);
+        }
+        drop(idle);
+
+        match reply.error {
+            Some(error) => Err(ServiceError::ParserError(error)),
+            None => Ok(IsolatedOutcome::Matches(reply.matches)),
+        }
+    }
+}
+
+/// A running worker process. Dropping it kills the process
+struct Worker {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Worker {
+    fn spawn(config: &WorkerConfig) -> std::io::Result<Self> {
+        let mut command = Command::new(&config.program);
+        command.arg(WORKER_SUBCOMMAND);
+        if let Some(limit) = config.memory_limit_mb {
+            command.arg("--memory-limit-mb").arg(limit.to_string());
+        }
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        Ok(Self {
+            child,
+            stdin,
+            stdout,
+        })
+    }
+
+    /// Send one job and wait for its reply, describing the failure if the worker dies
+    async fn run(&mut self, job: &MatchJob) -> Result<MatchReply, String> {
+        let mut line = serde_json::to_string(job).map_err(|e| e.to_string())?;
+        line.push('\n');
+
+        let exchange = async {
+            self.stdin.write_all(line.as_bytes()).await?;
+            self.stdin.flush().await?;
+            let mut reply = String::new();
+            self.stdout.read_line(&mut reply).await?;
+            Ok::<_, std::io::Error>(reply)
+        };
+        let reply = match exchange.await {
+            Ok(reply) if !reply.is_empty() => reply,
+            _ => return Err(self.exit_reason().await),
+        };
+        serde_json::from_str(&reply).map_err(|e| format!("worker sent an invalid reply: {e}"))
+    }
+
+    async fn exit_reason(&mut self) -> String {
+        match tokio::time::timeout(Duration::from_secs(1), self.child.wait()).await {
+            Ok(Ok(status)) => format!("worker exited unexpectedly ({status})"),
+            _ => "worker stopped responding".to_string(),
+        }
+    }
+}
+
+/// Entry point of the `worker` subcommand: answer jobs from stdin until it closes
+pub fn run_worker(memory_limit_mb: Option<u64>) -> std::io::Result<()> {
+    if let Some(limit) = memory_limit_mb {
+        limit_memory(limit)?;
+    }
+
+    let matcher = PatternMatcher::new();
+    let stdin = std::io::stdin().lock();
+    let mut stdout = std::io::stdout().lock();
+    for line in stdin.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str::<MatchJob>(&line) {
+            Ok(job) => run_job(&matcher, &job),
+            Err(e) => MatchReply {
+                matches: Vec::new(),
+                error: Some(format!("Invalid job: {e}")),
+            },
+        };
+        serde_json::to_writer(&mut stdout, &reply)?;
+        stdout.write_all(b"\n")?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+fn run_job(matcher: &PatternMatcher, job: &MatchJob) -> MatchReply {
+    let result = Language::from_str(&job.language)
+        .map_err(|_| ServiceError::Internal("Failed to parse language".to_string()))
+        .and_then(|lang| {
+            matcher.search_with_ast_context(
+                &job.code,
+                &job.pattern,
+                lang,
+                job.selector.as_deref(),
+                job.context.as_deref(),
+                job.include_ast_context,
+            )
+        });
+    match result {
+        Ok(matches) => MatchReply {
+            matches,
+            error: None,
+        },
+        Err(e) => MatchReply {
+            matches: Vec::new(),
+            error: Some(match e {
+                ServiceError::ParserError(message) => message,
+                other => other.to_string(),
+            }),
+        },
+    }
+}
+
+#[cfg(unix)]
+fn limit_memory(megabytes: u64) -> std::io::Result<()> {
+    let bytes = megabytes.saturating_mul(1024 * 1024) as libc::rlim_t;
+    let limit = libc::rlimit {
+        rlim_cur: bytes,
+        rlim_max: bytes,
+    };
+    // SAFETY: setrlimit only reads the struct we pass by reference
+    if unsafe { libc::setrlimit(libc::RLIMIT_AS, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn limit_memory(_megabytes: u64) -> std::io::Result<()> {
+    tracing::warn!("Worker memory limits are only supported on Unix");
+    Ok(())
+}
@@ -0,0 +1,159 @@
+//! # Wrapping Matches
+//!
+//! `wrap_matches` puts every match of a pattern inside a template such as
+//! `measure(() => $MATCH)` or `instrument!($MATCH)`. A match that is a whole statement keeps
+//! its terminating `;` after the wrapper, so `track();` becomes `measure(() => track());` rather
+//! than `measure(() => track();)`, and a template written as a statement, like
+//! `instrument!($MATCH);`, can still wrap an expression in the middle of a line. Matches inside
+//! another match are left alone: the outer one is wrapped with them in it.
+
+use crate::errors::ServiceError;
+use crate::file_fetch::content_hash;
+use crate::replace::byte_offset;
+use crate::search::SearchService;
+use crate::types::{
+    ChangeResult, FileDiffResult, FileSearchParam, MatchResult, WrapMatchesParam, WrapMatchesResult,
+};
+
+/// The placeholder a template marks the matched code with
+const MATCH: &str = "$MATCH";
+
+/// Wrap each match of `param.pattern` in `param.template`, writing the files unless
+/// `param.dry_run` is set
+pub async fn wrap_matches(
+    search_service: &SearchService,
+    param: WrapMatchesParam,
+) -> Result<WrapMatchesResult, ServiceError> {
+    if !param.template.contains(MATCH) {
+        return Err(ServiceError::Internal(format!(
+            "wrap_matches needs a template containing {MATCH}, e.g. measure(() => {MATCH})"
+        )));
+    }
+
+    let search = search_service
+        .file_search(FileSearchParam {
+            path_pattern: param.path_pattern.clone(),
+            pattern: param.pattern.clone(),
+            language: param.language.clone(),
+            selector: param.selector.clone(),
+            context: param.context.clone(),
+            max_results: param.max_results,
+            max_file_size: param.max_file_size,
+            workspace: param.workspace.clone(),
+            allow_broad: param.allow_broad,
+            include_ast_context: true,
+            walk: param.walk.clone(),
+            ..Default::default()
+        })
+        .await?;
+
+    let mut result = WrapMatchesResult {
+        file_results: Vec::new(),
+        total_wraps: 0,
+        files_with_changes: 0,
+        nested_matches: 0,
+        truncated: search.next_cursor.is_some_and(|cursor| !cursor.is_complete),
+        dry_run: param.dry_run,
+    };
+    for file in search.matches {
+        let content = tokio::fs::read_to_string(&file.file_path)
+            .await
+            .map_err(|e| ServiceError::FileIoError {
+                message: e.to_string(),
+                path: file.file_path.clone(),
+            })?;
+        let (wrapped, changes, nested_matches) =
+            wrap_file(&content, &file.matches, &param.template);
+        result.nested_matches += nested_matches;
+        if changes.is_empty() {
+            continue;
+        }
+
+        if !param.dry_run {
+            tokio::fs::write(&file.file_path, &wrapped)
+                .await
+                .map_err(|e| ServiceError::FileIoError {
+                    message: e.to_string(),
+                    path: file.file_path.clone(),
+                })?;
+        }
+        result.files_with_changes += 1;
+        result.total_wraps += changes.len();
+        result.file_results.push(FileDiffResult {
+            file_path: file.file_path,
+            file_size_bytes: content.len() as u64,
+            total_changes: changes.len(),
+            changes,
+            file_hash: content_hash(&content),
+            workspace: file.workspace,
+        });
+    }
+    Ok(result)
+}
+
+/// `content` with each of `matches` wrapped in `template`, one change per wrapped match, and
+/// how many matches were skipped for being inside one already wrapped
+fn wrap_file(
+    content: &str,
+    matches: &[MatchResult],
+    template: &str,
+) -> (String, Vec<ChangeResult>, usize) {
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut located: Vec<(usize, &MatchResult)> = matches
+        .iter()
+        .filter_map(|m| Some((byte_offset(content, m.start_line, m.start_col)?, m)))
+        .filter(|(start, m)| content[*start..].starts_with(m.text.as_str()))
+        .collect();
+    located.sort_by_key(|(start, _)| *start);
+
+    let mut wrapped = String::with_capacity(content.len());
+    let mut changes = Vec::new();
+    let mut nested_matches = 0;
+    let mut copied = 0;
+    for (start, m) in located {
+        if start < copied {
+            nested_matches += 1;
+            continue;
+        }
+        let line = &content[content[..start].rfind('\n').map_or(0, |i| i + 1)..];
+        let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+        let statement = m
+            .ast_context
+            .as_ref()
+            .is_some_and(|context| context.kind.ends_with("statement"));
+        let new_text = wrap(&m.text, statement, template, &format!("{newline}{indent}"));
+        wrapped.push_str(&content[copied..start]);
+        wrapped.push_str(&new_text);
+        copied = start + m.text.len();
+        changes.push(ChangeResult {
+            start_line: m.start_line,
+            end_line: m.end_line,
+            start_col: m.start_col,
+            end_col: m.end_col,
+            start_byte: start,
+            end_byte: copied,
+            old_text: m.text.clone(),
+            new_text,
+        });
+    }
+    wrapped.push_str(&content[copied..]);
+    (wrapped, changes, nested_matches)
+}
+
+/// `text` put in place of `$MATCH` in `template`. The template's own trailing `;` is dropped and
+/// a `statement` ending in `;` keeps it after the wrapper instead. Line breaks of the template
+/// are replaced by `line_break`, which indents the following lines like the match
+fn wrap(text: &str, statement: bool, template: &str, line_break: &str) -> String {
+    let template = template.trim_end();
+    let template = template.strip_suffix(';').unwrap_or(template).trim_end();
+    let (inner, terminator) = match text.strip_suffix(';') {
+        Some(inner) if statement => (inner.trim_end(), ";"),
+        _ => (text, ""),
+    };
+    let template = template.lines().collect::<Vec<_>>().join(line_break);
+    format!("{}{terminator}", template.replace(MATCH, inner))
+}
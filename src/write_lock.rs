@@ -0,0 +1,214 @@
+//! # Write Lock
+//!
+//! Build bots that start several servers over the same checkout, one per MCP session, must not
+//! have two of them rewriting the same files at once. Before a tool writes, the server takes a
+//! lockfile, `.ast-grep-mcp.lock`, in each root directory the call may write to, and removes it
+//! once the call returns. A server finding a root locked waits for the lock, up to
+//! `--lock-timeout-ms`, then fails the call.
+//!
+//! A lock left behind by a server that crashed is stale: one whose process no longer runs, or
+//! that is older than an hour, as no write takes that long. Stale locks are taken over. Servers
+//! that never write to the same files can skip all of this with `--no-lock`.
+
+use crate::config::ServiceConfig;
+use crate::errors::ServiceError;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Name of the lockfile kept in a root directory while a server writes below it
+pub const LOCK_FILE: &str = ".ast-grep-mcp.lock";
+
+/// Age after which a lock is stale even when its process still seems to run
+const STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// Time a lockfile may stay unreadable before it counts as left behind half-written
+const WRITE_GRACE: Duration = Duration::from_secs(5);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Tells the locks taken by one process apart
+static NEXT_LOCK: AtomicUsize = AtomicUsize::new(0);
+
+/// Contents of a lockfile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockOwner {
+    /// Process id of the server holding the lock
+    pub pid: u32,
+    /// When the lock was taken, in seconds since the Unix epoch
+    pub acquired_at: u64,
+    /// Identifies this particular lock, so its holder never removes one taken after it
+    pub token: String,
+}
+
+impl LockOwner {
+    fn current() -> Self {
+        let pid = std::process::id();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Self {
+            pid,
+            acquired_at: now.as_secs(),
+            token: format!(
+                "{pid}-{}-{}",
+                now.as_nanos(),
+                NEXT_LOCK.fetch_add(1, Ordering::Relaxed)
+            ),
+        }
+    }
+
+    /// Whether the server holding the lock is gone or has held it implausibly long
+    pub fn is_stale(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        now.as_secs().saturating_sub(self.acquired_at) > STALE_AFTER.as_secs()
+            || !process_alive(self.pid)
+    }
+}
+
+/// The lockfiles a call holds; dropping it releases them
+#[derive(Debug, Default)]
+pub struct WriteLock {
+    held: Vec<(PathBuf, String)>,
+}
+
+impl WriteLock {
+    /// The lockfiles held, one per locked root directory
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.held.iter().map(|(path, _)| path.as_path())
+    }
+}
+
+impl Drop for WriteLock {
+    fn drop(&mut self) {
+        for (path, token) in self.held.drain(..).rev() {
+            // A lock taken over as stale belongs to someone else now
+            if read_owner(&path).is_some_and(|owner| owner.token == token) {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+}
+
+/// Lock the root directories a call limited to `workspace` may write to, or every root without
+/// a workspace. Nothing is locked when `config.write_lock` is off
+pub async fn acquire(
+    config: &ServiceConfig,
+    workspace: Option<&str>,
+) -> Result<WriteLock, ServiceError> {
+    let mut lock = WriteLock::default();
+    if !config.write_lock {
+        return Ok(lock);
+    }
+    let mut roots = config.workspace_roots(workspace)?;
+    // The same order in every server, so two calls over several roots can't deadlock
+    roots.sort();
+    roots.dedup();
+    for root in roots.into_iter().filter(|root| root.is_dir()) {
+        let path = root.join(LOCK_FILE);
+        let token = lock_file(&path, config.write_lock_timeout).await?;
+        lock.held.push((path, token));
+    }
+    Ok(lock)
+}
+
+/// Take the lockfile at `path`, waiting up to `timeout` for its holder, and return its token
+async fn lock_file(path: &Path, timeout: Duration) -> Result<String, ServiceError> {
+    let started = std::time::Instant::now();
+    loop {
+        let owner = LockOwner::current();
+        match create(path, &owner) {
+            Ok(()) => return Ok(owner.token),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => {
+                return Err(ServiceError::Internal(format!(
+                    "Failed to create the lockfile {}: {e}",
+                    path.display()
+                )));
+            }
+        }
+
+        let holder = read_owner(path);
+        match &holder {
+            Some(holder) if holder.is_stale() => {
+                tracing::warn!(
+                    "Taking over the stale lockfile {} of pid {}",
+                    path.display(),
+                    holder.pid
+                );
+                remove_if_unchanged(path, Some(&holder.token));
+                continue;
+            }
+            None if modified_before(path, WRITE_GRACE) => {
+                tracing::warn!("Removing the unreadable lockfile {}", path.display());
+                remove_if_unchanged(path, None);
+                continue;
+            }
+            _ => {}
+        }
+
+        if started.elapsed() >= timeout {
+            let holder = holder.map_or_else(
+                || "another server".to_string(),
+                |holder| format!("another server (pid {})", holder.pid),
+            );
+            return Err(ServiceError::Internal(format!(
+                "{} is held by {holder}, which is applying changes to the same root directory; \
+                 gave up after {} ms. Start the servers with --no-lock if they never write to \
+                 the same files",
+                path.display(),
+                timeout.as_millis()
+            )));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+fn create(path: &Path, owner: &LockOwner) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    let contents = serde_json::to_vec(owner).map_err(std::io::Error::other)?;
+    file.write_all(&contents)?;
+    file.sync_all()
+}
+
+/// The holder recorded in the lockfile at `path`, or `None` when it is missing or unreadable
+pub fn read_owner(path: &Path) -> Option<LockOwner> {
+    serde_json::from_slice(&fs::read(path).ok()?).ok()
+}
+
+/// Remove the lockfile at `path` unless another server has replaced the one judged stale since
+fn remove_if_unchanged(path: &Path, token: Option<&str>) {
+    let current = read_owner(path);
+    if current.as_ref().map(|owner| owner.token.as_str()) == token {
+        let _ = fs::remove_file(path);
+    }
+}
+
+fn modified_before(path: &Path, age: Duration) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|elapsed| elapsed > age)
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // Signal 0 only checks the process exists; EPERM means it runs under another user
+    let signalled = unsafe { libc::kill(pid, 0) } == 0;
+    signalled || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Without a way to probe the process, only a lock's age makes it stale
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use ast_grep_mcp::annotation::{CommentSyntax, comment_syntax};
+use ast_grep_mcp::extra_languages::AnyLanguage;
+use ast_grep_mcp::types::AddAnnotationParam;
+use common::create_service;
+use std::fs;
+use std::str::FromStr;
+use tempfile::TempDir;
+
+#[test]
+fn test_comment_syntax() {
+    let syntax = |language: &str| comment_syntax(AnyLanguage::from_str(language).unwrap());
+    assert_eq!(syntax("rust"), Some(CommentSyntax::Line("//")));
+    assert_eq!(syntax("python"), Some(CommentSyntax::Line("#")));
+    assert_eq!(syntax("lua"), Some(CommentSyntax::Line("--")));
+    assert_eq!(syntax("css"), Some(CommentSyntax::Block("/*", "*/")));
+    assert_eq!(syntax("json"), None);
+    assert_eq!(
+        CommentSyntax::Block("<!--", "-->").render("first\nsecond"),
+        vec!["<!-- first -->", "<!-- second -->"]
+    );
+}
+
+#[tokio::test]
+async fn test_add_annotation_dry_run_and_apply() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("app.py");
+    let original = "def run():\n    legacy(1)\n    if ok:\n        legacy(2); legacy(3)\n";
+    fs::write(&path, original).unwrap();
+    let service = create_service(&temp_dir);
+    let param = AddAnnotationParam {
+        path_pattern: "*.py".to_string(),
+        pattern: "legacy($A)".to_string(),
+        language: "python".to_string(),
+        comment: "TODO(PROJ-7): drop legacy".to_string(),
+        ..Default::default()
+    };
+
+    let preview = service.add_annotation(param.clone()).await.unwrap();
+    assert!(preview.dry_run);
+    assert_eq!(preview.total_annotations, 2);
+    let changes = &preview.file_results[0].changes;
+    assert_eq!(changes[0].start_line, 1);
+    assert_eq!(
+        changes[0].new_text,
+        "    # TODO(PROJ-7): drop legacy\n    legacy(1)"
+    );
+    assert_eq!(changes[1].start_line, 3);
+    assert_eq!(
+        &original[changes[1].start_byte..changes[1].end_byte],
+        changes[1].old_text
+    );
+    assert_eq!(fs::read_to_string(&path).unwrap(), original);
+
+    let applied = service
+        .add_annotation(AddAnnotationParam {
+            dry_run: false,
+            ..param.clone()
+        })
+        .await
+        .unwrap();
+    assert_eq!(applied.total_annotations, 2);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "def run():\n    # TODO(PROJ-7): drop legacy\n    legacy(1)\n    if ok:\n        # TODO(PROJ-7): drop legacy\n        legacy(2); legacy(3)\n"
+    );
+
+    let again = service
+        .add_annotation(AddAnnotationParam {
+            dry_run: false,
+            ..param
+        })
+        .await
+        .unwrap();
+    assert_eq!(again.total_annotations, 0);
+    assert_eq!(again.already_annotated, 2);
+}
+
+#[tokio::test]
+async fn test_add_annotation_uses_each_file_language() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("view.tsx"),
+        "export function View() {\r\n  return fetchAll();\r\n}\r\n",
+    )
+    .unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .add_annotation(AddAnnotationParam {
+            path_pattern: "*.tsx".to_string(),
+            pattern: "fetchAll()".to_string(),
+            language: "typescript".to_string(),
+            comment: "@deprecated use fetchPage\nsee the migration guide".to_string(),
+            dry_run: false,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.total_annotations, 1);
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("view.tsx")).unwrap(),
+        "export function View() {\r\n  // @deprecated use fetchPage\r\n  // see the migration guide\r\n  return fetchAll();\r\n}\r\n"
+    );
+
+    let error = service
+        .add_annotation(AddAnnotationParam {
+            path_pattern: "*.tsx".to_string(),
+            pattern: "fetchAll()".to_string(),
+            language: "typescript".to_string(),
+            comment: "  ".to_string(),
+            ..Default::default()
+        })
+        .await;
+    assert!(error.is_err());
+}
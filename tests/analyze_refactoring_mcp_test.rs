@@ -36,7 +36,7 @@ async fn test_analyze_refactoring_mcp_integration() {
 
     // Verify suggested signature
     assert_eq!(result.suggested_signature.name, "extractedFunction");
-    assert_eq!(result.suggested_signature.parameters.len(), 2);
+    assert_eq!(result.suggested_signature.parameters, ["x", "y"]);
     assert!(!result.suggested_signature.is_pure); // console.log makes it impure
 
     // Verify return strategy (should be void since console.log doesn't return)
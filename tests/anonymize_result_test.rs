@@ -0,0 +1,93 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::types::{AnonymizeResultParam, FileSearchParam};
+use serde_json::json;
+use std::fs;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_search_result_is_anonymized_consistently() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join("billing")).unwrap();
+    fs::write(
+        temp_dir.path().join("billing/invoice.js"),
+        "class InvoiceService {\n  send() {\n    mailer.deliver(invoiceId /* acme */, 'acme-secret');\n  }\n}\n",
+    )
+    .unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+
+    let search = service
+        .file_search(FileSearchParam {
+            path_pattern: "**/*.js".to_string(),
+            pattern: "$OBJ.deliver($$$ARGS)".to_string(),
+            language: "javascript".to_string(),
+            context_before: Some(1),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let result = service
+        .anonymize_result(AnonymizeResultParam {
+            result: serde_json::to_value(&search).unwrap(),
+            keep_identifiers: vec!["deliver".to_string()],
+            include_mapping: true,
+            ..Default::default()
+        })
+        .unwrap();
+
+    let file = &result.result["matches"][0];
+    let path = file["file_path"].as_str().unwrap();
+    assert!(path.ends_with("/file1.js"), "{path}");
+    assert!(!path.contains("billing"));
+    assert!(file.get("file_hash").is_none());
+
+    let found = &file["matches"][0];
+    assert_eq!(found["text"], "id3.deliver(id4 /* comment */, 'str1')");
+    assert_eq!(found["vars"]["OBJ"], "id3");
+    // The same name gets the same placeholder in context lines and the enclosing symbol
+    assert_eq!(found["context_before"], json!(["class Id1 {"]));
+    assert_eq!(found["enclosing_symbol"]["name"], "Id1.id2");
+
+    let text = result.result.to_string();
+    for secret in ["mailer", "invoiceId", "acme", "InvoiceService", "billing"] {
+        assert!(!text.contains(secret), "{secret} leaked");
+    }
+    assert_eq!(
+        (result.identifiers, result.strings, result.comments),
+        (4, 1, 2)
+    );
+    let mapping = result.mapping.as_ref().unwrap();
+    assert_eq!(mapping["id4"], "invoiceId");
+    assert_eq!(mapping["str1"], "acme-secret");
+
+    let summary = ResponseFormatter::format_anonymize_result(&result);
+    assert!(summary.contains("4 identifiers, 1 strings, 2 comments, 1 file paths"));
+    assert!(summary.contains("remove it before sharing"));
+}
+
+#[tokio::test]
+async fn test_code_without_a_file_path_needs_a_language() {
+    let service = AstGrepService::new();
+    let search = json!({ "matches": [{ "text": "def charge(card): pass", "vars": {} }] });
+
+    let result = service.anonymize_result(AnonymizeResultParam {
+        result: search.clone(),
+        ..Default::default()
+    });
+    assert!(result.is_err());
+
+    let result = service
+        .anonymize_result(AnonymizeResultParam {
+            result: search,
+            language: Some("python".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(result.result["matches"][0]["text"], "def id1(id2): pass");
+    assert!(result.mapping.is_none());
+}
@@ -0,0 +1,169 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::types::{
+    ApplyPatchParam, FileReplaceParam, ListAppliedChangesParam, ReplaceOutput, RollbackReplaceParam,
+};
+use std::fs;
+use tempfile::TempDir;
+
+const APP: &str = "var a = 1;\nfunction f() {\n  return a;\n}\nvar b = 2;\n";
+const UTIL: &str = "var c = 3;";
+
+fn setup() -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("app.js"), APP).unwrap();
+    fs::write(temp_dir.path().join("util.js"), UTIL).unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        undo_directory: temp_dir.path().join(".undo"),
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+/// The unified diffs of a dry run turning `var` into `let`, joined into one patch
+async fn var_to_let_patch(service: &AstGrepService, diff_context: usize) -> String {
+    let result = service
+        .file_replace(FileReplaceParam {
+            path_pattern: "*.js".to_string(),
+            pattern: "var $N = $V".to_string(),
+            replacement: "let $N = $V".to_string(),
+            language: "javascript".to_string(),
+            output: ReplaceOutput::UnifiedDiff,
+            diff_context,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(
+        result
+            .summary_results
+            .iter()
+            .all(|file| file.sample_changes.is_empty())
+    );
+    result
+        .summary_results
+        .iter()
+        .map(|file| file.unified_diff.clone().unwrap())
+        .collect()
+}
+
+fn apply(patch: &str, dry_run: bool) -> ApplyPatchParam {
+    ApplyPatchParam {
+        patch: patch.to_string(),
+        dry_run,
+        workspace: None,
+    }
+}
+
+#[tokio::test]
+async fn test_file_replace_returns_unified_diffs() {
+    let (service, temp_dir) = setup();
+    let patch = var_to_let_patch(&service, 1).await;
+    let app = temp_dir.path().join("app.js");
+    let util = temp_dir.path().join("util.js");
+    assert_eq!(
+        patch,
+        format!(
+            "--- {app}\n+++ {app}\n@@ -1,2 +1,2 @@\n-var a = 1;\n+let a = 1;\n function f() {{\n\
+             @@ -4,2 +4,2 @@\n }}\n-var b = 2;\n+let b = 2;\n\
+             --- {util}\n+++ {util}\n@@ -1 +1 @@\n-var c = 3;\n\\ No newline at end of file\n\
+             +let c = 3;\n\\ No newline at end of file\n",
+            app = app.display(),
+            util = util.display()
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_review_then_apply_then_roll_back() {
+    let (service, temp_dir) = setup();
+    let patch = var_to_let_patch(&service, 3).await;
+
+    let checked = service.apply_patch(apply(&patch, true)).await.unwrap();
+    assert_eq!(checked.files.len(), 2);
+    assert_eq!(
+        (checked.files[0].hunks, checked.files[0].lines_added),
+        (1, 2)
+    );
+    assert_eq!(checked.apply_id, None);
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("app.js")).unwrap(),
+        APP
+    );
+
+    // The file changed between review and apply, but not around the hunks
+    fs::write(temp_dir.path().join("app.js"), format!("// header\n{APP}")).unwrap();
+    let applied = service.apply_patch(apply(&patch, false)).await.unwrap();
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("app.js")).unwrap(),
+        format!("// header\n{}", APP.replace("var", "let"))
+    );
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("util.js")).unwrap(),
+        "let c = 3;"
+    );
+    let summary = ResponseFormatter::format_apply_patch_result(&applied);
+    assert!(
+        summary.contains("🩹 **Patch applied** to 2 files"),
+        "{summary}"
+    );
+
+    let changes = service
+        .list_applied_changes(ListAppliedChangesParam {
+            limit: 10,
+            include_rolled_back: false,
+        })
+        .await
+        .unwrap();
+    assert!(
+        ResponseFormatter::format_list_applied_changes_result(&changes)
+            .contains("patch of 2 files")
+    );
+
+    service
+        .rollback_replace(RollbackReplaceParam {
+            apply_id: applied.apply_id.unwrap(),
+            force: false,
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("util.js")).unwrap(),
+        UTIL
+    );
+}
+
+#[tokio::test]
+async fn test_nothing_is_written_unless_every_hunk_applies() {
+    let (service, temp_dir) = setup();
+    let patch = var_to_let_patch(&service, 3).await;
+    fs::write(temp_dir.path().join("util.js"), "const c = 3;").unwrap();
+
+    let error = service.apply_patch(apply(&patch, false)).await.unwrap_err();
+    assert!(error.to_string().contains("does not apply"), "{error}");
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("app.js")).unwrap(),
+        APP
+    );
+}
+
+#[tokio::test]
+async fn test_patches_stay_within_the_root_directories() {
+    let (service, _temp_dir) = setup();
+    let outside = TempDir::new().unwrap();
+    let path = outside.path().join("x.js");
+    fs::write(&path, "a\n").unwrap();
+    let patch = format!(
+        "--- {path}\n+++ {path}\n@@ -1 +1 @@\n-a\n+b\n",
+        path = path.display()
+    );
+    assert!(service.apply_patch(apply(&patch, false)).await.is_err());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "a\n");
+
+    // Relative git-style paths resolve against the first root directory
+    let patch = "diff --git a/app.js b/app.js\n--- a/app.js\n+++ b/app.js\n@@ -5 +5 @@\n-var b = 2;\n+const b = 2;\n";
+    let result = service.apply_patch(apply(patch, true)).await.unwrap();
+    assert!(result.files[0].file_path.ends_with("app.js"));
+}
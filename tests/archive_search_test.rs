@@ -0,0 +1,162 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::{FileReplaceParam, FileSearchParam};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn write_zip(path: &Path, entries: &[(&str, &str)]) {
+    let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+    for (name, content) in entries {
+        zip.start_file(*name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+fn write_tar_gz(path: &Path, entries: &[(&str, &str)]) {
+    let encoder = GzEncoder::new(File::create(path).unwrap(), Compression::default());
+    let mut tar = tar::Builder::new(encoder);
+    for (name, content) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, name, content.as_bytes())
+            .unwrap();
+    }
+    tar.into_inner().unwrap().finish().unwrap();
+}
+
+fn setup() -> (TempDir, AstGrepService) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("main.js"), "eval(input);\n").unwrap();
+    write_zip(
+        &temp_dir.path().join("app.zip"),
+        &[
+            ("src/a.js", "const x = 1;\neval(payload);\n"),
+            ("README.md", "eval(docs)\n"),
+        ],
+    );
+    fs::create_dir(temp_dir.path().join("dist")).unwrap();
+    write_tar_gz(
+        &temp_dir.path().join("dist/site.tar.gz"),
+        &[("lib/b.js", "eval(other);\n")],
+    );
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+    (temp_dir, service)
+}
+
+fn eval_search(path_pattern: &str, search_archives: bool) -> FileSearchParam {
+    FileSearchParam {
+        path_pattern: path_pattern.to_string(),
+        pattern: "eval($CODE)".to_string(),
+        language: "javascript".to_string(),
+        search_archives,
+        ..Default::default()
+    }
+}
+
+fn matched_paths(result: &ast_grep_mcp::FileSearchResult, root: &Path) -> Vec<String> {
+    let root = root.to_string_lossy();
+    let mut paths: Vec<String> = result
+        .matches
+        .iter()
+        .map(|file| {
+            file.file_path
+                .strip_prefix(root.as_ref())
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[tokio::test]
+async fn test_archives_are_ignored_by_default() {
+    let (temp_dir, service) = setup();
+    let result = service
+        .file_search(eval_search("**/*.js", false))
+        .await
+        .unwrap();
+    assert_eq!(matched_paths(&result, temp_dir.path()), vec!["/main.js"]);
+}
+
+#[tokio::test]
+async fn test_search_archives_reports_entry_paths() {
+    let (temp_dir, service) = setup();
+    let result = service
+        .file_search(eval_search("**/*.js", true))
+        .await
+        .unwrap();
+    assert_eq!(
+        matched_paths(&result, temp_dir.path()),
+        vec![
+            "/app.zip!src/a.js",
+            "/dist/site.tar.gz!lib/b.js",
+            "/main.js"
+        ]
+    );
+
+    let entry = result
+        .matches
+        .iter()
+        .find(|file| file.file_path.ends_with("!src/a.js"))
+        .unwrap();
+    assert_eq!(entry.matches[0].start_line, 1);
+    assert_eq!(entry.matches[0].vars["CODE"], "payload");
+}
+
+#[tokio::test]
+async fn test_archive_entries_match_the_glob_by_inner_path() {
+    let (temp_dir, service) = setup();
+    let result = service
+        .file_search(eval_search("lib/**/*.js", true))
+        .await
+        .unwrap();
+    assert_eq!(
+        matched_paths(&result, temp_dir.path()),
+        vec!["/dist/site.tar.gz!lib/b.js"]
+    );
+}
+
+#[tokio::test]
+async fn test_oversized_entries_are_left_out() {
+    let (temp_dir, service) = setup();
+    let mut param = eval_search("*.js", true);
+    param.max_file_size = 16;
+    let result = service.file_search(param).await.unwrap();
+    assert_eq!(
+        matched_paths(&result, temp_dir.path()),
+        vec!["/dist/site.tar.gz!lib/b.js", "/main.js"]
+    );
+}
+
+#[tokio::test]
+async fn test_file_replace_never_touches_archives() {
+    let (temp_dir, service) = setup();
+    let archive = fs::read(temp_dir.path().join("app.zip")).unwrap();
+
+    let result = service
+        .file_replace(FileReplaceParam {
+            path_pattern: "**/*.js".to_string(),
+            pattern: "eval($CODE)".to_string(),
+            replacement: "safeEval($CODE)".to_string(),
+            language: "javascript".to_string(),
+            dry_run: false,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.total_changes, 1);
+    assert_eq!(fs::read(temp_dir.path().join("app.zip")).unwrap(), archive);
+}
@@ -0,0 +1,91 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::{FileSearchParam, SearchParam};
+use std::fs;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_search_ast_context_tells_call_from_new() {
+    let service = AstGrepService::new();
+    let code = "foo(1);\nconst x = new Foo(2);\n";
+
+    let result = service
+        .search(SearchParam {
+            include_ast_context: true,
+            ..SearchParam::new(code, "$F($A)", "javascript")
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.matches.len(), 1);
+    let ast_context = result.matches[0].ast_context.as_ref().unwrap();
+    assert_eq!(ast_context.kind, "call_expression");
+    assert_eq!(
+        ast_context.parent_kind.as_deref(),
+        Some("expression_statement")
+    );
+    assert_eq!(ast_context.child_kinds, vec!["identifier", "arguments"]);
+
+    let result = service
+        .search(SearchParam {
+            include_ast_context: true,
+            ..SearchParam::new(code, "new $F($A)", "javascript")
+        })
+        .await
+        .unwrap();
+    let ast_context = result.matches[0].ast_context.as_ref().unwrap();
+    assert_eq!(ast_context.kind, "new_expression");
+    assert_eq!(
+        ast_context.parent_kind.as_deref(),
+        Some("variable_declarator")
+    );
+}
+
+#[tokio::test]
+async fn test_ast_context_is_off_by_default() {
+    let service = AstGrepService::new();
+
+    let result = service
+        .search(SearchParam::new("foo(1);", "$F($A)", "javascript"))
+        .await
+        .unwrap();
+
+    assert_eq!(result.matches.len(), 1);
+    assert!(result.matches[0].ast_context.is_none());
+    let json = serde_json::to_value(&result.matches[0]).unwrap();
+    assert!(json.get("ast_context").is_none());
+}
+
+#[tokio::test]
+async fn test_file_search_ast_context() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        "fn main() {\n    let v = compute().unwrap();\n}\n",
+    )
+    .unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+
+    let result = service
+        .file_search(FileSearchParam {
+            path_pattern: "**/*.rs".to_string(),
+            pattern: "$A.unwrap()".to_string(),
+            language: "rust".to_string(),
+            include_ast_context: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.matches.len(), 1);
+    let ast_context = result.matches[0].matches[0].ast_context.as_ref().unwrap();
+    assert_eq!(ast_context.kind, "call_expression");
+    assert_eq!(ast_context.parent_kind.as_deref(), Some("let_declaration"));
+    assert_eq!(
+        ast_context.child_kinds,
+        vec!["field_expression", "arguments"]
+    );
+}
@@ -64,8 +64,11 @@ async fn test_replace_basic() {
     );
 
     let result = service.replace(param).await.unwrap();
-    assert_eq!(result.matches.len(), 1);
-    assert_eq!(result.new_code, "function greet() { console.warn(\"Hello\"); }");
+    assert_eq!(result.changes.len(), 1);
+    assert_eq!(
+        result.new_code,
+        "function greet() { console.warn(\"Hello\"); }"
+    );
 }
 
 #[tokio::test]
@@ -88,13 +91,65 @@ async fn test_generate_ast() {
     let param = GenerateAstParam {
         code: "function test() {}".to_string(),
         language: "javascript".to_string(),
+        buffer_id: None,
     };
 
     let result = service.generate_ast(param).await.unwrap();
-    assert!(!result.ast_structure.is_empty());
+    assert!(!result.ast.is_empty());
     assert!(!result.node_kinds.is_empty());
 }
 
+#[tokio::test]
+async fn test_generate_ast_kind_counts() {
+    let service = AstGrepService::new();
+    let param = GenerateAstParam {
+        code: "let a = 1; let b = 'x'; f(a, b);".to_string(),
+        language: "javascript".to_string(),
+        buffer_id: None,
+    };
+
+    let result = service.generate_ast(param).await.unwrap();
+    assert_eq!(result.node_kinds[0], "identifier");
+    assert_eq!(result.kind_counts["identifier"], 5);
+    assert_eq!(result.kind_counts["lexical_declaration"], 2);
+
+    // Most frequent first, ties broken alphabetically
+    let counts: Vec<(usize, &str)> = result
+        .node_kind_stats
+        .iter()
+        .map(|stat| (stat.count, stat.kind.as_str()))
+        .collect();
+    let mut sorted = counts.clone();
+    sorted.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(b.1)));
+    assert_eq!(counts, sorted);
+    assert_eq!(
+        result.node_kinds,
+        counts
+            .iter()
+            .map(|(_, kind)| kind.to_string())
+            .collect::<Vec<_>>()
+    );
+
+    let category = |kind: &str| {
+        result
+            .node_kind_stats
+            .iter()
+            .find(|stat| stat.kind == kind)
+            .and_then(|stat| stat.category)
+    };
+    assert_eq!(
+        category("lexical_declaration"),
+        Some(NodeCategory::Declaration)
+    );
+    assert_eq!(
+        category("expression_statement"),
+        Some(NodeCategory::Statement)
+    );
+    assert_eq!(category("call_expression"), Some(NodeCategory::Expression));
+    assert_eq!(category("number"), Some(NodeCategory::Literal));
+    assert_eq!(category("arguments"), None);
+}
+
 #[tokio::test]
 async fn test_list_languages() {
     let service = AstGrepService::new();
@@ -150,11 +205,9 @@ async fn test_custom_config() {
     
     let custom_config = ServiceConfig {
         max_file_size: 1024,
-        timeout_seconds: 10,
-        max_matches: 50,
-        enable_debug: true,
         pattern_cache_size: 500, // Smaller cache for testing
-        rules_directory: Some(PathBuf::from("test_rules")),
+        rules_directory: PathBuf::from("test_rules"),
+        ..Default::default()
     };
     
     let service = AstGrepService::with_config(custom_config);
@@ -192,8 +245,9 @@ async fn test_cache_eviction() {
     }
     
     // Verify cache has at most 2 entries
-    let cache = service.pattern_cache.lock().unwrap();
-    assert!(cache.len() <= 2);
+    let (cached, capacity) = service.get_cache_stats();
+    assert_eq!(capacity, 2);
+    assert!(cached <= 2);
 }
 
 #[tokio::test]
@@ -217,13 +271,13 @@ async fn test_search_with_context() {
 async fn test_service_initialization() {
     // Test default initialization
     let service1 = AstGrepService::new();
-    assert!(service1.get_debug_info().contains("AstGrepService"));
-    
+    assert_eq!(service1.get_cache_stats(), (0, 1000));
+
     // Test custom config initialization
     let service2 = AstGrepService::with_config(ast_grep_mcp::config::ServiceConfig {
         max_file_size: 2048,
         pattern_cache_size: 1000,
         ..Default::default()
     });
-    assert!(service2.get_debug_info().contains("AstGrepService"));
-}
\ No newline at end of file
+    assert_eq!(service2.get_cache_stats(), (0, 1000));
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use ast_grep_mcp::types::{BatchSearchEntry, BatchSearchParam};
+use common::create_service;
+use std::fs;
+use tempfile::TempDir;
+
+fn search(id: &str, pattern: &str, language: &str, path_pattern: &str) -> BatchSearchEntry {
+    BatchSearchEntry {
+        id: id.to_string(),
+        pattern: pattern.to_string(),
+        language: language.to_string(),
+        path_pattern: path_pattern.to_string(),
+        max_results: 100,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_batch_search_keys_results_by_id() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+    fs::write(
+        temp_dir.path().join("src/app.js"),
+        "console.log(a);\nconsole.log(b);\nfetch(url);\n",
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("src/view.ts"), "console.log(c);\n").unwrap();
+    fs::write(
+        temp_dir.path().join("main.rs"),
+        "fn main() { x.unwrap(); }\n",
+    )
+    .unwrap();
+
+    let result = create_service(&temp_dir)
+        .batch_search(BatchSearchParam {
+            searches: vec![
+                search("console", "console.log($A)", "javascript", "src/**/*.js"),
+                search("fetch", "fetch($URL)", "javascript", "*.js"),
+                search("unwrap", "$X.unwrap()", "rust", "**/*.rs"),
+                search("typescript", "console.log($A)", "typescript", "*.ts"),
+                search("none", "alert($A)", "javascript", "*.js"),
+            ],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.files_searched, 3);
+    let counts: Vec<(&str, usize, usize)> = result
+        .results
+        .iter()
+        .map(|(id, search)| (id.as_str(), search.total_matches, search.matches.len()))
+        .collect();
+    assert_eq!(
+        counts,
+        vec![
+            ("console", 2, 1),
+            ("fetch", 1, 1),
+            ("none", 0, 0),
+            ("typescript", 1, 1),
+            ("unwrap", 1, 1),
+        ]
+    );
+    let fetch = &result.results["fetch"].matches[0];
+    assert!(fetch.file_path.ends_with("app.js"));
+    assert_eq!(fetch.matches[0].vars["URL"], "url");
+}
+
+#[tokio::test]
+async fn test_batch_search_reports_errors_and_limits_per_search() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("app.js"),
+        "console.log(a);\nconsole.log(b);\nconsole.log(c);\n",
+    )
+    .unwrap();
+
+    let result = create_service(&temp_dir)
+        .batch_search(BatchSearchParam {
+            searches: vec![
+                BatchSearchEntry {
+                    max_results: 2,
+                    ..search("limited", "console.log($A)", "javascript", "*.js")
+                },
+                search("language", "x", "klingon", "*.js"),
+                search("broad", "$A", "javascript", "*.js"),
+                search("absolute", "x", "javascript", "/etc/*.js"),
+            ],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let limited = &result.results["limited"];
+    assert_eq!(limited.total_matches, 3);
+    assert_eq!(limited.matches[0].matches.len(), 2);
+    assert!(limited.truncated);
+    assert!(limited.error.is_none());
+    for id in ["language", "broad", "absolute"] {
+        assert!(result.results[id].error.is_some(), "{id} should fail");
+    }
+
+    let duplicate = create_service(&temp_dir)
+        .batch_search(BatchSearchParam {
+            searches: vec![
+                search("same", "a", "javascript", "*.js"),
+                search("same", "b", "javascript", "*.js"),
+            ],
+            ..Default::default()
+        })
+        .await;
+    assert!(duplicate.is_err());
+}
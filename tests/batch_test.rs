@@ -0,0 +1,77 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::batch::{BatchSummary, run_batch};
+use ast_grep_mcp::config::ServiceConfig;
+use serde_json::Value;
+use std::fs;
+use tempfile::TempDir;
+
+async fn run(service: &AstGrepService, input: &str) -> (BatchSummary, Vec<Value>) {
+    let mut output = Vec::new();
+    let summary = run_batch(service, input.as_bytes(), &mut output)
+        .await
+        .unwrap();
+    let lines = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    (summary, lines)
+}
+
+#[tokio::test]
+async fn test_batch_runs_tools_in_order() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("app.js"), "console.log(1);\n").unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+
+    let input = r#"{"id": "a", "tool": "file_search", "arguments": {"path_pattern": "*.js", "pattern": "console.log($A)", "language": "javascript"}}
+
+{"id": 2, "name": "list_languages"}
+"#;
+    let (summary, lines) = run(&service, input).await;
+
+    assert_eq!(
+        summary,
+        BatchSummary {
+            succeeded: 2,
+            failed: 0
+        }
+    );
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["id"], "a");
+    assert_eq!(lines[0]["tool"], "file_search");
+    let data = lines[0]["result"]["content"][1]["text"].as_str().unwrap();
+    let data: Value = serde_json::from_str(data).unwrap();
+    assert_eq!(data["matches"][0]["matches"][0]["text"], "console.log(1)");
+    assert_eq!(lines[1]["id"], 2);
+    assert!(lines[1]["result"]["content"].is_array());
+}
+
+#[tokio::test]
+async fn test_batch_reports_errors_per_line() {
+    let service = AstGrepService::new();
+    let input = "not json\n{\"id\": 7, \"tool\": \"no_such_tool\"}\n{\"id\": 8, \"tool\": \"search\", \"arguments\": {\"code\": \"a\"}}\n";
+    let (summary, lines) = run(&service, input).await;
+
+    assert_eq!(
+        summary,
+        BatchSummary {
+            succeeded: 0,
+            failed: 3
+        }
+    );
+    assert!(lines[0]["id"].is_null());
+    assert!(
+        lines[0]["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("Invalid batch request")
+    );
+    assert_eq!(lines[1]["id"], 7);
+    assert!(lines[1]["error"]["code"].is_number());
+    assert_eq!(lines[2]["id"], 8);
+    assert!(lines[2]["error"].is_object());
+}
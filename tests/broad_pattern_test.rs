@@ -0,0 +1,93 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::pattern::is_broad_pattern;
+use ast_grep_mcp::types::{FileReplaceParam, FileSearchParam};
+use std::collections::HashMap;
+use std::fs;
+use tempfile::TempDir;
+
+fn setup() -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("a.js"),
+        "const a = 1;\nconsole.log(a);\n",
+    )
+    .unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+#[test]
+fn test_lone_metavariables_are_broad() {
+    for pattern in ["$_", "$A", " $NODE ", "$$$", "$$$ARGS", "$$B", "$A;"] {
+        assert!(is_broad_pattern(pattern), "{pattern}");
+    }
+    for pattern in ["$A.$B", "console.log($A)", "$A = $B", "$", "$a", "foo", ""] {
+        assert!(!is_broad_pattern(pattern), "{pattern}");
+    }
+}
+
+#[tokio::test]
+async fn test_file_search_refuses_broad_pattern_without_opt_in() {
+    let (service, _temp_dir) = setup();
+    let param = FileSearchParam {
+        path_pattern: "**/*.js".to_string(),
+        pattern: "$_".to_string(),
+        language: "javascript".to_string(),
+        ..Default::default()
+    };
+
+    let error = service.file_search(param.clone()).await.unwrap_err();
+    assert!(error.to_string().contains("allow_broad"), "{error}");
+
+    let result = service
+        .file_search(FileSearchParam {
+            allow_broad: true,
+            ..param.clone()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.matches.len(), 1);
+
+    // A context narrows the match to the selector
+    let result = service
+        .file_search(FileSearchParam {
+            pattern: "$A".to_string(),
+            context: Some("console.log($A)".to_string()),
+            selector: Some("call_expression".to_string()),
+            ..param.clone()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.matches.len(), 1);
+
+    let per_language = FileSearchParam {
+        pattern: String::new(),
+        language: String::new(),
+        language_patterns: HashMap::from([("javascript".to_string(), "$$$".to_string())]),
+        ..param
+    };
+    assert!(service.file_search(per_language).await.is_err());
+}
+
+#[tokio::test]
+async fn test_file_replace_refuses_broad_pattern_without_opt_in() {
+    let (service, temp_dir) = setup();
+    let param = FileReplaceParam {
+        path_pattern: "**/*.js".to_string(),
+        pattern: "$A".to_string(),
+        replacement: "x".to_string(),
+        language: "javascript".to_string(),
+        dry_run: false,
+        ..Default::default()
+    };
+
+    assert!(service.file_replace(param).await.is_err());
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("a.js")).unwrap(),
+        "const a = 1;\nconsole.log(a);\n"
+    );
+}
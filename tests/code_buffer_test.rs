@@ -0,0 +1,185 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::tool_router::ToolRouter;
+use ast_grep_mcp::types::{
+    AppendCodeBufferParam, BeginCodeBufferParam, GenerateAstParam, ReplaceParam, SearchParam,
+};
+use rmcp::model::CallToolRequestParam;
+use serde_json::{Value, json};
+
+fn request(name: &'static str, arguments: Value) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: name.into(),
+        arguments: arguments.as_object().cloned(),
+    }
+}
+
+fn json_content(result: &rmcp::model::CallToolResult) -> Value {
+    let text = &result.content[1].as_text().unwrap().text;
+    serde_json::from_str(text).unwrap()
+}
+
+/// Open a buffer and send `chunks` to it through the tool router
+async fn upload(service: &AstGrepService, chunks: &[&str]) -> String {
+    let begun = ToolRouter::route_tool_call(service, request("begin_code_buffer", json!({})))
+        .await
+        .unwrap();
+    let buffer_id = json_content(&begun)["buffer_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    for chunk in chunks {
+        ToolRouter::route_tool_call(
+            service,
+            request(
+                "append_code_buffer",
+                json!({ "buffer_id": buffer_id, "content": chunk }),
+            ),
+        )
+        .await
+        .unwrap();
+    }
+    buffer_id
+}
+
+#[tokio::test]
+async fn test_search_buffer_matches_across_chunk_boundaries() {
+    let service = AstGrepService::new();
+    // The second call is split mid-line between chunks
+    let buffer_id = upload(
+        &service,
+        &["console.log('first');\ncons", "ole.log('second');\n"],
+    )
+    .await;
+
+    let result = ToolRouter::route_tool_call(
+        &service,
+        request(
+            "search_buffer",
+            json!({
+                "buffer_id": buffer_id,
+                "pattern": "console.log($A)",
+                "language": "javascript"
+            }),
+        ),
+    )
+    .await
+    .unwrap();
+    let matches = json_content(&result)["matches"].clone();
+    assert_eq!(matches.as_array().unwrap().len(), 2);
+    assert_eq!(matches[1]["vars"]["A"], "'second'");
+    assert_eq!(matches[1]["start_line"], 1);
+}
+
+#[tokio::test]
+async fn test_replace_and_generate_ast_accept_buffer_id() {
+    let service = AstGrepService::new();
+    let info = service
+        .begin_code_buffer(BeginCodeBufferParam {
+            content: "fn main() {\n    let x = ".to_string(),
+            ttl_seconds: 60,
+        })
+        .unwrap();
+    let info = service
+        .append_code_buffer(AppendCodeBufferParam {
+            buffer_id: info.buffer_id,
+            content: "compute().unwrap();\n}\n".to_string(),
+        })
+        .unwrap();
+    assert_eq!(info.chunks, 2);
+    let buffer_id = Some(info.buffer_id);
+
+    let replaced = service
+        .replace(ReplaceParam {
+            buffer_id: buffer_id.clone(),
+            ..ReplaceParam::new("", "$A.unwrap()", "$A?", "rust")
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        replaced.new_code,
+        "fn main() {\n    let x = compute()?;\n}\n"
+    );
+
+    let ast = service
+        .generate_ast(GenerateAstParam {
+            code: String::new(),
+            language: "rust".to_string(),
+            buffer_id: buffer_id.clone(),
+        })
+        .await
+        .unwrap();
+    assert!(ast.node_kinds.contains(&"call_expression".to_string()));
+
+    // The buffer still holds the original code
+    let search = service
+        .search(SearchParam {
+            buffer_id,
+            ..SearchParam::new("", "$A.unwrap()", "rust")
+        })
+        .await
+        .unwrap();
+    assert_eq!(search.matches.len(), 1);
+}
+
+#[tokio::test]
+async fn test_buffer_errors() {
+    let service = AstGrepService::new();
+    let buffer_id = upload(&service, &["let a = 1;"]).await;
+
+    // Inline code and a buffer are mutually exclusive
+    let both = service
+        .search(SearchParam {
+            buffer_id: Some(buffer_id),
+            ..SearchParam::new("let b = 2;", "let $A = $B", "javascript")
+        })
+        .await;
+    assert!(both.is_err());
+
+    let unknown = service
+        .search_buffer(SearchParam {
+            buffer_id: Some("buf-missing".to_string()),
+            ..SearchParam::new("", "let $A = $B", "javascript")
+        })
+        .await;
+    assert!(
+        unknown
+            .unwrap_err()
+            .to_string()
+            .contains("Unknown code buffer")
+    );
+
+    let missing_id = service
+        .search_buffer(SearchParam::new("let a = 1;", "let $A = $B", "javascript"))
+        .await;
+    assert!(missing_id.is_err());
+}
+
+#[tokio::test]
+async fn test_sessions_only_see_their_own_buffers() {
+    let service = AstGrepService::new();
+    let alice = service.for_session("alice");
+    let bob = service.for_session("bob");
+    let buffer_id = upload(&alice, &["let a = 1;"]).await;
+
+    let search = |service: AstGrepService, buffer_id: String| async move {
+        service
+            .search_buffer(SearchParam {
+                buffer_id: Some(buffer_id),
+                ..SearchParam::new("", "let $A = $B", "javascript")
+            })
+            .await
+    };
+    assert!(
+        search(service.for_session("alice"), buffer_id.clone())
+            .await
+            .is_ok()
+    );
+    assert!(search(bob.clone(), buffer_id.clone()).await.is_err());
+    assert!(search(service.clone(), buffer_id.clone()).await.is_err());
+
+    // Ending a session drops its buffers and leaves the others alone
+    let bob_buffer = upload(&bob, &["let b = 2;"]).await;
+    alice.end_session();
+    assert!(search(alice, buffer_id).await.is_err());
+    assert!(search(bob, bob_buffer).await.is_ok());
+}
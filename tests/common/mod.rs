@@ -1,7 +1,19 @@
-// Common test utilities
+// Common test utilities; each test crate uses only some of them
+#![allow(dead_code)]
+
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
 use std::fs;
 use tempfile::TempDir;
 
+/// A service rooted at `temp_dir` with the default configuration
+pub fn create_service(temp_dir: &TempDir) -> AstGrepService {
+    AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    })
+}
+
 pub fn setup_test_files() -> TempDir {
     let temp_dir = TempDir::new().unwrap();
 
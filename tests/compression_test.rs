@@ -0,0 +1,120 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::compression::decompress_json;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::{CompressedResult, FileSearchResult, ResponseCompression};
+use rmcp::model::{CallToolRequestParam, CallToolResult};
+use serde_json::{Value, json};
+use std::fs;
+use tempfile::TempDir;
+
+fn setup() -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    // More files than file_search returns in full JSON without compression
+    for i in 0..15 {
+        fs::write(
+            temp_dir.path().join(format!("file{i}.js")),
+            "console.log(1);\nconsole.log(2);\n",
+        )
+        .unwrap();
+    }
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn request(name: &'static str, arguments: Value) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: name.into(),
+        arguments: arguments.as_object().cloned(),
+    }
+}
+
+fn texts(result: &CallToolResult) -> Vec<String> {
+    result
+        .content
+        .iter()
+        .map(|content| content.as_text().unwrap().text.clone())
+        .collect()
+}
+
+fn search_arguments() -> Value {
+    json!({
+        "path_pattern": "*.js",
+        "pattern": "console.log($A)",
+        "language": "javascript",
+        "max_results": 100
+    })
+}
+
+#[tokio::test]
+async fn test_compressed_file_search_carries_the_full_result() {
+    let (service, _temp_dir) = setup();
+
+    let plain = service
+        .handle_tool_call(request("file_search", search_arguments()))
+        .await
+        .unwrap();
+    let plain = texts(&plain);
+    assert!(plain.last().unwrap().contains("Full JSON data omitted"));
+
+    let mut arguments = search_arguments();
+    arguments["compress"] = json!("gzip+base64");
+    let compressed = service
+        .handle_tool_call(request("file_search", arguments))
+        .await
+        .unwrap();
+    let texts = texts(&compressed);
+    assert_eq!(texts.len(), 3);
+    assert_eq!(texts[0], plain[0]);
+    assert!(texts[1].starts_with("📦 Full result compressed with gzip+base64"));
+
+    let blob: CompressedResult = serde_json::from_str(&texts[2]).unwrap();
+    assert_eq!(blob.compression, ResponseCompression::GzipBase64);
+    assert!(blob.compressed_bytes < blob.original_bytes);
+    let result: FileSearchResult = serde_json::from_value(decompress_json(&blob).unwrap()).unwrap();
+    assert_eq!(result.matches.len(), 15);
+    assert_eq!(
+        result
+            .matches
+            .iter()
+            .map(|f| f.matches.len())
+            .sum::<usize>(),
+        30
+    );
+}
+
+#[tokio::test]
+async fn test_compress_works_for_any_tool() {
+    let (service, _temp_dir) = setup();
+
+    let response = service
+        .handle_tool_call(request(
+            "search",
+            json!({
+                "code": "console.log(1);",
+                "pattern": "console.log($A)",
+                "language": "javascript",
+                "compress": "gzip+base64"
+            }),
+        ))
+        .await
+        .unwrap();
+    let blob: CompressedResult = serde_json::from_str(texts(&response).last().unwrap()).unwrap();
+    let result = decompress_json(&blob).unwrap();
+    assert_eq!(result["matches"][0]["vars"]["A"], "1");
+}
+
+#[tokio::test]
+async fn test_unknown_compression_is_rejected() {
+    let (service, _temp_dir) = setup();
+
+    let mut arguments = search_arguments();
+    arguments["compress"] = json!("zstd");
+    let error = service
+        .handle_tool_call(request("file_search", arguments))
+        .await
+        .unwrap_err();
+    assert!(error.message.contains("gzip+base64"));
+}
@@ -0,0 +1,161 @@
+use ast_grep_language::SupportLang as Language;
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::rules::{
+    RuleEvaluator, RuleSearchParam, RuleValidateParam, ValidateRulesParam, parse_rule_config,
+    validate_rule_config,
+};
+use std::collections::HashMap;
+use std::fs;
+use tempfile::TempDir;
+
+const SOURCE: &str = r#"useState(0);
+useEffect(load);
+setState(1);
+run(user.name);
+"#;
+
+const RULE: &str = r#"
+id: hook-calls
+language: javascript
+rule:
+  pattern: $HOOK($ARG)
+constraints:
+  HOOK:
+    regex: ^use
+"#;
+
+fn texts(rule_config: &str) -> Vec<String> {
+    let config = parse_rule_config(rule_config).unwrap();
+    RuleEvaluator::new()
+        .evaluate_rule_config(&config, SOURCE, Language::JavaScript)
+        .unwrap()
+        .into_iter()
+        .map(|m| m.text)
+        .collect()
+}
+
+#[test]
+fn test_regex_constraint_filters_captures() {
+    assert_eq!(texts(RULE), ["useState(0)", "useEffect(load)"]);
+}
+
+#[test]
+fn test_kind_and_pattern_constraints_check_the_captured_node() {
+    let by_kind = r#"
+id: member-arguments
+language: javascript
+rule:
+  pattern: $F($ARG)
+constraints:
+  ARG:
+    kind: member_expression
+"#;
+    assert_eq!(texts(by_kind), ["run(user.name)"]);
+
+    let by_pattern = r#"
+id: effect-calls
+language: javascript
+rule:
+  pattern: $F($ARG)
+constraints:
+  F:
+    pattern: useEffect
+  ARG:
+    regex: ^[a-z]+$
+"#;
+    assert_eq!(texts(by_pattern), ["useEffect(load)"]);
+
+    // A constraint on a metavariable the rule doesn't capture restricts nothing
+    let uncaptured = RULE.replace("HOOK:\n    regex", "OTHER:\n    regex");
+    assert_eq!(texts(&uncaptured).len(), 4);
+}
+
+#[test]
+fn test_invalid_constraints_are_errors() {
+    let lowercase = RULE.replace("  HOOK:", "  hook:");
+    let errors = validate_rule_config(&lowercase).unwrap();
+    assert!(
+        errors
+            .iter()
+            .any(|e| e.contains("must name a metavariable")),
+        "{errors:?}"
+    );
+
+    let empty = "id: r\nlanguage: javascript\nrule:\n  pattern: $F()\nconstraints:\n  F: {}\n";
+    let errors = validate_rule_config(empty).unwrap();
+    assert!(
+        errors
+            .iter()
+            .any(|e| e.contains("Constraint on $F must have at least one valid condition")),
+        "{errors:?}"
+    );
+    assert!(validate_rule_config(RULE).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_validate_rule_reports_constraint_violations() {
+    let service = AstGrepService::new();
+    let result = service
+        .validate_rule(RuleValidateParam {
+            rule_config: RULE.to_string(),
+            test_code: Some(SOURCE.to_string()),
+        })
+        .await
+        .unwrap();
+    assert!(result.valid, "{:?}", result.errors);
+    let test_results = result.test_results.as_ref().unwrap();
+    assert_eq!(test_results.matches_found, 2);
+    let dropped: Vec<(&str, &str)> = test_results
+        .constraint_violations
+        .iter()
+        .map(|v| (v.metavariable.as_str(), v.captured.as_str()))
+        .collect();
+    assert_eq!(dropped, [("HOOK", "setState"), ("HOOK", "run")]);
+    assert_eq!(test_results.constraint_violations[0].start_line, 2);
+
+    let summary = ResponseFormatter::format_rule_validate_result(&result);
+    assert!(summary.contains("🚫 **Dropped by constraints**: 2"));
+    assert!(summary.contains("`setState(1)` (line 2): $HOOK captured `setState`"));
+
+    let batch = service
+        .validate_rules(ValidateRulesParam {
+            rule_configs: vec![RULE.replace("$HOOK($ARG)", "$CALLEE($ARG)")],
+            test_code: HashMap::from([("hook-calls".to_string(), SOURCE.to_string())]),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let rule = &batch.rules[0];
+    assert!(
+        rule.warnings.iter().any(
+            |w| w.contains("constraints restrict $HOOK, which no pattern in the rule captures")
+        ),
+        "{:?}",
+        rule.warnings
+    );
+}
+
+#[tokio::test]
+async fn test_rule_search_applies_constraints() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("app.js"), SOURCE).unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+
+    let search: RuleSearchParam = serde_json::from_value(serde_json::json!({
+        "rule_config": RULE,
+        "path_pattern": "*.js"
+    }))
+    .unwrap();
+    let result = service.rule_search(search).await.unwrap();
+    let texts: Vec<&str> = result.matches[0]
+        .matches
+        .iter()
+        .map(|m| m.text.as_str())
+        .collect();
+    assert_eq!(texts, ["useState(0)", "useEffect(load)"]);
+}
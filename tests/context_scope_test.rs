@@ -0,0 +1,97 @@
+mod common;
+
+use ast_grep_mcp::types::{ContextScope, FileSearchParam, SearchParam};
+use common::create_service;
+use std::fs;
+use tempfile::TempDir;
+
+const CODE: &str = "class Cart {
+  total(items) {
+    let sum = 0;
+    for (const item of items) {
+      sum += price(item);
+    }
+    return sum;
+  }
+}
+
+price(first);
+";
+
+async fn enclosing(scope: ContextScope) -> Vec<Option<(String, usize, usize)>> {
+    let temp_dir = TempDir::new().unwrap();
+    let result = create_service(&temp_dir)
+        .search(SearchParam {
+            code: CODE.to_string(),
+            pattern: "price($A)".to_string(),
+            language: "javascript".to_string(),
+            context_lines: Some(3),
+            context_scope: Some(scope),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    // The scope replaces the context lines
+    assert!(result.matches.iter().all(|m| m.context_before.is_none()));
+    result
+        .matches
+        .into_iter()
+        .map(|m| {
+            m.enclosing_node
+                .map(|node| (node.kind, node.start_line, node.end_line))
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_statement_scope() {
+    assert_eq!(
+        enclosing(ContextScope::Statement).await,
+        vec![
+            Some(("expression_statement".to_string(), 4, 4)),
+            Some(("expression_statement".to_string(), 10, 10)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_function_scope() {
+    assert_eq!(
+        enclosing(ContextScope::Function).await,
+        vec![Some(("method_definition".to_string(), 1, 7)), None]
+    );
+}
+
+#[tokio::test]
+async fn test_class_scope() {
+    assert_eq!(
+        enclosing(ContextScope::Class).await,
+        vec![Some(("class_declaration".to_string(), 0, 8)), None]
+    );
+}
+
+#[tokio::test]
+async fn test_file_search_returns_the_enclosing_function() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("cart.py"),
+        "def total(items):\n    return sum(price(item) for item in items)\n",
+    )
+    .unwrap();
+
+    let result = create_service(&temp_dir)
+        .file_search(FileSearchParam {
+            path_pattern: "*.py".to_string(),
+            pattern: "price($A)".to_string(),
+            language: "python".to_string(),
+            context_scope: Some(ContextScope::Function),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let node = result.matches[0].matches[0].enclosing_node.clone().unwrap();
+    assert_eq!(node.kind, "function_definition");
+    assert!(node.text.starts_with("def total(items):"));
+}
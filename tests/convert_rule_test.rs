@@ -0,0 +1,188 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::{ConvertRuleParam, RuleSearchParam, parse_rule_config};
+use std::fs;
+use tempfile::TempDir;
+
+async fn convert(source: &str) -> ast_grep_mcp::rules::ConvertRuleResult {
+    AstGrepService::new()
+        .convert_rule(ConvertRuleParam {
+            source: source.to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+}
+
+/// Number of matches of a converted JavaScript rule in `code`
+async fn matches(rule_config: &str, code: &str) -> usize {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("test.js"), code).unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join("rules"),
+        ..Default::default()
+    });
+    let result = service
+        .rule_search(RuleSearchParam {
+            rule_config: rule_config.to_string(),
+            path_pattern: Some("*.js".to_string()),
+            max_results: 100,
+            max_file_size: 1024 * 1024,
+            cursor: None,
+            output_file: None,
+            workspace: None,
+            timeout_ms: None,
+            max_matches: None,
+            walk: Default::default(),
+            output_format: Default::default(),
+            pack: None,
+        })
+        .await
+        .unwrap();
+    result.matches.iter().map(|file| file.matches.len()).sum()
+}
+
+const SEMGREP: &str = r#"
+rules:
+  - id: no-unguarded-exec
+    languages: [python]
+    severity: WARNING
+    message: exec outside a sandbox
+    patterns:
+      - pattern: exec(...)
+      - pattern-not-inside: |
+          with sandbox():
+              ...
+    fix: safe_exec($...ARGS)
+  - id: either
+    languages: [javascript]
+    severity: ERROR
+    message: use the logger
+    pattern-either:
+      - pattern: console.log($X)
+      - pattern: console.warn($X)
+  - id: taint
+    mode: taint
+    languages: [python]
+    message: tainted
+    pattern-sources: []
+  - id: numbered
+    languages: [python]
+    message: big numbers
+    patterns:
+      - pattern: foo($X)
+      - metavariable-comparison:
+          comparison: $X > 10
+"#;
+
+#[tokio::test]
+async fn test_convert_semgrep_rules() {
+    let result = convert(SEMGREP).await;
+    assert_eq!(result.format, "semgrep");
+
+    let ids: Vec<&str> = result.rules.iter().map(|rule| rule.id.as_str()).collect();
+    assert_eq!(ids, vec!["no-unguarded-exec", "either"]);
+
+    let exec = parse_rule_config(&result.rules[0].rule_config).unwrap();
+    assert_eq!(exec.language, "python");
+    assert_eq!(exec.severity.as_deref(), Some("warning"));
+    assert_eq!(exec.fix.as_deref(), Some("safe_exec($$$ARGS)"));
+    let all = exec.rule.all.unwrap();
+    assert!(all[1].not.as_ref().unwrap().inside.is_some());
+
+    assert_eq!(
+        matches(
+            &result.rules[1].rule_config,
+            "console.log(1); console.warn(2); console.error(3);"
+        )
+        .await,
+        2
+    );
+
+    let unconverted: Vec<(&str, &str)> = result
+        .unconverted
+        .iter()
+        .map(|rule| (rule.id.as_str(), rule.reason.as_str()))
+        .collect();
+    assert_eq!(unconverted.len(), 2);
+    assert_eq!(unconverted[0].0, "taint");
+    assert!(unconverted[1].1.contains("metavariable-comparison"));
+}
+
+#[tokio::test]
+async fn test_convert_eslint_config() {
+    let source = r#"{
+        "rules": {
+            "no-console": ["warn", { "allow": ["error"] }],
+            "no-debugger": "error",
+            "eqeqeq": 2,
+            "no-var": "off",
+            "no-restricted-properties": ["error", { "object": "Math", "property": "pow" }],
+            "react/no-danger": "error"
+        }
+    }"#;
+    let result = convert(source).await;
+    assert_eq!(result.format, "eslint");
+
+    let ids: Vec<&str> = result.rules.iter().map(|rule| rule.id.as_str()).collect();
+    assert_eq!(
+        ids,
+        vec![
+            "no-console",
+            "no-debugger",
+            "eqeqeq",
+            "no-restricted-properties"
+        ]
+    );
+    assert_eq!(result.unconverted.len(), 1);
+    assert_eq!(result.unconverted[0].id, "react-no-danger");
+
+    let rule = |id: &str| {
+        result
+            .rules
+            .iter()
+            .find(|rule| rule.id == id)
+            .unwrap()
+            .rule_config
+            .clone()
+    };
+    assert_eq!(
+        matches(&rule("no-console"), "console.log(1); console.error(2);").await,
+        1
+    );
+    assert_eq!(
+        matches(&rule("no-debugger"), "function f() { debugger; }").await,
+        1
+    );
+    assert_eq!(
+        matches(&rule("eqeqeq"), "a == b; a === b; a != b;").await,
+        2
+    );
+    assert_eq!(
+        matches(
+            &rule("no-restricted-properties"),
+            "Math.pow(2, 3); Math.max(1);"
+        )
+        .await,
+        1
+    );
+    assert_eq!(
+        parse_rule_config(&rule("no-console"))
+            .unwrap()
+            .severity
+            .as_deref(),
+        Some("warning")
+    );
+}
+
+#[tokio::test]
+async fn test_convert_rejects_unknown_shape() {
+    let result = AstGrepService::new()
+        .convert_rule(ConvertRuleParam {
+            source: "id: not-a-suite\n".to_string(),
+            ..Default::default()
+        })
+        .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use ast_grep_mcp::types::DedupeCodeParam;
+use common::create_service;
+use std::fs;
+use tempfile::TempDir;
+
+const USERS: &str = r#"// Users
+function loadUsers(client) {
+  const response = client.get("/users", { retries: 3 });
+  if (!response.ok) {
+    throw new Error("failed to load users");
+  }
+  return response.body.items.map((item) => item.id);
+}
+"#;
+
+const ORDERS: &str = r#"function loadOrders(api) {
+  // Same dance, other names
+  const reply = api.get("/orders", { retries: 5 });
+  if (!reply.ok) {
+    throw new Error("failed to load orders");
+  }
+  return reply.body.items.map((entry) => entry.id);
+}
+
+function unrelated(a, b) {
+  return a + b;
+}
+"#;
+
+#[tokio::test]
+async fn test_dedupe_code_groups_renamed_copies() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("users.js"), USERS).unwrap();
+    fs::write(temp_dir.path().join("orders.js"), ORDERS).unwrap();
+    fs::write(temp_dir.path().join("users_copy.js"), USERS).unwrap();
+
+    let result = create_service(&temp_dir)
+        .dedupe_code(DedupeCodeParam::default())
+        .await
+        .unwrap();
+
+    assert_eq!(result.files_scanned, 3);
+    // The function bodies are clones too, but only the functions are reported
+    assert_eq!(result.total_groups, 1, "{:#?}", result.groups);
+    let group = &result.groups[0];
+    assert_eq!(group.language, "JavaScript");
+    // The first copy also has a comment line
+    assert_eq!(group.lines, 8);
+    assert_eq!(group.duplicated_lines, 14);
+    assert!(group.similarity > 0.5 && group.similarity < 1.0);
+    let copies: Vec<(&str, usize, usize, &str)> = group
+        .instances
+        .iter()
+        .map(|instance| {
+            (
+                instance.file_path.rsplit('/').next().unwrap(),
+                instance.start_line,
+                instance.end_line,
+                instance.kind.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        copies,
+        [
+            ("orders.js", 0, 7, "function_declaration"),
+            ("users.js", 1, 7, "function_declaration"),
+            ("users_copy.js", 1, 7, "function_declaration"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_dedupe_code_can_require_matching_names_and_literals() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("users.js"), USERS).unwrap();
+    fs::write(temp_dir.path().join("orders.js"), ORDERS).unwrap();
+    fs::write(temp_dir.path().join("users_copy.js"), USERS).unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .dedupe_code(DedupeCodeParam {
+            ignore_identifiers: false,
+            ignore_literals: false,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.total_groups, 1);
+    assert_eq!(result.groups[0].similarity, 1.0);
+    assert_eq!(result.groups[0].instances.len(), 2);
+
+    // Units under min_lines are not compared
+    let result = service
+        .dedupe_code(DedupeCodeParam {
+            min_lines: 8,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.total_groups, 0);
+
+    let result = service
+        .dedupe_code(DedupeCodeParam {
+            language: Some("python".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.files_scanned, 0);
+}
@@ -0,0 +1,139 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::types::{Convention, DetectConventionsParam};
+use std::fs;
+use tempfile::TempDir;
+
+fn setup(files: &[(&str, &str)]) -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    for (name, content) in files {
+        let path = temp_dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn find<'a>(conventions: &'a [Convention], language: &str, topic: &str) -> &'a Convention {
+    conventions
+        .iter()
+        .find(|c| c.language == language && c.topic == topic)
+        .unwrap_or_else(|| panic!("no {language} {topic}"))
+}
+
+#[tokio::test]
+async fn test_detects_javascript_and_rust_conventions() {
+    let (service, _temp_dir) = setup(&[
+        (
+            "src/api.ts",
+            "import { get } from './http';\nconst base = 'https://example.com';\nconst retries = 3;\nlet attempt = 0;\nexport async function load() {\n  try {\n    return await get(base);\n  } catch (e) {\n    log(\"failed\");\n  }\n}\n",
+        ),
+        (
+            "src/legacy.js",
+            "var cache = {};\nfetchAll().catch(handle);\nconst name = 'cache';\n",
+        ),
+        (
+            "test/api.test.ts",
+            "import { describe, it, expect, vi } from 'vitest';\ndescribe('load', () => { it('works', () => {}); });\n",
+        ),
+        (
+            "crates/core/lib.rs",
+            "fn read() -> Result<String, Error> {\n    let text = std::fs::read_to_string(\"a\")?;\n    let n: u32 = text.parse()?;\n    Ok(text)\n}\n#[test]\nfn parses() {\n    read().unwrap();\n}\n",
+        ),
+        ("README.md", "# Not source\n"),
+    ]);
+
+    let result = service
+        .detect_conventions(DetectConventionsParam::default())
+        .await
+        .unwrap();
+    assert_eq!(result.files_found, 4);
+    assert_eq!(result.files_sampled, 4);
+
+    let quotes = find(&result.conventions, "javascript", "quote_style");
+    assert_eq!(quotes.dominant, "single");
+    assert_eq!(quotes.counts["double"], 1);
+
+    let declarations = find(&result.conventions, "javascript", "variable_declarations");
+    assert_eq!(declarations.dominant, "const");
+    assert_eq!(declarations.observations, 5);
+    assert!((declarations.share - 0.6).abs() < 1e-9);
+    let example = declarations.example.as_ref().unwrap();
+    assert!(example.file_path.ends_with("api.ts"));
+    assert_eq!(example.line, 1);
+
+    let errors = find(&result.conventions, "javascript", "error_handling");
+    assert_eq!(errors.counts["try/catch"], 1);
+    assert_eq!(errors.counts["promise .catch()"], 1);
+
+    assert_eq!(
+        find(&result.conventions, "javascript", "test_framework").dominant,
+        "vitest"
+    );
+    let rust_errors = find(&result.conventions, "rust", "error_handling");
+    assert_eq!(rust_errors.dominant, "? operator");
+    assert_eq!(rust_errors.counts[".unwrap()"], 1);
+    assert_eq!(
+        find(&result.conventions, "rust", "test_framework").dominant,
+        "#[test]"
+    );
+
+    let summary = ResponseFormatter::format_detect_conventions_result(&result);
+    assert!(
+        summary.contains(
+            "**javascript** variable_declarations: `const` (60% of 5); also let 1, var 1"
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_detects_python_quotes_through_prefixes() {
+    let (service, _temp_dir) = setup(&[(
+        "app.py",
+        "import pytest\nname = \"a\"\ngreeting = f\"hi {name}\"\nother = 'b'\n",
+    )]);
+
+    let result = service
+        .detect_conventions(DetectConventionsParam::default())
+        .await
+        .unwrap();
+    let quotes = find(&result.conventions, "python", "quote_style");
+    assert_eq!(quotes.dominant, "double");
+    assert_eq!(quotes.counts["double"], 2);
+    assert_eq!(quotes.counts["single"], 1);
+    assert_eq!(
+        find(&result.conventions, "python", "test_framework").dominant,
+        "pytest"
+    );
+}
+
+#[tokio::test]
+async fn test_samples_at_most_max_files() {
+    let files: Vec<(String, String)> = (0..6)
+        .map(|i| (format!("dir{i}/f.js"), "var x = 1;\n".to_string()))
+        .collect();
+    let files: Vec<(&str, &str)> = files
+        .iter()
+        .map(|(name, content)| (name.as_str(), content.as_str()))
+        .collect();
+    let (service, _temp_dir) = setup(&files);
+
+    let result = service
+        .detect_conventions(DetectConventionsParam {
+            max_files: 2,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.files_found, 6);
+    assert_eq!(result.files_sampled, 2);
+    assert_eq!(
+        find(&result.conventions, "javascript", "variable_declarations").observations,
+        2
+    );
+}
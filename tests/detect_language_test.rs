@@ -0,0 +1,152 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::types::{DetectLanguageParam, DetectionMethod};
+use std::fs;
+use tempfile::TempDir;
+
+fn setup() -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn code(code: &str) -> DetectLanguageParam {
+    DetectLanguageParam {
+        code: Some(code.to_string()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_extension_decides() {
+    let (service, _temp_dir) = setup();
+    let result = service
+        .detect_language(DetectLanguageParam {
+            extension: Some(".ts".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.language.as_deref(), Some("typescript"));
+    assert_eq!(result.method, DetectionMethod::Extension);
+    assert_eq!(result.confidence, 1.0);
+    assert!(result.extensions.contains(&"mts".to_string()));
+    let path_pattern = result.path_pattern.unwrap();
+    assert!(path_pattern.starts_with("**/*.{"), "{path_pattern}");
+    assert!(path_pattern.contains("ts"), "{path_pattern}");
+
+    // The path wins over the code
+    let result = service
+        .detect_language(DetectLanguageParam {
+            path: Some("src/lib.rs".to_string()),
+            code: Some("def main():\n    pass\n".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.language.as_deref(), Some("rust"));
+    assert_eq!(result.path_pattern.as_deref(), Some("**/*.rs"));
+}
+
+#[tokio::test]
+async fn test_shebang_of_a_file_without_extension() {
+    let (service, temp_dir) = setup();
+    fs::create_dir_all(temp_dir.path().join("bin")).unwrap();
+    fs::write(
+        temp_dir.path().join("bin/deploy"),
+        "#!/usr/bin/env python3\nprint('deploying')\n",
+    )
+    .unwrap();
+    let result = service
+        .detect_language(DetectLanguageParam {
+            path: Some("bin/deploy".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.language.as_deref(), Some("python"));
+    assert_eq!(result.method, DetectionMethod::Shebang);
+    assert_eq!(result.confidence, 0.9);
+}
+
+#[tokio::test]
+async fn test_snippets_are_detected_by_keywords() {
+    let (service, _temp_dir) = setup();
+    let result = service
+        .detect_language(code(
+            "fn main() {\n    let mut total = 0;\n    for i in 0..10 {\n        total += i;\n    }\n}\n",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(result.language.as_deref(), Some("rust"));
+    assert_eq!(result.method, DetectionMethod::Content);
+    assert!(result.confidence > 0.5, "{}", result.confidence);
+    assert_eq!(result.candidates[0].language, "rust");
+    assert_eq!(result.candidates[0].score, 1.0);
+
+    let result = service
+        .detect_language(code(
+            "def load(path):\n    with open(path) as f:\n        return f.read()\n",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(result.language.as_deref(), Some("python"));
+
+    let summary = ResponseFormatter::format_detect_language_result(&result);
+    assert!(summary.contains("🔎 **Language**: `python`"), "{summary}");
+    assert!(summary.contains("**Candidates**"), "{summary}");
+}
+
+#[tokio::test]
+async fn test_ties_split_the_confidence() {
+    let (service, _temp_dir) = setup();
+    let result = service
+        .detect_language(code(
+            "function total(items) {\n  let sum = 0;\n  for (const item of items) {\n    sum += item;\n  }\n  return sum;\n}\n",
+        ))
+        .await
+        .unwrap();
+    let language = result.language.unwrap();
+    assert!(
+        ["javascript", "typescript", "tsx"].contains(&language.as_str()),
+        "{language}"
+    );
+    let best = result.candidates[0].keywords;
+    let ties = result
+        .candidates
+        .iter()
+        .filter(|candidate| candidate.keywords == best)
+        .count();
+    assert!(ties > 1, "{:?}", result.candidates);
+    assert!(result.confidence <= 0.8 / ties as f64 + f64::EPSILON);
+}
+
+#[tokio::test]
+async fn test_nothing_to_decide() {
+    let (service, _temp_dir) = setup();
+    let result = service.detect_language(code("x = 1")).await.unwrap();
+    assert_eq!(result.language, None);
+    assert_eq!(result.method, DetectionMethod::None);
+    assert_eq!(result.confidence, 0.0);
+    assert!(result.reason.contains("keyword"), "{}", result.reason);
+
+    let result = service
+        .detect_language(DetectLanguageParam {
+            path: Some("notes".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.language, None);
+
+    assert!(
+        service
+            .detect_language(DetectLanguageParam::default())
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use ast_grep_mcp::types::{FileSearchParam, SearchParam};
+use common::create_service;
+use std::fs;
+use tempfile::TempDir;
+
+const COMPONENT: &str = "export const App = () => <div>{value as string}</div>;\n";
+
+#[tokio::test]
+async fn test_search_dialect_selects_tsx_grammar() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir);
+
+    let param = SearchParam {
+        code: COMPONENT.to_string(),
+        pattern: "$X as string".to_string(),
+        language: "typescript".to_string(),
+        ..Default::default()
+    };
+    let plain = service.search(param.clone()).await.unwrap();
+    let tsx = service
+        .search(SearchParam {
+            dialect: Some("tsx".to_string()),
+            ..param
+        })
+        .await
+        .unwrap();
+
+    assert!(plain.matches.is_empty());
+    assert_eq!(tsx.matches.len(), 1);
+    assert_eq!(tsx.matches[0].text, "value as string");
+}
+
+#[tokio::test]
+async fn test_file_search_picks_dialect_from_extension() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("App.tsx"), COMPONENT).unwrap();
+    fs::write(temp_dir.path().join("util.ts"), "const n = <number>raw;\n").unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .file_search(FileSearchParam {
+            path_pattern: "*.{ts,tsx}".to_string(),
+            pattern: "$X as string".to_string(),
+            language: "typescript".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.matches.len(), 1);
+    assert!(result.matches[0].file_path.ends_with("App.tsx"));
+
+    // `<number>raw` is a cast in TypeScript but unparseable as TSX
+    let result = service
+        .file_search(FileSearchParam {
+            path_pattern: "*.{ts,tsx}".to_string(),
+            pattern: "<number>$X".to_string(),
+            language: "tsx".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.matches.len(), 1);
+    assert!(result.matches[0].file_path.ends_with("util.ts"));
+}
+
+#[tokio::test]
+async fn test_dialect_must_match_language() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .search(SearchParam {
+            code: "x = 1".to_string(),
+            pattern: "$X".to_string(),
+            language: "python".to_string(),
+            dialect: Some("tsx".to_string()),
+            ..Default::default()
+        })
+        .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,236 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::{ApplyPlanParam, PlanDirectoryMoveParam};
+use std::fs;
+use tempfile::TempDir;
+
+fn setup(files: &[(&str, &str)]) -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    for (name, content) in files {
+        let path = temp_dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn move_param(source: &str, destination: &str) -> PlanDirectoryMoveParam {
+    PlanDirectoryMoveParam {
+        source: source.to_string(),
+        destination: destination.to_string(),
+        max_file_size: 50 * 1024 * 1024,
+        workspace: None,
+    }
+}
+
+const FILES: &[(&str, &str)] = &[
+    (
+        "src/app.ts",
+        "import { util } from './lib/util';\nimport * as lib from './lib';\nimport x from 'lodash';\n",
+    ),
+    (
+        "src/lib/index.ts",
+        "export * from './util';\nexport { config } from '../config';\n",
+    ),
+    (
+        "src/lib/util.ts",
+        "export const util = require('./helpers/a');\n",
+    ),
+    ("src/lib/helpers/a.js", "module.exports = 1;\n"),
+    ("src/config.ts", "export const config = {};\n"),
+];
+
+#[tokio::test]
+async fn test_plan_lists_broken_imports_without_writing() {
+    let (service, temp_dir) = setup(FILES);
+
+    let plan = service
+        .plan_directory_move(move_param("src/lib", "src/shared/lib"))
+        .await
+        .unwrap();
+    assert_eq!(plan.moves.len(), 3);
+    assert!(plan.moves[0].to.ends_with("src/shared/lib/helpers/a.js"));
+
+    let changes: Vec<(String, String, String)> = plan
+        .edits
+        .iter()
+        .flat_map(|edit| {
+            let file = edit
+                .file_path
+                .rsplit_once("/src/")
+                .map(|(_, rest)| rest.to_string())
+                .unwrap();
+            edit.changes.iter().map(move |change| {
+                (
+                    file.clone(),
+                    change.old_text.clone(),
+                    change.new_text.clone(),
+                )
+            })
+        })
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            (
+                "app.ts".to_string(),
+                "./lib/util".to_string(),
+                "./shared/lib/util".to_string()
+            ),
+            (
+                "app.ts".to_string(),
+                "./lib".to_string(),
+                "./shared/lib".to_string()
+            ),
+            (
+                "lib/index.ts".to_string(),
+                "../config".to_string(),
+                "../../config".to_string()
+            ),
+        ]
+    );
+
+    // Planning writes nothing
+    assert!(temp_dir.path().join("src/lib/util.ts").exists());
+    assert!(!temp_dir.path().join("src/shared").exists());
+}
+
+#[tokio::test]
+async fn test_apply_plan_moves_files_and_rewrites_imports() {
+    let (service, temp_dir) = setup(FILES);
+    let plan = service
+        .plan_directory_move(move_param("src/lib", "src/shared/lib"))
+        .await
+        .unwrap();
+
+    let result = service.apply_plan(ApplyPlanParam { plan }).await.unwrap();
+    assert_eq!(result.files_moved, 3);
+    assert_eq!(result.files_edited, 2);
+    assert_eq!(result.total_changes, 3);
+    assert_eq!(result.removed_directories.len(), 2);
+
+    let read = |name: &str| fs::read_to_string(temp_dir.path().join(name)).unwrap();
+    assert_eq!(
+        read("src/app.ts"),
+        "import { util } from './shared/lib/util';\nimport * as lib from './shared/lib';\nimport x from 'lodash';\n"
+    );
+    assert_eq!(
+        read("src/shared/lib/index.ts"),
+        "export * from './util';\nexport { config } from '../../config';\n"
+    );
+    assert_eq!(
+        read("src/shared/lib/util.ts"),
+        "export const util = require('./helpers/a');\n"
+    );
+    assert!(temp_dir.path().join("src/shared/lib/helpers/a.js").exists());
+    assert!(!temp_dir.path().join("src/lib").exists());
+}
+
+#[tokio::test]
+async fn test_apply_plan_refuses_stale_plan() {
+    let (service, temp_dir) = setup(FILES);
+    let plan = service
+        .plan_directory_move(move_param("src/lib", "src/shared/lib"))
+        .await
+        .unwrap();
+
+    let edited =
+        "// reordered\nimport * as lib from './lib';\nimport { util } from './lib/util';\n";
+    fs::write(temp_dir.path().join("src/app.ts"), edited).unwrap();
+
+    let result = service.apply_plan(ApplyPlanParam { plan }).await;
+    assert!(result.is_err());
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("src/app.ts")).unwrap(),
+        edited
+    );
+    assert!(temp_dir.path().join("src/lib/util.ts").exists());
+    assert!(!temp_dir.path().join("src/shared").exists());
+}
+
+#[tokio::test]
+async fn test_plan_rewrites_local_includes_only() {
+    let (service, temp_dir) = setup(&[
+        (
+            "main.c",
+            "#include <stdio.h>\n#include \"net/socket.h\"\n#include \"config.h\"\n",
+        ),
+        (
+            "net/socket.h",
+            "#include \"../util.h\"\n#include \"buffer.h\"\n",
+        ),
+        ("net/buffer.h", "int size;\n"),
+        ("util.h", "int util;\n"),
+    ]);
+
+    let plan = service
+        .plan_directory_move(move_param("net", "lib/net"))
+        .await
+        .unwrap();
+    service.apply_plan(ApplyPlanParam { plan }).await.unwrap();
+
+    let read = |name: &str| fs::read_to_string(temp_dir.path().join(name)).unwrap();
+    assert_eq!(
+        read("main.c"),
+        "#include <stdio.h>\n#include \"lib/net/socket.h\"\n#include \"config.h\"\n"
+    );
+    assert_eq!(
+        read("lib/net/socket.h"),
+        "#include \"../../util.h\"\n#include \"buffer.h\"\n"
+    );
+}
+
+#[tokio::test]
+async fn test_plan_rejects_invalid_destinations() {
+    let (service, _temp_dir) = setup(FILES);
+
+    assert!(
+        service
+            .plan_directory_move(move_param("src/lib", "src"))
+            .await
+            .is_err()
+    );
+    assert!(
+        service
+            .plan_directory_move(move_param("src/lib", "src/lib/nested"))
+            .await
+            .is_err()
+    );
+    assert!(
+        service
+            .plan_directory_move(move_param("src/missing", "src/other"))
+            .await
+            .is_err()
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_symlinked_destinations_cannot_leave_the_root() {
+    let (service, temp_dir) = setup(FILES);
+    let outside = TempDir::new().unwrap();
+    std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("link")).unwrap();
+
+    let result = service
+        .plan_directory_move(move_param("src/lib", "link/lib"))
+        .await;
+    assert!(result.is_err());
+
+    // A plan edited to move the files through the link is refused as well
+    let mut plan = service
+        .plan_directory_move(move_param("src/lib", "src/shared/lib"))
+        .await
+        .unwrap();
+    for planned in &mut plan.moves {
+        planned.to = planned.to.replace("/src/shared/", "/link/");
+    }
+    let result = service.apply_plan(ApplyPlanParam { plan }).await;
+    assert!(result.is_err());
+    assert!(fs::read_dir(outside.path()).unwrap().next().is_none());
+    assert!(temp_dir.path().join("src/lib/util.ts").exists());
+}
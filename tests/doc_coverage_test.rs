@@ -0,0 +1,171 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::types::{DocCoverageParam, DocCoverageResult};
+use std::fs;
+use tempfile::TempDir;
+
+const RUST: &str = "/// Parses a request
+#[inline]
+pub fn parse() {}
+
+pub fn undocumented() {}
+
+fn private() {}
+
+#[doc = \"A request\"]
+pub struct Request;
+
+pub(crate) struct Internal;
+
+impl Request {
+    /// Sends it
+    pub fn send(&self) {}
+
+    pub fn cancel(&self) {}
+}
+";
+
+const PYTHON: &str = "def load(path):
+    \"\"\"Load a file.\"\"\"
+    def helper():
+        pass
+
+def save(path):
+    pass
+
+def _cache():
+    pass
+
+class Store:
+    \"\"\"Keeps things.\"\"\"
+
+    def get(self):
+        pass
+
+    def __init__(self):
+        pass
+";
+
+const TYPESCRIPT: &str = "/** Formats a value */
+export function format(value: string) {}
+
+export const parse = (text: string) => text;
+
+function local() {}
+
+export class Client {
+  /** Connects */
+  connect() {}
+
+  private retry() {}
+
+  close() {}
+}
+";
+
+fn setup() -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("lib.rs"), RUST).unwrap();
+    fs::write(temp_dir.path().join("store.py"), PYTHON).unwrap();
+    fs::write(temp_dir.path().join("client.ts"), TYPESCRIPT).unwrap();
+    fs::write(temp_dir.path().join("Main.java"), "public class Main {}\n").unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn undocumented(result: &DocCoverageResult) -> Vec<String> {
+    result
+        .undocumented
+        .iter()
+        .map(|symbol| symbol.qualified_name.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_public_symbols_without_doc_comments_are_listed() {
+    let (service, _temp_dir) = setup();
+    let result = service
+        .doc_coverage(DocCoverageParam::default())
+        .await
+        .unwrap();
+
+    // Files are in path order: client.ts, lib.rs, store.py
+    assert_eq!(
+        undocumented(&result),
+        [
+            "parse",
+            "Client",
+            "Client.close",
+            "undocumented",
+            "Request.cancel",
+            "save",
+            "Store.get",
+        ]
+    );
+    assert_eq!(result.files_indexed, 3);
+    assert_eq!(
+        (result.total.documented, result.total.public_symbols),
+        (7, 14)
+    );
+    assert_eq!(result.total.coverage_percent, 50.0);
+
+    let rust = &result.languages["rust"];
+    assert_eq!((rust.documented, rust.public_symbols), (3, 5));
+    assert_eq!(rust.coverage_percent, 60.0);
+    let modules: Vec<(&str, f64)> = result
+        .modules
+        .iter()
+        .map(|module| {
+            let name = module.file_path.rsplit('/').next().unwrap();
+            (name, module.coverage.coverage_percent)
+        })
+        .collect();
+    assert_eq!(
+        modules,
+        [("client.ts", 40.0), ("lib.rs", 60.0), ("store.py", 50.0)]
+    );
+}
+
+#[tokio::test]
+async fn test_undocumented_symbols_are_paginated() {
+    let (service, _temp_dir) = setup();
+    let page = |offset| DocCoverageParam {
+        page_size: 4,
+        offset,
+        ..Default::default()
+    };
+
+    let first = service.doc_coverage(page(0)).await.unwrap();
+    assert_eq!(first.undocumented.len(), 4);
+    assert_eq!(first.next_offset, Some(4));
+
+    let second = service.doc_coverage(page(4)).await.unwrap();
+    assert_eq!(
+        undocumented(&second),
+        ["Request.cancel", "save", "Store.get"]
+    );
+    assert_eq!(second.next_offset, None);
+    // Unchanged files come from the index
+    assert_eq!(second.cached_files, 3);
+
+    let summary = ResponseFormatter::format_doc_coverage_result(&first);
+    assert!(summary.contains("📚 **Doc coverage**: 50% (7 of 14 public symbols documented"));
+    assert!(summary.contains("   • typescript: 40% (2 of 5)"));
+    assert!(summary.contains("pass `\"offset\": 4` for the next page"));
+}
+
+#[tokio::test]
+async fn test_missing_directory_is_an_error() {
+    let (service, _temp_dir) = setup();
+    let result = service
+        .doc_coverage(DocCoverageParam {
+            directory: "missing".to_string(),
+            ..Default::default()
+        })
+        .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,159 @@
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::dynamic_languages::{DynamicLanguage, register};
+use ast_grep_mcp::sg_config::CustomLanguage;
+use std::fs;
+use std::str::FromStr;
+use tempfile::TempDir;
+
+#[test]
+fn test_missing_library_is_an_error() {
+    let error = register(
+        "solidity",
+        &CustomLanguage {
+            library_path: "/nonexistent/solidity.so".into(),
+            extensions: vec!["sol".to_string()],
+            expando_char: None,
+            language_symbol: None,
+        },
+    )
+    .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("Could not load grammar library /nonexistent/solidity.so"),
+        "{error}"
+    );
+    assert!(DynamicLanguage::from_str("solidity").is_err());
+}
+
+#[test]
+fn test_sg_config_registers_custom_languages() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("sgconfig.yml"),
+        "customLanguages:\n  zig:\n    libraryPath: grammars/zig.so\n    extensions: [zig]\n",
+    )
+    .unwrap();
+    // A library that can't be loaded is reported and skipped, not fatal
+    let config = ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    }
+    .with_sg_config(None);
+    assert!(config.sg_config_path.is_some());
+    assert!(DynamicLanguage::from_str("zig").is_err());
+}
+
+#[cfg(feature = "graphql")]
+mod registered {
+    use super::*;
+    use ast_grep_mcp::ast_grep_service::AstGrepService;
+    use ast_grep_mcp::dynamic_languages::register_language;
+    use ast_grep_mcp::types::{
+        FileSearchParam, GenerateAstParam, ListLanguagesParam, ReplaceParam, SearchParam,
+    };
+
+    /// Registers the bundled GraphQL grammar under another name, standing in for a library
+    fn register_schema_language(name: &str) -> DynamicLanguage {
+        register_language(
+            name,
+            &[format!(".{name}")],
+            Some('_'),
+            tree_sitter_graphql::LANGUAGE.into(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_bundled_names_are_rejected() {
+        let error = register_language(
+            "Rust",
+            &["rsx".to_string()],
+            None,
+            tree_sitter_graphql::LANGUAGE.into(),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("bundled language"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn test_pattern_tools_fall_back_to_registered_languages() {
+        let lang = register_schema_language("schemax");
+        assert_eq!(DynamicLanguage::from_str("SchemaX").unwrap(), lang);
+        assert_eq!(lang.extensions(), ["schemax"]);
+        assert!(lang.matches_path("api/user.schemax"));
+
+        let service = AstGrepService::new();
+        let search = service
+            .search(SearchParam::new(
+                "scalar Date\nscalar Time\n",
+                "scalar $NAME",
+                "schemax",
+            ))
+            .await
+            .unwrap();
+        let names: Vec<&str> = search
+            .matches
+            .iter()
+            .map(|m| m.vars["NAME"].as_str())
+            .collect();
+        assert_eq!(names, ["Date", "Time"]);
+
+        let replaced = service
+            .replace(ReplaceParam::new(
+                "scalar Date\n",
+                "scalar $NAME",
+                "scalar $NAME @deprecated",
+                "schemax",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(replaced.new_code, "scalar Date @deprecated\n");
+
+        let ast = service
+            .generate_ast(GenerateAstParam {
+                code: "scalar Date".to_string(),
+                language: "schemax".to_string(),
+                buffer_id: None,
+            })
+            .await
+            .unwrap();
+        assert!(
+            ast.node_kinds
+                .iter()
+                .any(|kind| kind == "scalar_type_definition")
+        );
+
+        let languages = service
+            .list_languages(ListLanguagesParam {})
+            .await
+            .unwrap()
+            .languages;
+        assert!(languages.contains(&"schemax".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_file_search_parses_only_registered_extensions() {
+        register_schema_language("schemay");
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("user.schemay"), "scalar Date\n").unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "scalar Other\n").unwrap();
+        let service = AstGrepService::with_config(ServiceConfig {
+            root_directories: vec![temp_dir.path().to_path_buf()],
+            ..Default::default()
+        });
+
+        let result = service
+            .file_search(FileSearchParam {
+                path_pattern: "**/*".to_string(),
+                pattern: "scalar $NAME".to_string(),
+                language: "schemay".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.matches.len(), 1);
+        assert!(result.matches[0].file_path.ends_with("user.schemay"));
+        assert_eq!(result.matches[0].matches[0].vars["NAME"], "Date");
+    }
+}
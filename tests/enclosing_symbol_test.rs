@@ -0,0 +1,79 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::types::{FileSearchParam, SearchParam};
+use std::fs;
+use tempfile::TempDir;
+
+const SERVICE: &str = r#"
+class UserService {
+  login(user) {
+    audit(user);
+  }
+}
+
+const logout = (user) => {
+  audit(user);
+};
+
+audit(null);
+"#;
+
+#[tokio::test]
+async fn test_search_reports_enclosing_symbol() {
+    let service = AstGrepService::new();
+    let result = service
+        .search(SearchParam {
+            code: SERVICE.to_string(),
+            pattern: "audit($A)".to_string(),
+            language: "javascript".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let symbols: Vec<Option<(String, String)>> = result
+        .matches
+        .iter()
+        .map(|m| {
+            m.enclosing_symbol
+                .as_ref()
+                .map(|symbol| (symbol.name.clone(), symbol.kind.clone()))
+        })
+        .collect();
+    assert_eq!(
+        symbols,
+        vec![
+            Some(("UserService.login".to_string(), "method".to_string())),
+            Some(("logout".to_string(), "function".to_string())),
+            None,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_file_search_summary_names_enclosing_symbol() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("service.js"), SERVICE).unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+
+    let result = service
+        .file_search(FileSearchParam {
+            path_pattern: "*.js".to_string(),
+            pattern: "audit($A)".to_string(),
+            language: "javascript".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let summary = ResponseFormatter::format_file_search_result(&result);
+    assert!(
+        summary.contains("in `UserService.login`: `audit(user)`"),
+        "{summary}"
+    );
+    assert!(summary.contains("in `logout`"), "{summary}");
+}
@@ -0,0 +1,140 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::{CreateRuleParam, RuleSearchParam, ScanParam, ValidateRulesParam};
+use ast_grep_mcp::types::FileSearchParam;
+use std::fs;
+use tempfile::TempDir;
+
+const RULE: &str =
+    "id: internal-import\nlanguage: javascript\nrule:\n  pattern: require('${ENV_TEST_PKG}')\n";
+
+fn setup(env_vars: &[(&str, &str)]) -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    for (name, content) in [
+        ("packages/a.js", "const core = require('@acme/core');\n"),
+        ("vendor/b.js", "const core = require('@acme/core');\n"),
+    ] {
+        let path = temp_dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        env_vars: env_vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn rule_search(rule_config: &str, path_pattern: &str) -> RuleSearchParam {
+    RuleSearchParam {
+        rule_config: rule_config.to_string(),
+        path_pattern: Some(path_pattern.to_string()),
+        max_results: 100,
+        max_file_size: 1024 * 1024,
+        cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
+    }
+}
+
+#[tokio::test]
+async fn test_allowlisted_variables_are_interpolated() {
+    let (service, _temp_dir) =
+        setup(&[("ENV_TEST_PKG", "@acme/core"), ("ENV_TEST_DIR", "packages")]);
+
+    let result = service
+        .rule_search(rule_search(RULE, "${ENV_TEST_DIR}/**/*.js"))
+        .await
+        .unwrap();
+    assert_eq!(result.matches.len(), 1);
+    assert!(result.matches[0].file_path.ends_with("a.js"));
+
+    let result = service
+        .file_search(FileSearchParam {
+            path_pattern: "${ENV_TEST_DIR}/*.js".to_string(),
+            pattern: "require('${ENV_TEST_PKG}')".to_string(),
+            language: "javascript".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    // Search patterns are not rule configs and keep their text
+    assert_eq!(result.total_files_found, 1);
+    assert!(result.matches.is_empty());
+}
+
+#[tokio::test]
+async fn test_variables_outside_the_allowlist_are_left_alone() {
+    let (service, _temp_dir) = setup(&[("ENV_TEST_DIR", "packages")]);
+
+    let result = service
+        .rule_search(rule_search(RULE, "**/*.js"))
+        .await
+        .unwrap();
+    assert!(result.matches.is_empty());
+}
+
+#[tokio::test]
+async fn test_stored_rules_keep_placeholders() {
+    let (service, temp_dir) = setup(&[("ENV_TEST_PKG", "@acme/core")]);
+
+    service
+        .create_rule(CreateRuleParam {
+            rule_config: RULE.to_string(),
+            overwrite: false,
+        })
+        .await
+        .unwrap();
+    let stored = fs::read_to_string(temp_dir.path().join(".rules/internal-import.yaml")).unwrap();
+    assert!(stored.contains("${ENV_TEST_PKG}"));
+
+    // ...and are interpolated each time they are loaded
+    let result = service
+        .scan(ScanParam {
+            path_pattern: Some("**/*.js".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.total_findings, 2);
+}
+
+#[tokio::test]
+async fn test_validate_rules_interpolates_stored_and_inline_rules() {
+    let (service, _temp_dir) = setup(&[("ENV_TEST_PKG", "@acme/core")]);
+    service
+        .create_rule(CreateRuleParam {
+            rule_config: RULE.to_string(),
+            overwrite: false,
+        })
+        .await
+        .unwrap();
+
+    let result = service
+        .validate_rules(ValidateRulesParam {
+            rule_configs: vec![RULE.replace("internal-import", "inline-import")],
+            all_stored: true,
+            test_code: ["internal-import", "inline-import"]
+                .into_iter()
+                .map(|id| (id.to_string(), "require('@acme/core');\n".to_string()))
+                .collect(),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.invalid_count, 0);
+    for rule in &result.rules {
+        let test_results = rule.test_results.as_ref().unwrap();
+        assert_eq!(test_results.matches_found, 1, "{}", rule.source);
+    }
+}
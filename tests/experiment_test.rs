@@ -0,0 +1,106 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::types::{ExperimentCandidate, ExperimentParam};
+
+const CODE: &str = "console.log(a);\nconsole.log(b, c);\nconsole.warn(d);\nlogger.log(e);\n";
+
+fn candidate(pattern: &str, replacement: Option<&str>) -> ExperimentCandidate {
+    ExperimentCandidate {
+        pattern: pattern.to_string(),
+        replacement: replacement.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_candidates_are_compared_side_by_side() {
+    let service = AstGrepService::new();
+
+    let result = service
+        .experiment(ExperimentParam {
+            code: CODE.to_string(),
+            language: "javascript".to_string(),
+            candidates: vec![
+                candidate("console.log($A)", Some("logger.debug($A)")),
+                candidate("console.log($$$ARGS)", Some("logger.debug($$$ARGS)")),
+                ExperimentCandidate {
+                    id: Some("any-method".to_string()),
+                    ..candidate("$OBJ.log($$$)", None)
+                },
+            ],
+            max_matches: 1,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let [single, variadic, any] = result.outcomes.as_slice() else {
+        panic!("expected three outcomes");
+    };
+    assert_eq!(single.id, "1");
+    assert_eq!(single.match_count, 1);
+    assert_eq!(
+        single.new_code.as_deref(),
+        Some("logger.debug(a);\nconsole.log(b, c);\nconsole.warn(d);\nlogger.log(e);\n")
+    );
+
+    assert_eq!(variadic.match_count, 2);
+    assert_eq!(variadic.matches.len(), 1);
+    assert!(
+        variadic
+            .new_code
+            .as_ref()
+            .unwrap()
+            .contains("logger.debug(b, c)")
+    );
+
+    assert_eq!(any.id, "any-method");
+    assert_eq!(any.match_count, 3);
+    assert!(any.new_code.is_none());
+
+    let summary = ResponseFormatter::format_experiment_result(&result);
+    assert!(summary.contains("**2** `console.log($$$ARGS)` (javascript): 2 matches"));
+    assert!(summary.contains("… and 2 more"));
+}
+
+#[tokio::test]
+async fn test_failing_candidate_does_not_stop_the_others() {
+    let service = AstGrepService::new();
+
+    let result = service
+        .experiment(ExperimentParam {
+            code: "let x: number = 1;\n".to_string(),
+            language: "typescript".to_string(),
+            candidates: vec![
+                ExperimentCandidate {
+                    language: Some("klingon".to_string()),
+                    ..candidate("$X", None)
+                },
+                candidate("let $N: $T = $V", Some("const $N: $T = $V")),
+            ],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert!(result.outcomes[0].error.is_some());
+    assert_eq!(result.outcomes[0].language, "klingon");
+    assert_eq!(result.outcomes[1].match_count, 1);
+    assert_eq!(
+        result.outcomes[1].new_code.as_deref(),
+        Some("const x: number = 1;\n")
+    );
+}
+
+#[tokio::test]
+async fn test_experiment_needs_candidates() {
+    let service = AstGrepService::new();
+    let result = service
+        .experiment(ExperimentParam {
+            code: CODE.to_string(),
+            language: "javascript".to_string(),
+            ..Default::default()
+        })
+        .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,203 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::{ExplainRuleParam, ExplainRuleResult, RuleSearchParam};
+use std::fs;
+use tempfile::TempDir;
+
+const SOURCE: &str = r#"function load() {
+  console.log("start");
+  await fetch(url);
+}
+
+function save() {
+  console.log("saving");
+}
+
+// ast-grep-ignore
+console.log("top level");
+"#;
+
+fn setup() -> (TempDir, AstGrepService) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("app.js"), SOURCE).unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+    (temp_dir, service)
+}
+
+async fn explain(service: &AstGrepService, rule_config: &str) -> ExplainRuleResult {
+    service
+        .explain_rule_evaluation(ExplainRuleParam {
+            rule_config: rule_config.to_string(),
+            file_path: "app.js".to_string(),
+            max_nodes_per_step: 10,
+        })
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_trace_shows_pruning_by_all_conditions() {
+    let (_temp_dir, service) = setup();
+    let rule = r#"
+id: log-outside-fetch
+language: javascript
+rule:
+  all:
+    - pattern: console.log($MSG)
+    - not:
+        inside:
+          pattern: await fetch($URL)
+    - inside:
+        kind: function_declaration
+        stopBy: end
+"#;
+    let result = explain(&service, rule).await;
+
+    let paths: Vec<(&str, &str)> = result
+        .steps
+        .iter()
+        .map(|step| (step.path.as_str(), step.operator.as_str()))
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            ("rule.all[0]", "pattern"),
+            ("rule.all[1].not.inside", "pattern"),
+            ("rule.all[1].not", "inside"),
+            ("rule.all[1]", "not"),
+            ("rule.all[2].inside", "kind"),
+            ("rule.all[2]", "inside"),
+            ("rule", "all"),
+        ]
+    );
+
+    let first = &result.steps[0];
+    assert_eq!(first.matched, 3);
+    let inside_function = &result.steps[5];
+    assert_eq!(inside_function.candidates, Some(3));
+    assert_eq!(inside_function.pruned, 1);
+    assert_eq!(
+        inside_function.pruned_nodes[0].text,
+        "console.log(\"top level\")"
+    );
+
+    // The trace agrees with rule_search
+    let search = service
+        .rule_search(RuleSearchParam {
+            rule_config: rule.to_string(),
+            path_pattern: Some("app.js".to_string()),
+            max_results: 10,
+            max_file_size: 1024 * 1024,
+            cursor: None,
+            output_file: None,
+            workspace: None,
+            timeout_ms: None,
+            max_matches: None,
+            walk: Default::default(),
+            output_format: Default::default(),
+            pack: None,
+        })
+        .await
+        .unwrap();
+    let searched: Vec<&str> = search.matches[0]
+        .matches
+        .iter()
+        .map(|m| m.text.as_str())
+        .collect();
+    let explained: Vec<&str> = result.matches.iter().map(|m| m.text.as_str()).collect();
+    assert_eq!(explained, searched);
+    assert!(
+        result
+            .explanation
+            .iter()
+            .any(|line| line.contains("`rule.all[2]` (inside) removed 1 of 3 candidates"))
+    );
+}
+
+#[tokio::test]
+async fn test_trace_splits_rule_objects_with_several_conditions() {
+    let (_temp_dir, service) = setup();
+    let result = explain(
+        &service,
+        r#"
+id: log-in-function
+language: javascript
+rule:
+  pattern: console.log($MSG)
+  inside:
+    kind: function_declaration
+    stopBy: end
+"#,
+    )
+    .await;
+
+    let paths: Vec<(&str, &str)> = result
+        .steps
+        .iter()
+        .map(|step| (step.path.as_str(), step.operator.as_str()))
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            ("rule[pattern]", "pattern"),
+            ("rule[inside].inside", "kind"),
+            ("rule[inside]", "inside"),
+            ("rule", "all"),
+        ]
+    );
+    assert_eq!(result.steps[2].pruned, 1);
+    assert_eq!(result.matches.len(), 2);
+}
+
+#[tokio::test]
+async fn test_trace_reports_suppressed_matches() {
+    let (_temp_dir, service) = setup();
+    let result = explain(
+        &service,
+        r#"
+id: no-log
+language: javascript
+rule:
+  pattern: console.log($MSG)
+"#,
+    )
+    .await;
+
+    assert_eq!(result.steps.len(), 1);
+    assert_eq!(result.suppressed, 1);
+    assert_eq!(result.matches.len(), 2);
+    assert!(
+        result
+            .explanation
+            .iter()
+            .any(|line| line.contains("suppressed by `ast-grep-ignore`"))
+    );
+}
+
+#[tokio::test]
+async fn test_trace_points_at_the_empty_sub_rule() {
+    let (_temp_dir, service) = setup();
+    let result = explain(
+        &service,
+        r#"
+id: no-match
+language: javascript
+rule:
+  all:
+    - pattern: console.error($MSG)
+    - inside:
+        kind: function_declaration
+"#,
+    )
+    .await;
+
+    assert!(result.matches.is_empty());
+    assert_eq!(result.steps[1].note, "skipped: no candidates left");
+    assert_eq!(
+        result.explanation[0],
+        "No matches: `rule.all[0]` (pattern `console.error($MSG)`) matched nothing"
+    );
+}
@@ -0,0 +1,161 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::{CreateRuleParam, ExportToSgconfigParam};
+use ast_grep_mcp::sg_config::SgConfig;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+const NO_CONSOLE: &str = "id: no-console\nlanguage: javascript\nmessage: remove console calls\nrule:\n  pattern: console.log($A)\n";
+const NO_DEBUGGER: &str = "id: no-debugger\nlanguage: javascript\nmessage: remove debugger\nrule:\n  kind: debugger_statement\n";
+
+async fn create_service(temp_dir: &TempDir) -> AstGrepService {
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..Default::default()
+    });
+    for rule_config in [NO_CONSOLE, NO_DEBUGGER] {
+        service
+            .create_rule(CreateRuleParam {
+                rule_config: rule_config.to_string(),
+                overwrite: false,
+            })
+            .await
+            .unwrap();
+    }
+    service
+}
+
+#[tokio::test]
+async fn test_export_writes_project_layout() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir).await;
+
+    let result = service
+        .export_to_sgconfig(ExportToSgconfigParam {
+            output_dir: "lint".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.exported.len(), 2);
+    assert!(result.skipped.is_empty());
+    let project = temp_dir.path().join("lint");
+    assert_eq!(
+        fs::read_to_string(project.join("rules/no-console.yml")).unwrap(),
+        NO_CONSOLE
+    );
+    assert!(project.join("rules/no-debugger.yml").exists());
+
+    let config = SgConfig::from_file(&project.join("sgconfig.yml")).unwrap();
+    assert_eq!(config.rule_dirs, vec![PathBuf::from("rules")]);
+}
+
+#[tokio::test]
+async fn test_export_selected_rules_reports_unknown_ids() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir).await;
+
+    let result = service
+        .export_to_sgconfig(ExportToSgconfigParam {
+            rule_ids: Some(vec!["no-debugger".to_string(), "missing".to_string()]),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.exported.len(), 1);
+    assert_eq!(result.exported[0].id, "no-debugger");
+    assert_eq!(result.skipped.len(), 1);
+    assert_eq!(result.skipped[0].rule_id, "missing");
+    assert!(!temp_dir.path().join("rules/no-console.yml").exists());
+}
+
+#[tokio::test]
+async fn test_export_keeps_existing_files_without_overwrite() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir).await;
+    let rules_dir = temp_dir.path().join("rules");
+    fs::create_dir_all(&rules_dir).unwrap();
+    fs::write(rules_dir.join("no-console.yml"), "# edited by hand\n").unwrap();
+    fs::write(
+        temp_dir.path().join("sgconfig.yml"),
+        "ruleDirs:\n  - ./rules\n",
+    )
+    .unwrap();
+
+    let result = service
+        .export_to_sgconfig(ExportToSgconfigParam::default())
+        .await
+        .unwrap();
+    assert_eq!(result.exported.len(), 1);
+    assert_eq!(result.skipped[0].rule_id, "no-console");
+    assert_eq!(
+        fs::read_to_string(rules_dir.join("no-console.yml")).unwrap(),
+        "# edited by hand\n"
+    );
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("sgconfig.yml")).unwrap(),
+        "ruleDirs:\n  - ./rules\n"
+    );
+
+    let result = service
+        .export_to_sgconfig(ExportToSgconfigParam {
+            overwrite: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.exported.len(), 2);
+    assert_eq!(
+        fs::read_to_string(rules_dir.join("no-console.yml")).unwrap(),
+        NO_CONSOLE
+    );
+}
+
+#[tokio::test]
+async fn test_export_requires_overwrite_to_edit_foreign_sgconfig() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir).await;
+    let sgconfig = temp_dir.path().join("sgconfig.yml");
+    fs::write(&sgconfig, "ruleDirs:\n  - checks\n").unwrap();
+
+    let result = service
+        .export_to_sgconfig(ExportToSgconfigParam::default())
+        .await;
+    assert!(result.is_err());
+
+    service
+        .export_to_sgconfig(ExportToSgconfigParam {
+            overwrite: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let config = SgConfig::from_file(&sgconfig).unwrap();
+    assert_eq!(
+        config.rule_dirs,
+        vec![PathBuf::from("checks"), PathBuf::from("rules")]
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_export_through_symlink_creates_nothing_outside_workspace() {
+    let temp_dir = TempDir::new().unwrap();
+    let outside = TempDir::new().unwrap();
+    std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("link")).unwrap();
+    let service = create_service(&temp_dir).await;
+
+    let result = service
+        .export_to_sgconfig(ExportToSgconfigParam {
+            output_dir: "link/lint".to_string(),
+            ..Default::default()
+        })
+        .await;
+
+    assert!(result.is_err());
+    assert!(!outside.path().join("lint").exists());
+}
@@ -0,0 +1,153 @@
+#![cfg(all(feature = "graphql", feature = "protobuf"))]
+
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::learning::ExplorePatternParam;
+use ast_grep_mcp::types::{
+    FileReplaceParam, FileSearchParam, GenerateAstParam, ListLanguagesParam, ReplaceParam,
+    SearchParam,
+};
+use std::fs;
+use tempfile::TempDir;
+
+fn setup() -> (TempDir, AstGrepService) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("user.graphql"),
+        "type User {\n  id: ID!\n}\n\nquery GetUser($id: ID!) {\n  user(id: $id) { id }\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("user.proto"),
+        "syntax = \"proto3\";\npackage demo;\n\nmessage User {\n  string name = 1;\n}\n",
+    )
+    .unwrap();
+    // Not a GraphQL file, so a graphql search over **/* must leave it alone
+    fs::write(
+        temp_dir.path().join("notes.txt"),
+        "query Other($id: ID!) { x }\n",
+    )
+    .unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+    (temp_dir, service)
+}
+
+#[tokio::test]
+async fn test_file_search_graphql_parses_only_graphql_files() {
+    let (_temp_dir, service) = setup();
+
+    let result = service
+        .file_search(FileSearchParam {
+            path_pattern: "**/*".to_string(),
+            pattern: "query $NAME($$$ARGS) { $$$BODY }".to_string(),
+            language: "graphql".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.matches.len(), 1);
+    let file = &result.matches[0];
+    assert!(file.file_path.ends_with("user.graphql"));
+    assert_eq!(file.engine, None);
+    assert_eq!(file.matches[0].vars["NAME"], "GetUser");
+    assert_eq!(file.matches[0].start_line, 4);
+}
+
+#[tokio::test]
+async fn test_file_replace_protobuf() {
+    let (temp_dir, service) = setup();
+
+    let result = service
+        .file_replace(FileReplaceParam {
+            path_pattern: "**/*.proto".to_string(),
+            pattern: "package $NAME;".to_string(),
+            replacement: "package $NAME.v1;".to_string(),
+            language: "proto".to_string(),
+            dry_run: false,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.total_changes, 1);
+    let content = fs::read_to_string(temp_dir.path().join("user.proto")).unwrap();
+    assert!(content.contains("package demo.v1;"));
+}
+
+#[tokio::test]
+async fn test_code_tools_accept_extra_languages() {
+    let service = AstGrepService::new();
+
+    let search = service
+        .search(SearchParam::new(
+            "syntax = \"proto3\";\nmessage A {}\nmessage B { int32 id = 1; }\n",
+            "message $NAME { $$$ }",
+            "protobuf",
+        ))
+        .await
+        .unwrap();
+    let names: Vec<&str> = search
+        .matches
+        .iter()
+        .map(|m| m.vars["NAME"].as_str())
+        .collect();
+    assert_eq!(names, vec!["A", "B"]);
+
+    let replaced = service
+        .replace(ReplaceParam::new(
+            "scalar Date\n",
+            "scalar $NAME",
+            "scalar $NAME @specifiedBy(url: \"https://example.com\")",
+            "gql",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(
+        replaced.new_code,
+        "scalar Date @specifiedBy(url: \"https://example.com\")\n"
+    );
+
+    let ast = service
+        .generate_ast(GenerateAstParam {
+            code: "query Q { user { id } }".to_string(),
+            language: "graphql".to_string(),
+            buffer_id: None,
+        })
+        .await
+        .unwrap();
+    assert!(ast.node_kinds.contains(&"operation_definition".to_string()));
+}
+
+#[tokio::test]
+async fn test_extra_languages_are_listed_with_catalog_patterns() {
+    let service = AstGrepService::new();
+
+    let languages = service
+        .list_languages(ListLanguagesParam {})
+        .await
+        .unwrap()
+        .languages;
+    assert!(languages.contains(&"graphql".to_string()));
+    assert!(languages.contains(&"protobuf".to_string()));
+
+    let catalog = service
+        .explore_patterns(ExplorePatternParam {
+            language: Some("protobuf".to_string()),
+            category: None,
+            complexity: None,
+            search: None,
+            limit: None,
+        })
+        .await
+        .unwrap();
+    assert!(
+        catalog
+            .patterns
+            .iter()
+            .any(|pattern| pattern.id == "protobuf_message")
+    );
+}
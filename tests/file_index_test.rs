@@ -0,0 +1,159 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::types::{FileSearchParam, IndexStatusParam, RebuildIndexParam};
+use std::fs;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn create_service(temp_dir: &TempDir, file_index: bool) -> AstGrepService {
+    AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        file_index,
+        ..Default::default()
+    })
+}
+
+/// Names of the files with a `console.log` call
+async fn files_logging(service: &AstGrepService) -> Vec<String> {
+    let mut names: Vec<String> = service
+        .file_search(FileSearchParam {
+            path_pattern: "**/*.js".to_string(),
+            pattern: "console.log($A)".to_string(),
+            language: "javascript".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .matches
+        .into_iter()
+        .map(|file| file.file_path.rsplit('/').next().unwrap().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn test_index_is_off_by_default() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir, false);
+
+    let status = service.index_status(IndexStatusParam {}).await.unwrap();
+    assert!(!status.enabled);
+    assert!(
+        ResponseFormatter::format_index_status(&status).contains("start the server with `--index`")
+    );
+    assert!(service.rebuild_index(RebuildIndexParam {}).await.is_err());
+}
+
+#[tokio::test]
+async fn test_repeated_searches_reuse_the_file_list_and_trees() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("a.js"), "console.log(1);\n").unwrap();
+    fs::write(temp_dir.path().join("b.js"), "console.log(2);\n").unwrap();
+    fs::write(temp_dir.path().join("c.js"), "alert(3);\n").unwrap();
+    let service = create_service(&temp_dir, true);
+
+    let status = service.index_status(IndexStatusParam {}).await.unwrap();
+    assert!(status.enabled);
+    assert_eq!((status.builds, status.files), (0, 0));
+
+    assert_eq!(files_logging(&service).await, ["a.js", "b.js"]);
+    assert_eq!(files_logging(&service).await, ["a.js", "b.js"]);
+    let status = service.index_status(IndexStatusParam {}).await.unwrap();
+    assert_eq!(status.builds, 1);
+    assert_eq!(status.files, 3);
+    assert_eq!(status.parsed_files, 3);
+    assert_eq!((status.cache_misses, status.cache_hits), (3, 3));
+
+    let summary = ResponseFormatter::format_index_status(&status);
+    assert!(summary.contains("🗂️ **File index**: 3 files, 3 syntax trees kept"));
+    assert!(summary.contains("Trees reused 3 times, parsed 3 times"));
+}
+
+#[tokio::test]
+async fn test_prewarm_builds_the_index_before_the_first_search() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("a.js"), "console.log(1);\n").unwrap();
+    fs::write(temp_dir.path().join("b.js"), "alert(2);\n").unwrap();
+    assert!(create_service(&temp_dir, true).spawn_prewarm().is_none());
+
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        file_index: true,
+        prewarm: Some(Duration::ZERO),
+        ..Default::default()
+    });
+    let stats = service.spawn_prewarm().unwrap().await.unwrap();
+    assert_eq!((stats.indexed_files, stats.symbol_files), (2, 2));
+    let status = service.index_status(IndexStatusParam {}).await.unwrap();
+    assert!(status.built);
+    assert_eq!((status.builds, status.files), (1, 2));
+
+    // The first search takes the pre-walked list
+    assert_eq!(files_logging(&service).await, ["a.js"]);
+    let status = service.index_status(IndexStatusParam {}).await.unwrap();
+    assert_eq!(status.builds, 1);
+}
+
+#[tokio::test]
+async fn test_changed_content_is_parsed_again() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("a.js");
+    fs::write(&path, "console.log(1);\n").unwrap();
+    let service = create_service(&temp_dir, true);
+    assert_eq!(files_logging(&service).await, ["a.js"]);
+
+    // Even a change the watcher hasn't reported yet never matches against the old tree
+    fs::write(&path, "alert(1);\n").unwrap();
+    assert!(files_logging(&service).await.is_empty());
+    let status = service.index_status(IndexStatusParam {}).await.unwrap();
+    assert_eq!(status.cache_hits, 0);
+}
+
+#[tokio::test]
+async fn test_rebuild_picks_up_new_files() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("a.js"), "console.log(1);\n").unwrap();
+    let service = create_service(&temp_dir, true);
+    assert_eq!(files_logging(&service).await, ["a.js"]);
+
+    fs::write(temp_dir.path().join("b.js"), "console.log(2);\n").unwrap();
+    let status = service.rebuild_index(RebuildIndexParam {}).await.unwrap();
+    assert_eq!(status.builds, 2);
+    assert_eq!(status.files, 2);
+    assert_eq!(status.parsed_files, 0);
+    assert_eq!(files_logging(&service).await, ["a.js", "b.js"]);
+}
+
+#[cfg(feature = "watch")]
+#[tokio::test]
+async fn test_watcher_picks_up_new_and_deleted_files() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("a.js"), "console.log(1);\n").unwrap();
+    let service = create_service(&temp_dir, true);
+    assert_eq!(files_logging(&service).await, ["a.js"]);
+    assert!(
+        service
+            .index_status(IndexStatusParam {})
+            .await
+            .unwrap()
+            .watching
+    );
+
+    fs::write(temp_dir.path().join("b.js"), "console.log(2);\n").unwrap();
+    fs::remove_file(temp_dir.path().join("a.js")).unwrap();
+    // Events arrive from the watcher's thread
+    let mut found = Vec::new();
+    for _ in 0..50 {
+        found = files_logging(&service).await;
+        if found == ["b.js"] {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(found, ["b.js"]);
+    let status = service.index_status(IndexStatusParam {}).await.unwrap();
+    assert!(status.file_events > 0);
+    assert!(status.builds >= 2);
+}
@@ -0,0 +1,62 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::types::FileSearchParam;
+use std::fs;
+use tempfile::TempDir;
+use tokio::sync::mpsc::unbounded_channel;
+
+#[tokio::test]
+async fn test_files_with_matches_are_sent_as_they_are_searched() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("a.js"), "console.log(1);\n").unwrap();
+    fs::write(temp_dir.path().join("b.js"), "other(2);\n").unwrap();
+    fs::write(
+        temp_dir.path().join("c.js"),
+        "console.log(3);\nconsole.log(4);\n",
+    )
+    .unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+
+    let (sender, mut receiver) = unbounded_channel();
+    let result = service
+        .file_search_streaming(
+            FileSearchParam {
+                path_pattern: "*.js".to_string(),
+                pattern: "console.log($A)".to_string(),
+                language: "javascript".to_string(),
+                stream: true,
+                ..Default::default()
+            },
+            sender,
+        )
+        .await
+        .unwrap();
+
+    let mut updates = Vec::new();
+    while let Some(update) = receiver.recv().await {
+        updates.push(update);
+    }
+    let sent: Vec<(usize, usize, usize)> = updates
+        .iter()
+        .map(|update| {
+            (
+                update.files_searched,
+                update.total_files,
+                update.file.matches.len(),
+            )
+        })
+        .collect();
+    assert_eq!(sent, vec![(1, 3, 1), (3, 3, 2)]);
+    assert!(updates[1].file.file_path.ends_with("c.js"));
+
+    // The result still holds every match for callers that are not streaming
+    assert_eq!(result.matches.len(), 2);
+
+    let summary = ResponseFormatter::format_streamed_file_search_result(&result, updates.len());
+    assert!(summary.contains("2 files with matches were sent as progress notifications"));
+    assert!(summary.contains("Search complete"));
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use ast_grep_mcp::types::FindCommentsParam;
+use common::create_service;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn git(dir: &Path, args: &[&str]) -> bool {
+    Command::new("git")
+        .args([
+            "-c",
+            "user.name=Ada Lovelace",
+            "-c",
+            "user.email=ada@example.com",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+#[tokio::test]
+async fn test_find_comments_default_tags() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("app.js"),
+        r#"// TODO: remove this
+const msg = "TODO: not a comment";
+/* FIXME handle errors */
+function f() {} // HACK around the cache
+// TODO second
+"#,
+    )
+    .unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .find_comments(FindCommentsParam {
+            path_pattern: "*.js".to_string(),
+            language: "javascript".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let tags: Vec<&str> = result.comments.iter().map(|c| c.tag.as_str()).collect();
+    assert_eq!(tags, vec!["TODO", "FIXME", "HACK", "TODO"]);
+    assert_eq!(result.tag_counts["TODO"], 2);
+    assert_eq!(result.tag_counts["FIXME"], 1);
+    assert_eq!(result.comments[1].start_line, 2);
+    assert!(result.comments.iter().all(|c| c.blame.is_none()));
+}
+
+#[tokio::test]
+async fn test_find_comments_custom_regex_ticket_ids() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("main.py"),
+        "# See JIRA-123 for context\nx = 1  # JIRA-77\n# nothing here\n",
+    )
+    .unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .find_comments(FindCommentsParam {
+            path_pattern: "*.py".to_string(),
+            language: "python".to_string(),
+            regex: Some(r"[A-Z]+-\d+".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let tags: Vec<&str> = result.comments.iter().map(|c| c.tag.as_str()).collect();
+    assert_eq!(tags, vec!["JIRA-123", "JIRA-77"]);
+}
+
+#[tokio::test]
+async fn test_find_comments_rust_doc_comment_reported_once() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        "/// TODO: document\nfn a() {}\n",
+    )
+    .unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .find_comments(FindCommentsParam {
+            path_pattern: "*.rs".to_string(),
+            language: "rust".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.comments.len(), 1);
+}
+
+#[tokio::test]
+async fn test_find_comments_with_blame() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("app.js"),
+        "let a = 1;\n// FIXME: blame me\n",
+    )
+    .unwrap();
+    if !(git(temp_dir.path(), &["init", "-q"])
+        && git(temp_dir.path(), &["add", "."])
+        && git(temp_dir.path(), &["commit", "-q", "-m", "init"]))
+    {
+        eprintln!("git unavailable, skipping blame test");
+        return;
+    }
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .find_comments(FindCommentsParam {
+            path_pattern: "*.js".to_string(),
+            language: "javascript".to_string(),
+            blame: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let blame = result.comments[0].blame.as_ref().unwrap();
+    assert_eq!(blame.author, "Ada Lovelace");
+    assert_eq!(blame.author_mail, "ada@example.com");
+}
@@ -0,0 +1,174 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::types::{FindReferencesParam, ReferenceKind};
+use std::fs;
+use tempfile::TempDir;
+
+fn setup(files: &[(&str, &str)]) -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    for (name, content) in files {
+        let path = temp_dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn param(symbol: &str, language: &str) -> FindReferencesParam {
+    FindReferencesParam {
+        symbol: symbol.to_string(),
+        language: language.to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_classifies_javascript_references_per_file() {
+    let (service, _temp_dir) = setup(&[
+        (
+            "src/config.ts",
+            "export function loadConfig(path: string) {\n  return read(path);\n}\n",
+        ),
+        (
+            "src/app.js",
+            "import { loadConfig } from './config';\nconst config = loadConfig('a.json');\nsettings.loadConfig();\n",
+        ),
+        (
+            "src/legacy.js",
+            "const loadConfig = require('./loader');\nnew loadConfig();\n// loadConfig is mentioned here only\n",
+        ),
+        ("scripts/config.py", "def loadConfig():\n    pass\n"),
+    ]);
+
+    let result = service
+        .find_references(param("loadConfig", "typescript"))
+        .await
+        .unwrap();
+
+    assert_eq!(result.files_searched, 3);
+    let files: Vec<&str> = result
+        .files
+        .iter()
+        .map(|file| file.file_path.rsplit('/').next().unwrap())
+        .collect();
+    assert_eq!(files, vec!["app.js", "config.ts", "legacy.js"]);
+
+    let kinds = |index: usize| -> Vec<(ReferenceKind, usize)> {
+        result.files[index]
+            .references
+            .iter()
+            .map(|reference| (reference.kind, reference.line))
+            .collect()
+    };
+    assert_eq!(
+        kinds(0),
+        vec![
+            (ReferenceKind::Import, 0),
+            (ReferenceKind::Call, 1),
+            (ReferenceKind::Call, 2)
+        ]
+    );
+    assert_eq!(kinds(1), vec![(ReferenceKind::Definition, 0)]);
+    // The require is an import rather than a second, definition, reference
+    assert_eq!(
+        kinds(2),
+        vec![(ReferenceKind::Import, 0), (ReferenceKind::Call, 1)]
+    );
+    assert_eq!(result.files[0].references[1].column, 15);
+    assert_eq!(result.total_references, 6);
+    assert_eq!(result.counts["call"], 3);
+
+    let summary = ResponseFormatter::format_find_references_result(&result);
+    assert!(summary.contains("6 in 3 of 3 files searched"));
+    assert!(summary.contains("   • import line 0: `import { loadConfig } from './config';`"));
+}
+
+#[tokio::test]
+async fn test_finds_python_rust_and_go_references() {
+    let (service, _temp_dir) = setup(&[
+        (
+            "app.py",
+            "from util import fetch, parse\nimport fetch\n\nclass Client:\n    def fetch(self):\n        return fetch(self.url)\n\nclient.fetch()\n",
+        ),
+        (
+            "lib.rs",
+            "use crate::net::{fetch, Client};\n\npub fn fetch(url: &str) {}\n\nfn main() {\n    fetch(\"a\");\n    client.fetch();\n    net::fetch();\n}\n",
+        ),
+        (
+            "main.go",
+            "package main\n\nimport \"example.com/fetch\"\n\nfunc (c *Client) fetch() {}\n\nfunc main() {\n    c.fetch()\n    fetch.Get()\n}\n",
+        ),
+    ]);
+
+    let kinds = |result: &ast_grep_mcp::types::FindReferencesResult| -> Vec<ReferenceKind> {
+        result.files[0]
+            .references
+            .iter()
+            .map(|reference| reference.kind)
+            .collect()
+    };
+    use ReferenceKind::{Call, Definition, Import};
+
+    let python = service
+        .find_references(param("fetch", "python"))
+        .await
+        .unwrap();
+    assert_eq!(kinds(&python), vec![Import, Import, Definition, Call, Call]);
+
+    let rust = service
+        .find_references(param("fetch", "rust"))
+        .await
+        .unwrap();
+    assert_eq!(kinds(&rust), vec![Import, Definition, Call, Call, Call]);
+
+    let go = service.find_references(param("fetch", "go")).await.unwrap();
+    assert_eq!(kinds(&go), vec![Import, Definition, Call]);
+}
+
+#[tokio::test]
+async fn test_max_results_truncates() {
+    let (service, _temp_dir) = setup(&[
+        ("a.js", "run();\nrun();\n"),
+        ("b.js", "run();\n"),
+        ("c.js", "stop();\n"),
+    ]);
+
+    let result = service
+        .find_references(FindReferencesParam {
+            max_results: 3,
+            ..param("run", "javascript")
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.total_references, 3);
+    assert!(!result.truncated);
+
+    let result = service
+        .find_references(FindReferencesParam {
+            max_results: 1,
+            ..param("run", "javascript")
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.total_references, 1);
+    assert!(result.truncated);
+    assert_eq!(result.files.len(), 1);
+}
+
+#[tokio::test]
+async fn test_rejects_non_identifiers_and_unsupported_languages() {
+    let (service, _temp_dir) = setup(&[("a.js", "run();\n")]);
+
+    assert!(
+        service
+            .find_references(param("$X($$$)", "javascript"))
+            .await
+            .is_err()
+    );
+    assert!(service.find_references(param("run", "html")).await.is_err());
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use ast_grep_language::SupportLang as Language;
+use ast_grep_mcp::similar_code::derive_pattern;
+use ast_grep_mcp::types::FindSimilarCodeParam;
+use common::create_service;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_derive_pattern() {
+    assert_eq!(
+        derive_pattern(
+            "// load it\nconst res = await fetch(url, { method: 'GET' });",
+            Language::JavaScript
+        )
+        .unwrap(),
+        "const $_ = await $_($_, { $_: $_ });"
+    );
+    assert_eq!(
+        derive_pattern("if x > 10:\n    log.warn(x)", Language::Python).unwrap(),
+        "if $_ > $_:\n    $_.$_($_)"
+    );
+    assert_eq!(
+        derive_pattern("let n = s.len();", Language::Rust).unwrap(),
+        "let $_ = $_.$_();"
+    );
+}
+
+#[test]
+fn test_derive_pattern_rejects_several_statements() {
+    let error = derive_pattern("a();\nb();", Language::JavaScript).unwrap_err();
+    assert!(error.contains("found 2"));
+    let error = derive_pattern("const = ;", Language::JavaScript).unwrap_err();
+    assert!(error.contains("doesn't parse"));
+}
+
+#[tokio::test]
+async fn test_find_similar_code_ranks_by_similarity() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("users.js"),
+        "async function loadUsers() {\n  const res = await fetch(url, { method: 'GET' });\n  return res.json();\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("orders.js"),
+        "async function loadOrders() {\n  const reply = await request(endpoint, { verb: 'POST' });\n  const res = await fetch(ordersUrl, { method: 'GET' });\n  const other = fetch(url);\n}\n",
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("users.py"), "res = fetch(url)\n").unwrap();
+
+    let result = create_service(&temp_dir)
+        .find_similar_code(FindSimilarCodeParam {
+            code: "const res = await fetch(url, { method: 'GET' });".to_string(),
+            language: "javascript".to_string(),
+            min_score: 0.0,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.pattern, "const $_ = await $_($_, { $_: $_ });");
+    assert_eq!(result.files_searched, 2);
+    let matches: Vec<(&str, usize)> = result
+        .matches
+        .iter()
+        .map(|m| (m.file_path.rsplit('/').next().unwrap(), m.start_line))
+        .collect();
+    assert_eq!(
+        matches,
+        vec![("users.js", 1), ("orders.js", 2), ("orders.js", 1)]
+    );
+    assert_eq!(result.matches[0].score, 1.0);
+    assert!(result.matches[1].score > result.matches[2].score);
+
+    let result = create_service(&temp_dir)
+        .find_similar_code(FindSimilarCodeParam {
+            code: "const res = await fetch(url, { method: 'GET' });".to_string(),
+            language: "javascript".to_string(),
+            min_score: 0.9,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.total_matches, 2);
+}
@@ -0,0 +1,107 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::rules::RuleValidateParam;
+use ast_grep_mcp::tool_router::ToolRouter;
+use rmcp::model::CallToolRequestParam;
+use serde_json::{Value, json};
+
+const TEST_CODE: &str = "var x = 1;\nconsole.log(x);\nvar y = x + 1;\n";
+
+const RULE: &str = r#"
+id: no-var
+language: javascript
+rule:
+  pattern: var $A = $B
+fix: let $A = $B;
+"#;
+
+async fn validate(rule_config: &str) -> ast_grep_mcp::rules::RuleValidateResult {
+    AstGrepService::new()
+        .validate_rule(RuleValidateParam {
+            rule_config: rule_config.to_string(),
+            test_code: Some(TEST_CODE.to_string()),
+        })
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_validate_rule_previews_the_fix() {
+    let result = validate(RULE).await;
+    assert!(result.valid, "{:?}", result.errors);
+    let test_results = result.test_results.as_ref().unwrap();
+    assert_eq!(test_results.matches_found, 2);
+    assert_eq!(
+        test_results.fixed_code.as_deref(),
+        Some("let x = 1;\nconsole.log(x);\nlet y = x + 1;\n")
+    );
+
+    let hunks: Vec<(usize, &str, &str)> = test_results
+        .fix_hunks
+        .iter()
+        .map(|hunk| {
+            (
+                hunk.start_line,
+                hunk.old_text.as_str(),
+                hunk.new_text.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        hunks,
+        [
+            (0, "var x = 1;\n", "let x = 1;\n"),
+            (2, "var y = x + 1;\n", "let y = x + 1;\n"),
+        ]
+    );
+
+    let summary = ResponseFormatter::format_rule_validate_result(&result);
+    assert!(summary.contains("🔧 **Fix preview**: 2 hunks"));
+    assert!(summary.contains("```diff\n-var x = 1;\n+let x = 1;\n```"));
+}
+
+#[tokio::test]
+async fn test_rules_without_a_fix_have_no_preview() {
+    let result = validate(&RULE.replace("fix: let $A = $B;\n", "")).await;
+    let test_results = result.test_results.as_ref().unwrap();
+    assert_eq!(test_results.matches_found, 2);
+    assert!(test_results.fixed_code.is_none());
+    assert!(test_results.fix_hunks.is_empty());
+    assert!(!ResponseFormatter::format_rule_validate_result(&result).contains("Fix preview"));
+
+    // A fix the test code doesn't match leaves it unchanged
+    let unmatched = validate(&RULE.replace("var $A = $B", "const $A = $B")).await;
+    let test_results = unmatched.test_results.as_ref().unwrap();
+    assert_eq!(test_results.fixed_code.as_deref(), Some(TEST_CODE));
+    assert!(test_results.fix_hunks.is_empty());
+}
+
+#[tokio::test]
+async fn test_validate_rule_tool_name_is_routed() {
+    let service = AstGrepService::new();
+    for name in ["validate_rule", "rule_validate"] {
+        let arguments = json!({ "rule_config": RULE, "test_code": TEST_CODE });
+        let result = ToolRouter::route_tool_call(
+            &service,
+            CallToolRequestParam {
+                name: name.into(),
+                arguments: arguments.as_object().cloned(),
+            },
+        )
+        .await
+        .unwrap_or_else(|error| panic!("{name}: {error:?}"));
+        let text = &result.content[0].as_text().unwrap().text;
+        let response: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(
+            response["test_results"]["fixed_code"], "let x = 1;\nconsole.log(x);\nlet y = x + 1;\n",
+            "{name}"
+        );
+        assert_eq!(
+            response["test_results"]["fix_hunks"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
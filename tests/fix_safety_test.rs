@@ -0,0 +1,101 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::{FixSafety, RuleReplaceParam};
+use std::fs;
+use tempfile::TempDir;
+
+const SOURCE: &str = "console.log('a');\nconsole.log('b');\n";
+
+fn setup() -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("app.js"), SOURCE).unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn replace_param(fix_safety: Option<&str>, dry_run: bool, allow_unsafe: bool) -> RuleReplaceParam {
+    let safety = fix_safety
+        .map(|safety| format!("fix_safety: {safety}\n"))
+        .unwrap_or_default();
+    RuleReplaceParam {
+        rule_config: format!(
+            "id: use-logger\nlanguage: javascript\nrule:\n  pattern: console.log($A)\nfix: logger.log($A)\n{safety}"
+        ),
+        path_pattern: Some("*.js".to_string()),
+        max_results: 100,
+        max_file_size: 1024 * 1024,
+        dry_run,
+        summary_only: false,
+        cursor: None,
+        allow_unsafe,
+        workspace: None,
+        ignore_whitespace_changes: true,
+        timeout_ms: None,
+        walk: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_unsafe_fixes_are_listed_separately_in_dry_run() {
+    let (service, _temp_dir) = setup();
+
+    let result = service
+        .rule_replace(replace_param(Some("unsafe"), true, false))
+        .await
+        .unwrap();
+
+    assert_eq!(result.fix_safety, Some(FixSafety::Unsafe));
+    assert!(result.file_results.is_empty());
+    assert_eq!(result.total_changes, 0);
+    assert_eq!(result.unsafe_fixes.len(), 1);
+    assert_eq!(result.unsafe_fixes[0].total_changes, 2);
+    assert!(result.unsafe_fixes[0].file_path.ends_with("app.js"));
+}
+
+#[tokio::test]
+async fn test_unsafe_fixes_require_allow_unsafe() {
+    let (service, temp_dir) = setup();
+    let file = temp_dir.path().join("app.js");
+
+    let refused = service
+        .rule_replace(replace_param(Some("unsafe"), false, false))
+        .await;
+    assert!(refused.unwrap_err().to_string().contains("allow_unsafe"));
+    assert_eq!(fs::read_to_string(&file).unwrap(), SOURCE);
+
+    let result = service
+        .rule_replace(replace_param(Some("unsafe"), false, true))
+        .await
+        .unwrap();
+    assert_eq!(result.total_changes, 2);
+    assert!(result.unsafe_fixes.is_empty());
+    assert!(
+        fs::read_to_string(&file)
+            .unwrap()
+            .contains("logger.log('a')")
+    );
+}
+
+#[tokio::test]
+async fn test_safe_and_suggestion_fixes_apply() {
+    let (service, _temp_dir) = setup();
+
+    let result = service
+        .rule_replace(replace_param(None, true, false))
+        .await
+        .unwrap();
+    assert_eq!(result.fix_safety, Some(FixSafety::Safe));
+    assert_eq!(result.total_changes, 2);
+
+    let result = service
+        .rule_replace(replace_param(Some("suggestion"), false, false))
+        .await
+        .unwrap();
+    assert_eq!(result.fix_safety, Some(FixSafety::Suggestion));
+    assert_eq!(result.total_changes, 2);
+    assert!(result.unsafe_fixes.is_empty());
+}
@@ -0,0 +1,7 @@
+function calculateTotal(price, quantity, taxRate) {
+    const subtotal = price * quantity;
+    const tax = price * quantity * taxRate;
+    return subtotal + tax;
+}
+
+module.exports = { calculateTotal };
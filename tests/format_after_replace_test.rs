@@ -0,0 +1,105 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::{FileReplaceParam, ReplaceParam};
+use std::collections::HashMap;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+// `other` is left unformatted on purpose: formatting the rewrite must not touch it
+const SOURCE: &str = "fn main() {\n    let x = compute(1) + 2;\n}\n\nfn   other( ) {}\n";
+
+fn rustfmt_available() -> bool {
+    Command::new("rustfmt")
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+fn file_replace_param(replacement: &str) -> FileReplaceParam {
+    FileReplaceParam {
+        path_pattern: "*.rs".to_string(),
+        pattern: "compute($A)".to_string(),
+        replacement: replacement.to_string(),
+        language: "rust".to_string(),
+        dry_run: false,
+        format_after_replace: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_replace_formats_the_rewrite() {
+    if !rustfmt_available() {
+        return;
+    }
+    let code = "fn main() {\n    let x = compute(1) + 2;\n}\n";
+    let mut param = ReplaceParam::new(code, "compute($A)", "evaluate( $A,true )", "rust");
+    param.format_after_replace = true;
+
+    let result = AstGrepService::new().replace(param).await.unwrap();
+    assert_eq!(
+        result.new_code,
+        "fn main() {\n    let x = evaluate(1, true) + 2;\n}\n"
+    );
+    assert_eq!(result.format_error, None);
+    assert_eq!(result.changes.len(), 1);
+    assert_eq!(result.changes[0].start_line, 1);
+    assert_eq!(
+        result.changes[0].new_text,
+        "    let x = evaluate(1, true) + 2;\n"
+    );
+}
+
+#[tokio::test]
+async fn test_file_replace_formats_only_rewritten_regions() {
+    if !rustfmt_available() {
+        return;
+    }
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("main.rs"), SOURCE).unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+
+    let result = service
+        .file_replace(file_replace_param("evaluate( $A,true )"))
+        .await
+        .unwrap();
+
+    assert_eq!(result.files_with_changes, 1);
+    let file = &result.summary_results[0];
+    assert_eq!(file.format_error, None);
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("main.rs")).unwrap(),
+        "fn main() {\n    let x = evaluate(1, true) + 2;\n}\n\nfn   other( ) {}\n"
+    );
+}
+
+#[tokio::test]
+async fn test_unformattable_rewrite_is_written_as_is() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("main.rs"), SOURCE).unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        formatters: HashMap::from([(
+            "rust".to_string(),
+            vec!["no-such-formatter-binary".to_string()],
+        )]),
+        ..Default::default()
+    });
+
+    let result = service
+        .file_replace(file_replace_param("evaluate( $A,true )"))
+        .await
+        .unwrap();
+
+    let error = result.summary_results[0].format_error.as_ref().unwrap();
+    assert!(error.contains("no-such-formatter-binary"), "{error}");
+    assert!(
+        fs::read_to_string(temp_dir.path().join("main.rs"))
+            .unwrap()
+            .contains("evaluate( 1,true )")
+    );
+}
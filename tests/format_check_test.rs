@@ -0,0 +1,100 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::format_check::{FormatCheck, FormatCheckStatus};
+use ast_grep_mcp::{FileReplaceParam, ReplaceParam};
+use std::collections::HashMap;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+const SOURCE: &str = "fn main() {\n    let x = compute(1) + 2;\n    println!(\"{x}\");\n}\n";
+
+fn rustfmt_available() -> bool {
+    Command::new("rustfmt")
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+async fn check(service: &AstGrepService, replacement: &str) -> FormatCheck {
+    let mut param = ReplaceParam::new(SOURCE, "compute($A)", replacement, "rust");
+    param.verify_formatting = true;
+    service.replace(param).await.unwrap().format_check.unwrap()
+}
+
+#[tokio::test]
+async fn test_clean_replacement_passes() {
+    if !rustfmt_available() {
+        return;
+    }
+    let result = check(&AstGrepService::new(), "evaluate($A, true)").await;
+    assert_eq!(result.status, FormatCheckStatus::Passed, "{result:?}");
+    assert!(result.formatter.starts_with("rustfmt"));
+}
+
+#[tokio::test]
+async fn test_unparseable_replacement_fails() {
+    if !rustfmt_available() {
+        return;
+    }
+    let result = check(&AstGrepService::new(), "compute($A").await;
+    assert_eq!(result.status, FormatCheckStatus::Failed);
+    assert!(result.issues[0].contains("rejected the rewritten code"));
+}
+
+#[tokio::test]
+async fn test_code_pushed_outside_the_edit_fails() {
+    if !rustfmt_available() {
+        return;
+    }
+    // The trailing comment moves `+ 2` onto a line of its own
+    let result = check(&AstGrepService::new(), "compute($A) // checked\n").await;
+    assert_eq!(result.status, FormatCheckStatus::Failed, "{result:?}");
+    assert!(result.issues[0].contains("+ 2"));
+}
+
+#[tokio::test]
+async fn test_missing_formatter_skips_check() {
+    let service = AstGrepService::with_config(ServiceConfig {
+        formatters: HashMap::from([(
+            "rust".to_string(),
+            vec!["no-such-formatter-binary".to_string()],
+        )]),
+        ..Default::default()
+    });
+    let result = check(&service, "evaluate($A)").await;
+    assert_eq!(result.status, FormatCheckStatus::Skipped);
+    assert_eq!(result.formatter, "no-such-formatter-binary");
+}
+
+#[tokio::test]
+async fn test_file_replace_reports_check_per_file() {
+    if !rustfmt_available() {
+        return;
+    }
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("main.rs"), SOURCE).unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+
+    let result = service
+        .file_replace(FileReplaceParam {
+            path_pattern: "*.rs".to_string(),
+            pattern: "compute($A)".to_string(),
+            replacement: "compute($A".to_string(),
+            language: "rust".to_string(),
+            dry_run: false,
+            verify_formatting: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let check = result.summary_results[0].format_check.as_ref().unwrap();
+    assert_eq!(check.status, FormatCheckStatus::Failed);
+    // The check reports problems but never blocks the write
+    let written = fs::read_to_string(temp_dir.path().join("main.rs")).unwrap();
+    assert!(written.contains("compute(1 + 2"));
+}
@@ -0,0 +1,102 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::types::{FileSearchParam, GetFileByHashParam};
+use std::fs;
+use tempfile::TempDir;
+
+const CONTENT: &str =
+    "function load() {\n  const data = fetch();\n  console.log(data);\n  return data;\n}\n";
+
+fn setup() -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("app.js"), CONTENT).unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+async fn searched_file(service: &AstGrepService) -> (String, String) {
+    let result = service
+        .file_search(FileSearchParam {
+            path_pattern: "*.js".to_string(),
+            pattern: "console.log($A)".to_string(),
+            language: "javascript".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let file = &result.matches[0];
+    assert!(file.file_hash.starts_with("sha256:"));
+    (file.file_path.clone(), file.file_hash.clone())
+}
+
+#[tokio::test]
+async fn test_fetches_unchanged_file_and_line_range() {
+    let (service, _temp_dir) = setup();
+    let (file_path, file_hash) = searched_file(&service).await;
+
+    let whole = service
+        .get_file_by_hash(GetFileByHashParam {
+            file_path: file_path.clone(),
+            file_hash: file_hash.clone(),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(whole.unchanged);
+    assert_eq!(whole.content.as_deref(), Some(CONTENT));
+    assert_eq!(whole.total_lines, 5);
+
+    let range = service
+        .get_file_by_hash(GetFileByHashParam {
+            file_path,
+            file_hash,
+            start_line: Some(1),
+            end_line: Some(2),
+        })
+        .unwrap();
+    assert_eq!(
+        range.content.as_deref(),
+        Some("  const data = fetch();\n  console.log(data);\n")
+    );
+    assert_eq!((range.start_line, range.end_line), (1, 2));
+
+    let summary = ResponseFormatter::format_get_file_by_hash_result(&range);
+    assert!(summary.contains("(lines 1-2 of 5, hash unchanged)"));
+}
+
+#[tokio::test]
+async fn test_changed_file_is_not_returned() {
+    let (service, temp_dir) = setup();
+    let (file_path, file_hash) = searched_file(&service).await;
+    fs::write(temp_dir.path().join("app.js"), "console.log(1);\n").unwrap();
+
+    let result = service
+        .get_file_by_hash(GetFileByHashParam {
+            file_path,
+            file_hash: file_hash.clone(),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(!result.unchanged);
+    assert!(result.content.is_none());
+    assert_ne!(result.file_hash, file_hash);
+    assert!(ResponseFormatter::format_get_file_by_hash_result(&result).contains("changed since"));
+}
+
+#[tokio::test]
+async fn test_files_outside_the_roots_are_rejected() {
+    let (service, _temp_dir) = setup();
+    let outside = TempDir::new().unwrap();
+    let path = outside.path().join("secret.js");
+    fs::write(&path, CONTENT).unwrap();
+
+    let result = service.get_file_by_hash(GetFileByHashParam {
+        file_path: path.to_string_lossy().to_string(),
+        file_hash: "sha256:0".to_string(),
+        ..Default::default()
+    });
+    assert!(result.is_err());
+}
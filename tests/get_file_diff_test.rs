@@ -0,0 +1,171 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::RuleReplaceParam;
+use ast_grep_mcp::types::{FileReplaceParam, GetFileDiffParam};
+use std::fs;
+use tempfile::TempDir;
+
+fn setup(files: &[(&str, &str)]) -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    for (name, content) in files {
+        fs::write(temp_dir.path().join(name), content).unwrap();
+    }
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        undo_directory: temp_dir.path().join(".undo"),
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn replace(dry_run: bool) -> FileReplaceParam {
+    FileReplaceParam {
+        path_pattern: "*.js".to_string(),
+        pattern: "var $A = $B".to_string(),
+        replacement: "let $A = $B".to_string(),
+        language: "javascript".to_string(),
+        dry_run,
+        max_samples: 1,
+        ..Default::default()
+    }
+}
+
+fn get_diff(operation_id: &str, file_path: Option<&str>) -> GetFileDiffParam {
+    GetFileDiffParam {
+        operation_id: operation_id.to_string(),
+        file_path: file_path.map(str::to_string),
+    }
+}
+
+#[tokio::test]
+async fn test_dry_run_diffs_are_fetched_file_by_file() {
+    let (service, _temp_dir) = setup(&[
+        ("a.js", "var a = 1;\nvar b = 2;\nvar c = 3;\n"),
+        ("b.js", "var d = 4;\n"),
+        ("c.js", "let e = 5;\n"),
+    ]);
+
+    let preview = service.file_replace(replace(true)).await.unwrap();
+    let operation_id = preview.operation_id.unwrap();
+    // The dry run itself only carries samples
+    let samples: usize = preview
+        .summary_results
+        .iter()
+        .map(|file| file.sample_changes.len())
+        .sum();
+    assert_eq!(samples, 2);
+
+    let first = service
+        .get_file_diff(get_diff(&operation_id, None))
+        .await
+        .unwrap();
+    assert_eq!(first.total_files, 2);
+    assert_eq!(first.file_index, 0);
+    let mut diffs = vec![first.diff];
+    let mut next = first.next_file_path;
+    while let Some(file_path) = next {
+        let page = service
+            .get_file_diff(get_diff(&operation_id, Some(&file_path)))
+            .await
+            .unwrap();
+        assert_eq!(page.diff.file_path, file_path);
+        diffs.push(page.diff);
+        next = page.next_file_path;
+    }
+    assert_eq!(diffs.len(), 2);
+
+    let a = diffs
+        .iter()
+        .find(|diff| diff.file_path.ends_with("a.js"))
+        .unwrap();
+    assert_eq!(a.total_changes, 3);
+    let new_texts: Vec<&str> = a.changes.iter().map(|c| c.new_text.as_str()).collect();
+    assert_eq!(new_texts, ["let a = 1", "let b = 2", "let c = 3"]);
+}
+
+#[tokio::test]
+async fn test_unknown_operations_and_files_are_errors() {
+    let (service, temp_dir) = setup(&[("a.js", "var a = 1;\n")]);
+
+    let applied = service.file_replace(replace(false)).await.unwrap();
+    assert!(applied.operation_id.is_none());
+    let unchanged = service.file_replace(replace(true)).await.unwrap();
+    assert!(unchanged.operation_id.is_none());
+
+    let error = service
+        .get_file_diff(get_diff("dry-run-999", None))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("dry-run-999"));
+
+    fs::write(temp_dir.path().join("b.js"), "var b = 2;\n").unwrap();
+    let preview = service.file_replace(replace(true)).await.unwrap();
+    let error = service
+        .get_file_diff(get_diff(&preview.operation_id.unwrap(), Some("missing.js")))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("missing.js"));
+}
+
+#[tokio::test]
+async fn test_rule_replace_dry_run_keeps_diffs() {
+    let (service, _temp_dir) = setup(&[("a.js", "wrap(1);\nwrap(2);\n")]);
+
+    let result = service
+        .rule_replace(RuleReplaceParam {
+            rule_config: "id: unwrap\nlanguage: javascript\nrule:\n  pattern: wrap($A)\nfix: $A"
+                .to_string(),
+            path_pattern: Some("*.js".to_string()),
+            max_results: 100,
+            max_file_size: 1024 * 1024,
+            dry_run: true,
+            summary_only: true,
+            cursor: None,
+            allow_unsafe: false,
+            workspace: None,
+            ignore_whitespace_changes: true,
+            timeout_ms: None,
+            walk: Default::default(),
+        })
+        .await
+        .unwrap();
+
+    let diff = service
+        .get_file_diff(get_diff(&result.operation_id.unwrap(), None))
+        .await
+        .unwrap();
+    assert_eq!(diff.total_files, 1);
+    assert_eq!(diff.diff.total_changes, 2);
+    assert!(diff.next_file_path.is_none());
+}
+
+#[tokio::test]
+async fn test_dry_run_diffs_stay_in_their_session() {
+    let (service, _temp_dir) = setup(&[("a.js", "var a = 1;\n")]);
+    let session_a = service.for_session("a");
+    let session_b = service.for_session("b");
+
+    let preview = session_a.file_replace(replace(true)).await.unwrap();
+    let operation_id = preview.operation_id.unwrap();
+    assert!(
+        session_a
+            .get_file_diff(get_diff(&operation_id, None))
+            .await
+            .is_ok()
+    );
+
+    let error = session_b
+        .get_file_diff(get_diff(&operation_id, None))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("No dry run"), "{error}");
+    // Session B's own dry runs don't reach session A's either
+    let other = session_b.file_replace(replace(true)).await.unwrap();
+    assert_ne!(other.operation_id.unwrap(), operation_id);
+    assert!(
+        session_a
+            .get_file_diff(get_diff(&operation_id, None))
+            .await
+            .is_ok()
+    );
+}
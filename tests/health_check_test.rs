@@ -0,0 +1,73 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::{CheckStatus, HealthCheckParam, HealthCheckResult};
+use std::fs;
+use tempfile::TempDir;
+
+fn status_of<'a>(result: &'a HealthCheckResult, check: &str) -> Vec<&'a CheckStatus> {
+    result
+        .checks
+        .iter()
+        .filter(|c| c.check == check)
+        .map(|c| &c.status)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_health_check_of_a_working_setup() {
+    let temp_dir = TempDir::new().unwrap();
+    let rules = temp_dir.path().join("rules");
+    fs::create_dir(&rules).unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: rules,
+        coverage_directory: temp_dir.path().join("state/coverage"),
+        undo_directory: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    });
+
+    let result = service.health_check(HealthCheckParam {}).await.unwrap();
+    assert!(result.healthy, "{:#?}", result.checks);
+    assert_eq!(result.errors, 0);
+    assert_eq!(status_of(&result, "root_directory"), [&CheckStatus::Ok]);
+    assert_eq!(status_of(&result, "rules_directory"), [&CheckStatus::Ok]);
+    assert_eq!(status_of(&result, "grammars"), [&CheckStatus::Ok]);
+    // Created on first use, and left alone by the check
+    assert_eq!(
+        status_of(&result, "coverage_directory"),
+        [&CheckStatus::Warning]
+    );
+    assert_eq!(result.warnings, 1);
+    assert!(!temp_dir.path().join("state").exists());
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+}
+
+#[tokio::test]
+async fn test_health_check_reports_missing_roots_and_unusable_directories() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("not-a-dir");
+    fs::write(&file, "").unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().join("missing"), file.clone()],
+        rules_directory: file.clone(),
+        coverage_directory: temp_dir.path().to_path_buf(),
+        undo_directory: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    });
+
+    let result = service.health_check(HealthCheckParam {}).await.unwrap();
+    assert!(!result.healthy);
+    assert_eq!(result.errors, 3);
+    assert_eq!(
+        status_of(&result, "root_directory"),
+        [&CheckStatus::Error, &CheckStatus::Error]
+    );
+    let rules = result
+        .checks
+        .iter()
+        .find(|check| check.check == "rules_directory")
+        .unwrap();
+    assert_eq!(rules.status, CheckStatus::Error);
+    assert_eq!(rules.target.as_deref(), Some(file.to_str().unwrap()));
+    assert_eq!(rules.message, "Exists but is not a directory");
+}
@@ -0,0 +1,90 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use rmcp::model::{CallToolRequestParam, ErrorCode};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use tempfile::TempDir;
+
+fn setup(max_input_sizes: BTreeMap<String, usize>) -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        max_input_sizes,
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn request(name: &'static str, arguments: Value) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: name.into(),
+        arguments: arguments.as_object().cloned(),
+    }
+}
+
+#[tokio::test]
+async fn test_oversized_code_points_to_code_buffers() {
+    let (service, _temp_dir) = setup(ServiceConfig::default().max_input_sizes);
+    let code = "console.log(1);\n".repeat(70_000);
+
+    let error = service
+        .handle_tool_call(request(
+            "search",
+            json!({ "code": code, "pattern": "console.log($A)", "language": "javascript" }),
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+    assert!(
+        error
+            .message
+            .contains("code is 1120000 bytes, over the 1048576 byte limit")
+    );
+    assert!(
+        error.message.contains("begin_code_buffer"),
+        "{}",
+        error.message
+    );
+    let data = error.data.unwrap();
+    assert_eq!(data["code"], "INPUT_TOO_LARGE");
+    assert_eq!(data["parameter"], "code");
+
+    // Within the limit the same call goes through
+    let small = service
+        .handle_tool_call(request(
+            "search",
+            json!({ "code": "console.log(1);", "pattern": "console.log($A)", "language": "javascript" }),
+        ))
+        .await;
+    assert!(small.is_ok());
+}
+
+#[tokio::test]
+async fn test_limits_are_configurable_per_parameter() {
+    let limits = BTreeMap::from([("rule_config".to_string(), 64)]);
+    let (service, _temp_dir) = setup(limits);
+    let rule_config = format!(
+        "id: big\nlanguage: javascript\nrule:\n  pattern: console.log($A)\nmessage: {}\n",
+        "x".repeat(100)
+    );
+
+    let error = service
+        .handle_tool_call(request(
+            "validate_rule",
+            json!({ "rule_config": rule_config }),
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(error.data.unwrap()["parameter"], "rule_config");
+    assert!(error.message.contains("rule_ids"), "{}", error.message);
+
+    // `code` has no limit here
+    let code = "console.log(1);\n".repeat(70_000);
+    let result = service
+        .handle_tool_call(request(
+            "search",
+            json!({ "code": code, "pattern": "console.log($A)", "language": "javascript" }),
+        ))
+        .await;
+    assert!(result.is_ok());
+}
@@ -0,0 +1,90 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::{FileReplaceParam, ReplaceParam};
+use serde_json::json;
+use std::fs;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_replace_json_returns_patch_instead_of_changes() {
+    let service = AstGrepService::new();
+    let code = r#"{"name": "app", "debug": true, "ports": [80, 443]}"#;
+
+    let result = service
+        .replace(ReplaceParam {
+            json_patch: true,
+            ..ReplaceParam::new(code, "true", "false", "json")
+        })
+        .await
+        .unwrap();
+
+    assert!(result.changes.is_empty());
+    assert_eq!(
+        serde_json::to_value(result.json_patch.unwrap()).unwrap(),
+        json!([{"op": "replace", "path": "/debug", "value": false}])
+    );
+}
+
+#[tokio::test]
+async fn test_file_replace_yaml_patch_leaves_dry_run_files_alone() {
+    let temp_dir = TempDir::new().unwrap();
+    let manifest = "spec:\n  replicas: 1\n  containers:\n    - image: nginx:1.25\n";
+    fs::write(temp_dir.path().join("deploy.yaml"), manifest).unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+
+    let result = service
+        .file_replace(FileReplaceParam {
+            path_pattern: "**/*.yaml".to_string(),
+            pattern: "image: nginx:1.25".to_string(),
+            replacement: "image: nginx:1.27".to_string(),
+            language: "yaml".to_string(),
+            json_patch: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.total_changes, 1);
+    let file = &result.summary_results[0];
+    assert!(file.sample_changes.is_empty());
+    assert_eq!(
+        serde_json::to_value(file.json_patch.as_ref().unwrap()).unwrap(),
+        json!([{"op": "replace", "path": "/spec/containers/0/image", "value": "nginx:1.27"}])
+    );
+    let content = fs::read_to_string(temp_dir.path().join("deploy.yaml")).unwrap();
+    assert_eq!(content, manifest);
+}
+
+#[tokio::test]
+async fn test_json_patch_errors() {
+    let service = AstGrepService::new();
+
+    let not_a_document = service
+        .replace(ReplaceParam {
+            json_patch: true,
+            ..ReplaceParam::new("let a = 1;", "let $A = 1", "let $A = 2", "javascript")
+        })
+        .await;
+    assert!(
+        not_a_document
+            .unwrap_err()
+            .to_string()
+            .contains("only available for json and yaml")
+    );
+
+    let broken = service
+        .replace(ReplaceParam {
+            json_patch: true,
+            ..ReplaceParam::new(r#"{"a": 1}"#, "1", "[", "json")
+        })
+        .await;
+    assert!(
+        broken
+            .unwrap_err()
+            .to_string()
+            .contains("does not leave a valid document")
+    );
+}
@@ -0,0 +1,67 @@
+use ast_grep_language::SupportLang as Language;
+use ast_grep_mcp::rules::{RuleEvaluator, parse_rule_config};
+
+const SOURCE: &str = "console.log(1);\nfoo(bar(2));\nlet x = a.b;\n";
+
+/// Text of the matches of `rule`, a YAML rule object, in `SOURCE`
+fn matches(rule: &str) -> Vec<String> {
+    let indented = rule.replace('\n', "\n  ");
+    let config = parse_rule_config(&format!(
+        "id: test\nlanguage: javascript\nrule:\n  {indented}\n"
+    ))
+    .unwrap();
+    RuleEvaluator::new()
+        .evaluate_rule_against_code(&config.rule, SOURCE, Language::JavaScript)
+        .unwrap()
+        .into_iter()
+        .map(|m| m.text)
+        .collect()
+}
+
+#[test]
+fn test_kind_matches_nodes_of_that_kind_only() {
+    assert_eq!(
+        matches("kind: call_expression"),
+        vec!["console.log(1)", "foo(bar(2))", "bar(2)"]
+    );
+    assert_eq!(
+        matches("kind: member_expression"),
+        vec!["console.log", "a.b"]
+    );
+
+    let config =
+        parse_rule_config("id: test\nlanguage: javascript\nrule:\n  kind: call_expresion\n")
+            .unwrap();
+    let error = RuleEvaluator::new()
+        .evaluate_rule_against_code(&config.rule, SOURCE, Language::JavaScript)
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("'call_expresion' is not a node kind"),
+        "{error}"
+    );
+}
+
+#[test]
+fn test_kind_combines_with_pattern_and_regex_on_the_same_node() {
+    // The call containing bar(2) is a call_expression too, but isn't what the pattern matched
+    assert_eq!(
+        matches("all:\n  - kind: call_expression\n  - pattern: bar($A)"),
+        vec!["bar(2)"]
+    );
+    assert_eq!(
+        matches("all:\n  - regex: log\n  - kind: call_expression"),
+        vec!["console.log(1)"]
+    );
+    assert_eq!(
+        matches(
+            "all:\n  - pattern: $F($A)\n  - not:\n      regex: ^foo\n  - kind: call_expression"
+        ),
+        vec!["console.log(1)", "bar(2)"]
+    );
+    assert_eq!(
+        matches("any:\n  - kind: member_expression\n  - kind: lexical_declaration"),
+        vec!["console.log", "let x = a.b;", "a.b"]
+    );
+}
@@ -0,0 +1,108 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::{FileReplaceParam, FileSearchParam};
+use std::fs;
+use tempfile::TempDir;
+
+fn create_service(temp_dir: &TempDir) -> AstGrepService {
+    fs::write(temp_dir.path().join("app.js"), "print(1);\n").unwrap();
+    fs::write(temp_dir.path().join("util.ts"), "print(2 as number);\n").unwrap();
+    fs::write(temp_dir.path().join("main.py"), "print(3)\n").unwrap();
+    fs::write(
+        temp_dir.path().join("deploy"),
+        "#!/usr/bin/env python3\nprint(4)\n",
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("notes"), "print(5)\n").unwrap();
+    AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        undo_directory: temp_dir.path().join(".undo"),
+        ..Default::default()
+    })
+}
+
+fn languages_by_file(result: &ast_grep_mcp::types::FileSearchResult) -> Vec<(String, String)> {
+    let mut languages: Vec<(String, String)> = result
+        .matches
+        .iter()
+        .map(|file| {
+            let name = file.file_path.rsplit('/').next().unwrap().to_string();
+            (name, file.language.clone().unwrap())
+        })
+        .collect();
+    languages.sort();
+    languages
+}
+
+#[tokio::test]
+async fn test_file_search_detects_language_per_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir);
+
+    for language in ["", "auto"] {
+        let result = service
+            .file_search(FileSearchParam {
+                path_pattern: "*".to_string(),
+                pattern: "print($A)".to_string(),
+                language: language.to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            languages_by_file(&result),
+            [
+                ("app.js".to_string(), "javascript".to_string()),
+                ("deploy".to_string(), "python".to_string()),
+                ("main.py".to_string(), "python".to_string()),
+                ("util.ts".to_string(), "typescript".to_string()),
+            ]
+        );
+        // The file without an extension or #! line has no language to search it in
+        assert!(result.stats.unwrap().skipped_other_language >= 1);
+    }
+}
+
+#[tokio::test]
+async fn test_file_search_auto_rejects_dialect() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .file_search(FileSearchParam {
+            path_pattern: "*".to_string(),
+            pattern: "print($A)".to_string(),
+            language: "auto".to_string(),
+            dialect: Some("tsx".to_string()),
+            ..Default::default()
+        })
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_file_replace_detects_language_per_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .file_replace(FileReplaceParam {
+            path_pattern: "*".to_string(),
+            pattern: "print($A)".to_string(),
+            replacement: "log($A)".to_string(),
+            language: "auto".to_string(),
+            dry_run: false,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.files_with_changes, 4);
+    let read = |name: &str| fs::read_to_string(temp_dir.path().join(name)).unwrap();
+    assert_eq!(read("app.js"), "log(1);\n");
+    assert_eq!(read("util.ts"), "log(2 as number);\n");
+    assert_eq!(read("main.py"), "log(3)\n");
+    assert_eq!(read("deploy"), "#!/usr/bin/env python3\nlog(4)\n");
+    assert_eq!(read("notes"), "print(5)\n");
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::types::FileSearchParam;
+use std::collections::HashMap;
+use std::fs;
+use tempfile::TempDir;
+
+fn create_service(temp_dir: &TempDir) -> AstGrepService {
+    fs::write(temp_dir.path().join("app.py"), "print('hi')\nlog('x')\n").unwrap();
+    fs::write(
+        temp_dir.path().join("app.js"),
+        "console.log('hi');\nprint('x');\n",
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("App.tsx"), "console.log(<div />);\n").unwrap();
+    fs::write(
+        temp_dir.path().join("main.rs"),
+        "fn main() { println!(\"hi\"); }\n",
+    )
+    .unwrap();
+    common::create_service(temp_dir)
+}
+
+fn patterns(entries: &[(&str, &str)]) -> HashMap<String, String> {
+    entries
+        .iter()
+        .map(|(language, pattern)| (language.to_string(), pattern.to_string()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_language_patterns_merge_results_with_attribution() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .file_search(FileSearchParam {
+            language_patterns: patterns(&[
+                ("python", "print($A)"),
+                ("javascript", "console.log($A)"),
+            ]),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let mut found: Vec<(String, String, String)> = result
+        .matches
+        .iter()
+        .map(|file| {
+            let name = file.file_path.rsplit('/').next().unwrap().to_string();
+            (
+                name,
+                file.language.clone().unwrap(),
+                file.matches[0].text.clone(),
+            )
+        })
+        .collect();
+    found.sort();
+    assert_eq!(
+        found,
+        vec![
+            (
+                "App.tsx".to_string(),
+                "javascript".to_string(),
+                "console.log(<div />)".to_string()
+            ),
+            (
+                "app.js".to_string(),
+                "javascript".to_string(),
+                "console.log('hi')".to_string()
+            ),
+            (
+                "app.py".to_string(),
+                "python".to_string(),
+                "print('hi')".to_string()
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_single_pattern_search_has_no_attribution() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .file_search(FileSearchParam {
+            path_pattern: "*.py".to_string(),
+            pattern: "print($A)".to_string(),
+            language: "python".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.matches.len(), 1);
+    assert!(result.matches[0].language.is_none());
+}
+
+#[tokio::test]
+async fn test_language_patterns_reject_dialect_and_unknown_languages() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .file_search(FileSearchParam {
+            language_patterns: patterns(&[("typescript", "$X as string")]),
+            dialect: Some("tsx".to_string()),
+            ..Default::default()
+        })
+        .await;
+    assert!(result.is_err());
+
+    let result = service
+        .file_search(FileSearchParam {
+            language_patterns: patterns(&[("klingon", "$X")]),
+            ..Default::default()
+        })
+        .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::types::{FileSearchParam, SearchParam, TriviaKind};
+use common::create_service;
+use std::fs;
+use tempfile::TempDir;
+
+const RUST: &str = "let total = 1; // trailing note
+/// Parses the header
+/// of a request.
+#[inline]
+pub fn parse() {}
+
+// Detached comment
+
+pub fn undocumented() {}
+";
+
+async fn trivia(code: &str, pattern: &str, language: &str) -> Vec<Vec<(TriviaKind, String)>> {
+    let temp_dir = TempDir::new().unwrap();
+    create_service(&temp_dir)
+        .search(SearchParam {
+            include_leading_trivia: true,
+            ..SearchParam::new(code, pattern, language)
+        })
+        .await
+        .unwrap()
+        .matches
+        .into_iter()
+        .map(|m| {
+            m.leading_trivia
+                .into_iter()
+                .map(|item| (item.kind, item.text))
+                .collect()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_rust_doc_comments_and_attributes() {
+    assert_eq!(
+        trivia(RUST, "pub fn $NAME() {}", "rust").await,
+        vec![
+            vec![
+                (TriviaKind::Comment, "/// Parses the header".to_string()),
+                (TriviaKind::Comment, "/// of a request.".to_string()),
+                (TriviaKind::Attribute, "#[inline]".to_string()),
+            ],
+            // The blank line separates the comment from the function
+            vec![],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_python_comment_above_decorators() {
+    let code = "x = 1\n# Handles requests\n@route('/')\ndef index():\n    pass\n";
+    assert_eq!(
+        trivia(code, "def index(): $$$BODY", "python").await,
+        vec![vec![
+            (TriviaKind::Comment, "# Handles requests".to_string()),
+            (TriviaKind::Attribute, "@route('/')".to_string()),
+        ]]
+    );
+}
+
+#[tokio::test]
+async fn test_exported_function_gets_comment_above_export() {
+    let code = "/** Adds two numbers */\nexport function add(a, b) { return a + b; }\n";
+    assert_eq!(
+        trivia(code, "function add($$$P) { $$$B }", "javascript").await,
+        vec![vec![(
+            TriviaKind::Comment,
+            "/** Adds two numbers */".to_string()
+        )]]
+    );
+}
+
+#[tokio::test]
+async fn test_file_search_and_formatting() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("lib.rs"), RUST).unwrap();
+    let service = create_service(&temp_dir);
+
+    let without = service
+        .file_search(FileSearchParam {
+            path_pattern: "*.rs".to_string(),
+            pattern: "pub fn parse() {}".to_string(),
+            language: "rust".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(without.matches[0].matches[0].leading_trivia.is_empty());
+
+    let with = service
+        .file_search(FileSearchParam {
+            path_pattern: "*.rs".to_string(),
+            pattern: "pub fn parse() {}".to_string(),
+            language: "rust".to_string(),
+            include_leading_trivia: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let trivia = &with.matches[0].matches[0].leading_trivia;
+    assert_eq!(trivia.len(), 3);
+    assert_eq!((trivia[0].start_line, trivia[0].end_line), (1, 1));
+    assert_eq!(trivia[2].node_kind, "attribute_item");
+
+    let result = service
+        .search(SearchParam {
+            include_leading_trivia: true,
+            ..SearchParam::new(RUST, "pub fn parse() {}", "rust")
+        })
+        .await
+        .unwrap();
+    let summary = ResponseFormatter::format_search_result(&result);
+    assert!(
+        summary.contains("**Leading**: `/// Parses the header`, `/// of a request.`, `#[inline]`")
+    );
+}
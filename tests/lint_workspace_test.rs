@@ -0,0 +1,159 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::rules::{CreateRuleParam, LintThreshold, LintWorkspaceParam};
+use std::fs;
+use tempfile::TempDir;
+
+const RULES: [&str; 3] = [
+    "id: no-eval\nlanguage: javascript\nseverity: error\nmessage: eval is unsafe\nrule:\n  pattern: eval($A)\n",
+    "id: no-console\nlanguage: javascript\nseverity: warning\nrule:\n  pattern: console.log($A)\n",
+    "id: no-debugger\nlanguage: javascript\nrule:\n  pattern: debugger\n",
+];
+
+async fn setup(rules: &[&str]) -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("app.js"),
+        "console.log(1);\neval(code);\nconsole.log(2);\ndebugger;\n",
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("util.js"), "console.log(3);\n").unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..Default::default()
+    });
+    for rule in rules {
+        service
+            .create_rule(CreateRuleParam {
+                rule_config: rule.to_string(),
+                overwrite: false,
+            })
+            .await
+            .unwrap();
+    }
+    (service, temp_dir)
+}
+
+fn lint(fail_on: LintThreshold) -> LintWorkspaceParam {
+    LintWorkspaceParam {
+        path_pattern: Some("*.js".to_string()),
+        fail_on,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_lint_aggregates_every_stored_rule() {
+    let (service, _temp_dir) = setup(&RULES).await;
+    let result = service
+        .lint_workspace(lint(LintThreshold::Error))
+        .await
+        .unwrap();
+
+    assert!(!result.passed);
+    assert_eq!(result.failing_findings, 1);
+    assert_eq!(result.rules_run, 3);
+    assert_eq!(
+        (
+            result.error_count,
+            result.warning_count,
+            result.info_count,
+            result.total_findings
+        ),
+        (1, 3, 1, 5)
+    );
+    let rules: Vec<_> = result
+        .rule_counts
+        .iter()
+        .map(|rule| (rule.rule_id.as_str(), rule.severity.as_str(), rule.count))
+        .collect();
+    assert_eq!(
+        rules,
+        [
+            ("no-eval", "error", 1),
+            ("no-console", "warning", 3),
+            ("no-debugger", "info", 1)
+        ]
+    );
+
+    // Errors come first, then each severity by file and position
+    let findings: Vec<_> = result
+        .findings
+        .iter()
+        .map(|f| (f.severity.as_str(), f.finding.start_line))
+        .collect();
+    assert_eq!(
+        findings,
+        [
+            ("error", 1),
+            ("warning", 0),
+            ("warning", 2),
+            ("warning", 0),
+            ("info", 3)
+        ]
+    );
+    assert!(result.findings[3].finding.file_path.ends_with("util.js"));
+    assert_eq!(result.next_offset, None);
+
+    let summary = ResponseFormatter::format_lint_workspace_result(&result);
+    assert!(summary.contains("❌ **Lint failed**: 1 findings at or above error"));
+    assert!(summary.contains("- `no-console` (warning): 3"));
+}
+
+#[tokio::test]
+async fn test_fail_on_threshold() {
+    let (service, _temp_dir) = setup(&RULES[1..]).await;
+    let result = service
+        .lint_workspace(lint(LintThreshold::Error))
+        .await
+        .unwrap();
+    assert!(result.passed);
+    assert_eq!(result.failing_findings, 0);
+    assert!(
+        ResponseFormatter::format_lint_workspace_result(&result).contains("✅ **Lint passed**")
+    );
+
+    let result = service
+        .lint_workspace(lint(LintThreshold::Warning))
+        .await
+        .unwrap();
+    assert!(!result.passed);
+    assert_eq!(result.failing_findings, 3);
+}
+
+#[tokio::test]
+async fn test_findings_are_paged_across_severities() {
+    let (service, _temp_dir) = setup(&RULES).await;
+    let mut param = LintWorkspaceParam {
+        page_size: 2,
+        ..lint(LintThreshold::Error)
+    };
+    let mut severities = Vec::new();
+    loop {
+        let result = service.lint_workspace(param.clone()).await.unwrap();
+        assert!(result.findings.len() <= 2);
+        // Counts cover every finding on every page
+        assert_eq!(result.total_findings, 5);
+        severities.extend(result.findings.into_iter().map(|f| f.severity));
+        match result.next_offset {
+            Some(offset) => param.offset = offset,
+            None => break,
+        }
+    }
+    assert_eq!(
+        severities,
+        ["error", "warning", "warning", "warning", "info"]
+    );
+}
+
+#[tokio::test]
+async fn test_fail_on_parses_from_json() {
+    let param: LintWorkspaceParam = serde_json::from_str(r#"{"fail_on": "warning"}"#).unwrap();
+    assert_eq!(param.fail_on, LintThreshold::Warning);
+    assert_eq!(param.page_size, 20);
+    let param: LintWorkspaceParam = serde_json::from_str("{}").unwrap();
+    assert_eq!(param.fail_on, LintThreshold::Error);
+    assert!(serde_json::from_str::<LintWorkspaceParam>(r#"{"fail_on": "info"}"#).is_err());
+}
@@ -0,0 +1,140 @@
+use ast_grep_language::SupportLang as Language;
+use ast_grep_mcp::lsp::{
+    DiagnosticSeverity, LspServer, Position, diagnostics_for_code, read_message, write_message,
+};
+use ast_grep_mcp::rules::{
+    FileRuleStorage, RuleConfig, RuleEvaluator, RuleStorage, parse_rule_config,
+};
+use serde_json::{Value, json};
+use std::fs;
+use std::io::Cursor;
+use tempfile::TempDir;
+
+fn rules() -> Vec<RuleConfig> {
+    [
+        "id: no-console\nlanguage: javascript\nseverity: warning\nmessage: Remove console.log\nrule:\n  pattern: console.log($A)\n",
+        "id: no-debugger\nlanguage: javascript\nseverity: error\nrule:\n  kind: debugger_statement\n",
+        "id: no-unwrap\nlanguage: rust\nrule:\n  pattern: $A.unwrap()\n",
+    ]
+    .iter()
+    .map(|yaml| parse_rule_config(yaml).unwrap())
+    .collect()
+}
+
+/// Run a session and return every message the server sent
+fn run_session(messages: &[Value]) -> Vec<Value> {
+    let mut input = Vec::new();
+    for message in messages {
+        write_message(&mut input, message).unwrap();
+    }
+    let mut output = Vec::new();
+    LspServer::new(rules())
+        .run(Cursor::new(input), &mut output)
+        .unwrap();
+
+    let mut reader = Cursor::new(output);
+    let mut sent = Vec::new();
+    while let Some(message) = read_message(&mut reader).unwrap() {
+        sent.push(message);
+    }
+    sent
+}
+
+#[test]
+fn test_session_publishes_diagnostics() {
+    let uri = "file:///project/src/app%20main.js";
+    let sent = run_session(&[
+        json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+        json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}),
+        json!({"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+            "textDocument": {
+                "uri": uri,
+                "languageId": "javascript",
+                "version": 1,
+                "text": "debugger;\nconsole.log(a);\n// ast-grep-ignore\nconsole.log(b);\n"
+            }
+        }}),
+        json!({"jsonrpc": "2.0", "method": "textDocument/didChange", "params": {
+            "textDocument": { "uri": uri, "version": 2 },
+            "contentChanges": [{ "text": "let a = 1;\n" }]
+        }}),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": {}}),
+        json!({"jsonrpc": "2.0", "id": 3, "method": "shutdown"}),
+        json!({"jsonrpc": "2.0", "method": "exit"}),
+    ]);
+
+    assert_eq!(sent.len(), 5);
+    assert_eq!(sent[0]["id"], 1);
+    assert_eq!(
+        sent[0]["result"]["capabilities"]["textDocumentSync"]["change"],
+        1
+    );
+
+    let opened = &sent[1];
+    assert_eq!(opened["method"], "textDocument/publishDiagnostics");
+    assert_eq!(opened["params"]["uri"], uri);
+    let diagnostics = opened["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0]["code"], "no-debugger");
+    assert_eq!(diagnostics[0]["severity"], 1);
+    assert_eq!(diagnostics[0]["message"], "no-debugger");
+    assert_eq!(diagnostics[1]["code"], "no-console");
+    assert_eq!(diagnostics[1]["severity"], 2);
+    assert_eq!(diagnostics[1]["source"], "ast-grep");
+    assert_eq!(diagnostics[1]["message"], "Remove console.log");
+    assert_eq!(
+        diagnostics[1]["range"],
+        json!({"start": {"line": 1, "character": 0}, "end": {"line": 1, "character": 14}})
+    );
+
+    // The edited document no longer matches, so its diagnostics are cleared
+    assert_eq!(sent[2]["params"]["diagnostics"], json!([]));
+    assert_eq!(sent[3]["id"], 2);
+    assert_eq!(sent[3]["error"]["code"], -32601);
+    assert_eq!(sent[4]["id"], 3);
+    assert_eq!(sent[4]["result"], Value::Null);
+}
+
+#[test]
+fn test_diagnostics_use_utf16_columns_and_rule_language() {
+    let code = "let s = \"😀\"; console.log(s);\n";
+    let diagnostics =
+        diagnostics_for_code(&rules(), &RuleEvaluator::new(), code, Language::JavaScript);
+
+    assert_eq!(diagnostics.len(), 1);
+    let diagnostic = &diagnostics[0];
+    assert_eq!(diagnostic.severity, DiagnosticSeverity::Warning);
+    // The emoji is one character but two UTF-16 code units
+    assert_eq!(
+        diagnostic.range.start,
+        Position {
+            line: 0,
+            character: 14
+        }
+    );
+
+    let rust = diagnostics_for_code(
+        &rules(),
+        &RuleEvaluator::new(),
+        "fn main() { x.unwrap(); }",
+        Language::Rust,
+    );
+    assert_eq!(rust.len(), 1);
+    assert_eq!(rust[0].severity, DiagnosticSeverity::Hint);
+}
+
+#[tokio::test]
+async fn test_load_all_rules_from_storage() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("no-console.yaml"),
+        "id: no-console\nlanguage: javascript\nrule:\n  pattern: console.log($A)\n",
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("broken.yaml"), "not: [a rule").unwrap();
+
+    let storage = FileRuleStorage::new(temp_dir.path().to_path_buf());
+    let rules = storage.load_all_rules().await.unwrap();
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0].id, "no-console");
+}
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use std::fs;
-use std::process::Command;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
 use tempfile::TempDir;
 
 #[test]
@@ -175,7 +176,6 @@ rule:
 }
 
 #[test]
-#[ignore = "TODO: Implement file replacement logic"]
 fn test_rule_replace_dry_run() -> Result<()> {
     let temp_dir = TempDir::new()?;
     let test_file = temp_dir.path().join("test.js");
@@ -221,7 +221,6 @@ fix: "logger.info($VAR)"
 }
 
 #[test]
-#[ignore = "TODO: Implement file replacement logic"]
 fn test_rule_replace_apply() -> Result<()> {
     let temp_dir = TempDir::new()?;
     let test_file = temp_dir.path().join("test.js");
@@ -264,6 +263,75 @@ fix: "logger.info($VAR)"
     Ok(())
 }
 
+/// Run `replace --stdin-files` with `input` piped to stdin
+fn run_replace_filter(input: &str, pattern: &str, replacement: &str) -> Output {
+    let mut child = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "replace",
+            "--stdin-files",
+            "--pattern",
+            pattern,
+            "--replacement",
+            replacement,
+            "--language",
+            "javascript",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to run cargo");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().expect("Failed to run cargo")
+}
+
+#[test]
+fn test_replace_filter_rewrites_stdin() {
+    let output = run_replace_filter("var a = 1;\nfoo(a);\n", "var $A = $B", "let $A = $B");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "let a = 1;\nfoo(a);\n"
+    );
+}
+
+#[test]
+fn test_replace_filter_exit_status_without_changes() {
+    let output = run_replace_filter("const a = 1;\n", "var $A = $B", "let $A = $B");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "const a = 1;\n");
+}
+
+#[test]
+fn test_replace_filter_requires_input_source() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "replace",
+            "--pattern",
+            "a",
+            "--replacement",
+            "b",
+            "--language",
+            "javascript",
+        ])
+        .output()
+        .expect("Failed to run cargo");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--stdin-files"));
+}
+
 #[test]
 fn test_generate_ast_command() {
     let output = Command::new("cargo")
@@ -0,0 +1,193 @@
+mod common;
+
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::{CreateRuleParam, ScanResult};
+use ast_grep_mcp::tool_router::ToolRouter;
+use ast_grep_mcp::types::FileSearchResult;
+use common::create_service;
+use rmcp::model::CallToolRequestParam;
+use serde_json::{Value, json};
+use std::fs;
+use tempfile::TempDir;
+
+fn request(name: &'static str, arguments: Value) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: name.into(),
+        arguments: arguments.as_object().cloned(),
+    }
+}
+
+fn json_content(result: &rmcp::model::CallToolResult) -> Value {
+    let text = &result.content[1].as_text().unwrap().text;
+    serde_json::from_str(text).unwrap()
+}
+
+#[tokio::test]
+async fn test_file_search_output_file_writes_full_result() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("app.js"),
+        "console.log('a');\nconsole.log('b');\n",
+    )
+    .unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = ToolRouter::route_tool_call(
+        &service,
+        request(
+            "file_search",
+            json!({
+                "path_pattern": "**/*.js",
+                "pattern": "console.log($A)",
+                "language": "javascript",
+                "output_file": "reports/console.json"
+            }),
+        ),
+    )
+    .await
+    .unwrap();
+
+    // Only the summary comes back
+    let summary = json_content(&result);
+    assert_eq!(summary["total_matches"], 2);
+    assert_eq!(summary["files_with_matches"], 1);
+    assert!(summary.get("matches").is_none());
+
+    // The full result is on disk
+    let written = temp_dir.path().join("reports/console.json");
+    let exported: FileSearchResult =
+        serde_json::from_str(&fs::read_to_string(&written).unwrap()).unwrap();
+    assert_eq!(exported.matches.len(), 1);
+    assert_eq!(exported.matches[0].matches.len(), 2);
+    assert!(
+        summary["output_file"]
+            .as_str()
+            .unwrap()
+            .ends_with("reports/console.json")
+    );
+}
+
+#[tokio::test]
+async fn test_rule_search_output_file() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("app.js"), "var x = 1;\nlet y = 2;\n").unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = ToolRouter::route_tool_call(
+        &service,
+        request(
+            "rule_search",
+            json!({
+                "rule_config": "id: no-var\nlanguage: javascript\nrule:\n  pattern: var $NAME = $VALUE;\n",
+                "path_pattern": "**/*.js",
+                "output_file": "no-var.json"
+            }),
+        ),
+    )
+    .await
+    .unwrap();
+
+    let summary = json_content(&result);
+    assert_eq!(summary["total_matches"], 1);
+    assert!(temp_dir.path().join("no-var.json").exists());
+}
+
+#[tokio::test]
+async fn test_scan_output_file() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("app.js"), "var x = 1;\nvar y = 2;\n").unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..Default::default()
+    });
+    service
+        .create_rule(CreateRuleParam {
+            rule_config:
+                "id: no-var\nlanguage: javascript\nseverity: warning\nrule:\n  pattern: var $NAME = $VALUE;\n"
+                    .to_string(),
+            overwrite: false,
+        })
+        .await
+        .unwrap();
+
+    let result = ToolRouter::route_tool_call(
+        &service,
+        request(
+            "scan",
+            json!({ "path_pattern": "**/*.js", "output_file": "reports/scan.json" }),
+        ),
+    )
+    .await
+    .unwrap();
+
+    let summary = json_content(&result);
+    assert_eq!(summary["total_findings"], 2);
+    assert_eq!(summary["severity_counts"]["warning"], 2);
+    assert!(summary.get("buckets").is_none());
+    let exported: ScanResult = serde_json::from_str(
+        &fs::read_to_string(temp_dir.path().join("reports/scan.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(exported.total_findings, 2);
+    assert_eq!(exported.buckets[1].findings.len(), 2);
+
+    let error = ToolRouter::route_tool_call(
+        &service,
+        request("scan", json!({ "output_file": "../scan.json" })),
+    )
+    .await;
+    assert!(error.is_err());
+}
+
+#[tokio::test]
+async fn test_output_file_outside_workspace_is_rejected() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("app.js"), "console.log('a');\n").unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = ToolRouter::route_tool_call(
+        &service,
+        request(
+            "file_search",
+            json!({
+                "path_pattern": "**/*.js",
+                "pattern": "console.log($A)",
+                "language": "javascript",
+                "output_file": "../escape.json"
+            }),
+        ),
+    )
+    .await;
+
+    assert!(result.is_err());
+    assert!(!temp_dir.path().join("../escape.json").exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_output_file_through_symlink_creates_nothing_outside_workspace() {
+    let temp_dir = TempDir::new().unwrap();
+    let outside = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("app.js"), "console.log('a');\n").unwrap();
+    std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("link")).unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = ToolRouter::route_tool_call(
+        &service,
+        request(
+            "file_search",
+            json!({
+                "path_pattern": "**/*.js",
+                "pattern": "console.log($A)",
+                "language": "javascript",
+                "output_file": "link/reports/out.json"
+            }),
+        ),
+    )
+    .await;
+
+    assert!(result.is_err());
+    assert!(!outside.path().join("reports").exists());
+}
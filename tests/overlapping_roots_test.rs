@@ -0,0 +1,101 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::{FileReplaceParam, FileSearchParam};
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn create_service(roots: Vec<PathBuf>) -> AstGrepService {
+    AstGrepService::with_config(ServiceConfig {
+        root_directories: roots,
+        ..Default::default()
+    })
+}
+
+fn search_param() -> FileSearchParam {
+    FileSearchParam {
+        path_pattern: "**/*.js".to_string(),
+        pattern: "console.log($A)".to_string(),
+        language: "javascript".to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_nested_roots_report_each_file_once() {
+    let temp_dir = TempDir::new().unwrap();
+    let src = temp_dir.path().join("src");
+    fs::create_dir(&src).unwrap();
+    fs::write(src.join("app.js"), "console.log(1);\n").unwrap();
+    fs::write(temp_dir.path().join("index.js"), "console.log(2);\n").unwrap();
+    let service = create_service(vec![temp_dir.path().to_path_buf(), src.clone()]);
+
+    let result = service.file_search(search_param()).await.unwrap();
+    let mut files: Vec<String> = result
+        .matches
+        .iter()
+        .map(|file| file.file_path.clone())
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        [
+            temp_dir.path().join("index.js").to_string_lossy(),
+            src.join("app.js").to_string_lossy(),
+        ]
+    );
+    assert_eq!(result.warnings.len(), 1);
+    assert!(result.warnings[0].contains("is inside root directory"));
+
+    let result = service
+        .file_replace(FileReplaceParam {
+            path_pattern: "**/*.js".to_string(),
+            pattern: "console.log($A)".to_string(),
+            replacement: "logger.info($A)".to_string(),
+            language: "javascript".to_string(),
+            dry_run: false,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.total_changes, 2);
+    assert_eq!(result.warnings.len(), 1);
+    assert_eq!(
+        fs::read_to_string(src.join("app.js")).unwrap(),
+        "logger.info(1);\n"
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_symlinked_root_is_the_same_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("project");
+    fs::create_dir(&project).unwrap();
+    fs::write(project.join("app.js"), "console.log(1);\n").unwrap();
+    let link = temp_dir.path().join("link");
+    std::os::unix::fs::symlink(&project, &link).unwrap();
+
+    let service = create_service(vec![project.clone(), link.clone()]);
+    let result = service.file_search(search_param()).await.unwrap();
+    assert_eq!(result.matches.len(), 1);
+    assert_eq!(
+        result.matches[0].file_path,
+        project.join("app.js").to_string_lossy()
+    );
+    assert_eq!(
+        result.warnings,
+        [format!(
+            "Root directories '{}' and '{}' are the same directory; files under both are only \
+             reported once",
+            project.display(),
+            link.display()
+        )]
+    );
+
+    // Separate roots get no warning
+    let other = TempDir::new().unwrap();
+    let service = create_service(vec![project, other.path().to_path_buf()]);
+    let result = service.file_search(search_param()).await.unwrap();
+    assert!(result.warnings.is_empty());
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use ast_grep_mcp::types::{FileReplaceParam, FileSearchParam};
+use common::create_service;
+use std::collections::HashMap;
+use std::fs;
+use tempfile::TempDir;
+
+fn overlay(path: &str, content: &str) -> HashMap<String, String> {
+    HashMap::from([(path.to_string(), content.to_string())])
+}
+
+#[tokio::test]
+async fn test_file_search_matches_overlay_content() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+    fs::write(temp_dir.path().join("src/app.js"), "foo();\n").unwrap();
+    fs::write(temp_dir.path().join("src/lib.js"), "console.log(0);\n").unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .file_search(FileSearchParam {
+            path_pattern: "src/*.js".to_string(),
+            pattern: "console.log($A)".to_string(),
+            language: "javascript".to_string(),
+            overlays: overlay("src/app.js", "foo();\n\nconsole.log(1);\n"),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let mut files: Vec<_> = result
+        .matches
+        .iter()
+        .map(|file| {
+            (
+                file.file_path.rsplit('/').next().unwrap(),
+                file.matches[0].start_line,
+            )
+        })
+        .collect();
+    files.sort();
+    assert_eq!(files, vec![("app.js", 2), ("lib.js", 0)]);
+}
+
+#[tokio::test]
+async fn test_file_replace_returns_edits_for_overlay() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("app.js");
+    fs::write(&path, "var a = 1;\n").unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .file_replace(FileReplaceParam {
+            path_pattern: "*.js".to_string(),
+            pattern: "var $A = $B;".to_string(),
+            replacement: "let $A = $B;".to_string(),
+            language: "javascript".to_string(),
+            dry_run: false,
+            overlays: overlay(
+                path.to_str().unwrap(),
+                "// unsaved\nvar a = 1;\nvar b = 2;\n",
+            ),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.total_changes, 2);
+    let edits = result.summary_results[0].edits.as_ref().unwrap();
+    assert_eq!(edits.len(), 2);
+    assert_eq!(edits[1].start_line, 2);
+    assert_eq!(edits[1].start_col, 0);
+    assert_eq!(edits[1].old_text, "var b = 2;");
+    assert_eq!(edits[1].new_text, "let b = 2;");
+
+    // The overlay belongs to the editor; the file on disk is untouched
+    assert_eq!(fs::read_to_string(&path).unwrap(), "var a = 1;\n");
+}
@@ -0,0 +1,110 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::{CoveragePattern, PatternCoverageParam};
+use std::fs;
+use tempfile::TempDir;
+
+fn setup() -> (TempDir, TempDir, AstGrepService) {
+    let project = TempDir::new().unwrap();
+    fs::write(
+        project.path().join("a.js"),
+        "moment(a);\nmoment(b);\n$.ajax(url);\n",
+    )
+    .unwrap();
+    fs::write(project.path().join("b.js"), "moment(c);\n").unwrap();
+    // Same text, wrong language: not counted
+    fs::write(project.path().join("notes.md"), "moment(d);\n").unwrap();
+    let coverage = TempDir::new().unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![project.path().to_path_buf()],
+        coverage_directory: coverage.path().to_path_buf(),
+        ..Default::default()
+    });
+    (project, coverage, service)
+}
+
+fn param() -> PatternCoverageParam {
+    let pattern = |id: &str, pattern: &str| CoveragePattern {
+        id: id.to_string(),
+        pattern: pattern.to_string(),
+        language: "javascript".to_string(),
+    };
+    PatternCoverageParam {
+        name: "drop-moment".to_string(),
+        patterns: vec![
+            pattern("moment", "moment($A)"),
+            pattern("ajax", "$.ajax($A)"),
+        ],
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_pattern_coverage_tracks_progress_across_snapshots() {
+    let (project, coverage, service) = setup();
+
+    let first = service.pattern_coverage(param()).await.unwrap();
+    assert!(first.complete && first.recorded);
+    assert_eq!(first.total_remaining, 4);
+    assert_eq!(first.patterns[0].remaining, 3);
+    assert_eq!(first.patterns[0].files, 2);
+    assert_eq!(first.patterns[0].baseline, None);
+    assert_eq!(first.progress_percent, None);
+    assert!(coverage.path().join("drop-moment.json").exists());
+
+    fs::write(project.path().join("b.js"), "dayjs(c);\n").unwrap();
+    let second = service.pattern_coverage(param()).await.unwrap();
+    assert_eq!(second.patterns[0].remaining, 2);
+    assert_eq!(second.patterns[0].baseline, Some(3));
+    assert_eq!(second.patterns[0].previous, Some(3));
+    assert_eq!(second.progress_percent, Some(25.0));
+    assert_eq!(second.history.len(), 2);
+    assert_eq!(second.history[0].total, 4);
+    assert_eq!(second.history[1].counts["moment"], 2);
+
+    // A preview compares against the history without adding to it
+    let preview = service
+        .pattern_coverage(PatternCoverageParam {
+            record: false,
+            history_limit: 1,
+            ..param()
+        })
+        .await
+        .unwrap();
+    assert!(!preview.recorded);
+    assert_eq!(preview.patterns[0].previous, Some(2));
+    assert_eq!(preview.history.len(), 1);
+}
+
+#[tokio::test]
+async fn test_pattern_coverage_timeout_is_not_recorded() {
+    let (_project, coverage, service) = setup();
+
+    let result = service
+        .pattern_coverage(PatternCoverageParam {
+            timeout_ms: 0,
+            ..param()
+        })
+        .await
+        .unwrap();
+    assert!(!result.complete);
+    assert!(!result.recorded);
+    assert!(!coverage.path().join("drop-moment.json").exists());
+}
+
+#[tokio::test]
+async fn test_pattern_coverage_rejects_bad_requests() {
+    let (_project, _coverage, service) = setup();
+
+    let traversal = service
+        .pattern_coverage(PatternCoverageParam {
+            name: "../outside".to_string(),
+            ..param()
+        })
+        .await;
+    assert!(traversal.is_err());
+
+    let mut duplicate = param();
+    duplicate.patterns[1].id = "moment".to_string();
+    assert!(service.pattern_coverage(duplicate).await.is_err());
+}
@@ -0,0 +1,146 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::tool_router::ToolRouter;
+use ast_grep_mcp::types::CreatePlaygroundParam;
+use rmcp::model::CallToolRequestParam;
+use serde_json::Value;
+use std::fs;
+use tempfile::TempDir;
+
+fn setup() -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        undo_directory: temp_dir.path().join(".undo"),
+        rules_directory: temp_dir.path().join(".rules"),
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn json_content(result: &rmcp::model::CallToolResult) -> Value {
+    let text = &result.content[1].as_text().unwrap().text;
+    serde_json::from_str(text).unwrap()
+}
+
+#[tokio::test]
+async fn test_every_suggestion_finds_something() {
+    let (service, temp_dir) = setup();
+    let result = service
+        .create_playground(CreatePlaygroundParam::default())
+        .await
+        .unwrap();
+    assert!(result.files.contains(&"python/report.py".to_string()));
+    assert!(
+        temp_dir
+            .path()
+            .join("ast-grep-playground/rules/no-var.yml")
+            .is_file()
+    );
+
+    for suggestion in &result.suggestions {
+        let request = CallToolRequestParam {
+            name: suggestion.tool.clone().into(),
+            arguments: suggestion.arguments.as_object().cloned(),
+        };
+        let response = ToolRouter::route_tool_call(&service, request)
+            .await
+            .unwrap_or_else(|e| panic!("{}: {e:?}", suggestion.description));
+        let json = json_content(&response);
+        let found = match json.get("matches") {
+            Some(matches) => matches.as_array().unwrap().len(),
+            None => json["total_changes"].as_u64().unwrap() as usize,
+        };
+        assert!(found > 0, "{}: {json}", suggestion.description);
+    }
+
+    // Suggested rewrites are dry runs
+    let cart = temp_dir.path().join("ast-grep-playground/js/cart.js");
+    assert!(
+        fs::read_to_string(cart)
+            .unwrap()
+            .starts_with("var TAX_RATE")
+    );
+
+    let summary = ResponseFormatter::format_create_playground_result(&result);
+    assert!(summary.contains("🛝 **Playground**: 10 files written to"));
+    assert!(summary.contains("\"path_pattern\": \"ast-grep-playground/**/*.py\""));
+}
+
+#[tokio::test]
+async fn test_existing_directories_need_overwrite() {
+    let (service, temp_dir) = setup();
+    let directory = temp_dir.path().join("sandbox");
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("notes.txt"), "keep me").unwrap();
+
+    let param = CreatePlaygroundParam {
+        directory: "sandbox".to_string(),
+        ..Default::default()
+    };
+    let error = service.create_playground(param.clone()).await.unwrap_err();
+    assert!(error.to_string().contains("overwrite=true"), "{error}");
+    assert!(!directory.join("js").exists());
+
+    service
+        .create_playground(CreatePlaygroundParam {
+            overwrite: true,
+            ..param
+        })
+        .await
+        .unwrap();
+    assert!(directory.join("js/cart.js").is_file());
+    assert_eq!(
+        fs::read_to_string(directory.join("notes.txt")).unwrap(),
+        "keep me"
+    );
+}
+
+#[tokio::test]
+async fn test_playground_stays_inside_the_roots() {
+    let (service, _temp_dir) = setup();
+    let outside = TempDir::new().unwrap();
+    let result = service
+        .create_playground(CreatePlaygroundParam {
+            directory: outside
+                .path()
+                .join("playground")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        })
+        .await;
+    assert!(result.is_err());
+    assert!(!outside.path().join("playground").exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_playground_cannot_leave_the_roots_through_a_symlink() {
+    let (service, temp_dir) = setup();
+    let outside = TempDir::new().unwrap();
+    std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("link")).unwrap();
+
+    let result = service
+        .create_playground(CreatePlaygroundParam {
+            directory: "link/play".to_string(),
+            ..Default::default()
+        })
+        .await;
+    assert!(result.is_err());
+    assert!(!outside.path().join("play").exists());
+
+    // Nor through a symlinked subdirectory of an existing project
+    let project = temp_dir.path().join("sandbox");
+    fs::create_dir_all(&project).unwrap();
+    std::os::unix::fs::symlink(outside.path(), project.join("js")).unwrap();
+    let result = service
+        .create_playground(CreatePlaygroundParam {
+            directory: "sandbox".to_string(),
+            overwrite: true,
+        })
+        .await;
+    assert!(result.is_err());
+    assert!(!outside.path().join("cart.js").exists());
+}
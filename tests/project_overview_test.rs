@@ -0,0 +1,130 @@
+mod common;
+
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::types::{ProjectOverviewParam, WalkOptions};
+use common::create_service;
+use std::fs;
+use tempfile::TempDir;
+
+fn write(temp_dir: &TempDir, files: &[(&str, &str)]) {
+    for (name, content) in files {
+        let path = temp_dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_project_overview() {
+    let temp_dir = TempDir::new().unwrap();
+    write(
+        &temp_dir,
+        &[
+            ("src/lib.rs", "pub fn parse() {}\n"),
+            ("src/walk/mod.rs", "mod tree;\n"),
+            ("src/walk/deep/tree.rs", &"// a long file\n".repeat(100)),
+            ("web/app.ts", "export const app = 1;\n"),
+            ("web/view.tsx", "export const View = () => <div />;\n"),
+            ("web/util.ts", "export {};\n"),
+            ("scripts/build.ts", "build();\n"),
+            ("README.md", "# Project\n"),
+        ],
+    );
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .project_overview(ProjectOverviewParam::default())
+        .await
+        .unwrap();
+
+    assert_eq!(result.total_files, 8);
+    assert_eq!(result.other_files, 1);
+    let languages: Vec<(&str, usize)> = result
+        .languages
+        .iter()
+        .map(|language| (language.language.as_str(), language.files))
+        .collect();
+    assert_eq!(languages, vec![("rust", 3), ("typescript", 3), ("tsx", 1)]);
+
+    let root = temp_dir.path().to_string_lossy().to_string();
+    let tree: Vec<(String, usize, usize, Option<&str>)> = result
+        .tree
+        .iter()
+        .map(|directory| {
+            (
+                directory.path.replacen(&root, "", 1),
+                directory.depth,
+                directory.files,
+                directory.main_language.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        tree,
+        vec![
+            ("".to_string(), 0, 8, Some("rust")),
+            ("/scripts".to_string(), 1, 1, Some("typescript")),
+            ("/src".to_string(), 1, 3, Some("rust")),
+            ("/src/walk".to_string(), 2, 2, Some("rust")),
+            ("/web".to_string(), 1, 3, Some("typescript")),
+        ]
+    );
+
+    assert_eq!(result.largest_files.len(), 8);
+    assert!(result.largest_files[0].file_path.ends_with("tree.rs"));
+    assert_eq!(result.largest_files[0].bytes, 1500);
+
+    let patterns: Vec<(&str, &str)> = result
+        .suggested_path_patterns
+        .iter()
+        .map(|suggestion| {
+            (
+                suggestion.path_pattern.as_str(),
+                suggestion.language.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        patterns,
+        vec![
+            ("src/**/*.rs", "rust"),
+            ("**/*.ts", "typescript"),
+            ("web/**/*.tsx", "tsx"),
+        ]
+    );
+
+    let summary = ResponseFormatter::format_project_overview_result(&result);
+    assert!(summary.contains("  • `walk/` 2 files, mostly rust"));
+    assert!(summary.contains("`src/**/*.rs` with language `rust` (3 files)"));
+}
+
+#[tokio::test]
+async fn test_project_overview_honors_walk_options() {
+    let temp_dir = TempDir::new().unwrap();
+    write(
+        &temp_dir,
+        &[
+            ("index.js", "run();\n"),
+            ("node_modules/lib/index.js", "module.exports = 1;\n"),
+            ("vendor/a.py", "pass\n"),
+        ],
+    );
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .project_overview(ProjectOverviewParam {
+            tree_depth: 0,
+            largest_files: 1,
+            walk: WalkOptions {
+                prune_dirs: Some(vec!["node_modules".to_string(), "vendor".to_string()]),
+                ..Default::default()
+            },
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.total_files, 1);
+    assert_eq!(result.tree.len(), 1);
+    assert_eq!(result.largest_files.len(), 1);
+    assert_eq!(result.suggested_path_patterns[0].path_pattern, "**/*.js");
+}
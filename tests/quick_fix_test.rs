@@ -0,0 +1,95 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::rules::{CreateRuleParam, FixSafety};
+use ast_grep_mcp::types::FileSearchParam;
+use std::fs;
+use tempfile::TempDir;
+
+async fn setup() -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("app.js"),
+        "var total = 0;\nconsole.log(total);\nlet done = false;\n",
+    )
+    .unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..Default::default()
+    });
+
+    for rule_config in [
+        "id: no-var\nlanguage: javascript\nmessage: Use let\nrule:\n  pattern: var $N = $V\nfix: let $N = $V;\nfix_safety: suggestion\n",
+        "id: no-console\nlanguage: javascript\nrule:\n  pattern: console.log($A)\nfix: logger.debug($A)\n",
+        "id: find-let\nlanguage: javascript\nrule:\n  pattern: let $N = $V\n",
+        "id: python-only\nlanguage: python\nrule:\n  pattern: print($A)\nfix: log($A)\n",
+    ] {
+        service
+            .create_rule(CreateRuleParam {
+                rule_config: rule_config.to_string(),
+                overwrite: false,
+            })
+            .await
+            .unwrap();
+    }
+    (service, temp_dir)
+}
+
+fn identifier_search(suggest_fixes: bool) -> FileSearchParam {
+    FileSearchParam {
+        path_pattern: "**/*.js".to_string(),
+        pattern: "total".to_string(),
+        language: "javascript".to_string(),
+        suggest_fixes,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_matches_are_annotated_with_overlapping_rule_fixes() {
+    let (service, _temp_dir) = setup().await;
+
+    let result = service.file_search(identifier_search(true)).await.unwrap();
+    let matches = &result.matches[0].matches;
+    assert_eq!(matches.len(), 2);
+
+    let var_fix = &matches[0].quick_fixes;
+    assert_eq!(var_fix.len(), 1);
+    assert_eq!(var_fix[0].rule_id, "no-var");
+    assert_eq!(var_fix[0].message.as_deref(), Some("Use let"));
+    assert_eq!(var_fix[0].fix_safety, FixSafety::Suggestion);
+    assert_eq!(var_fix[0].old_text, "var total = 0;");
+    assert_eq!(var_fix[0].new_text, "let total = 0;");
+
+    let console_fix = &matches[1].quick_fixes;
+    assert_eq!(console_fix.len(), 1);
+    assert_eq!(console_fix[0].rule_id, "no-console");
+    assert_eq!(console_fix[0].new_text, "logger.debug(total)");
+
+    let summary = ResponseFormatter::format_file_search_result(&result);
+    assert!(summary.contains("🔧 `no-var` (suggestion): `var total = 0;` → `let total = 0;`"));
+}
+
+#[tokio::test]
+async fn test_matches_are_not_annotated_unless_requested() {
+    let (service, _temp_dir) = setup().await;
+
+    let result = service.file_search(identifier_search(false)).await.unwrap();
+    assert!(
+        result.matches[0]
+            .matches
+            .iter()
+            .all(|m| m.quick_fixes.is_empty())
+    );
+
+    // Matches that no fixing rule overlaps stay bare
+    let result = service
+        .file_search(FileSearchParam {
+            pattern: "done".to_string(),
+            ..identifier_search(true)
+        })
+        .await
+        .unwrap();
+    assert!(result.matches[0].matches[0].quick_fixes.is_empty());
+}
@@ -104,22 +104,23 @@ function calculate() {
 
 #[tokio::test]
 async fn test_refactor_extract_method() {
-    let (service, _temp_dir) = create_test_refactoring_service();
-    
+    let (service, temp_dir) = create_test_refactoring_service();
+    std::fs::write(
+        temp_dir.path().join("sum.js"),
+        "let a = 5;\nlet b = 10;\nlet result = a + b;\n",
+    )
+    .unwrap();
+
     let request = RefactoringRequest {
         refactoring_id: "extract_method".to_string(),
-        pattern_example: Some(r#"
-let a = 5;
-let b = 10;
-let result = a + b;
-"#.to_string()),
-        options: RefactoringOptions {
+        pattern_example: Some("let result = $A + $B".to_string()),
+        options: Some(RefactoringOptions {
             function_name: Some("addNumbers".to_string()),
             language: Some("javascript".to_string()),
-            scope: Some("file".to_string()),
-            preview: Some(true),
+            scope: types::RefactoringScope::File,
+            preview: true,
             ..Default::default()
-        },
+        }),
     };
 
     let result = service.refactor(request).await.unwrap();
@@ -129,9 +130,8 @@ let result = a + b;
 }
 
 /// Test helper functions that are used by refactoring tests
+#[allow(dead_code)]
 mod test_helpers {
-    use super::*;
-
     pub fn create_javascript_test_code() -> &'static str {
         r#"
 function processData() {
@@ -177,16 +177,17 @@ async fn test_refactor_with_javascript() {
     let request = RefactoringRequest {
         refactoring_id: "extract_variable".to_string(),
         pattern_example: Some("item * 2".to_string()),
-        options: RefactoringOptions {
+        options: Some(RefactoringOptions {
             variable_name: Some("multipliedItem".to_string()),
             language: Some("javascript".to_string()),
-            preview: Some(true),
+            preview: true,
             ..Default::default()
-        },
+        }),
     };
 
     let result = service.refactor(request).await.unwrap();
-    assert!(result.matches_found >= 0); // May or may not find matches depending on pattern
+    // May or may not find matches depending on pattern, but preview never applies changes
+    assert!(!result.applied);
 }
 
 #[tokio::test]
@@ -196,14 +197,14 @@ async fn test_refactor_with_python() {
     let request = RefactoringRequest {
         refactoring_id: "extract_variable".to_string(),
         pattern_example: Some("item * 2".to_string()),
-        options: RefactoringOptions {
+        options: Some(RefactoringOptions {
             variable_name: Some("multiplied_item".to_string()),
             language: Some("python".to_string()),
-            preview: Some(true),
+            preview: true,
             ..Default::default()
-        },
+        }),
     };
 
     let result = service.refactor(request).await.unwrap();
-    assert!(result.matches_found >= 0);
-}
\ No newline at end of file
+    assert!(!result.applied);
+}
@@ -0,0 +1,77 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::FileSearchParam;
+use std::fs;
+use tempfile::TempDir;
+
+fn setup() -> (TempDir, AstGrepService) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("config.toml"),
+        "# settings\n\n[user]\nname = \"demo\"\n\n\n[account]\n",
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("app.js"), "console.log(user);\n").unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+    (temp_dir, service)
+}
+
+fn toml_param(regex_fallback: bool) -> FileSearchParam {
+    FileSearchParam {
+        path_pattern: "**/*.toml".to_string(),
+        pattern: r"^\[(?P<NAME>\w+)\]".to_string(),
+        language: "toml".to_string(),
+        regex_fallback,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_unsupported_language_falls_back_to_regex() {
+    let (_temp_dir, service) = setup();
+
+    let result = service.file_search(toml_param(true)).await.unwrap();
+    assert_eq!(result.matches.len(), 1);
+    let file = &result.matches[0];
+    assert_eq!(file.engine.as_deref(), Some("regex"));
+    let names: Vec<(&str, usize)> = file
+        .matches
+        .iter()
+        .map(|m| (m.vars["NAME"].as_str(), m.start_line))
+        .collect();
+    assert_eq!(names, vec![("user", 2), ("account", 6)]);
+}
+
+#[tokio::test]
+async fn test_unsupported_language_errors_without_fallback() {
+    let (_temp_dir, service) = setup();
+    assert!(service.file_search(toml_param(false)).await.is_err());
+
+    let invalid = FileSearchParam {
+        pattern: "[(".to_string(),
+        ..toml_param(true)
+    };
+    assert!(service.file_search(invalid).await.is_err());
+}
+
+#[tokio::test]
+async fn test_supported_language_ignores_fallback() {
+    let (_temp_dir, service) = setup();
+
+    let result = service
+        .file_search(FileSearchParam {
+            path_pattern: "**/*.js".to_string(),
+            pattern: "console.log($A)".to_string(),
+            language: "javascript".to_string(),
+            regex_fallback: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.matches.len(), 1);
+    assert_eq!(result.matches[0].engine, None);
+    assert_eq!(result.matches[0].matches[0].vars["A"], "user");
+}
@@ -0,0 +1,82 @@
+use ast_grep_language::SupportLang as Language;
+use ast_grep_mcp::rules::{RuleEvaluator, parse_rule_config};
+
+const SOURCE: &str = r#"class Cart {
+  total() {
+    if (ready) {
+      console.log(items);
+    }
+    return sum(items);
+  }
+}
+
+function helper(items) {
+  setup();
+  console.log(items);
+  teardown();
+}
+
+function other(id) {
+  console.log(items);
+}
+"#;
+
+/// Text of the matches of `rule`, a YAML rule object, in `SOURCE`
+fn matches(rule: &str) -> Vec<String> {
+    let indented = rule.replace('\n', "\n  ");
+    let config = parse_rule_config(&format!(
+        "id: test\nlanguage: javascript\nrule:\n  {indented}\n"
+    ))
+    .unwrap();
+    RuleEvaluator::new()
+        .evaluate_rule_against_code(&config.rule, SOURCE, Language::JavaScript)
+        .unwrap()
+        .into_iter()
+        .map(|m| m.text)
+        .collect()
+}
+
+#[test]
+fn test_inside_stops_at_the_parent_unless_told_otherwise() {
+    let in_class = "pattern: console.log($A)\ninside:\n  kind: class_declaration";
+    assert!(matches(in_class).is_empty());
+    assert_eq!(
+        matches(&format!("{in_class}\n  stopBy: end")),
+        vec!["console.log(items)"]
+    );
+
+    // The search ends at the first function-like node, so the class is never reached
+    let stopped = "pattern: console.log($A)\ninside:\n  kind: class_declaration\n  stopBy:\n    kind: method_definition";
+    assert!(matches(stopped).is_empty());
+    let stopped = "pattern: console.log($A)\ninside:\n  kind: method_definition\n  stopBy:\n    kind: method_definition";
+    assert_eq!(matches(stopped), vec!["console.log(items)"]);
+}
+
+#[test]
+fn test_has_with_field_and_shared_metavariables() {
+    // Only the condition of the `if`, not its body, is searched
+    let condition = "kind: if_statement\nhas:\n  field: condition\n  stopBy: end\n  pattern: ready";
+    assert_eq!(matches(condition).len(), 1);
+    let body =
+        "kind: if_statement\nhas:\n  field: condition\n  stopBy: end\n  pattern: console.log($A)";
+    assert!(matches(body).is_empty());
+
+    // $NAME must be the same parameter in the signature and the log call
+    let logs_parameter = "kind: function_declaration\nhas:\n  field: parameters\n  has:\n    pattern: $NAME\nall:\n  - has:\n      stopBy: end\n      pattern: console.log($NAME)";
+    let found = matches(logs_parameter);
+    assert_eq!(found.len(), 1);
+    assert!(found[0].starts_with("function helper"));
+}
+
+#[test]
+fn test_follows_and_precedes_look_at_siblings() {
+    let after_setup = "pattern: console.log($A);\nfollows:\n  pattern: setup();";
+    assert_eq!(matches(after_setup), vec!["console.log(items);"]);
+
+    let before_teardown = "pattern: setup();\nprecedes:\n  pattern: teardown();";
+    assert!(matches(before_teardown).is_empty());
+    assert_eq!(
+        matches(&format!("{before_teardown}\n  stopBy: end")),
+        vec!["setup();"]
+    );
+}
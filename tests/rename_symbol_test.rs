@@ -0,0 +1,190 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::types::{ReferenceKind, RenameScope, RenameSymbolParam};
+use std::fs;
+use tempfile::TempDir;
+
+const CONFIG_TS: &str =
+    "export function loadConfig(path: string): Config {\n  return read(path);\n}\n";
+
+const APP_JS: &str = "import { loadConfig } from './loadConfig';\n\
+// loadConfig reads the file\n\
+const config = loadConfig('loadConfig.json');\n\
+const loader = loadConfig;\n";
+
+fn setup(files: &[(&str, &str)]) -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    for (name, content) in files {
+        let path = temp_dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        undo_directory: temp_dir.path().join(".undo"),
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn param(old_name: &str, new_name: &str, language: &str) -> RenameSymbolParam {
+    RenameSymbolParam {
+        old_name: old_name.to_string(),
+        new_name: new_name.to_string(),
+        language: language.to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_dry_run_lists_renames_without_writing() {
+    let (service, temp_dir) = setup(&[("src/config.ts", CONFIG_TS), ("src/app.js", APP_JS)]);
+    let result = service
+        .rename_symbol(param("loadConfig", "readConfig", "typescript"))
+        .await
+        .unwrap();
+
+    assert!(!result.applied);
+    assert_eq!(result.total_renames, 4);
+    assert_eq!(result.files_searched, 2);
+    // Files are in path order: app.js, config.ts
+    let kinds: Vec<(usize, ReferenceKind)> = result.files[0]
+        .references
+        .iter()
+        .map(|reference| (reference.line, reference.kind))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            (0, ReferenceKind::Import),
+            (2, ReferenceKind::Call),
+            (3, ReferenceKind::Usage),
+        ]
+    );
+    assert_eq!(
+        result.files[1].references[0].kind,
+        ReferenceKind::Definition
+    );
+    assert!(result.collisions.is_empty());
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("src/app.js")).unwrap(),
+        APP_JS
+    );
+
+    let summary = ResponseFormatter::format_rename_symbol_result(&result);
+    assert!(summary.contains("✏️ **Would rename** `loadConfig` → `readConfig`"));
+    assert!(summary.contains("📄 **Places**: 4 in 2 of 2 files searched"));
+}
+
+#[tokio::test]
+async fn test_applying_leaves_strings_and_comments_alone() {
+    let (service, temp_dir) = setup(&[("src/config.ts", CONFIG_TS), ("src/app.js", APP_JS)]);
+    let result = service
+        .rename_symbol(RenameSymbolParam {
+            dry_run: false,
+            ..param("loadConfig", "readConfig", "javascript")
+        })
+        .await
+        .unwrap();
+    assert!(result.applied);
+
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("src/app.js")).unwrap(),
+        "import { readConfig } from './loadConfig';\n\
+         // loadConfig reads the file\n\
+         const config = readConfig('loadConfig.json');\n\
+         const loader = readConfig;\n"
+    );
+    assert!(
+        fs::read_to_string(temp_dir.path().join("src/config.ts"))
+            .unwrap()
+            .starts_with("export function readConfig(path: string): Config {")
+    );
+}
+
+#[tokio::test]
+async fn test_file_scope_renames_one_file() {
+    let (service, temp_dir) = setup(&[
+        (
+            "lib.rs",
+            "struct Parser;\n\nfn parse(p: Parser) -> Parser {\n    p\n}\n",
+        ),
+        ("other.rs", "use crate::Parser;\n"),
+    ]);
+    let result = service
+        .rename_symbol(RenameSymbolParam {
+            scope: RenameScope::File,
+            path: "lib.rs".to_string(),
+            dry_run: false,
+            ..param("Parser", "Reader", "rust")
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.total_renames, 3);
+    assert_eq!(result.files_searched, 1);
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("lib.rs")).unwrap(),
+        "struct Reader;\n\nfn parse(p: Reader) -> Reader {\n    p\n}\n"
+    );
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("other.rs")).unwrap(),
+        "use crate::Parser;\n"
+    );
+}
+
+#[tokio::test]
+async fn test_collisions_block_the_rename_unless_forced() {
+    let source = "def load(path):\n    return path\n\ndef read(path):\n    return load(path)\n";
+    let (service, temp_dir) = setup(&[("store.py", source)]);
+    let path = temp_dir.path().join("store.py");
+
+    let dry_run = service
+        .rename_symbol(param("load", "read", "python"))
+        .await
+        .unwrap();
+    assert_eq!(dry_run.collisions.len(), 1);
+    assert_eq!(dry_run.collisions[0].references[0].line, 3);
+    let summary = ResponseFormatter::format_rename_symbol_result(&dry_run);
+    assert!(summary.contains("⚠️ **Collisions**: `read` is already defined in scope"));
+
+    let apply = RenameSymbolParam {
+        dry_run: false,
+        ..param("load", "read", "python")
+    };
+    let error = service.rename_symbol(apply.clone()).await.unwrap_err();
+    assert!(error.to_string().contains("force=true"), "{error}");
+    assert_eq!(fs::read_to_string(&path).unwrap(), source);
+
+    let forced = service
+        .rename_symbol(RenameSymbolParam {
+            force: true,
+            ..apply
+        })
+        .await
+        .unwrap();
+    assert!(forced.applied);
+    assert!(
+        fs::read_to_string(&path)
+            .unwrap()
+            .starts_with("def read(path):")
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_renames_are_errors() {
+    let (service, _temp_dir) = setup(&[("app.js", APP_JS)]);
+    for (old_name, new_name, language) in [
+        ("loadConfig", "read-config", "javascript"),
+        ("loadConfig", "loadConfig", "javascript"),
+        ("loadConfig", "readConfig", "java"),
+    ] {
+        assert!(
+            service
+                .rename_symbol(param(old_name, new_name, language))
+                .await
+                .is_err(),
+            "{old_name} → {new_name} in {language}"
+        );
+    }
+}
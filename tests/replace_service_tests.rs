@@ -48,8 +48,44 @@ async fn test_replace_basic() {
     );
 
     let result = service.replace(param).await.unwrap();
-    assert_eq!(result.matches.len(), 2);
-    assert_eq!(result.new_code, "console.warn('Hello'); console.warn('World');");
+    assert_eq!(result.changes.len(), 2);
+    assert_eq!(
+        result.new_code,
+        "console.warn('Hello'); console.warn('World');"
+    );
+}
+
+#[tokio::test]
+async fn test_replace_changes_have_byte_ranges() {
+    let (service, temp_dir) = create_test_replace_service();
+    let code = "// héllo wörld\nconsole.log('ünïcode'); console.log(x);\n";
+    let param = ReplaceParam::new(code, "console.log($A)", "console.warn($A)", "javascript");
+    let anchored = ReplaceParam {
+        rewrite_selector: Some("$A".to_string()),
+        ..param.clone()
+    };
+
+    for param in [param, anchored] {
+        let result = service.replace(param).await.unwrap();
+        assert_eq!(result.changes.len(), 2);
+        for change in &result.changes {
+            assert_eq!(&code[change.start_byte..change.end_byte], change.old_text);
+        }
+    }
+
+    create_test_file(temp_dir.path(), "a.js", code);
+    let result = service
+        .file_replace(FileReplaceParam {
+            path_pattern: "a.js".to_string(),
+            pattern: "console.log($A)".to_string(),
+            replacement: "console.warn($A)".to_string(),
+            language: "javascript".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let change = &result.summary_results[0].sample_changes[1];
+    assert_eq!(&code[change.start_byte..change.end_byte], "console.log(x)");
 }
 
 #[tokio::test]
@@ -65,7 +101,7 @@ async fn test_replace_no_matches() {
     );
 
     let result = service.replace(param).await.unwrap();
-    assert_eq!(result.matches.len(), 0);
+    assert_eq!(result.changes.len(), 0);
     assert_eq!(result.new_code, code); // Should be unchanged
 }
 
@@ -76,13 +112,13 @@ async fn test_replace_with_multiple_variables() {
     
     let param = ReplaceParam::new(
         code,
-        "function $NAME($PARAMS) { $BODY }",
-        "const $NAME = ($PARAMS) => { $BODY }",
+        "function $NAME($$$PARAMS) { $$$BODY }",
+        "const $NAME = ($$$PARAMS) => { $$$BODY }",
         "javascript",
     );
 
     let result = service.replace(param).await.unwrap();
-    assert_eq!(result.matches.len(), 1);
+    assert_eq!(result.changes.len(), 1);
     assert!(result.new_code.contains("const test = (a, b) => {"));
 }
 
@@ -183,8 +219,28 @@ async fn test_file_replace_max_results_limit() {
         ..Default::default()
     };
 
-    let result = service.file_replace(param).await.unwrap();
+    let result = service.file_replace(param.clone()).await.unwrap();
     assert!(result.total_changes <= 3);
+    // Files are rewritten whole, so the page stops after the first file and resumes from it
+    assert_eq!(result.total_changes, 2);
+    assert_eq!(result.files_with_changes, 1);
+    let cursor = result.next_cursor.unwrap();
+    assert!(!cursor.is_complete);
+    assert!(cursor.last_file_path.ends_with("test1.js"));
+
+    let next = service
+        .file_replace(FileReplaceParam {
+            cursor: Some(CursorParam {
+                last_file_path: cursor.last_file_path,
+                is_complete: false,
+                token: None,
+            }),
+            ..param
+        })
+        .await
+        .unwrap();
+    assert_eq!(next.total_changes, 2);
+    assert!(next.summary_results[0].file_path.ends_with("test2.js"));
 }
 
 #[tokio::test]
@@ -201,10 +257,18 @@ line3
         pattern: "console.log($VAR)".to_string(),
         replacement: "console.warn($VAR)".to_string(),
         language: "javascript".to_string(),
+        strictness: None,
+        selector: None,
+        context: None,
+        verify_formatting: false,
+        buffer_id: None,
+        json_patch: false,
+        rewrite_selector: None,
+        format_after_replace: false,
     };
 
     let result = service.replace(param).await.unwrap();
-    assert_eq!(result.matches.len(), 1);
+    assert_eq!(result.changes.len(), 1);
     assert!(result.new_code.contains("console.warn('target');"));
 }
 
@@ -261,7 +325,151 @@ async fn test_replace_summary_only_mode() {
     assert_eq!(result.total_changes, 2);
     
     // In summary mode, detailed diffs should be empty or limited
-    if !result.files.is_empty() {
-        assert!(result.files[0].changes.len() <= result.files[0].change_count);
+    if !result.file_results.is_empty() {
+        assert!(result.file_results[0].changes.len() <= result.file_results[0].total_changes);
     }
-}
\ No newline at end of file
+}
+
+fn dry_run_hashes(result: &FileReplaceResult) -> std::collections::HashMap<String, String> {
+    result
+        .summary_results
+        .iter()
+        .map(|summary| (summary.file_path.clone(), summary.file_hash.clone()))
+        .collect()
+}
+
+fn var_to_let(dry_run: bool) -> FileReplaceParam {
+    FileReplaceParam {
+        path_pattern: "*.js".to_string(),
+        pattern: "var $A = $B;".to_string(),
+        replacement: "let $A = $B;".to_string(),
+        language: "javascript".to_string(),
+        dry_run,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_file_replace_rejects_drift_without_force() {
+    let (service, temp_dir) = create_test_replace_service();
+    create_test_file(temp_dir.path(), "app.js", "var a = 1;\n");
+
+    let preview = service.file_replace(var_to_let(true)).await.unwrap();
+    assert!(preview.summary_results[0].file_hash.starts_with("sha256:"));
+
+    create_test_file(temp_dir.path(), "app.js", "var a = 1;\nfoo();\n");
+    let param = FileReplaceParam {
+        expected_hashes: dry_run_hashes(&preview),
+        ..var_to_let(false)
+    };
+    let err = service.file_replace(param).await.unwrap_err();
+    assert!(err.to_string().contains("changed since the dry run"));
+
+    let content = fs::read_to_string(temp_dir.path().join("app.js")).unwrap();
+    assert_eq!(content, "var a = 1;\nfoo();\n");
+}
+
+#[tokio::test]
+async fn test_file_replace_force_merges_drifted_file() {
+    let (service, temp_dir) = create_test_replace_service();
+    create_test_file(
+        temp_dir.path(),
+        "app.js",
+        "var a = 1;\n\nfoo();\n\nvar b = 2;\n",
+    );
+    create_test_file(temp_dir.path(), "other.js", "var c = 3;\n");
+
+    let preview = service.file_replace(var_to_let(true)).await.unwrap();
+
+    // Edit a line the replacement doesn't touch
+    create_test_file(
+        temp_dir.path(),
+        "app.js",
+        "var a = 1;\n\nfoo(42);\n\nvar b = 2;\n",
+    );
+    let param = FileReplaceParam {
+        expected_hashes: dry_run_hashes(&preview),
+        force: true,
+        ..var_to_let(false)
+    };
+    let result = service.file_replace(param).await.unwrap();
+
+    assert_eq!(result.files_with_changes, 2);
+    assert_eq!(result.drifted_files.len(), 1);
+    assert!(!result.drifted_files[0].needs_resolution());
+    let content = fs::read_to_string(temp_dir.path().join("app.js")).unwrap();
+    assert_eq!(content, "let a = 1;\n\nfoo(42);\n\nlet b = 2;\n");
+    let content = fs::read_to_string(temp_dir.path().join("other.js")).unwrap();
+    assert_eq!(content, "let c = 3;\n");
+}
+
+#[tokio::test]
+async fn test_file_replace_force_marks_conflicts() {
+    let (service, temp_dir) = create_test_replace_service();
+    create_test_file(temp_dir.path(), "app.js", "foo();\nvar a = 1;\n");
+
+    let preview = service.file_replace(var_to_let(true)).await.unwrap();
+
+    create_test_file(temp_dir.path(), "app.js", "foo();\nvar a = 2;\n");
+    let param = FileReplaceParam {
+        expected_hashes: dry_run_hashes(&preview),
+        force: true,
+        ..var_to_let(false)
+    };
+    let result = service.file_replace(param).await.unwrap();
+
+    let drifted = &result.drifted_files[0];
+    assert_eq!(drifted.conflicts, 1);
+    assert!(drifted.written);
+    assert!(drifted.needs_resolution());
+    let content = fs::read_to_string(temp_dir.path().join("app.js")).unwrap();
+    assert_eq!(
+        content,
+        "foo();\n<<<<<<< current\nvar a = 2;\n||||||| dry run\nvar a = 1;\n=======\nlet a = 1;\n>>>>>>> replacement\n"
+    );
+}
+
+#[tokio::test]
+async fn test_file_replace_results_do_not_depend_on_cpu_concurrency() {
+    let temp_dir = TempDir::new().unwrap();
+    for index in 0..12 {
+        let content = match index % 3 {
+            0 => "foo();\n".to_string(),
+            _ => format!("var a{index} = {index};\nvar b{index} = 0;\n"),
+        };
+        create_test_file(temp_dir.path(), &format!("m{index:02}.js"), &content);
+    }
+    let summaries = |cpu_concurrency: usize| {
+        let config = ServiceConfig {
+            root_directories: vec![temp_dir.path().to_path_buf()],
+            cpu_concurrency,
+            ..Default::default()
+        };
+        let service = ReplaceService::new(config, PatternMatcher::new(), RuleEvaluator::new());
+        async move {
+            let result = service
+                .file_replace(FileReplaceParam {
+                    max_results: 10,
+                    ..var_to_let(true)
+                })
+                .await
+                .unwrap();
+            let files: Vec<(String, usize)> = result
+                .summary_results
+                .iter()
+                .map(|file| (file.file_path.clone(), file.total_changes))
+                .collect();
+            (
+                files,
+                result.next_cursor.map(|cursor| cursor.last_file_path),
+            )
+        }
+    };
+
+    let (sequential, sequential_cursor) = summaries(1).await;
+    let (parallel, parallel_cursor) = summaries(4).await;
+    assert_eq!(sequential.len(), 5);
+    assert!(sequential.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert_eq!(parallel, sequential);
+    assert_eq!(parallel_cursor, sequential_cursor);
+}
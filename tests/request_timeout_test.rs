@@ -0,0 +1,149 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::RuleReplaceParam;
+use ast_grep_mcp::types::{CursorParam, FileReplaceParam, FileSearchParam};
+use std::fs;
+use tempfile::TempDir;
+use tokio_util::sync::CancellationToken;
+
+const RULE: &str = "id: log-to-debug\nlanguage: javascript\nrule:\n  pattern: console.log($A)\nfix: console.debug($A)\n";
+
+fn setup() -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    for name in ["a.js", "b.js", "c.js"] {
+        fs::write(temp_dir.path().join(name), "console.log(1);\n").unwrap();
+    }
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn search(timeout_ms: Option<u64>, cursor: Option<CursorParam>) -> FileSearchParam {
+    FileSearchParam {
+        path_pattern: "**/*.js".to_string(),
+        pattern: "console.log($A)".to_string(),
+        language: "javascript".to_string(),
+        timeout_ms,
+        cursor,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_file_search_timeout_returns_a_resumable_partial_result() {
+    let (service, _temp_dir) = setup();
+
+    let result = service.file_search(search(Some(0), None)).await.unwrap();
+    assert!(result.timed_out);
+    assert!(result.matches.is_empty());
+    let cursor = result.next_cursor.unwrap();
+    assert!(!cursor.is_complete);
+
+    // The cursor resumes without a timeout, which may change between pages
+    let resumed = CursorParam {
+        last_file_path: cursor.last_file_path,
+        is_complete: false,
+        token: cursor.token,
+    };
+    let result = service
+        .file_search(search(None, Some(resumed)))
+        .await
+        .unwrap();
+    assert!(!result.timed_out);
+    assert_eq!(result.matches.len(), 3);
+
+    let result = service
+        .file_search(search(Some(60_000), None))
+        .await
+        .unwrap();
+    assert!(!result.timed_out);
+    assert_eq!(result.matches.len(), 3);
+}
+
+#[tokio::test]
+async fn test_cancelled_request_stops_searches_and_replacements() {
+    let (service, temp_dir) = setup();
+    let token = CancellationToken::new();
+    let service = service.with_cancellation(token.clone());
+
+    assert!(
+        !service
+            .file_search(search(None, None))
+            .await
+            .unwrap()
+            .timed_out
+    );
+    token.cancel();
+    let result = service.file_search(search(None, None)).await.unwrap();
+    assert!(result.timed_out);
+    assert!(result.matches.is_empty());
+
+    let result = service
+        .file_replace(FileReplaceParam {
+            path_pattern: "**/*.js".to_string(),
+            pattern: "console.log($A)".to_string(),
+            replacement: "console.debug($A)".to_string(),
+            language: "javascript".to_string(),
+            dry_run: false,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(result.timed_out);
+    assert_eq!(result.files_with_changes, 0);
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("a.js")).unwrap(),
+        "console.log(1);\n"
+    );
+}
+
+#[tokio::test]
+async fn test_rule_replace_timeout_writes_nothing_past_it() {
+    let (service, temp_dir) = setup();
+    let param = RuleReplaceParam {
+        rule_config: RULE.to_string(),
+        path_pattern: Some("*.js".to_string()),
+        max_results: 100,
+        max_file_size: 1024 * 1024,
+        dry_run: false,
+        summary_only: false,
+        cursor: None,
+        allow_unsafe: false,
+        workspace: None,
+        ignore_whitespace_changes: true,
+        timeout_ms: Some(0),
+        walk: Default::default(),
+    };
+
+    // Like rule_search, the first file is always searched, and only the files reported change
+    let result = service.rule_replace(param.clone()).await.unwrap();
+    assert!(result.timed_out);
+    assert_eq!(result.total_changes, 1);
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("a.js")).unwrap(),
+        "console.debug(1);\n"
+    );
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("b.js")).unwrap(),
+        "console.log(1);\n"
+    );
+
+    let cursor = result.next_cursor.unwrap();
+    assert!(cursor.last_file_path.ends_with("a.js"));
+    let result = service
+        .rule_replace(RuleReplaceParam {
+            timeout_ms: None,
+            cursor: Some(CursorParam {
+                last_file_path: cursor.last_file_path,
+                is_complete: false,
+                token: cursor.token,
+            }),
+            ..param
+        })
+        .await
+        .unwrap();
+    assert!(!result.timed_out);
+    assert_eq!(result.total_changes, 2);
+}
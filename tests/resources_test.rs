@@ -0,0 +1,115 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::CreateRuleParam;
+use rmcp::ServerHandler;
+use rmcp::model::{CallToolRequestParam, ReadResourceResult, ResourceContents};
+use serde_json::{Value, json};
+use std::fs;
+use tempfile::TempDir;
+
+const RULE: &str = "id: no-console\nlanguage: javascript\nmessage: Avoid console.log\nrule:\n  pattern: console.log($A)\n";
+
+async fn setup() -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("app.js"), "console.log(1);\n").unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..Default::default()
+    });
+    service
+        .create_rule(CreateRuleParam {
+            rule_config: RULE.to_string(),
+            overwrite: false,
+        })
+        .await
+        .unwrap();
+    (service, temp_dir)
+}
+
+fn text(result: &ReadResourceResult) -> (&str, &str) {
+    match &result.contents[..] {
+        [
+            ResourceContents::TextResourceContents {
+                mime_type, text, ..
+            },
+        ] => (mime_type.as_deref().unwrap(), text),
+        other => panic!("expected one text resource, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_rules_and_documentation_are_listed_and_readable() {
+    let (service, _temp_dir) = setup().await;
+    assert!(service.get_info().capabilities.resources.is_some());
+
+    let listed = service.list_resources().await.unwrap();
+    let uris: Vec<&str> = listed
+        .iter()
+        .map(|resource| resource.uri.as_str())
+        .collect();
+    assert_eq!(uris, ["rules://no-console", "docs://tool-usage-guide"]);
+    assert_eq!(listed[0].description.as_deref(), Some("Avoid console.log"));
+
+    let rule = service.read_resource("rules://no-console").await.unwrap();
+    let (mime_type, yaml) = text(&rule);
+    assert_eq!(mime_type, "application/yaml");
+    assert!(yaml.contains("id: no-console"));
+    assert!(yaml.contains("console.log($A)"));
+
+    let guide = service
+        .read_resource("docs://tool-usage-guide")
+        .await
+        .unwrap();
+    assert!(
+        text(&guide)
+            .1
+            .starts_with("# AST-Grep MCP Tool Usage Guide")
+    );
+
+    assert!(service.read_resource("rules://missing").await.is_err());
+    assert!(service.read_resource("file:///etc/passwd").await.is_err());
+}
+
+#[tokio::test]
+async fn test_latest_result_follows_search_and_replace_calls() {
+    let (service, _temp_dir) = setup().await;
+    assert!(service.read_resource("results://latest").await.is_err());
+
+    service
+        .handle_tool_call(CallToolRequestParam {
+            name: "file_search".into(),
+            arguments: json!({
+                "path_pattern": "*.js",
+                "pattern": "console.log($A)",
+                "language": "javascript"
+            })
+            .as_object()
+            .cloned(),
+        })
+        .await
+        .unwrap();
+    // Other tools leave it alone
+    service
+        .handle_tool_call(CallToolRequestParam {
+            name: "list_languages".into(),
+            arguments: None,
+        })
+        .await
+        .unwrap();
+
+    let listed = service.list_resources().await.unwrap();
+    let latest = listed.last().unwrap();
+    assert_eq!(latest.uri, "results://latest");
+    assert_eq!(
+        latest.description.as_deref(),
+        Some("Result of the most recent file_search call")
+    );
+    let result = service.read_resource("results://latest").await.unwrap();
+    let json: Value = serde_json::from_str(text(&result).1).unwrap();
+    assert_eq!(json["matches"][0]["matches"][0]["text"], "console.log(1)");
+
+    // Each session keeps its own
+    let other = service.for_session("other");
+    assert!(other.read_resource("results://latest").await.is_err());
+}
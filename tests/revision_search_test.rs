@@ -0,0 +1,147 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::FileSearchParam;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success(), "git {args:?}");
+}
+
+/// A repository tagged v1 while `legacy.js` still called `oldApi`, with the working tree
+/// moved on since
+fn setup() -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    git(dir, &["init", "-q"]);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src/app.js"), "oldApi(1);\nnewApi(2);\n").unwrap();
+    fs::write(dir.join("src/legacy.js"), "oldApi(3);\n").unwrap();
+    git(dir, &["add", "-A"]);
+    git(dir, &["commit", "-q", "-m", "v1"]);
+    git(dir, &["tag", "v1"]);
+
+    fs::write(dir.join("src/app.js"), "newApi(1);\nnewApi(2);\n").unwrap();
+    git(dir, &["rm", "-q", "src/legacy.js"]);
+    fs::write(dir.join("src/added.js"), "oldApi(4);\n").unwrap();
+    git(dir, &["add", "-A"]);
+    git(dir, &["commit", "-q", "-m", "v2"]);
+    fs::write(dir.join("src/app.js"), "oldApi(5);\n").unwrap();
+
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![dir.to_path_buf()],
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn old_api_search(revision: Option<&str>) -> FileSearchParam {
+    FileSearchParam {
+        path_pattern: "src/**/*.js".to_string(),
+        pattern: "oldApi($A)".to_string(),
+        language: "javascript".to_string(),
+        revision: revision.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_file_search_reads_files_at_revision() {
+    let (service, temp_dir) = setup();
+
+    let result = service
+        .file_search(old_api_search(Some("v1")))
+        .await
+        .unwrap();
+    let found: Vec<(String, String)> = result
+        .matches
+        .iter()
+        .flat_map(|file| {
+            let name = file.file_path.rsplit('/').next().unwrap().to_string();
+            file.matches
+                .iter()
+                .map(move |m| (name.clone(), m.vars["A"].clone()))
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("app.js".to_string(), "1".to_string()),
+            ("legacy.js".to_string(), "3".to_string()),
+        ]
+    );
+    // Files are reported where they sit in the working tree
+    assert!(
+        result.matches[0]
+            .file_path
+            .starts_with(temp_dir.path().to_string_lossy().as_ref())
+    );
+    // Nothing was checked out
+    assert!(!temp_dir.path().join("src/legacy.js").exists());
+
+    let result = service
+        .file_search(old_api_search(Some("HEAD")))
+        .await
+        .unwrap();
+    assert_eq!(result.matches.len(), 1);
+    assert!(result.matches[0].file_path.ends_with("added.js"));
+
+    // Without a revision the uncommitted working tree is searched
+    let result = service.file_search(old_api_search(None)).await.unwrap();
+    assert_eq!(result.matches.len(), 2);
+}
+
+#[tokio::test]
+async fn test_file_search_at_revision_paginates() {
+    let (service, _temp_dir) = setup();
+    let param = FileSearchParam {
+        max_results: 1,
+        ..old_api_search(Some("v1"))
+    };
+
+    let first = service.file_search(param.clone()).await.unwrap();
+    assert!(first.matches[0].file_path.ends_with("app.js"));
+    let cursor = first.next_cursor.unwrap();
+    assert!(!cursor.is_complete);
+
+    let second = service
+        .file_search(FileSearchParam {
+            cursor: Some(ast_grep_mcp::types::CursorParam {
+                last_file_path: cursor.last_file_path,
+                is_complete: false,
+                token: None,
+            }),
+            ..param
+        })
+        .await
+        .unwrap();
+    assert!(second.matches[0].file_path.ends_with("legacy.js"));
+}
+
+#[tokio::test]
+async fn test_file_search_rejects_bad_revisions() {
+    let (service, _temp_dir) = setup();
+
+    for revision in ["no-such-tag", "--output=x", ""] {
+        let error = service
+            .file_search(old_api_search(Some(revision)))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("revision") || error.to_string().contains("git"));
+    }
+
+    let with_archives = FileSearchParam {
+        search_archives: true,
+        ..old_api_search(Some("v1"))
+    };
+    assert!(service.file_search(with_archives).await.is_err());
+}
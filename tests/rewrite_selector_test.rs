@@ -0,0 +1,91 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::{FileReplaceParam, ReplaceParam};
+use std::fs;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_rewrite_selector_replaces_only_the_captured_node() {
+    let service = AstGrepService::new();
+    let code = "@cache(ttl=60)\ndef load_user(id):\n    return db.get(id)\n\n@cache(ttl=60)\nclass Config:\n    pass\n";
+
+    let result = service
+        .replace(ReplaceParam {
+            rewrite_selector: Some("$DEC".to_string()),
+            ..ReplaceParam::new(
+                code,
+                "@$DEC\ndef $NAME($$$PARAMS):\n    $$$BODY",
+                "lru_cache(maxsize=None)  # was $DEC on $NAME",
+                "python",
+            )
+        })
+        .await
+        .unwrap();
+
+    // Only the decorator of the function changes; the class keeps its decorator
+    assert_eq!(
+        result.new_code,
+        "@lru_cache(maxsize=None)  # was cache(ttl=60) on load_user\ndef load_user(id):\n    return db.get(id)\n\n@cache(ttl=60)\nclass Config:\n    pass\n"
+    );
+    assert_eq!(result.changes.len(), 1);
+    assert_eq!(result.changes[0].old_text, "cache(ttl=60)");
+    assert_eq!(result.changes[0].start_line, 0);
+}
+
+#[tokio::test]
+async fn test_rewrite_selector_multi_capture_in_files() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("api.js"),
+        "fetchJson(url, { retries: 3 }, onDone);\nother(url, a, b);\n",
+    )
+    .unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+
+    let result = service
+        .file_replace(FileReplaceParam {
+            path_pattern: "**/*.js".to_string(),
+            pattern: "fetchJson($URL, $$$REST)".to_string(),
+            replacement: "{ url: $URL }".to_string(),
+            language: "javascript".to_string(),
+            rewrite_selector: Some("$$$REST".to_string()),
+            dry_run: false,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.total_changes, 1);
+    let sample = &result.summary_results[0].sample_changes[0];
+    assert_eq!(sample.old_text, "{ retries: 3 }, onDone");
+    assert_eq!(sample.new_text, "{ url: url }");
+    let content = fs::read_to_string(temp_dir.path().join("api.js")).unwrap();
+    assert_eq!(
+        content,
+        "fetchJson(url, { url: url });\nother(url, a, b);\n"
+    );
+}
+
+#[tokio::test]
+async fn test_rewrite_selector_must_be_a_pattern_metavariable() {
+    let service = AstGrepService::new();
+
+    for rewrite_selector in ["$MISSING", "ARGS", "$"] {
+        let result = service
+            .replace(ReplaceParam {
+                rewrite_selector: Some(rewrite_selector.to_string()),
+                ..ReplaceParam::new("foo(1);", "foo($ARG)", "2", "javascript")
+            })
+            .await;
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("must be a metavariable captured by the pattern"),
+            "{rewrite_selector} was accepted"
+        );
+    }
+}
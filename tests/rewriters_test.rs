@@ -0,0 +1,178 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::{RuleReplaceParam, RuleValidateParam, parse_rule_config};
+use std::fs;
+use tempfile::TempDir;
+
+/// Turns `'' + x` string coercions inside array literals into `String(x)` calls
+const LIST_RULE: &str = r#"
+id: stringify-items
+language: javascript
+rule:
+  pattern: "[$$$ITEMS]"
+rewriters:
+  - id: concat-to-string
+    rule:
+      pattern: "'' + $A"
+    fix: String($A)
+transform:
+  STRINGS:
+    rewrite:
+      source: $$$ITEMS
+      rewriters: [concat-to-string]
+      joinBy: ", "
+fix: "[$STRINGS]"
+"#;
+
+fn setup(code: &str) -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("app.js"), code).unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        undo_directory: temp_dir.path().join(".undo"),
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn replace_param(rule_config: &str, dry_run: bool) -> RuleReplaceParam {
+    RuleReplaceParam {
+        rule_config: rule_config.to_string(),
+        path_pattern: Some("*.js".to_string()),
+        max_results: 100,
+        max_file_size: 1024 * 1024,
+        dry_run,
+        summary_only: false,
+        cursor: None,
+        allow_unsafe: false,
+        workspace: None,
+        ignore_whitespace_changes: true,
+        timeout_ms: None,
+        walk: Default::default(),
+    }
+}
+
+async fn rule_replace(service: &AstGrepService, rule_config: &str) {
+    service
+        .rule_replace(replace_param(rule_config, false))
+        .await
+        .unwrap();
+}
+
+#[test]
+fn test_rewriters_and_transforms_parse() {
+    let rule = parse_rule_config(LIST_RULE).unwrap();
+    assert_eq!(rule.rewriters.len(), 1);
+    assert_eq!(rule.rewriters[0].fix, "String($A)");
+    let rewrite = rule.transform["STRINGS"].rewrite.as_ref().unwrap();
+    assert_eq!(rewrite.source, "$$$ITEMS");
+    assert_eq!(rewrite.rewriters, ["concat-to-string"]);
+    assert_eq!(rewrite.join_by.as_deref(), Some(", "));
+}
+
+#[tokio::test]
+async fn test_rewriters_apply_within_matched_lists() {
+    let (service, temp_dir) = setup("const ids = ['' + a, b, '' + c.id];\n");
+    rule_replace(&service, LIST_RULE).await;
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("app.js")).unwrap(),
+        "const ids = [String(a), b, String(c.id)];\n"
+    );
+}
+
+#[tokio::test]
+async fn test_without_join_by_the_separators_are_kept() {
+    let (service, temp_dir) = setup("f(['' + a,\n  '' + b]);\n");
+    rule_replace(&service, &LIST_RULE.replace("      joinBy: \", \"\n", "")).await;
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("app.js")).unwrap(),
+        "f([String(a),\n  String(b)]);\n"
+    );
+}
+
+#[tokio::test]
+async fn test_rewriters_rewrite_nested_nodes_and_use_transforms() {
+    // The arguments of each call are rewritten by a second rewriter, limited to identifiers
+    let rule = r#"
+id: log-to-logger
+language: javascript
+rule:
+  pattern: "function $NAME() { $$$BODY }"
+rewriters:
+  - id: log-call
+    rule:
+      pattern: console.log($$$ARGS)
+    transform:
+      STRINGS:
+        rewrite:
+          source: $$$ARGS
+          rewriters: [to-string]
+          joinBy: ", "
+    fix: logger.info($STRINGS)
+  - id: to-string
+    rule:
+      pattern: $VALUE
+    constraints:
+      VALUE:
+        kind: identifier
+    fix: String($VALUE)
+transform:
+  NEW_BODY:
+    rewrite:
+      source: $$$BODY
+      rewriters: [log-call]
+fix: "function $NAME() { $NEW_BODY }"
+"#;
+    let (service, temp_dir) = setup(
+        "function run() { console.log(a, 'b'); if (x) { console.log(c); } }\nconsole.log(outside);\n",
+    );
+    rule_replace(&service, rule).await;
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("app.js")).unwrap(),
+        "function run() { logger.info(String(a), 'b'); if (x) { logger.info(String(c)); } }\nconsole.log(outside);\n"
+    );
+}
+
+#[tokio::test]
+async fn test_unknown_rewriters_are_rejected() {
+    let rule = LIST_RULE.replace("rewriters: [concat-to-string]", "rewriters: [missing]");
+    let (service, _temp_dir) = setup("const ids = ['' + a];\n");
+    let error = service
+        .rule_replace(replace_param(&rule, true))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("rewriter 'missing'"), "{error}");
+
+    let result = service
+        .validate_rule(RuleValidateParam {
+            rule_config: LIST_RULE.replace("source: $$$ITEMS", "source: ITEMS"),
+            test_code: None,
+        })
+        .await
+        .unwrap();
+    assert!(!result.valid);
+    assert!(
+        result
+            .errors
+            .iter()
+            .any(|e| e.contains("must be a metavariable")),
+        "{:?}",
+        result.errors
+    );
+}
+
+#[tokio::test]
+async fn test_validate_rule_previews_rewrites() {
+    let result = AstGrepService::new()
+        .validate_rule(RuleValidateParam {
+            rule_config: LIST_RULE.to_string(),
+            test_code: Some("let x = ['' + a, '' + b];\n".to_string()),
+        })
+        .await
+        .unwrap();
+    assert!(result.valid, "{:?}", result.errors);
+    assert_eq!(
+        result.test_results.unwrap().fixed_code.as_deref(),
+        Some("let x = [String(a), String(b)];\n")
+    );
+}
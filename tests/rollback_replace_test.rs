@@ -0,0 +1,141 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::{FileReplaceParam, ListAppliedChangesParam, RollbackReplaceParam};
+use std::fs;
+use tempfile::TempDir;
+
+fn create_service(temp_dir: &TempDir) -> AstGrepService {
+    AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().join("src")],
+        undo_directory: temp_dir.path().join("undo"),
+        ..Default::default()
+    })
+}
+
+fn replace(dry_run: bool) -> FileReplaceParam {
+    FileReplaceParam {
+        path_pattern: "*.js".to_string(),
+        pattern: "var $A = $B".to_string(),
+        replacement: "let $A = $B".to_string(),
+        language: "javascript".to_string(),
+        dry_run,
+        ..Default::default()
+    }
+}
+
+fn rollback(apply_id: &str, force: bool) -> RollbackReplaceParam {
+    RollbackReplaceParam {
+        apply_id: apply_id.to_string(),
+        force,
+    }
+}
+
+#[tokio::test]
+async fn test_file_replace_records_and_rolls_back() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+    let app = temp_dir.path().join("src/app.js");
+    let lib = temp_dir.path().join("src/lib.js");
+    fs::write(&app, "var a = 1;\n").unwrap();
+    fs::write(&lib, "var b = 2;\nvar c = 3;\n").unwrap();
+    let service = create_service(&temp_dir);
+
+    let preview = service.file_replace(replace(true)).await.unwrap();
+    assert!(preview.apply_id.is_none());
+
+    let applied = service.file_replace(replace(false)).await.unwrap();
+    let apply_id = applied.apply_id.unwrap();
+    assert_eq!(fs::read_to_string(&app).unwrap(), "let a = 1;\n");
+
+    let listed = service
+        .list_applied_changes(ListAppliedChangesParam::default())
+        .await
+        .unwrap();
+    assert_eq!(listed.total, 1);
+    let change = &listed.changes[0];
+    assert_eq!(change.apply_id, apply_id);
+    assert_eq!(change.pattern, "var $A = $B");
+    assert_eq!(change.files.len(), 2);
+    assert!(!change.rolled_back);
+
+    let result = service
+        .rollback_replace(rollback(&apply_id, false))
+        .await
+        .unwrap();
+    assert_eq!(result.restored_files.len(), 2);
+    assert!(result.overwritten_files.is_empty());
+    assert_eq!(fs::read_to_string(&app).unwrap(), "var a = 1;\n");
+    assert_eq!(
+        fs::read_to_string(&lib).unwrap(),
+        "var b = 2;\nvar c = 3;\n"
+    );
+
+    assert!(
+        service
+            .rollback_replace(rollback(&apply_id, false))
+            .await
+            .is_err()
+    );
+    let listed = service
+        .list_applied_changes(ListAppliedChangesParam::default())
+        .await
+        .unwrap();
+    assert_eq!(listed.total, 0);
+    let listed = service
+        .list_applied_changes(ListAppliedChangesParam {
+            include_rolled_back: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(listed.changes[0].rolled_back);
+}
+
+#[tokio::test]
+async fn test_rollback_refuses_files_edited_since() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+    let app = temp_dir.path().join("src/app.js");
+    let lib = temp_dir.path().join("src/lib.js");
+    fs::write(&app, "var a = 1;\n").unwrap();
+    fs::write(&lib, "var b = 2;\n").unwrap();
+    let service = create_service(&temp_dir);
+
+    let apply_id = service
+        .file_replace(replace(false))
+        .await
+        .unwrap()
+        .apply_id
+        .unwrap();
+    fs::write(&app, "let a = 1;\nlet extra = 4;\n").unwrap();
+
+    assert!(
+        service
+            .rollback_replace(rollback(&apply_id, false))
+            .await
+            .is_err()
+    );
+    assert_eq!(fs::read_to_string(&lib).unwrap(), "let b = 2;\n");
+
+    let result = service
+        .rollback_replace(rollback(&apply_id, true))
+        .await
+        .unwrap();
+    assert_eq!(result.overwritten_files.len(), 1);
+    assert!(result.overwritten_files[0].ends_with("app.js"));
+    assert_eq!(fs::read_to_string(&app).unwrap(), "var a = 1;\n");
+    assert_eq!(fs::read_to_string(&lib).unwrap(), "var b = 2;\n");
+
+    assert!(
+        service
+            .rollback_replace(rollback("../app", false))
+            .await
+            .is_err()
+    );
+    assert!(
+        service
+            .rollback_replace(rollback("42-0", false))
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,148 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::RuleSearchParam;
+use std::fs;
+use std::time::Duration;
+use tempfile::TempDir;
+
+const RULE: &str = "id: find-log\nlanguage: javascript\nrule:\n  pattern: console.log($A)\n";
+
+fn setup() -> (AstGrepService, TempDir) {
+    setup_with(ServiceConfig::default())
+}
+
+fn setup_with(config: ServiceConfig) -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    for name in ["a.js", "b.js", "c.js"] {
+        fs::write(
+            temp_dir.path().join(name),
+            "console.log(1);\nconsole.log(2);\nconsole.log(3);\n",
+        )
+        .unwrap();
+    }
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..config
+    });
+    (service, temp_dir)
+}
+
+fn search_param() -> RuleSearchParam {
+    RuleSearchParam {
+        rule_config: RULE.to_string(),
+        path_pattern: Some("*.js".to_string()),
+        max_results: 100,
+        max_file_size: 1024 * 1024,
+        cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
+    }
+}
+
+#[tokio::test]
+async fn test_rule_search_within_budget() {
+    let (service, _temp_dir) = setup();
+
+    let result = service.rule_search(search_param()).await.unwrap();
+    assert!(!result.budget_exceeded);
+    assert_eq!(result.matches.len(), 3);
+}
+
+#[tokio::test]
+async fn test_max_matches_truncates_and_resumes() {
+    let (service, _temp_dir) = setup();
+
+    let result = service
+        .rule_search(RuleSearchParam {
+            max_matches: Some(4),
+            ..search_param()
+        })
+        .await
+        .unwrap();
+    assert!(result.budget_exceeded);
+    assert_eq!(result.matches.len(), 2);
+    assert_eq!(result.matches[0].matches.len(), 3);
+    assert_eq!(result.matches[1].matches.len(), 1);
+
+    // The cursor picks up after the file the limit was reached in
+    let cursor = result.next_cursor.unwrap();
+    assert!(!cursor.is_complete);
+    assert!(cursor.last_file_path.ends_with("b.js"));
+    let result = service
+        .rule_search(RuleSearchParam {
+            cursor: Some(ast_grep_mcp::types::CursorParam {
+                last_file_path: cursor.last_file_path,
+                is_complete: false,
+                token: None,
+            }),
+            ..search_param()
+        })
+        .await
+        .unwrap();
+    assert!(!result.budget_exceeded);
+    assert_eq!(result.matches.len(), 1);
+    assert!(result.matches[0].file_path.ends_with("c.js"));
+}
+
+#[tokio::test]
+async fn test_exhausted_time_budget_stops_the_scan() {
+    let (service, _temp_dir) = setup();
+
+    let result = service
+        .rule_search(RuleSearchParam {
+            timeout_ms: Some(0),
+            ..search_param()
+        })
+        .await
+        .unwrap();
+    assert!(result.budget_exceeded);
+    assert!(result.timed_out);
+    // The first file is always searched, so resuming with the same budget gets further
+    assert_eq!(result.matches.len(), 1);
+    assert_eq!(result.matches[0].matches.len(), 3);
+    let cursor = result.next_cursor.unwrap();
+    assert!(!cursor.is_complete);
+    assert!(cursor.last_file_path.ends_with("a.js"));
+}
+
+#[tokio::test]
+async fn test_files_the_budget_ran_out_in_are_searched_again() {
+    // No time at all: only the first file of each page is searched
+    let (service, _temp_dir) = setup_with(ServiceConfig {
+        rule_timeout: Duration::ZERO,
+        ..Default::default()
+    });
+
+    let mut cursor = None;
+    let mut searched = Vec::new();
+    loop {
+        let result = service
+            .rule_search(RuleSearchParam {
+                cursor,
+                ..search_param()
+            })
+            .await
+            .unwrap();
+        for file in result.matches {
+            assert_eq!(file.matches.len(), 3);
+            searched.push(file.file_path);
+        }
+        let next = result.next_cursor.unwrap();
+        if next.is_complete {
+            break;
+        }
+        cursor = Some(ast_grep_mcp::types::CursorParam {
+            last_file_path: next.last_file_path,
+            is_complete: false,
+            token: next.token,
+        });
+    }
+    assert_eq!(searched.len(), 3);
+    assert!(searched[1].ends_with("b.js"));
+}
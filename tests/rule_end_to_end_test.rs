@@ -56,6 +56,13 @@ class Logger {
         max_results: 100,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result = service.rule_search(param).await.unwrap();
@@ -73,7 +80,6 @@ class Logger {
 }
 
 #[tokio::test]
-#[ignore = "TODO: Implement file replacement logic"]
 async fn test_console_to_logger_rule_replace() {
     let (service, temp_dir) = create_test_service_with_examples();
 
@@ -97,6 +103,11 @@ function debug() {
         dry_run: false, // Actually perform the replacement
         summary_only: false,
         cursor: None,
+        allow_unsafe: false,
+        workspace: None,
+        ignore_whitespace_changes: true,
+        timeout_ms: None,
+        walk: Default::default(),
     };
 
     let result = service.rule_replace(param).await.unwrap();
@@ -117,7 +128,6 @@ function debug() {
 }
 
 #[tokio::test]
-#[ignore = "TODO: Implement file replacement logic"]
 async fn test_var_to_let_rule() {
     let (service, temp_dir) = create_test_service_with_examples();
 
@@ -143,6 +153,13 @@ function oldCode() {
         max_results: 100,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let search_result = service.rule_search(search_param).await.unwrap();
@@ -160,6 +177,11 @@ function oldCode() {
         dry_run: true, // Just preview
         summary_only: false,
         cursor: None,
+        allow_unsafe: false,
+        workspace: None,
+        ignore_whitespace_changes: true,
+        timeout_ms: None,
+        walk: Default::default(),
     };
 
     let replace_result = service.rule_replace(replace_param).await.unwrap();
@@ -197,6 +219,13 @@ function safeCalls(obj: any) {
         max_results: 100,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result = service.rule_search(param).await.unwrap();
@@ -241,6 +270,13 @@ function complexLogic(a, b, c, d) {
         max_results: 100,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result = service.rule_search(param).await.unwrap();
@@ -280,6 +316,13 @@ const goodArrow = (data: string) => data;
         max_results: 100,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result = service.rule_search(param).await.unwrap();
@@ -337,6 +380,13 @@ function standalone() {
         max_results: 100,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result = service.rule_search(param).await.unwrap();
@@ -369,6 +419,13 @@ async fn test_multiple_languages() {
             max_results: 100,
             max_file_size: 1024 * 1024,
             cursor: None,
+            output_file: None,
+            workspace: None,
+            timeout_ms: None,
+            max_matches: None,
+            walk: Default::default(),
+            output_format: Default::default(),
+            pack: None,
         })
         .await
         .unwrap();
@@ -382,6 +439,13 @@ async fn test_multiple_languages() {
             max_results: 100,
             max_file_size: 1024 * 1024,
             cursor: None,
+            output_file: None,
+            workspace: None,
+            timeout_ms: None,
+            max_matches: None,
+            walk: Default::default(),
+            output_format: Default::default(),
+            pack: None,
         })
         .await
         .unwrap();
@@ -395,6 +459,13 @@ async fn test_multiple_languages() {
             max_results: 100,
             max_file_size: 1024 * 1024,
             cursor: None,
+            output_file: None,
+            workspace: None,
+            timeout_ms: None,
+            max_matches: None,
+            walk: Default::default(),
+            output_format: Default::default(),
+            pack: None,
         })
         .await
         .unwrap();
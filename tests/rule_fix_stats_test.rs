@@ -0,0 +1,95 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+use ast_grep_mcp::rules::RuleReplaceParam;
+use std::fs;
+use tempfile::TempDir;
+
+fn setup(files: &[(&str, &str)]) -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    for (name, content) in files {
+        fs::write(temp_dir.path().join(name), content).unwrap();
+    }
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn replace_param(rule_config: &str) -> RuleReplaceParam {
+    RuleReplaceParam {
+        rule_config: rule_config.to_string(),
+        path_pattern: Some("*.js".to_string()),
+        max_results: 100,
+        max_file_size: 1024 * 1024,
+        dry_run: true,
+        summary_only: false,
+        cursor: None,
+        allow_unsafe: false,
+        workspace: None,
+        ignore_whitespace_changes: true,
+        timeout_ms: None,
+        walk: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_changes_are_broken_down_by_file() {
+    let (service, temp_dir) = setup(&[
+        ("a.js", "wrap(wrap(1));\n"),
+        ("b.js", "wrap(2);\nwrap(3);\n"),
+        ("c.js", "other(4);\n"),
+    ]);
+
+    let result = service
+        .rule_replace(replace_param(
+            "id: unwrap\nlanguage: javascript\nrule:\n  pattern: wrap($A)\nfix: $A\n",
+        ))
+        .await
+        .unwrap();
+
+    let [stats] = result.rule_stats.as_slice() else {
+        panic!("expected the stats of one rule");
+    };
+    assert_eq!(stats.rule_id, "unwrap");
+    assert_eq!(stats.matches, 4);
+    assert_eq!(stats.total_changes, result.total_changes);
+    let per_file: Vec<(String, usize)> = stats
+        .files
+        .iter()
+        .map(|(path, changes)| {
+            let relative = path
+                .strip_prefix(temp_dir.path().to_str().unwrap())
+                .unwrap();
+            (relative.trim_start_matches('/').to_string(), *changes)
+        })
+        .collect();
+    assert_eq!(
+        per_file,
+        vec![("a.js".to_string(), 1), ("b.js".to_string(), 2)]
+    );
+    // The inner call is inside the outer one, which the fix already rewrote
+    assert_eq!(stats.unfixed_matches, 1);
+
+    let summary = ResponseFormatter::format_file_replace_result(&result);
+    assert!(summary.contains("1 of 4 matches of `unwrap` were left as they were"));
+}
+
+#[tokio::test]
+async fn test_held_back_unsafe_fixes_are_unfixed() {
+    let (service, _temp_dir) = setup(&[("a.js", "console.log(1);\n")]);
+
+    let result = service
+        .rule_replace(replace_param(
+            "id: drop-log\nlanguage: javascript\nrule:\n  pattern: console.log($A)\nfix: ''\nfix_safety: unsafe\n",
+        ))
+        .await
+        .unwrap();
+
+    let stats = &result.rule_stats[0];
+    assert_eq!((stats.matches, stats.total_changes), (1, 0));
+    assert_eq!(stats.unfixed_matches, 1);
+    assert!(stats.files.is_empty());
+}
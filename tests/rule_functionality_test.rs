@@ -111,6 +111,13 @@ rule:
         max_results: 10000,
         max_file_size: 50 * 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result = service.rule_search(param).await.unwrap();
@@ -155,6 +162,13 @@ rule:
         max_results: 10000,
         max_file_size: 50 * 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result = service.rule_search(param).await.unwrap();
@@ -165,7 +179,6 @@ rule:
 }
 
 #[tokio::test]
-#[ignore = "TODO: Implement file replacement logic"]
 async fn test_rule_replace_basic() {
     // Types are already imported at the top of the file
 
@@ -199,6 +212,11 @@ fix: "console.debug($ARG)"
         dry_run: true, // Dry run for testing
         summary_only: false,
         cursor: None,
+        allow_unsafe: false,
+        workspace: None,
+        ignore_whitespace_changes: true,
+        timeout_ms: None,
+        walk: Default::default(),
     };
 
     let result = service.rule_replace(param).await.unwrap();
@@ -249,6 +267,7 @@ fix: "console.debug($ARG)"
     let list_param = ListRulesParam {
         language: None,
         severity: None,
+        tag: None,
     };
 
     let list_result = service.list_rules(list_param).await.unwrap();
@@ -280,6 +299,7 @@ fix: "console.debug($ARG)"
         .list_rules(ListRulesParam {
             language: None,
             severity: None,
+            tag: None,
         })
         .await
         .unwrap();
@@ -394,6 +414,13 @@ rule:
         max_results: 10000,
         max_file_size: 50 * 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result = service.rule_search(param).await.unwrap();
@@ -441,6 +468,13 @@ rule:
         max_results: 10000,
         max_file_size: 50 * 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result = service.rule_search(param).await.unwrap();
@@ -490,6 +524,13 @@ rule:
         max_results: 10000,
         max_file_size: 50 * 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result = service.rule_search(param).await.unwrap();
@@ -534,6 +575,13 @@ rule:
         max_results: 10000,
         max_file_size: 50 * 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result = service.rule_search(param).await.unwrap();
@@ -0,0 +1,308 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::{
+    CreateRuleParam, DeleteRuleParam, ExportRulesParam, GetRuleParam, ImportRulesParam,
+    ListRulesParam, RuleSearchParam,
+};
+use std::fs;
+use tempfile::TempDir;
+
+const PACK: &str = "# Logging rules
+id: no-console-log
+language: javascript
+message: Use the logger
+rule:
+  pattern: console.log($A)
+---
+id: no-debugger
+language: javascript
+rule:
+  kind: debugger_statement
+";
+
+const CODE: &str = "console.log(1);\ndebugger;\nconsole.log(2);\n";
+
+fn create_service(temp_dir: &TempDir) -> AstGrepService {
+    AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..Default::default()
+    })
+}
+
+fn list_all() -> ListRulesParam {
+    ListRulesParam {
+        language: None,
+        severity: None,
+        tag: None,
+    }
+}
+
+fn search_pack(pack: &str) -> RuleSearchParam {
+    RuleSearchParam {
+        rule_config: String::new(),
+        pack: Some(pack.to_string()),
+        path_pattern: Some("*.js".to_string()),
+        max_results: 100,
+        max_file_size: 1024 * 1024,
+        cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_multi_document_rule_file_is_a_pack() {
+    let temp_dir = TempDir::new().unwrap();
+    let rules_dir = temp_dir.path().join(".rules");
+    fs::create_dir(&rules_dir).unwrap();
+    fs::write(rules_dir.join("logging.yaml"), PACK).unwrap();
+    fs::write(temp_dir.path().join("app.js"), CODE).unwrap();
+    let service = create_service(&temp_dir);
+
+    let rules = service.list_rules(list_all()).await.unwrap().rules;
+    let listed: Vec<(&str, Option<&str>)> = rules
+        .iter()
+        .map(|rule| (rule.id.as_str(), rule.pack.as_deref()))
+        .collect();
+    assert_eq!(
+        listed,
+        [
+            ("no-console-log", Some("logging")),
+            ("no-debugger", Some("logging"))
+        ]
+    );
+    let rule = service
+        .get_rule(GetRuleParam {
+            rule_id: "no-debugger".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(rule.rule_config.id, "no-debugger");
+
+    let result = service.rule_search(search_pack("logging")).await.unwrap();
+    assert_eq!(result.matches.len(), 1);
+    let found: Vec<(usize, Option<&str>)> = result.matches[0]
+        .matches
+        .iter()
+        .map(|found| (found.start_line, found.rule_id.as_deref()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (0, Some("no-console-log")),
+            (1, Some("no-debugger")),
+            (2, Some("no-console-log"))
+        ]
+    );
+    assert!(service.rule_search(search_pack("missing")).await.is_err());
+
+    // Deleting one rule of the pack keeps the other
+    let deleted = service
+        .delete_rule(DeleteRuleParam {
+            rule_id: "no-console-log".to_string(),
+        })
+        .await
+        .unwrap();
+    assert!(deleted.deleted);
+    let remaining = fs::read_to_string(rules_dir.join("logging.yaml")).unwrap();
+    assert_eq!(
+        remaining,
+        "id: no-debugger\nlanguage: javascript\nrule:\n  kind: debugger_statement\n"
+    );
+}
+
+#[tokio::test]
+async fn test_export_and_import_keep_rule_ids() {
+    let source = TempDir::new().unwrap();
+    let service = create_service(&source);
+    fs::write(source.path().join("logging.yaml"), PACK).unwrap();
+    let imported = service
+        .import_rules(ImportRulesParam {
+            path: "logging.yaml".to_string(),
+            pack: Some("team".to_string()),
+            overwrite: false,
+        })
+        .await
+        .unwrap();
+    let ids: Vec<&str> = imported
+        .imported
+        .iter()
+        .map(|rule| rule.rule_id.as_str())
+        .collect();
+    assert_eq!(ids, ["no-console-log", "no-debugger"]);
+    assert!(imported.imported.iter().all(|rule| rule.created));
+    service
+        .create_rule(CreateRuleParam {
+            rule_config: "id: no-eval\nlanguage: javascript\nrule:\n  pattern: eval($A)\n"
+                .to_string(),
+            overwrite: false,
+        })
+        .await
+        .unwrap();
+
+    // One file with the pack's rules, and a directory with a file per rule
+    let exported = service
+        .export_rules(ExportRulesParam {
+            output_path: "out/team.yml".to_string(),
+            pack: Some("team".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(exported.exported, ["no-console-log", "no-debugger"]);
+    assert_eq!(
+        fs::read_to_string(source.path().join("out/team.yml")).unwrap(),
+        PACK
+    );
+    let exported = service
+        .export_rules(ExportRulesParam {
+            output_path: "out/all".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(exported.exported.len(), 3);
+    assert!(source.path().join("out/all/no-eval.yaml").exists());
+
+    // Imported into another project, the rules keep their ids and can be run as a pack
+    let target = TempDir::new().unwrap();
+    let target_service = create_service(&target);
+    fs::write(target.path().join("app.js"), CODE).unwrap();
+    fs::rename(source.path().join("out"), target.path().join("shared")).unwrap();
+    target_service
+        .import_rules(ImportRulesParam {
+            path: "shared/team.yml".to_string(),
+            pack: Some("team".to_string()),
+            overwrite: false,
+        })
+        .await
+        .unwrap();
+    let result = target_service
+        .rule_search(search_pack("team"))
+        .await
+        .unwrap();
+    assert_eq!(result.matches[0].matches.len(), 3);
+
+    // A directory import stores new rules on their own, and refuses ids already stored
+    let error = target_service
+        .import_rules(ImportRulesParam {
+            path: "shared/all".to_string(),
+            pack: None,
+            overwrite: false,
+        })
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("already exists"), "{error}");
+    assert!(!target.path().join(".rules/no-eval.yaml").exists());
+    let imported = target_service
+        .import_rules(ImportRulesParam {
+            path: "shared/all".to_string(),
+            pack: None,
+            overwrite: true,
+        })
+        .await
+        .unwrap();
+    assert_eq!(imported.imported.len(), 3);
+    // Replaced rules stay in their pack
+    let rules = target_service.list_rules(list_all()).await.unwrap().rules;
+    let listed: Vec<(&str, Option<&str>)> = rules
+        .iter()
+        .map(|rule| (rule.id.as_str(), rule.pack.as_deref()))
+        .collect();
+    assert_eq!(
+        listed,
+        [
+            ("no-eval", None),
+            ("no-console-log", Some("team")),
+            ("no-debugger", Some("team"))
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_rule_search_needs_rule_config_or_pack() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir);
+    fs::write(temp_dir.path().join("logging.yaml"), PACK).unwrap();
+    service
+        .import_rules(ImportRulesParam {
+            path: "logging.yaml".to_string(),
+            pack: Some("logging".to_string()),
+            overwrite: false,
+        })
+        .await
+        .unwrap();
+
+    let error = service
+        .rule_search(RuleSearchParam {
+            rule_config: PACK.split("---").next().unwrap().to_string(),
+            ..search_pack("logging")
+        })
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("not both"), "{error}");
+
+    let error = service
+        .import_rules(ImportRulesParam {
+            path: "logging.yaml".to_string(),
+            pack: Some("../escape".to_string()),
+            overwrite: true,
+        })
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("Invalid rule pack name"),
+        "{error}"
+    );
+}
+
+#[tokio::test]
+async fn test_pack_without_path_pattern_walks_each_rules_language() {
+    let temp_dir = TempDir::new().unwrap();
+    let rules_dir = temp_dir.path().join(".rules");
+    fs::create_dir(&rules_dir).unwrap();
+    fs::write(rules_dir.join("logging.yaml"), PACK).unwrap();
+    fs::write(temp_dir.path().join("app.js"), CODE).unwrap();
+    // Parsed as JavaScript, these would match too
+    fs::write(temp_dir.path().join("notes.txt"), "console.log(3);\n").unwrap();
+    fs::write(temp_dir.path().join("rules.yml"), PACK).unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .rule_search(RuleSearchParam {
+            path_pattern: None,
+            ..search_pack("logging")
+        })
+        .await
+        .unwrap();
+    let files: Vec<&str> = result
+        .matches
+        .iter()
+        .map(|file| file.file_path.as_str())
+        .collect();
+    assert_eq!(files.len(), 1, "{files:?}");
+    assert!(files[0].ends_with("app.js"));
+    assert_eq!(result.matches[0].matches.len(), 3);
+
+    // The rule files are left out even when the path pattern reaches them
+    let result = service
+        .rule_search(RuleSearchParam {
+            path_pattern: Some("**/*".to_string()),
+            ..search_pack("logging")
+        })
+        .await
+        .unwrap();
+    assert!(
+        result
+            .matches
+            .iter()
+            .all(|file| !file.file_path.contains(".rules")),
+        "{:?}",
+        result.matches
+    );
+}
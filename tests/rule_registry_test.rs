@@ -0,0 +1,270 @@
+#![cfg(feature = "registry")]
+
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::{RuleRegistryConfig, ServiceConfig};
+use ast_grep_mcp::rules::{
+    ConflictResolution, CreateRuleParam, GetRuleParam, PullRulesParam, PushRuleParam, RemoteRule,
+};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const TOKEN: &str = "team-secret";
+
+type Registry = Arc<Mutex<BTreeMap<String, RemoteRule>>>;
+
+/// A rule registry over plain HTTP/1.1, one request per connection
+async fn start_registry() -> (String, Registry) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let registry = Registry::default();
+    let rules = registry.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(serve(stream, rules.clone()));
+        }
+    });
+    (url, registry)
+}
+
+async fn serve(mut stream: TcpStream, rules: Registry) {
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+    let (head, body) = loop {
+        let read = stream.read(&mut buffer).await.unwrap();
+        request.extend_from_slice(&buffer[..read]);
+        let text = String::from_utf8_lossy(&request).to_string();
+        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    line.to_lowercase()
+                        .strip_prefix("content-length:")
+                        .map(|v| v.trim().parse().unwrap())
+                })
+                .unwrap_or(0);
+            if body.len() >= length {
+                break (head.to_string(), body.to_string());
+            }
+        }
+    };
+
+    let (status, response) = respond(&head, &body, &rules);
+    let response = response.to_string();
+    let reply = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+        response.len()
+    );
+    stream.write_all(reply.as_bytes()).await.unwrap();
+}
+
+fn respond(head: &str, body: &str, rules: &Registry) -> (&'static str, Value) {
+    let authorized = head
+        .lines()
+        .any(|line| line.eq_ignore_ascii_case(&format!("authorization: Bearer {TOKEN}")));
+    if !authorized {
+        return ("401 Unauthorized", json!({"error": "unauthorized"}));
+    }
+    let mut rules = rules.lock().unwrap();
+    let request_line = head.lines().next().unwrap();
+    if request_line.starts_with("GET /rules ") {
+        let listed: Vec<&RemoteRule> = rules.values().collect();
+        return ("200 OK", json!({ "rules": listed }));
+    }
+    let id = request_line
+        .strip_prefix("PUT /rules/")
+        .and_then(|rest| rest.split(' ').next())
+        .unwrap();
+    let body: Value = serde_json::from_str(body).unwrap();
+    let current = rules.get(id).map_or(0, |rule| rule.version);
+    if let Some(base_version) = body["base_version"].as_u64()
+        && base_version != current
+    {
+        return ("409 Conflict", json!(rules[id]));
+    }
+    let stored = RemoteRule {
+        id: id.to_string(),
+        rule_config: body["rule_config"].as_str().unwrap().to_string(),
+        version: current + 1,
+    };
+    rules.insert(id.to_string(), stored.clone());
+    ("200 OK", json!(stored))
+}
+
+fn service(dir: &TempDir, url: &str, token: Option<&str>) -> AstGrepService {
+    AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![dir.path().to_path_buf()],
+        rules_directory: dir.path().join(".rules"),
+        rule_registry: Some(RuleRegistryConfig {
+            url: url.to_string(),
+            token: token.map(str::to_string),
+        }),
+        ..Default::default()
+    })
+}
+
+fn no_eval(pattern: &str) -> String {
+    format!("id: no-eval\nlanguage: javascript\nrule:\n  pattern: {pattern}\n")
+}
+
+async fn store(service: &AstGrepService, rule_config: &str) {
+    service
+        .create_rule(CreateRuleParam {
+            rule_config: rule_config.to_string(),
+            overwrite: true,
+        })
+        .await
+        .unwrap();
+}
+
+async fn stored_pattern(service: &AstGrepService) -> String {
+    let rule = service
+        .get_rule(GetRuleParam {
+            rule_id: "no-eval".to_string(),
+        })
+        .await
+        .unwrap();
+    serde_yaml::to_string(&rule.rule_config.rule).unwrap()
+}
+
+fn push(force: bool) -> PushRuleParam {
+    PushRuleParam {
+        rule_id: "no-eval".to_string(),
+        force,
+    }
+}
+
+fn pull(on_conflict: ConflictResolution) -> PullRulesParam {
+    PullRulesParam { on_conflict }
+}
+
+#[tokio::test]
+async fn test_rules_travel_between_teammates() {
+    let (url, registry) = start_registry().await;
+    let (alice_dir, bob_dir) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+    let alice = service(&alice_dir, &url, Some(TOKEN));
+    let bob = service(&bob_dir, &url, Some(TOKEN));
+
+    store(&alice, &no_eval("eval($A)")).await;
+    let pushed = alice.push_rule(push(false)).await.unwrap();
+    assert!(pushed.pushed);
+    assert_eq!(pushed.version, Some(1));
+
+    let pulled = bob
+        .pull_rules(pull(ConflictResolution::Report))
+        .await
+        .unwrap();
+    assert_eq!(pulled.added, vec!["no-eval"]);
+    assert!(stored_pattern(&bob).await.contains("eval($A)"));
+
+    // Bob changes the rule; Alice only pulled it, so hers is replaced
+    store(&bob, &no_eval("eval($$$)")).await;
+    assert_eq!(bob.push_rule(push(false)).await.unwrap().version, Some(2));
+    let pulled = alice
+        .pull_rules(pull(ConflictResolution::Report))
+        .await
+        .unwrap();
+    assert_eq!(pulled.updated, vec!["no-eval"]);
+    assert!(stored_pattern(&alice).await.contains("eval($$$)"));
+
+    let pulled = alice
+        .pull_rules(pull(ConflictResolution::Report))
+        .await
+        .unwrap();
+    assert_eq!(pulled.unchanged, 1);
+    assert_eq!(registry.lock().unwrap()["no-eval"].version, 2);
+}
+
+#[tokio::test]
+async fn test_concurrent_changes_are_conflicts() {
+    let (url, registry) = start_registry().await;
+    let (alice_dir, bob_dir) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+    let alice = service(&alice_dir, &url, Some(TOKEN));
+    let bob = service(&bob_dir, &url, Some(TOKEN));
+    store(&alice, &no_eval("eval($A)")).await;
+    alice.push_rule(push(false)).await.unwrap();
+    bob.pull_rules(pull(ConflictResolution::Report))
+        .await
+        .unwrap();
+
+    store(&alice, &no_eval("eval($B)")).await;
+    store(&bob, &no_eval("eval($C)")).await;
+    alice.push_rule(push(false)).await.unwrap();
+
+    // Bob's push would lose Alice's change
+    let refused = bob.push_rule(push(false)).await.unwrap();
+    assert!(!refused.pushed);
+    assert!(refused.conflict.unwrap().rule_config.contains("eval($B)"));
+
+    let reported = bob
+        .pull_rules(pull(ConflictResolution::Report))
+        .await
+        .unwrap();
+    assert_eq!(reported.conflicts.len(), 1);
+    assert!(stored_pattern(&bob).await.contains("eval($C)"));
+
+    // Keeping the local rule lets the next push through
+    bob.pull_rules(pull(ConflictResolution::KeepLocal))
+        .await
+        .unwrap();
+    assert!(bob.push_rule(push(false)).await.unwrap().pushed);
+    assert!(
+        registry.lock().unwrap()["no-eval"]
+            .rule_config
+            .contains("eval($C)")
+    );
+
+    store(&alice, &no_eval("eval($D)")).await;
+    let taken = alice
+        .pull_rules(pull(ConflictResolution::TakeRemote))
+        .await
+        .unwrap();
+    assert_eq!(
+        taken.conflicts[0].resolution,
+        ConflictResolution::TakeRemote
+    );
+    assert!(stored_pattern(&alice).await.contains("eval($C)"));
+}
+
+#[tokio::test]
+async fn test_forced_push_replaces_the_registry_copy() {
+    let (url, registry) = start_registry().await;
+    let (alice_dir, bob_dir) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+    let alice = service(&alice_dir, &url, Some(TOKEN));
+    let bob = service(&bob_dir, &url, Some(TOKEN));
+    store(&alice, &no_eval("eval($A)")).await;
+    alice.push_rule(push(false)).await.unwrap();
+
+    store(&bob, &no_eval("eval($B)")).await;
+    assert!(!bob.push_rule(push(false)).await.unwrap().pushed);
+    assert_eq!(bob.push_rule(push(true)).await.unwrap().version, Some(2));
+    assert!(
+        registry.lock().unwrap()["no-eval"]
+            .rule_config
+            .contains("eval($B)")
+    );
+}
+
+#[tokio::test]
+async fn test_registry_errors_are_reported() {
+    let (url, _) = start_registry().await;
+    let dir = TempDir::new().unwrap();
+    store(&service(&dir, &url, None), &no_eval("eval($A)")).await;
+
+    let unauthorized = service(&dir, &url, None).push_rule(push(false)).await;
+    assert!(unauthorized.unwrap_err().to_string().contains("401"));
+
+    let unconfigured = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![dir.path().to_path_buf()],
+        rules_directory: dir.path().join(".rules"),
+        ..Default::default()
+    });
+    let error = unconfigured
+        .pull_rules(pull(ConflictResolution::Report))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("--rule-registry"));
+}
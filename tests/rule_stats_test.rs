@@ -0,0 +1,108 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::{CreateRuleParam, RuleSearchParam, RuleStatsParam};
+use std::fs;
+use tempfile::TempDir;
+
+const NO_CONSOLE: &str =
+    "id: no-console\nlanguage: javascript\nrule:\n  pattern: console.log($A)\n";
+const NO_EVAL: &str = "id: no-eval\nlanguage: javascript\nrule:\n  pattern: eval($A)\n";
+
+fn create_service(temp_dir: &TempDir) -> AstGrepService {
+    let src = temp_dir.path().join("src");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(
+        src.join("app.js"),
+        "console.log(1);\n// ast-grep-ignore: no-console\nconsole.log(2);\nconsole.log(3); // ast-grep-ignore\n",
+    )
+    .unwrap();
+
+    let config = ServiceConfig {
+        root_directories: vec![src],
+        rules_directory: temp_dir.path().join("rules"),
+        ..Default::default()
+    };
+    AstGrepService::with_config(config)
+}
+
+fn search_param(rule_config: &str) -> RuleSearchParam {
+    RuleSearchParam {
+        rule_config: rule_config.to_string(),
+        path_pattern: Some("**/*.js".to_string()),
+        max_results: 100,
+        max_file_size: 1024 * 1024,
+        cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
+    }
+}
+
+#[tokio::test]
+async fn test_rule_search_honors_ignore_comments() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service.rule_search(search_param(NO_CONSOLE)).await.unwrap();
+    let matches = &result.matches[0].matches;
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].text, "console.log(1)");
+
+    let stats = service.rule_stats(RuleStatsParam::default()).await.unwrap();
+    assert_eq!(stats.rules.len(), 1);
+    assert_eq!(stats.rules[0].total_matches, 1);
+    assert_eq!(stats.rules[0].suppressions, 2);
+}
+
+#[tokio::test]
+async fn test_rule_stats_persist_and_flag_rules() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir);
+    service
+        .create_rule(CreateRuleParam {
+            rule_config: "id: unused\nlanguage: javascript\nrule:\n  pattern: debugger\n"
+                .to_string(),
+            overwrite: false,
+        })
+        .await
+        .unwrap();
+
+    for _ in 0..3 {
+        service.rule_search(search_param(NO_CONSOLE)).await.unwrap();
+        service.rule_search(search_param(NO_EVAL)).await.unwrap();
+    }
+
+    // A fresh service reads the statistics back from the rules directory
+    let service = create_service(&temp_dir);
+    let stats = service
+        .rule_stats(RuleStatsParam {
+            min_scans: 3,
+            noise_threshold: 0.5,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let ids: Vec<&str> = stats.rules.iter().map(|s| s.rule_id.as_str()).collect();
+    assert_eq!(ids, vec!["no-console", "no-eval"]);
+    assert_eq!(stats.rules[0].scans, 3);
+    assert_eq!(stats.rules[0].scans_with_matches, 3);
+    assert_eq!(stats.rules[0].recent_matches, vec![1, 1, 1]);
+    assert_eq!(stats.never_fired, vec!["no-eval"]);
+    assert_eq!(stats.noisy, vec!["no-console"]);
+    assert_eq!(stats.never_scanned, vec!["unused"]);
+
+    let filtered = service
+        .rule_stats(RuleStatsParam {
+            rule_id: Some("no-eval".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(filtered.rules.len(), 1);
+    assert!(filtered.never_scanned.is_empty());
+}
@@ -20,8 +20,16 @@ fn test_rule_config_serialization() {
             any: None,
             not: None,
             matches: None,
+            stop_by: None,
+            field: None,
         },
         fix: Some("logger.info($VAR)".to_string()),
+        fix_safety: None,
+        tags: Vec::new(),
+        utils: Default::default(),
+        constraints: Default::default(),
+        rewriters: Vec::new(),
+        transform: Default::default(),
     };
 
     let json = serde_json::to_string(&rule_config).unwrap();
@@ -53,8 +61,16 @@ fn test_rule_config_minimal() {
             any: None,
             not: None,
             matches: None,
+            stop_by: None,
+            field: None,
         },
         fix: None,
+        fix_safety: None,
+        tags: Vec::new(),
+        utils: Default::default(),
+        constraints: Default::default(),
+        rewriters: Vec::new(),
+        transform: Default::default(),
     };
 
     let json = serde_json::to_string(&rule_config).unwrap();
@@ -142,6 +158,8 @@ fn test_rule_object_with_kind() {
         any: None,
         not: None,
         matches: None,
+        stop_by: None,
+        field: None,
     };
 
     let json = serde_json::to_string(&rule).unwrap();
@@ -165,6 +183,8 @@ fn test_rule_object_with_regex() {
         any: None,
         not: None,
         matches: None,
+        stop_by: None,
+        field: None,
     };
 
     let json = serde_json::to_string(&rule).unwrap();
@@ -196,6 +216,8 @@ fn test_rule_object_composite_all() {
                 any: None,
                 not: None,
                 matches: None,
+                stop_by: None,
+                field: None,
             },
             RuleObject {
                 pattern: None,
@@ -209,11 +231,15 @@ fn test_rule_object_composite_all() {
                 any: None,
                 not: None,
                 matches: None,
+                stop_by: None,
+                field: None,
             },
         ]),
         any: None,
         not: None,
         matches: None,
+        stop_by: None,
+        field: None,
     };
 
     let json = serde_json::to_string(&rule).unwrap();
@@ -246,9 +272,13 @@ fn test_rule_object_composite_any() {
             any: None,
             not: None,
             matches: None,
+            stop_by: None,
+            field: None,
         }]),
         not: None,
         matches: None,
+        stop_by: None,
+        field: None,
     };
 
     let json = serde_json::to_string(&rule).unwrap();
@@ -282,8 +312,12 @@ fn test_rule_object_with_not() {
             any: None,
             not: None,
             matches: None,
+            stop_by: None,
+            field: None,
         })),
         matches: None,
+        stop_by: None,
+        field: None,
     };
 
     let json = serde_json::to_string(&rule).unwrap();
@@ -312,6 +346,8 @@ fn test_rule_object_relational_inside() {
             any: None,
             not: None,
             matches: None,
+            stop_by: None,
+            field: None,
         })),
         has: None,
         follows: None,
@@ -320,6 +356,8 @@ fn test_rule_object_relational_inside() {
         any: None,
         not: None,
         matches: None,
+        stop_by: None,
+        field: None,
     };
 
     let json = serde_json::to_string(&rule).unwrap();
@@ -349,6 +387,8 @@ fn test_rule_object_relational_has() {
             any: None,
             not: None,
             matches: None,
+            stop_by: None,
+            field: None,
         })),
         follows: None,
         precedes: None,
@@ -356,6 +396,8 @@ fn test_rule_object_relational_has() {
         any: None,
         not: None,
         matches: None,
+        stop_by: None,
+        field: None,
     };
 
     let json = serde_json::to_string(&rule).unwrap();
@@ -384,12 +426,16 @@ fn test_rule_object_relational_follows() {
             any: None,
             not: None,
             matches: None,
+            stop_by: None,
+            field: None,
         })),
         precedes: None,
         all: None,
         any: None,
         not: None,
         matches: None,
+        stop_by: None,
+        field: None,
     };
 
     let json = serde_json::to_string(&rule).unwrap();
@@ -419,11 +465,15 @@ fn test_rule_object_relational_precedes() {
             any: None,
             not: None,
             matches: None,
+            stop_by: None,
+            field: None,
         })),
         all: None,
         any: None,
         not: None,
         matches: None,
+        stop_by: None,
+        field: None,
     };
 
     let json = serde_json::to_string(&rule).unwrap();
@@ -446,6 +496,8 @@ fn test_rule_object_with_matches() {
         any: None,
         not: None,
         matches: Some("variable-name".to_string()),
+        stop_by: None,
+        field: None,
     };
 
     let json = serde_json::to_string(&rule).unwrap();
@@ -471,6 +523,7 @@ fn test_rule_search_param_with_all_fields() {
     let cursor = CursorParam {
         last_file_path: "/path/to/file.rs".to_string(),
         is_complete: false,
+        token: None,
     };
 
     let param = RuleSearchParam {
@@ -479,6 +532,13 @@ fn test_rule_search_param_with_all_fields() {
         max_results: 500,
         max_file_size: 1024 * 1024,
         cursor: Some(cursor),
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let json = serde_json::to_string(&param).unwrap();
@@ -518,6 +578,11 @@ fn test_rule_replace_param_with_all_fields() {
         dry_run: false,
         summary_only: true,
         cursor: None,
+        allow_unsafe: false,
+        workspace: None,
+        ignore_whitespace_changes: true,
+        timeout_ms: None,
+        walk: Default::default(),
     };
 
     let json = serde_json::to_string(&param).unwrap();
@@ -571,6 +636,9 @@ fn test_rule_validate_result() {
                 "console.log('hello')".to_string(),
                 "console.log('world')".to_string(),
             ],
+            constraint_violations: vec![],
+            fixed_code: None,
+            fix_hunks: vec![],
         }),
     };
 
@@ -648,6 +716,7 @@ fn test_list_rules_param() {
     let param = ListRulesParam {
         language: Some("javascript".to_string()),
         severity: Some("warning".to_string()),
+        tag: None,
     };
 
     let json = serde_json::to_string(&param).unwrap();
@@ -662,6 +731,7 @@ fn test_list_rules_param_empty() {
     let param = ListRulesParam {
         language: None,
         severity: None,
+        tag: None,
     };
 
     let json = serde_json::to_string(&param).unwrap();
@@ -682,6 +752,8 @@ fn test_list_rules_result() {
                 severity: Some("warning".to_string()),
                 file_path: "/rules/no-console-log.yaml".to_string(),
                 has_fix: true,
+                tags: Vec::new(),
+                pack: None,
             },
             RuleInfo {
                 id: "use-const".to_string(),
@@ -690,6 +762,8 @@ fn test_list_rules_result() {
                 severity: Some("error".to_string()),
                 file_path: "/rules/use-const.yaml".to_string(),
                 has_fix: false,
+                tags: Vec::new(),
+                pack: None,
             },
         ],
     };
@@ -736,8 +810,16 @@ fn test_get_rule_result() {
             any: None,
             not: None,
             matches: None,
+            stop_by: None,
+            field: None,
         },
         fix: None,
+        fix_safety: None,
+        tags: Vec::new(),
+        utils: Default::default(),
+        constraints: Default::default(),
+        rewriters: Vec::new(),
+        transform: Default::default(),
     };
 
     let result = GetRuleResult {
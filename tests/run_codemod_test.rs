@@ -0,0 +1,189 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::codemods::Codemod;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::RunCodemodParam;
+use std::fs;
+use tempfile::TempDir;
+
+fn setup(files: &[(&str, &str)]) -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    for (name, content) in files {
+        fs::write(temp_dir.path().join(name), content).unwrap();
+    }
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn apply(codemod: Codemod) -> RunCodemodParam {
+    RunCodemodParam {
+        language: "javascript".to_string(),
+        dry_run: false,
+        ..RunCodemodParam::new(codemod)
+    }
+}
+
+#[tokio::test]
+async fn test_rename_import_source_keeps_quotes_and_other_strings() {
+    let code = "import a from 'old';\nimport { b } from \"old\";\nexport * from 'old';\nconst c = require('old');\nconst lazy = import('old');\nconst label = 'old';\n";
+    let (service, temp_dir) = setup(&[("a.js", code)]);
+
+    let preview = service
+        .run_codemod(RunCodemodParam {
+            language: "javascript".to_string(),
+            ..RunCodemodParam::new(Codemod::RenameImportSource {
+                from: "old".to_string(),
+                to: "new".to_string(),
+            })
+        })
+        .await
+        .unwrap();
+    assert!(preview.dry_run);
+    assert_eq!(preview.steps.len(), 2);
+    assert_eq!(preview.total_changes, 5);
+    assert_eq!(preview.files_with_changes, 1);
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("a.js")).unwrap(),
+        code
+    );
+
+    service
+        .run_codemod(apply(Codemod::RenameImportSource {
+            from: "old".to_string(),
+            to: "new".to_string(),
+        }))
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("a.js")).unwrap(),
+        "import a from 'new';\nimport { b } from \"new\";\nexport * from 'new';\nconst c = require('new');\nconst lazy = import('new');\nconst label = 'old';\n"
+    );
+}
+
+#[tokio::test]
+async fn test_wrap_calls_with_try_catch_skips_guarded_calls() {
+    let code = "async function f() {\n  save(a, {\n    b: 1,\n  });\n  try { save(1); } catch (e) {}\n  await save(x);\n  run(() => { save(2); });\n  const y = save(3);\n}\n";
+    let (service, temp_dir) = setup(&[("a.js", code)]);
+
+    let codemod = Codemod::WrapCallsWithTryCatch {
+        callee: "save".to_string(),
+        handler: "report(error);".to_string(),
+    };
+    let result = service.run_codemod(apply(codemod.clone())).await.unwrap();
+    assert_eq!(result.total_changes, 3);
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("a.js")).unwrap(),
+        "async function f() {\n  try { save(a, {\n    b: 1,\n  }); } catch (error) { report(error); }\n  try { save(1); } catch (e) {}\n  try { await save(x); } catch (error) { report(error); }\n  run(() => { try { save(2); } catch (error) { report(error); } });\n  const y = save(3);\n}\n"
+    );
+
+    // Wrapped calls are inside a try now, so running again changes nothing
+    let again = service.run_codemod(apply(codemod)).await.unwrap();
+    assert_eq!(again.total_changes, 0);
+}
+
+#[tokio::test]
+async fn test_add_argument_with_default_only_touches_matching_arity() {
+    let code =
+        "connect();\nconnect(url);\nconnect(url, {\n  verbose: true });\nother.connect(url);\n";
+    let (service, temp_dir) = setup(&[("a.js", code)]);
+
+    let codemod = Codemod::AddArgumentWithDefault {
+        function: "connect".to_string(),
+        arity: 1,
+        default: "5000".to_string(),
+    };
+    service.run_codemod(apply(codemod.clone())).await.unwrap();
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("a.js")).unwrap(),
+        "connect();\nconnect(url, 5000);\nconnect(url, {\n  verbose: true });\nother.connect(url);\n"
+    );
+
+    let again = service.run_codemod(apply(codemod)).await.unwrap();
+    assert_eq!(again.total_changes, 0);
+}
+
+#[tokio::test]
+async fn test_convert_default_export_to_named() {
+    let (service, temp_dir) = setup(&[
+        (
+            "main.js",
+            "export default function main(a) {\n  return a;\n}\n",
+        ),
+        ("foo.js", "class Foo {}\nexport default Foo;\n"),
+        (
+            "bar.js",
+            "export default class Bar extends Base {\n  x() {}\n}\n",
+        ),
+        ("call.js", "export default create();\n"),
+        ("object.js", "export default {\n  a: 1,\n};\n"),
+    ]);
+
+    let result = service
+        .run_codemod(apply(Codemod::ConvertDefaultExportToNamed { name: None }))
+        .await
+        .unwrap();
+    assert_eq!(result.files_with_changes, 3);
+    let read = |name: &str| fs::read_to_string(temp_dir.path().join(name)).unwrap();
+    assert_eq!(
+        read("main.js"),
+        "export function main(a) {\n  return a;\n}\n"
+    );
+    assert_eq!(read("foo.js"), "class Foo {}\nexport { Foo };\n");
+    assert_eq!(
+        read("bar.js"),
+        "export class Bar extends Base {\n  x() {}\n}\n"
+    );
+    assert_eq!(read("call.js"), "export default create();\n");
+    assert_eq!(read("object.js"), "export default {\n  a: 1,\n};\n");
+}
+
+#[tokio::test]
+async fn test_codemod_is_selected_by_name_with_params() {
+    let param: RunCodemodParam = serde_json::from_value(serde_json::json!({
+        "codemod": "add_argument_with_default",
+        "params": { "function": "db.open", "arity": 0, "default": "{ retries: 3 }" },
+        "language": "typescript"
+    }))
+    .unwrap();
+    assert_eq!(
+        param.codemod,
+        Codemod::AddArgumentWithDefault {
+            function: "db.open".to_string(),
+            arity: 0,
+            default: "{ retries: 3 }".to_string(),
+        }
+    );
+    assert!(param.dry_run);
+
+    let (service, _temp_dir) = setup(&[("a.ts", "const conn = db.open();\n")]);
+    let result = service.run_codemod(param).await.unwrap();
+    assert_eq!(
+        result.steps[0].rule_id,
+        "codemod-add-argument-with-default-call"
+    );
+    assert_eq!(
+        result.steps[0].file_results[0].changes[0].new_text,
+        "db.open({ retries: 3 })"
+    );
+
+    let param: RunCodemodParam = serde_json::from_value(serde_json::json!({
+        "codemod": "convert_default_export_to_named",
+        "params": {}
+    }))
+    .unwrap();
+    assert_eq!(
+        param.codemod,
+        Codemod::ConvertDefaultExportToNamed { name: None }
+    );
+
+    let invalid = service
+        .run_codemod(RunCodemodParam::new(Codemod::RenameImportSource {
+            from: "old'".to_string(),
+            to: "new".to_string(),
+        }))
+        .await;
+    assert!(invalid.is_err());
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::tool_router::ToolRouter;
+use rmcp::model::CallToolRequestParam;
+use serde_json::{Value, json};
+use std::fs;
+use tempfile::TempDir;
+
+const RULE: &str = "id: no-eval
+language: javascript
+severity: error
+message: Avoid eval
+rule:
+  pattern: eval($A)
+";
+
+fn create_service(temp_dir: &TempDir) -> AstGrepService {
+    fs::create_dir_all(temp_dir.path().join("src/my lib")).unwrap();
+    fs::write(
+        temp_dir.path().join("src/my lib/app.js"),
+        "const a = 1;\nconst b = eval(input);\n",
+    )
+    .unwrap();
+    common::create_service(temp_dir)
+}
+
+async fn rule_search(service: &AstGrepService, arguments: Value) -> Vec<Value> {
+    let result = ToolRouter::route_tool_call(
+        service,
+        CallToolRequestParam {
+            name: "rule_search".into(),
+            arguments: arguments.as_object().cloned(),
+        },
+    )
+    .await
+    .unwrap();
+    result
+        .content
+        .iter()
+        .map(|content| {
+            let text = &content.as_text().unwrap().text;
+            serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.clone()))
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_rule_search_sarif_output() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir);
+
+    let content = rule_search(
+        &service,
+        json!({ "rule_config": RULE, "output_format": "sarif" }),
+    )
+    .await;
+    assert_eq!(content.len(), 1);
+    let sarif = &content[0];
+    assert_eq!(sarif["version"], "2.1.0");
+    let run = &sarif["runs"][0];
+    assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "no-eval");
+    assert!(
+        run["originalUriBaseIds"]["%SRCROOT%"]["uri"]
+            .as_str()
+            .unwrap()
+            .starts_with("file:///")
+    );
+
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["ruleId"], "no-eval");
+    assert_eq!(results[0]["level"], "error");
+    assert_eq!(results[0]["message"]["text"], "Avoid eval");
+    let location = &results[0]["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "src/my%20lib/app.js");
+    assert_eq!(location["artifactLocation"]["uriBaseId"], "%SRCROOT%");
+    assert_eq!(
+        location["region"],
+        json!({
+            "startLine": 2,
+            "startColumn": 11,
+            "endLine": 2,
+            "endColumn": 22,
+            "snippet": { "text": "eval(input)" }
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_rule_search_json_output_and_sarif_export() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir);
+
+    let content = rule_search(
+        &service,
+        json!({ "rule_config": RULE, "output_format": "json" }),
+    )
+    .await;
+    assert_eq!(content.len(), 1);
+    assert_eq!(
+        content[0]["matches"][0]["matches"][0]["text"],
+        "eval(input)"
+    );
+
+    rule_search(
+        &service,
+        json!({
+            "rule_config": RULE,
+            "output_format": "sarif",
+            "output_file": "reports/results.sarif"
+        }),
+    )
+    .await;
+    let written: Value = serde_json::from_str(
+        &fs::read_to_string(temp_dir.path().join("reports/results.sarif")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(written["runs"][0]["results"][0]["ruleId"], "no-eval");
+}
@@ -0,0 +1,202 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::{CreateRuleParam, ScanParam};
+use std::fs;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::sync::mpsc::unbounded_channel;
+
+const RULES: [&str; 3] = [
+    "id: no-eval\nlanguage: javascript\nseverity: error\nmessage: eval is unsafe\nrule:\n  pattern: eval($A)\n",
+    "id: no-console\nlanguage: javascript\nseverity: warning\nrule:\n  pattern: console.log($A)\n",
+    "id: no-debugger\nlanguage: javascript\nrule:\n  pattern: debugger\n",
+];
+
+async fn setup() -> (AstGrepService, TempDir) {
+    setup_with(ServiceConfig::default()).await
+}
+
+async fn setup_with(config: ServiceConfig) -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("app.js"),
+        "console.log(1);\neval(code);\nconsole.log(2);\ndebugger;\n",
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("util.js"), "console.log(3);\n").unwrap();
+    fs::write(temp_dir.path().join("notes.py"), "eval(code)\n").unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..config
+    });
+    for rule in RULES {
+        service
+            .create_rule(CreateRuleParam {
+                rule_config: rule.to_string(),
+                overwrite: false,
+            })
+            .await
+            .unwrap();
+    }
+    (service, temp_dir)
+}
+
+#[tokio::test]
+async fn test_scan_groups_findings_by_severity() {
+    let (service, _temp_dir) = setup().await;
+
+    let result = service
+        .scan(ScanParam {
+            path_pattern: Some("*.{js,py}".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.rules_run, 3);
+    assert_eq!(result.total_findings, 5);
+    let buckets: Vec<_> = result
+        .buckets
+        .iter()
+        .map(|bucket| (bucket.severity.as_str(), bucket.count))
+        .collect();
+    // The python file is not checked by the javascript rules; rules without a severity are info
+    assert_eq!(buckets, [("error", 1), ("warning", 3), ("info", 1)]);
+    assert_eq!(result.buckets[0].findings[0].message, "eval is unsafe");
+    assert_eq!(result.buckets[1].rule_counts["no-console"], 3);
+    assert_eq!(result.buckets[2].findings[0].rule_id, "no-debugger");
+}
+
+#[tokio::test]
+async fn test_scan_pages_through_one_bucket() {
+    let (service, _temp_dir) = setup().await;
+
+    let result = service
+        .scan(ScanParam {
+            path_pattern: Some("*.js".to_string()),
+            page_size: 2,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let warnings = &result.buckets[1];
+    assert_eq!(warnings.findings.len(), 2);
+    assert_eq!(warnings.next_offset, Some(2));
+
+    let result = service
+        .scan(ScanParam {
+            path_pattern: Some("*.js".to_string()),
+            severity: Some("warning".to_string()),
+            offset: 2,
+            page_size: 2,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(result.buckets[0].findings.is_empty());
+    assert_eq!(result.buckets[0].count, 1);
+    let warnings = &result.buckets[1];
+    assert_eq!(warnings.findings.len(), 1);
+    assert!(warnings.findings[0].file_path.ends_with("util.js"));
+    assert_eq!(warnings.next_offset, None);
+}
+
+#[tokio::test]
+async fn test_scan_rejects_unknown_rules_and_severities() {
+    let (service, _temp_dir) = setup().await;
+
+    let error = service
+        .scan(ScanParam {
+            rule_ids: vec!["missing".to_string()],
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("missing"));
+
+    let error = service
+        .scan(ScanParam {
+            severity: Some("fatal".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("fatal"));
+}
+
+#[tokio::test]
+async fn test_scan_streaming_sends_error_findings() {
+    let (service, _temp_dir) = setup().await;
+
+    let (sender, mut receiver) = unbounded_channel();
+    let result = service
+        .scan_streaming(
+            ScanParam {
+                path_pattern: Some("*.{js,py}".to_string()),
+                ..Default::default()
+            },
+            sender,
+        )
+        .await
+        .unwrap();
+
+    let mut sent = Vec::new();
+    while let Some(finding) = receiver.recv().await {
+        sent.push(finding);
+    }
+    // Only the error bucket is sent; warnings and info wait for the result
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].rule_id, "no-eval");
+    assert!(sent[0].file_path.ends_with("app.js"));
+    assert_eq!(sent[0].start_line, 1);
+    assert_eq!(result.buckets[0].count, 1);
+    assert_eq!(result.total_findings, 5);
+}
+
+#[tokio::test]
+async fn test_rules_over_their_match_limit_are_reported() {
+    let (service, _temp_dir) = setup_with(ServiceConfig {
+        rule_max_matches: 2,
+        ..Default::default()
+    })
+    .await;
+
+    let result = service
+        .scan(ScanParam {
+            path_pattern: Some("*.js".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    // no-console has three findings, the others stay under the limit
+    assert_eq!(result.truncated_rules, ["no-console"]);
+    assert_eq!(result.total_findings, 4);
+    let warnings = &result.buckets[1];
+    assert_eq!(warnings.severity, "warning");
+    assert_eq!(warnings.count, 2);
+}
+
+#[tokio::test]
+async fn test_rules_out_of_time_are_reported() {
+    let (service, _temp_dir) = setup_with(ServiceConfig {
+        rule_timeout: Duration::ZERO,
+        ..Default::default()
+    })
+    .await;
+
+    let result = service
+        .scan(ScanParam {
+            path_pattern: Some("*.js".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.total_findings, 0);
+    assert_eq!(
+        result.truncated_rules,
+        ["no-console", "no-debugger", "no-eval"]
+    );
+}
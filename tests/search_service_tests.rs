@@ -35,6 +35,10 @@ fn create_test_file(dir: &Path, name: &str, content: &str) {
     fs::write(file_path, content).unwrap();
 }
 
+fn total_matches(result: &FileSearchResult) -> usize {
+    result.matches.iter().map(|f| f.matches.len()).sum()
+}
+
 #[tokio::test]
 async fn test_search_basic() {
     let (service, _temp_dir) = create_test_search_service();
@@ -115,8 +119,8 @@ async fn test_file_search_basic() {
     };
 
     let result = service.file_search(param).await.unwrap();
-    assert_eq!(result.files.len(), 2); // Only .js files
-    assert_eq!(result.total_matches, 2);
+    assert_eq!(result.matches.len(), 2); // Only .js files
+    assert_eq!(total_matches(&result), 2);
 }
 
 #[tokio::test]
@@ -136,8 +140,8 @@ async fn test_file_search_nested_directories() {
     };
 
     let result = service.file_search(param).await.unwrap();
-    assert_eq!(result.files.len(), 3);
-    assert_eq!(result.total_matches, 3);
+    assert_eq!(result.matches.len(), 3);
+    assert_eq!(total_matches(&result), 3);
 }
 
 #[tokio::test]
@@ -158,8 +162,45 @@ async fn test_file_search_max_results_limit() {
     };
 
     let result = service.file_search(param).await.unwrap();
-    assert!(result.files.len() <= 3);
-    assert!(result.total_matches <= 3);
+    assert!(result.matches.len() <= 3);
+    assert!(total_matches(&result) <= 3);
+}
+
+#[tokio::test]
+async fn test_file_search_pages_cover_every_file() {
+    let (service, temp_dir) = create_test_search_service();
+
+    for i in 1..=5 {
+        create_test_file(
+            temp_dir.path(),
+            &format!("test{i}.js"),
+            "console.log('test');",
+        );
+    }
+
+    let mut param = FileSearchParam {
+        path_pattern: "*.js".to_string(),
+        pattern: "console.log($VAR)".to_string(),
+        language: "javascript".to_string(),
+        max_results: 2,
+        ..Default::default()
+    };
+    let mut found = Vec::new();
+    loop {
+        let result = service.file_search(param.clone()).await.unwrap();
+        found.extend(result.matches.into_iter().map(|m| m.file_path));
+        match result.next_cursor {
+            Some(cursor) if !cursor.is_complete => {
+                param.cursor = Some(CursorParam {
+                    last_file_path: cursor.last_file_path,
+                    is_complete: false,
+                    token: None,
+                });
+            }
+            _ => break,
+        }
+    }
+    assert_eq!(found.len(), 5);
 }
 
 #[tokio::test]
@@ -184,24 +225,26 @@ async fn test_search_with_strictness() {
 async fn test_search_with_selector() {
     let (service, _temp_dir) = create_test_search_service();
     let code = r#"
-function test() {
-    console.log("inside function");
+class Counter {
+    count = 0;
 }
-console.log("outside function");
+count = 1;
 "#;
-    
+
+    // The selector picks the class field out of the contextual pattern, so the
+    // plain assignment outside the class must not match
     let param = SearchParam {
         code: code.to_string(),
-        pattern: "console.log($VAR)".to_string(),
+        pattern: "$F = $V".to_string(),
         language: "javascript".to_string(),
-        selector: Some("function_declaration".to_string()),
+        context: Some("class A { $F = $V }".to_string()),
+        selector: Some("field_definition".to_string()),
         ..Default::default()
     };
 
     let result = service.search(param).await.unwrap();
-    // Should only match the console.log inside the function
     assert_eq!(result.matches.len(), 1);
-    assert!(result.matches[0].text.contains("inside function"));
+    assert!(result.matches[0].text.contains("count = 0"));
 }
 
 #[tokio::test]
@@ -225,6 +268,42 @@ async fn test_file_search_no_files_found() {
     };
 
     let result = service.file_search(param).await.unwrap();
-    assert_eq!(result.files.len(), 0);
-    assert_eq!(result.total_matches, 0);
-}
\ No newline at end of file
+    assert_eq!(result.matches.len(), 0);
+    assert_eq!(total_matches(&result), 0);
+}
+
+#[tokio::test]
+async fn test_file_search_reports_scan_stats() {
+    let (service, temp_dir) = create_test_search_service();
+    create_test_file(temp_dir.path(), "a.js", "console.log(1);");
+    create_test_file(temp_dir.path(), "b.ts", "let x = 1;");
+    create_test_file(temp_dir.path(), "big.js", &"console.log(2);\n".repeat(100));
+    fs::write(temp_dir.path().join("binary.js"), [0xff, 0xfe, 0x00]).unwrap();
+    create_test_file(temp_dir.path(), "notes.txt", "console.log(3);");
+
+    let param = FileSearchParam {
+        path_pattern: "**/*".to_string(),
+        pattern: "console.log($A)".to_string(),
+        language: "javascript".to_string(),
+        max_file_size: 100,
+        ..Default::default()
+    };
+    let stats = service.file_search(param).await.unwrap().stats.unwrap();
+    assert_eq!(stats.files_matching_glob, 5);
+    assert_eq!(stats.skipped_too_large, 1);
+    assert_eq!(stats.skipped_unreadable, 1);
+    assert_eq!(stats.files_with_matches, 2);
+    assert_eq!(stats.files_without_matches, 1);
+    assert_eq!(stats.searched_by_extension.get(".js"), Some(&1));
+    assert_eq!(stats.searched_by_extension.get(".ts"), Some(&1));
+    assert_eq!(stats.searched_by_extension.get(".txt"), Some(&1));
+
+    let param = FileSearchParam {
+        path_pattern: "**/*.jsx".to_string(),
+        pattern: "console.log($A)".to_string(),
+        language: "javascript".to_string(),
+        ..Default::default()
+    };
+    let stats = service.file_search(param).await.unwrap().stats.unwrap();
+    assert_eq!(stats.files_matching_glob, 0);
+}
@@ -0,0 +1,94 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::SymbolSearchParam;
+use std::fs;
+use tempfile::TempDir;
+
+fn setup() -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("users.ts"),
+        "export class UserService {\n  getUserName(id: string) { return id; }\n  formatUserDisplayName() {}\n}\n\nfunction receiveMessage() {}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("users.py"),
+        "def get_user_name(user):\n    return user.name\n\nclass UserCache:\n    pass\n",
+    )
+    .unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn query(query: &str) -> SymbolSearchParam {
+    SymbolSearchParam {
+        query: query.to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_search_symbols_across_naming_styles() {
+    let (service, _temp_dir) = setup();
+
+    let result = service.search_symbols(query("getUserName")).await.unwrap();
+    let found: Vec<_> = result
+        .matches
+        .iter()
+        .map(|m| {
+            (
+                m.qualified_name.as_str(),
+                m.kind.as_str(),
+                m.match_kind.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(found[0], ("UserService.getUserName", "method", "exact"));
+    assert_eq!(found[1], ("get_user_name", "function", "words"));
+    assert_eq!(result.files_indexed, 2);
+    assert_eq!(result.cached_files, 0);
+
+    // Every word of the query appears in these names; shorter names rank higher
+    let result = service.search_symbols(query("user name")).await.unwrap();
+    let found: Vec<_> = result
+        .matches
+        .iter()
+        .map(|m| (m.name.as_str(), m.match_kind.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("getUserName", "partial"),
+            ("get_user_name", "partial"),
+            ("formatUserDisplayName", "partial")
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_search_symbols_tolerates_typos_and_filters_kinds() {
+    let (service, _temp_dir) = setup();
+
+    let result = service
+        .search_symbols(query("recieveMessage"))
+        .await
+        .unwrap();
+    assert_eq!(result.matches[0].name, "receiveMessage");
+    assert_eq!(result.matches[0].match_kind, "fuzzy");
+
+    let result = service
+        .search_symbols(SymbolSearchParam {
+            kinds: vec!["class".to_string()],
+            min_score: 0.5,
+            ..query("user")
+        })
+        .await
+        .unwrap();
+    let names: Vec<_> = result.matches.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["UserCache", "UserService"]);
+    // The second call reuses the declarations indexed by the first
+    assert_eq!(result.cached_files, 2);
+}
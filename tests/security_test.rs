@@ -47,6 +47,22 @@ async fn test_path_traversal_blocked() {
             context_before: None,
             context_after: None,
             context_lines: None,
+            output_file: None,
+            overlays: Default::default(),
+            dialect: None,
+            language_patterns: Default::default(),
+            search_archives: false,
+            workspace: None,
+            regex_fallback: false,
+            include_ast_context: false,
+            allow_broad: false,
+            revision: None,
+            suggest_fixes: false,
+            timeout_ms: None,
+            walk: Default::default(),
+            stream: false,
+            context_scope: None,
+            include_leading_trivia: false,
         };
 
         let result = service.file_search(param).await;
@@ -105,6 +121,22 @@ async fn test_absolute_path_outside_root_blocked() {
         context_before: None,
         context_after: None,
         context_lines: None,
+        output_file: None,
+        overlays: Default::default(),
+        dialect: None,
+        language_patterns: Default::default(),
+        search_archives: false,
+        workspace: None,
+        regex_fallback: false,
+        include_ast_context: false,
+        allow_broad: false,
+        revision: None,
+        suggest_fixes: false,
+        timeout_ms: None,
+        walk: Default::default(),
+        stream: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result = service.file_search(param).await;
@@ -145,6 +177,22 @@ async fn test_valid_patterns_still_work() {
             context_before: None,
             context_after: None,
             context_lines: None,
+            output_file: None,
+            overlays: Default::default(),
+            dialect: None,
+            language_patterns: Default::default(),
+            search_archives: false,
+            workspace: None,
+            regex_fallback: false,
+            include_ast_context: false,
+            allow_broad: false,
+            revision: None,
+            suggest_fixes: false,
+            timeout_ms: None,
+            walk: Default::default(),
+            stream: false,
+            context_scope: None,
+            include_leading_trivia: false,
         };
 
         let result = service.file_search(param).await.unwrap();
@@ -29,6 +29,13 @@ rule:
         max_results: 100,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result = service.rule_search(param).await.unwrap();
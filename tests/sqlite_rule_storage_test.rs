@@ -0,0 +1,198 @@
+#![cfg(feature = "sqlite")]
+
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::{
+    CreateRuleParam, DeleteRuleParam, GetRuleParam, ListRulesParam, RuleInfo, RuleStorage,
+    SqliteRuleStorage,
+};
+use std::fs;
+use tempfile::TempDir;
+
+fn create(rule_config: &str, overwrite: bool) -> CreateRuleParam {
+    CreateRuleParam {
+        rule_config: rule_config.to_string(),
+        overwrite,
+    }
+}
+
+fn list(language: Option<&str>, tag: Option<&str>) -> ListRulesParam {
+    ListRulesParam {
+        language: language.map(str::to_string),
+        severity: None,
+        tag: tag.map(str::to_string),
+    }
+}
+
+fn ids(rules: &[RuleInfo]) -> Vec<&str> {
+    rules.iter().map(|rule| rule.id.as_str()).collect()
+}
+
+#[tokio::test]
+async fn test_rules_are_filtered_by_language_and_tag() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = SqliteRuleStorage::open(&temp_dir.path().join("nested/rules.db")).unwrap();
+
+    storage
+        .create_rule(create(
+            "id: no-eval\nlanguage: javascript\ntags: [security, legacy]\nrule:\n  pattern: eval($A)\n",
+            false,
+        ))
+        .await
+        .unwrap();
+    storage
+        .create_rule(create(
+            "id: no-log\nlanguage: javascript\nrule:\n  pattern: console.log($A)\nfix: ''\n",
+            false,
+        ))
+        .await
+        .unwrap();
+    storage
+        .create_rule(create(
+            "id: no-exec\nlanguage: python\ntags: [security]\nrule:\n  pattern: exec($A)\n",
+            false,
+        ))
+        .await
+        .unwrap();
+
+    let all = storage.list_rules(list(None, None)).await.unwrap().rules;
+    assert_eq!(ids(&all), vec!["no-eval", "no-exec", "no-log"]);
+    assert_eq!(all[0].tags, vec!["legacy", "security"]);
+    assert!(all[0].file_path.ends_with("rules.db#no-eval"));
+    assert!(all[2].has_fix);
+
+    let security = storage
+        .list_rules(list(None, Some("security")))
+        .await
+        .unwrap();
+    assert_eq!(ids(&security.rules), vec!["no-eval", "no-exec"]);
+    let javascript_security = storage
+        .list_rules(list(Some("javascript"), Some("security")))
+        .await
+        .unwrap();
+    assert_eq!(ids(&javascript_security.rules), vec!["no-eval"]);
+}
+
+#[tokio::test]
+async fn test_updates_replace_the_rule_and_its_tags() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = SqliteRuleStorage::open(&temp_dir.path().join("rules.db")).unwrap();
+    let first = "id: no-eval\nlanguage: javascript\ntags: [security]\nrule:\n  pattern: eval($A)\n";
+    let second = "id: no-eval\nlanguage: javascript\ntags: [style]\nrule:\n  pattern: eval($$$)\n";
+
+    let created = storage.create_rule(create(first, false)).await.unwrap();
+    assert!(created.created);
+    assert!(storage.create_rule(create(second, false)).await.is_err());
+    let updated = storage.create_rule(create(second, true)).await.unwrap();
+    assert!(!updated.created);
+
+    let security = storage
+        .list_rules(list(None, Some("security")))
+        .await
+        .unwrap();
+    assert!(security.rules.is_empty());
+    let rule = storage
+        .get_rule(GetRuleParam {
+            rule_id: "no-eval".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(rule.rule_config.tags, vec!["style"]);
+    assert_eq!(storage.rule_sources().unwrap()[0].1, second);
+
+    let deleted = storage
+        .delete_rule(DeleteRuleParam {
+            rule_id: "no-eval".to_string(),
+        })
+        .await
+        .unwrap();
+    assert!(deleted.deleted);
+    assert!(storage.load_all_rules().await.unwrap().is_empty());
+
+    // The database outlives the storage that wrote it
+    storage.create_rule(create(first, false)).await.unwrap();
+    drop(storage);
+    let reopened = SqliteRuleStorage::open(&temp_dir.path().join("rules.db")).unwrap();
+    assert_eq!(reopened.load_all_rules().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_service_stores_rules_in_the_configured_database() {
+    let temp_dir = TempDir::new().unwrap();
+    let shared_rules = temp_dir.path().join("shared");
+    fs::create_dir_all(&shared_rules).unwrap();
+    fs::write(
+        shared_rules.join("no-alert.yaml"),
+        "id: no-alert\nlanguage: javascript\nrule:\n  pattern: alert($A)\n",
+    )
+    .unwrap();
+    let database = temp_dir.path().join("rules.db");
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        rules_database: Some(database.clone()),
+        additional_rule_dirs: vec![shared_rules],
+        ..Default::default()
+    });
+
+    service
+        .create_rule(create(
+            "id: no-eval\nlanguage: javascript\nrule:\n  pattern: eval($A)\n",
+            false,
+        ))
+        .await
+        .unwrap();
+
+    assert!(database.exists());
+    assert!(!temp_dir.path().join(".rules/no-eval.yaml").exists());
+    let listed = service.list_rules(list(None, None)).await.unwrap();
+    assert_eq!(ids(&listed.rules), vec!["no-eval", "no-alert"]);
+}
+
+#[tokio::test]
+async fn test_imported_rules_keep_their_pack() {
+    let temp_dir = TempDir::new().unwrap();
+    let storage = SqliteRuleStorage::open(&temp_dir.path().join("rules.db")).unwrap();
+    let rules = vec![
+        "id: no-eval\nlanguage: javascript\nrule:\n  pattern: eval($A)\n".to_string(),
+        "id: no-log\nlanguage: javascript\nrule:\n  pattern: console.log($A)\n".to_string(),
+    ];
+
+    storage
+        .import_rules(rules.clone(), Some("security".to_string()), false)
+        .await
+        .unwrap();
+    let listed = storage.list_rules(list(None, None)).await.unwrap().rules;
+    assert!(
+        listed
+            .iter()
+            .all(|rule| rule.pack.as_deref() == Some("security"))
+    );
+    let stored = storage.stored_rules().unwrap();
+    assert_eq!(stored[0].content, rules[0]);
+
+    // Nothing is stored when one rule already exists
+    let error = storage
+        .import_rules(
+            vec![
+                "id: no-alert\nlanguage: javascript\nrule:\n  pattern: alert($A)\n".to_string(),
+                rules[0].clone(),
+            ],
+            None,
+            false,
+        )
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("already exists"), "{error}");
+    assert_eq!(storage.stored_rules().unwrap().len(), 2);
+
+    storage
+        .delete_rule(DeleteRuleParam {
+            rule_id: "no-eval".to_string(),
+        })
+        .await
+        .unwrap();
+    storage.create_rule(create(&rules[0], false)).await.unwrap();
+    let listed = storage.list_rules(list(None, None)).await.unwrap().rules;
+    assert_eq!(listed[0].pack, None);
+}
@@ -0,0 +1,151 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::errors::ServiceError;
+use ast_grep_mcp::types::{CursorParam, CursorResult, FileSearchParam};
+use rmcp::model::ErrorData;
+use std::fs;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn setup(cursor_ttl: Duration) -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    for name in ["a.js", "b.js", "c.js"] {
+        fs::write(temp_dir.path().join(name), "console.log(1);\n").unwrap();
+    }
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        cursor_ttl,
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn search(cursor: Option<CursorParam>) -> FileSearchParam {
+    FileSearchParam {
+        path_pattern: "**/*.js".to_string(),
+        pattern: "console.log($A)".to_string(),
+        language: "javascript".to_string(),
+        max_results: 1,
+        cursor,
+        ..Default::default()
+    }
+}
+
+fn resume(cursor: CursorResult) -> Option<CursorParam> {
+    Some(CursorParam {
+        last_file_path: cursor.last_file_path,
+        is_complete: cursor.is_complete,
+        token: cursor.token,
+    })
+}
+
+fn assert_stale(error: ServiceError) {
+    assert!(matches!(error, ServiceError::StaleCursor(_)), "{error}");
+    assert!(error.to_string().contains("without a cursor"), "{error}");
+    let data = ErrorData::from(error).data.unwrap();
+    assert_eq!(data["code"], "STALE_CURSOR");
+}
+
+#[tokio::test]
+async fn test_stamped_cursors_page_through_every_file() {
+    let (service, _temp_dir) = setup(Duration::from_secs(60));
+    let mut param = search(None);
+    let mut files = Vec::new();
+    loop {
+        let result = service.file_search(param.clone()).await.unwrap();
+        files.extend(result.matches.into_iter().map(|file| file.file_path));
+        match result.next_cursor {
+            Some(cursor) if !cursor.is_complete => {
+                assert!(cursor.token.is_some());
+                param = FileSearchParam {
+                    // Page size may change between pages
+                    max_results: 2,
+                    ..search(resume(cursor))
+                };
+            }
+            Some(cursor) => {
+                assert!(cursor.token.is_none());
+                break;
+            }
+            None => break,
+        }
+    }
+    assert_eq!(files.len(), 3);
+}
+
+#[tokio::test]
+async fn test_cursor_for_other_parameters_is_stale() {
+    let (service, _temp_dir) = setup(Duration::from_secs(60));
+    let cursor = service.file_search(search(None)).await.unwrap().next_cursor;
+    let error = service
+        .file_search(FileSearchParam {
+            path_pattern: "*.js".to_string(),
+            ..search(resume(cursor.unwrap()))
+        })
+        .await
+        .unwrap_err();
+    assert_stale(error);
+}
+
+#[tokio::test]
+async fn test_altered_cursor_is_stale() {
+    let (service, _temp_dir) = setup(Duration::from_secs(60));
+    let mut cursor = resume(
+        service
+            .file_search(search(None))
+            .await
+            .unwrap()
+            .next_cursor
+            .unwrap(),
+    );
+    cursor.as_mut().unwrap().last_file_path.push('x');
+    assert_stale(service.file_search(search(cursor)).await.unwrap_err());
+
+    let mut cursor = resume(
+        service
+            .file_search(search(None))
+            .await
+            .unwrap()
+            .next_cursor
+            .unwrap(),
+    );
+    cursor.as_mut().unwrap().token = Some("not-a-token".to_string());
+    assert_stale(service.file_search(search(cursor)).await.unwrap_err());
+}
+
+#[tokio::test]
+async fn test_expired_cursor_is_stale() {
+    let (service, _temp_dir) = setup(Duration::ZERO);
+    let cursor = service.file_search(search(None)).await.unwrap().next_cursor;
+    let error = service
+        .file_search(search(resume(cursor.unwrap())))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("expired"), "{error}");
+    assert_stale(error);
+}
+
+#[tokio::test]
+async fn test_cursor_from_other_roots_is_stale() {
+    let (service, _temp_dir) = setup(Duration::from_secs(60));
+    let (other, _other_dir) = setup(Duration::from_secs(60));
+    let cursor = service.file_search(search(None)).await.unwrap().next_cursor;
+    let error = other
+        .file_search(search(resume(cursor.clone().unwrap())))
+        .await
+        .unwrap_err();
+    assert_stale(error);
+
+    // A cursor without a token must at least name a file below the root directories
+    let tokenless = CursorParam {
+        token: None,
+        ..resume(cursor.unwrap()).unwrap()
+    };
+    assert_stale(
+        other
+            .file_search(search(Some(tokenless.clone())))
+            .await
+            .unwrap_err(),
+    );
+    assert!(service.file_search(search(Some(tokenless))).await.is_ok());
+}
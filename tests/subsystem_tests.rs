@@ -76,6 +76,13 @@ rule:
             max_results: 100,
             max_file_size: 1024 * 1024,
             cursor: None,
+            output_file: None,
+            workspace: None,
+            timeout_ms: None,
+            max_matches: None,
+            walk: Default::default(),
+            output_format: Default::default(),
+            pack: None,
         };
 
         let result = service.rule_search(param).await.unwrap();
@@ -153,6 +160,13 @@ rule:
         max_results: 100,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result = service.rule_search(param).await.unwrap();
@@ -217,17 +231,17 @@ function standalone() {
             3, // All 3 methods in the class
         ),
         (
-            "has:\n  pattern: console.log($MSG)",
+            "has:\n  pattern: console.log($MSG)\n  stopBy: end",
             "Methods containing console.log",
             2, // debug() and calculate()
         ),
         (
-            "inside:\n  pattern: class $CLASS { $METHODS }",
+            "inside:\n  pattern: class $CLASS { $METHODS }\n  stopBy: end",
             "Methods inside a class",
             3, // All methods in MyClass
         ),
         (
-            "not:\n  has:\n    pattern: return $VALUE",
+            "not:\n  has:\n    pattern: return $VALUE\n    stopBy: end",
             "Methods without return statements",
             2, // debug() and process()
         ),
@@ -249,6 +263,13 @@ rule:
             max_results: 100,
             max_file_size: 1024 * 1024,
             cursor: None,
+            output_file: None,
+            workspace: None,
+            timeout_ms: None,
+            max_matches: None,
+            walk: Default::default(),
+            output_format: Default::default(),
+            pack: None,
         };
 
         let result = service.rule_search(param).await.unwrap();
@@ -316,11 +337,14 @@ rule:
     - kind: method_definition
     - has:
         pattern: console.log($MSG)
+        stopBy: end
     - inside:
         pattern: class $CLASS { $$$METHODS }
+        stopBy: end
     - not:
         has:
           pattern: return $VALUE
+          stopBy: end
 "#;
 
     let param = RuleSearchParam {
@@ -329,6 +353,13 @@ rule:
         max_results: 100,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result = service.rule_search(param).await.unwrap();
@@ -388,6 +419,13 @@ rule:
         max_results: 100,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let result = service.rule_search(param).await.unwrap();
@@ -464,6 +502,13 @@ rule:
             max_results: 100,
             max_file_size: 1024 * 1024,
             cursor: None,
+            output_file: None,
+            workspace: None,
+            timeout_ms: None,
+            max_matches: None,
+            walk: Default::default(),
+            output_format: Default::default(),
+            pack: None,
         };
 
         let result = service.rule_search(param).await.unwrap();
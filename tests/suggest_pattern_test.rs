@@ -0,0 +1,119 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::learning::{PatternExample, SuggestPatternParam, SuggestPatternResult};
+use ast_grep_mcp::response_formatter::ResponseFormatter;
+
+fn example(before: &str, after: &str) -> PatternExample {
+    PatternExample {
+        before: before.to_string(),
+        after: after.to_string(),
+    }
+}
+
+async fn suggest(examples: Vec<PatternExample>, language: &str) -> SuggestPatternResult {
+    AstGrepService::new()
+        .suggest_pattern(SuggestPatternParam {
+            examples,
+            language: language.to_string(),
+            max_candidates: 5,
+        })
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_single_example_generalizes_kept_arguments() {
+    let result = suggest(
+        vec![example(
+            "function f() {\n  console.log(user.name);\n}\n",
+            "function f() {\n  logger.info(user.name);\n}\n",
+        )],
+        "javascript",
+    )
+    .await;
+
+    let best = &result.candidates[0];
+    assert_eq!(best.pattern, "console.log($A)");
+    assert_eq!(best.replacement, "logger.info($A)");
+    assert_eq!(best.strategy, "generalized");
+    assert_eq!(best.examples_reproduced, 1);
+    assert!(
+        result
+            .candidates
+            .iter()
+            .any(|c| c.strategy == "literal" && c.pattern == "console.log")
+    );
+}
+
+#[tokio::test]
+async fn test_candidates_are_ranked_by_examples_reproduced() {
+    let result = suggest(
+        vec![
+            example("let a = foo(1, b);", "let a = bar(b, 1);"),
+            example("let c = foo(x + 1, y);", "let c = bar(y, x + 1);"),
+        ],
+        "rust",
+    )
+    .await;
+
+    let best = &result.candidates[0];
+    assert_eq!(best.pattern, "foo($A, $B)");
+    assert_eq!(best.replacement, "bar($B, $A)");
+    assert_eq!(best.examples_reproduced, 2);
+    assert_eq!(best.score, 1.0);
+    assert_eq!(best.validation[1].rewritten, "let c = bar(y, x + 1);");
+    // The literal from the first example doesn't match the second
+    let literal = result
+        .candidates
+        .iter()
+        .find(|c| c.pattern == "foo(1, b)")
+        .unwrap();
+    assert_eq!(literal.examples_reproduced, 1);
+    assert!(!literal.validation[1].reproduces_after);
+}
+
+#[tokio::test]
+async fn test_unchanged_examples_are_noted() {
+    let result = suggest(
+        vec![
+            example("x = 1\n", "x = 1\n"),
+            example("print(x)\n", "log(x)\n"),
+        ],
+        "python",
+    )
+    .await;
+
+    assert_eq!(result.notes, ["Example 0: before and after are identical"]);
+    assert!(result.candidates.iter().all(|c| c.examples_matched == 1));
+    assert!(
+        result
+            .candidates
+            .iter()
+            .any(|c| c.pattern == "print($A)" && c.replacement == "log($A)")
+    );
+
+    let summary = ResponseFormatter::format_suggest_pattern_result(&result);
+    // The unchanged example is reproduced by leaving it alone
+    assert!(summary.contains("`print($A)` → `log($A)` (generalized, reproduces 2/2, matches 1/2)"));
+}
+
+#[tokio::test]
+async fn test_invalid_requests_are_errors() {
+    let service = AstGrepService::new();
+    let no_examples = service
+        .suggest_pattern(SuggestPatternParam {
+            examples: vec![],
+            language: "rust".to_string(),
+            max_candidates: 5,
+        })
+        .await;
+    assert!(no_examples.is_err());
+
+    let unknown_language = service
+        .suggest_pattern(SuggestPatternParam {
+            examples: vec![example("a", "b")],
+            language: "cobol".to_string(),
+            max_candidates: 5,
+        })
+        .await;
+    assert!(unknown_language.is_err());
+}
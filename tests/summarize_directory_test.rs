@@ -0,0 +1,119 @@
+mod common;
+
+use ast_grep_mcp::types::SummarizeDirectoryParam;
+use common::create_service;
+use std::fs;
+use tempfile::TempDir;
+
+fn write_project(temp_dir: &TempDir) {
+    let src = temp_dir.path().join("src");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(
+        src.join("lib.rs"),
+        "pub struct Parser;\n\nimpl Parser {\n    pub fn parse(&self) -> usize {\n        let a = 1;\n        a + 1\n    }\n}\n\nfn helper() {}\n",
+    )
+    .unwrap();
+    fs::write(
+        src.join("app.py"),
+        "class App:\n    def run(self):\n        pass\n\n\ndef main():\n    App().run()\n",
+    )
+    .unwrap();
+    fs::write(src.join("notes.txt"), "not source code\n").unwrap();
+    fs::write(temp_dir.path().join("build.rs"), "fn main() {}\n").unwrap();
+}
+
+#[tokio::test]
+async fn test_summarize_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    write_project(&temp_dir);
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .summarize_directory(SummarizeDirectoryParam {
+            directory: "src".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let files: Vec<&str> = result
+        .files
+        .iter()
+        .map(|file| file.file_path.rsplit('/').next().unwrap())
+        .collect();
+    assert_eq!(files, vec!["app.py", "lib.rs"]);
+    assert_eq!(result.languages["python"], 1);
+    assert_eq!(result.languages["rust"], 1);
+
+    let rust = &result.files[1];
+    assert_eq!(rust.lines_of_code, 8);
+    assert_eq!(rust.functions, 2);
+    assert_eq!(rust.classes, 1);
+    assert_eq!(rust.largest_functions[0].name, "Parser.parse");
+    assert_eq!(rust.largest_functions[0].kind, "method");
+    assert_eq!(rust.largest_functions[0].lines, 4);
+
+    assert_eq!(result.total_functions, 4);
+    assert_eq!(result.total_classes, 2);
+    assert_eq!(result.largest_functions[0].name, "Parser.parse");
+    assert!(
+        result.largest_functions[0]
+            .file_path
+            .as_deref()
+            .unwrap()
+            .ends_with("lib.rs")
+    );
+    assert!(!result.truncated);
+}
+
+#[tokio::test]
+async fn test_summarize_directory_reuses_unchanged_files() {
+    let temp_dir = TempDir::new().unwrap();
+    write_project(&temp_dir);
+    let service = create_service(&temp_dir);
+    let param = SummarizeDirectoryParam {
+        directory: "src".to_string(),
+        ..Default::default()
+    };
+
+    let first = service.summarize_directory(param.clone()).await.unwrap();
+    assert_eq!(first.cached_files, 0);
+
+    fs::write(
+        temp_dir.path().join("src/app.py"),
+        "def main():\n    pass\n\ndef other():\n    pass\n",
+    )
+    .unwrap();
+    let second = service.summarize_directory(param).await.unwrap();
+    assert_eq!(second.cached_files, 1);
+    assert_eq!(second.files[0].functions, 2);
+    assert_eq!(second.files[0].classes, 0);
+}
+
+#[tokio::test]
+async fn test_summarize_directory_limits() {
+    let temp_dir = TempDir::new().unwrap();
+    write_project(&temp_dir);
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .summarize_directory(SummarizeDirectoryParam {
+            max_files: 2,
+            largest_functions: 1,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(result.truncated);
+    assert_eq!(result.files.len(), 2);
+    assert!(result.files.iter().all(|f| f.largest_functions.len() <= 1));
+    assert_eq!(result.largest_functions.len(), 1);
+
+    let missing = service
+        .summarize_directory(SummarizeDirectoryParam {
+            directory: "no-such-dir".to_string(),
+            ..Default::default()
+        })
+        .await;
+    assert!(missing.is_err());
+}
@@ -0,0 +1,110 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::RuleReplaceParam;
+use ast_grep_mcp::{FileReplaceParam, ReplaceParam};
+use std::fs;
+use tempfile::TempDir;
+
+async fn replace(code: &str, pattern: &str, replacement: &str) -> String {
+    AstGrepService::new()
+        .replace(ReplaceParam::new(code, pattern, replacement, "javascript"))
+        .await
+        .unwrap()
+        .new_code
+}
+
+#[tokio::test]
+async fn test_case_functions_in_replacement() {
+    assert_eq!(
+        replace(
+            "const maxRetryCount = 3;",
+            "const $NAME = $VALUE;",
+            "const ${upper($NAME)} = $VALUE;"
+        )
+        .await,
+        "const MAXRETRYCOUNT = 3;"
+    );
+    assert_eq!(
+        replace(
+            "track(userSignedIn, data);",
+            "track($EVENT, $$$REST)",
+            "track('${snake_case($EVENT)}', ${lower($$$REST)})"
+        )
+        .await,
+        "track('user_signed_in', data);"
+    );
+}
+
+#[tokio::test]
+async fn test_template_literals_are_left_alone() {
+    assert_eq!(
+        replace("log(name);", "log($A)", "log(`${$A}: ${camelCase($A)}`)").await,
+        "log(`${name}: name`);"
+    );
+}
+
+#[tokio::test]
+async fn test_malformed_call_is_rejected() {
+    let result = AstGrepService::new()
+        .replace(ReplaceParam::new(
+            "getName();",
+            "$F()",
+            "${strip_prefix($F)}()",
+            "javascript",
+        ))
+        .await;
+    assert!(result.unwrap_err().to_string().contains("strip_prefix"));
+}
+
+#[tokio::test]
+async fn test_functions_in_file_replace_and_rule_fix() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("user.js");
+    fs::write(&file, "user.get_name();\nuser.get_email_address();\n").unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..Default::default()
+    });
+
+    service
+        .file_replace(FileReplaceParam {
+            path_pattern: "*.js".to_string(),
+            pattern: "$OBJ.$METHOD()".to_string(),
+            replacement: "$OBJ.${camelCase($METHOD)}()".to_string(),
+            language: "javascript".to_string(),
+            dry_run: false,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "user.getName();\nuser.getEmailAddress();\n"
+    );
+
+    let result = service
+        .rule_replace(RuleReplaceParam {
+            rule_config: "id: drop-getters\nlanguage: javascript\nrule:\n  pattern: $OBJ.$METHOD()\nfix: $OBJ.${strip_prefix($METHOD, 'get')}\n"
+                .to_string(),
+            path_pattern: Some("*.js".to_string()),
+            max_results: 100,
+            max_file_size: 1024 * 1024,
+            dry_run: false,
+            summary_only: false,
+            cursor: None,
+            allow_unsafe: false,
+            workspace: None,
+            ignore_whitespace_changes: true,
+            timeout_ms: None,
+            walk: Default::default(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.total_changes, 2);
+    assert_eq!(result.file_results[0].changes[0].new_text, "user.Name");
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "user.Name;\nuser.EmailAddress;\n"
+    );
+}
@@ -38,6 +38,11 @@ async fn test_automatic_js_in_html_search() {
         context_before: None,
         context_after: None,
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result = service.search(param).await.unwrap();
@@ -94,6 +99,11 @@ async fn test_automatic_css_in_html_search() {
         context_before: None,
         context_after: None,
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result = service.search(param).await.unwrap();
@@ -159,6 +169,22 @@ async fn test_automatic_js_in_html_file() {
         context_before: None,
         context_after: None,
         context_lines: None,
+        output_file: None,
+        overlays: Default::default(),
+        dialect: None,
+        language_patterns: Default::default(),
+        search_archives: false,
+        workspace: None,
+        regex_fallback: false,
+        include_ast_context: false,
+        allow_broad: false,
+        revision: None,
+        suggest_fixes: false,
+        timeout_ms: None,
+        walk: Default::default(),
+        stream: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result = service.file_search(param).await.unwrap();
@@ -211,6 +237,22 @@ greet("World");
         context_before: None,
         context_after: None,
         context_lines: None,
+        output_file: None,
+        overlays: Default::default(),
+        dialect: None,
+        language_patterns: Default::default(),
+        search_archives: false,
+        workspace: None,
+        regex_fallback: false,
+        include_ast_context: false,
+        allow_broad: false,
+        revision: None,
+        suggest_fixes: false,
+        timeout_ms: None,
+        walk: Default::default(),
+        stream: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result = service.file_search(param).await.unwrap();
@@ -282,6 +324,22 @@ h1 {
         context_before: None,
         context_after: None,
         context_lines: None,
+        output_file: None,
+        overlays: Default::default(),
+        dialect: None,
+        language_patterns: Default::default(),
+        search_archives: false,
+        workspace: None,
+        regex_fallback: false,
+        include_ast_context: false,
+        allow_broad: false,
+        revision: None,
+        suggest_fixes: false,
+        timeout_ms: None,
+        walk: Default::default(),
+        stream: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result = service.file_search(param).await.unwrap();
@@ -8,7 +8,6 @@ use std::fs;
 use tempfile::TempDir;
 
 #[tokio::test]
-#[ignore = "TODO: Implement file replacement logic"]
 async fn test_complete_sgconfig_workflow() {
     // Setup a realistic project structure
     let project_dir = TempDir::new().unwrap();
@@ -172,6 +171,22 @@ rule:
         context_before: Some(2),
         context_after: Some(2),
         context_lines: None,
+        output_file: None,
+        overlays: Default::default(),
+        dialect: None,
+        language_patterns: Default::default(),
+        search_archives: false,
+        workspace: None,
+        regex_fallback: false,
+        include_ast_context: false,
+        allow_broad: false,
+        revision: None,
+        suggest_fixes: false,
+        timeout_ms: None,
+        walk: Default::default(),
+        stream: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let search_result = service.file_search(search_param).await.unwrap();
@@ -188,6 +203,13 @@ rule:
         max_results: 100,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        workspace: None,
+        timeout_ms: None,
+        max_matches: None,
+        walk: Default::default(),
+        output_format: Default::default(),
+        pack: None,
     };
 
     let rule_search_result = service.rule_search(rule_search_param).await.unwrap();
@@ -202,6 +224,11 @@ rule:
         dry_run: true,
         summary_only: false,
         cursor: None,
+        allow_unsafe: false,
+        workspace: None,
+        ignore_whitespace_changes: true,
+        timeout_ms: None,
+        walk: Default::default(),
     };
 
     let replace_result = service.rule_replace(rule_replace_param).await.unwrap();
@@ -234,6 +261,22 @@ rule:
         context_before: None,
         context_after: None,
         context_lines: None,
+        output_file: None,
+        overlays: Default::default(),
+        dialect: None,
+        language_patterns: Default::default(),
+        search_archives: false,
+        workspace: None,
+        regex_fallback: false,
+        include_ast_context: false,
+        allow_broad: false,
+        revision: None,
+        suggest_fixes: false,
+        timeout_ms: None,
+        walk: Default::default(),
+        stream: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let equality_result = service.file_search(equality_search).await.unwrap();
@@ -244,6 +287,7 @@ rule:
     let list_param = ListRulesParam {
         language: None,
         severity: None,
+        tag: None,
     };
     let list_result = service.list_rules(list_param).await.unwrap();
 
@@ -45,6 +45,11 @@ function transform(item) {
         context_before: Some(2),
         context_after: Some(1),
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result = search_service.search(param).await.unwrap();
@@ -96,6 +101,11 @@ TARGET_LINE;
         context_before: None,
         context_after: None,
         context_lines: Some(2),
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result = search_service.search(param).await.unwrap();
@@ -151,6 +161,22 @@ function example() {
         max_results: 10,
         max_file_size: 1024 * 1024,
         cursor: None,
+        output_file: None,
+        overlays: Default::default(),
+        dialect: None,
+        language_patterns: Default::default(),
+        search_archives: false,
+        workspace: None,
+        regex_fallback: false,
+        include_ast_context: false,
+        allow_broad: false,
+        revision: None,
+        suggest_fixes: false,
+        timeout_ms: None,
+        walk: Default::default(),
+        stream: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result = search_service.file_search(param).await.unwrap();
@@ -196,6 +222,11 @@ async fn test_context_lines_edge_cases() {
         context_before: Some(3),
         context_after: Some(2),
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result = search_service.search(param).await.unwrap();
@@ -224,6 +255,11 @@ MATCH_HERE;"#;
         context_before: Some(2),
         context_after: Some(3),
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result2 = search_service.search(param2).await.unwrap();
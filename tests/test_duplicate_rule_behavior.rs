@@ -77,6 +77,7 @@ rule:
     let list_param = ListRulesParam {
         language: None,
         severity: None,
+        tag: None,
     };
 
     let rules = service.list_rules(list_param).await.unwrap();
@@ -180,6 +181,7 @@ rule:
     let list_param = ListRulesParam {
         language: Some("javascript".to_string()),
         severity: None,
+        tag: None,
     };
 
     let rules = service.list_rules(list_param).await.unwrap();
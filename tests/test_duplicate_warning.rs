@@ -78,6 +78,7 @@ rule:
     let list_param = ListRulesParam {
         language: None,
         severity: None,
+        tag: None,
     };
 
     let rules = service.list_rules(list_param).await.unwrap();
@@ -152,6 +153,7 @@ rule:
     let list_param = ListRulesParam {
         language: None,
         severity: None,
+        tag: None,
     };
 
     let rules = service.list_rules(list_param).await.unwrap();
@@ -68,6 +68,11 @@ let param = FileSearchParam {
             context_before: None,
             context_after: None,
             context_lines: None,
+            dialect: None,
+            buffer_id: None,
+            include_ast_context: false,
+            context_scope: None,
+            include_leading_trivia: false,
         };
 
         let search_result = search_service.search(search_param).await.unwrap();
@@ -94,6 +99,11 @@ let param = FileSearchParam {
         strictness: None,
         selector: None,
         context: None,
+        verify_formatting: false,
+        buffer_id: None,
+        json_patch: false,
+        rewrite_selector: None,
+        format_after_replace: false,
     };
 
     let simple_result = replace_service.replace(simple_param).await.unwrap();
@@ -123,6 +133,11 @@ let param = FileSearchParam {
         strictness: None,
         selector: None,
         context: None,
+        verify_formatting: false,
+        buffer_id: None,
+        json_patch: false,
+        rewrite_selector: None,
+        format_after_replace: false,
     };
 
     let result = replace_service.replace(param).await.unwrap();
@@ -218,6 +233,11 @@ let other = AnotherStruct {
         strictness: None,
         selector: None,
         context: None,
+        verify_formatting: false,
+        buffer_id: None,
+        json_patch: false,
+        rewrite_selector: None,
+        format_after_replace: false,
     };
 
     let result = replace_service.replace(param).await.unwrap();
@@ -290,6 +310,11 @@ let param = FileSearchParam {
         context_before: Some(5),
         context_after: Some(2),
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result = search_service.search(param).await.unwrap();
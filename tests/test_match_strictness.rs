@@ -23,6 +23,11 @@ fn test_search_param_with_strictness() {
         context_before: None,
         context_after: None,
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let json = serde_json::to_string(&param).unwrap();
@@ -39,6 +44,11 @@ fn test_search_param_with_strictness() {
         context_before: None,
         context_after: None,
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let json = serde_json::to_string(&param_without).unwrap();
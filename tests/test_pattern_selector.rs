@@ -12,6 +12,11 @@ fn test_search_param_with_selector() {
         context_before: None,
         context_after: None,
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let json = serde_json::to_string(&param).unwrap();
@@ -41,6 +46,22 @@ fn test_file_search_param_with_selector() {
         context_before: None,
         context_after: None,
         context_lines: None,
+        output_file: None,
+        overlays: Default::default(),
+        dialect: None,
+        language_patterns: Default::default(),
+        search_archives: false,
+        workspace: None,
+        regex_fallback: false,
+        include_ast_context: false,
+        allow_broad: false,
+        revision: None,
+        suggest_fixes: false,
+        timeout_ms: None,
+        walk: Default::default(),
+        stream: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let json = serde_json::to_string(&param).unwrap();
@@ -58,6 +79,11 @@ fn test_replace_param_with_selector() {
         strictness: None,
         selector: Some("field_definition".to_string()),
         context: Some("class X { $PATTERN }".to_string()),
+        verify_formatting: false,
+        buffer_id: None,
+        json_patch: false,
+        rewrite_selector: None,
+        format_after_replace: false,
     };
 
     let json = serde_json::to_string(&param).unwrap();
@@ -106,6 +132,11 @@ mod integration_tests {
             context_before: None,
             context_after: None,
             context_lines: None,
+            dialect: None,
+            buffer_id: None,
+            include_ast_context: false,
+            context_scope: None,
+            include_leading_trivia: false,
         };
 
         // The actual test would verify only the field is matched, not the variable
@@ -207,6 +207,12 @@ fn create_sample_match_result() -> ast_grep_mcp::types::MatchResult {
         vars: std::collections::HashMap::new(),
         context_before: None,
         context_after: None,
+        enclosing_symbol: None,
+        ast_context: None,
+        quick_fixes: Vec::new(),
+        enclosing_node: None,
+        leading_trivia: Vec::new(),
+        rule_id: None,
     }
 }
 
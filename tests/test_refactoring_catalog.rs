@@ -1,4 +1,3 @@
-use std::fs;
 use ast_grep_mcp::refactoring::catalog::RefactoringCatalog;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -18,6 +18,12 @@ fn test_search_result_formatting() {
                 vars: vars.clone(),
                 context_before: None,
                 context_after: None,
+                enclosing_symbol: None,
+                ast_context: None,
+                quick_fixes: Vec::new(),
+                enclosing_node: None,
+                leading_trivia: Vec::new(),
+                rule_id: None,
             },
             MatchResult {
                 text: "console.log('world')".to_string(),
@@ -28,6 +34,12 @@ fn test_search_result_formatting() {
                 vars: HashMap::new(),
                 context_before: None,
                 context_after: None,
+                enclosing_symbol: None,
+                ast_context: None,
+                quick_fixes: Vec::new(),
+                enclosing_node: None,
+                leading_trivia: Vec::new(),
+                rule_id: None,
             },
         ],
         matches_summary: None,
@@ -71,11 +83,25 @@ fn test_file_search_result_formatting() {
                 vars: HashMap::new(),
                 context_before: None,
                 context_after: None,
+                enclosing_symbol: None,
+                ast_context: None,
+                quick_fixes: Vec::new(),
+                enclosing_node: None,
+                leading_trivia: Vec::new(),
+                rule_id: None,
             }],
             file_hash: "abc123".to_string(),
+            language: None,
+            workspace: None,
+            engine: None,
         }],
         next_cursor: None,
         total_files_found: 1,
+        skipped_files: Vec::new(),
+        budget_exceeded: false,
+        timed_out: false,
+        stats: None,
+        warnings: Vec::new(),
     };
 
     let summary = ResponseFormatter::format_file_search_result(&result);
@@ -94,6 +120,11 @@ fn test_file_search_result_no_matches() {
         matches: vec![],
         next_cursor: None,
         total_files_found: 0,
+        skipped_files: Vec::new(),
+        budget_exceeded: false,
+        timed_out: false,
+        stats: None,
+        warnings: Vec::new(),
     };
 
     let summary = ResponseFormatter::format_file_search_result(&result);
@@ -102,6 +133,40 @@ fn test_file_search_result_no_matches() {
     assert!(summary.contains("No files matched the search pattern"));
 }
 
+#[test]
+fn test_file_search_result_scan_stats() {
+    let mut stats = FileScanStats {
+        files_matching_glob: 4,
+        skipped_too_large: 1,
+        ..Default::default()
+    };
+    stats.record_searched("src/a.js", true);
+    stats.record_searched("src/b.ts", false);
+    stats.record_searched("Makefile", false);
+    let result = FileSearchResult {
+        matches: vec![],
+        next_cursor: None,
+        total_files_found: 3,
+        skipped_files: Vec::new(),
+        budget_exceeded: false,
+        timed_out: false,
+        stats: Some(stats),
+        warnings: Vec::new(),
+    };
+
+    let summary = ResponseFormatter::format_file_search_result(&result);
+    assert!(summary.contains(
+        "3 of 4 files matching the glob ((none) 1, .js 1, .ts 1); skipped 1 over max_file_size"
+    ));
+
+    let empty = FileSearchResult {
+        stats: Some(FileScanStats::default()),
+        ..result
+    };
+    let summary = ResponseFormatter::format_file_search_result(&empty);
+    assert!(summary.contains("no files match path_pattern"));
+}
+
 #[test]
 fn test_replace_result_formatting() {
     let result = ReplaceResult {
@@ -112,6 +177,8 @@ fn test_replace_result_formatting() {
                 end_line: 1,
                 start_col: 0,
                 end_col: 20,
+                start_byte: 0,
+                end_byte: 20,
                 old_text: "console.log('hello')".to_string(),
                 new_text: "logger.info('hello')".to_string(),
             },
@@ -120,10 +187,15 @@ fn test_replace_result_formatting() {
                 end_line: 1,
                 start_col: 22,
                 end_col: 42,
+                start_byte: 22,
+                end_byte: 42,
                 old_text: "console.log('world')".to_string(),
                 new_text: "logger.info('world')".to_string(),
             },
         ],
+        format_check: None,
+        format_error: None,
+        json_patch: None,
     };
 
     let summary = ResponseFormatter::format_replace_result(&result);
@@ -170,6 +242,8 @@ fn test_generate_ast_result_formatting() {
         ],
         ast: "(program (function_declaration name: (identifier) body: (statement_block)))"
             .to_string(),
+        node_kind_stats: vec![],
+        kind_counts: Default::default(),
     };
 
     let summary = ResponseFormatter::format_generate_ast_result(&result);
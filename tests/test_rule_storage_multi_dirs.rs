@@ -1,6 +1,6 @@
 //! Test RuleStorage with multiple rule directories
 
-use ast_grep_mcp::rules::RuleStorage;
+use ast_grep_mcp::rules::{FileRuleStorage, RuleStorage};
 use ast_grep_mcp::rules::types::{CreateRuleParam, DeleteRuleParam, GetRuleParam, ListRulesParam};
 use std::fs;
 use tempfile::TempDir;
@@ -10,7 +10,7 @@ async fn test_rule_storage_single_directory() {
     let temp_dir = TempDir::new().unwrap();
     let rules_dir = temp_dir.path().to_path_buf();
 
-    let storage = RuleStorage::new(rules_dir.clone());
+    let storage = FileRuleStorage::new(rules_dir.clone());
 
     // Create a rule
     let rule_config = r#"
@@ -34,6 +34,7 @@ rule:
     let list_param = ListRulesParam {
         language: Some("javascript".to_string()),
         severity: None,
+        tag: None,
     };
 
     let list_result = storage.list_rules(list_param).await.unwrap();
@@ -84,7 +85,7 @@ rule:
     fs::write(rules_dir3.join("rule-from-dir3.yaml"), rule3).unwrap();
 
     // Create storage with multiple directories
-    let storage = RuleStorage::with_directories(vec![
+    let storage = FileRuleStorage::with_directories(vec![
         rules_dir1.clone(),
         rules_dir2.clone(),
         rules_dir3.clone(),
@@ -94,6 +95,7 @@ rule:
     let list_param = ListRulesParam {
         language: None,
         severity: None,
+        tag: None,
     };
 
     let list_result = storage.list_rules(list_param).await.unwrap();
@@ -103,6 +105,7 @@ rule:
     let js_list_param = ListRulesParam {
         language: Some("javascript".to_string()),
         severity: None,
+        tag: None,
     };
 
     let js_list_result = storage.list_rules(js_list_param).await.unwrap();
@@ -146,12 +149,13 @@ rule:
     fs::write(rules_dir2.join("duplicate-rule.yaml"), rule).unwrap();
 
     // Create storage with multiple directories
-    let storage = RuleStorage::with_directories(vec![rules_dir1.clone(), rules_dir2.clone()]);
+    let storage = FileRuleStorage::with_directories(vec![rules_dir1.clone(), rules_dir2.clone()]);
 
     // List rules should only show one instance
     let list_param = ListRulesParam {
         language: None,
         severity: None,
+        tag: None,
     };
 
     let list_result = storage.list_rules(list_param).await.unwrap();
@@ -181,7 +185,7 @@ rule:
     fs::write(rules_dir1.join("deletable-rule.yaml"), rule).unwrap();
 
     // Create storage with multiple directories
-    let storage = RuleStorage::with_directories(vec![rules_dir1.clone(), rules_dir2.clone()]);
+    let storage = FileRuleStorage::with_directories(vec![rules_dir1.clone(), rules_dir2.clone()]);
 
     // Delete the rule
     let delete_param = DeleteRuleParam {
@@ -203,7 +207,7 @@ async fn test_rule_storage_create_in_primary_dir() {
     let rules_dir2 = temp_dir.path().join("secondary");
 
     // Create storage with multiple directories
-    let storage = RuleStorage::with_directories(vec![rules_dir1.clone(), rules_dir2.clone()]);
+    let storage = FileRuleStorage::with_directories(vec![rules_dir1.clone(), rules_dir2.clone()]);
 
     // Create a new rule
     let rule_config = r#"
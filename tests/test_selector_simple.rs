@@ -37,6 +37,11 @@ async fn test_selector_functionality() {
         context_before: None,
         context_after: None,
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result1 = search_service.search(param1).await.unwrap();
@@ -56,6 +61,11 @@ async fn test_selector_functionality() {
         context_before: None,
         context_after: None,
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result2 = search_service.search(param2).await.unwrap();
@@ -75,6 +85,11 @@ async fn test_selector_functionality() {
         context_before: None,
         context_after: None,
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result3 = search_service.search(param3).await.unwrap();
@@ -144,6 +144,7 @@ async fn test_sgconfig_rule_discovery() {
     let list_param = ListRulesParam {
         language: Some("javascript".to_string()),
         severity: None,
+        tag: None,
     };
 
     let rules = service.list_rules(list_param).await.unwrap();
@@ -183,6 +184,22 @@ async fn test_sgconfig_file_search_with_rules() {
         context_before: None,
         context_after: None,
         context_lines: None,
+        output_file: None,
+        overlays: Default::default(),
+        dialect: None,
+        language_patterns: Default::default(),
+        search_archives: false,
+        workspace: None,
+        regex_fallback: false,
+        include_ast_context: false,
+        allow_broad: false,
+        revision: None,
+        suggest_fixes: false,
+        timeout_ms: None,
+        walk: Default::default(),
+        stream: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result = service.file_search(search_param).await.unwrap();
@@ -333,6 +350,7 @@ rule:
     let list_param = ListRulesParam {
         language: None,
         severity: None,
+        tag: None,
     };
 
     let rules = service.list_rules(list_param).await.unwrap();
@@ -371,6 +389,7 @@ export function Button({ onClick, children }) {
     let list_param = ListRulesParam {
         language: Some("javascript".to_string()),
         severity: None,
+        tag: None,
     };
 
     let rules = service.list_rules(list_param).await.unwrap();
@@ -394,6 +413,7 @@ async fn test_sgconfig_severity_filtering() {
     let warning_param = ListRulesParam {
         language: Some("javascript".to_string()),
         severity: Some("warning".to_string()),
+        tag: None,
     };
 
     let warning_rules = service.list_rules(warning_param).await.unwrap();
@@ -404,6 +424,7 @@ async fn test_sgconfig_severity_filtering() {
     let error_param = ListRulesParam {
         language: Some("javascript".to_string()),
         severity: Some("error".to_string()),
+        tag: None,
     };
 
     let error_rules = service.list_rules(error_param).await.unwrap();
@@ -42,6 +42,11 @@ let param = FileSearchParam {
         context_before: Some(1),
         context_after: Some(1),
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result1 = search_service.search(param1).await.unwrap();
@@ -66,6 +71,11 @@ let param = FileSearchParam {
         context_before: Some(1),
         context_after: Some(1),
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result2 = search_service.search(param2).await.unwrap();
@@ -90,6 +100,11 @@ let param = FileSearchParam {
         context_before: Some(1),
         context_after: Some(1),
         context_lines: None,
+        dialect: None,
+        buffer_id: None,
+        include_ast_context: false,
+        context_scope: None,
+        include_leading_trivia: false,
     };
 
     let result3 = search_service.search(param3).await.unwrap();
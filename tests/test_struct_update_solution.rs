@@ -40,6 +40,11 @@ let param = FileSearchParam {
         strictness: None,
         selector: None,
         context: None,
+        verify_formatting: false,
+        buffer_id: None,
+        json_patch: false,
+        rewrite_selector: None,
+        format_after_replace: false,
     };
 
     let result1 = replace_service.replace(param1).await.unwrap();
@@ -96,6 +101,11 @@ fn example() {
         strictness: None,
         selector: None,
         context: None,
+        verify_formatting: false,
+        buffer_id: None,
+        json_patch: false,
+        rewrite_selector: None,
+        format_after_replace: false,
     };
 
     let result = replace_service.replace(param).await.unwrap();
@@ -109,14 +119,15 @@ fn example() {
 
 #[tokio::test]
 async fn test_rule_based_struct_modification() {
-    use ast_grep_mcp::rules::{RuleEvaluator, RuleReplaceParam, RuleService, RuleStorage};
+    use ast_grep_mcp::rules::{FileRuleStorage, RuleEvaluator, RuleReplaceParam, RuleService};
+    use std::sync::Arc;
 
     let config = ServiceConfig {
         root_directories: vec![PathBuf::from("/tmp")],
         ..Default::default()
     };
 
-    let rule_storage = RuleStorage::new(PathBuf::from("/tmp/rules"));
+    let rule_storage = Arc::new(FileRuleStorage::new(PathBuf::from("/tmp/rules")));
     let rule_evaluator = RuleEvaluator::new();
     let _rule_service = RuleService::new(config, rule_evaluator, rule_storage);
 
@@ -153,6 +164,11 @@ let param = FileSearchParam {
         dry_run: true,
         summary_only: false,
         cursor: None,
+        allow_unsafe: false,
+        workspace: None,
+        ignore_whitespace_changes: true,
+        timeout_ms: None,
+        walk: Default::default(),
     };
 
     // Note: This would require file-based operation
@@ -44,6 +44,11 @@ fn create_param() {
         strictness: None,
         selector: None,
         context: None,
+        verify_formatting: false,
+        buffer_id: None,
+        json_patch: false,
+        rewrite_selector: None,
+        format_after_replace: false,
     };
 
     let result = replace_service.replace(param).await.unwrap();
@@ -96,6 +101,11 @@ fn create_param() {
         strictness: None,
         selector: None,
         context: None,
+        verify_formatting: false,
+        buffer_id: None,
+        json_patch: false,
+        rewrite_selector: None,
+        format_after_replace: false,
     };
 
     let result = replace_service.replace(param).await.unwrap();
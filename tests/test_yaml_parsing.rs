@@ -39,8 +39,8 @@ mod yaml_parsing_tests {
                 println!("Parsing: {:?}", path);
                 
                 let content = fs::read_to_string(&path)
-                    .expect(&format!("Failed to read {:?}", path));
-                
+                    .unwrap_or_else(|e| panic!("Failed to read {path:?}: {e}"));
+
                 let result: Result<RefactoringDefinition, _> = serde_yaml::from_str(&content);
                 
                 match result {
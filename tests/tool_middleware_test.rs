@@ -0,0 +1,167 @@
+mod common;
+
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::tool_middleware::ToolMiddleware;
+use ast_grep_mcp::tool_router::ToolRouter;
+use common::create_service;
+use rmcp::model::{CallToolRequestParam, CallToolResult, Content, ErrorData};
+use serde_json::{Value, json};
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn request(name: &'static str, arguments: Value) -> CallToolRequestParam {
+    CallToolRequestParam {
+        name: name.into(),
+        arguments: arguments.as_object().cloned(),
+    }
+}
+
+/// Rejects non-dry-run replacements whose path pattern points into a protected directory
+struct ProtectDirectory(&'static str);
+
+impl ToolMiddleware for ProtectDirectory {
+    fn before_call(
+        &self,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolRequestParam, ErrorData> {
+        let args = request.arguments.clone().unwrap_or_default();
+        let writes = args.get("dry_run").and_then(Value::as_bool) == Some(false);
+        let path = args
+            .get("path_pattern")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if writes && path.starts_with(self.0) {
+            return Err(ErrorData::invalid_request(
+                format!("writes to {} are not allowed", self.0),
+                None,
+            ));
+        }
+        Ok(request)
+    }
+}
+
+/// Records every call's tool name and whether it succeeded
+#[derive(Clone, Default)]
+struct Audit(Arc<Mutex<Vec<(String, bool)>>>);
+
+impl ToolMiddleware for Audit {
+    fn after_call(
+        &self,
+        tool_name: &str,
+        result: Result<CallToolResult, ErrorData>,
+        _elapsed: Duration,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.0
+            .lock()
+            .unwrap()
+            .push((tool_name.to_string(), result.is_ok()));
+        result
+    }
+}
+
+/// Appends a footer to every successful result
+struct Footer;
+
+impl ToolMiddleware for Footer {
+    fn after_call(
+        &self,
+        _tool_name: &str,
+        result: Result<CallToolResult, ErrorData>,
+        _elapsed: Duration,
+    ) -> Result<CallToolResult, ErrorData> {
+        result.map(|mut result| {
+            result.content.push(Content::text("served by policy proxy"));
+            result
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_pre_call_policy_blocks_writes() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join("vendor")).unwrap();
+    fs::write(temp_dir.path().join("vendor/lib.js"), "var a = 1;\n").unwrap();
+    let service = create_service(&temp_dir);
+    let audit = Audit::default();
+    service.add_middleware(audit.clone());
+    service.add_middleware(ProtectDirectory("vendor/"));
+
+    let blocked = ToolRouter::route_tool_call(
+        &service,
+        request(
+            "file_replace",
+            json!({
+                "path_pattern": "vendor/**/*.js",
+                "pattern": "var $A = $B;",
+                "replacement": "let $A = $B;",
+                "language": "javascript",
+                "dry_run": false
+            }),
+        ),
+    )
+    .await;
+    assert!(blocked.is_err());
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("vendor/lib.js")).unwrap(),
+        "var a = 1;\n"
+    );
+
+    // Previews are still allowed
+    let preview = ToolRouter::route_tool_call(
+        &service,
+        request(
+            "file_replace",
+            json!({
+                "path_pattern": "vendor/**/*.js",
+                "pattern": "var $A = $B;",
+                "replacement": "let $A = $B;",
+                "language": "javascript",
+                "dry_run": true
+            }),
+        ),
+    )
+    .await;
+    assert!(preview.is_ok());
+
+    // The outer audit layer saw both calls, including the rejected one
+    let calls = audit.0.lock().unwrap().clone();
+    assert_eq!(
+        calls,
+        vec![
+            ("file_replace".to_string(), false),
+            ("file_replace".to_string(), true)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_post_call_transformation() {
+    let service = AstGrepService::new();
+    service.add_middleware(Footer);
+
+    let result = ToolRouter::route_tool_call(
+        &service,
+        request(
+            "search",
+            json!({
+                "code": "console.log(1);",
+                "pattern": "console.log($A)",
+                "language": "javascript"
+            }),
+        ),
+    )
+    .await
+    .unwrap();
+
+    let last = result.content.last().unwrap().as_text().unwrap();
+    assert_eq!(last.text, "served by policy proxy");
+}
+
+#[tokio::test]
+async fn test_no_middleware_passes_through() {
+    let service = AstGrepService::new();
+    let result = ToolRouter::route_tool_call(&service, request("list_languages", json!({}))).await;
+    assert!(result.is_ok());
+}
@@ -0,0 +1,141 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::tool_registry::CustomTool;
+use ast_grep_mcp::tool_router::ToolRouter;
+use async_trait::async_trait;
+use rmcp::model::{CallToolRequestParam, CallToolResult, Content, ErrorData, Tool};
+use serde_json::json;
+use std::sync::Arc;
+
+struct CountCalls;
+
+#[async_trait]
+impl CustomTool for CountCalls {
+    fn definition(&self) -> Tool {
+        Tool {
+            name: "count_calls".into(),
+            description: Some("Count console.log calls in a snippet".into()),
+            input_schema: Arc::new(
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": { "code": { "type": "string" } },
+                    "required": ["code"]
+                }))
+                .unwrap(),
+            ),
+            annotations: None,
+        }
+    }
+
+    async fn call(
+        &self,
+        service: &AstGrepService,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        let code = request
+            .arguments
+            .as_ref()
+            .and_then(|args| args.get("code"))
+            .and_then(|code| code.as_str())
+            .unwrap_or_default();
+        let result = service
+            .search(ast_grep_mcp::SearchParam::new(
+                code,
+                "console.log($A)",
+                "javascript",
+            ))
+            .await
+            .map_err(ErrorData::from)?;
+        Ok(CallToolResult::success(vec![Content::text(
+            result.matches.len().to_string(),
+        )]))
+    }
+}
+
+struct Shadow(&'static str);
+
+#[async_trait]
+impl CustomTool for Shadow {
+    fn definition(&self) -> Tool {
+        Tool {
+            name: self.0.into(),
+            description: None,
+            input_schema: Arc::new(serde_json::Map::new()),
+            annotations: None,
+        }
+    }
+
+    async fn call(
+        &self,
+        _service: &AstGrepService,
+        _request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        Ok(CallToolResult::success(vec![]))
+    }
+}
+
+#[tokio::test]
+async fn test_registered_tool_is_listed_and_routed() {
+    let service = AstGrepService::new();
+    let builtin_count = service.available_tools().tools.len();
+    service.register_tool(CountCalls).unwrap();
+
+    let tools = service.available_tools().tools;
+    assert_eq!(tools.len(), builtin_count + 1);
+    assert!(tools.iter().any(|t| t.name == "count_calls"));
+
+    let result = ToolRouter::route_tool_call(
+        &service,
+        CallToolRequestParam {
+            name: "count_calls".into(),
+            arguments: json!({ "code": "console.log(1); console.log(2);" })
+                .as_object()
+                .cloned(),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(result.content[0].as_text().unwrap().text, "2");
+}
+
+#[tokio::test]
+async fn test_registry_is_shared_between_clones() {
+    let service = AstGrepService::new();
+    let clone = service.clone();
+    service.register_tool(Shadow("custom_noop")).unwrap();
+
+    let result = ToolRouter::route_tool_call(
+        &clone,
+        CallToolRequestParam {
+            name: "custom_noop".into(),
+            arguments: None,
+        },
+    )
+    .await;
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_register_rejects_conflicting_names() {
+    let service = AstGrepService::new();
+    assert!(service.register_tool(Shadow("file_search")).is_err());
+
+    service.register_tool(Shadow("custom_noop")).unwrap();
+    assert!(service.register_tool(Shadow("custom_noop")).is_err());
+
+    assert!(service.unregister_tool("custom_noop"));
+    assert!(!service.unregister_tool("custom_noop"));
+}
+
+#[tokio::test]
+async fn test_unknown_tool_still_not_found() {
+    let service = AstGrepService::new();
+    let result = ToolRouter::route_tool_call(
+        &service,
+        CallToolRequestParam {
+            name: "does_not_exist".into(),
+            arguments: None,
+        },
+    )
+    .await;
+    assert!(result.is_err());
+}
@@ -13,19 +13,72 @@ fn test_list_tools() {
     let expected_tools = vec![
         "search",
         "file_search",
+        "batch_search",
+        "get_file_by_hash",
+        "search_strings",
+        "search_identifiers",
+        "find_comments",
+        "project_overview",
+        "summarize_directory",
+        "search_symbols",
+        "doc_coverage",
+        "find_references",
+        "rename_symbol",
+        "find_similar_code",
+        "dedupe_code",
+        "detect_conventions",
+        "pattern_coverage",
+        "begin_code_buffer",
+        "append_code_buffer",
+        "search_buffer",
+        "experiment",
         "replace",
         "file_replace",
+        "get_file_diff",
+        "apply_patch",
+        "add_annotation",
+        "wrap_matches",
+        "verify_refactoring",
+        "rollback_replace",
+        "list_applied_changes",
         "list_languages",
+        "create_playground",
+        "detect_language",
         "rule_search",
         "rule_replace",
+        "scan",
+        "lint_workspace",
+        "run_codemod",
+        "plan_directory_move",
+        "apply_plan",
         "validate_rule",
+        "validate_rules",
+        "explain_rule_evaluation",
         "create_rule",
         "list_rules",
+        "rule_stats",
+        "convert_rule",
+        "export_to_sgconfig",
+        "export_rules",
+        "import_rules",
         "get_rule",
         "delete_rule",
+        "push_rule",
+        "pull_rules",
         "generate_ast",
+        "anonymize_result",
+        "index_status",
+        "health_check",
+        "rebuild_index",
         "validate_pattern",
         "explore_patterns",
+        "suggest_pattern",
+        "analyze_refactoring",
+        "extract_function",
+        "refactor",
+        "validate_refactoring",
+        "list_refactorings",
+        "get_refactoring_info",
     ];
 
     for expected in &expected_tools {
@@ -80,8 +133,13 @@ fn test_file_search_tool_schema() {
             .unwrap()
             .contains(&json!("path_pattern"))
     );
-    assert!(required.as_array().unwrap().contains(&json!("pattern")));
-    assert!(required.as_array().unwrap().contains(&json!("language")));
+
+    // Either a single pattern, its language detected per file unless given, or one pattern
+    // per language
+    let alternatives = schema["anyOf"].as_array().unwrap();
+    assert_eq!(alternatives[0]["required"], json!(["pattern"]));
+    assert_eq!(alternatives[1]["required"], json!(["language_patterns"]));
+    assert!(properties["language_patterns"].is_object());
 }
 
 #[test]
@@ -99,10 +157,11 @@ fn test_rule_search_tool_schema() {
     let schema = &tool.input_schema;
     let properties = &schema["properties"];
     assert!(properties["rule_config"].is_object());
+    assert!(properties["pack"].is_object());
     assert!(properties["path_pattern"].is_object());
 
-    let required = &schema["required"];
-    assert!(required.as_array().unwrap().contains(&json!("rule_config")));
+    // Either rule_config or pack is needed
+    assert!(schema.get("required").is_none());
 }
 
 #[test]
@@ -219,6 +278,12 @@ fn test_create_success_result() {
             vars: std::collections::HashMap::new(),
             context_before: None,
             context_after: None,
+            enclosing_symbol: None,
+            ast_context: None,
+            quick_fixes: Vec::new(),
+            enclosing_node: None,
+            leading_trivia: Vec::new(),
+            rule_id: None,
         }],
         matches_summary: None,
     };
@@ -483,8 +548,12 @@ fn test_generate_ast_tool_schema() {
     assert!(properties["language"].is_object());
 
     let required = &schema["required"];
-    assert!(required.as_array().unwrap().contains(&json!("code")));
     assert!(required.as_array().unwrap().contains(&json!("language")));
+
+    // Code comes inline or from a code buffer
+    let alternatives = schema["anyOf"].as_array().unwrap();
+    assert_eq!(alternatives[0]["required"], json!(["code"]));
+    assert_eq!(alternatives[1]["required"], json!(["buffer_id"]));
 }
 
 #[test]
@@ -0,0 +1,147 @@
+use ast_grep_language::SupportLang as Language;
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::{
+    RuleEvaluator, RuleReplaceParam, RuleSearchParam, parse_rule_config, validate_rule_config,
+};
+use std::fs;
+use tempfile::TempDir;
+
+const SOURCE: &str = r#"function load() {
+  console.log(items);
+  console.error(items);
+}
+
+console.log(top);
+alert(top);
+"#;
+
+const RULE: &str = r#"
+id: logging-in-functions
+language: javascript
+utils:
+  is-logging:
+    any:
+      - pattern: console.log($$$)
+      - pattern: console.error($$$)
+  in-function:
+    inside:
+      kind: function_declaration
+      stopBy: end
+rule:
+  all:
+    - matches: is-logging
+    - matches: in-function
+fix: logger.debug()
+"#;
+
+fn texts(rule_config: &str) -> Vec<String> {
+    let config = parse_rule_config(rule_config).unwrap();
+    RuleEvaluator::new()
+        .evaluate_rule_config(&config, SOURCE, Language::JavaScript)
+        .unwrap()
+        .into_iter()
+        .map(|m| m.text)
+        .collect()
+}
+
+#[test]
+fn test_matches_resolves_local_utils() {
+    assert_eq!(
+        texts(RULE),
+        vec!["console.log(items)", "console.error(items)"]
+    );
+
+    // Utils can refer to each other, and sit under relations and `not`
+    let nested = r#"
+id: nested
+language: javascript
+utils:
+  log-call:
+    pattern: console.log($$$)
+  top-level-log:
+    all:
+      - matches: log-call
+      - not:
+          inside:
+            kind: function_declaration
+            stopBy: end
+rule:
+  kind: expression_statement
+  has:
+    matches: top-level-log
+"#;
+    assert_eq!(texts(nested), vec!["console.log(top);"]);
+}
+
+#[test]
+fn test_undefined_and_cyclic_utils_are_errors() {
+    let undefined =
+        parse_rule_config("id: undefined\nlanguage: javascript\nrule:\n  matches: missing\n")
+            .unwrap();
+    let error = RuleEvaluator::new()
+        .evaluate_rule_config(&undefined, SOURCE, Language::JavaScript)
+        .unwrap_err();
+    assert!(error.to_string().contains("missing"), "{error}");
+
+    let cyclic = r#"
+id: cyclic
+language: javascript
+utils:
+  first:
+    any:
+      - pattern: alert($A)
+      - matches: second
+  second:
+    not:
+      matches: first
+rule:
+  matches: first
+"#;
+    let config = parse_rule_config(cyclic).unwrap();
+    let error = RuleEvaluator::new()
+        .evaluate_rule_config(&config, SOURCE, Language::JavaScript)
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("first -> second -> first"),
+        "{error}"
+    );
+
+    let errors = validate_rule_config(cyclic).unwrap();
+    assert!(errors.iter().any(|e| e.contains("cycle")), "{errors:?}");
+    let errors =
+        validate_rule_config("id: undefined\nlanguage: javascript\nrule:\n  matches: missing\n")
+            .unwrap();
+    assert!(errors.iter().any(|e| e.contains("missing")), "{errors:?}");
+    assert!(validate_rule_config(RULE).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_rule_search_and_replace_with_utils() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("app.js"), SOURCE).unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        ..Default::default()
+    });
+
+    let search: RuleSearchParam = serde_json::from_value(serde_json::json!({
+        "rule_config": RULE,
+        "path_pattern": "*.js"
+    }))
+    .unwrap();
+    let result = service.rule_search(search).await.unwrap();
+    assert_eq!(result.matches[0].matches.len(), 2);
+
+    let replace: RuleReplaceParam = serde_json::from_value(serde_json::json!({
+        "rule_config": RULE,
+        "path_pattern": "*.js",
+        "dry_run": false
+    }))
+    .unwrap();
+    let result = service.rule_replace(replace).await.unwrap();
+    assert_eq!(result.total_changes, 2);
+    let content = fs::read_to_string(temp_dir.path().join("app.js")).unwrap();
+    assert!(content.contains("  logger.debug();\n  logger.debug();\n"));
+    assert!(content.contains("console.log(top);"));
+}
@@ -0,0 +1,97 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::ValidateRulesParam;
+use std::collections::HashMap;
+use std::fs;
+use tempfile::TempDir;
+
+const NO_CONSOLE: &str = "id: no-console\nlanguage: javascript\nrule:\n  pattern: console.log($MSG)\nfix: logger.info($MSG)\n";
+
+fn service(temp_dir: &TempDir) -> AstGrepService {
+    AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn test_validate_rules_reports_each_rule() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = service(&temp_dir);
+
+    let result = service
+        .validate_rules(ValidateRulesParam {
+            rule_configs: vec![
+                NO_CONSOLE.to_string(),
+                "id: bad-lang\nlanguage: cobol\nrule:\n  pattern: foo\n".to_string(),
+                "id: literal\nlanguage: javascript\nrule:\n  pattern: alert($msg)\nfix: notify($MSG)\n"
+                    .to_string(),
+                NO_CONSOLE.to_string(),
+            ],
+            test_code: HashMap::from([(
+                "no-console".to_string(),
+                "console.log(a);\nconsole.log(b);\nconsole.warn(c);\n".to_string(),
+            )]),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.rules.len(), 4);
+    assert_eq!(result.valid_count, 3);
+    assert_eq!(result.invalid_count, 1);
+
+    let no_console = &result.rules[0];
+    assert_eq!(no_console.source, "rule_configs[0]");
+    assert!(no_console.valid && no_console.warnings.is_empty());
+    let tested = no_console.test_results.as_ref().unwrap();
+    assert_eq!(tested.matches_found, 2);
+    assert_eq!(tested.sample_matches[0], "console.log(a)");
+
+    let bad_lang = &result.rules[1];
+    assert!(!bad_lang.valid);
+    assert!(bad_lang.errors[0].contains("Unsupported language: cobol"));
+
+    let literal = &result.rules[2];
+    assert!(literal.valid);
+    assert_eq!(literal.warnings.len(), 2, "{:?}", literal.warnings);
+
+    assert!(result.rules[3].warnings[0].contains("already used by rule_configs[0]"));
+}
+
+#[tokio::test]
+async fn test_validate_rules_all_stored_includes_unparsable_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let rules_dir = temp_dir.path().join(".rules");
+    fs::create_dir_all(&rules_dir).unwrap();
+    fs::write(rules_dir.join("no-console.yaml"), NO_CONSOLE).unwrap();
+    fs::write(rules_dir.join("broken.yml"), "id: broken\nrule: [\n").unwrap();
+    fs::write(rules_dir.join("notes.txt"), "not a rule").unwrap();
+    let service = service(&temp_dir);
+
+    let result = service
+        .validate_rules(ValidateRulesParam {
+            all_stored: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(result.rules.len(), 2);
+    assert_eq!(result.valid_count, 1);
+    let broken = &result.rules[0];
+    assert!(broken.source.ends_with("broken.yml"));
+    assert_eq!(broken.id, None);
+    assert!(broken.errors[0].contains("Failed to parse rule configuration"));
+    assert_eq!(result.rules[1].id.as_deref(), Some("no-console"));
+}
+
+#[tokio::test]
+async fn test_validate_rules_requires_rules() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = service(&temp_dir);
+
+    let result = service.validate_rules(ValidateRulesParam::default()).await;
+    assert!(result.is_err());
+}
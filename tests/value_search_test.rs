@@ -0,0 +1,162 @@
+mod common;
+
+use ast_grep_mcp::tool_router::ToolRouter;
+use ast_grep_mcp::types::{FileSearchResult, ValueSearchParam};
+use common::create_service;
+use rmcp::model::CallToolRequestParam;
+use serde_json::json;
+use std::fs;
+use tempfile::TempDir;
+
+fn all_values(result: &FileSearchResult) -> Vec<String> {
+    result
+        .matches
+        .iter()
+        .flat_map(|f| f.matches.iter().map(|m| m.vars["VALUE"].clone()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_search_strings_regex_finds_urls() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("api.js"),
+        r#"const base = "https://api.example.com";
+const name = 'https';
+fetch(`http://localhost:${port}/health`);
+"#,
+    )
+    .unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .search_strings(ValueSearchParam {
+            path_pattern: "**/*.js".to_string(),
+            language: "javascript".to_string(),
+            regex: Some("^https?://".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        all_values(&result),
+        vec!["https://api.example.com", "http://localhost:${port}/health"]
+    );
+    assert_eq!(result.matches[0].matches[0].start_line, 0);
+}
+
+#[tokio::test]
+async fn test_search_strings_exact_value_python() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("settings.py"),
+        "MODE = \"debug\"\nOTHER = 'debugger'\nLEVEL = f\"debug\"\n",
+    )
+    .unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = service
+        .search_strings(ValueSearchParam {
+            path_pattern: "*.py".to_string(),
+            language: "python".to_string(),
+            value: Some("debug".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(all_values(&result), vec!["debug", "debug"]);
+}
+
+#[tokio::test]
+async fn test_search_identifiers_exact_and_regex() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        "const MAX_RETRIES: u32 = 3;\nconst MAX_WAIT: u32 = 5;\nfn run() -> u32 { MAX_RETRIES + std::u32::MIN }\n",
+    )
+    .unwrap();
+    let service = create_service(&temp_dir);
+
+    let exact = service
+        .search_identifiers(ValueSearchParam {
+            path_pattern: "*.rs".to_string(),
+            language: "rust".to_string(),
+            value: Some("MAX_RETRIES".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let lines: Vec<usize> = exact.matches[0]
+        .matches
+        .iter()
+        .map(|m| m.start_line)
+        .collect();
+    assert_eq!(lines, vec![0, 2]);
+
+    let regex = service
+        .search_identifiers(ValueSearchParam {
+            path_pattern: "*.rs".to_string(),
+            language: "rust".to_string(),
+            regex: Some("^MAX_".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        all_values(&regex),
+        vec!["MAX_RETRIES", "MAX_WAIT", "MAX_RETRIES"]
+    );
+}
+
+#[tokio::test]
+async fn test_search_strings_requires_value_or_regex() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("a.js"), "const a = 'x';\n").unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = ToolRouter::route_tool_call(
+        &service,
+        CallToolRequestParam {
+            name: "search_strings".into(),
+            arguments: json!({ "path_pattern": "*.js", "language": "javascript" })
+                .as_object()
+                .cloned(),
+        },
+    )
+    .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_search_identifiers_via_router() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("a.ts"),
+        "const apiKey = load();\nconsole.log(apiKey);\nconst other = 1;\n",
+    )
+    .unwrap();
+    let service = create_service(&temp_dir);
+
+    let result = ToolRouter::route_tool_call(
+        &service,
+        CallToolRequestParam {
+            name: "search_identifiers".into(),
+            arguments: json!({
+                "path_pattern": "*.ts",
+                "language": "typescript",
+                "value": "apiKey"
+            })
+            .as_object()
+            .cloned(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let text = &result.content[1].as_text().unwrap().text;
+    let parsed: FileSearchResult = serde_json::from_str(text).unwrap();
+    assert_eq!(parsed.matches[0].matches.len(), 2);
+}
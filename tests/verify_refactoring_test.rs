@@ -0,0 +1,131 @@
+#![cfg(unix)]
+
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::{FileReplaceParam, VerifyRefactoringParam};
+use std::collections::BTreeMap;
+use std::fs;
+use tempfile::TempDir;
+
+const ORIGINAL: &str = "console.log(user);\nconsole.log(order);\n";
+
+fn setup() -> (TempDir, AstGrepService) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("app.js"), ORIGINAL).unwrap();
+    let sh = |script: &str| vec!["sh".to_string(), "-c".to_string(), script.to_string()];
+    let verify_commands = BTreeMap::from([
+        // Runs in the root directory, so the relative path finds the rewritten file
+        ("check".to_string(), sh("grep -q 'console.warn' app.js")),
+        (
+            "failing".to_string(),
+            sh("echo compiling; echo 'error: boom' >&2; exit 3"),
+        ),
+        ("slow".to_string(), sh("sleep 5")),
+    ]);
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        verify_commands,
+        ..Default::default()
+    });
+    (temp_dir, service)
+}
+
+fn param(command: &str, rollback_on_failure: bool) -> VerifyRefactoringParam {
+    VerifyRefactoringParam {
+        replace: FileReplaceParam {
+            path_pattern: "**/*.js".to_string(),
+            pattern: "console.log($A)".to_string(),
+            replacement: "console.warn($A)".to_string(),
+            language: "javascript".to_string(),
+            ..Default::default()
+        },
+        command: command.to_string(),
+        timeout_ms: 10_000,
+        rollback_on_failure,
+        tail_lines: 40,
+    }
+}
+
+#[tokio::test]
+async fn test_passing_verification_keeps_changes() {
+    let (temp_dir, service) = setup();
+
+    let result = service
+        .verify_refactoring(param("check", true))
+        .await
+        .unwrap();
+    assert!(!result.replace.dry_run);
+    assert_eq!(result.replace.total_changes, 2);
+    assert!(result.verification.passed);
+    assert_eq!(result.verification.exit_code, Some(0));
+    assert!(!result.rolled_back);
+    let content = fs::read_to_string(temp_dir.path().join("app.js")).unwrap();
+    assert_eq!(content, "console.warn(user);\nconsole.warn(order);\n");
+}
+
+#[tokio::test]
+async fn test_failing_verification_rolls_back() {
+    let (temp_dir, service) = setup();
+
+    let result = service
+        .verify_refactoring(param("failing", true))
+        .await
+        .unwrap();
+    let verification = &result.verification;
+    assert!(!verification.passed);
+    assert_eq!(verification.exit_code, Some(3));
+    assert_eq!(verification.stdout_tail, "compiling");
+    assert_eq!(verification.stderr_tail, "error: boom");
+    assert!(result.rolled_back);
+    assert_eq!(result.restored_files.len(), 1);
+    let content = fs::read_to_string(temp_dir.path().join("app.js")).unwrap();
+    assert_eq!(content, ORIGINAL);
+}
+
+#[tokio::test]
+async fn test_failing_verification_without_rollback_keeps_changes() {
+    let (temp_dir, service) = setup();
+
+    let result = service
+        .verify_refactoring(param("failing", false))
+        .await
+        .unwrap();
+    assert!(!result.verification.passed);
+    assert!(!result.rolled_back);
+    assert!(result.restored_files.is_empty());
+    let content = fs::read_to_string(temp_dir.path().join("app.js")).unwrap();
+    assert!(content.contains("console.warn"));
+}
+
+#[tokio::test]
+async fn test_timed_out_verification_fails() {
+    let (temp_dir, service) = setup();
+
+    let result = service
+        .verify_refactoring(VerifyRefactoringParam {
+            timeout_ms: 200,
+            ..param("slow", true)
+        })
+        .await
+        .unwrap();
+    assert!(result.verification.timed_out);
+    assert!(!result.verification.passed);
+    assert_eq!(result.verification.exit_code, None);
+    assert!(result.verification.duration_ms < 5_000);
+    assert!(result.rolled_back);
+    let content = fs::read_to_string(temp_dir.path().join("app.js")).unwrap();
+    assert_eq!(content, ORIGINAL);
+}
+
+#[tokio::test]
+async fn test_unknown_command_is_rejected_before_writing() {
+    let (temp_dir, service) = setup();
+
+    let error = service
+        .verify_refactoring(param("rm", true))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("check, failing, slow"));
+    let content = fs::read_to_string(temp_dir.path().join("app.js")).unwrap();
+    assert_eq!(content, ORIGINAL);
+}
@@ -0,0 +1,160 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::{CreateRuleParam, RuleSearchParam, ScanParam};
+use ast_grep_mcp::types::{FileSearchParam, WalkOptions};
+use std::fs;
+use tempfile::TempDir;
+
+fn setup(config: ServiceConfig) -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    for name in [
+        "app.js",
+        "src/lib.js",
+        "src/deep/nested/util.js",
+        "node_modules/pkg/index.js",
+        ".cache/build.js",
+    ] {
+        let path = temp_dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "console.log(1);\n").unwrap();
+    }
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..config
+    });
+    (service, temp_dir)
+}
+
+fn console_search(walk: WalkOptions) -> FileSearchParam {
+    FileSearchParam {
+        path_pattern: "**/*.js".to_string(),
+        pattern: "console.log($A)".to_string(),
+        language: "javascript".to_string(),
+        walk,
+        ..Default::default()
+    }
+}
+
+async fn searched_files(service: &AstGrepService, walk: WalkOptions) -> Vec<String> {
+    let result = service.file_search(console_search(walk)).await.unwrap();
+    let mut files: Vec<String> = result
+        .matches
+        .iter()
+        .map(|m| m.file_path.replace('\\', "/"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn names(files: &[String]) -> Vec<&str> {
+    files
+        .iter()
+        .map(|f| f.rsplit('/').next().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_default_walk_visits_everything() {
+    let (service, _temp_dir) = setup(ServiceConfig::default());
+    let files = searched_files(&service, WalkOptions::default()).await;
+    assert_eq!(files.len(), 5);
+}
+
+#[tokio::test]
+async fn test_per_call_options_prune_hide_and_limit_depth() {
+    let (service, _temp_dir) = setup(ServiceConfig::default());
+
+    let files = searched_files(
+        &service,
+        WalkOptions {
+            prune_dirs: Some(vec!["node_modules".to_string()]),
+            follow_hidden: Some(false),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(names(&files), vec!["app.js", "util.js", "lib.js"]);
+
+    let files = searched_files(
+        &service,
+        WalkOptions {
+            max_depth: Some(1),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(names(&files), vec!["app.js"]);
+}
+
+#[tokio::test]
+async fn test_config_defaults_apply_unless_overridden() {
+    let (service, _temp_dir) = setup(ServiceConfig {
+        prune_dirs: vec!["node_modules".to_string(), "deep".to_string()],
+        follow_hidden: false,
+        ..Default::default()
+    });
+
+    let files = searched_files(&service, WalkOptions::default()).await;
+    assert_eq!(names(&files), vec!["app.js", "lib.js"]);
+
+    // An explicit empty list turns the configured pruning off for one call
+    let files = searched_files(
+        &service,
+        WalkOptions {
+            prune_dirs: Some(Vec::new()),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(files.len(), 4);
+}
+
+#[tokio::test]
+async fn test_rule_search_and_scan_honour_walk_options() {
+    let (service, _temp_dir) = setup(ServiceConfig::default());
+    let walk = WalkOptions {
+        prune_dirs: Some(vec!["node_modules".to_string(), "src".to_string()]),
+        follow_hidden: Some(false),
+        ..Default::default()
+    };
+
+    let result = service
+        .rule_search(RuleSearchParam {
+            rule_config: "id: console\nlanguage: javascript\nrule:\n  pattern: console.log($A)\n"
+                .to_string(),
+            path_pattern: Some("**/*.js".to_string()),
+            max_results: 100,
+            max_file_size: 1024 * 1024,
+            cursor: None,
+            output_file: None,
+            workspace: None,
+            timeout_ms: None,
+            max_matches: None,
+            walk: walk.clone(),
+            output_format: Default::default(),
+            pack: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.matches.len(), 1);
+    assert!(result.matches[0].file_path.ends_with("app.js"));
+
+    service
+        .create_rule(CreateRuleParam {
+            rule_config: "id: console\nlanguage: javascript\nrule:\n  pattern: console.log($A)\n"
+                .to_string(),
+            overwrite: false,
+        })
+        .await
+        .unwrap();
+    let result = service
+        .scan(ScanParam {
+            path_pattern: Some("**/*.js".to_string()),
+            walk,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.files_scanned, 1);
+}
@@ -0,0 +1,108 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::RuleReplaceParam;
+use ast_grep_mcp::types::FileReplaceParam;
+use std::fs;
+use tempfile::TempDir;
+
+// The replacements below only add trailing spaces to `cache.get(...)` lines
+const CLEAN: &str = "value = cache.get(key)\n";
+const MIXED: &str = "value = cache.get(key)\nother = store.get(key)\n";
+
+fn setup() -> (AstGrepService, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("clean.py"), CLEAN).unwrap();
+    fs::write(temp_dir.path().join("mixed.py"), MIXED).unwrap();
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        rules_directory: temp_dir.path().join(".rules"),
+        ..Default::default()
+    });
+    (service, temp_dir)
+}
+
+fn file_replace_param(pattern: &str, replacement: &str) -> FileReplaceParam {
+    FileReplaceParam {
+        path_pattern: "*.py".to_string(),
+        pattern: pattern.to_string(),
+        replacement: replacement.to_string(),
+        language: "python".to_string(),
+        dry_run: false,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_whitespace_only_rewrites_are_not_written() {
+    let (service, temp_dir) = setup();
+
+    let result = service
+        .file_replace(file_replace_param("$C.get($K)", "$C.get($K)  "))
+        .await
+        .unwrap();
+    assert_eq!(result.files_with_changes, 0);
+    assert!(result.summary_results.is_empty());
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("mixed.py")).unwrap(),
+        MIXED
+    );
+
+    // A file with a real change keeps the whole rewrite, trailing spaces included
+    let result = service
+        .file_replace(file_replace_param("store.get($K)", "store.fetch($K)  "))
+        .await
+        .unwrap();
+    assert_eq!(result.files_with_changes, 1);
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("mixed.py")).unwrap(),
+        "value = cache.get(key)\nother = store.fetch(key)  \n"
+    );
+}
+
+#[tokio::test]
+async fn test_whitespace_only_rewrites_can_be_kept() {
+    let (service, temp_dir) = setup();
+
+    let result = service
+        .file_replace(FileReplaceParam {
+            ignore_whitespace_changes: false,
+            ..file_replace_param("cache.get($K)", "cache.get($K) ")
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.files_with_changes, 2);
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("clean.py")).unwrap(),
+        "value = cache.get(key) \n"
+    );
+}
+
+#[tokio::test]
+async fn test_rule_replace_skips_whitespace_only_fixes() {
+    let (service, temp_dir) = setup();
+
+    let result = service
+        .rule_replace(RuleReplaceParam {
+            rule_config: "id: pad\nlanguage: python\nrule:\n  pattern: cache.get($K)\nfix: \"cache.get($K)\\t\"\n"
+                .to_string(),
+            path_pattern: Some("*.py".to_string()),
+            max_results: 100,
+            max_file_size: 1024 * 1024,
+            dry_run: false,
+            summary_only: false,
+            cursor: None,
+            allow_unsafe: false,
+            workspace: None,
+            ignore_whitespace_changes: true,
+            timeout_ms: None,
+            walk: Default::default(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.files_with_changes, 0);
+    assert!(result.file_results.is_empty());
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("clean.py")).unwrap(),
+        CLEAN
+    );
+}
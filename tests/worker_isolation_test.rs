@@ -0,0 +1,118 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::FileSearchParam;
+use ast_grep_mcp::worker::WorkerConfig;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn create_service(temp_dir: &TempDir, workers: Option<WorkerConfig>) -> AstGrepService {
+    fs::write(temp_dir.path().join("a.js"), "console.log(1);\nfoo();\n").unwrap();
+    fs::write(temp_dir.path().join("b.js"), "console.log(2);\n").unwrap();
+    let config = ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        worker_isolation: workers,
+        ..Default::default()
+    };
+    AstGrepService::with_config(config)
+}
+
+fn worker_config() -> WorkerConfig {
+    WorkerConfig::new(PathBuf::from(env!("CARGO_BIN_EXE_ast-grep-mcp")))
+}
+
+fn search_param(pattern: &str) -> FileSearchParam {
+    FileSearchParam {
+        path_pattern: "*.js".to_string(),
+        pattern: pattern.to_string(),
+        language: "javascript".to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_isolated_search_matches_in_process_search() {
+    let temp_dir = TempDir::new().unwrap();
+    let isolated = create_service(&temp_dir, Some(worker_config()));
+    let in_process = create_service(&temp_dir, None);
+
+    let expected = in_process
+        .file_search(search_param("console.log($A)"))
+        .await
+        .unwrap();
+    let result = isolated
+        .file_search(search_param("console.log($A)"))
+        .await
+        .unwrap();
+
+    assert!(result.skipped_files.is_empty());
+    assert_eq!(
+        serde_json::to_value(&result.matches).unwrap(),
+        serde_json::to_value(&expected.matches).unwrap()
+    );
+    assert_eq!(result.matches.len(), 2);
+}
+
+#[tokio::test]
+async fn test_worker_timeout_skips_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut workers = worker_config();
+    workers.timeout = Duration::from_millis(1);
+    let service = create_service(&temp_dir, Some(workers));
+
+    let result = service
+        .file_search(search_param("console.log($A)"))
+        .await
+        .unwrap();
+
+    assert!(result.matches.is_empty());
+    assert_eq!(result.skipped_files.len(), 2);
+    assert!(result.skipped_files[0].reason.contains("timed out"));
+}
+
+#[tokio::test]
+async fn test_panicking_worker_is_reported_and_replaced() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir, Some(worker_config()));
+
+    // A bare ellipsis panics inside ast-grep-core while matching
+    let result = service
+        .file_search(FileSearchParam {
+            allow_broad: true,
+            ..search_param("$$$")
+        })
+        .await
+        .unwrap();
+
+    assert!(result.matches.is_empty());
+    assert_eq!(result.skipped_files.len(), 2, "every file gets a fresh worker");
+    assert!(
+        result.skipped_files[0].reason.contains("exited"),
+        "{}",
+        result.skipped_files[0].reason
+    );
+
+    // The pool recovers for the next search
+    let result = service
+        .file_search(search_param("console.log($A)"))
+        .await
+        .unwrap();
+    assert_eq!(result.matches.len(), 2);
+    assert!(result.skipped_files.is_empty());
+}
+
+#[tokio::test]
+async fn test_invalid_pattern_is_an_error_not_a_skip() {
+    let temp_dir = TempDir::new().unwrap();
+    let service = create_service(&temp_dir, Some(worker_config()));
+
+    let result = service
+        .file_search(FileSearchParam {
+            selector: Some("no_such_kind".to_string()),
+            context: Some("class A { $F }".to_string()),
+            ..search_param("$F")
+        })
+        .await;
+    assert!(result.is_err());
+}
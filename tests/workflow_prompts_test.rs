@@ -0,0 +1,87 @@
+use ast_grep_language::SupportLang as Language;
+use ast_grep_mcp::learning::{ProjectContext, PromptGenerator};
+use ast_grep_mcp::pattern::PatternMatcher;
+use std::fs;
+use tempfile::TempDir;
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("node_modules/dep")).unwrap();
+    fs::write(root.join("src/a.js"), "var a = 1;\nvar b = 2;\n").unwrap();
+    fs::write(root.join("src/b.js"), "let c = 3;\n").unwrap();
+    fs::write(root.join("src/c.py"), "x = 1\n").unwrap();
+    fs::write(root.join("node_modules/dep/index.js"), "var d = 4;\n").unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_project_context_counts_languages_and_matches() {
+    let temp_dir = create_project();
+    let context = ProjectContext::collect(
+        &[temp_dir.path().to_path_buf()],
+        &PatternMatcher::new(),
+        Some("var $A = $B;"),
+        Some(Language::JavaScript),
+    );
+
+    // node_modules is skipped
+    assert_eq!(context.total_files, 3);
+    assert_eq!(context.languages[0].language, "javascript");
+    assert_eq!(context.languages[0].file_count, 2);
+    assert_eq!(context.languages[1].language, "python");
+
+    let matches = context.pattern_matches.unwrap();
+    assert_eq!(matches.total_matches, 2);
+    assert_eq!(matches.files_with_matches, 1);
+    assert_eq!(matches.files_scanned, 2);
+}
+
+#[test]
+fn test_bulk_refactor_plan_includes_project_context() {
+    let temp_dir = create_project();
+    let context = ProjectContext::collect(
+        &[temp_dir.path().to_path_buf()],
+        &PatternMatcher::new(),
+        Some("var $A = $B;"),
+        Some(Language::JavaScript),
+    );
+
+    let prompt = PromptGenerator::new()
+        .generate_bulk_refactor_plan("replace var with let", Some("var $A = $B;"), &context)
+        .unwrap();
+
+    assert!(prompt.contains("replace var with let"));
+    assert!(prompt.contains("javascript: 2 files"));
+    assert!(prompt.contains("2 matches in 1 of 2 files checked"));
+    assert!(prompt.contains("starting from `var $A = $B;`"));
+    assert!(!prompt.contains('{'), "unfilled placeholder in: {prompt}");
+}
+
+#[test]
+fn test_codemod_rule_prompt_has_rule_skeleton() {
+    let temp_dir = create_project();
+    let context = ProjectContext::collect(
+        &[temp_dir.path().to_path_buf()],
+        &PatternMatcher::new(),
+        None,
+        None,
+    );
+
+    let prompt = PromptGenerator::new()
+        .generate_codemod_rule_prompt(
+            "Use let instead of var",
+            "javascript",
+            Some("var $A = $B;"),
+            Some("var a = 1;"),
+            &context,
+        )
+        .unwrap();
+
+    assert!(prompt.contains("id: use-let-instead-of-var"));
+    assert!(prompt.contains("language: javascript"));
+    assert!(prompt.contains("pattern: var $A = $B;"));
+    assert!(prompt.contains("```javascript\nvar a = 1;\n```"));
+    assert!(context.pattern_matches.is_none());
+}
@@ -0,0 +1,132 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::rules::RuleSearchParam;
+use ast_grep_mcp::types::{FileReplaceParam, FileSearchParam};
+use std::collections::BTreeMap;
+use std::fs;
+use tempfile::TempDir;
+
+/// Two labelled projects, `web` and `api`, each with one file calling `console.log`
+fn setup() -> (TempDir, AstGrepService) {
+    let temp_dir = TempDir::new().unwrap();
+    let mut workspaces = BTreeMap::new();
+    for label in ["web", "api"] {
+        let root = temp_dir.path().join(label);
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/main.js"), "console.log(\"hello\");\n").unwrap();
+        workspaces.insert(label.to_string(), root);
+    }
+    let service = AstGrepService::with_config(ServiceConfig {
+        root_directories: workspaces.values().cloned().collect(),
+        workspaces,
+        ..Default::default()
+    });
+    (temp_dir, service)
+}
+
+fn search_param(workspace: Option<&str>) -> FileSearchParam {
+    FileSearchParam {
+        path_pattern: "**/*.js".to_string(),
+        pattern: "console.log($A)".to_string(),
+        language: "javascript".to_string(),
+        workspace: workspace.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_file_search_scoped_to_workspace() {
+    let (_temp_dir, service) = setup();
+
+    let all = service.file_search(search_param(None)).await.unwrap();
+    let mut labels: Vec<Option<&str>> = all
+        .matches
+        .iter()
+        .map(|file| file.workspace.as_deref())
+        .collect();
+    labels.sort();
+    assert_eq!(labels, vec![Some("api"), Some("web")]);
+
+    let web = service
+        .file_search(search_param(Some("web")))
+        .await
+        .unwrap();
+    assert_eq!(web.matches.len(), 1);
+    assert!(web.matches[0].file_path.contains("/web/"));
+    assert_eq!(web.matches[0].workspace.as_deref(), Some("web"));
+
+    let rules = service
+        .rule_search(RuleSearchParam {
+            rule_config: "id: log\nlanguage: javascript\nrule:\n  pattern: console.log($A)\n"
+                .to_string(),
+            path_pattern: Some("**/*.js".to_string()),
+            max_results: 10,
+            max_file_size: 1024 * 1024,
+            cursor: None,
+            output_file: None,
+            workspace: Some("api".to_string()),
+            timeout_ms: None,
+            max_matches: None,
+            walk: Default::default(),
+            output_format: Default::default(),
+            pack: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(rules.matches.len(), 1);
+    assert_eq!(rules.matches[0].workspace.as_deref(), Some("api"));
+}
+
+#[tokio::test]
+async fn test_file_replace_stays_inside_workspace() {
+    let (temp_dir, service) = setup();
+
+    let result = service
+        .file_replace(FileReplaceParam {
+            path_pattern: "**/*.js".to_string(),
+            pattern: "console.log($A)".to_string(),
+            replacement: "logger.info($A)".to_string(),
+            language: "javascript".to_string(),
+            dry_run: false,
+            workspace: Some("api".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.files_with_changes, 1);
+    assert_eq!(result.summary_results[0].workspace.as_deref(), Some("api"));
+
+    let api = fs::read_to_string(temp_dir.path().join("api/src/main.js")).unwrap();
+    let web = fs::read_to_string(temp_dir.path().join("web/src/main.js")).unwrap();
+    assert_eq!(api, "logger.info(\"hello\");\n");
+    assert_eq!(web, "console.log(\"hello\");\n");
+}
+
+#[tokio::test]
+async fn test_workspace_rejects_paths_outside_it() {
+    let (temp_dir, service) = setup();
+
+    let unknown = service
+        .file_search(search_param(Some("mobile")))
+        .await
+        .unwrap_err();
+    assert!(
+        unknown
+            .to_string()
+            .contains("configured workspaces: api, web")
+    );
+
+    // An absolute path into another project is refused rather than silently searched
+    let mut param = search_param(Some("web"));
+    param.path_pattern = format!("{}/**/*.js", temp_dir.path().join("api").display());
+    let outside = service.file_search(param).await.unwrap_err();
+    assert!(outside.to_string().contains("outside workspace 'web'"));
+
+    let mut param = search_param(Some("web"));
+    param.path_pattern = temp_dir
+        .path()
+        .join("api/src/main.js")
+        .to_string_lossy()
+        .to_string();
+    assert!(service.file_search(param).await.is_err());
+}
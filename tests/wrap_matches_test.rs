@@ -0,0 +1,107 @@
+mod common;
+
+use ast_grep_mcp::types::WrapMatchesParam;
+use common::create_service;
+use std::fs;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_wrap_expressions_and_statements() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("app.js");
+    let original = "const rows = load(query);\nload(other);\n";
+    fs::write(&path, original).unwrap();
+    let service = create_service(&temp_dir);
+    let param = WrapMatchesParam {
+        path_pattern: "*.js".to_string(),
+        pattern: "load($A)".to_string(),
+        language: "javascript".to_string(),
+        template: "measure(() => $MATCH)".to_string(),
+        ..Default::default()
+    };
+
+    let preview = service.wrap_matches(param.clone()).await.unwrap();
+    assert!(preview.dry_run);
+    assert_eq!(preview.total_wraps, 2);
+    let change = &preview.file_results[0].changes[0];
+    assert_eq!(change.old_text, "load(query)");
+    assert_eq!(change.new_text, "measure(() => load(query))");
+    assert_eq!(fs::read_to_string(&path).unwrap(), original);
+
+    service
+        .wrap_matches(WrapMatchesParam {
+            dry_run: false,
+            ..param
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "const rows = measure(() => load(query));\nmeasure(() => load(other));\n"
+    );
+}
+
+#[tokio::test]
+async fn test_wrap_keeps_statement_terminators_and_skips_nested_matches() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("main.rs");
+    fs::write(
+        &path,
+        "fn main() {\n    step(step(1));\n    let x = step(2);\n}\n",
+    )
+    .unwrap();
+    let service = create_service(&temp_dir);
+
+    // A statement pattern: its `;` stays after the wrapper, not doubled by the template's
+    let result = service
+        .wrap_matches(WrapMatchesParam {
+            path_pattern: "*.rs".to_string(),
+            pattern: "step($A);".to_string(),
+            language: "rust".to_string(),
+            template: "instrument!($MATCH);".to_string(),
+            dry_run: false,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.total_wraps, 1);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "fn main() {\n    instrument!(step(step(1)));\n    let x = step(2);\n}\n"
+    );
+
+    // The inner call of `step(step(1))` sits inside the outer one and is left alone
+    fs::write(
+        &path,
+        "fn main() {\n    step(step(1));\n    let x = step(2);\n}\n",
+    )
+    .unwrap();
+    let result = service
+        .wrap_matches(WrapMatchesParam {
+            path_pattern: "*.rs".to_string(),
+            pattern: "step($A)".to_string(),
+            language: "rust".to_string(),
+            template: "timed(|| {\n    $MATCH\n})".to_string(),
+            dry_run: false,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.total_wraps, 2);
+    assert_eq!(result.nested_matches, 1);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "fn main() {\n    timed(|| {\n        step(step(1))\n    });\n    let x = timed(|| {\n        step(2)\n    });\n}\n"
+    );
+
+    let missing_placeholder = service
+        .wrap_matches(WrapMatchesParam {
+            path_pattern: "*.rs".to_string(),
+            pattern: "step($A)".to_string(),
+            language: "rust".to_string(),
+            template: "timed()".to_string(),
+            ..Default::default()
+        })
+        .await;
+    assert!(missing_placeholder.is_err());
+}
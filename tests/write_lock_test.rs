@@ -0,0 +1,129 @@
+use ast_grep_mcp::ast_grep_service::AstGrepService;
+use ast_grep_mcp::config::ServiceConfig;
+use ast_grep_mcp::types::FileReplaceParam;
+use ast_grep_mcp::write_lock::{self, LOCK_FILE, LockOwner};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn config(temp_dir: &TempDir) -> ServiceConfig {
+    ServiceConfig {
+        root_directories: vec![temp_dir.path().to_path_buf()],
+        undo_directory: temp_dir.path().join(".undo"),
+        write_lock_timeout: Duration::from_millis(300),
+        ..Default::default()
+    }
+}
+
+fn replace_param(dry_run: bool) -> FileReplaceParam {
+    FileReplaceParam {
+        path_pattern: "*.js".to_string(),
+        pattern: "var $A = $B".to_string(),
+        replacement: "let $A = $B".to_string(),
+        language: "javascript".to_string(),
+        dry_run,
+        ..Default::default()
+    }
+}
+
+fn write_owner(path: &Path, pid: u32) {
+    let owner = LockOwner {
+        pid,
+        acquired_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        token: "held-elsewhere".to_string(),
+    };
+    fs::write(path, serde_json::to_vec(&owner).unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn test_lock_is_held_while_writing_and_released_after() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = config(&temp_dir);
+    let lock_path = temp_dir.path().join(LOCK_FILE);
+
+    let lock = write_lock::acquire(&config, None).await.unwrap();
+    assert_eq!(lock.paths().collect::<Vec<_>>(), [lock_path.as_path()]);
+    let owner = write_lock::read_owner(&lock_path).unwrap();
+    assert_eq!(owner.pid, std::process::id());
+    assert!(!owner.is_stale());
+
+    // A second writer waits for the lock, then gives up
+    let error = write_lock::acquire(&config, None).await.unwrap_err();
+    assert!(error.to_string().contains("--no-lock"), "{error}");
+
+    drop(lock);
+    assert!(!lock_path.exists());
+    assert!(write_lock::acquire(&config, None).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_writes_fail_while_another_server_holds_the_lock() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("app.js");
+    fs::write(&file, "var x = 1;\n").unwrap();
+    let lock_path = temp_dir.path().join(LOCK_FILE);
+    // This test process is alive, so its lock is not stale
+    write_owner(&lock_path, std::process::id());
+    let service = AstGrepService::with_config(config(&temp_dir));
+
+    let error = service
+        .file_replace(replace_param(false))
+        .await
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains(&format!("pid {}", std::process::id())),
+        "{error}"
+    );
+    assert_eq!(fs::read_to_string(&file).unwrap(), "var x = 1;\n");
+
+    // Previews write nothing and take no lock
+    let preview = service.file_replace(replace_param(true)).await.unwrap();
+    assert_eq!(preview.total_changes, 1);
+    assert!(lock_path.exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_stale_lock_of_a_dead_process_is_taken_over() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("app.js");
+    fs::write(&file, "var x = 1;\n").unwrap();
+    let mut child = std::process::Command::new("true").spawn().unwrap();
+    let dead_pid = child.id();
+    child.wait().unwrap();
+    let lock_path = temp_dir.path().join(LOCK_FILE);
+    write_owner(&lock_path, dead_pid);
+    assert!(write_lock::read_owner(&lock_path).unwrap().is_stale());
+
+    let service = AstGrepService::with_config(config(&temp_dir));
+    service.file_replace(replace_param(false)).await.unwrap();
+    assert_eq!(fs::read_to_string(&file).unwrap(), "let x = 1;\n");
+    assert!(!lock_path.exists());
+}
+
+#[tokio::test]
+async fn test_no_lock_skips_the_lockfile() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("app.js");
+    fs::write(&file, "var x = 1;\n").unwrap();
+    let lock_path = temp_dir.path().join(LOCK_FILE);
+    write_owner(&lock_path, std::process::id());
+    let service = AstGrepService::with_config(ServiceConfig {
+        write_lock: false,
+        ..config(&temp_dir)
+    });
+
+    service.file_replace(replace_param(false)).await.unwrap();
+    assert_eq!(fs::read_to_string(&file).unwrap(), "let x = 1;\n");
+    // Another server's lock is left alone
+    assert_eq!(
+        write_lock::read_owner(&lock_path).unwrap().token,
+        "held-elsewhere"
+    );
+}